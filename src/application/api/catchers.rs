@@ -0,0 +1,382 @@
+use rocket::{
+    catch,
+    http::{ContentType, Method, Status},
+    patch, post, put,
+    response::Responder,
+    Request, Route,
+};
+
+use crate::application::api::utils::error::ApiError;
+
+pub struct UnsupportedMediaTypeError;
+
+impl<'r> Responder<'r, 'static> for UnsupportedMediaTypeError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        if !has_json_route(req) {
+            return Err(Status::NotFound);
+        }
+        let message = "Unsupported media type, expected Content-Type: application/json".into();
+        let status = Status::UnsupportedMediaType;
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+fn route_path_matches(route: &Route, req: &Request<'_>) -> bool {
+    let mut request_segments = req.uri().path().segments();
+    for route_segment in route.uri.origin.path().segments() {
+        if route_segment.starts_with('<') && route_segment.ends_with("..>") {
+            return true;
+        }
+        match request_segments.next() {
+            Some(request_segment)
+                if route_segment.starts_with('<') || route_segment == request_segment => {}
+            _ => return false,
+        }
+    }
+    request_segments.next().is_none()
+}
+
+fn has_json_route(req: &Request<'_>) -> bool {
+    req.rocket().routes().any(|route| {
+        route.method == req.method()
+            && route.format.as_ref().is_some_and(|format| format.is_json())
+            && route_path_matches(route, req)
+    })
+}
+
+// Rocket doesn't match routes with `format = "application/json"` when the request has other
+// Content-Type, so such requests end up here. They are rejected with 415 if a json route exists for
+// the method and path, requests to unknown paths get 404 whatever their Content-Type is.
+fn get_unsupported_media_type_error(
+    content_type: Option<&ContentType>,
+) -> Option<UnsupportedMediaTypeError> {
+    match content_type {
+        Some(content_type) if content_type.is_json() => None,
+        _ => Some(UnsupportedMediaTypeError),
+    }
+}
+
+#[post("/<_..>", rank = 100)]
+pub fn reject_unsupported_media_type(
+    content_type: Option<&ContentType>,
+) -> Option<UnsupportedMediaTypeError> {
+    get_unsupported_media_type_error(content_type)
+}
+
+#[put("/<_..>", rank = 100)]
+pub fn reject_unsupported_media_type_of_put(
+    content_type: Option<&ContentType>,
+) -> Option<UnsupportedMediaTypeError> {
+    get_unsupported_media_type_error(content_type)
+}
+
+#[patch("/<_..>", rank = 100)]
+pub fn reject_unsupported_media_type_of_patch(
+    content_type: Option<&ContentType>,
+) -> Option<UnsupportedMediaTypeError> {
+    get_unsupported_media_type_error(content_type)
+}
+
+pub struct CatcherError(Status);
+
+impl<'r> Responder<'r, 'static> for CatcherError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let message = self.0.reason_lossy().into();
+        ApiError::build_rocket_response(req, message, self.0)
+    }
+}
+
+fn accepts_json(req: &Request<'_>) -> bool {
+    req.accept().is_none_or(|accept| {
+        let media_type = accept.preferred().media_type();
+        media_type.is_json()
+            || media_type.top() == "*"
+            || (media_type.top() == "application" && media_type.sub() == "*")
+    })
+}
+
+// GET routes declaring `format = "application/json"` are matched by rocket against the preferred
+// media type of the Accept header, so e.g. `Accept: text/html` matches no route at all. Such
+// requests get 406 instead of a confusing 404, requests accepting json to unknown paths still 404.
+#[catch(404)]
+pub fn not_found(req: &Request<'_>) -> CatcherError {
+    if req.method() == Method::Get && req.route().is_none() && !accepts_json(req) {
+        return CatcherError(Status::NotAcceptable);
+    }
+    CatcherError(Status::NotFound)
+}
+
+// Reached when a path parameter, e.g. an id that isn't a valid UUID, or the request body fails to
+// parse, so that these errors have the same format as the ones returned by the handlers
+#[catch(422)]
+pub fn unprocessable_entity() -> CatcherError {
+    CatcherError(Status::UnprocessableEntity)
+}
+
+#[catch(500)]
+pub fn internal_server_error() -> CatcherError {
+    CatcherError(Status::InternalServerError)
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        catchers, get,
+        http::{ContentType, Header, Status},
+        local::asynchronous::Client,
+        routes,
+        serde::json::{self, Value},
+    };
+
+    use crate::application::api::{
+        controllers::{
+            admin_controller, doctors_controller, drugs_controller, prescriptions_controller,
+        },
+        utils::fake_api_context::create_fake_api_context,
+    };
+
+    #[get("/panic")]
+    fn panicking_route() -> &'static str {
+        panic!("Something went wrong")
+    }
+
+    async fn create_api_client() -> Client {
+        let context = create_fake_api_context();
+
+        let routes = routes![
+            doctors_controller::create_doctor,
+            doctors_controller::get_doctor_by_id,
+            drugs_controller::get_drugs_with_pagination,
+            prescriptions_controller::reassign_prescription_patient,
+            admin_controller::set_user_active,
+            super::reject_unsupported_media_type,
+            super::reject_unsupported_media_type_of_put,
+            super::reject_unsupported_media_type_of_patch,
+            panicking_route
+        ];
+
+        let rocket = rocket::build().manage(context).mount("/", routes).register(
+            "/",
+            catchers![
+                super::not_found,
+                super::unprocessable_entity,
+                super::internal_server_error
+            ],
+        );
+
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn returns_unsupported_media_type_if_body_is_not_json() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/doctors")
+            .body("name=John Doe")
+            .header(ContentType::Plain)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["status"], 415);
+        assert_eq!(error["path"], "/doctors");
+        assert_eq!(error["method"], "POST");
+        assert!(error["message"]
+            .as_str()
+            .unwrap()
+            .contains("application/json"));
+    }
+
+    #[tokio::test]
+    async fn returns_unsupported_media_type_if_content_type_is_missing() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/doctors")
+            .body(r#"{"name":"John Doe", "pesel_number":"96021807250", "pwz_number":"5425740"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+    }
+
+    #[tokio::test]
+    async fn returns_unsupported_media_type_if_put_or_patch_body_is_not_json() {
+        let client = create_api_client().await;
+
+        let response = client
+            .patch("/prescriptions/00000000-0000-0000-0000-000000000000/patient")
+            .body("patient_id=00000000-0000-0000-0000-000000000000")
+            .header(ContentType::Form)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["method"], "PATCH");
+
+        let response = client
+            .put("/admin/users/00000000-0000-0000-0000-000000000000/active")
+            .body("active=false")
+            .header(ContentType::Plain)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["method"], "PUT");
+    }
+
+    #[tokio::test]
+    async fn returns_not_found_if_json_is_posted_to_unknown_path() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/unknown")
+            .body("{}")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn returns_not_found_if_non_json_body_is_sent_to_unknown_path() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/unknown")
+            .body("name=John Doe")
+            .header(ContentType::Plain)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let response = client
+            .patch("/doctors/00000000-0000-0000-0000-000000000000/unknown")
+            .body("pwz_number=3123456")
+            .header(ContentType::Form)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn returns_not_acceptable_if_client_doesnt_accept_json() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/drugs")
+            .header(Header::new("Accept", "text/html"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotAcceptable);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["status"], 406);
+        assert_eq!(error["path"], "/drugs");
+        assert_eq!(error["method"], "GET");
+        assert_eq!(error["message"], "Not Acceptable");
+    }
+
+    #[tokio::test]
+    async fn returns_ok_if_client_accepts_json_or_anything() {
+        let client = create_api_client().await;
+
+        for accept in [None, Some("application/json"), Some("*/*")] {
+            let mut request = client.get("/drugs");
+            if let Some(accept) = accept {
+                request = request.header(Header::new("Accept", accept));
+            }
+            let response = request.dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_json_error_if_route_does_not_exist() {
+        let client = create_api_client().await;
+
+        let response = client.get("/unknown/path").dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["status"], 404);
+        assert_eq!(error["path"], "/unknown/path");
+        assert_eq!(error["method"], "GET");
+        assert_eq!(error["message"], "Not Found");
+        assert!(error["timestamp_ms"].is_i64());
+    }
+
+    #[tokio::test]
+    async fn returns_json_error_if_path_id_is_not_a_valid_uuid() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/doctors/not-a-uuid")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["status"], 422);
+        assert_eq!(error["path"], "/doctors/not-a-uuid");
+        assert_eq!(error["method"], "GET");
+        assert_eq!(error["message"], "Unprocessable Entity");
+    }
+
+    #[tokio::test]
+    async fn returns_json_error_if_body_cant_be_parsed() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/doctors")
+            .body(r#"{"name":"John Doe"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+    }
+
+    #[tokio::test]
+    async fn returns_json_error_if_handler_panics() {
+        let client = create_api_client().await;
+
+        let response = client.get("/panic").dispatch().await;
+
+        assert_eq!(response.status(), Status::InternalServerError);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["status"], 500);
+        assert_eq!(error["path"], "/panic");
+        assert_eq!(error["message"], "Internal Server Error");
+    }
+}
@@ -0,0 +1,712 @@
+use okapi::openapi3::Responses;
+use rocket::{get, http::Status, post, put, response::Responder, serde::json::Json, Request};
+use rocket_okapi::{gen::OpenApiGenerator, openapi, response::OpenApiResponderInner, OpenApiError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        api::{
+            guards::{admin_ip_allowlist::AdminIpAllowlist, authorization::AdminSession},
+            utils::{error::ApiError, openapi_responses::get_openapi_responses},
+        },
+        authentication::{
+            repository::{GetUserByIdRepositoryError, SetUserActiveRepositoryError},
+            service::{GetUserByIdError, SetUserActiveError},
+        },
+        sessions::{
+            entities::SessionsStats,
+            repository::{GetSessionRepositoryError, UpdateSessionRepositoryError},
+            service::{CountSessionsError, InvalidateAllSessionsForUserError},
+        },
+    },
+    Ctx,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SetUserActiveDto {
+    active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SetUserActiveResponse {
+    user_id: Uuid,
+    is_active: bool,
+    invalidated_sessions: u64,
+}
+
+pub enum ChangeUserActiveError {
+    UsersError(SetUserActiveError),
+    SessionsError(InvalidateAllSessionsForUserError),
+}
+
+impl<'r> Responder<'r, 'static> for ChangeUserActiveError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::UsersError(SetUserActiveError::RepositoryError(err)) => {
+                let message = err.to_string();
+                let status = match err {
+                    SetUserActiveRepositoryError::NotFound(_) => Status::NotFound,
+                    SetUserActiveRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    SetUserActiveRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+            Self::SessionsError(InvalidateAllSessionsForUserError::RepositoryError(err)) => {
+                let message = err.to_string();
+                let status = match err {
+                    UpdateSessionRepositoryError::NotFound(_) => Status::NotFound,
+                    UpdateSessionRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    UpdateSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for ChangeUserActiveError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "401",
+                    "Returned when the admin session expired",
+                ),
+                (
+                    "403",
+                    "Returned without an admin session or when the client address isn't in the admin allowlist",
+                ),
+                (
+                    "404",
+                    "Returned when the the user with given id doesn't exist",
+                ),
+                ("422", "Returned when the the user_id is not a valid UUID"),
+            ],
+        )
+    }
+}
+
+// Deactivated users can't log in and all their sessions are invalidated right away
+#[openapi(tag = "Admin")]
+#[put(
+    "/admin/users/<user_id>/active",
+    format = "application/json",
+    data = "<dto>"
+)]
+pub async fn set_user_active(
+    ctx: &Ctx,
+    _admin_ip_allowlist: AdminIpAllowlist,
+    _admin_session: AdminSession,
+    user_id: Uuid,
+    dto: Json<SetUserActiveDto>,
+) -> Result<Json<SetUserActiveResponse>, ChangeUserActiveError> {
+    let user = ctx
+        .authentication_service
+        .set_user_active(user_id, dto.0.active)
+        .await
+        .map_err(ChangeUserActiveError::UsersError)?;
+
+    let invalidated_sessions = if user.is_active {
+        0
+    } else {
+        ctx.sessions_service
+            .invalidate_all_sessions_for_user(user.id)
+            .await
+            .map_err(ChangeUserActiveError::SessionsError)?
+    };
+
+    Ok(Json(SetUserActiveResponse {
+        user_id: user.id,
+        is_active: user.is_active,
+        invalidated_sessions,
+    }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LogoutAllResponse {
+    user_id: Uuid,
+    invalidated_sessions: u64,
+}
+
+pub enum LogoutAllError {
+    UsersError(GetUserByIdError),
+    SessionsError(InvalidateAllSessionsForUserError),
+}
+
+impl<'r> Responder<'r, 'static> for LogoutAllError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::UsersError(GetUserByIdError::RepositoryError(err)) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetUserByIdRepositoryError::NotFound(_) => Status::NotFound,
+                    GetUserByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetUserByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+            Self::SessionsError(InvalidateAllSessionsForUserError::RepositoryError(err)) => {
+                let message = err.to_string();
+                let status = match err {
+                    UpdateSessionRepositoryError::NotFound(_) => Status::NotFound,
+                    UpdateSessionRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    UpdateSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for LogoutAllError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "401",
+                    "Returned when the admin session expired",
+                ),
+                (
+                    "403",
+                    "Returned without an admin session or when the client address isn't in the admin allowlist",
+                ),
+                (
+                    "404",
+                    "Returned when the the user with given id doesn't exist",
+                ),
+                ("422", "Returned when the the user_id is not a valid UUID"),
+            ],
+        )
+    }
+}
+
+// Logs a compromised account out everywhere without deactivating it
+#[openapi(tag = "Admin")]
+#[post("/admin/users/<user_id>/logout-all")]
+pub async fn logout_all(
+    ctx: &Ctx,
+    _admin_ip_allowlist: AdminIpAllowlist,
+    _admin_session: AdminSession,
+    user_id: Uuid,
+) -> Result<Json<LogoutAllResponse>, LogoutAllError> {
+    let user = ctx
+        .authentication_service
+        .get_user_by_id(user_id)
+        .await
+        .map_err(LogoutAllError::UsersError)?;
+
+    let invalidated_sessions = ctx
+        .sessions_service
+        .invalidate_all_sessions_for_user(user.id)
+        .await
+        .map_err(LogoutAllError::SessionsError)?;
+
+    Ok(Json(LogoutAllResponse {
+        user_id: user.id,
+        invalidated_sessions,
+    }))
+}
+
+impl<'r> Responder<'r, 'static> for CountSessionsError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetSessionRepositoryError::NotFound(_) => Status::NotFound,
+                    GetSessionRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CountSessionsError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "401",
+                    "Returned when the admin session expired",
+                ),
+                (
+                    "403",
+                    "Returned without an admin session or when the client address isn't in the admin allowlist",
+                ),
+                (
+                    "503",
+                    "Returned when the database is temporarily unavailable",
+                ),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Admin")]
+#[get("/admin/sessions/stats")]
+pub async fn get_sessions_stats(
+    ctx: &Ctx,
+    _admin_ip_allowlist: AdminIpAllowlist,
+    _admin_session: AdminSession,
+) -> Result<Json<SessionsStats>, CountSessionsError> {
+    let stats = ctx.sessions_service.count_sessions().await?;
+
+    Ok(Json(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Header, Status},
+        local::asynchronous::Client,
+        routes,
+        serde::json::Value,
+    };
+
+    use super::{LogoutAllResponse, SetUserActiveResponse};
+    use crate::application::{
+        api::{
+            controllers::authentication_controller,
+            utils::{api_config::ApiConfig, fake_api_context::create_fake_api_context},
+        },
+        sessions::entities::SessionsStats,
+    };
+
+    async fn create_api_client() -> (Client, String) {
+        create_api_client_with_config(ApiConfig::default()).await
+    }
+
+    async fn create_api_client_with_config(config: ApiConfig) -> (Client, String) {
+        let context = create_fake_api_context();
+        context
+            .authentication_service
+            .register_admin(
+                "admin".into(),
+                "password123".into(),
+                "admin@gmail.com".into(),
+                "123456789".into(),
+            )
+            .await
+            .unwrap();
+
+        let routes = routes![
+            super::set_user_active,
+            super::logout_all,
+            super::get_sessions_stats,
+            authentication_controller::register_doctor,
+            authentication_controller::login_doctor,
+            authentication_controller::login_admin,
+            authentication_controller::get_current_user,
+            authentication_controller::endpoint_that_requires_authorization_as_doctor
+        ];
+
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(config)
+            .mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let response = client
+            .post("/auth/login/admin")
+            .header(ContentType::JSON)
+            .body(r#"{"username": "admin", "password": "password123"}"#)
+            .dispatch()
+            .await;
+        let body = response.into_json::<Value>().await.unwrap();
+        let admin_token = body["token"].as_str().unwrap().to_string();
+
+        (client, admin_token)
+    }
+
+    async fn login(client: &Client) -> Option<String> {
+        let response = client
+            .post("/auth/login/doctor")
+            .header(ContentType::JSON)
+            .body(r#"{"username": "doctor", "password": "password123"}"#)
+            .dispatch()
+            .await;
+
+        if response.status() != Status::Ok {
+            return None;
+        }
+
+        let body = response.into_json::<Value>().await.unwrap();
+        Some(body["token"].as_str().unwrap().to_string())
+    }
+
+    async fn set_user_active(client: &Client, token: &str, user_id: &str, active: bool) -> Status {
+        client
+            .put(format!("/admin/users/{}/active", user_id))
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(r#"{{"active": {}}}"#, active))
+            .dispatch()
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn deactivated_user_cant_log_in_until_reactivated() {
+        let (client, admin_token) = create_api_client().await;
+
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+
+        let token = login(&client).await.unwrap();
+        let user = client
+            .get("/auth/me")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await
+            .into_json::<Value>()
+            .await
+            .unwrap();
+        let user_id = user["id"].as_str().unwrap();
+
+        let response = client
+            .put(format!("/admin/users/{}/active", user_id))
+            .header(ContentType::JSON)
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .body(r#"{"active": false}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = response.into_json::<SetUserActiveResponse>().await.unwrap();
+
+        assert!(!response.is_active);
+        assert_eq!(response.invalidated_sessions, 1);
+
+        let response = client
+            .get("/auth/me")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(login(&client).await, None);
+
+        assert_eq!(
+            set_user_active(&client, &admin_token, user_id, true).await,
+            Status::Ok
+        );
+        assert!(login(&client).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn set_user_active_returns_error_if_user_doesnt_exist_or_id_is_invalid() {
+        let (client, admin_token) = create_api_client().await;
+
+        assert_eq!(
+            set_user_active(
+                &client,
+                &admin_token,
+                "00000000-0000-0000-0000-000000000000",
+                false
+            )
+            .await,
+            Status::NotFound
+        );
+        assert_eq!(
+            set_user_active(&client, &admin_token, "not-a-uuid", false).await,
+            Status::UnprocessableEntity
+        );
+    }
+
+    #[tokio::test]
+    async fn logout_all_invalidates_every_session_of_user() {
+        let (client, admin_token) = create_api_client().await;
+
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+
+        let tokens = vec![
+            login(&client).await.unwrap(),
+            login(&client).await.unwrap(),
+            login(&client).await.unwrap(),
+        ];
+        let user = client
+            .get("/auth/me")
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", tokens[0]),
+            ))
+            .dispatch()
+            .await
+            .into_json::<Value>()
+            .await
+            .unwrap();
+        let user_id = user["id"].as_str().unwrap();
+
+        let response = client
+            .post(format!("/admin/users/{}/logout-all", user_id))
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = response.into_json::<LogoutAllResponse>().await.unwrap();
+
+        assert_eq!(response.invalidated_sessions, 3);
+
+        for token in tokens {
+            let response = client
+                .get("/test-collection/endpoint-that-requires-authorization-as-doctor")
+                .header(Header::new("Authorization", format!("Bearer {}", token)))
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Forbidden);
+        }
+
+        // The account itself stays active
+        assert!(login(&client).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn logout_all_returns_not_found_if_user_doesnt_exist() {
+        let (client, admin_token) = create_api_client().await;
+
+        let response = client
+            .post("/admin/users/00000000-0000-0000-0000-000000000000/logout-all")
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    async fn get_sessions_stats(client: &Client, token: &str) -> SessionsStats {
+        client
+            .get("/admin/sessions/stats")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await
+            .into_json::<SessionsStats>()
+            .await
+            .unwrap()
+    }
+
+    // The session of the admin is counted as well
+    #[tokio::test]
+    async fn counts_active_and_invalidated_sessions() {
+        let (client, admin_token) = create_api_client().await;
+
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+
+        assert_eq!(
+            get_sessions_stats(&client, &admin_token).await,
+            SessionsStats {
+                active: 1,
+                invalidated: 0
+            }
+        );
+
+        let token = login(&client).await.unwrap();
+        login(&client).await.unwrap();
+        login(&client).await.unwrap();
+
+        assert_eq!(
+            get_sessions_stats(&client, &admin_token).await,
+            SessionsStats {
+                active: 4,
+                invalidated: 0
+            }
+        );
+
+        let user = client
+            .get("/auth/me")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await
+            .into_json::<Value>()
+            .await
+            .unwrap();
+        client
+            .post(format!(
+                "/admin/users/{}/logout-all",
+                user["id"].as_str().unwrap()
+            ))
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(
+            get_sessions_stats(&client, &admin_token).await,
+            SessionsStats {
+                active: 1,
+                invalidated: 3
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_endpoints_require_admin_session() {
+        let (client, _) = create_api_client().await;
+
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+
+        let token = login(&client).await.unwrap();
+
+        let response = client.get("/admin/sessions/stats").dispatch().await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .get("/admin/sessions/stats")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .post("/admin/users/00000000-0000-0000-0000-000000000000/logout-all")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        assert_eq!(
+            set_user_active(
+                &client,
+                &token,
+                "00000000-0000-0000-0000-000000000000",
+                false
+            )
+            .await,
+            Status::Forbidden
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_endpoints_are_restricted_to_allowlisted_addresses() {
+        let config = ApiConfig {
+            admin_ip_allowlist: vec!["10.0.0.1".parse().unwrap()],
+            ..ApiConfig::default()
+        };
+        let (client, admin_token) = create_api_client_with_config(config).await;
+
+        let response = client
+            .get("/admin/sessions/stats")
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get("/admin/sessions/stats")
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .remote("10.0.0.2:8000".parse().unwrap())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .post("/admin/users/00000000-0000-0000-0000-000000000000/logout-all")
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", admin_token),
+            ))
+            .remote("10.0.0.2:8000".parse().unwrap())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+}
@@ -1,32 +1,59 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
 use okapi::openapi3::Responses;
 use rocket::{get, http::Status, post, response::Responder, serde::json::Json, Request};
 use rocket_okapi::{gen::OpenApiGenerator, openapi, response::OpenApiResponderInner, OpenApiError};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
     application::{
         api::{
             guards::{
-                authorization::{DoctorSession, PharmacistSession},
+                authorization::{DoctorSession, PatientSession, PharmacistSession},
                 client_request_info::ClientRequestInfo,
             },
-            utils::{error::ApiError, openapi_responses::get_openapi_responses},
+            utils::{
+                error::ApiError, openapi_responses::get_openapi_responses,
+                pagination_messages::pagination_error_message,
+            },
         },
         authentication::{
-            entities::UserRole,
-            repository::CreateUserRepositoryError,
-            service::{AuthenticationWithCredentialsError, CreateUserError},
+            entities::{TotpEnrollment, User, UserLink, UserRole},
+            repository::{
+                CreateUserRepositoryError, GetUserByIdRepositoryError, UpdateTotpRepositoryError,
+            },
+            service::{
+                AuthenticationWithCredentialsError, CreateUserError, EnrollTotpError,
+                GetUserByIdError, VerifyTotpError,
+            },
         },
         sessions::{
-            entities::Session, repository::UpdateSessionRepositoryError,
-            service::InvalidateSessionError,
+            entities::Session,
+            repository::{
+                CreateSessionRepositoryError, GetSessionRepositoryError,
+                UpdateSessionRepositoryError,
+            },
+            service::{CreateSessionError, InvalidateSessionError},
         },
     },
     domain::{
-        doctors::{repository::CreateDoctorRepositoryError, service::CreateDoctorError},
+        doctors::{
+            entities::Doctor, repository::CreateDoctorRepositoryError, service::CreateDoctorError,
+        },
+        patients::{
+            entities::Patient, repository::CreatePatientRepositoryError,
+            service::CreatePatientError,
+        },
         pharmacists::{
-            repository::CreatePharmacistRepositoryError, service::CreatePharmacistError,
+            entities::Pharmacist, repository::CreatePharmacistRepositoryError,
+            service::CreatePharmacistError,
+        },
+        prescriptions::{
+            entities::Prescription, repository::GetPrescriptionsRepositoryError,
+            service::GetPrescriptionsForPatientError,
         },
     },
     Ctx,
@@ -53,6 +80,9 @@ fn example_pesel_number() -> &'static str {
 fn example_pwz_number() -> &'static str {
     "3123456"
 }
+fn example_specialization() -> Option<&'static str> {
+    Some("cardiology")
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RegisterDoctorDto {
@@ -70,6 +100,8 @@ pub struct RegisterDoctorDto {
     pesel_number: String,
     #[schemars(example = "example_pwz_number")]
     pwz_number: String,
+    #[schemars(example = "example_specialization")]
+    specialization: Option<String>,
 }
 
 pub enum RegisterDoctorError {
@@ -90,6 +122,7 @@ impl<'r> Responder<'r, 'static> for RegisterDoctorError {
                         CreateDoctorRepositoryError::DatabaseError(_) => {
                             Status::InternalServerError
                         }
+                        CreateDoctorRepositoryError::Unavailable => Status::ServiceUnavailable,
                     };
                     (message, status)
                 }
@@ -100,6 +133,7 @@ impl<'r> Responder<'r, 'static> for RegisterDoctorError {
                     let message = err.to_string();
                     let status = match err {
                         CreateUserRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                        CreateUserRepositoryError::Unavailable => Status::ServiceUnavailable,
                     };
                     (message, status)
                 }
@@ -111,9 +145,15 @@ impl<'r> Responder<'r, 'static> for RegisterDoctorError {
 }
 
 impl OpenApiResponderInner for RegisterDoctorError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
         // TODO: Add all responses
-        get_openapi_responses(vec![])
+        get_openapi_responses(
+            gen,
+            vec![(
+                "415",
+                "Returned when the Content-Type is not application/json",
+            )],
+        )
     }
 }
 
@@ -130,7 +170,12 @@ pub async fn register_doctor(
 ) -> Result<Json<SuccessResponse>, RegisterDoctorError> {
     let created_doctor = ctx
         .doctors_service
-        .create_doctor(dto.0.name, dto.0.pesel_number, dto.0.pwz_number)
+        .create_doctor(
+            dto.0.name,
+            dto.0.pesel_number,
+            dto.0.pwz_number,
+            dto.0.specialization,
+        )
         .await
         .map_err(|err| RegisterDoctorError::DoctorsError(err))?;
 
@@ -140,9 +185,7 @@ pub async fn register_doctor(
             dto.0.password,
             dto.0.email,
             dto.0.phone_number,
-            UserRole::Doctor,
-            Some(created_doctor.id),
-            None,
+            UserLink::Doctor(created_doctor.id),
         )
         .await
         .map_err(|err| RegisterDoctorError::UsersError(err))?;
@@ -183,6 +226,7 @@ impl<'r> Responder<'r, 'static> for RegisterPharmacistError {
                         CreatePharmacistRepositoryError::DatabaseError(_) => {
                             Status::InternalServerError
                         }
+                        CreatePharmacistRepositoryError::Unavailable => Status::ServiceUnavailable,
                     };
                     (message, status)
                 }
@@ -193,6 +237,7 @@ impl<'r> Responder<'r, 'static> for RegisterPharmacistError {
                     let message = err.to_string();
                     let status = match err {
                         CreateUserRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                        CreateUserRepositoryError::Unavailable => Status::ServiceUnavailable,
                     };
                     (message, status)
                 }
@@ -204,9 +249,15 @@ impl<'r> Responder<'r, 'static> for RegisterPharmacistError {
 }
 
 impl OpenApiResponderInner for RegisterPharmacistError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
         // TODO: Add all responses
-        get_openapi_responses(vec![])
+        get_openapi_responses(
+            gen,
+            vec![(
+                "415",
+                "Returned when the Content-Type is not application/json",
+            )],
+        )
     }
 }
 
@@ -232,9 +283,7 @@ pub async fn register_pharmacist(
             dto.0.password,
             dto.0.email,
             dto.0.phone_number,
-            UserRole::Pharmacist,
-            None,
-            Some(created_pharmacist.id),
+            UserLink::Pharmacist(created_pharmacist.id),
         )
         .await
         .map_err(|err| RegisterPharmacistError::UsersError(err))?;
@@ -242,22 +291,189 @@ pub async fn register_pharmacist(
     Ok(Json(SuccessResponse { success: true }))
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RegisterPatientDto {
+    #[schemars(example = "example_username")]
+    username: String,
+    #[schemars(example = "example_password")]
+    password: String,
+    #[schemars(example = "example_email")]
+    email: String,
+    #[schemars(example = "example_phone_number")]
+    phone_number: String,
+    #[schemars(example = "example_name")]
+    name: String,
+    #[schemars(example = "example_pesel_number")]
+    pesel_number: String,
+}
+
+pub enum RegisterPatientError {
+    PatientsError(CreatePatientError),
+    UsersError(CreateUserError),
+}
+
+impl<'r> Responder<'r, 'static> for RegisterPatientError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::PatientsError(patients_err) => match patients_err {
+                CreatePatientError::DomainError(err) => (err, Status::UnprocessableEntity),
+                CreatePatientError::RepositoryError(err) => {
+                    let message = err.to_string();
+                    let status = match err {
+                        CreatePatientRepositoryError::DuplicatedPeselNumber => Status::Conflict,
+                        CreatePatientRepositoryError::DatabaseError(_) => {
+                            Status::InternalServerError
+                        }
+                        CreatePatientRepositoryError::Unavailable => Status::ServiceUnavailable,
+                    };
+                    (message, status)
+                }
+            },
+            Self::UsersError(users_err) => match users_err {
+                CreateUserError::DomainError(err) => (err, Status::UnprocessableEntity),
+                CreateUserError::RepositoryError(err) => {
+                    let message = err.to_string();
+                    let status = match err {
+                        CreateUserRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                        CreateUserRepositoryError::Unavailable => Status::ServiceUnavailable,
+                    };
+                    (message, status)
+                }
+            },
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for RegisterPatientError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "409",
+                    "Returned when a patient with the given pesel_number already exists",
+                ),
+                (
+                    "415",
+                    "Returned when the Content-Type is not application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the name, pesel_number or email are incorrect",
+                ),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Auth")]
+#[post("/auth/register/patient", data = "<dto>", format = "application/json")]
+pub async fn register_patient(
+    ctx: &Ctx,
+    dto: Json<RegisterPatientDto>,
+) -> Result<Json<SuccessResponse>, RegisterPatientError> {
+    let created_patient = ctx
+        .patients_service
+        .create_patient(dto.0.name, dto.0.pesel_number, None)
+        .await
+        .map_err(RegisterPatientError::PatientsError)?;
+
+    ctx.authentication_service
+        .register_user(
+            dto.0.username,
+            dto.0.password,
+            dto.0.email,
+            dto.0.phone_number,
+            UserLink::Patient(created_patient.id),
+        )
+        .await
+        .map_err(RegisterPatientError::UsersError)?;
+
+    Ok(Json(SuccessResponse { success: true }))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SessionTokenResponse {
     token: String,
 }
 
-impl<'r> Responder<'r, 'static> for AuthenticationWithCredentialsError {
+pub enum LoginError {
+    EmptyCredentials,
+    AuthenticationError(AuthenticationWithCredentialsError),
+    SessionsError(CreateSessionError),
+}
+
+impl<'r> Responder<'r, 'static> for LoginError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
-        let message = self.to_string();
-        let status = Status::Unauthorized;
+        let (message, status) = match self {
+            Self::EmptyCredentials => (
+                "Username and password can't be empty".to_string(),
+                Status::UnprocessableEntity,
+            ),
+            Self::AuthenticationError(err) => (err.to_string(), Status::Unauthorized),
+            Self::SessionsError(sessions_err) => match sessions_err {
+                CreateSessionError::SessionLimitReached(max_sessions) => (
+                    format!(
+                        "Maximum number of active sessions ({}) reached, log out of another session first",
+                        max_sessions
+                    ),
+                    Status::Conflict,
+                ),
+                CreateSessionError::ActiveSessionsError(err) => {
+                    let message = err.to_string();
+                    let status = match err {
+                        GetSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                        _ => Status::InternalServerError,
+                    };
+                    (message, status)
+                }
+                CreateSessionError::InvalidateOldestError(err) => {
+                    let message = err.to_string();
+                    let status = match err {
+                        UpdateSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                        _ => Status::InternalServerError,
+                    };
+                    (message, status)
+                }
+                CreateSessionError::RepositoryError(err) => {
+                    let message = err.to_string();
+                    let status = match err {
+                        CreateSessionRepositoryError::DatabaseError(_) => {
+                            Status::InternalServerError
+                        }
+                        CreateSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                    };
+                    (message, status)
+                }
+            },
+        };
+
         ApiError::build_rocket_response(req, message, status)
     }
 }
 
-impl OpenApiResponderInner for AuthenticationWithCredentialsError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![("401", "Ivalid credentials")])
+impl OpenApiResponderInner for LoginError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "401",
+                "Ivalid credentials, or missing or invalid two-factor authentication code of a user who enabled it",
+            ),
+            (
+                "409",
+                "Returned when the user reached the maximum number of active sessions and new sessions over the limit are rejected",
+            ),
+            (
+                "415",
+                "Returned when the Content-Type is not application/json",
+            ),
+            (
+                "422",
+                "Returned when the username or password is empty",
+            ),
+        ])
     }
 }
 
@@ -265,6 +481,19 @@ impl OpenApiResponderInner for AuthenticationWithCredentialsError {
 pub struct LoginWithCredentialsDto {
     username: String,
     password: String,
+    // Required only from users who enabled two-factor authentication
+    totp_code: Option<String>,
+}
+
+impl LoginWithCredentialsDto {
+    // Rejected before looking the user up and hashing the password
+    fn validate(&self) -> Result<(), LoginError> {
+        if self.username.trim().is_empty() || self.password.trim().is_empty() {
+            Err(LoginError::EmptyCredentials)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[openapi(tag = "Auth")]
@@ -273,12 +502,21 @@ pub async fn login_doctor(
     ctx: &Ctx,
     dto: Json<LoginWithCredentialsDto>,
     client: ClientRequestInfo,
-) -> Result<Json<SessionTokenResponse>, AuthenticationWithCredentialsError> {
+) -> Result<Json<SessionTokenResponse>, LoginError> {
+    dto.validate()?;
+
     let user = ctx
         .authentication_service
-        .authenticate_with_credentials(dto.0.username, dto.0.password, UserRole::Doctor)
+        .authenticate_with_credentials(
+            dto.0.username,
+            dto.0.password,
+            UserRole::Doctor,
+            dto.0.totp_code,
+            client.ip_address,
+            &client.user_agent,
+        )
         .await
-        .map_err(|_| AuthenticationWithCredentialsError::InvalidCredentials)?;
+        .map_err(LoginError::AuthenticationError)?;
 
     let session = ctx
         .sessions_service
@@ -286,14 +524,24 @@ pub async fn login_doctor(
             user.id,
             user.doctor.map(|d| d.id),
             None,
+            None,
             client.ip_address,
             client.user_agent,
         )
         .await
-        .unwrap();
+        .map_err(LoginError::SessionsError)?;
+
+    // Failing to record the login shouldn't prevent the user from logging in
+    if let Err(err) = ctx
+        .authentication_service
+        .record_login(user.id, client.ip_address)
+        .await
+    {
+        tracing::warn!(user_id = %user.id, error = ?err, "Failed to record login");
+    }
 
     Ok(Json(SessionTokenResponse {
-        token: session.id.to_string(),
+        token: ctx.sessions_service.encode_session_token(&session),
     }))
 }
 
@@ -303,12 +551,21 @@ pub async fn login_pharmacist(
     ctx: &Ctx,
     dto: Json<LoginWithCredentialsDto>,
     client: ClientRequestInfo,
-) -> Result<Json<SessionTokenResponse>, AuthenticationWithCredentialsError> {
+) -> Result<Json<SessionTokenResponse>, LoginError> {
+    dto.validate()?;
+
     let user = ctx
         .authentication_service
-        .authenticate_with_credentials(dto.0.username, dto.0.password, UserRole::Pharmacist)
+        .authenticate_with_credentials(
+            dto.0.username,
+            dto.0.password,
+            UserRole::Pharmacist,
+            dto.0.totp_code,
+            client.ip_address,
+            &client.user_agent,
+        )
         .await
-        .map_err(|_| AuthenticationWithCredentialsError::InvalidCredentials)?;
+        .map_err(LoginError::AuthenticationError)?;
 
     let session = ctx
         .sessions_service
@@ -316,14 +573,122 @@ pub async fn login_pharmacist(
             user.id,
             None,
             user.pharmacist.map(|p| p.id),
+            None,
+            client.ip_address,
+            client.user_agent,
+        )
+        .await
+        .map_err(LoginError::SessionsError)?;
+
+    // Failing to record the login shouldn't prevent the user from logging in
+    if let Err(err) = ctx
+        .authentication_service
+        .record_login(user.id, client.ip_address)
+        .await
+    {
+        tracing::warn!(user_id = %user.id, error = ?err, "Failed to record login");
+    }
+
+    Ok(Json(SessionTokenResponse {
+        token: ctx.sessions_service.encode_session_token(&session),
+    }))
+}
+
+#[openapi(tag = "Auth")]
+#[post("/auth/login/patient", data = "<dto>", format = "application/json")]
+pub async fn login_patient(
+    ctx: &Ctx,
+    dto: Json<LoginWithCredentialsDto>,
+    client: ClientRequestInfo,
+) -> Result<Json<SessionTokenResponse>, LoginError> {
+    dto.validate()?;
+
+    let user = ctx
+        .authentication_service
+        .authenticate_with_credentials(
+            dto.0.username,
+            dto.0.password,
+            UserRole::Patient,
+            dto.0.totp_code,
+            client.ip_address,
+            &client.user_agent,
+        )
+        .await
+        .map_err(LoginError::AuthenticationError)?;
+
+    let session = ctx
+        .sessions_service
+        .create_session(
+            user.id,
+            None,
+            None,
+            user.patient.map(|p| p.id),
+            client.ip_address,
+            client.user_agent,
+        )
+        .await
+        .map_err(LoginError::SessionsError)?;
+
+    // Failing to record the login shouldn't prevent the user from logging in
+    if let Err(err) = ctx
+        .authentication_service
+        .record_login(user.id, client.ip_address)
+        .await
+    {
+        tracing::warn!(user_id = %user.id, error = ?err, "Failed to record login");
+    }
+
+    Ok(Json(SessionTokenResponse {
+        token: ctx.sessions_service.encode_session_token(&session),
+    }))
+}
+
+#[openapi(tag = "Auth")]
+#[post("/auth/login/admin", data = "<dto>", format = "application/json")]
+pub async fn login_admin(
+    ctx: &Ctx,
+    dto: Json<LoginWithCredentialsDto>,
+    client: ClientRequestInfo,
+) -> Result<Json<SessionTokenResponse>, LoginError> {
+    dto.validate()?;
+
+    let user = ctx
+        .authentication_service
+        .authenticate_with_credentials(
+            dto.0.username,
+            dto.0.password,
+            UserRole::Admin,
+            dto.0.totp_code,
+            client.ip_address,
+            &client.user_agent,
+        )
+        .await
+        .map_err(LoginError::AuthenticationError)?;
+
+    let session = ctx
+        .sessions_service
+        .create_session(
+            user.id,
+            None,
+            None,
+            None,
             client.ip_address,
             client.user_agent,
         )
         .await
-        .unwrap();
+        .map_err(LoginError::SessionsError)?;
+
+    // Failing to record the login shouldn't prevent the user from logging in
+    if let Err(err) = ctx
+        .authentication_service
+        .record_login(user.id, client.ip_address)
+        .await
+    {
+        tracing::warn!(user_id = %user.id, error = ?err, "Failed to record login");
+    }
 
     Ok(Json(SessionTokenResponse {
-        token: session.id.to_string(),
+        token: ctx.sessions_service.encode_session_token(&session),
     }))
 }
 
@@ -335,6 +700,7 @@ impl<'r> Responder<'r, 'static> for InvalidateSessionError {
                 let message = err.to_string();
                 let status = match err {
                     UpdateSessionRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    UpdateSessionRepositoryError::Unavailable => Status::ServiceUnavailable,
                     UpdateSessionRepositoryError::NotFound(_) => Status::NotFound,
                 };
                 (message, status)
@@ -346,8 +712,8 @@ impl<'r> Responder<'r, 'static> for InvalidateSessionError {
 }
 
 impl OpenApiResponderInner for InvalidateSessionError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![("404", "Session not found")])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![("404", "Session not found")])
     }
 }
 
@@ -363,58 +729,377 @@ pub async fn logout(
         .map(|_| Json(SuccessResponse { success: true }))
 }
 
-pub struct AuthError;
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CurrentUserResponse {
+    id: Uuid,
+    username: String,
+    email: String,
+    phone_number: String,
+    role: UserRole,
+    last_login_at: Option<DateTime<Utc>>,
+    last_login_ip: Option<IpAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doctor: Option<Doctor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pharmacist: Option<Pharmacist>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    patient: Option<Patient>,
+}
 
-impl<'r> Responder<'r, 'static> for AuthError {
-    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
-        let message = "Unauthorized".into();
-        let status = Status::Unauthorized;
-        ApiError::build_rocket_response(req, message, status)
+impl From<User> for CurrentUserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            phone_number: user.phone_number,
+            role: user.role,
+            last_login_at: user.last_login_at,
+            last_login_ip: user.last_login_ip,
+            doctor: user.doctor,
+            pharmacist: user.pharmacist,
+            patient: user.patient,
+        }
     }
 }
 
-#[get("/test-collection/endpoint-that-requires-authorization-as-doctor")]
-pub async fn endpoint_that_requires_authorization_as_doctor(
-    session: DoctorSession,
-) -> Result<String, AuthError> {
-    Ok(format!(
-        "You are authorized as a doctor {}",
-        session.0.doctor_id.unwrap()
-    ))
+pub enum GetCurrentUserError {
+    Unauthorized,
+    UsersError(GetUserByIdError),
 }
 
-#[get("/test-collection/endpoint-that-requires-authorization-as-pharmacist")]
-pub async fn endpoint_that_requires_authorization_as_pharmacist(
-    session: PharmacistSession,
-) -> Result<String, AuthError> {
-    Ok(format!(
-        "You are authorized as a pharmacist {}",
-        session.0.pharmacist_id.unwrap()
-    ))
+impl<'r> Responder<'r, 'static> for GetCurrentUserError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::Unauthorized => ("Unauthorized".into(), Status::Unauthorized),
+            Self::UsersError(users_err) => match users_err {
+                GetUserByIdError::RepositoryError(err) => {
+                    let message = err.to_string();
+                    let status = match err {
+                        GetUserByIdRepositoryError::NotFound(_) => Status::NotFound,
+                        GetUserByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                        GetUserByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
+                    };
+                    (message, status)
+                }
+            },
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use rocket::{
-        http::{ContentType, Header, Status},
-        local::asynchronous::Client,
-        routes,
-    };
+impl OpenApiResponderInner for GetCurrentUserError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "401",
+                    "Returned when the session token is missing, invalid or expired",
+                ),
+                (
+                    "404",
+                    "Returned when the user the session belongs to doesn't exist",
+                ),
+            ],
+        )
+    }
+}
 
-    use super::SessionTokenResponse;
-    use crate::application::api::utils::fake_api_context::create_fake_api_context;
+#[openapi(tag = "Auth")]
+#[get("/auth/me")]
+pub async fn get_current_user(
+    ctx: &Ctx,
+    session: Option<Session>,
+) -> Result<Json<CurrentUserResponse>, GetCurrentUserError> {
+    let session = session.ok_or(GetCurrentUserError::Unauthorized)?;
 
-    async fn create_api_client() -> Client {
-        let context = create_fake_api_context();
+    let user = ctx
+        .authentication_service
+        .get_user_by_id(session.user_id)
+        .await
+        .map_err(GetCurrentUserError::UsersError)?;
 
-        let routes = routes![
-            super::register_doctor,
-            super::register_pharmacist,
-            super::login_doctor,
-            super::login_pharmacist,
+    Ok(Json(user.into()))
+}
+
+impl<'r> Responder<'r, 'static> for GetPrescriptionsForPatientError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetPrescriptionsForPatientError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "403",
+                    "Returned when the session token is missing or doesn't belong to a patient",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                ("422", "Returned when the page < 0 or page_size < 1"),
+            ],
+        )
+    }
+}
+
+// Patients only ever see their own prescriptions, the patient comes from the session
+#[openapi(tag = "Auth")]
+#[get(
+    "/auth/me/prescriptions?<page>&<page_size>",
+    format = "application/json"
+)]
+pub async fn get_my_prescriptions(
+    ctx: &Ctx,
+    session: PatientSession,
+    page: Option<i64>,
+    page_size: Option<i64>,
+) -> Result<Json<Vec<Prescription>>, GetPrescriptionsForPatientError> {
+    let patient_id = session.0.patient_id.unwrap();
+    let prescriptions = ctx
+        .prescriptions_service
+        .get_prescriptions_for_patient(patient_id, page, page_size)
+        .await?;
+
+    Ok(Json(prescriptions))
+}
+
+impl<'r> Responder<'r, 'static> for EnrollTotpError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::Conflict),
+            Self::UsersError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetUserByIdRepositoryError::NotFound(_) => Status::NotFound,
+                    GetUserByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetUserByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    UpdateTotpRepositoryError::NotFound(_) => Status::NotFound,
+                    UpdateTotpRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    UpdateTotpRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for EnrollTotpError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "403",
+                    "Returned when the session token is missing, invalid or expired",
+                ),
+                (
+                    "409",
+                    "Returned when the user already enabled two-factor authentication",
+                ),
+            ],
+        )
+    }
+}
+
+// Stores a new secret, the code is required to log in only after it's confirmed with /auth/2fa/verify
+#[openapi(tag = "Auth")]
+#[post("/auth/2fa/enroll", format = "application/json")]
+pub async fn enroll_totp(
+    ctx: &Ctx,
+    session: Session,
+) -> Result<Json<TotpEnrollment>, EnrollTotpError> {
+    let enrollment = ctx
+        .authentication_service
+        .enroll_totp(session.user_id)
+        .await?;
+
+    Ok(Json(enrollment))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VerifyTotpDto {
+    code: String,
+}
+
+impl<'r> Responder<'r, 'static> for VerifyTotpError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::UsersError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetUserByIdRepositoryError::NotFound(_) => Status::NotFound,
+                    GetUserByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetUserByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    UpdateTotpRepositoryError::NotFound(_) => Status::NotFound,
+                    UpdateTotpRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    UpdateTotpRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for VerifyTotpError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "403",
+                "Returned when the session token is missing, invalid or expired",
+            ),
+            (
+                "422",
+                "Returned when the code is invalid or the user didn't enroll for two-factor authentication",
+            ),
+        ])
+    }
+}
+
+#[openapi(tag = "Auth")]
+#[post("/auth/2fa/verify", data = "<dto>", format = "application/json")]
+pub async fn verify_totp(
+    ctx: &Ctx,
+    session: Session,
+    dto: Json<VerifyTotpDto>,
+) -> Result<Json<SuccessResponse>, VerifyTotpError> {
+    ctx.authentication_service
+        .verify_totp(session.user_id, &dto.0.code)
+        .await?;
+
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+pub struct AuthError;
+
+impl<'r> Responder<'r, 'static> for AuthError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let message = "Unauthorized".into();
+        let status = Status::Unauthorized;
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+#[get("/test-collection/endpoint-that-requires-authorization-as-doctor")]
+pub async fn endpoint_that_requires_authorization_as_doctor(
+    session: DoctorSession,
+) -> Result<String, AuthError> {
+    Ok(format!(
+        "You are authorized as a doctor {}",
+        session.0.doctor_id.unwrap()
+    ))
+}
+
+#[get("/test-collection/endpoint-that-requires-authorization-as-pharmacist")]
+pub async fn endpoint_that_requires_authorization_as_pharmacist(
+    session: PharmacistSession,
+) -> Result<String, AuthError> {
+    Ok(format!(
+        "You are authorized as a pharmacist {}",
+        session.0.pharmacist_id.unwrap()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+    use rocket::{
+        http::{ContentType, Header, Status},
+        local::asynchronous::Client,
+        routes,
+        serde::json::{self, Value},
+    };
+    use uuid::Uuid;
+
+    use super::{CurrentUserResponse, SessionTokenResponse};
+    use crate::{
+        application::{
+            api::utils::fake_api_context::create_fake_api_context,
+            authentication::entities::{TotpEnrollment, UserRole},
+            helpers::totp::Totp,
+            sessions::{
+                repository::{SessionsRepository, SessionsRepositoryFake},
+                service::{SessionLimitBehavior, SessionsService},
+                token_codec::UuidTokenCodec,
+            },
+        },
+        domain::prescriptions::{
+            entities::{
+                Prescription, PrescriptionDoctor, PrescriptionFillStatus, PrescriptionPatient,
+                PrescriptionType,
+            },
+            policy::PrescriptionPolicy,
+            repository::PrescriptionsRepositoryFake,
+            service::PrescriptionsService,
+        },
+        Context,
+    };
+
+    async fn create_api_client() -> Client {
+        create_api_client_with_context(create_fake_api_context()).await
+    }
+
+    async fn create_api_client_with_context(context: Context) -> Client {
+        let routes = routes![
+            super::register_doctor,
+            super::register_pharmacist,
+            super::register_patient,
+            super::login_doctor,
+            super::login_pharmacist,
+            super::login_patient,
             super::endpoint_that_requires_authorization_as_doctor,
             super::endpoint_that_requires_authorization_as_pharmacist,
-            super::logout
+            super::logout,
+            super::get_current_user,
+            super::get_my_prescriptions,
+            super::enroll_totp,
+            super::verify_totp
         ];
 
         let rocket = rocket::build().manage(context).mount("/", routes);
@@ -572,4 +1257,592 @@ mod tests {
 
         assert_eq!(response.status(), Status::Forbidden);
     }
+
+    async fn register_and_login(client: &Client, role: &str, dto: &str) -> String {
+        let response = client
+            .post(format!("/auth/register/{}", role))
+            .header(ContentType::JSON)
+            .body(dto)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post(format!("/auth/login/{}", role))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "username": "{}",
+                    "password": "password123"
+                }}"#,
+                role
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        response
+            .into_json::<SessionTokenResponse>()
+            .await
+            .unwrap()
+            .token
+    }
+
+    #[tokio::test]
+    async fn returns_current_user_profile_for_doctor() {
+        let client = create_api_client().await;
+        let token = register_and_login(
+            &client,
+            "doctor",
+            r#"{
+                "username": "doctor",
+                "password": "password123",
+                "email": "doctor_john_doe@gmail.com",
+                "phone_number": "123456789",
+                "name": "John Doe",
+                "pesel_number": "99031301347",
+                "pwz_number": "3123456"
+            }"#,
+        )
+        .await;
+
+        let response = client
+            .get("/auth/me")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let user = response.into_json::<CurrentUserResponse>().await.unwrap();
+
+        assert_eq!(user.username, "doctor");
+        assert_eq!(user.role, UserRole::Doctor);
+        assert!(user.doctor.is_some());
+        assert!(user.pharmacist.is_none());
+    }
+
+    #[tokio::test]
+    async fn returns_current_user_profile_for_pharmacist() {
+        let client = create_api_client().await;
+        let token = register_and_login(
+            &client,
+            "pharmacist",
+            r#"{
+                "username": "pharmacist",
+                "password": "password123",
+                "email": "pharmacist_john_doe@gmail.com",
+                "phone_number": "123456789",
+                "name": "John Doe",
+                "pesel_number": "99031301347"
+            }"#,
+        )
+        .await;
+
+        let response = client
+            .get("/auth/me")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body = response.into_string().await.unwrap();
+
+        assert!(!body.contains("password"));
+
+        let user: CurrentUserResponse = rocket::serde::json::from_str(&body).unwrap();
+
+        assert_eq!(user.username, "pharmacist");
+        assert_eq!(user.role, UserRole::Pharmacist);
+        assert!(user.doctor.is_none());
+        assert!(user.pharmacist.is_some());
+    }
+
+    #[tokio::test]
+    async fn registers_and_logs_in_patient() {
+        let client = create_api_client().await;
+        let token = register_and_login(
+            &client,
+            "patient",
+            r#"{
+                "username": "patient",
+                "password": "password123",
+                "email": "patient_john_doe@gmail.com",
+                "phone_number": "123456789",
+                "name": "John Doe",
+                "pesel_number": "96021807250"
+            }"#,
+        )
+        .await;
+
+        let user = get_current_user(&client, &token).await;
+
+        assert_eq!(user.username, "patient");
+        assert_eq!(user.role, UserRole::Patient);
+        assert!(user.doctor.is_none());
+        assert!(user.pharmacist.is_none());
+        assert!(user.patient.is_some());
+
+        let response = client
+            .get("/auth/me/prescriptions")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response
+            .into_json::<Vec<Prescription>>()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    fn create_mock_prescription(patient_id: Uuid) -> Prescription {
+        let prescription_type = PrescriptionType::Regular;
+        let created_at = Utc::now() - Duration::days(1);
+
+        Prescription {
+            id: Uuid::new_v4(),
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: patient_id,
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type,
+            start_date: created_at,
+            end_date: created_at + PrescriptionPolicy::default().get_duration(&prescription_type),
+            prescribed_drugs: vec![],
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_only_prescriptions_of_the_logged_in_patient() {
+        let patient_id = Uuid::new_v4();
+        let other_patient_id = Uuid::new_v4();
+        let prescriptions = vec![
+            create_mock_prescription(patient_id),
+            create_mock_prescription(other_patient_id),
+            create_mock_prescription(patient_id),
+        ];
+        let context = Context {
+            prescriptions_service: Arc::new(PrescriptionsService::new(
+                Box::new(PrescriptionsRepositoryFake::new(
+                    Some(prescriptions.clone()),
+                    None,
+                    None,
+                    None,
+                    None,
+                )),
+                PrescriptionPolicy::default(),
+            )),
+            ..create_fake_api_context()
+        };
+        let create_token = |doctor_id, patient_id| {
+            let sessions_service = context.sessions_service.clone();
+            async move {
+                let session = sessions_service
+                    .create_session(
+                        Uuid::new_v4(),
+                        doctor_id,
+                        None,
+                        patient_id,
+                        "127.0.0.1".parse().unwrap(),
+                        "test".to_string(),
+                    )
+                    .await
+                    .unwrap();
+                sessions_service.encode_session_token(&session)
+            }
+        };
+        let patient_token = create_token(None, Some(patient_id)).await;
+        let other_patient_token = create_token(None, Some(other_patient_id)).await;
+        let doctor_token = create_token(Some(Uuid::new_v4()), None).await;
+        let client = create_api_client_with_context(context).await;
+        let get_prescription_ids = |token: Option<String>| {
+            let mut request = client
+                .get("/auth/me/prescriptions")
+                .header(ContentType::JSON);
+            if let Some(token) = token {
+                request = request.header(Header::new("Authorization", format!("Bearer {}", token)));
+            }
+            async move {
+                let response = request.dispatch().await;
+                if response.status() != Status::Ok {
+                    return Err(response.status());
+                }
+                let mut ids: Vec<Uuid> = response
+                    .into_json::<Vec<Prescription>>()
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|prescription| prescription.id)
+                    .collect();
+                ids.sort();
+                Ok(ids)
+            }
+        };
+
+        let mut expected_ids = vec![prescriptions[0].id, prescriptions[2].id];
+        expected_ids.sort();
+
+        assert_eq!(
+            get_prescription_ids(Some(patient_token)).await,
+            Ok(expected_ids)
+        );
+        assert_eq!(
+            get_prescription_ids(Some(other_patient_token)).await,
+            Ok(vec![prescriptions[1].id])
+        );
+        assert_eq!(
+            get_prescription_ids(Some(doctor_token)).await,
+            Err(Status::Forbidden)
+        );
+        assert_eq!(get_prescription_ids(None).await, Err(Status::Forbidden));
+    }
+
+    async fn login_from(client: &Client, remote: &str) -> String {
+        let response = client
+            .post("/auth/login/doctor")
+            .header(ContentType::JSON)
+            .body(r#"{"username": "doctor", "password": "password123"}"#)
+            .remote(remote.parse().unwrap())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        response
+            .into_json::<SessionTokenResponse>()
+            .await
+            .unwrap()
+            .token
+    }
+
+    async fn get_current_user(client: &Client, token: &str) -> CurrentUserResponse {
+        client
+            .get("/auth/me")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await
+            .into_json::<CurrentUserResponse>()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn returns_last_login_time_and_ip() {
+        let client = create_api_client().await;
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+
+        let token = login_from(&client, "192.168.1.10:443").await;
+        let user = get_current_user(&client, &token).await;
+
+        assert!(user.last_login_at.is_some());
+        assert_eq!(user.last_login_ip, Some("192.168.1.10".parse().unwrap()));
+
+        let token = login_from(&client, "10.0.0.7:443").await;
+        let user_after_second_login = get_current_user(&client, &token).await;
+
+        assert!(user_after_second_login.last_login_at >= user.last_login_at);
+        assert_eq!(
+            user_after_second_login.last_login_ip,
+            Some("10.0.0.7".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn get_current_user_returns_unauthorized_without_valid_session() {
+        let client = create_api_client().await;
+
+        let response = client.get("/auth/me").dispatch().await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client
+            .get("/auth/me")
+            .header(Header::new(
+                "Authorization",
+                "Bearer 00000000-0000-0000-0000-000000000000",
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn login_returns_unprocessable_entity_if_username_or_password_is_empty() {
+        let client = create_api_client().await;
+
+        for uri in [
+            "/auth/login/doctor",
+            "/auth/login/pharmacist",
+            "/auth/login/patient",
+        ] {
+            for body in [
+                r#"{"username": "", "password": "password123"}"#,
+                r#"{"username": "doctor", "password": ""}"#,
+                r#"{"username": "  ", "password": "\t"}"#,
+            ] {
+                let response = client
+                    .post(uri)
+                    .header(ContentType::JSON)
+                    .body(body)
+                    .dispatch()
+                    .await;
+
+                // Unknown users get 401 from the authentication service
+                assert_eq!(response.status(), Status::UnprocessableEntity);
+
+                let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+                assert_eq!(error["message"], "Username and password can't be empty");
+            }
+        }
+    }
+
+    async fn login_with_totp_code(client: &Client, totp_code: Option<&str>) -> Status {
+        let body = match totp_code {
+            Some(totp_code) => format!(
+                r#"{{"username": "doctor", "password": "password123", "totp_code": "{}"}}"#,
+                totp_code
+            ),
+            None => r#"{"username": "doctor", "password": "password123"}"#.to_string(),
+        };
+
+        client
+            .post("/auth/login/doctor")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn requires_totp_code_to_log_in_after_enabling_two_factor_authentication() {
+        let client = create_api_client().await;
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+        let token = login_from(&client, "192.168.1.10:443").await;
+        let authorization = Header::new("Authorization", format!("Bearer {}", token));
+
+        let response = client
+            .post("/auth/2fa/enroll")
+            .header(ContentType::JSON)
+            .header(authorization.clone())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let enrollment = response.into_json::<TotpEnrollment>().await.unwrap();
+
+        assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+
+        let response = client
+            .post("/auth/2fa/verify")
+            .header(ContentType::JSON)
+            .header(authorization.clone())
+            .body(r#"{"code": "abcdef"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/auth/2fa/verify")
+            .header(ContentType::JSON)
+            .header(authorization)
+            .body(format!(
+                r#"{{"code": "{}"}}"#,
+                Totp::generate_current_code(&enrollment.secret)
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/auth/login/doctor")
+            .header(ContentType::JSON)
+            .body(r#"{"username": "doctor", "password": "password123"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert!(response
+            .into_string()
+            .await
+            .unwrap()
+            .contains("Two-factor authentication code required"));
+
+        assert_eq!(
+            login_with_totp_code(&client, Some("abcdef")).await,
+            Status::Unauthorized
+        );
+        assert_eq!(
+            login_with_totp_code(
+                &client,
+                Some(&Totp::generate_current_code(&enrollment.secret))
+            )
+            .await,
+            Status::Ok
+        );
+    }
+
+    #[tokio::test]
+    async fn enroll_totp_returns_forbidden_without_session() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/auth/2fa/enroll")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[tokio::test]
+    async fn rejects_login_over_session_limit() {
+        let sessions_service = SessionsService::new(
+            Box::new(SessionsRepositoryFake::new()),
+            Box::new(UuidTokenCodec),
+        )
+        .with_session_limit(1, SessionLimitBehavior::Reject);
+        let client = create_api_client_with_context(Context {
+            sessions_service: Arc::new(sessions_service),
+            ..create_fake_api_context()
+        })
+        .await;
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+
+        login_from(&client, "127.0.0.1:8000").await;
+
+        let response = client
+            .post("/auth/login/doctor")
+            .header(ContentType::JSON)
+            .body(r#"{"username": "doctor", "password": "password123"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+    }
+
+    #[tokio::test]
+    async fn rejects_expired_session_with_unauthorized() {
+        let sessions_repository = SessionsRepositoryFake::new();
+        let sessions_service = SessionsService::new(
+            Box::new(sessions_repository.clone()),
+            Box::new(UuidTokenCodec),
+        )
+        .with_session_lifetime(Duration::minutes(5));
+        let client = create_api_client_with_context(Context {
+            sessions_service: Arc::new(sessions_service),
+            ..create_fake_api_context()
+        })
+        .await;
+        client
+            .post("/auth/register/doctor")
+            .header(ContentType::JSON)
+            .body(
+                r#"{
+                    "username": "doctor",
+                    "password": "password123",
+                    "email": "doctor_john_doe@gmail.com",
+                    "phone_number": "123456789",
+                    "name": "John Doe",
+                    "pesel_number": "99031301347",
+                    "pwz_number": "3123456"
+                }"#,
+            )
+            .dispatch()
+            .await;
+        let token = login_from(&client, "127.0.0.1:8000").await;
+        let request_as_doctor = || {
+            client
+                .get("/test-collection/endpoint-that-requires-authorization-as-doctor")
+                .header(Header::new("Authorization", format!("Bearer {}", token)))
+                .dispatch()
+        };
+
+        assert_eq!(request_as_doctor().await.status(), Status::Ok);
+
+        let mut session = sessions_repository
+            .get_session_by_id(Uuid::parse_str(&token).unwrap())
+            .await
+            .unwrap();
+
+        assert!(session.expires_at <= Utc::now() + Duration::minutes(5));
+
+        session.expires_at = Utc::now() - Duration::seconds(1);
+        sessions_repository.update_session(session).await.unwrap();
+
+        assert_eq!(request_as_doctor().await.status(), Status::Unauthorized);
+    }
 }
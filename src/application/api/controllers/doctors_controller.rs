@@ -2,10 +2,10 @@ use okapi::openapi3::Responses;
 use rocket::{
     get,
     http::Status,
-    post,
+    patch, post,
     response::{status::Created, Responder},
     serde::json::Json,
-    Request,
+    Request, State,
 };
 use rocket_okapi::{
     gen::OpenApiGenerator, okapi::schemars, openapi, response::OpenApiResponderInner, JsonSchema,
@@ -15,13 +15,24 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    application::api::utils::{error::ApiError, openapi_responses::get_openapi_responses},
+    application::api::{
+        guards::authorization::DoctorSession,
+        utils::{
+            api_config::ApiConfig, error::ApiError, openapi_responses::get_openapi_responses,
+            pagination_messages::pagination_error_message, pesel_masking::mask_pesel_numbers,
+        },
+    },
     domain::doctors::{
         entities::Doctor,
         repository::{
-            CreateDoctorRepositoryError, GetDoctorByIdRepositoryError, GetDoctorsRepositoryError,
+            CreateDoctorRepositoryError, GetDoctorByIdRepositoryError,
+            GetDoctorByPwzNumberRepositoryError, GetDoctorsRepositoryError,
+            UpdateDoctorPwzNumberRepositoryError,
+        },
+        service::{
+            CreateDoctorError, GetDoctorByIdError, GetDoctorByPwzNumberError,
+            GetDoctorsWithPaginationError, UpdateDoctorPwzError,
         },
-        service::{CreateDoctorError, GetDoctorByIdError, GetDoctorsWithPaginationError},
     },
     Ctx,
 };
@@ -35,8 +46,12 @@ fn example_pesel_number() -> &'static str {
 fn example_pwz_number() -> &'static str {
     "5425740"
 }
+fn example_specialization() -> Option<&'static str> {
+    Some("cardiology")
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreateDoctorDto {
     #[schemars(example = "example_name")]
     name: String,
@@ -44,6 +59,8 @@ pub struct CreateDoctorDto {
     pesel_number: String,
     #[schemars(example = "example_pwz_number")]
     pwz_number: String,
+    #[schemars(example = "example_specialization")]
+    specialization: Option<String>,
 }
 
 impl<'r> Responder<'r, 'static> for CreateDoctorError {
@@ -56,6 +73,7 @@ impl<'r> Responder<'r, 'static> for CreateDoctorError {
                     CreateDoctorRepositoryError::DuplicatedPeselNumber => Status::Conflict,
                     CreateDoctorRepositoryError::DuplicatedPwzNumber => Status::Conflict,
                     CreateDoctorRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    CreateDoctorRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -66,16 +84,17 @@ impl<'r> Responder<'r, 'static> for CreateDoctorError {
 }
 
 impl OpenApiResponderInner for CreateDoctorError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
             (
                 "422",
-                "Returned when the name, the pesel_number or the pwz_number are incorrect",
+                "Returned when the name, the pesel_number, the pwz_number or the specialization are incorrect",
             ),
             (
                 "409",
                 "Returned when the doctor with given pwz_number or pesel_number exist in the database",
             ),
+            ("415", "Returned when the Content-Type is not application/json"),
         ])
     }
 }
@@ -88,7 +107,12 @@ pub async fn create_doctor(
 ) -> Result<Created<Json<Doctor>>, CreateDoctorError> {
     let created_doctor = ctx
         .doctors_service
-        .create_doctor(dto.0.name, dto.0.pesel_number, dto.0.pwz_number)
+        .create_doctor(
+            dto.0.name,
+            dto.0.pesel_number,
+            dto.0.pwz_number,
+            dto.0.specialization,
+        )
         .await?;
 
     let location = format!("/doctors/{}", created_doctor.id);
@@ -103,6 +127,7 @@ impl<'r> Responder<'r, 'static> for GetDoctorByIdError {
                 let status = match err {
                     GetDoctorByIdRepositoryError::NotFound(_) => Status::NotFound,
                     GetDoctorByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDoctorByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -113,14 +138,21 @@ impl<'r> Responder<'r, 'static> for GetDoctorByIdError {
 }
 
 impl OpenApiResponderInner for GetDoctorByIdError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "404",
-                "Returned when the the doctor with given id doesn't exist",
-            ),
-            ("422", "Returned when the the doctor_id is not a valid UUID"),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the doctor with given id doesn't exist",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                ("422", "Returned when the the doctor_id is not a valid UUID"),
+            ],
+        )
     }
 }
 
@@ -135,16 +167,156 @@ pub async fn get_doctor_by_id(
     Ok(Json(doctor))
 }
 
-impl<'r> Responder<'r, 'static> for GetDoctorsWithPaginationError {
+impl<'r> Responder<'r, 'static> for GetDoctorByPwzNumberError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
             Self::RepositoryError(err) => {
                 let message = err.to_string();
+                let status = match err {
+                    GetDoctorByPwzNumberRepositoryError::NotFound(_) => Status::NotFound,
+                    GetDoctorByPwzNumberRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetDoctorByPwzNumberRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetDoctorByPwzNumberError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the doctor with given PWZ number doesn't exist",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                ("422", "Returned when the the PWZ number is malformed"),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Doctors")]
+#[get("/doctors/pwz/<pwz_number>", format = "application/json")]
+pub async fn get_doctor_by_pwz_number(
+    ctx: &Ctx,
+    pwz_number: &str,
+) -> Result<Json<Doctor>, GetDoctorByPwzNumberError> {
+    let doctor = ctx
+        .doctors_service
+        .get_doctor_by_pwz_number(pwz_number)
+        .await?;
+
+    Ok(Json(doctor))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateDoctorPwzDto {
+    #[schemars(example = "example_pwz_number")]
+    pwz_number: String,
+}
+
+impl<'r> Responder<'r, 'static> for UpdateDoctorPwzError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::NotSameDoctor => (
+                "Only the doctor themselves can change their PWZ number".to_string(),
+                Status::Forbidden,
+            ),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    UpdateDoctorPwzNumberRepositoryError::NotFound(_) => Status::NotFound,
+                    UpdateDoctorPwzNumberRepositoryError::DuplicatedPwzNumber => Status::Conflict,
+                    UpdateDoctorPwzNumberRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    UpdateDoctorPwzNumberRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for UpdateDoctorPwzError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "403",
+                    "Returned when the session doesn't belong to the doctor with given id",
+                ),
+                (
+                    "404",
+                    "Returned when the the doctor with given id doesn't exist",
+                ),
+                (
+                    "409",
+                    "Returned when another doctor with given pwz_number exists in the database",
+                ),
+                (
+                    "422",
+                    "Returned when the doctor_id is not a valid UUID or the pwz_number is malformed",
+                ),
+                ("415", "Returned when the Content-Type is not application/json"),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Doctors")]
+#[patch(
+    "/doctors/<doctor_id>/pwz",
+    format = "application/json",
+    data = "<dto>"
+)]
+pub async fn update_doctor_pwz(
+    ctx: &Ctx,
+    session: DoctorSession,
+    doctor_id: Uuid,
+    dto: Json<UpdateDoctorPwzDto>,
+) -> Result<Json<Doctor>, UpdateDoctorPwzError> {
+    let doctor = ctx
+        .doctors_service
+        .update_doctor_pwz(doctor_id, &dto.0.pwz_number, session.0.doctor_id.unwrap())
+        .await?;
+
+    Ok(Json(doctor))
+}
+
+impl<'r> Responder<'r, 'static> for GetDoctorsWithPaginationError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetDoctorsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
                 let status = match err {
                     GetDoctorsRepositoryError::InvalidPaginationParams(_) => {
                         Status::UnprocessableEntity
                     }
                     GetDoctorsRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDoctorsRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -155,41 +327,64 @@ impl<'r> Responder<'r, 'static> for GetDoctorsWithPaginationError {
 }
 
 impl OpenApiResponderInner for GetDoctorsWithPaginationError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![(
-            "422",
-            "Returned when the the page < 0 or page_size < 1",
-        )])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the page < 0, page_size < 1, page_size > 100 or the specialization is incorrect",
+                ),
+            ],
+        )
     }
 }
 
 #[openapi(tag = "Doctors")]
-#[get("/doctors?<page>&<page_size>", format = "application/json")]
+#[get(
+    "/doctors?<page>&<page_size>&<specialization>",
+    format = "application/json"
+)]
 pub async fn get_doctors_with_pagination(
     ctx: &Ctx,
+    config: &State<ApiConfig>,
     page: Option<i64>,
     page_size: Option<i64>,
+    specialization: Option<String>,
 ) -> Result<Json<Vec<Doctor>>, GetDoctorsWithPaginationError> {
     let doctors = ctx
         .doctors_service
-        .get_doctors_with_pagination(page, page_size)
+        .get_doctors_with_pagination(page, page_size, specialization)
         .await?;
 
-    Ok(Json(doctors))
+    Ok(Json(mask_pesel_numbers(doctors, config.mask_pesel_numbers)))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{sync::Arc, time::Duration};
+
     use rocket::{
-        http::{ContentType, Status},
+        http::{ContentType, Header, Status},
         local::asynchronous::Client,
         routes,
         serde::json,
     };
+    use sqlx::postgres::PgPoolOptions;
+    use uuid::Uuid;
 
     use crate::{
-        application::api::utils::fake_api_context::create_fake_api_context,
-        domain::doctors::entities::Doctor,
+        application::api::utils::{
+            api_config::ApiConfig, error::RETRY_AFTER_SECONDS,
+            fake_api_context::create_fake_api_context,
+        },
+        domain::doctors::{entities::Doctor, service::DoctorsService},
+        infrastructure::postgres_repository_impl::doctors::PostgresDoctorsRepository,
+        Context,
     };
 
     async fn create_api_client() -> Client {
@@ -198,14 +393,81 @@ mod tests {
         let routes = routes![
             super::create_doctor,
             super::get_doctor_by_id,
+            super::get_doctor_by_pwz_number,
             super::get_doctors_with_pagination
         ];
 
-        let rocket = rocket::build().manage(context).mount("/", routes);
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(ApiConfig::default())
+            .mount("/", routes);
 
         Client::tracked(rocket).await.unwrap()
     }
 
+    async fn create_api_client_with_doctor_session() -> (Client, Doctor, Doctor, String) {
+        let context = create_fake_api_context();
+        let doctor = context
+            .doctors_service
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
+            .await
+            .unwrap();
+        let other_doctor = context
+            .doctors_service
+            .create_doctor(
+                "Jane Doex".into(),
+                "99031301347".into(),
+                "8463856".into(),
+                None,
+            )
+            .await
+            .unwrap();
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                Some(doctor.id),
+                None,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+
+        let routes = routes![super::get_doctor_by_pwz_number, super::update_doctor_pwz];
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(ApiConfig::default())
+            .mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        (client, doctor, other_doctor, token)
+    }
+
+    async fn update_doctor_pwz(
+        client: &Client,
+        token: &str,
+        doctor_id: Uuid,
+        pwz_number: &str,
+    ) -> (Status, String) {
+        let response = client
+            .patch(format!("/doctors/{}/pwz", doctor_id))
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(r#"{{"pwz_number": "{}"}}"#, pwz_number))
+            .dispatch()
+            .await;
+
+        (response.status(), response.into_string().await.unwrap())
+    }
+
     #[tokio::test]
     async fn creates_doctor_and_reads_by_id() {
         let client = create_api_client().await;
@@ -255,6 +517,19 @@ mod tests {
         assert_eq!(response.status(), Status::UnprocessableEntity);
     }
 
+    #[tokio::test]
+    async fn create_doctor_returns_unprocessable_entity_if_body_has_unknown_keys() {
+        let client = create_api_client().await;
+
+        let request_with_unknown_key = client
+            .post("/doctors")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250", "pwz_number":"5425740", "nickname":"Johnny"}"#)
+            .header(ContentType::JSON);
+        let response = request_with_unknown_key.dispatch().await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
     #[tokio::test]
     async fn create_doctor_returns_unprocessable_entity_if_body_has_incorrect_value_incorrect() {
         let client = create_api_client().await;
@@ -317,6 +592,65 @@ mod tests {
         assert_eq!(response.status(), Status::NotFound);
     }
 
+    #[tokio::test]
+    async fn gets_doctor_by_pwz_number() {
+        let client = create_api_client().await;
+
+        client
+            .post("/doctors")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250", "pwz_number":"5425740"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        let response = client
+            .get("/doctors/pwz/5425740")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let doctor: Doctor = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(doctor.name, "John Doex");
+        assert_eq!(doctor.pwz_number, "5425740");
+    }
+
+    #[tokio::test]
+    async fn get_doctor_by_pwz_number_returns_unprocessable_entity_if_pwz_number_is_malformed() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/doctors/pwz/54257")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .get("/doctors/pwz/5425741")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn get_doctor_by_pwz_number_returns_not_found_if_such_doctor_does_not_exist() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/doctors/pwz/5425740")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
     #[tokio::test]
     async fn gets_doctors_with_pagination() {
         let client = create_api_client().await;
@@ -383,4 +717,166 @@ mod tests {
             Status::UnprocessableEntity
         );
     }
+
+    #[tokio::test]
+    async fn get_doctors_with_pagination_returns_reason_of_invalid_pagination_params() {
+        let client = create_api_client().await;
+
+        for (query, expected_message) in [
+            ("page=-1", "Page must not be negative"),
+            ("page_size=0", "Page size must be at least 1"),
+            ("page_size=101", "Page size must not exceed 100"),
+        ] {
+            let response = client
+                .get(format!("/doctors?{}", query))
+                .header(ContentType::JSON)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::UnprocessableEntity);
+
+            let error: json::Value =
+                json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+            assert_eq!(error["message"], expected_message);
+        }
+    }
+
+    #[tokio::test]
+    async fn gets_doctors_filtered_by_specialization() {
+        let client = create_api_client().await;
+        client
+            .post("/doctors")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021817257", "pwz_number":"5425740", "specialization":"Family  Medicine"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        client
+            .post("/doctors")
+            .body(r#"{"name":"John Doey", "pesel_number":"99031301347", "pwz_number":"8463856", "specialization":"cardiology"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        let response = client
+            .get("/doctors?specialization=family%20medicine")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let doctors: Vec<Doctor> = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(doctors.len(), 1);
+        assert_eq!(doctors[0].name, "John Doex");
+        assert_eq!(doctors[0].specialization, Some("family medicine".into()));
+
+        assert_eq!(
+            client
+                .get("/doctors?specialization=x")
+                .header(ContentType::JSON)
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_doctor_pwz_number() {
+        let (client, doctor, _, token) = create_api_client_with_doctor_session().await;
+
+        let (status, body) = update_doctor_pwz(&client, &token, doctor.id, "3123456").await;
+
+        assert_eq!(status, Status::Ok);
+
+        let updated_doctor: Doctor = json::from_str(&body).unwrap();
+
+        assert_eq!(updated_doctor.id, doctor.id);
+        assert_eq!(updated_doctor.pwz_number, "3123456");
+
+        let response = client
+            .get("/doctors/pwz/3123456")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[tokio::test]
+    async fn update_doctor_pwz_returns_conflict_if_pwz_number_is_taken() {
+        let (client, doctor, other_doctor, token) = create_api_client_with_doctor_session().await;
+
+        let (status, _) =
+            update_doctor_pwz(&client, &token, doctor.id, &other_doctor.pwz_number).await;
+
+        assert_eq!(status, Status::Conflict);
+    }
+
+    #[tokio::test]
+    async fn update_doctor_pwz_returns_unprocessable_entity_if_pwz_number_is_malformed() {
+        let (client, doctor, _, token) = create_api_client_with_doctor_session().await;
+
+        let (status, _) = update_doctor_pwz(&client, &token, doctor.id, "54257").await;
+
+        assert_eq!(status, Status::UnprocessableEntity);
+
+        let (status, _) = update_doctor_pwz(&client, &token, doctor.id, "5425741").await;
+
+        assert_eq!(status, Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn update_doctor_pwz_requires_session_of_the_same_doctor() {
+        let (client, _, other_doctor, token) = create_api_client_with_doctor_session().await;
+
+        let (status, _) = update_doctor_pwz(&client, &token, other_doctor.id, "3123456").await;
+
+        assert_eq!(status, Status::Forbidden);
+
+        let response = client
+            .patch(format!("/doctors/{}/pwz", other_doctor.id))
+            .header(ContentType::JSON)
+            .body(r#"{"pwz_number": "3123456"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[sqlx::test]
+    async fn returns_service_unavailable_if_database_pool_is_exhausted(pool: sqlx::PgPool) {
+        let tiny_pool = PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let _held_connection = tiny_pool.acquire().await.unwrap();
+
+        let doctors_repository = Box::new(PostgresDoctorsRepository::new(tiny_pool));
+        let context = Context {
+            doctors_service: Arc::new(DoctorsService::new(doctors_repository)),
+            ..create_fake_api_context()
+        };
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(ApiConfig::default())
+            .mount("/", routes![super::get_doctors_with_pagination]);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let response = client
+            .get("/doctors")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        assert_eq!(
+            response.headers().get_one("Retry-After"),
+            Some(RETRY_AFTER_SECONDS.to_string().as_str())
+        );
+    }
 }
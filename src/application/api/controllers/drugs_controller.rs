@@ -1,11 +1,13 @@
+use std::collections::HashMap;
+
 use okapi::openapi3::Responses;
 use rocket::{
-    get,
+    delete, get,
     http::Status,
     post,
     response::{status::Created, Responder},
     serde::json::Json,
-    Request,
+    Request, State,
 };
 use rocket_okapi::{gen::OpenApiGenerator, openapi, response::OpenApiResponderInner, OpenApiError};
 use schemars::JsonSchema;
@@ -13,11 +15,31 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    application::api::utils::{error::ApiError, openapi_responses::get_openapi_responses},
-    domain::drugs::{
-        entities::{Drug, DrugContentType},
-        repository::{GetDrugByIdRepositoryError, GetDrugsRepositoryError},
-        service::{CreateDrugError, GetDrugByIdError, GetDrugsWithPaginationError},
+    application::{
+        api::{
+            guards::authorization::PharmacistSession,
+            utils::{
+                api_config::ApiConfig, error::ApiError, openapi_responses::get_openapi_responses,
+                pagination_messages::pagination_error_message,
+            },
+        },
+        sessions::entities::Session,
+    },
+    domain::{
+        drugs::{
+            entities::{Drug, DrugCategory, DrugContentType},
+            repository::{
+                AdjustDrugStockRepositoryError, CreateDrugRepositoryError,
+                DeactivateDrugRepositoryError, GetDrugByBarcodeRepositoryError,
+                GetDrugByIdRepositoryError, GetDrugsRepositoryError,
+            },
+            service::{
+                AdjustDrugStockError, CountDrugsByContentTypeError, CreateDrugError,
+                DeactivateDrugError, GetDrugByBarcodeError, GetDrugByIdError,
+                GetDrugsWithPaginationError,
+            },
+        },
+        prescriptions::entities::PrescriptionType,
     },
     Ctx,
 };
@@ -40,8 +62,36 @@ fn example_ml_per_pill() -> Option<i32> {
 fn example_volume_ml() -> Option<i32> {
     None
 }
+fn example_doses_count() -> Option<i32> {
+    None
+}
+fn example_mcg_per_dose() -> Option<i32> {
+    None
+}
+fn example_ml_per_dose() -> Option<i32> {
+    None
+}
+fn example_unit_price_cents() -> Option<i32> {
+    Some(1299)
+}
+fn example_manufacturer() -> Option<&'static str> {
+    Some("US Pharmacia")
+}
+fn example_barcode() -> Option<&'static str> {
+    Some("5909990733828")
+}
+fn example_category() -> Option<DrugCategory> {
+    None
+}
+fn example_allowed_prescription_types() -> Option<Vec<PrescriptionType>> {
+    None
+}
+fn example_stock_delta() -> i32 {
+    -2
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreateDrugDto {
     #[schemars(example = "example_drug_name")]
     name: String,
@@ -55,26 +105,58 @@ pub struct CreateDrugDto {
     ml_per_pill: Option<i32>,
     #[schemars(example = "example_volume_ml")]
     volume_ml: Option<i32>,
+    #[schemars(example = "example_doses_count")]
+    doses_count: Option<i32>,
+    #[schemars(example = "example_mcg_per_dose")]
+    mcg_per_dose: Option<i32>,
+    #[schemars(example = "example_ml_per_dose")]
+    ml_per_dose: Option<i32>,
+    #[schemars(example = "example_unit_price_cents")]
+    unit_price_cents: Option<i32>,
+    #[schemars(example = "example_manufacturer")]
+    manufacturer: Option<String>,
+    #[schemars(example = "example_barcode")]
+    barcode: Option<String>,
+    #[schemars(example = "example_category")]
+    category: Option<DrugCategory>,
+    // Null or empty allows the drug on any prescription type
+    #[schemars(example = "example_allowed_prescription_types")]
+    allowed_prescription_types: Option<Vec<PrescriptionType>>,
+}
+
+// Shared by the single and the batch drug creation, which prefixes the message with the drug index
+fn status_of(err: &CreateDrugError) -> (String, Status) {
+    match err {
+        CreateDrugError::DomainError(message) => (message.clone(), Status::UnprocessableEntity),
+        CreateDrugError::RepositoryError(err) => {
+            let status = match err {
+                CreateDrugRepositoryError::DuplicatedBarcode
+                | CreateDrugRepositoryError::DuplicatedDrug => Status::Conflict,
+                CreateDrugRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                CreateDrugRepositoryError::Unavailable => Status::ServiceUnavailable,
+            };
+            (err.to_string(), status)
+        }
+    }
 }
 
 impl<'r> Responder<'r, 'static> for CreateDrugError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
-        let (message, status) = match self {
-            Self::DomainError(message) => (message, Status::UnprocessableEntity),
-            Self::RepositoryError(err) => (err.to_string(), Status::InternalServerError),
-        };
+        let (message, status) = status_of(&self);
 
         ApiError::build_rocket_response(req, message, status)
     }
 }
 
 impl OpenApiResponderInner for CreateDrugError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
             (
                 "422",
-                "Returned when the quantity parameters dont match the content type (for instance when missing volume_ml from BOTTLE_OF_LIQUID content_type)",
+                "Returned when the quantity parameters dont match the content type (for instance when missing volume_ml from BOTTLE_OF_LIQUID content_type, doses_count from INHALER or providing volume_ml for SOLID_PILLS) or the barcode is malformed",
             ),
+            ("409", "Returned when a drug with the same barcode already exists"),
+            ("415", "Returned when the Content-Type is not application/json"),
         ])
     }
 }
@@ -83,6 +165,7 @@ impl OpenApiResponderInner for CreateDrugError {
 #[post("/drugs", format = "json", data = "<dto>")]
 pub async fn create_drug(
     ctx: &Ctx,
+    session: Option<Session>,
     dto: Json<CreateDrugDto>,
 ) -> Result<Created<Json<Drug>>, CreateDrugError> {
     let created_drug = ctx
@@ -94,6 +177,15 @@ pub async fn create_drug(
             dto.0.mg_per_pill,
             dto.0.ml_per_pill,
             dto.0.volume_ml,
+            dto.0.doses_count,
+            dto.0.mcg_per_dose,
+            dto.0.ml_per_dose,
+            dto.0.unit_price_cents,
+            dto.0.manufacturer,
+            dto.0.barcode,
+            dto.0.category,
+            dto.0.allowed_prescription_types.unwrap_or_default(),
+            session.map(|session| session.user_id),
         )
         .await?;
 
@@ -101,6 +193,92 @@ pub async fn create_drug(
     Ok(Created::new(location).body(Json(created_drug)))
 }
 
+#[derive(Debug)]
+pub enum CreateDrugsBatchError {
+    TooManyDrugs(usize),
+    DrugError(usize, CreateDrugError),
+}
+
+impl<'r> Responder<'r, 'static> for CreateDrugsBatchError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::TooManyDrugs(max_batch_size) => (
+                format!(
+                    "Can't create more than {} drugs in one batch",
+                    max_batch_size
+                ),
+                Status::PayloadTooLarge,
+            ),
+            Self::DrugError(index, err) => {
+                let (message, status) = status_of(&err);
+                (format!("Drug at index {}: {}", index, message), status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CreateDrugsBatchError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "413",
+                "Returned when the batch has more drugs than allowed (MAX_DRUGS_BATCH_SIZE, 100 by default) or the body exceeds 256 KiB",
+            ),
+            (
+                "422",
+                "Returned when any of the drugs is invalid, drugs preceding it in the batch are already created",
+            ),
+            ("415", "Returned when the Content-Type is not application/json"),
+        ])
+    }
+}
+
+#[openapi(tag = "Drugs")]
+#[post("/drugs/batch", format = "json", data = "<dtos>")]
+pub async fn create_drugs_batch(
+    ctx: &Ctx,
+    config: &State<ApiConfig>,
+    session: Option<Session>,
+    dtos: Json<Vec<CreateDrugDto>>,
+) -> Result<Created<Json<Vec<Drug>>>, CreateDrugsBatchError> {
+    if dtos.len() > config.max_drugs_batch_size {
+        return Err(CreateDrugsBatchError::TooManyDrugs(
+            config.max_drugs_batch_size,
+        ));
+    }
+
+    let created_by = session.map(|session| session.user_id);
+    let mut created_drugs = Vec::with_capacity(dtos.len());
+    for (index, dto) in dtos.0.into_iter().enumerate() {
+        let created_drug = ctx
+            .drugs_service
+            .create_drug(
+                dto.name,
+                dto.content_type,
+                dto.pills_count,
+                dto.mg_per_pill,
+                dto.ml_per_pill,
+                dto.volume_ml,
+                dto.doses_count,
+                dto.mcg_per_dose,
+                dto.ml_per_dose,
+                dto.unit_price_cents,
+                dto.manufacturer,
+                dto.barcode,
+                dto.category,
+                dto.allowed_prescription_types.unwrap_or_default(),
+                created_by,
+            )
+            .await
+            .map_err(|err| CreateDrugsBatchError::DrugError(index, err))?;
+        created_drugs.push(created_drug);
+    }
+
+    Ok(Created::new("/drugs").body(Json(created_drugs)))
+}
+
 impl<'r> Responder<'r, 'static> for GetDrugByIdError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
@@ -109,6 +287,7 @@ impl<'r> Responder<'r, 'static> for GetDrugByIdError {
                 let status = match err {
                     GetDrugByIdRepositoryError::NotFound(_) => Status::NotFound,
                     GetDrugByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDrugByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -119,14 +298,17 @@ impl<'r> Responder<'r, 'static> for GetDrugByIdError {
 }
 
 impl OpenApiResponderInner for GetDrugByIdError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "404",
-                "Returned when the drug with the given id was not found",
-            ),
-            ("422", "Returned when the drug_id is not a valid UUID"),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the drug with the given id was not found",
+                ),
+                ("422", "Returned when the drug_id is not a valid UUID"),
+            ],
+        )
     }
 }
 
@@ -138,16 +320,69 @@ pub async fn get_drug_by_id(ctx: &Ctx, drug_id: Uuid) -> Result<Json<Drug>, GetD
     Ok(Json(drug))
 }
 
-impl<'r> Responder<'r, 'static> for GetDrugsWithPaginationError {
+impl<'r> Responder<'r, 'static> for GetDrugByBarcodeError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
             Self::RepositoryError(err) => {
                 let message = err.to_string();
+                let status = match err {
+                    GetDrugByBarcodeRepositoryError::NotFound(_) => Status::NotFound,
+                    GetDrugByBarcodeRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetDrugByBarcodeRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetDrugByBarcodeError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the drug with the given barcode was not found",
+                ),
+                ("422", "Returned when the barcode is malformed"),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Drugs")]
+#[get("/drugs/barcode/<barcode>")]
+pub async fn get_drug_by_barcode(
+    ctx: &Ctx,
+    barcode: &str,
+) -> Result<Json<Drug>, GetDrugByBarcodeError> {
+    let drug = ctx.drugs_service.get_drug_by_barcode(barcode).await?;
+
+    Ok(Json(drug))
+}
+
+impl<'r> Responder<'r, 'static> for GetDrugsWithPaginationError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetDrugsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
                 let status = match err {
                     GetDrugsRepositoryError::InvalidPaginationParams(_) => {
                         Status::UnprocessableEntity
                     }
                     GetDrugsRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDrugsRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -158,52 +393,287 @@ impl<'r> Responder<'r, 'static> for GetDrugsWithPaginationError {
 }
 
 impl OpenApiResponderInner for GetDrugsWithPaginationError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![(
-            "422",
-            "Returned when the the page < 0 or page_size < 1",
-        )])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the page < 0, page_size < 1 or page_size > 100",
+                ),
+            ],
+        )
     }
 }
 
+// Deactivated drugs are only listed when include_inactive=true
 #[openapi(tag = "Drugs")]
-#[get("/drugs?<page>&<page_size>", format = "application/json")]
+#[get(
+    "/drugs?<page>&<page_size>&<include_inactive>",
+    format = "application/json"
+)]
 pub async fn get_drugs_with_pagination(
     ctx: &Ctx,
     page: Option<i64>,
     page_size: Option<i64>,
+    include_inactive: Option<bool>,
 ) -> Result<Json<Vec<Drug>>, GetDrugsWithPaginationError> {
     let drugs = ctx
         .drugs_service
-        .get_drugs_with_pagination(page, page_size)
+        .get_drugs_with_pagination(page, page_size, include_inactive.unwrap_or(false))
         .await?;
 
     Ok(Json(drugs))
 }
+
+impl<'r> Responder<'r, 'static> for CountDrugsByContentTypeError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetDrugsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetDrugsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetDrugsRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDrugsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CountDrugsByContentTypeError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![(
+                "503",
+                "Returned when the database is temporarily unavailable",
+            )],
+        )
+    }
+}
+
+// Counts active drugs of every content type, including the ones without any drugs
+#[openapi(tag = "Drugs")]
+#[get("/drugs/content-types")]
+pub async fn get_drug_content_types(
+    ctx: &Ctx,
+) -> Result<Json<HashMap<DrugContentType, i64>>, CountDrugsByContentTypeError> {
+    let counts = ctx.drugs_service.count_drugs_by_content_type().await?;
+
+    Ok(Json(counts))
+}
+
+impl<'r> Responder<'r, 'static> for DeactivateDrugError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    DeactivateDrugRepositoryError::NotFound(_) => Status::NotFound,
+                    DeactivateDrugRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    DeactivateDrugRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for DeactivateDrugError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the drug with given id doesn't exist",
+                ),
+                ("422", "Returned when the drug_id is not a valid UUID"),
+            ],
+        )
+    }
+}
+
+// Drugs are only soft-deleted, so the prescriptions referencing them stay intact
+#[openapi(tag = "Drugs")]
+#[delete("/drugs/<drug_id>")]
+pub async fn deactivate_drug(ctx: &Ctx, drug_id: Uuid) -> Result<Json<Drug>, DeactivateDrugError> {
+    let drug = ctx.drugs_service.deactivate_drug(drug_id).await?;
+
+    Ok(Json(drug))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AdjustDrugStockDto {
+    // Positive for deliveries, negative for dispensed or disposed packages
+    #[schemars(example = "example_stock_delta")]
+    delta: i32,
+}
+
+impl<'r> Responder<'r, 'static> for AdjustDrugStockError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    AdjustDrugStockRepositoryError::NotFound(_) => Status::NotFound,
+                    AdjustDrugStockRepositoryError::InsufficientStock(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    AdjustDrugStockRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    AdjustDrugStockRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for AdjustDrugStockError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the drug with given id doesn't exist",
+                ),
+                (
+                    "422",
+                    "Returned when the stock would go below zero or the drug_id is not a valid UUID",
+                ),
+                ("403", "Returned when the session doesn't belong to a pharmacist"),
+                ("415", "Returned when the Content-Type is not application/json"),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Drugs")]
+#[post("/drugs/<drug_id>/stock", format = "json", data = "<dto>")]
+pub async fn adjust_drug_stock(
+    ctx: &Ctx,
+    _session: PharmacistSession,
+    drug_id: Uuid,
+    dto: Json<AdjustDrugStockDto>,
+) -> Result<Json<Drug>, AdjustDrugStockError> {
+    let drug = ctx
+        .drugs_service
+        .adjust_drug_stock(drug_id, dto.0.delta)
+        .await?;
+
+    Ok(Json(drug))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use rocket::{
-        http::{ContentType, Status},
+        http::{ContentType, Header, Status},
         local::asynchronous::Client,
         routes,
-        serde::json,
+        serde::json::{self, Value},
     };
+    use uuid::Uuid;
 
     use crate::{
-        application::api::utils::fake_api_context::create_fake_api_context,
+        application::api::utils::{
+            api_config::ApiConfig, fake_api_context::create_fake_api_context,
+        },
         domain::drugs::entities::{Drug, DrugContentType},
+        Context,
     };
 
     async fn create_api_client() -> Client {
+        create_api_client_with_context(create_fake_api_context()).await
+    }
+
+    async fn create_api_client_with_session() -> (Client, Uuid, String) {
         let context = create_fake_api_context();
+        let user_id = Uuid::new_v4();
+        let session = context
+            .sessions_service
+            .create_session(
+                user_id,
+                None,
+                None,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Admin panel".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
 
+        (
+            create_api_client_with_context(context).await,
+            user_id,
+            token,
+        )
+    }
+
+    async fn create_api_client_with_pharmacist_session() -> (Client, String) {
+        let context = create_fake_api_context();
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                None,
+                Some(Uuid::new_v4()),
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Pharmacy terminal".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+
+        (create_api_client_with_context(context).await, token)
+    }
+
+    async fn create_api_client_with_context(context: Context) -> Client {
         let routes = routes![
             super::create_drug,
+            super::create_drugs_batch,
             super::get_drug_by_id,
+            super::get_drug_by_barcode,
             super::get_drugs_with_pagination,
+            super::get_drug_content_types,
+            super::deactivate_drug,
+            super::adjust_drug_stock,
         ];
 
-        let rocket = rocket::build().manage(context).mount("/", routes);
+        let config = ApiConfig {
+            max_drugs_batch_size: 2,
+            ..ApiConfig::default()
+        };
+
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(config)
+            .mount("/", routes);
 
         Client::tracked(rocket).await.unwrap()
     }
@@ -215,7 +685,7 @@ mod tests {
         let created_drug_response = client
             .post("/drugs")
             .header(ContentType::JSON)
-            .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS"}"#)
+            .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS", "unit_price_cents": 1299}"#)
             .dispatch()
             .await;
 
@@ -228,6 +698,7 @@ mod tests {
         assert_eq!(created_drug.pills_count, Some(30));
         assert_eq!(created_drug.mg_per_pill, Some(300));
         assert_eq!(created_drug.content_type, DrugContentType::SolidPills);
+        assert_eq!(created_drug.unit_price_cents, Some(1299));
 
         let response = client
             .get(format!("/drugs/{}", created_drug.id))
@@ -262,6 +733,183 @@ mod tests {
             .await.status(), Status::UnprocessableEntity);
     }
 
+    #[tokio::test]
+    async fn create_drug_returns_unprocessable_entity_if_body_has_unknown_keys() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS", "color": "white"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn creates_inhaler_and_injection_drugs() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Ventolin", "doses_count": 200, "mcg_per_dose": 100, "content_type": "INHALER"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        let created_drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(created_drug.content_type, DrugContentType::Inhaler);
+        assert_eq!(created_drug.doses_count, Some(200));
+        assert_eq!(created_drug.mcg_per_dose, Some(100));
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Clexane", "doses_count": 10, "ml_per_dose": 1, "content_type": "INJECTION"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        assert_eq!(
+            client
+                .post("/drugs")
+                .header(ContentType::JSON)
+                .body(r#"{"name": "Ventolin", "doses_count": 200, "content_type": "INHALER"}"#)
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+
+        assert_eq!(client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Clexane", "doses_count": 10, "ml_per_dose": 1, "volume_ml": 10, "content_type": "INJECTION"}"#)
+            .dispatch()
+            .await.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn creates_drug_with_barcode_and_gets_it_by_barcode() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS", "manufacturer": "US Pharmacia", "barcode": "5909990733828"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        let created_drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(created_drug.manufacturer, Some("US Pharmacia".into()));
+        assert_eq!(created_drug.barcode, Some("5909990733828".into()));
+
+        let response = client.get("/drugs/barcode/5909990733828").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(drug.id, created_drug.id);
+    }
+
+    #[tokio::test]
+    async fn create_drug_returns_conflict_if_barcode_is_duplicated() {
+        let client = create_api_client().await;
+        let body = r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS", "barcode": "5909990733828"}"#;
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+    }
+
+    #[tokio::test]
+    async fn create_drug_returns_conflict_if_drug_with_same_name_and_strength_exists() {
+        let client = create_api_client().await;
+        let body = r#"{"name": "Apap", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS"}"#;
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Apap", "pills_count": 30, "mg_per_pill": 500, "content_type": "SOLID_PILLS"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+    }
+
+    #[tokio::test]
+    async fn get_drug_by_barcode_returns_error_if_barcode_is_invalid_or_unknown() {
+        let client = create_api_client().await;
+
+        assert_eq!(
+            client
+                .get("/drugs/barcode/5909990733828")
+                .dispatch()
+                .await
+                .status(),
+            Status::NotFound
+        );
+        assert_eq!(
+            client
+                .get("/drugs/barcode/5909990733827")
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+        assert_eq!(
+            client
+                .post("/drugs")
+                .header(ContentType::JSON)
+                .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS", "barcode": "123"}"#)
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+    }
+
     #[tokio::test]
     async fn get_drug_by_id_returns_unprocessable_entity_if_id_param_is_invalid() {
         let client = create_api_client().await;
@@ -350,4 +998,272 @@ mod tests {
             Status::UnprocessableEntity
         );
     }
+
+    #[tokio::test]
+    async fn hides_deactivated_drugs_unless_include_inactive_is_true() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs/batch")
+            .header(ContentType::JSON)
+            .body(r#"[{"name":"Drug 1", "volume_ml":100, "content_type":"BOTTLE_OF_LIQUID"}, {"name":"Drug 2", "volume_ml":200, "content_type":"BOTTLE_OF_LIQUID"}]"#)
+            .dispatch()
+            .await;
+        let created_drugs: Vec<Drug> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        let response = client
+            .delete(format!("/drugs/{}", created_drugs[0].id))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let deactivated_drug: Drug =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert!(deactivated_drug.deactivated_at.is_some());
+
+        let response = client
+            .get("/drugs")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let drugs: Vec<Drug> = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(drugs.len(), 1);
+        assert_eq!(drugs[0].id, created_drugs[1].id);
+
+        let response = client
+            .get("/drugs?include_inactive=true")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let drugs: Vec<Drug> = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(drugs.len(), 2);
+        assert_eq!(drugs[0].id, created_drugs[0].id);
+
+        let response = client
+            .delete(format!("/drugs/{}", Uuid::new_v4()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn creates_drugs_in_batch() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs/batch")
+            .header(ContentType::JSON)
+            .body(r#"[{"name":"Drug 1", "pills_count":30, "mg_per_pill":300, "content_type":"SOLID_PILLS"}, {"name":"Drug 2", "volume_ml":1000, "content_type":"BOTTLE_OF_LIQUID"}]"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        let created_drugs: Vec<Drug> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(created_drugs.len(), 2);
+        assert_eq!(created_drugs[0].name, "Drug 1");
+        assert_eq!(created_drugs[1].name, "Drug 2");
+    }
+
+    #[tokio::test]
+    async fn create_drugs_batch_returns_payload_too_large_if_batch_exceeds_max_size() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs/batch")
+            .header(ContentType::JSON)
+            .body(r#"[{"name":"Drug 1", "volume_ml":100, "content_type":"BOTTLE_OF_LIQUID"}, {"name":"Drug 2", "volume_ml":200, "content_type":"BOTTLE_OF_LIQUID"}, {"name":"Drug 3", "volume_ml":300, "content_type":"BOTTLE_OF_LIQUID"}]"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["status"], 413);
+        assert_eq!(error["path"], "/drugs/batch");
+        assert_eq!(
+            error["message"],
+            "Can't create more than 2 drugs in one batch"
+        );
+
+        let drugs_response = client
+            .get("/drugs")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let drugs: Vec<Drug> =
+            json::from_str(&drugs_response.into_string().await.unwrap()).unwrap();
+
+        assert!(drugs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_drugs_batch_returns_unprocessable_entity_with_index_of_invalid_drug() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/drugs/batch")
+            .header(ContentType::JSON)
+            .body(r#"[{"name":"Drug 1", "volume_ml":100, "content_type":"BOTTLE_OF_LIQUID"}, {"name":"Drug 2", "pills_count":30, "content_type":"BOTTLE_OF_LIQUID"}]"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert!(error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Drug at index 1:"));
+    }
+
+    #[tokio::test]
+    async fn records_session_user_as_creator_of_drug() {
+        let (client, user_id, token) = create_api_client_with_session().await;
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+        let created_drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(created_drug.created_by, Some(user_id));
+
+        let response = client
+            .post("/drugs/batch")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(r#"[{"name":"Drug 2", "volume_ml":100, "content_type":"BOTTLE_OF_LIQUID"}]"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+        let created_drugs: Vec<Drug> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(created_drugs[0].created_by, Some(user_id));
+
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Drug 3", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+        let created_drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(created_drug.created_by, None);
+    }
+
+    #[tokio::test]
+    async fn counts_drugs_by_content_type() {
+        let client = create_api_client().await;
+        client
+            .post("/drugs/batch")
+            .header(ContentType::JSON)
+            .body(r#"[{"name":"Drug 1", "volume_ml":100, "content_type":"BOTTLE_OF_LIQUID"}, {"name":"Drug 2", "volume_ml":200, "content_type":"BOTTLE_OF_LIQUID"}]"#)
+            .dispatch()
+            .await;
+        client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name":"Drug 3", "pills_count":20, "mg_per_pill":300, "content_type":"SOLID_PILLS"}"#)
+            .dispatch()
+            .await;
+
+        let response = client.get("/drugs/content-types").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let counts: HashMap<DrugContentType, i64> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(counts.len(), DrugContentType::ALL.len());
+        assert_eq!(counts[&DrugContentType::BottleOfLiquid], 2);
+        assert_eq!(counts[&DrugContentType::SolidPills], 1);
+        assert_eq!(counts[&DrugContentType::LiquidPills], 0);
+        assert_eq!(counts[&DrugContentType::Inhaler], 0);
+        assert_eq!(counts[&DrugContentType::Injection], 0);
+    }
+
+    async fn adjust_stock(client: &Client, token: &str, drug_id: Uuid, delta: i32) -> Status {
+        client
+            .post(format!("/drugs/{}/stock", drug_id))
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(r#"{{"delta": {}}}"#, delta))
+            .dispatch()
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn adjusts_drug_stock_without_going_below_zero() {
+        let (client, token) = create_api_client_with_pharmacist_session().await;
+        let response = client
+            .post("/drugs")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Drug 1", "pills_count": 30, "mg_per_pill": 300, "content_type": "SOLID_PILLS"}"#)
+            .dispatch()
+            .await;
+        let created_drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(created_drug.stock, 0);
+        assert_eq!(
+            adjust_stock(&client, &token, created_drug.id, 10).await,
+            Status::Ok
+        );
+        assert_eq!(
+            adjust_stock(&client, &token, created_drug.id, -4).await,
+            Status::Ok
+        );
+        assert_eq!(
+            adjust_stock(&client, &token, created_drug.id, -7).await,
+            Status::UnprocessableEntity
+        );
+        assert_eq!(
+            adjust_stock(&client, &token, Uuid::new_v4(), 1).await,
+            Status::NotFound
+        );
+
+        let response = client
+            .get(format!("/drugs/{}", created_drug.id))
+            .dispatch()
+            .await;
+        let drug: Drug = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(drug.stock, 6);
+    }
+
+    #[tokio::test]
+    async fn doesnt_adjust_drug_stock_without_pharmacist_session() {
+        let (client, _, token) = create_api_client_with_session().await;
+
+        let response = client
+            .post(format!("/drugs/{}/stock", Uuid::new_v4()))
+            .header(ContentType::JSON)
+            .body(r#"{"delta": 1}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+        assert_eq!(
+            adjust_stock(&client, &token, Uuid::new_v4(), 1).await,
+            Status::Forbidden
+        );
+    }
 }
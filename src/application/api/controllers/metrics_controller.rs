@@ -0,0 +1,66 @@
+use rocket::{get, State};
+
+use crate::application::api::fairings::request_metrics::RequestMetrics;
+
+// Not part of the api docs, scraped by Prometheus
+#[get("/metrics")]
+pub async fn get_metrics(metrics: &State<RequestMetrics>) -> String {
+    metrics.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::asynchronous::Client, routes};
+
+    use crate::application::api::{
+        controllers::drugs_controller,
+        fairings::request_metrics::{RequestMetrics, RequestMetricsFairing},
+        utils::fake_api_context::create_fake_api_context,
+    };
+
+    async fn create_api_client() -> Client {
+        let context = create_fake_api_context();
+
+        let routes = routes![
+            super::get_metrics,
+            drugs_controller::get_drugs_with_pagination,
+            drugs_controller::get_drug_by_id
+        ];
+
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(RequestMetrics::new(None))
+            .attach(RequestMetricsFairing)
+            .mount("/", routes);
+
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn exposes_request_counts_and_latencies() {
+        let client = create_api_client().await;
+
+        client.get("/drugs").dispatch().await;
+        client.get("/drugs").dispatch().await;
+        client
+            .get("/drugs/00000000-0000-0000-0000-000000000000")
+            .dispatch()
+            .await;
+
+        let response = client.get("/metrics").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body = response.into_string().await.unwrap();
+
+        assert!(body.contains("# TYPE http_requests_total counter"));
+        assert!(body.contains(r#"http_requests_total{method="GET",route="/drugs",status="200"} 2"#));
+        assert!(body.contains(
+            r#"http_requests_total{method="GET",route="/drugs/<drug_id>",status="404"} 1"#
+        ));
+        assert!(body.contains("# TYPE http_request_duration_seconds histogram"));
+        assert!(
+            body.contains(r#"http_request_duration_seconds_count{method="GET",route="/drugs"} 2"#)
+        );
+    }
+}
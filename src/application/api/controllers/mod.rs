@@ -1,6 +1,9 @@
+pub mod admin_controller;
 pub mod authentication_controller;
 pub mod doctors_controller;
 pub mod drugs_controller;
+pub mod metrics_controller;
 pub mod patients_controller;
 pub mod pharmacists_controller;
 pub mod prescriptions_controller;
+pub mod reports_controller;
@@ -1,11 +1,13 @@
+use chrono::{DateTime, Utc};
 use okapi::openapi3::Responses;
 use rocket::{
+    data::Capped,
     get,
     http::Status,
     post,
     response::{status::Created, Responder},
     serde::json::Json,
-    Request,
+    Request, State,
 };
 use rocket_okapi::{
     gen::OpenApiGenerator, okapi::schemars, openapi, response::OpenApiResponderInner, OpenApiError,
@@ -15,13 +17,24 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    application::api::utils::{error::ApiError, openapi_responses::get_openapi_responses},
+    application::{
+        api::utils::{
+            api_config::ApiConfig, error::ApiError, openapi_responses::get_openapi_responses,
+            pagination_messages::pagination_error_message, pesel_masking::mask_pesel_numbers,
+        },
+        sessions::entities::Session,
+    },
     domain::patients::{
         entities::Patient,
         repository::{
-            CreatePatientRepositoryError, GetPatientByIdRepositoryError, GetPatientsRepositoryError,
+            CreatePatientRepositoryError, GetPatientByIdRepositoryError,
+            GetPatientByPeselNumberRepositoryError, GetPatientsRepositoryError,
+            MergePatientsRepositoryError,
+        },
+        service::{
+            CreatePatientError, FindPatientIdByPeselNumberError, GetPatientByIdError,
+            GetPatientsWithPaginationError, MergePatientsError,
         },
-        service::{CreatePatientError, GetPatientByIdError, GetPatientsWithPaginationError},
     },
     Ctx,
 };
@@ -34,6 +47,7 @@ fn example_pesel_number() -> &'static str {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreatePatientDto {
     #[schemars(example = "example_name")]
     name: String,
@@ -41,6 +55,21 @@ pub struct CreatePatientDto {
     pesel_number: String,
 }
 
+// The age is derived from the PESEL number on every request, so it's never stale
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PatientResponse {
+    #[serde(flatten)]
+    patient: Patient,
+    age: Option<u32>,
+}
+
+impl PatientResponse {
+    fn new(patient: Patient, now: DateTime<Utc>) -> Self {
+        let age = patient.age(now);
+        Self { patient, age }
+    }
+}
+
 impl<'r> Responder<'r, 'static> for CreatePatientError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
@@ -50,6 +79,7 @@ impl<'r> Responder<'r, 'static> for CreatePatientError {
                 let status = match err {
                     CreatePatientRepositoryError::DuplicatedPeselNumber => Status::Conflict,
                     CreatePatientRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    CreatePatientRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -60,17 +90,24 @@ impl<'r> Responder<'r, 'static> for CreatePatientError {
 }
 
 impl OpenApiResponderInner for CreatePatientError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "422",
-                "Returned when the name or the pesel_number are incorrect",
-            ),
-            (
-                "409",
-                "Returned when patient with given pesel_number exist in the database",
-            ),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "422",
+                    "Returned when the name or the pesel_number are incorrect",
+                ),
+                (
+                    "409",
+                    "Returned when patient with given pesel_number exist in the database",
+                ),
+                (
+                    "415",
+                    "Returned when the Content-Type is not application/json",
+                ),
+            ],
+        )
     }
 }
 
@@ -78,15 +115,166 @@ impl OpenApiResponderInner for CreatePatientError {
 #[post("/patients", format = "application/json", data = "<dto>")]
 pub async fn create_patient(
     ctx: &Ctx,
+    session: Option<Session>,
     dto: Json<CreatePatientDto>,
-) -> Result<Created<Json<Patient>>, CreatePatientError> {
+) -> Result<Created<Json<PatientResponse>>, CreatePatientError> {
     let created_patient = ctx
         .patients_service
-        .create_patient(dto.0.name, dto.0.pesel_number)
+        .create_patient(
+            dto.0.name,
+            dto.0.pesel_number,
+            session.map(|session| session.user_id),
+        )
         .await?;
 
     let location = format!("/patients/{}", created_patient.id);
-    Ok(Created::new(location).body(Json(created_patient)))
+    Ok(Created::new(location).body(Json(PatientResponse::new(created_patient, Utc::now()))))
+}
+
+#[derive(Debug, Deserialize)]
+struct PatientCsvRow {
+    name: String,
+    pesel_number: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImportPatientsRowError {
+    line: u64,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImportPatientsSummary {
+    imported: usize,
+    errors: Vec<ImportPatientsRowError>,
+}
+
+#[derive(Debug)]
+pub enum ImportPatientsError {
+    TooLarge,
+    InvalidCsv(String),
+    RepositoryError(CreatePatientRepositoryError),
+}
+
+impl<'r> Responder<'r, 'static> for ImportPatientsError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::TooLarge => (
+                "CSV file is too large to import".to_string(),
+                Status::PayloadTooLarge,
+            ),
+            Self::InvalidCsv(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    CreatePatientRepositoryError::DuplicatedPeselNumber => Status::Conflict,
+                    CreatePatientRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    CreatePatientRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for ImportPatientsError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "413",
+                    "Returned when the CSV file exceeds the bytes limit (1 MiB)",
+                ),
+                (
+                    "422",
+                    "Returned when the CSV file doesn't have the name and pesel_number columns",
+                ),
+                ("415", "Returned when the Content-Type is not text/csv"),
+            ],
+        )
+    }
+}
+
+// Invalid and duplicated rows don't abort the import, they are reported with their line number
+// instead. Only database failures stop it, leaving the rows before them imported
+#[openapi(tag = "Patients")]
+#[post("/patients/import", format = "text/csv", data = "<body>")]
+pub async fn import_patients(
+    ctx: &Ctx,
+    session: Option<Session>,
+    body: Capped<&[u8]>,
+) -> Result<Json<ImportPatientsSummary>, ImportPatientsError> {
+    if !body.is_complete() {
+        return Err(ImportPatientsError::TooLarge);
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.into_inner());
+    let headers = reader
+        .headers()
+        .map_err(|err| ImportPatientsError::InvalidCsv(err.to_string()))?
+        .clone();
+    if !["name", "pesel_number"]
+        .iter()
+        .all(|column| headers.iter().any(|header| header == *column))
+    {
+        return Err(ImportPatientsError::InvalidCsv(
+            "CSV must have name and pesel_number columns".into(),
+        ));
+    }
+
+    let created_by = session.map(|session| session.user_id);
+    let mut imported = 0;
+    let mut errors = Vec::new();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(ImportPatientsRowError {
+                    line: err.position().map_or(0, |position| position.line()),
+                    reason: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let row: PatientCsvRow = match record.deserialize(Some(&headers)) {
+            Ok(row) => row,
+            Err(err) => {
+                errors.push(ImportPatientsRowError {
+                    line,
+                    reason: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        match ctx
+            .patients_service
+            .create_patient(row.name, row.pesel_number, created_by)
+            .await
+        {
+            Ok(_) => imported += 1,
+            Err(CreatePatientError::DomainError(reason)) => {
+                errors.push(ImportPatientsRowError { line, reason })
+            }
+            Err(CreatePatientError::RepositoryError(
+                err @ CreatePatientRepositoryError::DuplicatedPeselNumber,
+            )) => errors.push(ImportPatientsRowError {
+                line,
+                reason: err.to_string(),
+            }),
+            Err(CreatePatientError::RepositoryError(err)) => {
+                Err(ImportPatientsError::RepositoryError(err))?
+            }
+        }
+    }
+
+    Ok(Json(ImportPatientsSummary { imported, errors }))
 }
 
 impl<'r> Responder<'r, 'static> for GetPatientByIdError {
@@ -97,6 +285,7 @@ impl<'r> Responder<'r, 'static> for GetPatientByIdError {
                 let status = match err {
                     GetPatientByIdRepositoryError::NotFound(_) => Status::NotFound,
                     GetPatientByIdRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetPatientByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -107,17 +296,24 @@ impl<'r> Responder<'r, 'static> for GetPatientByIdError {
 }
 
 impl OpenApiResponderInner for GetPatientByIdError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "404",
-                "Returned when the the patient with given id doesn't exist",
-            ),
-            (
-                "422",
-                "Returned when the the patient_id is not a valid UUID",
-            ),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the patient with given id doesn't exist",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the patient_id is not a valid UUID",
+                ),
+            ],
+        )
     }
 }
 
@@ -126,22 +322,28 @@ impl OpenApiResponderInner for GetPatientByIdError {
 pub async fn get_patient_by_id(
     ctx: &Ctx,
     patient_id: Uuid,
-) -> Result<Json<Patient>, GetPatientByIdError> {
+) -> Result<Json<PatientResponse>, GetPatientByIdError> {
     let patient = ctx.patients_service.get_patient_by_id(patient_id).await?;
 
-    Ok(Json(patient))
+    Ok(Json(PatientResponse::new(patient, Utc::now())))
 }
 
 impl<'r> Responder<'r, 'static> for GetPatientsWithPaginationError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
             Self::RepositoryError(err) => {
-                let message = err.to_string();
+                let message = match &err {
+                    GetPatientsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
                 let status = match err {
                     GetPatientsRepositoryError::InvalidPaginationParams(_) => {
                         Status::UnprocessableEntity
                     }
                     GetPatientsRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetPatientsRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -152,11 +354,24 @@ impl<'r> Responder<'r, 'static> for GetPatientsWithPaginationError {
 }
 
 impl OpenApiResponderInner for GetPatientsWithPaginationError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            ("404", "Returned when the the page < 0 or page_size < 1"),
-            ("422", "Returned when the the page < 0 or page_size < 1"),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the page < 0, page_size < 1 or page_size > 100",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the page < 0, page_size < 1 or page_size > 100",
+                ),
+            ],
+        )
     }
 }
 
@@ -164,42 +379,213 @@ impl OpenApiResponderInner for GetPatientsWithPaginationError {
 #[get("/patients?<page>&<page_size>", format = "application/json")]
 pub async fn get_patients_with_pagination(
     ctx: &Ctx,
+    config: &State<ApiConfig>,
     page: Option<i64>,
     page_size: Option<i64>,
-) -> Result<Json<Vec<Patient>>, GetPatientsWithPaginationError> {
+) -> Result<Json<Vec<PatientResponse>>, GetPatientsWithPaginationError> {
     let patients = ctx
         .patients_service
         .get_patients_with_pagination(page, page_size)
         .await?;
 
+    // Masking keeps the date of birth digits, so the age can still be derived
+    let now = Utc::now();
+    let patients = mask_pesel_numbers(patients, config.mask_pesel_numbers)
+        .into_iter()
+        .map(|patient| PatientResponse::new(patient, now))
+        .collect();
+
     Ok(Json(patients))
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PatientExistsResponse {
+    exists: bool,
+    id: Option<Uuid>,
+}
+
+impl<'r> Responder<'r, 'static> for FindPatientIdByPeselNumberError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetPatientByPeselNumberRepositoryError::NotFound(_) => Status::NotFound,
+                    GetPatientByPeselNumberRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPatientByPeselNumberRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for FindPatientIdByPeselNumberError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                ("422", "Returned when the pesel_number is malformed"),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Patients")]
+#[get("/patients/exists?<pesel_number>", format = "application/json")]
+pub async fn check_patient_exists(
+    ctx: &Ctx,
+    pesel_number: &str,
+) -> Result<Json<PatientExistsResponse>, FindPatientIdByPeselNumberError> {
+    let id = ctx
+        .patients_service
+        .find_patient_id_by_pesel_number(pesel_number)
+        .await?;
+
+    Ok(Json(PatientExistsResponse {
+        exists: id.is_some(),
+        id,
+    }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MergePatientsDto {
+    keep_id: Uuid,
+    merge_id: Uuid,
+    #[serde(default)]
+    confirmed: bool,
+}
+
+impl<'r> Responder<'r, 'static> for MergePatientsError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::NotConfirmed => (
+                "Merging patients has to be confirmed".into(),
+                Status::Conflict,
+            ),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    MergePatientsRepositoryError::NotFound(_) => Status::NotFound,
+                    MergePatientsRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    MergePatientsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for MergePatientsError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                ("404", "Returned when one of the patients doesn't exist"),
+                ("409", "Returned when the merge isn't confirmed"),
+                (
+                    "422",
+                    "Returned when keep_id and merge_id are the same patient",
+                ),
+            ],
+        )
+    }
+}
+
+// Prescriptions of the merged patient are moved to the kept one, then the merged patient is deleted.
+// Duplicates have different PESEL numbers, so the merge has to be confirmed with `confirmed`
+#[openapi(tag = "Patients")]
+#[post("/patients/merge", format = "application/json", data = "<dto>")]
+pub async fn merge_patients(
+    ctx: &Ctx,
+    dto: Json<MergePatientsDto>,
+) -> Result<Json<PatientResponse>, MergePatientsError> {
+    let patient = ctx
+        .patients_service
+        .merge_patients(dto.0.keep_id, dto.0.merge_id, dto.0.confirmed)
+        .await?;
+
+    Ok(Json(PatientResponse::new(patient, Utc::now())))
+}
+
 #[cfg(test)]
 mod tests {
 
+    use chrono::Utc;
     use rocket::{
-        http::{ContentType, Status},
+        http::{ContentType, Header, Status},
         local::asynchronous::Client,
         routes,
-        serde::json,
+        serde::json::{self, Value},
     };
 
+    use uuid::Uuid;
+
     use crate::{
-        application::api::utils::fake_api_context::create_fake_api_context,
-        domain::patients::entities::Patient,
+        application::api::utils::{
+            api_config::ApiConfig, fake_api_context::create_fake_api_context,
+        },
+        domain::patients::entities::{get_age_from_pesel_number, Patient},
+        Context,
     };
 
     async fn create_api_client() -> Client {
+        create_api_client_with_context(create_fake_api_context(), ApiConfig::default()).await
+    }
+
+    async fn create_api_client_with_session() -> (Client, Uuid, String) {
         let context = create_fake_api_context();
+        let user_id = Uuid::new_v4();
+        let session = context
+            .sessions_service
+            .create_session(
+                user_id,
+                None,
+                None,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
 
+        (
+            create_api_client_with_context(context, ApiConfig::default()).await,
+            user_id,
+            token,
+        )
+    }
+
+    async fn create_api_client_with_context(context: Context, config: ApiConfig) -> Client {
         let routes = routes![
             super::create_patient,
             super::get_patient_by_id,
-            super::get_patients_with_pagination
+            super::get_patients_with_pagination,
+            super::check_patient_exists,
+            super::import_patients,
+            super::merge_patients
         ];
 
-        let rocket = rocket::build().manage(context).mount("/", routes);
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(config)
+            .mount("/", routes);
 
         Client::tracked(rocket).await.unwrap()
     }
@@ -238,6 +624,44 @@ mod tests {
         assert_eq!(patient.pesel_number, "96021807250");
     }
 
+    #[tokio::test]
+    async fn returns_age_of_patient_derived_from_pesel_number() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/patients")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let created_patient: Value =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let expected_age = get_age_from_pesel_number("96021807250", Utc::now()).unwrap();
+
+        assert_eq!(created_patient["age"], expected_age);
+
+        let response = client
+            .get(format!(
+                "/patients/{}",
+                created_patient["id"].as_str().unwrap()
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let patient: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(patient["age"], expected_age);
+
+        let response = client
+            .get("/patients")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let patients: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(patients[0]["age"], expected_age);
+    }
+
     #[tokio::test]
     async fn create_patient_returns_unprocessable_entity_if_body_has_incorrect_keys() {
         let client = create_api_client().await;
@@ -251,6 +675,19 @@ mod tests {
         assert_eq!(response.status(), Status::UnprocessableEntity);
     }
 
+    #[tokio::test]
+    async fn create_patient_returns_unprocessable_entity_if_body_has_unknown_keys() {
+        let client = create_api_client().await;
+
+        let request_with_unknown_key = client
+            .post("/patients")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250", "nickname":"Johnny"}"#)
+            .header(ContentType::JSON);
+        let response = request_with_unknown_key.dispatch().await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
     #[tokio::test]
     async fn create_patient_returns_unprocessable_entity_if_body_has_incorrect_value_incorrect() {
         let client = create_api_client().await;
@@ -264,6 +701,40 @@ mod tests {
         assert_eq!(response.status(), Status::UnprocessableEntity);
     }
 
+    #[tokio::test]
+    async fn create_patient_returns_error_message_in_language_from_accept_language_header() {
+        let client = create_api_client().await;
+
+        let polish_response = client
+            .post("/patients")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807251"}"#)
+            .header(ContentType::JSON)
+            .header(Header::new("Accept-Language", "pl-PL,pl;q=0.9,en;q=0.8"))
+            .dispatch()
+            .await;
+        let polish_error: Value =
+            json::from_str(&polish_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(
+            polish_error["message"],
+            "Suma kontrolna numeru PESEL jest nieprawidłowa"
+        );
+
+        let english_response = client
+            .post("/patients")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807251"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let english_error: Value =
+            json::from_str(&english_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(
+            english_error["message"],
+            "The checksum of PESEL number is incorrect"
+        );
+    }
+
     #[tokio::test]
     async fn create_patient_returns_conflict_if_pesel_number_is_duplicated() {
         let client = create_api_client().await;
@@ -305,6 +776,59 @@ mod tests {
         assert_eq!(response.status(), Status::NotFound);
     }
 
+    #[tokio::test]
+    async fn checks_if_patient_with_pesel_number_exists() {
+        let client = create_api_client().await;
+
+        let create_patient_response = client
+            .post("/patients")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let created_patient: Patient =
+            json::from_str(&create_patient_response.into_string().await.unwrap()).unwrap();
+
+        let response = client
+            .get("/patients/exists?pesel_number=96021807250")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(body["exists"], true);
+        assert_eq!(body["id"], created_patient.id.to_string());
+
+        let response = client
+            .get("/patients/exists?pesel_number=99031301347")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(body["exists"], false);
+        assert_eq!(body["id"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn check_patient_exists_returns_unprocessable_entity_if_pesel_number_is_malformed() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/patients/exists?pesel_number=9602180725X")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
     #[tokio::test]
     async fn gets_patients_with_pagination() {
         let client = create_api_client().await;
@@ -372,4 +896,219 @@ mod tests {
             Status::UnprocessableEntity
         );
     }
+
+    #[tokio::test]
+    async fn records_session_user_as_creator_of_patient() {
+        let (client, user_id, token) = create_api_client_with_session().await;
+
+        let response = client
+            .post("/patients")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+        let created_patient: Patient =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(created_patient.created_by, Some(user_id));
+
+        let response = client
+            .get(format!("/patients/{}", created_patient.id))
+            .dispatch()
+            .await;
+        let patient: Patient = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(patient.created_by, Some(user_id));
+
+        let response = client
+            .post("/patients")
+            .header(ContentType::JSON)
+            .body(r#"{"name":"John Doey", "pesel_number":"99031301347"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+        let created_patient: Patient =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(created_patient.created_by, None);
+    }
+
+    #[tokio::test]
+    async fn imports_valid_patients_from_csv_and_reports_invalid_rows() {
+        let client = create_api_client().await;
+
+        let csv = "name,pesel_number\n\
+            John Doex,96021807250\n\
+            Jane Doe,96021807251\n\
+            John Doey,96021807250\n\
+            Broken row\n\
+            John Doez,99031301347\n";
+        let response = client
+            .post("/patients/import")
+            .header(ContentType::CSV)
+            .body(csv)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let summary: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(summary["imported"], 2);
+        let error_lines: Vec<u64> = summary["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["line"].as_u64().unwrap())
+            .collect();
+        assert_eq!(error_lines, vec![3, 4, 5]);
+
+        let response = client
+            .get("/patients?page=0&page_size=10")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let patients: Vec<Patient> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(patients.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn doesnt_import_patients_from_csv_without_required_columns() {
+        let client = create_api_client().await;
+
+        let response = client
+            .post("/patients/import")
+            .header(ContentType::CSV)
+            .body("name,pesel\nJohn Doex,96021807250\n")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn masks_pesel_numbers_in_list_but_not_when_reading_by_id() {
+        let config = ApiConfig {
+            mask_pesel_numbers: true,
+            ..ApiConfig::default()
+        };
+        let client = create_api_client_with_context(create_fake_api_context(), config).await;
+
+        let response = client
+            .post("/patients")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250"}"#)
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let created_patient: Patient =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        let response = client
+            .get("/patients?page=0&page_size=10")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let patients: Vec<Patient> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(patients[0].pesel_number, "960218*****");
+
+        let response = client
+            .get(format!("/patients/{}", created_patient.id))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let patient: Patient = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(patient.pesel_number, "96021807250");
+    }
+
+    #[tokio::test]
+    async fn merges_patients_only_when_confirmed() {
+        let client = create_api_client().await;
+
+        let mut patient_ids = vec![];
+        for pesel_number in ["96021817257", "99031301347"] {
+            let response = client
+                .post("/patients")
+                .body(format!(
+                    r#"{{"name":"John Doe", "pesel_number":"{}"}}"#,
+                    pesel_number
+                ))
+                .header(ContentType::JSON)
+                .dispatch()
+                .await;
+            let patient: Patient = json::from_str(&response.into_string().await.unwrap()).unwrap();
+            patient_ids.push(patient.id);
+        }
+
+        let response = client
+            .post("/patients/merge")
+            .body(format!(
+                r#"{{"keep_id":"{}", "merge_id":"{}"}}"#,
+                patient_ids[0], patient_ids[1]
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+
+        let response = client
+            .post("/patients/merge")
+            .body(format!(
+                r#"{{"keep_id":"{}", "merge_id":"{}", "confirmed":true}}"#,
+                patient_ids[0],
+                Uuid::new_v4()
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .post("/patients/merge")
+            .body(format!(
+                r#"{{"keep_id":"{}", "merge_id":"{}", "confirmed":true}}"#,
+                patient_ids[0], patient_ids[0]
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .get(format!("/patients/{}", patient_ids[1]))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/patients/merge")
+            .body(format!(
+                r#"{{"keep_id":"{}", "merge_id":"{}", "confirmed":true}}"#,
+                patient_ids[0], patient_ids[1]
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let patient: Patient = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(patient.id, patient_ids[0]);
+
+        let response = client
+            .get(format!("/patients/{}", patient_ids[1]))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
 }
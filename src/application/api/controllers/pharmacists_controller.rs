@@ -5,7 +5,7 @@ use rocket::{
     post,
     response::{status::Created, Responder},
     serde::json::Json,
-    Request,
+    Request, State,
 };
 use rocket_okapi::{
     gen::OpenApiGenerator, okapi::schemars, openapi, response::OpenApiResponderInner, OpenApiError,
@@ -15,7 +15,10 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    application::api::utils::{error::ApiError, openapi_responses::get_openapi_responses},
+    application::api::utils::{
+        api_config::ApiConfig, error::ApiError, openapi_responses::get_openapi_responses,
+        pagination_messages::pagination_error_message, pesel_masking::mask_pesel_numbers,
+    },
     domain::pharmacists::{
         entities::Pharmacist,
         repository::{
@@ -37,6 +40,7 @@ fn example_pesel_number() -> &'static str {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreatePharmacistDto {
     #[schemars(example = "example_name")]
     name: String,
@@ -55,6 +59,7 @@ impl<'r> Responder<'r, 'static> for CreatePharmacistError {
                     CreatePharmacistRepositoryError::DatabaseError(_) => {
                         Status::InternalServerError
                     }
+                    CreatePharmacistRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -65,17 +70,24 @@ impl<'r> Responder<'r, 'static> for CreatePharmacistError {
 }
 
 impl OpenApiResponderInner for CreatePharmacistError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "422",
-                "Returned when the name or the pesel_number are incorrect",
-            ),
-            (
-                "409",
-                "Returned when pharmacist with given pesel_number exist in the database",
-            ),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "422",
+                    "Returned when the name or the pesel_number are incorrect",
+                ),
+                (
+                    "409",
+                    "Returned when pharmacist with given pesel_number exist in the database",
+                ),
+                (
+                    "415",
+                    "Returned when the Content-Type is not application/json",
+                ),
+            ],
+        )
     }
 }
 
@@ -104,6 +116,7 @@ impl<'r> Responder<'r, 'static> for GetPharmacistByIdError {
                     GetPharmacistByIdRepositoryError::DatabaseError(_) => {
                         Status::InternalServerError
                     }
+                    GetPharmacistByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -114,17 +127,24 @@ impl<'r> Responder<'r, 'static> for GetPharmacistByIdError {
 }
 
 impl OpenApiResponderInner for GetPharmacistByIdError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "404",
-                "Returned when the the pharmacist with given id doesn't exist",
-            ),
-            (
-                "422",
-                "Returned when the the pharmacist_id is not a valid UUID",
-            ),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the pharmacist with given id doesn't exist",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the pharmacist_id is not a valid UUID",
+                ),
+            ],
+        )
     }
 }
 
@@ -146,12 +166,18 @@ impl<'r> Responder<'r, 'static> for GetPharmacistsWithPaginationError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
             Self::RepositoryError(err) => {
-                let message = err.to_string();
+                let message = match &err {
+                    GetPharmacistsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
                 let status = match err {
                     GetPharmacistsRepositoryError::InvalidPaginationParams(_) => {
                         Status::UnprocessableEntity
                     }
                     GetPharmacistsRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetPharmacistsRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -162,11 +188,20 @@ impl<'r> Responder<'r, 'static> for GetPharmacistsWithPaginationError {
 }
 
 impl OpenApiResponderInner for GetPharmacistsWithPaginationError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![(
-            "422",
-            "Returned when the the page < 0 or page_size < 1",
-        )])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the page < 0, page_size < 1 or page_size > 100",
+                ),
+            ],
+        )
     }
 }
 
@@ -174,6 +209,7 @@ impl OpenApiResponderInner for GetPharmacistsWithPaginationError {
 #[get("/pharmacists?<page>&<page_size>", format = "application/json")]
 pub async fn get_pharmacists_with_pagination(
     ctx: &Ctx,
+    config: &State<ApiConfig>,
     page: Option<i64>,
     page_size: Option<i64>,
 ) -> Result<Json<Vec<Pharmacist>>, GetPharmacistsWithPaginationError> {
@@ -182,7 +218,10 @@ pub async fn get_pharmacists_with_pagination(
         .get_pharmacists_with_pagination(page, page_size)
         .await?;
 
-    Ok(Json(pharmacists))
+    Ok(Json(mask_pesel_numbers(
+        pharmacists,
+        config.mask_pesel_numbers,
+    )))
 }
 
 #[cfg(test)]
@@ -196,7 +235,9 @@ mod tests {
     };
 
     use crate::{
-        application::api::utils::fake_api_context::create_fake_api_context,
+        application::api::utils::{
+            api_config::ApiConfig, fake_api_context::create_fake_api_context,
+        },
         domain::pharmacists::entities::Pharmacist,
     };
 
@@ -209,7 +250,10 @@ mod tests {
             super::get_pharmacists_with_pagination
         ];
 
-        let rocket = rocket::build().manage(context).mount("/", routes);
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(ApiConfig::default())
+            .mount("/", routes);
 
         Client::tracked(rocket).await.unwrap()
     }
@@ -261,6 +305,19 @@ mod tests {
         assert_eq!(response.status(), Status::UnprocessableEntity);
     }
 
+    #[tokio::test]
+    async fn create_pharmacist_returns_unprocessable_entity_if_body_has_unknown_keys() {
+        let client = create_api_client().await;
+
+        let request_with_unknown_key = client
+            .post("/pharmacists")
+            .body(r#"{"name":"John Doex", "pesel_number":"96021807250", "nickname":"Johnny"}"#)
+            .header(ContentType::JSON);
+        let response = request_with_unknown_key.dispatch().await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
     #[tokio::test]
     async fn create_pharmacist_returns_unprocessable_entity_if_body_has_incorrect_value_incorrect()
     {
@@ -1,29 +1,70 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use okapi::openapi3::Responses;
 use rocket::{
+    delete,
+    futures::stream::{BoxStream, StreamExt},
     get,
-    http::Status,
-    post,
-    response::{status::Created, Responder},
+    http::{ContentType, Status},
+    patch, post, put,
+    request::FromParam,
+    response::{
+        status::{Created, NoContent},
+        stream::{stream, Event, EventStream},
+        Responder,
+    },
     serde::json::Json,
-    Request,
+    tokio::{select, sync::broadcast::error::RecvError},
+    Either, FromFormField, Request, Shutdown, State,
 };
 use rocket_okapi::{gen::OpenApiGenerator, openapi, response::OpenApiResponderInner, OpenApiError};
-use schemars::JsonSchema;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    application::api::utils::{error::ApiError, openapi_responses::get_openapi_responses},
+    application::api::{
+        guards::authorization::{DoctorSession, PharmacistSession},
+        rendering::{
+            prescription_pdf::render_prescription_pdf, prescription_qr::render_qr_code_png,
+        },
+        utils::{
+            api_config::ApiConfig,
+            date_range::{CreatedAtRange, DateRange},
+            date_time_param::DateTimeParam,
+            error::ApiError,
+            openapi_responses::get_openapi_responses,
+            pagination_messages::pagination_error_message,
+        },
+    },
     domain::prescriptions::{
-        entities::{Prescription, PrescriptionType},
+        entities::{
+            AffectedPatient, DrugHistoryEntry, DrugLabel, DrugUsage, PharmacistFillStats,
+            Prescription, PrescriptionFillStatus, PrescriptionTemplate, PrescriptionType,
+            PrescriptionVerification,
+        },
+        prescription_code::PrescriptionCode,
         repository::{
-            CreatePrescriptionRepositoryError, FillPrescriptionRepositoryError,
-            GetPrescriptionByIdRepositoryError, GetPrescriptionsRepositoryError,
+            CancelPrescriptionRepositoryError, CreatePrescriptionRepositoryError,
+            CreatePrescriptionTemplateRepositoryError, DeletePrescriptionTemplateRepositoryError,
+            FillPrescriptionRepositoryError, GetDrugUsageRepositoryError,
+            GetPharmacistFillStatsRepositoryError, GetPrescriptionByIdRepositoryError,
+            GetPrescriptionTemplateByIdRepositoryError, GetPrescriptionTemplatesRepositoryError,
+            GetPrescriptionsRepositoryError, ReassignPrescriptionPatientRepositoryError,
+            UpdatePrescriptionTemplateRepositoryError,
         },
         service::{
-            CreatePrescriptionError, FillPrescriptionError, GetPrescriptionByIdError,
-            GetPrescriptionsWithPaginationError,
+            CancelPrescriptionError, CountFillsByPharmacistError, CountPrescriptionsForDrugError,
+            CreatePrescriptionError, CreatePrescriptionFromTemplateError,
+            CreatePrescriptionTemplateError, DeletePrescriptionTemplateError,
+            FillPrescriptionError, FillPrescriptionsBatchError,
+            GetActivePrescriptionsForPatientError, GetDrugHistoryForPatientError,
+            GetPatientsPrescribedDrugError, GetPrescriptionByIdError,
+            GetPrescriptionTemplatesError, GetPrescriptionsByIdsError,
+            GetPrescriptionsExpiringBeforeError, GetPrescriptionsWithPaginationError,
+            ReassignPrescriptionPatientError, SearchPrescriptionsError,
+            UpdatePrescriptionTemplateError,
         },
     },
     Ctx,
@@ -32,9 +73,13 @@ use crate::{
 fn example_prescribed_drug() -> Vec<(Uuid, u32)> {
     vec![(Uuid::new_v4(), 2)]
 }
+fn example_diagnoses() -> Option<Vec<String>> {
+    Some(vec!["J06.9".into()])
+}
 
 type PrescribedDrugDto = (Uuid, u32);
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreatePrescriptionDto {
     doctor_id: Uuid,
     patient_id: Uuid,
@@ -45,6 +90,15 @@ pub struct CreatePrescriptionDto {
         description = "List of tuples with drug_id and quantity"
     )]
     prescribed_drugs: Vec<PrescribedDrugDto>,
+    #[schemars(
+        example = "example_diagnoses",
+        description = "ICD-10 codes of the diagnoses"
+    )]
+    diagnoses: Option<Vec<String>>,
+    #[schemars(
+        description = "Restarts the validity window at the first fill instead of the start date, defaults to false"
+    )]
+    window_from_fill: Option<bool>,
 }
 
 impl<'r> Responder<'r, 'static> for CreatePrescriptionError {
@@ -57,9 +111,13 @@ impl<'r> Responder<'r, 'static> for CreatePrescriptionError {
                     CreatePrescriptionRepositoryError::DoctorNotFound(_) => Status::NotFound,
                     CreatePrescriptionRepositoryError::PatientNotFound(_) => Status::NotFound,
                     CreatePrescriptionRepositoryError::DrugNotFound(_) => Status::NotFound,
+                    CreatePrescriptionRepositoryError::CodeGenerationFailed => {
+                        Status::InternalServerError
+                    }
                     CreatePrescriptionRepositoryError::DatabaseError(_) => {
                         Status::InternalServerError
                     }
+                    CreatePrescriptionRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -70,41 +128,118 @@ impl<'r> Responder<'r, 'static> for CreatePrescriptionError {
 }
 
 impl OpenApiResponderInner for CreatePrescriptionError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, 
             vec![
                 (
                     "422",
-                    "Returned when the body parameters are invalid, or the doctor_id, patient_id or drug_id is not a valid UUID",
+                    "Returned when the body parameters are invalid, the doctor_id, patient_id or drug_id is not a valid UUID, or a diagnosis is not a valid ICD-10 code",
                 ),
                 (
                     "404",
                     "Returned when doctor, patient or drug with given id doesn't exist",
                 ),
+                ("415", "Returned when the Content-Type is not application/json"),
             ]
         )
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DryRunPrescriptionResponse {
+    #[serde(flatten)]
+    prescription: Prescription,
+    dry_run: bool,
+}
+
+// dry_run=true validates the prescription the same way and returns it with 200 instead of saving it
 #[openapi(tag = "Prescriptions")]
-#[post("/prescriptions", format = "application/json", data = "<dto>")]
+#[post(
+    "/prescriptions?<dry_run>",
+    format = "application/json",
+    data = "<dto>"
+)]
 pub async fn create_prescription(
     ctx: &Ctx,
+    dry_run: Option<bool>,
     dto: Json<CreatePrescriptionDto>,
-) -> Result<Created<Json<Prescription>>, CreatePrescriptionError> {
+) -> Result<
+    Either<Created<Json<Prescription>>, Json<DryRunPrescriptionResponse>>,
+    CreatePrescriptionError,
+> {
+    if dry_run == Some(true) {
+        let prescription = ctx
+            .prescriptions_service
+            .preview_prescription_with_diagnoses(
+                dto.0.doctor_id,
+                dto.0.patient_id,
+                dto.0.start_date,
+                dto.0.prescription_type,
+                dto.0.prescribed_drugs,
+                dto.0.diagnoses.unwrap_or_default(),
+                dto.0.window_from_fill.unwrap_or_default(),
+            )
+            .await?;
+
+        return Ok(Either::Right(Json(DryRunPrescriptionResponse {
+            prescription,
+            dry_run: true,
+        })));
+    }
+
     let created_prescription = ctx
         .prescriptions_service
-        .create_prescription(
+        .create_prescription_with_diagnoses(
             dto.0.doctor_id,
             dto.0.patient_id,
             dto.0.start_date,
             dto.0.prescription_type,
             dto.0.prescribed_drugs,
+            dto.0.diagnoses.unwrap_or_default(),
+            dto.0.window_from_fill.unwrap_or_default(),
         )
         .await?;
 
     let location = format!("/prescriptions/{}", created_prescription.id);
-    Ok(Created::new(location).body(Json(created_prescription)))
+    Ok(Either::Left(
+        Created::new(location).body(Json(created_prescription)),
+    ))
+}
+
+#[openapi(tag = "Prescriptions")]
+#[get("/prescriptions/events")]
+pub async fn get_prescription_events(
+    ctx: &Ctx,
+    _session: PharmacistSession,
+    mut shutdown: Shutdown,
+) -> EventStream<BoxStream<'static, Event>> {
+    let mut created_prescriptions = ctx
+        .prescriptions_service
+        .subscribe_to_created_prescriptions();
+
+    let events = stream! {
+        loop {
+            let prescription = select! {
+                result = created_prescriptions.recv() => match result {
+                    Ok(prescription) => prescription,
+                    // Slow clients skip the oldest prescriptions instead of holding back the producers
+                    Err(RecvError::Lagged(skipped_count)) => {
+                        tracing::warn!(
+                            skipped_count,
+                            "Prescription events subscriber lagged behind"
+                        );
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+
+            yield Event::json(&prescription).event("prescription_created");
+        }
+    };
+
+    EventStream::from(events.boxed())
 }
 
 impl<'r> Responder<'r, 'static> for GetPrescriptionByIdError {
@@ -117,6 +252,7 @@ impl<'r> Responder<'r, 'static> for GetPrescriptionByIdError {
                     GetPrescriptionByIdRepositoryError::DatabaseError(_) => {
                         Status::InternalServerError
                     }
+                    GetPrescriptionByIdRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -127,17 +263,24 @@ impl<'r> Responder<'r, 'static> for GetPrescriptionByIdError {
 }
 
 impl OpenApiResponderInner for GetPrescriptionByIdError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
-            (
-                "404",
-                "Returned when the the prescription with given id doesn't exist",
-            ),
-            (
-                "422",
-                "Returned when the the prescription_id is not a valid UUID",
-            ),
-        ])
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "404",
+                    "Returned when the the prescription with given id doesn't exist",
+                ),
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the prescription_id is not a valid UUID",
+                ),
+            ],
+        )
     }
 }
 
@@ -155,10 +298,191 @@ pub async fn get_prescription_by_id(
     Ok(Json(prescription))
 }
 
+#[derive(Debug)]
+pub enum GetPrescriptionsBatchError {
+    TooManyPrescriptions(usize),
+    PrescriptionsError(GetPrescriptionsByIdsError),
+}
+
+impl From<GetPrescriptionsByIdsError> for GetPrescriptionsBatchError {
+    fn from(err: GetPrescriptionsByIdsError) -> Self {
+        Self::PrescriptionsError(err)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for GetPrescriptionsBatchError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::TooManyPrescriptions(max_batch_size) => (
+                format!(
+                    "Can't get more than {} prescriptions in one batch",
+                    max_batch_size
+                ),
+                Status::PayloadTooLarge,
+            ),
+            Self::PrescriptionsError(GetPrescriptionsByIdsError::RepositoryError(err)) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetPrescriptionsBatchError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "413",
+                "Returned when the batch has more ids than allowed (MAX_PRESCRIPTIONS_BATCH_GET_SIZE, 100 by default)",
+            ),
+            ("422", "Returned when any of the ids is not a valid UUID"),
+        ])
+    }
+}
+
+// Takes a list of prescription ids, unknown ids are left out of the response
+#[openapi(tag = "Prescriptions")]
+#[post(
+    "/prescriptions/batch-get",
+    format = "json",
+    data = "<prescription_ids>"
+)]
+pub async fn get_prescriptions_batch(
+    ctx: &Ctx,
+    config: &State<ApiConfig>,
+    prescription_ids: Json<Vec<Uuid>>,
+) -> Result<Json<Vec<Prescription>>, GetPrescriptionsBatchError> {
+    if prescription_ids.len() > config.max_prescriptions_batch_get_size {
+        return Err(GetPrescriptionsBatchError::TooManyPrescriptions(
+            config.max_prescriptions_batch_get_size,
+        ));
+    }
+
+    let prescriptions = ctx
+        .prescriptions_service
+        .get_prescriptions_by_ids(&prescription_ids)
+        .await?;
+
+    Ok(Json(prescriptions))
+}
+
+// Path segment in the `<prescription_id>.pdf` format
+pub struct PrescriptionPdfFileName(Uuid);
+
+impl<'r> FromParam<'r> for PrescriptionPdfFileName {
+    type Error = &'r str;
+
+    fn from_param(param: &'r str) -> Result<Self, Self::Error> {
+        param
+            .strip_suffix(".pdf")
+            .and_then(|prescription_id| Uuid::parse_str(prescription_id).ok())
+            .map(Self)
+            .ok_or(param)
+    }
+}
+
+impl JsonSchema for PrescriptionPdfFileName {
+    fn schema_name() -> String {
+        "PrescriptionPdfFileName".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+// Ranked below `get_prescription_by_id`, which forwards the `.pdf` paths as they aren't valid UUIDs
+#[openapi(tag = "Prescriptions")]
+#[get("/prescriptions/<file_name>", rank = 2)]
+pub async fn get_prescription_pdf(
+    ctx: &Ctx,
+    file_name: PrescriptionPdfFileName,
+) -> Result<(ContentType, Vec<u8>), GetPrescriptionByIdError> {
+    let prescription = ctx
+        .prescriptions_service
+        .get_prescription_by_id(file_name.0)
+        .await?;
+
+    let mut drug_names = HashMap::new();
+    for prescribed_drug in &prescription.prescribed_drugs {
+        if let Ok(drug) = ctx
+            .drugs_service
+            .get_drug_by_id(prescribed_drug.drug_id)
+            .await
+        {
+            drug_names.insert(drug.id, drug.name);
+        }
+    }
+
+    let pdf = render_prescription_pdf(&prescription, &drug_names);
+
+    Ok((ContentType::PDF, pdf))
+}
+
+// Encodes the prescription code, which is what pharmacies type in to pull up a prescription
+#[openapi(tag = "Prescriptions")]
+#[get("/prescriptions/<prescription_id>/qr.png")]
+pub async fn get_prescription_qr_code(
+    ctx: &Ctx,
+    prescription_id: Uuid,
+) -> Result<(ContentType, Vec<u8>), GetPrescriptionByIdError> {
+    let prescription = ctx
+        .prescriptions_service
+        .get_prescription_by_id(prescription_id)
+        .await?;
+
+    let png = render_qr_code_png(&prescription.code)
+        .expect("prescription codes are short enough to fit in a QR code");
+
+    Ok((ContentType::PNG, png))
+}
+
+// Labels printed on the packages when dispensing, one per prescribed drug
+#[openapi(tag = "Prescriptions")]
+#[get("/prescriptions/<prescription_id>/labels", format = "application/json")]
+pub async fn get_prescription_labels(
+    ctx: &Ctx,
+    prescription_id: Uuid,
+) -> Result<Json<Vec<DrugLabel>>, GetPrescriptionByIdError> {
+    let prescription = ctx
+        .prescriptions_service
+        .get_prescription_by_id(prescription_id)
+        .await?;
+
+    let mut drugs = HashMap::new();
+    for prescribed_drug in &prescription.prescribed_drugs {
+        if let Ok(drug) = ctx
+            .drugs_service
+            .get_drug_by_id(prescribed_drug.drug_id)
+            .await
+        {
+            drugs.insert(drug.id, drug);
+        }
+    }
+
+    Ok(Json(prescription.dispense_label(&drugs)))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FillPrescriptionDto {
     pharmacist_id: Uuid,
-    prescription_code: String,
+    prescription_code: PrescriptionCode,
+    #[schemars(
+        example = "example_prescribed_drug",
+        description = "List of tuples with drug_id and dispensed quantity, everything that remains is dispensed if omitted"
+    )]
+    dispensed_drugs: Option<Vec<PrescribedDrugDto>>,
 }
 
 impl<'r> Responder<'r, 'static> for FillPrescriptionError {
@@ -169,9 +493,13 @@ impl<'r> Responder<'r, 'static> for FillPrescriptionError {
                 let status = match err {
                     FillPrescriptionRepositoryError::PharmacistNotFound(_) => Status::NotFound,
                     FillPrescriptionRepositoryError::PrescriptionNotFound(_) => Status::NotFound,
+                    FillPrescriptionRepositoryError::QuantityExceeded(_) => {
+                        Status::UnprocessableEntity
+                    }
                     FillPrescriptionRepositoryError::DatabaseError(_) => {
                         Status::InternalServerError
                     }
+                    FillPrescriptionRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -183,16 +511,17 @@ impl<'r> Responder<'r, 'static> for FillPrescriptionError {
 }
 
 impl OpenApiResponderInner for FillPrescriptionError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
             (
                 "404",
                 "Returned when the the prescription or pharmacist with given id doesn't exist",
             ),
             (
                 "422",
-                "Returned when the the prescription_id or pharmacist_id is not a valid UUID, prescriptions is already filled, or the prescription cant be filled today (e.g. today is before start_date or after end_date)",
+                "Returned when the the prescription_id or pharmacist_id is not a valid UUID, prescriptions is already filled, more than remains of a drug is dispensed, or the prescription cant be filled today (e.g. today is before start_date or after end_date)",
             ),
+            ("415", "Returned when the Content-Type is not application/json"),
         ])
     }
 }
@@ -214,6 +543,7 @@ pub async fn fill_prescription(
             prescription_id,
             dto.0.pharmacist_id,
             dto.0.prescription_code,
+            dto.0.dispensed_drugs,
         )
         .await?;
 
@@ -221,18 +551,64 @@ pub async fn fill_prescription(
     Ok(Created::new(location).body(Json(prescription)))
 }
 
-impl<'r> Responder<'r, 'static> for GetPrescriptionsWithPaginationError {
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VerifyPrescriptionDto {
+    prescription_code: PrescriptionCode,
+}
+
+// Nothing is saved, the verification tells whether filling the prescription would succeed
+#[openapi(tag = "Prescriptions")]
+#[post(
+    "/prescriptions/<prescription_id>/verify",
+    format = "application/json",
+    data = "<dto>"
+)]
+pub async fn verify_prescription(
+    ctx: &Ctx,
+    _session: PharmacistSession,
+    prescription_id: Uuid,
+    dto: Json<VerifyPrescriptionDto>,
+) -> Result<Json<PrescriptionVerification>, GetPrescriptionByIdError> {
+    let verification = ctx
+        .prescriptions_service
+        .verify_prescription(prescription_id, dto.0.prescription_code)
+        .await?;
+
+    Ok(Json(verification))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FillPrescriptionsBatchItemDto {
+    prescription_id: Uuid,
+    prescription_code: PrescriptionCode,
+}
+
+impl<'r> Responder<'r, 'static> for FillPrescriptionsBatchError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::ItemError(index, err) => {
+                let message = match err {
+                    FillPrescriptionError::DomainError(message) => message,
+                    FillPrescriptionError::RepositoryError(err) => err.to_string(),
+                };
+                (
+                    format!("Prescription at index {}: {}", index, message),
+                    Status::UnprocessableEntity,
+                )
+            }
             Self::RepositoryError(err) => {
                 let message = err.to_string();
                 let status = match err {
-                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                    FillPrescriptionRepositoryError::PharmacistNotFound(_) => Status::NotFound,
+                    FillPrescriptionRepositoryError::PrescriptionNotFound(_)
+                    | FillPrescriptionRepositoryError::QuantityExceeded(_) => {
                         Status::UnprocessableEntity
                     }
-                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                    FillPrescriptionRepositoryError::DatabaseError(_) => {
                         Status::InternalServerError
                     }
+                    FillPrescriptionRepositoryError::Unavailable => Status::ServiceUnavailable,
                 };
                 (message, status)
             }
@@ -242,482 +618,3530 @@ impl<'r> Responder<'r, 'static> for GetPrescriptionsWithPaginationError {
     }
 }
 
-impl OpenApiResponderInner for GetPrescriptionsWithPaginationError {
-    fn responses(_: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
-        get_openapi_responses(vec![(
-            "422",
-            "Returned when the the page < 0 or page_size < 1",
-        )])
+impl OpenApiResponderInner for FillPrescriptionsBatchError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "422",
+                "Returned when the batch is empty or any of the prescriptions can't be filled, none of the prescriptions are filled then",
+            ),
+            ("415", "Returned when the Content-Type is not application/json"),
+        ])
     }
 }
 
 #[openapi(tag = "Prescriptions")]
-#[get("/prescriptions?<page>&<page_size>", format = "application/json")]
-pub async fn get_prescriptions_with_pagination(
+#[post(
+    "/prescriptions/fill-batch",
+    format = "application/json",
+    data = "<dtos>"
+)]
+pub async fn fill_prescriptions_batch(
     ctx: &Ctx,
-    page: Option<i64>,
-    page_size: Option<i64>,
-) -> Result<Json<Vec<Prescription>>, GetPrescriptionsWithPaginationError> {
+    session: PharmacistSession,
+    dtos: Json<Vec<FillPrescriptionsBatchItemDto>>,
+) -> Result<Created<Json<Vec<Prescription>>>, FillPrescriptionsBatchError> {
     let prescriptions = ctx
         .prescriptions_service
-        .get_prescriptions_with_pagination(page, page_size)
+        .fill_prescriptions(
+            session.0.pharmacist_id.unwrap(),
+            dtos.0
+                .into_iter()
+                .map(|dto| (dto.prescription_id, dto.prescription_code))
+                .collect(),
+        )
         .await?;
 
-    Ok(Json(prescriptions))
+    Ok(Created::new("/prescriptions").body(Json(prescriptions)))
 }
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReassignPrescriptionPatientDto {
+    patient_id: Uuid,
+}
 
-    use rocket::{
-        http::{ContentType, Status},
-        local::asynchronous::Client,
-        routes,
-        serde::json,
-    };
+impl<'r> Responder<'r, 'static> for ReassignPrescriptionPatientError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::NotPrescribingDoctor => (
+                "Only the prescribing doctor can reassign the prescription".to_string(),
+                Status::Forbidden,
+            ),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    ReassignPrescriptionPatientRepositoryError::PrescriptionNotFound(_) => {
+                        Status::NotFound
+                    }
+                    ReassignPrescriptionPatientRepositoryError::PatientNotFound(_) => {
+                        Status::NotFound
+                    }
+                    ReassignPrescriptionPatientRepositoryError::AlreadyFilled(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    ReassignPrescriptionPatientRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    ReassignPrescriptionPatientRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
 
-    use crate::{
-        application::{
-            authentication::{
-                repository::AuthenticationRepositoryFake, service::AuthenticationService,
-            },
-            sessions::{repository::SessionsRepositoryFake, service::SessionsService},
-        },
-        domain::{
-            doctors::{
-                entities::Doctor, repository::DoctorsRepositoryFake, service::DoctorsService,
-            },
-            drugs::{
-                entities::{Drug, DrugContentType},
-                repository::DrugsRepositoryFake,
-                service::DrugsService,
-            },
-            patients::{
-                entities::Patient, repository::PatientsRepositoryFake, service::PatientsService,
-            },
-            pharmacists::{
-                entities::Pharmacist, repository::PharmacistsRepositoryFake,
-                service::PharmacistsService,
-            },
-            prescriptions::{
-                entities::Prescription, repository::PrescriptionsRepositoryFake,
-                service::PrescriptionsService,
-            },
-        },
-        Context,
-    };
-    struct DatabaseSeeds {
-        doctor: Doctor,
-        pharmacist: Pharmacist,
-        patient: Patient,
-        drugs: Vec<Drug>,
+        ApiError::build_rocket_response(req, message, status)
     }
+}
 
-    async fn setup_services_and_seed_database() -> (Context, DatabaseSeeds) {
-        let doctors_service = DoctorsService::new(Box::new(DoctorsRepositoryFake::new()));
-        let created_doctor = doctors_service
-            .create_doctor("John Doctor".into(), "92022900002".into(), "3123456".into())
+impl OpenApiResponderInner for ReassignPrescriptionPatientError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "403",
+                "Returned when the session doesn't belong to the doctor who issued the prescription",
+            ),
+            (
+                "404",
+                "Returned when the prescription or patient with given id doesn't exist",
+            ),
+            (
+                "422",
+                "Returned when the prescription_id or patient_id is not a valid UUID, or the prescription is already filled",
+            ),
+            ("415", "Returned when the Content-Type is not application/json"),
+        ])
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[patch(
+    "/prescriptions/<prescription_id>/patient",
+    format = "application/json",
+    data = "<dto>"
+)]
+pub async fn reassign_prescription_patient(
+    ctx: &Ctx,
+    session: DoctorSession,
+    prescription_id: Uuid,
+    dto: Json<ReassignPrescriptionPatientDto>,
+) -> Result<Json<Prescription>, ReassignPrescriptionPatientError> {
+    let prescription = ctx
+        .prescriptions_service
+        .reassign_prescription_patient(
+            prescription_id,
+            dto.0.patient_id,
+            session.0.doctor_id.unwrap(),
+        )
+        .await?;
+
+    Ok(Json(prescription))
+}
+
+impl<'r> Responder<'r, 'static> for CancelPrescriptionError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::NotPrescribingDoctor => (
+                "Only the prescribing doctor can cancel the prescription".to_string(),
+                Status::Forbidden,
+            ),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    CancelPrescriptionRepositoryError::PrescriptionNotFound(_) => Status::NotFound,
+                    CancelPrescriptionRepositoryError::AlreadyFilled(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    CancelPrescriptionRepositoryError::AlreadyCancelled(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    CancelPrescriptionRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    CancelPrescriptionRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CancelPrescriptionError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "403",
+                "Returned when the session doesn't belong to the doctor who issued the prescription",
+            ),
+            (
+                "404",
+                "Returned when the prescription with given id doesn't exist",
+            ),
+            (
+                "422",
+                "Returned when the prescription_id is not a valid UUID, or the prescription is already filled or cancelled",
+            ),
+        ])
+    }
+}
+
+// The prescription is kept with its cancellation date instead of being deleted
+#[openapi(tag = "Prescriptions")]
+#[post("/prescriptions/<prescription_id>/cancel")]
+pub async fn cancel_prescription(
+    ctx: &Ctx,
+    session: DoctorSession,
+    prescription_id: Uuid,
+) -> Result<Json<Prescription>, CancelPrescriptionError> {
+    let prescription = ctx
+        .prescriptions_service
+        .cancel_prescription(prescription_id, session.0.doctor_id.unwrap())
+        .await?;
+
+    Ok(Json(prescription))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionTemplateDto {
+    name: String,
+    #[schemars(
+        example = "example_prescribed_drug",
+        description = "List of tuples with drug_id and quantity"
+    )]
+    prescribed_drugs: Vec<PrescribedDrugDto>,
+}
+
+impl<'r> Responder<'r, 'static> for CreatePrescriptionTemplateError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    CreatePrescriptionTemplateRepositoryError::DoctorNotFound(_) => {
+                        Status::NotFound
+                    }
+                    CreatePrescriptionTemplateRepositoryError::DrugNotFound(_) => Status::NotFound,
+                    CreatePrescriptionTemplateRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    CreatePrescriptionTemplateRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CreatePrescriptionTemplateError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+            (
+                "422",
+                "Returned when the name is empty or too long, or the prescribed drugs are invalid",
+            ),
+            ("404", "Returned when drug with given id doesn't exist"),
+            ("415", "Returned when the Content-Type is not application/json"),
+        ],
+        )
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[post(
+    "/prescriptions/templates",
+    format = "application/json",
+    data = "<dto>"
+)]
+pub async fn create_prescription_template(
+    ctx: &Ctx,
+    session: DoctorSession,
+    dto: Json<PrescriptionTemplateDto>,
+) -> Result<Created<Json<PrescriptionTemplate>>, CreatePrescriptionTemplateError> {
+    let created_template = ctx
+        .prescriptions_service
+        .create_prescription_template(
+            session.0.doctor_id.unwrap(),
+            dto.0.name,
+            dto.0.prescribed_drugs,
+        )
+        .await?;
+
+    let location = format!("/prescriptions/templates/{}", created_template.id);
+    Ok(Created::new(location).body(Json(created_template)))
+}
+
+impl<'r> Responder<'r, 'static> for GetPrescriptionTemplatesError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetPrescriptionTemplatesRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionTemplatesRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetPrescriptionTemplatesError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![(
+                "503",
+                "Returned when the database is temporarily unavailable",
+            )],
+        )
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[get("/prescriptions/templates")]
+pub async fn get_prescription_templates(
+    ctx: &Ctx,
+    session: DoctorSession,
+) -> Result<Json<Vec<PrescriptionTemplate>>, GetPrescriptionTemplatesError> {
+    let templates = ctx
+        .prescriptions_service
+        .get_prescription_templates_for_doctor(session.0.doctor_id.unwrap())
+        .await?;
+
+    Ok(Json(templates))
+}
+
+impl<'r> Responder<'r, 'static> for UpdatePrescriptionTemplateError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::NotTemplateOwner => (
+                "Only the doctor who created the template can update it".to_string(),
+                Status::Forbidden,
+            ),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    UpdatePrescriptionTemplateRepositoryError::NotFound(_) => Status::NotFound,
+                    UpdatePrescriptionTemplateRepositoryError::DrugNotFound(_) => Status::NotFound,
+                    UpdatePrescriptionTemplateRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    UpdatePrescriptionTemplateRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for UpdatePrescriptionTemplateError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+            (
+                "403",
+                "Returned when the session doesn't belong to the doctor who created the template",
+            ),
+            (
+                "404",
+                "Returned when the template or drug with given id doesn't exist",
+            ),
+            (
+                "422",
+                "Returned when the name is empty or too long, or the prescribed drugs are invalid",
+            ),
+            ("415", "Returned when the Content-Type is not application/json"),
+        ],
+        )
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[put(
+    "/prescriptions/templates/<template_id>",
+    format = "application/json",
+    data = "<dto>"
+)]
+pub async fn update_prescription_template(
+    ctx: &Ctx,
+    session: DoctorSession,
+    template_id: Uuid,
+    dto: Json<PrescriptionTemplateDto>,
+) -> Result<Json<PrescriptionTemplate>, UpdatePrescriptionTemplateError> {
+    let template = ctx
+        .prescriptions_service
+        .update_prescription_template(
+            template_id,
+            session.0.doctor_id.unwrap(),
+            dto.0.name,
+            dto.0.prescribed_drugs,
+        )
+        .await?;
+
+    Ok(Json(template))
+}
+
+impl<'r> Responder<'r, 'static> for DeletePrescriptionTemplateError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::NotTemplateOwner => (
+                "Only the doctor who created the template can delete it".to_string(),
+                Status::Forbidden,
+            ),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    DeletePrescriptionTemplateRepositoryError::NotFound(_) => Status::NotFound,
+                    DeletePrescriptionTemplateRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    DeletePrescriptionTemplateRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for DeletePrescriptionTemplateError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+            (
+                "403",
+                "Returned when the session doesn't belong to the doctor who created the template",
+            ),
+            ("404", "Returned when the template with given id doesn't exist"),
+            ("422", "Returned when the template_id is not a valid UUID"),
+        ],
+        )
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[delete("/prescriptions/templates/<template_id>")]
+pub async fn delete_prescription_template(
+    ctx: &Ctx,
+    session: DoctorSession,
+    template_id: Uuid,
+) -> Result<NoContent, DeletePrescriptionTemplateError> {
+    ctx.prescriptions_service
+        .delete_prescription_template(template_id, session.0.doctor_id.unwrap())
+        .await?;
+
+    Ok(NoContent)
+}
+
+impl<'r> Responder<'r, 'static> for CreatePrescriptionFromTemplateError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::NotTemplateOwner => (
+                "Only the doctor who created the template can use it".to_string(),
+                Status::Forbidden,
+            ),
+            Self::TemplateRepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetPrescriptionTemplateByIdRepositoryError::NotFound(_) => Status::NotFound,
+                    GetPrescriptionTemplateByIdRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionTemplateByIdRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+            Self::CreatePrescriptionError(err) => return err.respond_to(req),
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CreatePrescriptionFromTemplateError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            (
+                "403",
+                "Returned when the session doesn't belong to the doctor who created the template",
+            ),
+            (
+                "404",
+                "Returned when the template, patient or drug with given id doesn't exist",
+            ),
+            (
+                "422",
+                "Returned when the template_id or patient_id is not a valid UUID, or the template can't be prescribed anymore",
+            ),
+        ])
+    }
+}
+
+// The prescription is issued by the doctor of the session, starting now with the default type
+#[openapi(tag = "Prescriptions")]
+#[post("/prescriptions/from-template/<template_id>?<patient_id>")]
+pub async fn create_prescription_from_template(
+    ctx: &Ctx,
+    session: DoctorSession,
+    template_id: Uuid,
+    patient_id: Uuid,
+) -> Result<Created<Json<Prescription>>, CreatePrescriptionFromTemplateError> {
+    let created_prescription = ctx
+        .prescriptions_service
+        .create_prescription_from_template(template_id, session.0.doctor_id.unwrap(), patient_id)
+        .await?;
+
+    let location = format!("/prescriptions/{}", created_prescription.id);
+    Ok(Created::new(location).body(Json(created_prescription)))
+}
+
+impl<'r> Responder<'r, 'static> for GetPrescriptionsWithPaginationError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetPrescriptionsWithPaginationError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the the page < 0, page_size < 1 or page_size > 100, created_from or created_to is not a valid RFC 3339 date or created_from is later than created_to",
+                ),
+            ],
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, FromFormField, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PrescriptionsExpand {
+    None,
+    Full,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionSummary {
+    pub id: Uuid,
+    pub code: PrescriptionCode,
+    pub prescription_type: PrescriptionType,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub filled: bool,
+}
+
+impl From<Prescription> for PrescriptionSummary {
+    fn from(prescription: Prescription) -> Self {
+        Self {
+            id: prescription.id,
+            code: prescription.code,
+            prescription_type: prescription.prescription_type,
+            start_date: prescription.start_date,
+            end_date: prescription.end_date,
+            filled: prescription.fill_status == PrescriptionFillStatus::Filled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum PrescriptionsResponse {
+    Full(Vec<Prescription>),
+    Summary(Vec<PrescriptionSummary>),
+}
+
+// expand=none returns lightweight summaries without the nested doctor, patient and drugs, any
+// other value falls back to full prescriptions. filled=true/false returns only filled/unfilled ones,
+// created_from/created_to (RFC 3339, inclusive) only ones created within the window
+#[openapi(tag = "Prescriptions")]
+#[get(
+    "/prescriptions?<page>&<page_size>&<expand>&<filled>&<created..>",
+    format = "application/json"
+)]
+pub async fn get_prescriptions_with_pagination(
+    ctx: &Ctx,
+    page: Option<i64>,
+    page_size: Option<i64>,
+    expand: Option<PrescriptionsExpand>,
+    filled: Option<bool>,
+    created: CreatedAtRange,
+) -> Result<Json<PrescriptionsResponse>, GetPrescriptionsWithPaginationError> {
+    let prescriptions = ctx
+        .prescriptions_service
+        .get_prescriptions_with_pagination(page, page_size, filled, created.from, created.to)
+        .await?;
+
+    let response = match expand {
+        Some(PrescriptionsExpand::None) => PrescriptionsResponse::Summary(
+            prescriptions
+                .into_iter()
+                .map(PrescriptionSummary::from)
+                .collect(),
+        ),
+        Some(PrescriptionsExpand::Full) | None => PrescriptionsResponse::Full(prescriptions),
+    };
+
+    Ok(Json(response))
+}
+
+// Ids of the prescriptions listed by GET /prescriptions with the same params, for clients fetching
+// the details lazily
+#[openapi(tag = "Prescriptions")]
+#[get(
+    "/prescriptions/ids?<page>&<page_size>&<filled>&<created..>",
+    format = "application/json"
+)]
+pub async fn get_prescription_ids_with_pagination(
+    ctx: &Ctx,
+    page: Option<i64>,
+    page_size: Option<i64>,
+    filled: Option<bool>,
+    created: CreatedAtRange,
+) -> Result<Json<Vec<Uuid>>, GetPrescriptionsWithPaginationError> {
+    let prescription_ids = ctx
+        .prescriptions_service
+        .get_prescription_ids_with_pagination(page, page_size, filled, created.from, created.to)
+        .await?;
+
+    Ok(Json(prescription_ids))
+}
+
+impl<'r> Responder<'r, 'static> for SearchPrescriptionsError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for SearchPrescriptionsError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when q is blank, page < 0, page_size < 1 or page_size > 100",
+                ),
+            ],
+        )
+    }
+}
+
+// Prescriptions whose patient name, doctor name or code contains q (case-insensitive), newest first
+#[openapi(tag = "Prescriptions")]
+#[get(
+    "/prescriptions/search?<q>&<page>&<page_size>",
+    format = "application/json"
+)]
+pub async fn search_prescriptions(
+    ctx: &Ctx,
+    q: &str,
+    page: Option<i64>,
+    page_size: Option<i64>,
+) -> Result<Json<Vec<Prescription>>, SearchPrescriptionsError> {
+    let prescriptions = ctx
+        .prescriptions_service
+        .search_prescriptions(q, page, page_size)
+        .await?;
+
+    Ok(Json(prescriptions))
+}
+
+// Validity of each prescription type in days, counted from the start date
+#[openapi(tag = "Prescriptions")]
+#[get("/prescriptions/policy", format = "application/json")]
+pub async fn get_prescription_policy(ctx: &Ctx) -> Json<HashMap<PrescriptionType, i64>> {
+    Json(ctx.prescriptions_service.get_prescription_durations())
+}
+
+impl<'r> Responder<'r, 'static> for GetPrescriptionsExpiringBeforeError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetPrescriptionsExpiringBeforeError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the patient_id is not a valid UUID or before is not a valid RFC 3339 date",
+                ),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[get(
+    "/patients/<patient_id>/expiring?<before>",
+    format = "application/json"
+)]
+pub async fn get_prescriptions_expiring_before(
+    ctx: &Ctx,
+    patient_id: Uuid,
+    before: DateTimeParam,
+) -> Result<Json<Vec<Prescription>>, GetPrescriptionsExpiringBeforeError> {
+    let prescriptions = ctx
+        .prescriptions_service
+        .get_prescriptions_expiring_before(patient_id, before.0)
+        .await?;
+
+    Ok(Json(prescriptions))
+}
+
+impl<'r> Responder<'r, 'static> for GetActivePrescriptionsForPatientError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetActivePrescriptionsForPatientError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                ("422", "Returned when the patient_id is not a valid UUID"),
+            ],
+        )
+    }
+}
+
+// Prescriptions the patient can fill right now, soonest to expire first
+#[openapi(tag = "Prescriptions")]
+#[get(
+    "/patients/<patient_id>/active-prescriptions",
+    format = "application/json"
+)]
+pub async fn get_active_prescriptions_for_patient(
+    ctx: &Ctx,
+    patient_id: Uuid,
+) -> Result<Json<Vec<Prescription>>, GetActivePrescriptionsForPatientError> {
+    let prescriptions = ctx
+        .prescriptions_service
+        .get_active_prescriptions_for_patient(patient_id, Utc::now())
+        .await?;
+
+    Ok(Json(prescriptions))
+}
+
+impl<'r> Responder<'r, 'static> for GetDrugHistoryForPatientError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetDrugHistoryForPatientError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![
+                (
+                    "406",
+                    "Returned when the Accept header doesn't allow application/json",
+                ),
+                (
+                    "422",
+                    "Returned when the patient_id is not a valid UUID or the page < 0 or page_size < 1",
+                ),
+            ],
+        )
+    }
+}
+
+#[openapi(tag = "Prescriptions")]
+#[get(
+    "/patients/<patient_id>/drug-history?<page>&<page_size>",
+    format = "application/json"
+)]
+pub async fn get_drug_history_for_patient(
+    ctx: &Ctx,
+    patient_id: Uuid,
+    page: Option<i64>,
+    page_size: Option<i64>,
+) -> Result<Json<Vec<DrugHistoryEntry>>, GetDrugHistoryForPatientError> {
+    let drug_history = ctx
+        .prescriptions_service
+        .get_drug_history_for_patient(patient_id, page, page_size)
+        .await?;
+
+    Ok(Json(drug_history))
+}
+
+impl<'r> Responder<'r, 'static> for CountPrescriptionsForDrugError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetDrugUsageRepositoryError::DrugNotFound(_) => Status::NotFound,
+                    GetDrugUsageRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDrugUsageRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CountPrescriptionsForDrugError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            ("404", "Returned when the drug with given id doesn't exist"),
+            (
+                "422",
+                "Returned when the drug_id is not a valid UUID, from or to is not a valid RFC 3339 date or from is later than to",
+            ),
+        ])
+    }
+}
+
+// Counts prescriptions starting within the range, which defaults to the last 30 days
+#[openapi(tag = "Prescriptions")]
+#[get("/drugs/<drug_id>/usage?<range..>")]
+pub async fn get_drug_usage(
+    ctx: &Ctx,
+    drug_id: Uuid,
+    range: DateRange,
+) -> Result<Json<DrugUsage>, CountPrescriptionsForDrugError> {
+    let drug_usage = ctx
+        .prescriptions_service
+        .count_prescriptions_for_drug(drug_id, range.from, range.to)
+        .await?;
+
+    Ok(Json(drug_usage))
+}
+
+impl<'r> Responder<'r, 'static> for GetPatientsPrescribedDrugError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetDrugUsageRepositoryError::DrugNotFound(_) => Status::NotFound,
+                    GetDrugUsageRepositoryError::DatabaseError(_) => Status::InternalServerError,
+                    GetDrugUsageRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetPatientsPrescribedDrugError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            ("404", "Returned when the drug with given id doesn't exist"),
+            (
+                "422",
+                "Returned when the drug_id is not a valid UUID, from or to is not a valid RFC 3339 date or from is later than to",
+            ),
+        ])
+    }
+}
+
+// Patients to contact when the drug is recalled, those with prescriptions starting within the
+// range, which defaults to the last 30 days. Pharmacists only, as the PESEL numbers aren't masked
+#[openapi(tag = "Prescriptions")]
+#[get("/drugs/<drug_id>/affected-patients?<range..>")]
+pub async fn get_patients_prescribed_drug(
+    ctx: &Ctx,
+    _session: PharmacistSession,
+    drug_id: Uuid,
+    range: DateRange,
+) -> Result<Json<Vec<AffectedPatient>>, GetPatientsPrescribedDrugError> {
+    let affected_patients = ctx
+        .prescriptions_service
+        .get_patients_prescribed_drug(drug_id, range.from, range.to)
+        .await?;
+
+    Ok(Json(affected_patients))
+}
+
+impl<'r> Responder<'r, 'static> for CountFillsByPharmacistError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::DomainError(message) => (message, Status::UnprocessableEntity),
+            Self::RepositoryError(err) => {
+                let message = err.to_string();
+                let status = match err {
+                    GetPharmacistFillStatsRepositoryError::PharmacistNotFound(_) => {
+                        Status::NotFound
+                    }
+                    GetPharmacistFillStatsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPharmacistFillStatsRepositoryError::Unavailable => {
+                        Status::ServiceUnavailable
+                    }
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CountFillsByPharmacistError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(gen, vec![
+            ("404", "Returned when the pharmacist with given id doesn't exist"),
+            (
+                "422",
+                "Returned when the pharmacist_id is not a valid UUID, from or to is not a valid RFC 3339 date, from is later than to or the range spans more than 366 days",
+            ),
+        ])
+    }
+}
+
+// Fills made by the pharmacist per UTC day within the range, which defaults to the last 30 days.
+// Every day of the range is listed, days without fills with a zero count
+#[openapi(tag = "Prescriptions")]
+#[get("/pharmacists/<pharmacist_id>/fill-stats?<range..>")]
+pub async fn get_pharmacist_fill_stats(
+    ctx: &Ctx,
+    pharmacist_id: Uuid,
+    range: DateRange,
+) -> Result<Json<PharmacistFillStats>, CountFillsByPharmacistError> {
+    let fill_stats = ctx
+        .prescriptions_service
+        .count_fills_by_pharmacist_per_day(pharmacist_id, range.from, range.to)
+        .await?;
+
+    Ok(Json(fill_stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use chrono::{DateTime, Duration, SecondsFormat, Utc};
+    use rocket::{
+        http::{ContentType, Header, Status},
+        local::asynchronous::Client,
+        routes,
+        serde::json::{self, Value},
+        tokio::{io::AsyncReadExt, time::timeout},
+    };
+    use uuid::Uuid;
+
+    use crate::{
+        application::{
+            api::utils::{api_config::ApiConfig, error::RETRY_AFTER_SECONDS},
+            authentication::{
+                repository::AuthenticationRepositoryFake, service::AuthenticationService,
+            },
+            sessions::{
+                repository::SessionsRepositoryFake, service::SessionsService,
+                token_codec::UuidTokenCodec,
+            },
+        },
+        domain::{
+            doctors::{
+                entities::Doctor, repository::DoctorsRepositoryFake, service::DoctorsService,
+            },
+            drugs::{
+                entities::{Drug, DrugContentType},
+                repository::DrugsRepositoryFake,
+                service::DrugsService,
+            },
+            patients::{
+                entities::Patient, repository::PatientsRepositoryFake, service::PatientsService,
+            },
+            pharmacists::{
+                entities::Pharmacist, repository::PharmacistsRepositoryFake,
+                service::PharmacistsService,
+            },
+            prescriptions::{
+                entities::{
+                    AffectedPatient, DrugHistoryEntry, DrugLabel, DrugUsage, PharmacistFillStats,
+                    Prescription, PrescriptionFillStatus, PrescriptionTemplate, PrescriptionType,
+                    PrescriptionVerification, PrescriptionVerificationStatus,
+                },
+                policy::PrescriptionPolicy,
+                repository::PrescriptionsRepositoryFake,
+                service::PrescriptionsService,
+            },
+        },
+        Context,
+    };
+    struct DatabaseSeeds {
+        doctor: Doctor,
+        pharmacist: Pharmacist,
+        patient: Patient,
+        other_patient: Patient,
+        drugs: Vec<Drug>,
+    }
+
+    async fn setup_services_and_seed_database() -> (Context, DatabaseSeeds) {
+        let doctors_service = DoctorsService::new(Box::new(DoctorsRepositoryFake::new()));
+        let created_doctor = doctors_service
+            .create_doctor(
+                "John Doctor".into(),
+                "92022900002".into(),
+                "3123456".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let pharmacist_service =
+            PharmacistsService::new(Box::new(PharmacistsRepositoryFake::new()));
+        let created_pharmacist = pharmacist_service
+            .create_pharmacist("John Pharmacist".into(), "92022900002".into())
+            .await
+            .unwrap();
+
+        let patients_service = PatientsService::new(Box::new(PatientsRepositoryFake::new()));
+        let created_patient = patients_service
+            .create_patient("John Patient".into(), "92022900002".into(), None)
+            .await
+            .unwrap();
+        let created_other_patient = patients_service
+            .create_patient("Jane Patient".into(), "96021807250".into(), None)
+            .await
+            .unwrap();
+
+        let drugs_service = DrugsService::new(Box::new(DrugsRepositoryFake::new()));
+        let created_drug_0 = drugs_service
+            .create_drug(
+                "Gripex".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        let created_drug_1 = drugs_service
+            .create_drug(
+                "Apap".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        let created_drug_2 = drugs_service
+            .create_drug(
+                "Flegamax".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        let created_drug_3 = drugs_service
+            .create_drug(
+                "Rutinoscorbin".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let prescriptions_service = PrescriptionsService::new(
+            Box::new(PrescriptionsRepositoryFake::new(
+                None,
+                Some(vec![created_doctor.clone()]),
+                Some(vec![created_patient.clone(), created_other_patient.clone()]),
+                Some(vec![created_pharmacist.clone()]),
+                Some(vec![
+                    created_drug_0.clone(),
+                    created_drug_1.clone(),
+                    created_drug_2.clone(),
+                    created_drug_3.clone(),
+                ]),
+            )),
+            // Some tests backdate prescriptions to check their expiration
+            PrescriptionPolicy {
+                max_start_date_in_past: Duration::days(90),
+                ..Default::default()
+            },
+        );
+
+        let authentication_repository = Box::new(AuthenticationRepositoryFake::new());
+        let authentication_service =
+            Arc::new(AuthenticationService::new(authentication_repository));
+
+        let sessions_repository = Box::new(SessionsRepositoryFake::new());
+        let sessions_service = Arc::new(SessionsService::new(
+            sessions_repository,
+            Box::new(UuidTokenCodec),
+        ));
+
+        (
+            Context {
+                doctors_service: Arc::new(doctors_service),
+                pharmacists_service: Arc::new(pharmacist_service),
+                patients_service: Arc::new(patients_service),
+                drugs_service: Arc::new(drugs_service),
+                prescriptions_service: Arc::new(prescriptions_service),
+                authentication_service,
+                sessions_service,
+            },
+            DatabaseSeeds {
+                doctor: created_doctor,
+                pharmacist: created_pharmacist,
+                patient: created_patient,
+                other_patient: created_other_patient,
+                drugs: vec![
+                    created_drug_0,
+                    created_drug_1,
+                    created_drug_2,
+                    created_drug_3,
+                ],
+            },
+        )
+    }
+
+    async fn create_api_client() -> (Client, DatabaseSeeds) {
+        let (context, seeds) = setup_services_and_seed_database().await;
+
+        let routes = routes![
+            super::create_prescription,
+            super::get_prescription_by_id,
+            super::get_prescription_pdf,
+            super::get_prescription_qr_code,
+            super::get_prescription_labels,
+            super::get_prescriptions_with_pagination,
+            super::fill_prescription,
+            super::fill_prescriptions_batch,
+            super::get_prescriptions_expiring_before,
+            super::get_active_prescriptions_for_patient,
+            super::get_drug_history_for_patient,
+            super::get_drug_usage,
+            super::get_pharmacist_fill_stats,
+            super::get_prescription_ids_with_pagination,
+            super::search_prescriptions,
+            super::get_prescription_policy,
+            super::verify_prescription
+        ];
+
+        let rocket = rocket::build().manage(context).mount("/", routes);
+
+        let client = Client::tracked(rocket).await.unwrap();
+
+        (client, seeds)
+    }
+
+    async fn create_api_client_with_config(config: ApiConfig) -> (Client, DatabaseSeeds) {
+        let (context, seeds) = setup_services_and_seed_database().await;
+
+        let routes = routes![super::create_prescription, super::get_prescriptions_batch];
+        let rocket = rocket::build()
+            .manage(context)
+            .manage(config)
+            .mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        (client, seeds)
+    }
+
+    async fn create_api_client_with_pharmacist_session() -> (Client, DatabaseSeeds, String) {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                None,
+                Some(seeds.pharmacist.id),
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Pharmacy terminal".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+
+        let routes = routes![
+            super::create_prescription,
+            super::get_prescription_by_id,
+            super::fill_prescription,
+            super::fill_prescriptions_batch,
+            super::verify_prescription,
+            super::get_patients_prescribed_drug
+        ];
+        let rocket = rocket::build().manage(context).mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        (client, seeds, token)
+    }
+
+    async fn create_api_client_with_doctor_session() -> (Client, DatabaseSeeds, String) {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                Some(seeds.doctor.id),
+                None,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+
+        let routes = routes![
+            super::create_prescription,
+            super::fill_prescription,
+            super::reassign_prescription_patient,
+            super::cancel_prescription,
+            super::create_prescription_template,
+            super::get_prescription_templates,
+            super::update_prescription_template,
+            super::delete_prescription_template,
+            super::create_prescription_from_template
+        ];
+        let rocket = rocket::build().manage(context).mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        (client, seeds, token)
+    }
+
+    #[tokio::test]
+    async fn streams_created_prescriptions_to_pharmacists() {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                None,
+                Some(seeds.pharmacist.id),
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Dashboard".into(),
+            )
+            .await
+            .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+
+        let routes = routes![super::create_prescription, super::get_prescription_events];
+        let rocket = rocket::build().manage(context).mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let mut events_response = client
+            .get("/prescriptions/events")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(events_response.status(), Status::Ok);
+        assert_eq!(
+            events_response.content_type(),
+            Some(ContentType::EventStream)
+        );
+
+        let created_prescription =
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let mut event = String::new();
+        let mut buffer = [0; 1024];
+        while !event.ends_with("\n\n") {
+            let read_count = timeout(
+                std::time::Duration::from_secs(5),
+                events_response.read(&mut buffer),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            event.push_str(std::str::from_utf8(&buffer[..read_count]).unwrap());
+        }
+
+        assert!(event.contains("event:prescription_created"));
+        assert!(event.contains(&created_prescription.id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn prescription_events_require_pharmacist_session() {
+        let (context, _) = setup_services_and_seed_database().await;
+        let rocket = rocket::build()
+            .manage(context)
+            .mount("/", routes![super::get_prescription_events]);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let response = client.get("/prescriptions/events").dispatch().await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[tokio::test]
+    async fn creates_and_fills_prescription() {
+        let (client, seeds) = create_api_client().await;
+
+        let create_prescription_response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
+                    "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(create_prescription_response.status(), Status::Created);
+
+        let created_prescription = json::from_str::<Prescription>(
+            &create_prescription_response.into_string().await.unwrap(),
+        )
+        .unwrap();
+
+        assert!(created_prescription.fills.is_empty());
+
+        let fill_prescription_response = client
+            .post(format!("/prescriptions/{}/fill", created_prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "pharmacist_id": "{}",
+                    "prescription_code": "{}"
+                }}"#,
+                seeds.pharmacist.id,
+                created_prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_prescription_response.status(), Status::Created);
+
+        json::from_str::<Prescription>(&fill_prescription_response.into_string().await.unwrap())
+            .unwrap();
+
+        let get_prescription_by_id_response = client
+            .get(format!("/prescriptions/{}", created_prescription.id))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(get_prescription_by_id_response.status(), Status::Ok);
+
+        let prescription_by_id: Prescription =
+            json::from_str(&get_prescription_by_id_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(
+            prescription_by_id.fill_status,
+            PrescriptionFillStatus::Filled
+        );
+    }
+
+    #[tokio::test]
+    async fn creates_prescription_with_diagnoses() {
+        let (client, seeds) = create_api_client().await;
+
+        let create_prescription_response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  1] ],
+                    "diagnoses": [ "J06.9", "R50" ]
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(create_prescription_response.status(), Status::Created);
+
+        let created_prescription = json::from_str::<Prescription>(
+            &create_prescription_response.into_string().await.unwrap(),
+        )
+        .unwrap();
+
+        let get_prescription_by_id_response = client
+            .get(format!("/prescriptions/{}", created_prescription.id))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(get_prescription_by_id_response.status(), Status::Ok);
+
+        let prescription_by_id: Prescription =
+            json::from_str(&get_prescription_by_id_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescription_by_id.diagnoses, vec!["J06.9", "R50"]);
+    }
+
+    #[tokio::test]
+    async fn restarts_window_of_prescription_at_first_fill() {
+        let (client, seeds) = create_api_client().await;
+
+        let create_prescription_response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  1] ],
+                    "window_from_fill": true
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(create_prescription_response.status(), Status::Created);
+
+        let created_prescription = json::from_str::<Prescription>(
+            &create_prescription_response.into_string().await.unwrap(),
+        )
+        .unwrap();
+
+        assert!(created_prescription.window_from_fill);
+
+        let fill_prescription_response = client
+            .post(format!("/prescriptions/{}/fill", created_prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "pharmacist_id": "{}",
+                    "prescription_code": "{}"
+                }}"#,
+                seeds.pharmacist.id,
+                created_prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_prescription_response.status(), Status::Created);
+
+        let filled_prescription = json::from_str::<Prescription>(
+            &fill_prescription_response.into_string().await.unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            filled_prescription.end_date,
+            filled_prescription.fills[0].created_at
+                + (created_prescription.end_date - created_prescription.start_date)
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_validated_prescription_without_saving_it_on_dry_run() {
+        let (client, seeds) = create_api_client().await;
+
+        let response = client
+            .post("/prescriptions?dry_run=true")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  2] ],
+                    "diagnoses": [ "J06.9" ]
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let prescription: Prescription = json::from_value(body.clone()).unwrap();
+
+        assert_eq!(body["dry_run"], true);
+        assert_eq!(prescription.doctor.id, seeds.doctor.id);
+        assert_eq!(prescription.patient.id, seeds.patient.id);
+        assert_eq!(prescription.prescribed_drugs[0].quantity, 2);
+        assert_eq!(prescription.diagnoses, vec!["J06.9"]);
+
+        let get_prescription_by_id_response = client
+            .get(format!("/prescriptions/{}", prescription.id))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(get_prescription_by_id_response.status(), Status::NotFound);
+
+        let response = client
+            .post("/prescriptions?dry_run=true")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  2] ]
+                }}"#,
+                seeds.doctor.id,
+                seeds.patient.id,
+                Uuid::new_v4()
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_prescription_with_malformed_diagnosis_code() {
+        let (client, seeds) = create_api_client().await;
+
+        let response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  1] ],
+                    "diagnoses": [ "J06.9", "flu" ]
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(error["message"], "ICD-10 code flu has invalid format");
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_prescription_if_body_has_unknown_keys() {
+        let (client, seeds) = create_api_client().await;
+
+        let response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  1] ],
+                    "refills": 2
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn fills_prescription_partially_and_then_dispenses_the_remainder() {
+        let (client, seeds) = create_api_client().await;
+        let response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescribed_drugs": [ ["{}",  3] ]
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+        let prescription: Prescription =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let fill = |dispensed_drugs: String| {
+            client
+                .post(format!("/prescriptions/{}/fill", prescription.id))
+                .header(ContentType::JSON)
+                .body(format!(
+                    r#"{{
+                        "pharmacist_id": "{}",
+                        "prescription_code": "{}"
+                        {}
+                    }}"#,
+                    seeds.pharmacist.id, prescription.code, dispensed_drugs
+                ))
+                .dispatch()
+        };
+
+        let response = fill(format!(
+            r#", "dispensed_drugs": [ ["{}", 1] ]"#,
+            seeds.drugs[0].id
+        ))
+        .await;
+
+        assert_eq!(response.status(), Status::Created);
+        let partially_filled_prescription: Prescription =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(
+            partially_filled_prescription.fill_status,
+            PrescriptionFillStatus::PartiallyFilled
+        );
+
+        let response = fill(format!(
+            r#", "dispensed_drugs": [ ["{}", 3] ]"#,
+            seeds.drugs[0].id
+        ))
+        .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = fill(String::new()).await;
+
+        assert_eq!(response.status(), Status::Created);
+        let filled_prescription: Prescription =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(
+            filled_prescription.fill_status,
+            PrescriptionFillStatus::Filled
+        );
+        assert_eq!(
+            filled_prescription.prescribed_drugs[0].dispensed_quantity,
+            3
+        );
+        assert_eq!(filled_prescription.fills.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn doesnt_fill_if_already_filled() {
+        let (client, seeds) = create_api_client().await;
+        let create_seed_prescription_response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
+                    "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
+                }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+            ))
+            .dispatch()
+            .await;
+        let seed_prescription: Prescription = json::from_str(
+            &create_seed_prescription_response
+                .into_string()
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            client
+                .post(format!("/prescriptions/{}/fill", seed_prescription.id))
+                .header(ContentType::JSON)
+                .body(format!(
+                    r#"{{
+                        "pharmacist_id": "{}",
+                        "prescription_code": "{}"
+                    }}"#,
+                    seeds.pharmacist.id, seed_prescription.code,
+                ))
+                .dispatch()
+                .await
+                .status(),
+            Status::Created
+        );
+
+        assert_eq!(
+            client
+                .post(format!("/prescriptions/{}/fill", seed_prescription.id))
+                .header(ContentType::JSON)
+                .body(format!(
+                    r#"{{
+                        "pharmacist_id": "{}",
+                        "prescription_code": "{}"
+                    }}"#,
+                    seeds.pharmacist.id, seed_prescription.code,
+                ))
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+    }
+
+    #[tokio::test]
+    async fn fills_prescriptions_in_batch() {
+        let (client, seeds, token) = create_api_client_with_pharmacist_session().await;
+        let prescriptions = vec![
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await,
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await,
+        ];
+
+        let response = client
+            .post("/prescriptions/fill-batch")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(
+                r#"[
+                    {{ "prescription_id": "{}", "prescription_code": "{}" }},
+                    {{ "prescription_id": "{}", "prescription_code": "{}" }}
+                ]"#,
+                prescriptions[0].id,
+                prescriptions[0].code,
+                prescriptions[1].id,
+                prescriptions[1].code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        let filled_prescriptions: Vec<Prescription> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(filled_prescriptions.len(), 2);
+        for (filled_prescription, prescription) in filled_prescriptions.iter().zip(&prescriptions) {
+            assert_eq!(filled_prescription.id, prescription.id);
+            assert_eq!(
+                filled_prescription.fills[0].pharmacist_id,
+                seeds.pharmacist.id
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn doesnt_fill_any_prescription_in_batch_if_one_code_is_wrong() {
+        let (client, seeds, token) = create_api_client_with_pharmacist_session().await;
+        let prescriptions = vec![
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await,
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await,
+        ];
+
+        let response = client
+            .post("/prescriptions/fill-batch")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(
+                r#"[
+                    {{ "prescription_id": "{}", "prescription_code": "{}" }},
+                    {{ "prescription_id": "{}", "prescription_code": "00000000" }}
+                ]"#,
+                prescriptions[0].id, prescriptions[0].code, prescriptions[1].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(error.to_string().contains("Prescription at index 1"));
+
+        for prescription in &prescriptions {
+            let response = client
+                .get(format!("/prescriptions/{}", prescription.id))
+                .dispatch()
+                .await;
+            let prescription: Prescription =
+                json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+            assert!(prescription.fills.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_service_unavailable_if_database_is_unavailable_when_filling_in_batch() {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let context = Context {
+            prescriptions_service: Arc::new(PrescriptionsService::new(
+                Box::new(
+                    PrescriptionsRepositoryFake::new(None, None, None, None, None).unavailable(),
+                ),
+                PrescriptionPolicy::default(),
+            )),
+            ..context
+        };
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                None,
+                Some(seeds.pharmacist.id),
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Pharmacy terminal".into(),
+            )
             .await
             .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+        let rocket = rocket::build()
+            .manage(context)
+            .mount("/", routes![super::fill_prescriptions_batch]);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let response = client
+            .post("/prescriptions/fill-batch")
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(
+                r#"[{{ "prescription_id": "{}", "prescription_code": "12345678" }}]"#,
+                Uuid::new_v4()
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        assert_eq!(
+            response.headers().get_one("Retry-After"),
+            Some(RETRY_AFTER_SECONDS.to_string().as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn fill_prescriptions_batch_requires_pharmacist_session() {
+        let (client, _) = create_api_client().await;
+
+        let response = client
+            .post("/prescriptions/fill-batch")
+            .header(ContentType::JSON)
+            .body("[]")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[tokio::test]
+    async fn returns_error_if_prescription_does_not_exist() {
+        let (client, _) = create_api_client().await;
+
+        let get_prescription_by_id_response = client
+            .get("/prescriptions/00000000-0000-0000-0000-000000000000")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(get_prescription_by_id_response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn gets_prescription_as_pdf() {
+        let (client, seeds) = create_api_client().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let response = client
+            .get(format!("/prescriptions/{}.pdf", prescription.id))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::PDF));
+
+        let pdf = response.into_bytes().await.unwrap();
+
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(pdf
+            .windows(b"(1. Gripex x 1)".len())
+            .any(|window| window == b"(1. Gripex x 1)"));
+    }
+
+    #[tokio::test]
+    async fn get_prescription_pdf_returns_not_found_if_prescription_does_not_exist() {
+        let (client, _) = create_api_client().await;
+
+        let response = client
+            .get("/prescriptions/00000000-0000-0000-0000-000000000000.pdf")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client.get("/prescriptions/not-a-uuid.pdf").dispatch().await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn gets_prescription_qr_code() {
+        let (client, seeds) = create_api_client().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let response = client
+            .get(format!("/prescriptions/{}/qr.png", prescription.id))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::PNG));
+
+        let png = response.into_bytes().await.unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.len() > 8);
+    }
+
+    #[tokio::test]
+    async fn get_prescription_qr_code_returns_not_found_if_prescription_does_not_exist() {
+        let (client, _) = create_api_client().await;
+
+        let response = client
+            .get("/prescriptions/00000000-0000-0000-0000-000000000000/qr.png")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn gets_prescription_labels() {
+        let (client, seeds) = create_api_client().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let response = client
+            .get(format!("/prescriptions/{}/labels", prescription.id))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let labels: Vec<DrugLabel> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].drug_id, seeds.drugs[0].id);
+        assert_eq!(labels[0].drug_name, seeds.drugs[0].name);
+        assert_eq!(labels[0].quantity, 1);
+        assert_eq!(labels[0].patient_name, prescription.patient.name);
+        assert!(labels[0].text.starts_with(&seeds.drugs[0].name));
+
+        let response = client
+            .get("/prescriptions/00000000-0000-0000-0000-000000000000/labels")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[tokio::test]
+    async fn gets_pharmacists_with_pagination() {
+        let (client, seeds) = create_api_client().await;
+
+        client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                "doctor_id": "{}",
+                "patient_id": "{}",
+                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
+                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
+            }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+            ))
+            .dispatch()
+            .await;
+        client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                "doctor_id": "{}",
+                "patient_id": "{}",
+                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
+                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
+            }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+            ))
+            .dispatch()
+            .await;
+        client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                "doctor_id": "{}",
+                "patient_id": "{}",
+                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
+                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
+            }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+            ))
+            .dispatch()
+            .await;
+        client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                "doctor_id": "{}",
+                "patient_id": "{}",
+                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
+                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
+            }}"#,
+                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+            ))
+            .dispatch()
+            .await;
+
+        let prescriptions_response = client
+            .get("/prescriptions?page_size=2&page=1")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 2);
+
+        let prescriptions_response = client
+            .get("/prescriptions?page_size=3&page=1")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+
+        let prescriptions_response = client
+            .get("/prescriptions?page_size=10")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 4);
+
+        let prescriptions_response = client
+            .get("/prescriptions?page=1")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 0);
+
+        let prescriptions_response = client
+            .get("/prescriptions")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 4);
+
+        let prescriptions_response = client
+            .get("/prescriptions?page_size=3&page=2")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn gets_prescription_summaries_if_expand_is_none() {
+        let (client, seeds) = create_api_client().await;
+        let created_prescription =
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let response = client
+            .get("/prescriptions?expand=none")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions: Value = json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let summary = prescriptions[0].as_object().unwrap();
+
+        assert_eq!(prescriptions.as_array().unwrap().len(), 1);
+        assert_eq!(summary["id"], created_prescription.id.to_string());
+        assert_eq!(summary["code"], created_prescription.code.as_str());
+        assert_eq!(summary["prescription_type"], "REGULAR");
+        assert_eq!(summary["filled"], false);
+        assert!(!summary.contains_key("doctor"));
+        assert!(!summary.contains_key("patient"));
+        assert!(!summary.contains_key("prescribed_drugs"));
+    }
+
+    async fn get_prescription_ids(client: &Client, uri: &str) -> Vec<Uuid> {
+        let response = client.get(uri).header(ContentType::JSON).dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        prescriptions
+            .into_iter()
+            .map(|prescription| prescription.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn filters_prescriptions_by_filled_status() {
+        let (client, seeds) = create_api_client().await;
+        let filled_prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        let unfilled_prescriptions = [
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await,
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await,
+        ];
+        let fill_response = client
+            .post(format!("/prescriptions/{}/fill", filled_prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                seeds.pharmacist.id, filled_prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::Created);
+
+        assert_eq!(
+            get_prescription_ids(&client, "/prescriptions?filled=true").await,
+            vec![filled_prescription.id]
+        );
+        assert_eq!(
+            get_prescription_ids(&client, "/prescriptions?filled=false").await,
+            unfilled_prescriptions
+                .iter()
+                .map(|prescription| prescription.id)
+                .collect::<Vec<Uuid>>()
+        );
+        assert_eq!(
+            get_prescription_ids(&client, "/prescriptions?filled=false&page_size=1&page=1").await,
+            vec![unfilled_prescriptions[1].id]
+        );
+        assert_eq!(
+            get_prescription_ids(&client, "/prescriptions").await.len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_prescriptions_by_created_at_window() {
+        let (client, seeds) = create_api_client().await;
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            prescriptions.push(create_prescription(&client, &seeds, Utc::now(), "REGULAR").await);
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        let created_at = |i: usize| {
+            prescriptions[i]
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::Nanos, true)
+        };
+
+        assert_eq!(
+            get_prescription_ids(
+                &client,
+                &format!(
+                    "/prescriptions?created_from={}&created_to={}",
+                    created_at(1),
+                    created_at(2)
+                )
+            )
+            .await,
+            vec![prescriptions[1].id, prescriptions[2].id]
+        );
+        assert_eq!(
+            get_prescription_ids(
+                &client,
+                &format!("/prescriptions?created_to={}", created_at(0))
+            )
+            .await,
+            vec![prescriptions[0].id]
+        );
+
+        let response = client
+            .get(format!("/prescriptions/ids?created_from={}", created_at(2)))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.into_json::<Vec<Uuid>>().await.unwrap(),
+            vec![prescriptions[2].id]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_prescriptions_returns_unprocessable_entity_if_created_window_is_invalid() {
+        let (client, _) = create_api_client().await;
+
+        for uri in ["/prescriptions", "/prescriptions/ids"] {
+            for query in [
+                "?created_from=2024-03-02T00:00:00Z&created_to=2024-03-01T00:00:00Z",
+                "?created_from=yesterday",
+            ] {
+                let response = client
+                    .get(format!("{}{}", uri, query))
+                    .header(ContentType::JSON)
+                    .dispatch()
+                    .await;
+
+                assert_eq!(response.status(), Status::UnprocessableEntity);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn searches_prescriptions_by_partial_patient_name_and_code() {
+        let (client, seeds) = create_api_client().await;
+        let first_prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        let second_prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let response = client
+            .get("/prescriptions/search?q=john%20pat")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 2);
+
+        let response = client
+            .get(format!(
+                "/prescriptions/search?q={}",
+                second_prescription.code
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, second_prescription.id);
+        assert_ne!(prescriptions[0].id, first_prescription.id);
+
+        let response = client
+            .get("/prescriptions/search?q=Jane")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "[]");
+
+        let response = client
+            .get("/prescriptions/search?q=%20")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn gets_prescription_ids_matching_full_listing() {
+        let (client, seeds) = create_api_client().await;
+        let filled_prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        for _ in 0..3 {
+            create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        }
+        let fill_response = client
+            .post(format!("/prescriptions/{}/fill", filled_prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                seeds.pharmacist.id, filled_prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::Created);
+
+        for query in [
+            "",
+            "?filled=true",
+            "?filled=false",
+            "?page=1&page_size=2",
+            "?filled=false&page=1&page_size=2",
+        ] {
+            let response = client
+                .get(format!("/prescriptions/ids{}", query))
+                .header(ContentType::JSON)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+
+            let prescription_ids = response.into_json::<Vec<Uuid>>().await.unwrap();
+
+            assert_eq!(
+                prescription_ids,
+                get_prescription_ids(&client, &format!("/prescriptions{}", query)).await
+            );
+        }
+
+        let response = client
+            .get("/prescriptions/ids?page_size=0")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn gets_duration_of_each_prescription_type_in_days() {
+        let (client, _) = create_api_client().await;
+
+        let response = client
+            .get("/prescriptions/policy")
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response
+                .into_json::<HashMap<PrescriptionType, i64>>()
+                .await
+                .unwrap(),
+            HashMap::from([
+                (PrescriptionType::Regular, 30),
+                (PrescriptionType::ForAntibiotics, 7),
+                (PrescriptionType::ForImmunologicalDrugs, 120),
+                (PrescriptionType::ForChronicDiseaseDrugs, 365),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn gets_full_prescriptions_by_default() {
+        let (client, seeds) = create_api_client().await;
+        create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        for uri in ["/prescriptions", "/prescriptions?expand=full"] {
+            let response = client.get(uri).header(ContentType::JSON).dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+
+            let prescriptions: Vec<Prescription> =
+                json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+            assert_eq!(prescriptions.len(), 1);
+            assert_eq!(prescriptions[0].doctor.id, seeds.doctor.id);
+            assert_eq!(prescriptions[0].prescribed_drugs.len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn get_pharmacists_with_pagination_returns_error_if_params_are_invalid() {
+        let (client, _) = create_api_client().await;
+
+        assert_eq!(
+            client
+                .get("/prescriptions?page=-1")
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+
+        assert_eq!(
+            client
+                .get("/prescriptions?page_size=0")
+                .dispatch()
+                .await
+                .status(),
+            Status::UnprocessableEntity
+        );
+    }
+
+    async fn create_prescription(
+        client: &Client,
+        seeds: &DatabaseSeeds,
+        start_date: DateTime<Utc>,
+        prescription_type: &str,
+    ) -> Prescription {
+        let response = client
+            .post("/prescriptions")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "doctor_id": "{}",
+                    "patient_id": "{}",
+                    "start_date": "{}",
+                    "prescription_type": "{}",
+                    "prescribed_drugs": [ ["{}",  1] ]
+                }}"#,
+                seeds.doctor.id,
+                seeds.patient.id,
+                start_date.to_rfc3339(),
+                prescription_type,
+                seeds.drugs[0].id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Created);
+
+        json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn gets_unfilled_prescriptions_expiring_before_given_date() {
+        let (client, seeds) = create_api_client().await;
+        let now = Utc::now();
+
+        create_prescription(&client, &seeds, now - Duration::days(60), "REGULAR").await;
+        let expiring_prescription =
+            create_prescription(&client, &seeds, now - Duration::days(25), "REGULAR").await;
+        let filled_expiring_prescription =
+            create_prescription(&client, &seeds, now - Duration::days(26), "REGULAR").await;
+        create_prescription(&client, &seeds, now, "FOR_CHRONIC_DISEASE_DRUGS").await;
+
+        let fill_response = client
+            .post(format!(
+                "/prescriptions/{}/fill",
+                filled_expiring_prescription.id
+            ))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "pharmacist_id": "{}",
+                    "prescription_code": "{}"
+                }}"#,
+                seeds.pharmacist.id, filled_expiring_prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::Created);
+
+        let before = (now + Duration::days(7)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let response = client
+            .get(format!(
+                "/patients/{}/expiring?before={}",
+                seeds.patient.id, before
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, expiring_prescription.id);
+    }
+
+    #[tokio::test]
+    async fn get_prescriptions_expiring_before_returns_unprocessable_entity_if_date_is_invalid() {
+        let (client, seeds) = create_api_client().await;
+
+        let response = client
+            .get(format!(
+                "/patients/{}/expiring?before=next-week",
+                seeds.patient.id
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn gets_active_unfilled_prescriptions_for_patient() {
+        let (client, seeds) = create_api_client().await;
+        let now = Utc::now();
+
+        create_prescription(&client, &seeds, now + Duration::days(2), "REGULAR").await;
+        let active_prescription =
+            create_prescription(&client, &seeds, now - Duration::days(2), "REGULAR").await;
+        create_prescription(&client, &seeds, now - Duration::days(60), "REGULAR").await;
+        let filled_prescription =
+            create_prescription(&client, &seeds, now - Duration::days(1), "REGULAR").await;
+
+        let fill_response = client
+            .post(format!("/prescriptions/{}/fill", filled_prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{
+                    "pharmacist_id": "{}",
+                    "prescription_code": "{}"
+                }}"#,
+                seeds.pharmacist.id, filled_prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::Created);
+
+        let response = client
+            .get(format!(
+                "/patients/{}/active-prescriptions",
+                seeds.patient.id
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions: Vec<Prescription> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, active_prescription.id);
+    }
+
+    #[tokio::test]
+    async fn gets_drug_history_for_patient_ordered_by_date() {
+        let (client, seeds) = create_api_client().await;
+        let now = Utc::now();
+        let older_prescription =
+            create_prescription(&client, &seeds, now - Duration::days(30), "REGULAR").await;
+        let newer_prescription =
+            create_prescription(&client, &seeds, now - Duration::days(2), "REGULAR").await;
+        let newest_prescription = create_prescription(&client, &seeds, now, "REGULAR").await;
+
+        let response = client
+            .get(format!("/patients/{}/drug-history", seeds.patient.id))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let drug_history: Vec<DrugHistoryEntry> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let prescription_codes: Vec<&str> = drug_history
+            .iter()
+            .map(|entry| entry.prescription_code.as_str())
+            .collect();
+
+        assert_eq!(
+            prescription_codes,
+            vec![
+                newest_prescription.code.as_str(),
+                newer_prescription.code.as_str(),
+                older_prescription.code.as_str()
+            ]
+        );
+        assert!(drug_history
+            .iter()
+            .all(|entry| entry.drug_id == seeds.drugs[0].id && entry.quantity == 1));
+
+        let response = client
+            .get(format!(
+                "/patients/{}/drug-history?page=0&page_size=0",
+                seeds.patient.id
+            ))
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    async fn get_drug_usage(client: &Client, uri: &str) -> DrugUsage {
+        let response = client.get(uri).dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        response.into_json::<DrugUsage>().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn gets_drug_usage_within_range() {
+        let (client, seeds) = create_api_client().await;
+        let now = Utc::now();
+        create_prescription(&client, &seeds, now - Duration::days(20), "REGULAR").await;
+        create_prescription(&client, &seeds, now - Duration::days(5), "REGULAR").await;
+        let drug_usage =
+            get_drug_usage(&client, &format!("/drugs/{}/usage", seeds.drugs[0].id)).await;
+
+        assert_eq!(drug_usage.prescriptions_count, 2);
+        assert_eq!(drug_usage.total_quantity, 2);
+
+        let from = (now - Duration::days(10)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let drug_usage = get_drug_usage(
+            &client,
+            &format!("/drugs/{}/usage?from={}", seeds.drugs[0].id, from),
+        )
+        .await;
+
+        assert_eq!(drug_usage.prescriptions_count, 1);
+        assert_eq!(drug_usage.total_quantity, 1);
+
+        let drug_usage =
+            get_drug_usage(&client, &format!("/drugs/{}/usage", seeds.drugs[1].id)).await;
+
+        assert_eq!(drug_usage.prescriptions_count, 0);
+        assert_eq!(drug_usage.total_quantity, 0);
 
-        let pharmacist_service =
-            PharmacistsService::new(Box::new(PharmacistsRepositoryFake::new()));
-        let created_pharmacist = pharmacist_service
-            .create_pharmacist("John Pharmacist".into(), "92022900002".into())
-            .await
-            .unwrap();
+        let response = client
+            .get(format!("/drugs/{}/usage", Uuid::new_v4()))
+            .dispatch()
+            .await;
 
-        let patients_service = PatientsService::new(Box::new(PatientsRepositoryFake::new()));
-        let created_patient = patients_service
-            .create_patient("John Patient".into(), "92022900002".into())
-            .await
-            .unwrap();
+        assert_eq!(response.status(), Status::NotFound);
+    }
 
-        let drugs_service = DrugsService::new(Box::new(DrugsRepositoryFake::new()));
-        let created_drug_0 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
-                None,
+    #[tokio::test]
+    async fn gets_distinct_patients_prescribed_drug() {
+        let (client, seeds, token) = create_api_client_with_pharmacist_session().await;
+        for (patient_id, drug_id) in [
+            (seeds.patient.id, seeds.drugs[0].id),
+            (seeds.patient.id, seeds.drugs[0].id),
+            (seeds.other_patient.id, seeds.drugs[0].id),
+            (seeds.other_patient.id, seeds.drugs[1].id),
+        ] {
+            let response = client
+                .post("/prescriptions")
+                .header(ContentType::JSON)
+                .body(format!(
+                    r#"{{
+                        "doctor_id": "{}",
+                        "patient_id": "{}",
+                        "prescribed_drugs": [ ["{}",  1] ]
+                    }}"#,
+                    seeds.doctor.id, patient_id, drug_id
+                ))
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Created);
+        }
+
+        let response = client
+            .get(format!("/drugs/{}/affected-patients", seeds.drugs[0].id))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let affected_patients = response.into_json::<Vec<AffectedPatient>>().await.unwrap();
+        let patient_ids: Vec<Uuid> = affected_patients.iter().map(|patient| patient.id).collect();
+
+        assert_eq!(patient_ids, vec![seeds.other_patient.id, seeds.patient.id]);
+        assert_eq!(affected_patients[1].pesel_number, "92022900002");
+
+        let response = client
+            .get(format!("/drugs/{}/affected-patients", seeds.drugs[2].id))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(
+            response.into_json::<Vec<AffectedPatient>>().await,
+            Some(vec![])
+        );
+
+        let response = client
+            .get(format!("/drugs/{}/affected-patients", Uuid::new_v4()))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .get(format!("/drugs/{}/affected-patients", seeds.drugs[0].id))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[tokio::test]
+    async fn gets_pharmacist_fill_stats_per_day() {
+        let (client, seeds) = create_api_client().await;
+        let now = Utc::now();
+        for _ in 0..2 {
+            let prescription =
+                create_prescription(&client, &seeds, now - Duration::minutes(1), "REGULAR").await;
+            let response = client
+                .post(format!("/prescriptions/{}/fill", prescription.id))
+                .header(ContentType::JSON)
+                .body(format!(
+                    r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                    seeds.pharmacist.id, prescription.code
+                ))
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Created);
+        }
+
+        let from = (now - Duration::days(2)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let response = client
+            .get(format!(
+                "/pharmacists/{}/fill-stats?from={}",
+                seeds.pharmacist.id, from
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let fill_stats = response.into_json::<PharmacistFillStats>().await.unwrap();
+        let fills_counts: Vec<i64> = fill_stats.days.iter().map(|day| day.fills_count).collect();
+
+        assert_eq!(fill_stats.pharmacist_id, seeds.pharmacist.id);
+        assert_eq!(fills_counts, vec![0, 0, 2]);
+        assert_eq!(fill_stats.days[2].date, Utc::now().date_naive());
+
+        let response = client
+            .get(format!("/pharmacists/{}/fill-stats", Uuid::new_v4()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .get(format!(
+                "/pharmacists/{}/fill-stats?from=2022-01-01T00:00:00Z&to=2024-01-01T00:00:00Z",
+                seeds.pharmacist.id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn gets_only_existing_prescriptions_in_batch() {
+        let config = ApiConfig {
+            max_prescriptions_batch_get_size: 3,
+            ..ApiConfig::default()
+        };
+        let (client, seeds) = create_api_client_with_config(config).await;
+        let now = Utc::now();
+        let first_prescription = create_prescription(&client, &seeds, now, "REGULAR").await;
+        let second_prescription = create_prescription(&client, &seeds, now, "REGULAR").await;
+
+        let response = client
+            .post("/prescriptions/batch-get")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"["{}", "{}", "{}"]"#,
+                second_prescription.id,
+                Uuid::new_v4(),
+                first_prescription.id
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let prescriptions = response.into_json::<Vec<Prescription>>().await.unwrap();
+
+        assert_eq!(prescriptions, vec![second_prescription, first_prescription]);
+
+        let response = client
+            .post("/prescriptions/batch-get")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"["{}", "{}", "{}", "{}"]"#,
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4()
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+    }
+
+    async fn reassign_patient(
+        client: &Client,
+        token: &str,
+        prescription_id: Uuid,
+        patient_id: Uuid,
+    ) -> (Status, String) {
+        let response = client
+            .patch(format!("/prescriptions/{}/patient", prescription_id))
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(r#"{{"patient_id": "{}"}}"#, patient_id))
+            .dispatch()
+            .await;
+
+        (response.status(), response.into_string().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn reassigns_prescription_patient() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let (status, body) =
+            reassign_patient(&client, &token, prescription.id, seeds.other_patient.id).await;
+
+        assert_eq!(status, Status::Ok);
+
+        let reassigned_prescription: Prescription = json::from_str(&body).unwrap();
+
+        assert_eq!(reassigned_prescription.id, prescription.id);
+        assert_eq!(reassigned_prescription.patient.id, seeds.other_patient.id);
+    }
+
+    #[tokio::test]
+    async fn doesnt_reassign_patient_of_filled_prescription() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        let fill_response = client
+            .post(format!("/prescriptions/{}/fill", prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                seeds.pharmacist.id, prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::Created);
+
+        let (status, _) =
+            reassign_patient(&client, &token, prescription.id, seeds.other_patient.id).await;
+
+        assert_eq!(status, Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn doesnt_reassign_prescription_to_nonexistent_patient() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let (status, body) =
+            reassign_patient(&client, &token, prescription.id, Uuid::new_v4()).await;
+
+        assert_eq!(status, Status::NotFound);
+        assert!(body.contains("Patient with id"));
+    }
+
+    #[tokio::test]
+    async fn reassigning_prescription_patient_requires_prescribing_doctor_session() {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let other_doctor = context
+            .doctors_service
+            .create_doctor(
+                "Jane Doctor".into(),
+                "96021807250".into(),
+                "5425740".into(),
                 None,
             )
             .await
             .unwrap();
-        let created_drug_1 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                Some(other_doctor.id),
                 None,
                 None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal".into(),
             )
             .await
             .unwrap();
-        let created_drug_2 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
-                None,
+        let token = context.sessions_service.encode_session_token(&session);
+        let routes = routes![
+            super::create_prescription,
+            super::reassign_prescription_patient
+        ];
+        let rocket = rocket::build().manage(context).mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let (status, _) =
+            reassign_patient(&client, &token, prescription.id, seeds.other_patient.id).await;
+
+        assert_eq!(status, Status::Forbidden);
+
+        let response = client
+            .patch(format!("/prescriptions/{}/patient", prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(r#"{{"patient_id": "{}"}}"#, seeds.other_patient.id))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    async fn cancel_prescription(
+        client: &Client,
+        token: &str,
+        prescription_id: Uuid,
+    ) -> (Status, String) {
+        let response = client
+            .post(format!("/prescriptions/{}/cancel", prescription_id))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        (response.status(), response.into_string().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn cancels_prescription_and_blocks_its_fill() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let (status, body) = cancel_prescription(&client, &token, prescription.id).await;
+
+        assert_eq!(status, Status::Ok);
+
+        let cancelled_prescription: Prescription = json::from_str(&body).unwrap();
+
+        assert_eq!(cancelled_prescription.id, prescription.id);
+        assert!(cancelled_prescription.cancelled_at.is_some());
+        assert_eq!(
+            cancelled_prescription.fill_status,
+            PrescriptionFillStatus::Cancelled
+        );
+
+        let fill_response = client
+            .post(format!("/prescriptions/{}/fill", prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                seeds.pharmacist.id, prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::UnprocessableEntity);
+        assert!(fill_response
+            .into_string()
+            .await
+            .unwrap()
+            .contains("Prescription is cancelled"));
+
+        let (status, _) = cancel_prescription(&client, &token, prescription.id).await;
+
+        assert_eq!(status, Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn doesnt_cancel_filled_prescription() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        let fill_response = client
+            .post(format!("/prescriptions/{}/fill", prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                seeds.pharmacist.id, prescription.code
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(fill_response.status(), Status::Created);
+
+        let (status, body) = cancel_prescription(&client, &token, prescription.id).await;
+
+        assert_eq!(status, Status::UnprocessableEntity);
+        assert!(body.contains("Filled prescription can't be cancelled"));
+    }
+
+    #[tokio::test]
+    async fn cancelling_prescription_requires_prescribing_doctor_session() {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let other_doctor = context
+            .doctors_service
+            .create_doctor(
+                "Jane Doctor".into(),
+                "96021807250".into(),
+                "5425740".into(),
                 None,
             )
             .await
             .unwrap();
-        let created_drug_3 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+        let session = context
+            .sessions_service
+            .create_session(
+                Uuid::new_v4(),
+                Some(other_doctor.id),
                 None,
                 None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal".into(),
             )
             .await
             .unwrap();
+        let token = context.sessions_service.encode_session_token(&session);
+        let routes = routes![super::create_prescription, super::cancel_prescription];
+        let rocket = rocket::build().manage(context).mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
 
-        let prescriptions_service =
-            PrescriptionsService::new(Box::new(PrescriptionsRepositoryFake::new(
-                None,
-                Some(vec![created_doctor.clone()]),
-                Some(vec![created_patient.clone()]),
-                Some(vec![created_pharmacist.clone()]),
-                Some(vec![
-                    created_drug_0.clone(),
-                    created_drug_1.clone(),
-                    created_drug_2.clone(),
-                    created_drug_3.clone(),
-                ]),
-            )));
-
-        let authentication_repository = Box::new(AuthenticationRepositoryFake::new());
-        let authentication_service =
-            Arc::new(AuthenticationService::new(authentication_repository));
-
-        let sessions_repository = Box::new(SessionsRepositoryFake::new());
-        let sessions_service = Arc::new(SessionsService::new(sessions_repository));
-
-        (
-            Context {
-                doctors_service: Arc::new(doctors_service),
-                pharmacists_service: Arc::new(pharmacist_service),
-                patients_service: Arc::new(patients_service),
-                drugs_service: Arc::new(drugs_service),
-                prescriptions_service: Arc::new(prescriptions_service),
-                authentication_service,
-                sessions_service,
-            },
-            DatabaseSeeds {
-                doctor: created_doctor,
-                pharmacist: created_pharmacist,
-                patient: created_patient,
-                drugs: vec![
-                    created_drug_0,
-                    created_drug_1,
-                    created_drug_2,
-                    created_drug_3,
-                ],
-            },
-        )
-    }
-
-    async fn create_api_client() -> (Client, DatabaseSeeds) {
-        let (context, seeds) = setup_services_and_seed_database().await;
-
-        let routes = routes![
-            super::create_prescription,
-            super::get_prescription_by_id,
-            super::get_prescriptions_with_pagination,
-            super::fill_prescription
-        ];
+        let (status, _) = cancel_prescription(&client, &token, prescription.id).await;
 
-        let rocket = rocket::build().manage(context).mount("/", routes);
+        assert_eq!(status, Status::Forbidden);
 
-        let client = Client::tracked(rocket).await.unwrap();
+        let response = client
+            .post(format!("/prescriptions/{}/cancel", prescription.id))
+            .dispatch()
+            .await;
 
-        (client, seeds)
+        assert_eq!(response.status(), Status::Forbidden);
     }
 
-    #[tokio::test]
-    async fn creates_and_fills_prescription() {
-        let (client, seeds) = create_api_client().await;
-
-        let create_prescription_response = client
-            .post("/prescriptions")
+    async fn create_prescription_template(
+        client: &Client,
+        token: &str,
+        prescribed_drugs: &[(Uuid, u32)],
+    ) -> PrescriptionTemplate {
+        let prescribed_drugs = prescribed_drugs
+            .iter()
+            .map(|(drug_id, quantity)| format!(r#"["{}", {}]"#, drug_id, quantity))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let response = client
+            .post("/prescriptions/templates")
             .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
             .body(format!(
-                r#"{{
-                    "doctor_id": "{}",
-                    "patient_id": "{}",
-                    "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
-                    "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
-                }}"#,
-                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+                r#"{{"name": "Flu season", "prescribed_drugs": [{}]}}"#,
+                prescribed_drugs
             ))
             .dispatch()
             .await;
 
-        assert_eq!(create_prescription_response.status(), Status::Created);
+        assert_eq!(response.status(), Status::Created);
 
-        let created_prescription = json::from_str::<Prescription>(
-            &create_prescription_response.into_string().await.unwrap(),
-        )
-        .unwrap();
+        json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
 
-        assert!(created_prescription.fill.is_none());
+    #[tokio::test]
+    async fn creates_prescription_from_template() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let template = create_prescription_template(
+            &client,
+            &token,
+            &[(seeds.drugs[0].id, 2), (seeds.drugs[2].id, 1)],
+        )
+        .await;
 
-        let fill_prescription_response = client
-            .post(format!("/prescriptions/{}/fill", created_prescription.id))
-            .header(ContentType::JSON)
-            .body(format!(
-                r#"{{
-                    "pharmacist_id": "{}",
-                    "prescription_code": "{}"
-                }}"#,
-                seeds.pharmacist.id,
-                created_prescription.code
+        let response = client
+            .post(format!(
+                "/prescriptions/from-template/{}?patient_id={}",
+                template.id, seeds.patient.id
             ))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
             .dispatch()
             .await;
 
-        assert_eq!(fill_prescription_response.status(), Status::Created);
-
-        json::from_str::<Prescription>(&fill_prescription_response.into_string().await.unwrap())
-            .unwrap();
+        assert_eq!(response.status(), Status::Created);
+        let prescription: Prescription =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(prescription.doctor.id, seeds.doctor.id);
+        assert_eq!(prescription.patient.id, seeds.patient.id);
+        assert_eq!(
+            prescription
+                .prescribed_drugs
+                .iter()
+                .map(|prescribed_drug| (prescribed_drug.drug_id, prescribed_drug.quantity))
+                .collect::<Vec<_>>(),
+            vec![(seeds.drugs[0].id, 2), (seeds.drugs[2].id, 1)]
+        );
 
-        let get_prescription_by_id_response = client
-            .get(format!("/prescriptions/{}", created_prescription.id))
-            .header(ContentType::JSON)
+        let response = client
+            .post(format!(
+                "/prescriptions/from-template/{}?patient_id={}",
+                Uuid::new_v4(),
+                seeds.patient.id
+            ))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
             .dispatch()
             .await;
 
-        assert_eq!(get_prescription_by_id_response.status(), Status::Ok);
-
-        let prescription_by_id: Prescription =
-            json::from_str(&get_prescription_by_id_response.into_string().await.unwrap()).unwrap();
-
-        assert!(prescription_by_id.fill.is_some());
+        assert_eq!(response.status(), Status::NotFound);
     }
 
     #[tokio::test]
-    async fn doesnt_fill_if_already_filled() {
-        let (client, seeds) = create_api_client().await;
-        let create_seed_prescription_response = client
-            .post("/prescriptions")
+    async fn updates_lists_and_deletes_prescription_templates() {
+        let (client, seeds, token) = create_api_client_with_doctor_session().await;
+        let template =
+            create_prescription_template(&client, &token, &[(seeds.drugs[0].id, 2)]).await;
+
+        let response = client
+            .put(format!("/prescriptions/templates/{}", template.id))
             .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
             .body(format!(
-                r#"{{
-                    "doctor_id": "{}",
-                    "patient_id": "{}",
-                    "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
-                    "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
-                }}"#,
-                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+                r#"{{"name": "Cold", "prescribed_drugs": [["{}", 3]]}}"#,
+                seeds.drugs[1].id
             ))
             .dispatch()
             .await;
-        let seed_prescription: Prescription = json::from_str(
-            &create_seed_prescription_response
-                .into_string()
-                .await
-                .unwrap(),
-        )
-        .unwrap();
 
-        assert_eq!(
-            client
-                .post(format!("/prescriptions/{}/fill", seed_prescription.id))
-                .header(ContentType::JSON)
-                .body(format!(
-                    r#"{{
-                        "pharmacist_id": "{}",
-                        "prescription_code": "{}"
-                    }}"#,
-                    seeds.pharmacist.id, seed_prescription.code,
-                ))
-                .dispatch()
-                .await
-                .status(),
-            Status::Created
-        );
+        assert_eq!(response.status(), Status::Ok);
 
-        assert_eq!(
-            client
-                .post(format!("/prescriptions/{}/fill", seed_prescription.id))
-                .header(ContentType::JSON)
-                .body(format!(
-                    r#"{{
-                        "pharmacist_id": "{}",
-                        "prescription_code": "{}"
-                    }}"#,
-                    seeds.pharmacist.id, seed_prescription.code,
-                ))
-                .dispatch()
-                .await
-                .status(),
-            Status::UnprocessableEntity
-        );
-    }
+        let response = client
+            .get("/prescriptions/templates")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
 
-    #[tokio::test]
-    async fn returns_error_if_prescription_does_not_exist() {
-        let (client, _) = create_api_client().await;
+        assert_eq!(response.status(), Status::Ok);
+        let templates: Vec<PrescriptionTemplate> =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].name, "Cold");
+        assert_eq!(templates[0].prescribed_drugs[0].drug_id, seeds.drugs[1].id);
+        assert_eq!(templates[0].prescribed_drugs[0].quantity, 3);
 
-        let get_prescription_by_id_response = client
-            .get("/prescriptions/00000000-0000-0000-0000-000000000000")
-            .header(ContentType::JSON)
+        let response = client
+            .delete(format!("/prescriptions/templates/{}", template.id))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
             .dispatch()
             .await;
 
-        assert_eq!(get_prescription_by_id_response.status(), Status::NotFound);
+        assert_eq!(response.status(), Status::NoContent);
+
+        let response = client
+            .delete(format!("/prescriptions/templates/{}", template.id))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
     }
 
     #[tokio::test]
-    async fn gets_pharmacists_with_pagination() {
-        let (client, seeds) = create_api_client().await;
+    async fn using_prescription_template_requires_owning_doctor_session() {
+        let (context, seeds) = setup_services_and_seed_database().await;
+        let other_doctor = context
+            .doctors_service
+            .create_doctor(
+                "Jane Doctor".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
+            .await
+            .unwrap();
+        let mut tokens = vec![];
+        for doctor_id in [seeds.doctor.id, other_doctor.id] {
+            let session = context
+                .sessions_service
+                .create_session(
+                    Uuid::new_v4(),
+                    Some(doctor_id),
+                    None,
+                    None,
+                    "127.0.0.1".parse().unwrap(),
+                    "Clinic terminal".into(),
+                )
+                .await
+                .unwrap();
+            tokens.push(context.sessions_service.encode_session_token(&session));
+        }
+        let routes = routes![
+            super::create_prescription_template,
+            super::update_prescription_template,
+            super::delete_prescription_template,
+            super::create_prescription_from_template
+        ];
+        let rocket = rocket::build().manage(context).mount("/", routes);
+        let client = Client::tracked(rocket).await.unwrap();
+        let template =
+            create_prescription_template(&client, &tokens[0], &[(seeds.drugs[0].id, 2)]).await;
+        let other_doctor_authorization =
+            Header::new("Authorization", format!("Bearer {}", tokens[1]));
 
-        client
-            .post("/prescriptions")
-            .header(ContentType::JSON)
-            .body(format!(
-                r#"{{
-                "doctor_id": "{}",
-                "patient_id": "{}",
-                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
-                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
-            }}"#,
-                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+        let response = client
+            .post(format!(
+                "/prescriptions/from-template/{}?patient_id={}",
+                template.id, seeds.patient.id
             ))
+            .header(other_doctor_authorization.clone())
             .dispatch()
             .await;
-        client
-            .post("/prescriptions")
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .put(format!("/prescriptions/templates/{}", template.id))
             .header(ContentType::JSON)
+            .header(other_doctor_authorization.clone())
             .body(format!(
-                r#"{{
-                "doctor_id": "{}",
-                "patient_id": "{}",
-                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
-                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
-            }}"#,
-                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+                r#"{{"name": "Cold", "prescribed_drugs": [["{}", 3]]}}"#,
+                seeds.drugs[1].id
             ))
             .dispatch()
             .await;
-        client
-            .post("/prescriptions")
-            .header(ContentType::JSON)
-            .body(format!(
-                r#"{{
-                "doctor_id": "{}",
-                "patient_id": "{}",
-                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
-                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
-            }}"#,
-                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
-            ))
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .delete(format!("/prescriptions/templates/{}", template.id))
+            .header(other_doctor_authorization)
             .dispatch()
             .await;
-        client
-            .post("/prescriptions")
-            .header(ContentType::JSON)
-            .body(format!(
-                r#"{{
-                "doctor_id": "{}",
-                "patient_id": "{}",
-                "prescription_type": "FOR_CHRONIC_DISEASE_DRUGS",
-                "prescribed_drugs": [ ["{}",  1], ["{}",  2] ]
-            }}"#,
-                seeds.doctor.id, seeds.patient.id, seeds.drugs[0].id, seeds.drugs[1].id
+
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .post(format!(
+                "/prescriptions/from-template/{}?patient_id={}",
+                template.id, seeds.patient.id
             ))
             .dispatch()
             .await;
 
-        let prescriptions_response = client
-            .get("/prescriptions?page_size=2&page=1")
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    async fn verify_prescription(
+        client: &Client,
+        token: &str,
+        prescription: &Prescription,
+        code: &str,
+    ) -> PrescriptionVerification {
+        let response = client
+            .post(format!("/prescriptions/{}/verify", prescription.id))
             .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(format!(r#"{{"prescription_code": "{}"}}"#, code))
             .dispatch()
             .await;
-        let prescriptions: Vec<Prescription> =
-            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
 
-        assert_eq!(prescriptions.len(), 2);
+        assert_eq!(response.status(), Status::Ok);
 
-        let prescriptions_response = client
-            .get("/prescriptions?page_size=3&page=1")
-            .header(ContentType::JSON)
-            .dispatch()
-            .await;
-        let prescriptions: Vec<Prescription> =
-            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+        json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
 
-        assert_eq!(prescriptions.len(), 1);
+    #[tokio::test]
+    async fn verifies_prescription_without_filling_it() {
+        let (client, seeds, token) = create_api_client_with_pharmacist_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+        let expired_prescription =
+            create_prescription(&client, &seeds, Utc::now() - Duration::days(60), "REGULAR").await;
 
-        let prescriptions_response = client
-            .get("/prescriptions?page_size=10")
+        let verification =
+            verify_prescription(&client, &token, &prescription, &prescription.code).await;
+
+        assert_eq!(
+            verification.verification,
+            PrescriptionVerificationStatus::Valid
+        );
+        assert_eq!(verification.prescription.id, prescription.id);
+        assert!(verification.prescription.fills.is_empty());
+
+        let verification = verify_prescription(&client, &token, &prescription, "00000000").await;
+
+        assert_eq!(
+            verification.verification,
+            PrescriptionVerificationStatus::CodeMismatch
+        );
+
+        let verification = verify_prescription(
+            &client,
+            &token,
+            &expired_prescription,
+            &expired_prescription.code,
+        )
+        .await;
+
+        assert_eq!(
+            verification.verification,
+            PrescriptionVerificationStatus::Expired
+        );
+
+        let response = client
+            .get(format!("/prescriptions/{}", prescription.id))
             .header(ContentType::JSON)
             .dispatch()
             .await;
-        let prescriptions: Vec<Prescription> =
-            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
+        let prescription_by_id: Prescription =
+            json::from_str(&response.into_string().await.unwrap()).unwrap();
 
-        assert_eq!(prescriptions.len(), 4);
+        assert_eq!(
+            prescription_by_id.fill_status,
+            PrescriptionFillStatus::Unfilled
+        );
 
-        let prescriptions_response = client
-            .get("/prescriptions?page=1")
+        let response = client
+            .post(format!("/prescriptions/{}/fill", prescription.id))
             .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "{}"}}"#,
+                seeds.pharmacist.id, prescription.code
+            ))
             .dispatch()
             .await;
-        let prescriptions: Vec<Prescription> =
-            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
 
-        assert_eq!(prescriptions.len(), 0);
+        assert_eq!(response.status(), Status::Created);
 
-        let prescriptions_response = client
-            .get("/prescriptions")
+        let verification =
+            verify_prescription(&client, &token, &prescription, &prescription.code).await;
+
+        assert_eq!(
+            verification.verification,
+            PrescriptionVerificationStatus::AlreadyFilled
+        );
+        assert_eq!(verification.prescription.fills.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn doesnt_verify_or_fill_prescription_with_malformed_code() {
+        let (client, seeds, token) = create_api_client_with_pharmacist_session().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
+
+        let response = client
+            .post(format!("/prescriptions/{}/verify", prescription.id))
             .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .body(r#"{"prescription_code": "1234"}"#)
             .dispatch()
             .await;
-        let prescriptions: Vec<Prescription> =
-            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
 
-        assert_eq!(prescriptions.len(), 4);
+        assert_eq!(response.status(), Status::UnprocessableEntity);
 
-        let prescriptions_response = client
-            .get("/prescriptions?page_size=3&page=2")
+        let response = client
+            .post(format!("/prescriptions/{}/fill", prescription.id))
             .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"pharmacist_id": "{}", "prescription_code": "1234567a"}}"#,
+                seeds.pharmacist.id
+            ))
             .dispatch()
             .await;
-        let prescriptions: Vec<Prescription> =
-            json::from_str(&prescriptions_response.into_string().await.unwrap()).unwrap();
 
-        assert_eq!(prescriptions.len(), 0);
+        assert_eq!(response.status(), Status::UnprocessableEntity);
     }
 
     #[tokio::test]
-    async fn get_pharmacists_with_pagination_returns_error_if_params_are_invalid() {
-        let (client, _) = create_api_client().await;
+    async fn verifying_prescription_requires_pharmacist_session() {
+        let (client, seeds) = create_api_client().await;
+        let prescription = create_prescription(&client, &seeds, Utc::now(), "REGULAR").await;
 
-        assert_eq!(
-            client
-                .get("/prescriptions?page=-1")
-                .dispatch()
-                .await
-                .status(),
-            Status::UnprocessableEntity
-        );
+        let response = client
+            .post(format!("/prescriptions/{}/verify", prescription.id))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"prescription_code": "{}"}}"#,
+                prescription.code
+            ))
+            .dispatch()
+            .await;
 
-        assert_eq!(
-            client
-                .get("/prescriptions?page_size=0")
-                .dispatch()
-                .await
-                .status(),
-            Status::UnprocessableEntity
-        );
+        assert_eq!(response.status(), Status::Forbidden);
     }
 }
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use okapi::openapi3::Responses;
+use rocket::{get, http::Status, response::Responder, serde::json::Json, Request};
+use rocket_okapi::{gen::OpenApiGenerator, openapi, response::OpenApiResponderInner, OpenApiError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::api::utils::{
+        date_range::DateRange, error::ApiError, openapi_responses::get_openapi_responses,
+        pagination_messages::pagination_error_message,
+    },
+    domain::{
+        drugs::entities::Drug,
+        prescriptions::{
+            entities::{DoctorPrescriptionCount, PrescriptionType},
+            repository::GetPrescriptionsRepositoryError,
+            service::{
+                CountPrescriptionsByTypeError, CountPrescriptionsPerDoctorError,
+                GetUnprescribedDrugsError,
+            },
+        },
+    },
+    Ctx,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionsByTypeReport {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    counts: HashMap<PrescriptionType, i64>,
+}
+
+impl<'r> Responder<'r, 'static> for CountPrescriptionsByTypeError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CountPrescriptionsByTypeError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![(
+                "422",
+                "Returned when from or to is not a valid RFC 3339 date or from is later than to",
+            )],
+        )
+    }
+}
+
+// Counts prescriptions created within the range, which defaults to the last 30 days
+#[openapi(tag = "Reports")]
+#[get("/reports/prescriptions-by-type?<range..>")]
+pub async fn get_prescriptions_by_type(
+    ctx: &Ctx,
+    range: DateRange,
+) -> Result<Json<PrescriptionsByTypeReport>, CountPrescriptionsByTypeError> {
+    let counts = ctx
+        .prescriptions_service
+        .count_prescriptions_by_type(range.from, range.to)
+        .await?;
+
+    Ok(Json(PrescriptionsByTypeReport {
+        from: range.from,
+        to: range.to,
+        counts,
+    }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionsPerDoctorReport {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    doctors: Vec<DoctorPrescriptionCount>,
+}
+
+impl<'r> Responder<'r, 'static> for CountPrescriptionsPerDoctorError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for CountPrescriptionsPerDoctorError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![(
+                "422",
+                "Returned when from or to is not a valid RFC 3339 date, from is later than to, the page < 0, page_size < 1 or page_size > 100",
+            )],
+        )
+    }
+}
+
+// Doctors ranked by the number of prescriptions created within the range, which defaults to the
+// last 30 days
+#[openapi(tag = "Reports")]
+#[get("/reports/prescriptions-per-doctor?<page>&<page_size>&<range..>")]
+pub async fn get_prescriptions_per_doctor(
+    ctx: &Ctx,
+    page: Option<i64>,
+    page_size: Option<i64>,
+    range: DateRange,
+) -> Result<Json<PrescriptionsPerDoctorReport>, CountPrescriptionsPerDoctorError> {
+    let doctors = ctx
+        .prescriptions_service
+        .count_prescriptions_per_doctor(range.from, range.to, page, page_size)
+        .await?;
+
+    Ok(Json(PrescriptionsPerDoctorReport {
+        from: range.from,
+        to: range.to,
+        doctors,
+    }))
+}
+
+impl<'r> Responder<'r, 'static> for GetUnprescribedDrugsError {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let (message, status) = match self {
+            Self::RepositoryError(err) => {
+                let message = match &err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(err) => {
+                        pagination_error_message(err)
+                    }
+                    err => err.to_string(),
+                };
+                let status = match err {
+                    GetPrescriptionsRepositoryError::InvalidPaginationParams(_) => {
+                        Status::UnprocessableEntity
+                    }
+                    GetPrescriptionsRepositoryError::DatabaseError(_) => {
+                        Status::InternalServerError
+                    }
+                    GetPrescriptionsRepositoryError::Unavailable => Status::ServiceUnavailable,
+                };
+                (message, status)
+            }
+        };
+
+        ApiError::build_rocket_response(req, message, status)
+    }
+}
+
+impl OpenApiResponderInner for GetUnprescribedDrugsError {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses, OpenApiError> {
+        get_openapi_responses(
+            gen,
+            vec![(
+                "422",
+                "Returned when the the page < 0, page_size < 1 or page_size > 100",
+            )],
+        )
+    }
+}
+
+// Drugs which no prescription has ever included, candidates for being taken off stock
+#[openapi(tag = "Reports")]
+#[get("/reports/unprescribed-drugs?<page>&<page_size>")]
+pub async fn get_unprescribed_drugs(
+    ctx: &Ctx,
+    page: Option<i64>,
+    page_size: Option<i64>,
+) -> Result<Json<Vec<Drug>>, GetUnprescribedDrugsError> {
+    let drugs = ctx
+        .prescriptions_service
+        .get_unprescribed_drugs(page, page_size)
+        .await?;
+
+    Ok(Json(drugs))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, SecondsFormat, Utc};
+    use rocket::{http::Status, local::asynchronous::Client, routes};
+    use uuid::Uuid;
+
+    use super::{PrescriptionsByTypeReport, PrescriptionsPerDoctorReport};
+    use crate::{
+        application::api::utils::fake_api_context::create_fake_api_context,
+        domain::{
+            drugs::{
+                entities::{Drug, DrugContentType, NewDrug},
+                repository::{DrugsRepository, DrugsRepositoryFake},
+            },
+            prescriptions::{
+                entities::{
+                    PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFillStatus,
+                    PrescriptionPatient, PrescriptionType,
+                },
+                policy::PrescriptionPolicy,
+                repository::PrescriptionsRepositoryFake,
+                service::PrescriptionsService,
+            },
+        },
+        Context,
+    };
+
+    fn create_mock_prescription(prescription_type: PrescriptionType) -> Prescription {
+        let created_at = Utc::now() - Duration::days(1);
+
+        Prescription {
+            id: Uuid::new_v4(),
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type,
+            start_date: created_at,
+            end_date: created_at + PrescriptionPolicy::default().get_duration(&prescription_type),
+            prescribed_drugs: vec![],
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    async fn create_mock_drugs() -> Vec<Drug> {
+        let drugs_repository = DrugsRepositoryFake::new();
+        let mut drugs = vec![];
+        for name in ["Gripex", "Apap", "Rutinoscorbin"] {
+            let new_drug = NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            drugs.push(drugs_repository.create_drug(new_drug).await.unwrap());
+        }
+        drugs
+    }
+
+    async fn create_api_client() -> Client {
+        let drugs = create_mock_drugs().await;
+        let mut prescription_with_drug = create_mock_prescription(PrescriptionType::Regular);
+        prescription_with_drug.prescribed_drugs = vec![PrescribedDrug {
+            id: Uuid::new_v4(),
+            prescription_id: prescription_with_drug.id,
+            drug_id: drugs[0].id,
+            quantity: 1,
+            dispensed_quantity: 0,
+            unit_price_cents: None,
+            created_at: prescription_with_drug.created_at,
+            updated_at: prescription_with_drug.updated_at,
+        }];
+        let busiest_doctor = PrescriptionDoctor {
+            id: Uuid::new_v4(),
+            name: "Jane Doctor".to_string(),
+            pesel_number: "92022900002".to_string(),
+            pwz_number: "5425740".try_into().unwrap(),
+        };
+        let mut prescriptions = vec![
+            prescription_with_drug,
+            create_mock_prescription(PrescriptionType::ForAntibiotics),
+            create_mock_prescription(PrescriptionType::Regular),
+            create_mock_prescription(PrescriptionType::ForChronicDiseaseDrugs),
+        ];
+        prescriptions[1].doctor = busiest_doctor.clone();
+        prescriptions[2].doctor = busiest_doctor;
+        let prescriptions_repository = Box::new(PrescriptionsRepositoryFake::new(
+            Some(prescriptions),
+            None,
+            None,
+            None,
+            Some(drugs),
+        ));
+        let context = Context {
+            prescriptions_service: Arc::new(PrescriptionsService::new(
+                prescriptions_repository,
+                PrescriptionPolicy::default(),
+            )),
+            ..create_fake_api_context()
+        };
+
+        let rocket = rocket::build().manage(context).mount(
+            "/",
+            routes![
+                super::get_prescriptions_by_type,
+                super::get_prescriptions_per_doctor,
+                super::get_unprescribed_drugs
+            ],
+        );
+
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn counts_prescriptions_by_type() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/reports/prescriptions-by-type")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let report = response
+            .into_json::<PrescriptionsByTypeReport>()
+            .await
+            .unwrap();
+
+        assert_eq!(report.counts.len(), 4);
+        assert_eq!(report.counts[&PrescriptionType::Regular], 2);
+        assert_eq!(report.counts[&PrescriptionType::ForAntibiotics], 1);
+        assert_eq!(report.counts[&PrescriptionType::ForImmunologicalDrugs], 0);
+        assert_eq!(report.counts[&PrescriptionType::ForChronicDiseaseDrugs], 1);
+    }
+
+    #[tokio::test]
+    async fn counts_only_prescriptions_created_within_range() {
+        let client = create_api_client().await;
+        let to = (Utc::now() - Duration::days(2)).to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let response = client
+            .get(format!("/reports/prescriptions-by-type?to={}", to))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let report = response
+            .into_json::<PrescriptionsByTypeReport>()
+            .await
+            .unwrap();
+
+        assert_eq!(report.counts.len(), 4);
+        assert!(report.counts.values().all(|&count| count == 0));
+    }
+
+    #[tokio::test]
+    async fn returns_unprocessable_entity_if_range_is_invalid() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/reports/prescriptions-by-type?from=2024-03-02T00:00:00Z&to=2024-03-01T00:00:00Z")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn counts_prescriptions_per_doctor_busiest_first() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/reports/prescriptions-per-doctor")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let report = response
+            .into_json::<PrescriptionsPerDoctorReport>()
+            .await
+            .unwrap();
+        let counts: Vec<i64> = report
+            .doctors
+            .iter()
+            .map(|doctor| doctor.prescriptions_count)
+            .collect();
+
+        assert_eq!(counts, vec![2, 1, 1]);
+        assert_eq!(report.doctors[0].doctor_name, "Jane Doctor");
+    }
+
+    #[tokio::test]
+    async fn paginates_and_filters_prescriptions_per_doctor() {
+        let client = create_api_client().await;
+        let to = (Utc::now() - Duration::days(2)).to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let response = client
+            .get("/reports/prescriptions-per-doctor?page=1&page_size=2")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let report = response
+            .into_json::<PrescriptionsPerDoctorReport>()
+            .await
+            .unwrap();
+
+        assert_eq!(report.doctors.len(), 1);
+        assert_eq!(report.doctors[0].prescriptions_count, 1);
+
+        let response = client
+            .get(format!("/reports/prescriptions-per-doctor?to={}", to))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let report = response
+            .into_json::<PrescriptionsPerDoctorReport>()
+            .await
+            .unwrap();
+
+        assert!(report.doctors.is_empty());
+
+        let response = client
+            .get("/reports/prescriptions-per-doctor?page_size=0")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[tokio::test]
+    async fn lists_only_drugs_that_were_never_prescribed() {
+        let client = create_api_client().await;
+
+        let response = client.get("/reports/unprescribed-drugs").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let drugs = response.into_json::<Vec<Drug>>().await.unwrap();
+        let names: Vec<&str> = drugs.iter().map(|drug| drug.name.as_str()).collect();
+
+        assert_eq!(names, vec!["Apap", "Rutinoscorbin"]);
+    }
+
+    #[tokio::test]
+    async fn returns_unprocessable_entity_if_unprescribed_drugs_page_is_invalid() {
+        let client = create_api_client().await;
+
+        let response = client
+            .get("/reports/unprescribed-drugs?page_size=0")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+}
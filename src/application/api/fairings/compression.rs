@@ -0,0 +1,256 @@
+use std::io::{Cursor, Write};
+
+use flate2::{write::GzEncoder, Compression};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::{ContentType, Header},
+    Request, Response,
+};
+
+// Smaller bodies fit in a few packets anyway, gzip headers and CPU time aren't worth it
+pub const DEFAULT_COMPRESSION_MIN_SIZE_BYTES: usize = 1024;
+
+fn accepts_gzip(req: &Request<'_>) -> bool {
+    req.headers()
+        .get("Accept-Encoding")
+        .flat_map(|value| value.split(','))
+        .any(|encoding| {
+            let mut params = encoding.split(';').map(str::trim);
+            let is_gzip = params
+                .next()
+                .is_some_and(|name| name.eq_ignore_ascii_case("gzip"));
+            let is_refused = params.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            is_gzip && !is_refused
+        })
+}
+
+fn gzip(body: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    encoder.finish()
+}
+
+// Gzips JSON bodies of at least `min_size` bytes for clients sending `Accept-Encoding: gzip`
+pub struct CompressionFairing {
+    min_size: usize,
+}
+
+impl CompressionFairing {
+    pub fn new(min_size: usize) -> Self {
+        Self { min_size }
+    }
+}
+
+impl Default for CompressionFairing {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION_MIN_SIZE_BYTES)
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for CompressionFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Response compression",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if !accepts_gzip(req)
+            || res.headers().contains("Content-Encoding")
+            || res.content_type() != Some(ContentType::JSON)
+        {
+            return;
+        }
+        // Bodies of known size below the threshold are left untouched without being read
+        if res
+            .body()
+            .preset_size()
+            .is_some_and(|size| size < self.min_size)
+        {
+            return;
+        }
+
+        let Ok(body) = res.body_mut().to_bytes().await else {
+            return;
+        };
+        if body.len() < self.min_size {
+            res.set_sized_body(body.len(), Cursor::new(body));
+            return;
+        }
+        match gzip(&body) {
+            Ok(compressed) => {
+                res.set_header(Header::new("Content-Encoding", "gzip"));
+                res.set_header(Header::new("Vary", "Accept-Encoding"));
+                res.set_sized_body(compressed.len(), Cursor::new(compressed));
+            }
+            Err(_) => res.set_sized_body(body.len(), Cursor::new(body)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, sync::Arc};
+
+    use chrono::{Duration, Utc};
+    use flate2::read::GzDecoder;
+    use rocket::{
+        http::{Header, Status},
+        local::asynchronous::Client,
+        routes,
+        serde::json::{self, Value},
+    };
+    use uuid::Uuid;
+
+    use super::CompressionFairing;
+    use crate::{
+        application::api::{
+            controllers::prescriptions_controller, utils::fake_api_context::create_fake_api_context,
+        },
+        domain::prescriptions::{
+            entities::{
+                Prescription, PrescriptionDoctor, PrescriptionFillStatus, PrescriptionPatient,
+                PrescriptionType,
+            },
+            policy::PrescriptionPolicy,
+            repository::PrescriptionsRepositoryFake,
+            service::PrescriptionsService,
+        },
+        Context,
+    };
+
+    fn create_mock_prescription() -> Prescription {
+        let prescription_type = PrescriptionType::Regular;
+        let created_at = Utc::now() - Duration::days(1);
+
+        Prescription {
+            id: Uuid::new_v4(),
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type,
+            start_date: created_at,
+            end_date: created_at + PrescriptionPolicy::default().get_duration(&prescription_type),
+            prescribed_drugs: vec![],
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    async fn create_api_client(prescriptions_count: usize) -> Client {
+        let prescriptions = (0..prescriptions_count)
+            .map(|_| create_mock_prescription())
+            .collect();
+        let prescriptions_repository = Box::new(PrescriptionsRepositoryFake::new(
+            Some(prescriptions),
+            None,
+            None,
+            None,
+            None,
+        ));
+        let context = Context {
+            prescriptions_service: Arc::new(PrescriptionsService::new(
+                prescriptions_repository,
+                PrescriptionPolicy::default(),
+            )),
+            ..create_fake_api_context()
+        };
+
+        let rocket = rocket::build()
+            .manage(context)
+            .attach(CompressionFairing::default())
+            .mount(
+                "/",
+                routes![prescriptions_controller::get_prescriptions_with_pagination],
+            );
+
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn compresses_large_json_response_if_client_accepts_gzip() {
+        let client = create_api_client(20).await;
+
+        let response = client
+            .get("/prescriptions")
+            .header(Header::new("Accept", "application/json"))
+            .header(Header::new("Accept-Encoding", "deflate, gzip"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("Content-Encoding"), Some("gzip"));
+
+        let compressed = response.into_bytes().await.unwrap();
+        let mut body = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut body)
+            .unwrap();
+        let prescriptions: Value = json::from_str(&body).unwrap();
+
+        assert!(compressed.len() < body.len());
+        assert_eq!(prescriptions.as_array().unwrap().len(), 20);
+    }
+
+    #[tokio::test]
+    async fn doesnt_compress_response_if_client_doesnt_accept_gzip() {
+        let client = create_api_client(20).await;
+
+        for accept_encoding in [None, Some("deflate"), Some("gzip;q=0")] {
+            let mut request = client
+                .get("/prescriptions")
+                .header(Header::new("Accept", "application/json"));
+            if let Some(accept_encoding) = accept_encoding {
+                request = request.header(Header::new("Accept-Encoding", accept_encoding));
+            }
+            let response = request.dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+            assert_eq!(response.headers().get_one("Content-Encoding"), None);
+
+            let prescriptions: Value =
+                json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+            assert_eq!(prescriptions.as_array().unwrap().len(), 20);
+        }
+    }
+
+    #[tokio::test]
+    async fn doesnt_compress_small_response() {
+        let client = create_api_client(0).await;
+
+        let response = client
+            .get("/prescriptions")
+            .header(Header::new("Accept", "application/json"))
+            .header(Header::new("Accept-Encoding", "gzip"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("Content-Encoding"), None);
+        assert_eq!(response.into_string().await.unwrap(), "[]");
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    Data, Request, Response,
+};
+
+use crate::infrastructure::postgres_repository_impl::query_metrics::QueryMetrics;
+
+// Upper bounds in seconds, the same as the default buckets of the Prometheus client libraries
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RouteKey {
+    method: String,
+    route: String,
+}
+
+#[derive(Debug, Default)]
+struct RouteMetrics {
+    requests_by_status: BTreeMap<u16, u64>,
+    bucket_counts: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+}
+
+pub struct RequestMetrics {
+    routes: Mutex<BTreeMap<RouteKey, RouteMetrics>>,
+    pool: Option<sqlx::PgPool>,
+    query_metrics: Option<Arc<QueryMetrics>>,
+}
+
+impl RequestMetrics {
+    pub fn new(pool: Option<sqlx::PgPool>) -> Self {
+        Self {
+            routes: Mutex::new(BTreeMap::new()),
+            pool,
+            query_metrics: None,
+        }
+    }
+
+    pub fn with_query_metrics(mut self, query_metrics: Arc<QueryMetrics>) -> Self {
+        self.query_metrics = Some(query_metrics);
+        self
+    }
+
+    pub fn record(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        let key = RouteKey {
+            method: method.into(),
+            route: route.into(),
+        };
+        let latency = latency.as_secs_f64();
+
+        let mut routes = self.routes.lock().unwrap();
+        let metrics = routes.entry(key).or_default();
+        *metrics.requests_by_status.entry(status).or_default() += 1;
+        for (bucket_count, upper_bound) in metrics.bucket_counts.iter_mut().zip(LATENCY_BUCKETS) {
+            if latency <= upper_bound {
+                *bucket_count += 1;
+            }
+        }
+        metrics.latency_sum += latency;
+        metrics.latency_count += 1;
+    }
+
+    // Text exposition format, see https://prometheus.io/docs/instrumenting/exposition_formats/
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let routes = self.routes.lock().unwrap();
+
+        output.push_str("# HELP http_requests_total Number of handled HTTP requests.\n");
+        output.push_str("# TYPE http_requests_total counter\n");
+        for (key, metrics) in routes.iter() {
+            for (status, count) in &metrics.requests_by_status {
+                writeln!(
+                    output,
+                    r#"http_requests_total{{method="{}",route="{}",status="{}"}} {}"#,
+                    key.method, key.route, status, count
+                )
+                .unwrap();
+            }
+        }
+
+        output
+            .push_str("# HELP http_request_duration_seconds Time spent handling HTTP requests.\n");
+        output.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for (key, metrics) in routes.iter() {
+            let labels = format!(r#"method="{}",route="{}""#, key.method, key.route);
+            for (bucket_count, upper_bound) in metrics.bucket_counts.iter().zip(LATENCY_BUCKETS) {
+                writeln!(
+                    output,
+                    r#"http_request_duration_seconds_bucket{{{},le="{}"}} {}"#,
+                    labels, upper_bound, bucket_count
+                )
+                .unwrap();
+            }
+            writeln!(
+                output,
+                r#"http_request_duration_seconds_bucket{{{},le="+Inf"}} {}"#,
+                labels, metrics.latency_count
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels, metrics.latency_sum
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, metrics.latency_count
+            )
+            .unwrap();
+        }
+
+        if let Some(pool) = &self.pool {
+            let idle = pool.num_idle() as u32;
+            output.push_str("# HELP db_pool_connections Connections of the database pool.\n");
+            output.push_str("# TYPE db_pool_connections gauge\n");
+            writeln!(
+                output,
+                r#"db_pool_connections{{state="active"}} {}"#,
+                pool.size().saturating_sub(idle)
+            )
+            .unwrap();
+            writeln!(output, r#"db_pool_connections{{state="idle"}} {}"#, idle).unwrap();
+        }
+
+        if let Some(query_metrics) = &self.query_metrics {
+            output.push_str(&query_metrics.render());
+        }
+
+        output
+    }
+}
+
+struct RequestStart(Option<Instant>);
+
+pub struct RequestMetricsFairing;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetricsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(metrics) = req.rocket().state::<RequestMetrics>() else {
+            return;
+        };
+        let Some(started_at) = req.local_cache(|| RequestStart(None)).0 else {
+            return;
+        };
+
+        // Labelled with the route template rather than the path to keep the number of series bounded
+        let route = req
+            .route()
+            .map(|route| route.uri.origin.path().to_string())
+            .unwrap_or_else(|| "unmatched".into());
+
+        metrics.record(
+            req.method().as_str(),
+            &route,
+            res.status().code,
+            started_at.elapsed(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::RequestMetrics;
+    use crate::infrastructure::postgres_repository_impl::query_metrics::QueryMetrics;
+
+    #[test]
+    fn renders_request_counts_and_latency_histogram() {
+        let metrics = RequestMetrics::new(None);
+
+        metrics.record("GET", "/drugs", 200, Duration::from_millis(20));
+        metrics.record("GET", "/drugs", 200, Duration::from_millis(300));
+        metrics.record("GET", "/drugs", 422, Duration::from_millis(1));
+
+        let rendered = metrics.render();
+
+        assert!(
+            rendered.contains(r#"http_requests_total{method="GET",route="/drugs",status="200"} 2"#)
+        );
+        assert!(
+            rendered.contains(r#"http_requests_total{method="GET",route="/drugs",status="422"} 1"#)
+        );
+        assert!(rendered.contains(
+            r#"http_request_duration_seconds_bucket{method="GET",route="/drugs",le="0.005"} 1"#
+        ));
+        assert!(rendered.contains(
+            r#"http_request_duration_seconds_bucket{method="GET",route="/drugs",le="0.25"} 2"#
+        ));
+        assert!(rendered.contains(
+            r#"http_request_duration_seconds_bucket{method="GET",route="/drugs",le="+Inf"} 3"#
+        ));
+        assert!(rendered
+            .contains(r#"http_request_duration_seconds_count{method="GET",route="/drugs"} 3"#));
+        assert!(!rendered.contains("db_pool_connections"));
+        assert!(!rendered.contains("db_queries_total"));
+    }
+
+    #[test]
+    fn renders_query_counts_of_attached_query_metrics() {
+        let query_metrics = Arc::new(QueryMetrics::new());
+        let metrics = RequestMetrics::new(None).with_query_metrics(query_metrics.clone());
+
+        query_metrics.record("get_prescription_by_id");
+
+        let rendered = metrics.render();
+
+        assert!(rendered.contains("# TYPE db_queries_total counter"));
+        assert!(rendered.contains(r#"db_queries_total{method="get_prescription_by_id"} 1"#));
+    }
+}
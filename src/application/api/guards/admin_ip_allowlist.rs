@@ -0,0 +1,35 @@
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+use rocket_okapi::request::OpenApiFromRequest;
+
+use super::{authorization::AuthorizationError, client_request_info::ClientRequestInfo};
+use crate::application::api::utils::api_config::ApiConfig;
+
+// Restricts the admin endpoints to the addresses listed in ApiConfig, an empty list allows all
+#[derive(OpenApiFromRequest)]
+pub struct AdminIpAllowlist;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminIpAllowlist {
+    type Error = AuthorizationError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let allowlist = match req.rocket().state::<ApiConfig>() {
+            Some(config) if !config.admin_ip_allowlist.is_empty() => &config.admin_ip_allowlist,
+            _ => return Outcome::Success(Self),
+        };
+
+        let client_request_info = match req.guard::<ClientRequestInfo>().await {
+            Outcome::Success(client_request_info) => client_request_info,
+            _ => return Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
+        };
+
+        if allowlist.contains(&client_request_info.ip_address) {
+            Outcome::Success(Self)
+        } else {
+            Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized))
+        }
+    }
+}
@@ -5,31 +5,87 @@ use rocket::{
 use rocket_okapi::request::OpenApiFromRequest;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
-use crate::{application::sessions::entities::Session, Context};
+use super::client_request_info::ClientRequestInfo;
+use crate::{
+    application::{
+        authentication::entities::UserRole,
+        helpers::authentication_failure_log::{
+            log_authentication_failure, AuthenticationFailureReason,
+        },
+        sessions::{
+            entities::Session, token_codec::TokenDecodingError,
+            use_cases::validate_session::SessionValidationError,
+        },
+    },
+    Context,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum AuthorizationError {
     Unauthorized,
 }
 
-async fn get_session<'r>(req: &'r Request<'_>) -> Option<Session> {
-    let ctx = req.rocket().state::<Context>()?;
+// Expired sessions are rejected with 401 so that clients know to log in again, other failures
+// with 403
+async fn get_session<'r>(req: &'r Request<'_>) -> Result<Session, Status> {
+    let ctx = req.rocket().state::<Context>().ok_or(Status::Forbidden)?;
+
+    match find_session(ctx, req).await {
+        Ok(session) => Ok(session),
+        Err((status, reason)) => {
+            if let Outcome::Success(client) = req.guard::<ClientRequestInfo>().await {
+                log_authentication_failure(None, client.ip_address, &client.user_agent, reason);
+            }
+            Err(status)
+        }
+    }
+}
 
-    let header = req.headers().get_one("Authorization")?;
+async fn find_session(
+    ctx: &Context,
+    req: &Request<'_>,
+) -> Result<Session, (Status, AuthenticationFailureReason)> {
+    let header = req
+        .headers()
+        .get_one("Authorization")
+        .ok_or((Status::Forbidden, AuthenticationFailureReason::MissingToken))?;
     let (_, session_token) = header.split_at(7);
-    let session_id = Uuid::parse_str(session_token).ok()?;
+    let session_id = ctx
+        .sessions_service
+        .decode_session_token(session_token)
+        .map_err(|err| match err {
+            TokenDecodingError::ExpiredToken => (
+                Status::Unauthorized,
+                AuthenticationFailureReason::SessionExpired,
+            ),
+            TokenDecodingError::InvalidToken(_) => {
+                (Status::Forbidden, AuthenticationFailureReason::InvalidToken)
+            }
+        })?;
 
     let session = ctx
         .sessions_service
         .get_session_by_id(session_id)
         .await
-        .ok()?;
+        .map_err(|_| {
+            (
+                Status::Forbidden,
+                AuthenticationFailureReason::SessionNotFound,
+            )
+        })?;
 
-    session.validate().ok()?;
-
-    Some(session)
+    match ctx.sessions_service.validate_session(&session) {
+        Ok(()) => Ok(session),
+        Err(SessionValidationError::SessionExpired) => Err((
+            Status::Unauthorized,
+            AuthenticationFailureReason::SessionExpired,
+        )),
+        Err(SessionValidationError::SessionInvalidated) => Err((
+            Status::Forbidden,
+            AuthenticationFailureReason::SessionInvalidated,
+        )),
+    }
 }
 
 #[rocket::async_trait]
@@ -38,8 +94,8 @@ impl<'r> FromRequest<'r> for Session {
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         match get_session(req).await {
-            Some(session) => Outcome::Success(session),
-            None => Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
+            Ok(session) => Outcome::Success(session),
+            Err(status) => Outcome::Error((status, AuthorizationError::Unauthorized)),
         }
     }
 }
@@ -53,8 +109,9 @@ impl<'r> FromRequest<'r> for DoctorSession {
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         match get_session(req).await {
-            Some(session) if session.doctor_id.is_some() => Outcome::Success(Self(session)),
-            _ => Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
+            Ok(session) if session.doctor_id.is_some() => Outcome::Success(Self(session)),
+            Ok(_) => Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
+            Err(status) => Outcome::Error((status, AuthorizationError::Unauthorized)),
         }
     }
 }
@@ -68,7 +125,52 @@ impl<'r> FromRequest<'r> for PharmacistSession {
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         match get_session(req).await {
-            Some(session) if session.pharmacist_id.is_some() => Outcome::Success(Self(session)),
+            Ok(session) if session.pharmacist_id.is_some() => Outcome::Success(Self(session)),
+            Ok(_) => Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
+            Err(status) => Outcome::Error((status, AuthorizationError::Unauthorized)),
+        }
+    }
+}
+
+#[derive(OpenApiFromRequest)]
+pub struct PatientSession(pub Session);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PatientSession {
+    type Error = AuthorizationError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match get_session(req).await {
+            Ok(session) if session.patient_id.is_some() => Outcome::Success(Self(session)),
+            Ok(_) => Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
+            Err(status) => Outcome::Error((status, AuthorizationError::Unauthorized)),
+        }
+    }
+}
+
+// Admin sessions aren't linked to a doctor, pharmacist or patient, so the role is read from the user
+#[derive(OpenApiFromRequest)]
+pub struct AdminSession(pub Session);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminSession {
+    type Error = AuthorizationError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let session = match get_session(req).await {
+            Ok(session) => session,
+            Err(status) => return Outcome::Error((status, AuthorizationError::Unauthorized)),
+        };
+        let Some(ctx) = req.rocket().state::<Context>() else {
+            return Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized));
+        };
+
+        match ctx
+            .authentication_service
+            .get_user_by_id(session.user_id)
+            .await
+        {
+            Ok(user) if user.role == UserRole::Admin => Outcome::Success(Self(session)),
             _ => Outcome::Error((Status::Forbidden, AuthorizationError::Unauthorized)),
         }
     }
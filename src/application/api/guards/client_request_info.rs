@@ -3,24 +3,52 @@ use std::net::{IpAddr, Ipv4Addr};
 use rocket::request::{FromRequest, Outcome};
 use rocket_okapi::request::OpenApiFromRequest;
 
+use crate::application::api::utils::api_config::ApiConfig;
+
+const UNKNOWN_USER_AGENT: &str = "unknown";
+
 #[derive(Debug, PartialEq, Clone, OpenApiFromRequest)]
 pub struct ClientRequestInfo {
     pub ip_address: IpAddr,
     pub user_agent: String,
 }
 
+// First hop of X-Forwarded-For, then X-Real-IP. Both can be set by anyone, so they're only read
+// when the API runs behind a proxy overwriting them
+fn get_forwarded_ip(req: &rocket::Request<'_>) -> Option<IpAddr> {
+    let forwarded_for = req
+        .headers()
+        .get_one("X-Forwarded-For")
+        .and_then(|value| value.split(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+
+    forwarded_for.or_else(|| {
+        req.headers()
+            .get_one("X-Real-IP")
+            .and_then(|ip| ip.trim().parse().ok())
+    })
+}
+
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for ClientRequestInfo {
     type Error = ();
 
     async fn from_request(req: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
-        let ip_address = req
-            .client_ip()
+        let trust_forwarded_headers = req
+            .rocket()
+            .state::<ApiConfig>()
+            .is_some_and(|config| config.trust_forwarded_headers);
+        let ip_address = trust_forwarded_headers
+            .then(|| get_forwarded_ip(req))
+            .flatten()
+            .or_else(|| req.remote().map(|remote| remote.ip()))
             .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)));
         let user_agent = req
             .headers()
             .get_one("User-Agent")
-            .unwrap_or("")
+            .map(str::trim)
+            .filter(|user_agent| !user_agent.is_empty())
+            .unwrap_or(UNKNOWN_USER_AGENT)
             .to_string();
 
         Outcome::Success(ClientRequestInfo {
@@ -29,3 +57,82 @@ impl<'r> FromRequest<'r> for ClientRequestInfo {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rocket::{get, http::Header, local::asynchronous::Client, routes};
+
+    use super::ClientRequestInfo;
+    use crate::application::api::utils::api_config::ApiConfig;
+
+    #[get("/client")]
+    fn get_client(client: ClientRequestInfo) -> String {
+        format!("{} {}", client.ip_address, client.user_agent)
+    }
+
+    async fn create_client(trust_forwarded_headers: bool) -> Client {
+        let rocket = rocket::build()
+            .manage(ApiConfig {
+                trust_forwarded_headers,
+                ..ApiConfig::default()
+            })
+            .mount("/", routes![get_client]);
+
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    async fn get_client_info(client: &Client, headers: Vec<Header<'static>>) -> String {
+        let mut request = client
+            .get("/client")
+            .remote("10.0.0.1:8000".parse().unwrap());
+        for header in headers {
+            request = request.header(header);
+        }
+
+        request.dispatch().await.into_string().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn takes_ip_from_forwarded_headers_if_trusted() {
+        let client = create_client(true).await;
+
+        assert_eq!(
+            get_client_info(
+                &client,
+                vec![
+                    Header::new("X-Forwarded-For", "203.0.113.7, 10.0.0.2"),
+                    Header::new("X-Real-IP", "198.51.100.1"),
+                    Header::new("User-Agent", "Pharmacy terminal"),
+                ]
+            )
+            .await,
+            "203.0.113.7 Pharmacy terminal"
+        );
+        assert_eq!(
+            get_client_info(&client, vec![Header::new("X-Real-IP", "198.51.100.1")]).await,
+            "198.51.100.1 unknown"
+        );
+        assert_eq!(
+            get_client_info(&client, vec![Header::new("X-Forwarded-For", "not an ip")]).await,
+            "10.0.0.1 unknown"
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_forwarded_headers_if_not_trusted() {
+        let client = create_client(false).await;
+
+        assert_eq!(
+            get_client_info(
+                &client,
+                vec![
+                    Header::new("X-Forwarded-For", "203.0.113.7"),
+                    Header::new("X-Real-IP", "198.51.100.1"),
+                    Header::new("User-Agent", " "),
+                ]
+            )
+            .await,
+            "10.0.0.1 unknown"
+        );
+    }
+}
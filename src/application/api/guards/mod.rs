@@ -1,2 +1,3 @@
+pub mod admin_ip_allowlist;
 pub mod authorization;
 pub mod client_request_info;
\ No newline at end of file
@@ -1,3 +1,6 @@
+pub mod catchers;
 pub mod controllers;
+pub mod fairings;
 pub mod guards;
+pub mod rendering;
 pub mod utils;
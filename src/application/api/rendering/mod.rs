@@ -0,0 +1,2 @@
+pub mod prescription_pdf;
+pub mod prescription_qr;
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use uuid::Uuid;
+
+use crate::domain::prescriptions::entities::{Prescription, PrescriptionType};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const FONT_SIZE: f32 = 12.0;
+const LINE_HEIGHT: f32 = 18.0;
+
+fn format_prescription_type(prescription_type: &PrescriptionType) -> &'static str {
+    match prescription_type {
+        PrescriptionType::Regular => "regular",
+        PrescriptionType::ForAntibiotics => "for antibiotics",
+        PrescriptionType::ForImmunologicalDrugs => "for immunological drugs",
+        PrescriptionType::ForChronicDiseaseDrugs => "for chronic disease drugs",
+    }
+}
+
+// Drugs missing from `drug_names` are listed by their id
+pub fn get_prescription_lines(
+    prescription: &Prescription,
+    drug_names: &HashMap<Uuid, String>,
+) -> Vec<String> {
+    let mut lines = vec![
+        format!("Prescription {}", prescription.code),
+        format!(
+            "Type: {}",
+            format_prescription_type(&prescription.prescription_type)
+        ),
+        format!(
+            "Valid from {} to {}",
+            prescription.start_date.format("%Y-%m-%d"),
+            prescription.end_date.format("%Y-%m-%d")
+        ),
+        String::new(),
+        format!(
+            "Doctor: {} (PWZ {})",
+            prescription.doctor.name, prescription.doctor.pwz_number
+        ),
+        format!(
+            "Patient: {} (PESEL {})",
+            prescription.patient.name, prescription.patient.pesel_number
+        ),
+        String::new(),
+        "Drugs:".to_string(),
+    ];
+
+    lines.extend(prescription.prescribed_drugs.iter().enumerate().map(
+        |(index, prescribed_drug)| {
+            let drug_name = drug_names
+                .get(&prescribed_drug.drug_id)
+                .cloned()
+                .unwrap_or_else(|| prescribed_drug.drug_id.to_string());
+            format!(
+                "{}. {} x {}",
+                index + 1,
+                drug_name,
+                prescribed_drug.quantity
+            )
+        },
+    ));
+
+    lines
+}
+
+// The standard Helvetica font only covers latin characters, so polish letters lose their
+// diacritics and other characters are replaced with `?`
+fn encode_text(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|character| match character {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ó' => 'o',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            'Ą' => 'A',
+            'Ć' => 'C',
+            'Ę' => 'E',
+            'Ł' => 'L',
+            'Ń' => 'N',
+            'Ó' => 'O',
+            'Ś' => 'S',
+            'Ź' | 'Ż' => 'Z',
+            character if character.is_ascii() => character,
+            _ => '?',
+        } as u8)
+        .collect()
+}
+
+pub fn render_prescription_pdf(
+    prescription: &Prescription,
+    drug_names: &HashMap<Uuid, String>,
+) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let page_id = Ref::new(3);
+    let font_id = Ref::new(4);
+    let content_id = Ref::new(5);
+    let font_name = Name(b"F1");
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids([page_id]).count(1);
+
+    let mut page = pdf.page(page_id);
+    page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+    page.parent(page_tree_id);
+    page.contents(content_id);
+    page.resources().fonts().pair(font_name, font_id);
+    page.finish();
+
+    pdf.type1_font(font_id).base_font(Name(b"Helvetica"));
+
+    let mut content = Content::new();
+    content.begin_text();
+    content.set_font(font_name, FONT_SIZE);
+    content.set_leading(LINE_HEIGHT);
+    content.next_line(MARGIN, PAGE_HEIGHT - MARGIN);
+    for line in get_prescription_lines(prescription, drug_names) {
+        content.show(Str(&encode_text(&line)));
+        content.next_line_using_leading();
+    }
+    content.end_text();
+    pdf.stream(content_id, &content.finish());
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    use super::{encode_text, get_prescription_lines, render_prescription_pdf};
+    use crate::domain::prescriptions::entities::{
+        PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFillStatus,
+        PrescriptionPatient, PrescriptionType,
+    };
+
+    fn create_mock_prescription(drug_ids: Vec<Uuid>) -> Prescription {
+        let prescription_id = Uuid::new_v4();
+        let start_date = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        Prescription {
+            id: prescription_id,
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type: PrescriptionType::Regular,
+            start_date,
+            end_date: Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap(),
+            prescribed_drugs: drug_ids
+                .into_iter()
+                .enumerate()
+                .map(|(index, drug_id)| PrescribedDrug {
+                    id: Uuid::new_v4(),
+                    drug_id,
+                    prescription_id,
+                    quantity: index as i32 + 1,
+                    dispensed_quantity: 0,
+                    unit_price_cents: None,
+                    created_at: start_date,
+                    updated_at: start_date,
+                })
+                .collect(),
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at: start_date,
+            updated_at: start_date,
+        }
+    }
+
+    #[test]
+    fn lists_prescription_details() {
+        let drug_id = Uuid::new_v4();
+        let prescription = create_mock_prescription(vec![drug_id]);
+        let drug_names = HashMap::from([(drug_id, "Apap".to_string())]);
+
+        let lines = get_prescription_lines(&prescription, &drug_names);
+
+        assert_eq!(
+            lines,
+            vec![
+                "Prescription 12345678",
+                "Type: regular",
+                "Valid from 2024-03-01 to 2024-03-31",
+                "",
+                "Doctor: John Doctor (PWZ 8463856)",
+                "Patient: John Patient (PESEL 92022900002)",
+                "",
+                "Drugs:",
+                "1. Apap x 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_drugs_without_name_by_id() {
+        let drug_ids = vec![Uuid::new_v4(), Uuid::new_v4()];
+        let prescription = create_mock_prescription(drug_ids.clone());
+        let drug_names = HashMap::from([(drug_ids[0], "Apap".to_string())]);
+
+        let lines = get_prescription_lines(&prescription, &drug_names);
+
+        assert_eq!(lines[8], "1. Apap x 1");
+        assert_eq!(lines[9], format!("2. {} x 2", drug_ids[1]));
+    }
+
+    #[test]
+    fn encodes_polish_characters_without_diacritics() {
+        assert_eq!(encode_text("Żółć Łódź"), b"Zolc Lodz");
+        assert_eq!(encode_text("Apap 💊"), b"Apap ?");
+    }
+
+    #[test]
+    fn renders_pdf_document() {
+        let prescription = create_mock_prescription(vec![Uuid::new_v4()]);
+
+        let pdf = render_prescription_pdf(&prescription, &HashMap::new());
+
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(pdf
+            .windows(b"(Prescription 12345678)".len())
+            .any(|window| window == b"(Prescription 12345678)"));
+    }
+}
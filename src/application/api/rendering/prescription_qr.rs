@@ -0,0 +1,80 @@
+use png::{BitDepth, ColorType, Encoder};
+use qrcode::{types::QrError, Color, QrCode};
+
+const MODULE_SIZE: usize = 8;
+// Scanners need a light margin of at least 4 modules around the code
+const QUIET_ZONE_MODULES: usize = 4;
+const DARK: u8 = 0;
+const LIGHT: u8 = 255;
+
+fn render_pixels(qr_code: &QrCode) -> (usize, Vec<u8>) {
+    let modules_width = qr_code.width();
+    let image_width = (modules_width + 2 * QUIET_ZONE_MODULES) * MODULE_SIZE;
+    let colors = qr_code.to_colors();
+
+    let mut pixels = vec![LIGHT; image_width * image_width];
+    for (index, color) in colors.iter().enumerate() {
+        if *color == Color::Light {
+            continue;
+        }
+        let x = (index % modules_width + QUIET_ZONE_MODULES) * MODULE_SIZE;
+        let y = (index / modules_width + QUIET_ZONE_MODULES) * MODULE_SIZE;
+        for row in y..y + MODULE_SIZE {
+            pixels[row * image_width + x..row * image_width + x + MODULE_SIZE].fill(DARK);
+        }
+    }
+
+    (image_width, pixels)
+}
+
+pub fn render_qr_code_png(data: &str) -> Result<Vec<u8>, QrError> {
+    let qr_code = QrCode::new(data.as_bytes())?;
+    let (image_width, pixels) = render_pixels(&qr_code);
+
+    let mut png = Vec::new();
+    let mut encoder = Encoder::new(&mut png, image_width as u32, image_width as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::Eight);
+    // Writing into a Vec can't fail and the dimensions always match the pixels
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&pixels).unwrap();
+    writer.finish().unwrap();
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use qrcode::QrCode;
+
+    use super::{render_pixels, render_qr_code_png, DARK, LIGHT, MODULE_SIZE, QUIET_ZONE_MODULES};
+
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[test]
+    fn renders_qr_code_as_png() {
+        let png = render_qr_code_png("12345678").unwrap();
+
+        assert!(png.starts_with(PNG_SIGNATURE));
+    }
+
+    #[test]
+    fn renders_modules_with_quiet_zone() {
+        let qr_code = QrCode::new(b"12345678").unwrap();
+
+        let (image_width, pixels) = render_pixels(&qr_code);
+
+        assert_eq!(
+            image_width,
+            (qr_code.width() + 2 * QUIET_ZONE_MODULES) * MODULE_SIZE
+        );
+        assert_eq!(pixels.len(), image_width * image_width);
+        // The top left module of every QR code is a dark part of the finder pattern
+        let quiet_zone_width = QUIET_ZONE_MODULES * MODULE_SIZE;
+        assert_eq!(pixels[0], LIGHT);
+        assert_eq!(
+            pixels[quiet_zone_width * image_width + quiet_zone_width],
+            DARK
+        );
+    }
+}
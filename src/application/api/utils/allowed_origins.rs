@@ -0,0 +1,80 @@
+use rocket::http::uri::Absolute;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AllowedOriginError {
+    #[error("Invalid origin {0}, expected scheme://host[:port] with http or https scheme")]
+    InvalidOrigin(String),
+}
+
+// Origin as sent by the browsers: http(s) scheme and host with an optional port, nothing else
+fn parse_allowed_origin(origin: &str) -> Result<Absolute<'static>, AllowedOriginError> {
+    let invalid_origin = || AllowedOriginError::InvalidOrigin(origin.to_string());
+    let uri = Absolute::parse_owned(origin.to_string()).map_err(|_| invalid_origin())?;
+
+    let is_http = ["http", "https"]
+        .iter()
+        .any(|scheme| uri.scheme().eq_ignore_ascii_case(scheme));
+    let has_only_host = uri
+        .authority()
+        .is_some_and(|authority| !authority.host().is_empty() && authority.user_info().is_none());
+    if !is_http || !has_only_host || !uri.path().is_empty() || uri.query().is_some() {
+        Err(invalid_origin())?;
+    }
+
+    Ok(uri)
+}
+
+// Comma separated list, e.g. https://clinic.example.com,http://localhost:3000
+pub fn parse_allowed_origins(value: &str) -> Result<Vec<Absolute<'static>>, AllowedOriginError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(parse_allowed_origin)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{parse_allowed_origins, AllowedOriginError};
+
+    #[test]
+    fn parses_valid_origins() {
+        let origins = parse_allowed_origins(
+            "https://clinic.example.com, http://localhost:3000,,HTTPS://pharmacy.example.com",
+        )
+        .unwrap();
+
+        assert_eq!(
+            origins
+                .iter()
+                .map(|origin| origin.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://clinic.example.com",
+                "http://localhost:3000",
+                "HTTPS://pharmacy.example.com"
+            ]
+        );
+        assert!(parse_allowed_origins("").unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[case("clinic.example.com")]
+    #[case("ftp://clinic.example.com")]
+    #[case("https://")]
+    #[case("https://clinic.example.com/")]
+    #[case("https://clinic.example.com/app")]
+    #[case("https://clinic.example.com?tab=1")]
+    #[case("https://user@clinic.example.com")]
+    #[case("https://clinic.example.com:port")]
+    #[case("https://clinic example.com")]
+    fn rejects_malformed_origin(#[case] origin: &str) {
+        assert_eq!(
+            parse_allowed_origins(&format!("https://clinic.example.com,{}", origin)),
+            Err(AllowedOriginError::InvalidOrigin(origin.to_string()))
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::net::IpAddr;
+
+use rocket::{
+    data::{Limits, ToByteUnit},
+    http::uri::Absolute,
+};
+
+pub const DEFAULT_MAX_DRUGS_BATCH_SIZE: usize = 100;
+pub const DEFAULT_MAX_PRESCRIPTIONS_BATCH_GET_SIZE: usize = 100;
+
+pub struct ApiConfig {
+    pub max_drugs_batch_size: usize,
+    pub max_prescriptions_batch_get_size: usize,
+    // Masks PESEL numbers in the list endpoints, single entity fetches still return them in full
+    pub mask_pesel_numbers: bool,
+    // Source addresses allowed to call the /admin endpoints, empty allows all
+    pub admin_ip_allowlist: Vec<IpAddr>,
+    // Browser origins allowed to call the API cross-origin, validated at startup
+    pub allowed_origins: Vec<Absolute<'static>>,
+    // Reads the client IP from X-Forwarded-For/X-Real-IP, only safe behind a proxy setting them
+    pub trust_forwarded_headers: bool,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            max_drugs_batch_size: DEFAULT_MAX_DRUGS_BATCH_SIZE,
+            max_prescriptions_batch_get_size: DEFAULT_MAX_PRESCRIPTIONS_BATCH_GET_SIZE,
+            mask_pesel_numbers: false,
+            admin_ip_allowlist: vec![],
+            allowed_origins: vec![],
+            trust_forwarded_headers: false,
+        }
+    }
+}
+
+// Json bodies above the limit are rejected by rocket with 413 before reaching the handlers,
+// raw bytes (CSV imports) are cut at the limit and rejected by the handlers themselves
+pub fn get_data_limits() -> Limits {
+    Limits::default()
+        .limit("json", 256.kibibytes())
+        .limit("bytes", 1.mebibytes())
+}
@@ -0,0 +1,242 @@
+use chrono::{DateTime, Duration, Utc};
+use rocket::form::{self, DataField, FromForm, Options, ValueField};
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+
+pub const DEFAULT_DATE_RANGE_DAYS: i64 = 30;
+
+// Query params `from` and `to` (RFC 3339), mounted with `?<range..>`. `to` defaults to now and
+// `from` to 30 days before `to`, malformed dates and `from` after `to` are rejected with 422
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+pub struct DateRangeContext<'r> {
+    from: Option<&'r str>,
+    to: Option<&'r str>,
+}
+
+fn parse_date_time<'r>(
+    name: &'r str,
+    value: Option<&'r str>,
+) -> form::Result<'r, Option<DateTime<Utc>>> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|date_time| date_time.with_timezone(&Utc))
+                .map_err(|err| {
+                    form::Error::validation(err.to_string())
+                        .with_name(name)
+                        .into()
+                })
+        })
+        .transpose()
+}
+
+#[rocket::async_trait]
+impl<'r> FromForm<'r> for DateRange {
+    type Context = DateRangeContext<'r>;
+
+    fn init(_: Options) -> Self::Context {
+        DateRangeContext {
+            from: None,
+            to: None,
+        }
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: ValueField<'r>) {
+        match field.name.key_lossy().as_str() {
+            "from" => ctxt.from = Some(field.value),
+            "to" => ctxt.to = Some(field.value),
+            _ => {}
+        }
+    }
+
+    async fn push_data(_: &mut Self::Context, _: DataField<'r, '_>) {}
+
+    fn finalize(ctxt: Self::Context) -> form::Result<'r, Self> {
+        let to = parse_date_time("to", ctxt.to)?.unwrap_or_else(Utc::now);
+        let from = parse_date_time("from", ctxt.from)?
+            .unwrap_or(to - Duration::days(DEFAULT_DATE_RANGE_DAYS));
+
+        if from > to {
+            Err(form::Error::validation("from can't be later than to").with_name("from"))?;
+        }
+
+        Ok(Self { from, to })
+    }
+}
+
+// Query params `created_from` and `created_to` (RFC 3339), mounted with `?<created..>`. Both are
+// optional, but unlike plain Option params malformed dates are rejected with 422 instead of ignored
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CreatedAtRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromForm<'r> for CreatedAtRange {
+    type Context = DateRangeContext<'r>;
+
+    fn init(_: Options) -> Self::Context {
+        DateRangeContext {
+            from: None,
+            to: None,
+        }
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: ValueField<'r>) {
+        match field.name.key_lossy().as_str() {
+            "created_from" => ctxt.from = Some(field.value),
+            "created_to" => ctxt.to = Some(field.value),
+            _ => {}
+        }
+    }
+
+    async fn push_data(_: &mut Self::Context, _: DataField<'r, '_>) {}
+
+    fn finalize(ctxt: Self::Context) -> form::Result<'r, Self> {
+        Ok(Self {
+            from: parse_date_time("created_from", ctxt.from)?,
+            to: parse_date_time("created_to", ctxt.to)?,
+        })
+    }
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct DateRangeSchema {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+impl JsonSchema for DateRange {
+    fn schema_name() -> String {
+        "DateRange".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        DateRangeSchema::json_schema(gen)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct CreatedAtRangeSchema {
+    created_from: Option<DateTime<Utc>>,
+    created_to: Option<DateTime<Utc>>,
+}
+
+impl JsonSchema for CreatedAtRange {
+    fn schema_name() -> String {
+        "CreatedAtRange".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        CreatedAtRangeSchema::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use rocket::{
+        form::Form,
+        get,
+        http::Status,
+        local::asynchronous::Client,
+        routes,
+        serde::json::{self, Json, Value},
+    };
+
+    use super::{CreatedAtRange, DateRange};
+
+    #[test]
+    fn defaults_to_last_30_days() {
+        let before = Utc::now();
+        let sut = Form::<DateRange>::parse("").unwrap();
+        let after = Utc::now();
+
+        assert!(sut.to >= before && sut.to <= after);
+        assert_eq!(sut.to - sut.from, Duration::days(30));
+    }
+
+    #[test]
+    fn defaults_from_to_30_days_before_to() {
+        let sut = Form::<DateRange>::parse("to=2024-03-31T00:00:00Z").unwrap();
+
+        assert_eq!(sut.to, Utc.with_ymd_and_hms(2024, 3, 31, 0, 0, 0).unwrap());
+        assert_eq!(sut.from, Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn parses_explicit_range() {
+        let sut =
+            Form::<DateRange>::parse("from=2024-03-01T00:00:00Z&to=2024-03-02T12:00:00+02:00")
+                .unwrap();
+
+        assert_eq!(sut.from, Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+        assert_eq!(sut.to, Utc.with_ymd_and_hms(2024, 3, 2, 10, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn rejects_inverted_range() {
+        let sut = Form::<DateRange>::parse("from=2024-03-02T00:00:00Z&to=2024-03-01T00:00:00Z");
+
+        assert!(sut.is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_date_time() {
+        assert!(Form::<DateRange>::parse("from=2024-03-01").is_err());
+        assert!(Form::<DateRange>::parse("to=yesterday").is_err());
+    }
+
+    #[test]
+    fn parses_optional_created_at_range() {
+        assert_eq!(
+            Form::<CreatedAtRange>::parse("").unwrap(),
+            CreatedAtRange::default()
+        );
+        assert_eq!(
+            Form::<CreatedAtRange>::parse("created_to=2024-03-02T12:00:00+02:00").unwrap(),
+            CreatedAtRange {
+                from: None,
+                to: Some(Utc.with_ymd_and_hms(2024, 3, 2, 10, 0, 0).unwrap()),
+            }
+        );
+        assert!(Form::<CreatedAtRange>::parse("created_from=yesterday").is_err());
+    }
+
+    #[get("/report?<range..>")]
+    fn report(range: DateRange) -> Json<Value> {
+        Json(json::json!({ "from": range.from, "to": range.to }))
+    }
+
+    #[tokio::test]
+    async fn returns_unprocessable_entity_if_range_is_invalid() {
+        let client = Client::tracked(rocket::build().mount("/", routes![report]))
+            .await
+            .unwrap();
+
+        let response = client
+            .get("/report?from=2024-03-01T00:00:00Z&to=2024-03-02T00:00:00Z")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client.get("/report").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get("/report?from=2024-03-02T00:00:00Z&to=2024-03-01T00:00:00Z")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client.get("/report?from=not-a-date").dispatch().await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+}
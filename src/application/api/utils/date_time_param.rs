@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use rocket::form::{self, FromFormField, ValueField};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, JsonSchema)]
+pub struct DateTimeParam(pub DateTime<Utc>);
+
+impl<'v> FromFormField<'v> for DateTimeParam {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        let date_time = DateTime::parse_from_rfc3339(field.value)
+            .map_err(|err| form::Error::validation(err.to_string()))?;
+
+        Ok(Self(date_time.with_timezone(&Utc)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rocket::form::{FromFormField, ValueField};
+
+    use super::DateTimeParam;
+
+    #[test]
+    fn parses_rfc3339_date_time() {
+        let sut = DateTimeParam::from_value(ValueField::parse("before=2024-03-01T10:00:00Z"));
+
+        assert_eq!(
+            sut.unwrap(),
+            DateTimeParam(Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn converts_offset_to_utc() {
+        let sut = DateTimeParam::from_value(ValueField::parse("before=2024-03-01T12:00:00+02:00"));
+
+        assert_eq!(
+            sut.unwrap(),
+            DateTimeParam(Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_date_time() {
+        let sut = DateTimeParam::from_value(ValueField::parse("before=2024-03-01"));
+
+        assert!(sut.is_err());
+    }
+}
@@ -6,13 +6,20 @@ use rocket::{
     serde::json,
     Request, Response,
 };
+use schemars::JsonSchema;
 use serde::Serialize;
 
-#[derive(Serialize)]
+use super::messages::{translate_message, Locale};
+
+pub const RETRY_AFTER_SECONDS: u32 = 5;
+
+#[derive(Serialize, JsonSchema)]
 pub struct ApiError {
     pub message: String,
     pub path: String,
+    #[schemars(with = "u16")]
     pub status: http::Status,
+    #[schemars(with = "String")]
     pub method: http::Method,
     pub timestamp_ms: i64,
 }
@@ -35,14 +42,23 @@ impl ApiError {
     ) -> rocket::response::Result<'static> {
         let path = req.uri().path().to_string();
         let method = req.method();
+        let locale = Locale::from_accept_language(req.headers().get_one("Accept-Language"));
+        let message = translate_message(message, locale);
 
         let error = Self::new(message, path, status, method);
         let body = json::to_string(&error).unwrap();
 
-        Response::build()
+        let mut response = Response::build();
+        response
             .sized_body(body.len(), std::io::Cursor::new(body))
             .header(ContentType::JSON)
-            .status(status)
-            .ok()
+            .status(status);
+
+        // Tells the client when to try again, for instance after the database pool got exhausted
+        if status == http::Status::ServiceUnavailable {
+            response.raw_header("Retry-After", RETRY_AFTER_SECONDS.to_string());
+        }
+
+        response.ok()
     }
 }
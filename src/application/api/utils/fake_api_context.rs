@@ -5,14 +5,20 @@ use crate::{
         authentication::{
             repository::AuthenticationRepositoryFake, service::AuthenticationService,
         },
-        sessions::{repository::SessionsRepositoryFake, service::SessionsService},
+        sessions::{
+            repository::SessionsRepositoryFake, service::SessionsService,
+            token_codec::UuidTokenCodec,
+        },
     },
     domain::{
         doctors::{repository::DoctorsRepositoryFake, service::DoctorsService},
         drugs::{repository::DrugsRepositoryFake, service::DrugsService},
         patients::{repository::PatientsRepositoryFake, service::PatientsService},
         pharmacists::{repository::PharmacistsRepositoryFake, service::PharmacistsService},
-        prescriptions::{repository::PrescriptionsRepositoryFake, service::PrescriptionsService},
+        prescriptions::{
+            policy::PrescriptionPolicy, repository::PrescriptionsRepositoryFake,
+            service::PrescriptionsService,
+        },
     },
     Context,
 };
@@ -33,13 +39,19 @@ pub fn create_fake_api_context() -> Context {
     let prescriptions_repository = Box::new(PrescriptionsRepositoryFake::new(
         None, None, None, None, None,
     ));
-    let prescriptions_service = Arc::new(PrescriptionsService::new(prescriptions_repository));
+    let prescriptions_service = Arc::new(PrescriptionsService::new(
+        prescriptions_repository,
+        PrescriptionPolicy::default(),
+    ));
 
     let authentication_repository = Box::new(AuthenticationRepositoryFake::new());
     let authentication_service = Arc::new(AuthenticationService::new(authentication_repository));
 
     let sessions_repository = Box::new(SessionsRepositoryFake::new());
-    let sessions_service = Arc::new(SessionsService::new(sessions_repository));
+    let sessions_service = Arc::new(SessionsService::new(
+        sessions_repository,
+        Box::new(UuidTokenCodec),
+    ));
 
     Context {
         doctors_service,
@@ -0,0 +1,191 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Locale {
+    En,
+    Pl,
+}
+
+impl Locale {
+    // Picks the first supported language from the header, quality values are not taken into account
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        header
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|language_range| {
+                let tag = language_range.split(';').next()?.trim();
+                let primary_subtag = tag.split('-').next()?.to_lowercase();
+                match primary_subtag.as_str() {
+                    "pl" => Some(Self::Pl),
+                    "en" => Some(Self::En),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(Self::En)
+    }
+}
+
+// English messages of the common errors with their polish variants, `{}` stands for the dynamic
+// part of the message (for instance an id)
+const POLISH_MESSAGES: &[(&str, &str)] = &[
+    (
+        "PESEL number must be 11 characters long and contain only digits",
+        "Numer PESEL musi mieć 11 znaków i składać się wyłącznie z cyfr",
+    ),
+    (
+        "The checksum of PESEL number is incorrect",
+        "Suma kontrolna numeru PESEL jest nieprawidłowa",
+    ),
+    (
+        "The date part of PESEL number is incorrect",
+        "Data zawarta w numerze PESEL jest nieprawidłowa",
+    ),
+    (
+        "PWZ number must be 7 characters long and contain only digits",
+        "Numer PWZ musi mieć 7 znaków i składać się wyłącznie z cyfr",
+    ),
+    (
+        "The checksum of PWZ number is incorrect",
+        "Suma kontrolna numeru PWZ jest nieprawidłowa",
+    ),
+    (
+        "Barcode must be 8, 12, 13 or 14 characters long and contain only digits",
+        "Kod kreskowy musi mieć 8, 12, 13 lub 14 znaków i składać się wyłącznie z cyfr",
+    ),
+    (
+        "The checksum of barcode is incorrect",
+        "Suma kontrolna kodu kreskowego jest nieprawidłowa",
+    ),
+    ("PESEL number already exists", "Numer PESEL już istnieje"),
+    ("PWZ number already exists", "Numer PWZ już istnieje"),
+    ("Barcode already exists", "Kod kreskowy już istnieje"),
+    (
+        "Doctor with this id not found ({})",
+        "Nie znaleziono lekarza o podanym id ({})",
+    ),
+    (
+        "Doctor with this PWZ number not found ({})",
+        "Nie znaleziono lekarza o podanym numerze PWZ ({})",
+    ),
+    (
+        "Patient with this id not found ({})",
+        "Nie znaleziono pacjenta o podanym id ({})",
+    ),
+    (
+        "Pharmacist with this id not found ({})",
+        "Nie znaleziono farmaceuty o podanym id ({})",
+    ),
+    (
+        "Drug with this id not found ({})",
+        "Nie znaleziono leku o podanym id ({})",
+    ),
+    (
+        "Drug with this barcode not found ({})",
+        "Nie znaleziono leku o podanym kodzie kreskowym ({})",
+    ),
+    (
+        "User with this id not found ({})",
+        "Nie znaleziono użytkownika o podanym id ({})",
+    ),
+    (
+        "Doctor with id {} not found",
+        "Nie znaleziono lekarza o id {}",
+    ),
+    (
+        "Patient with id {} not found",
+        "Nie znaleziono pacjenta o id {}",
+    ),
+    (
+        "Pharmacist with id {} not found",
+        "Nie znaleziono farmaceuty o id {}",
+    ),
+    ("Drug with id {} not found", "Nie znaleziono leku o id {}"),
+    (
+        "Prescription with id {} not found",
+        "Nie znaleziono recepty o id {}",
+    ),
+    ("Not Found", "Nie znaleziono"),
+    ("Internal Server Error", "Wewnętrzny błąd serwera"),
+];
+
+fn match_pattern<'a>(message: &'a str, pattern: &str) -> Option<&'a str> {
+    match pattern.split_once("{}") {
+        Some((prefix, suffix)) => message
+            .strip_prefix(prefix)?
+            .strip_suffix(suffix)
+            .filter(|argument| !argument.is_empty()),
+        None => (message == pattern).then_some(""),
+    }
+}
+
+// Messages without a translation are returned unchanged
+pub fn translate_message(message: String, locale: Locale) -> String {
+    if locale == Locale::En {
+        return message;
+    }
+
+    POLISH_MESSAGES
+        .iter()
+        .find_map(|(english, polish)| {
+            match_pattern(&message, english).map(|argument| polish.replace("{}", argument))
+        })
+        .unwrap_or(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{translate_message, Locale};
+
+    #[test]
+    fn picks_locale_from_accept_language_header() {
+        assert_eq!(Locale::from_accept_language(Some("pl")), Locale::Pl);
+        assert_eq!(Locale::from_accept_language(Some("pl-PL")), Locale::Pl);
+        assert_eq!(
+            Locale::from_accept_language(Some("de-DE,pl;q=0.8,en;q=0.5")),
+            Locale::Pl
+        );
+        assert_eq!(
+            Locale::from_accept_language(Some("en-US,pl;q=0.5")),
+            Locale::En
+        );
+    }
+
+    #[test]
+    fn defaults_to_english_if_header_is_missing_or_unknown() {
+        assert_eq!(Locale::from_accept_language(None), Locale::En);
+        assert_eq!(Locale::from_accept_language(Some("")), Locale::En);
+        assert_eq!(Locale::from_accept_language(Some("de, fr")), Locale::En);
+    }
+
+    #[test]
+    fn translates_messages_to_polish() {
+        assert_eq!(
+            translate_message(
+                "The checksum of PESEL number is incorrect".into(),
+                Locale::Pl
+            ),
+            "Suma kontrolna numeru PESEL jest nieprawidłowa"
+        );
+        assert_eq!(
+            translate_message(
+                "Drug with this id not found (00000000-0000-0000-0000-000000000000)".into(),
+                Locale::Pl
+            ),
+            "Nie znaleziono leku o podanym id (00000000-0000-0000-0000-000000000000)"
+        );
+    }
+
+    #[test]
+    fn leaves_messages_unchanged_in_english_or_without_translation() {
+        assert_eq!(
+            translate_message(
+                "The checksum of PESEL number is incorrect".into(),
+                Locale::En
+            ),
+            "The checksum of PESEL number is incorrect"
+        );
+        assert_eq!(
+            translate_message("Session is expired".into(), Locale::Pl),
+            "Session is expired"
+        );
+    }
+}
@@ -1,3 +1,10 @@
+pub mod allowed_origins;
+pub mod api_config;
+pub mod date_range;
+pub mod date_time_param;
 pub mod error;
+pub mod messages;
 pub mod openapi_responses;
+pub mod pagination_messages;
+pub mod pesel_masking;
 pub mod fake_api_context;
\ No newline at end of file
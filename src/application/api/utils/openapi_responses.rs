@@ -1,12 +1,20 @@
-use okapi::openapi3::{RefOr, Response as OpenApiReponse, Responses};
-use rocket_okapi::OpenApiError;
+use okapi::{
+    map,
+    openapi3::{MediaType, RefOr, Response as OpenApiReponse, Responses},
+};
+use rocket_okapi::{gen::OpenApiGenerator, OpenApiError};
 use schemars::Map;
 
+use super::error::ApiError;
+
 type ResponseDescription = (&'static str, &'static str); // (status_code, description)
 
 pub fn get_openapi_responses(
+    gen: &mut OpenApiGenerator,
     descriptions: Vec<ResponseDescription>,
 ) -> Result<Responses, OpenApiError> {
+    // Registers ApiError in the components once and references it from every error response
+    let schema = gen.json_schema::<ApiError>();
     let mut responses = Map::new();
 
     for (status_code, description) in descriptions {
@@ -14,6 +22,12 @@ pub fn get_openapi_responses(
             status_code.to_string(),
             RefOr::Object(OpenApiReponse {
                 description: description.to_string(),
+                content: map! {
+                    "application/json".to_string() => MediaType {
+                        schema: Some(schema.clone()),
+                        ..Default::default()
+                    }
+                },
                 ..Default::default()
             }),
         );
@@ -24,3 +38,38 @@ pub fn get_openapi_responses(
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use okapi::openapi3::RefOr;
+    use rocket_okapi::openapi_get_spec;
+
+    use crate::application::api::controllers::pharmacists_controller;
+
+    #[test]
+    fn references_shared_api_error_schema_in_error_responses() {
+        let spec = openapi_get_spec![pharmacists_controller::create_pharmacist];
+
+        let components = spec.components.unwrap();
+        let api_error_schema = components.schemas.get("ApiError").unwrap();
+        let properties = &api_error_schema.object.as_ref().unwrap().properties;
+
+        assert!(properties.contains_key("message"));
+        assert!(properties.contains_key("status"));
+
+        let operation = spec.paths["/pharmacists"].post.as_ref().unwrap();
+        let conflict_response = match &operation.responses.responses["409"] {
+            RefOr::Object(response) => response,
+            RefOr::Ref(_) => panic!("Expected an inline response"),
+        };
+        let schema = conflict_response.content["application/json"]
+            .schema
+            .as_ref()
+            .unwrap();
+
+        assert_eq!(
+            schema.reference,
+            Some("#/components/schemas/ApiError".to_string())
+        );
+    }
+}
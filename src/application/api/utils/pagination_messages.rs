@@ -0,0 +1,10 @@
+use crate::domain::utils::pagination::{PaginationError, MAX_PAGE_SIZE};
+
+// Every reason gets its own fixed message, so clients can tell them apart without parsing it
+pub fn pagination_error_message(err: &PaginationError) -> String {
+    match err {
+        PaginationError::NegativePage => "Page must not be negative".into(),
+        PaginationError::ZeroOrNegativePageSize => "Page size must be at least 1".into(),
+        PaginationError::PageSizeTooLarge => format!("Page size must not exceed {MAX_PAGE_SIZE}"),
+    }
+}
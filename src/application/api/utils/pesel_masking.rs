@@ -0,0 +1,73 @@
+use crate::domain::{
+    doctors::entities::Doctor, patients::entities::Patient, pharmacists::entities::Pharmacist,
+};
+
+// The date of birth part is kept, so that entries can still be told apart in the lists
+const VISIBLE_PESEL_DIGITS: usize = 6;
+
+pub fn mask_pesel_number(pesel_number: &str) -> String {
+    pesel_number
+        .chars()
+        .enumerate()
+        .map(|(index, digit)| {
+            if index < VISIBLE_PESEL_DIGITS {
+                digit
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+pub trait MaskPeselNumber {
+    fn with_masked_pesel_number(self) -> Self;
+}
+
+impl MaskPeselNumber for Patient {
+    fn with_masked_pesel_number(self) -> Self {
+        Self {
+            pesel_number: mask_pesel_number(&self.pesel_number),
+            ..self
+        }
+    }
+}
+
+impl MaskPeselNumber for Doctor {
+    fn with_masked_pesel_number(self) -> Self {
+        Self {
+            pesel_number: mask_pesel_number(&self.pesel_number),
+            ..self
+        }
+    }
+}
+
+impl MaskPeselNumber for Pharmacist {
+    fn with_masked_pesel_number(self) -> Self {
+        Self {
+            pesel_number: mask_pesel_number(&self.pesel_number),
+            ..self
+        }
+    }
+}
+
+pub fn mask_pesel_numbers<T: MaskPeselNumber>(entities: Vec<T>, mask: bool) -> Vec<T> {
+    if !mask {
+        return entities;
+    }
+
+    entities
+        .into_iter()
+        .map(MaskPeselNumber::with_masked_pesel_number)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mask_pesel_number;
+
+    #[test]
+    fn masks_all_but_date_of_birth_digits() {
+        assert_eq!(mask_pesel_number("96021807250"), "960218*****");
+        assert_eq!(mask_pesel_number("9602"), "9602");
+    }
+}
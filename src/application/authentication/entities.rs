@@ -1,15 +1,41 @@
+use std::net::IpAddr;
+
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::{doctors::entities::Doctor, pharmacists::entities::Pharmacist};
+use crate::domain::{
+    doctors::entities::Doctor, patients::entities::Patient, pharmacists::entities::Pharmacist,
+};
 
-#[derive(sqlx::Type, Debug, PartialEq, Clone, Copy, Serialize)]
+#[derive(sqlx::Type, Debug, PartialEq, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 #[sqlx(type_name = "user_role", rename_all = "snake_case")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum UserRole {
     Doctor,
     Pharmacist,
+    Patient,
+    // Isn't linked to a doctor, pharmacist or patient, created from the environment at startup
+    Admin,
+}
+
+// The doctor, pharmacist or patient a registered user logs in as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserLink {
+    Doctor(Uuid),
+    Pharmacist(Uuid),
+    Patient(Uuid),
+}
+
+impl UserLink {
+    pub fn role(&self) -> UserRole {
+        match self {
+            UserLink::Doctor(_) => UserRole::Doctor,
+            UserLink::Pharmacist(_) => UserRole::Pharmacist,
+            UserLink::Patient(_) => UserRole::Patient,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +48,7 @@ pub struct NewUser {
     pub role: UserRole,
     pub doctor_id: Option<Uuid>,
     pub pharmacist_id: Option<Uuid>,
+    pub patient_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -33,14 +60,29 @@ pub struct User {
     pub email: String,
     pub phone_number: String,
     pub role: UserRole,
+    pub is_active: bool,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub last_login_ip: Option<IpAddr>,
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    // Set once the secret is confirmed with a valid code, only then the code is required to log in
+    pub totp_enabled: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub doctor: Option<Doctor>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pharmacist: Option<Pharmacist>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patient: Option<Patient>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
 impl PartialEq<NewUser> for User {
     fn eq(&self, other: &NewUser) -> bool {
         self.id == other.id
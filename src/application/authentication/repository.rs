@@ -1,15 +1,20 @@
-use std::sync::RwLock;
+use std::{net::IpAddr, sync::RwLock};
 
 use chrono::Utc;
 use rocket::async_trait;
+use uuid::Uuid;
 
 use super::entities::{NewUser, User};
-use crate::domain::{doctors::entities::Doctor, pharmacists::entities::Pharmacist};
+use crate::domain::{
+    doctors::entities::Doctor, patients::entities::Patient, pharmacists::entities::Pharmacist,
+};
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreateUserRepositoryError {
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -18,6 +23,48 @@ pub enum GetUserRepositoryError {
     NotFound(String),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetUserByIdRepositoryError {
+    #[error("User with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum SetUserActiveRepositoryError {
+    #[error("User with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum UpdateLastLoginRepositoryError {
+    #[error("User with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum UpdateTotpRepositoryError {
+    #[error("User with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -27,6 +74,24 @@ pub trait AuthenticationRepository: Send + Sync + 'static {
         &self,
         username: &'a str,
     ) -> Result<User, GetUserRepositoryError>;
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, GetUserByIdRepositoryError>;
+    async fn set_user_active(
+        &self,
+        user_id: Uuid,
+        active: bool,
+    ) -> Result<User, SetUserActiveRepositoryError>;
+    async fn update_last_login(
+        &self,
+        user_id: Uuid,
+        ip_address: IpAddr,
+    ) -> Result<User, UpdateLastLoginRepositoryError>;
+    // Replaces the secret and disables TOTP until the new secret is confirmed
+    async fn set_totp_secret(
+        &self,
+        user_id: Uuid,
+        totp_secret: String,
+    ) -> Result<User, UpdateTotpRepositoryError>;
+    async fn enable_totp(&self, user_id: Uuid) -> Result<User, UpdateTotpRepositoryError>;
 }
 
 pub struct AuthenticationRepositoryFake {
@@ -52,13 +117,19 @@ impl AuthenticationRepository for AuthenticationRepositoryFake {
             email: new_user.email,
             phone_number: new_user.phone_number,
             role: new_user.role,
+            is_active: true,
+            last_login_at: None,
+            last_login_ip: None,
+            totp_secret: None,
+            totp_enabled: false,
             doctor: new_user.doctor_id.map(|id| Doctor {
                 id,
                 name: "Joe Doctor".to_string(),
-                pwz_number: "8463856".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
                 pesel_number: "92022900002".to_string(),
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
+                specialization: None,
             }),
             pharmacist: new_user.pharmacist_id.map(|id| Pharmacist {
                 id,
@@ -67,6 +138,14 @@ impl AuthenticationRepository for AuthenticationRepositoryFake {
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             }),
+            patient: new_user.patient_id.map(|id| Patient {
+                id,
+                name: "Joe Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                created_by: None,
+            }),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -88,13 +167,107 @@ impl AuthenticationRepository for AuthenticationRepositoryFake {
             .ok_or(GetUserRepositoryError::NotFound(username.to_owned()))
             .map(|user| user.to_owned())
     }
+
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, GetUserByIdRepositoryError> {
+        self.users
+            .read()
+            .unwrap()
+            .iter()
+            .find(|user| user.id == user_id)
+            .ok_or(GetUserByIdRepositoryError::NotFound(user_id))
+            .map(|user| user.to_owned())
+    }
+
+    async fn set_user_active(
+        &self,
+        user_id: Uuid,
+        active: bool,
+    ) -> Result<User, SetUserActiveRepositoryError> {
+        match self
+            .users
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|user| user.id == user_id)
+        {
+            Some(user) => {
+                user.is_active = active;
+                user.updated_at = Utc::now();
+                Ok(user.clone())
+            }
+            None => Err(SetUserActiveRepositoryError::NotFound(user_id)),
+        }
+    }
+
+    async fn update_last_login(
+        &self,
+        user_id: Uuid,
+        ip_address: IpAddr,
+    ) -> Result<User, UpdateLastLoginRepositoryError> {
+        match self
+            .users
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|user| user.id == user_id)
+        {
+            Some(user) => {
+                user.last_login_at = Some(Utc::now());
+                user.last_login_ip = Some(ip_address);
+                Ok(user.clone())
+            }
+            None => Err(UpdateLastLoginRepositoryError::NotFound(user_id)),
+        }
+    }
+
+    async fn set_totp_secret(
+        &self,
+        user_id: Uuid,
+        totp_secret: String,
+    ) -> Result<User, UpdateTotpRepositoryError> {
+        match self
+            .users
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|user| user.id == user_id)
+        {
+            Some(user) => {
+                user.totp_secret = Some(totp_secret);
+                user.totp_enabled = false;
+                user.updated_at = Utc::now();
+                Ok(user.clone())
+            }
+            None => Err(UpdateTotpRepositoryError::NotFound(user_id)),
+        }
+    }
+
+    async fn enable_totp(&self, user_id: Uuid) -> Result<User, UpdateTotpRepositoryError> {
+        match self
+            .users
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|user| user.id == user_id)
+        {
+            Some(user) => {
+                user.totp_enabled = true;
+                user.updated_at = Utc::now();
+                Ok(user.clone())
+            }
+            None => Err(UpdateTotpRepositoryError::NotFound(user_id)),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use uuid::Uuid;
 
-    use super::{AuthenticationRepository, AuthenticationRepositoryFake};
+    use super::{
+        AuthenticationRepository, AuthenticationRepositoryFake, GetUserByIdRepositoryError,
+        SetUserActiveRepositoryError, UpdateLastLoginRepositoryError, UpdateTotpRepositoryError,
+    };
     use crate::application::authentication::entities::{NewUser, UserRole};
 
     fn setup_repository() -> AuthenticationRepositoryFake {
@@ -110,6 +283,7 @@ mod tests {
             UserRole::Doctor,
             Some(Uuid::default()),
             None,
+            None,
         )
         .unwrap()
     }
@@ -130,4 +304,156 @@ mod tests {
 
         assert_eq!(created_user, user_by_username);
     }
+
+    #[tokio::test]
+    async fn creates_new_user_and_reads_by_id() {
+        let repository = setup_repository();
+        let mock_new_user = create_mock_new_user();
+
+        let created_user = repository.create_user(mock_new_user.clone()).await.unwrap();
+
+        let user_by_id = repository.get_user_by_id(created_user.id).await.unwrap();
+
+        assert_eq!(created_user, user_by_id);
+    }
+
+    #[tokio::test]
+    async fn returns_not_found_if_user_with_given_id_doesnt_exist() {
+        let repository = setup_repository();
+        repository
+            .create_user(create_mock_new_user())
+            .await
+            .unwrap();
+
+        let user_id = Uuid::new_v4();
+        let result = repository.get_user_by_id(user_id).await;
+
+        assert_eq!(result, Err(GetUserByIdRepositoryError::NotFound(user_id)));
+    }
+
+    #[tokio::test]
+    async fn deactivates_and_reactivates_user() {
+        let repository = setup_repository();
+        let created_user = repository
+            .create_user(create_mock_new_user())
+            .await
+            .unwrap();
+
+        assert!(created_user.is_active);
+
+        let deactivated_user = repository
+            .set_user_active(created_user.id, false)
+            .await
+            .unwrap();
+
+        assert!(!deactivated_user.is_active);
+        assert!(
+            !repository
+                .get_user_by_id(created_user.id)
+                .await
+                .unwrap()
+                .is_active
+        );
+
+        let reactivated_user = repository
+            .set_user_active(created_user.id, true)
+            .await
+            .unwrap();
+
+        assert!(reactivated_user.is_active);
+    }
+
+    #[tokio::test]
+    async fn set_user_active_returns_not_found_if_user_doesnt_exist() {
+        let repository = setup_repository();
+        let user_id = Uuid::new_v4();
+
+        let result = repository.set_user_active(user_id, false).await;
+
+        assert_eq!(result, Err(SetUserActiveRepositoryError::NotFound(user_id)));
+    }
+
+    #[tokio::test]
+    async fn updates_last_login() {
+        let repository = setup_repository();
+        let created_user = repository
+            .create_user(create_mock_new_user())
+            .await
+            .unwrap();
+
+        assert_eq!(created_user.last_login_at, None);
+        assert_eq!(created_user.last_login_ip, None);
+
+        repository
+            .update_last_login(created_user.id, "192.168.1.10".parse().unwrap())
+            .await
+            .unwrap();
+
+        let user = repository.get_user_by_id(created_user.id).await.unwrap();
+
+        assert!(user.last_login_at.is_some());
+        assert_eq!(user.last_login_ip, Some("192.168.1.10".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn update_last_login_returns_not_found_if_user_doesnt_exist() {
+        let repository = setup_repository();
+        let user_id = Uuid::new_v4();
+
+        let result = repository
+            .update_last_login(user_id, "192.168.1.10".parse().unwrap())
+            .await;
+
+        assert_eq!(
+            result,
+            Err(UpdateLastLoginRepositoryError::NotFound(user_id))
+        );
+    }
+
+    #[tokio::test]
+    async fn sets_totp_secret_and_enables_totp() {
+        let repository = setup_repository();
+        let created_user = repository
+            .create_user(create_mock_new_user())
+            .await
+            .unwrap();
+
+        assert_eq!(created_user.totp_secret, None);
+        assert!(!created_user.totp_enabled);
+
+        let user = repository
+            .set_totp_secret(created_user.id, "JBSWY3DPEHPK3PXP".into())
+            .await
+            .unwrap();
+
+        assert_eq!(user.totp_secret, Some("JBSWY3DPEHPK3PXP".into()));
+        assert!(!user.totp_enabled);
+
+        repository.enable_totp(created_user.id).await.unwrap();
+
+        assert!(
+            repository
+                .get_user_by_id(created_user.id)
+                .await
+                .unwrap()
+                .totp_enabled
+        );
+
+        let user = repository
+            .set_totp_secret(created_user.id, "KRSXG5CTMVRXEZLU".into())
+            .await
+            .unwrap();
+
+        assert!(!user.totp_enabled);
+    }
+
+    #[tokio::test]
+    async fn enable_totp_returns_not_found_if_user_doesnt_exist() {
+        let repository = setup_repository();
+        let user_id = Uuid::new_v4();
+
+        let result = repository.enable_totp(user_id).await;
+
+        assert_eq!(result, Err(UpdateTotpRepositoryError::NotFound(user_id)));
+    }
 }
@@ -1,10 +1,20 @@
+use std::net::IpAddr;
+
 use uuid::Uuid;
 
 use super::{
-    entities::{NewUser, User, UserRole},
-    repository::{AuthenticationRepository, CreateUserRepositoryError},
+    entities::{NewUser, TotpEnrollment, User, UserLink, UserRole},
+    repository::{
+        AuthenticationRepository, CreateUserRepositoryError, GetUserByIdRepositoryError,
+        GetUserRepositoryError, SetUserActiveRepositoryError, UpdateLastLoginRepositoryError,
+        UpdateTotpRepositoryError,
+    },
+};
+use crate::application::helpers::{
+    authentication_failure_log::{log_authentication_failure, AuthenticationFailureReason},
+    hashing::Hasher,
+    totp::Totp,
 };
-use crate::application::helpers::hashing::Hasher;
 
 #[derive(Debug)]
 pub enum CreateUserError {
@@ -16,6 +26,39 @@ pub enum CreateUserError {
 pub enum AuthenticationWithCredentialsError {
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("Two-factor authentication code required")]
+    TotpRequired,
+    #[error("Invalid two-factor authentication code")]
+    InvalidTotpCode,
+}
+
+#[derive(Debug)]
+pub enum GetUserByIdError {
+    RepositoryError(GetUserByIdRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum SetUserActiveError {
+    RepositoryError(SetUserActiveRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum RecordLoginError {
+    RepositoryError(UpdateLastLoginRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum EnrollTotpError {
+    DomainError(String),
+    UsersError(GetUserByIdRepositoryError),
+    RepositoryError(UpdateTotpRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum VerifyTotpError {
+    DomainError(String),
+    UsersError(GetUserByIdRepositoryError),
+    RepositoryError(UpdateTotpRepositoryError),
 }
 
 pub struct AuthenticationService {
@@ -35,18 +78,22 @@ impl AuthenticationService {
         password: String,
         email: String,
         phone_number: String,
-        user_role: UserRole,
-        doctor_id: Option<Uuid>,
-        pharmacist_id: Option<Uuid>,
+        user_link: UserLink,
     ) -> Result<User, CreateUserError> {
+        let (doctor_id, pharmacist_id, patient_id) = match user_link {
+            UserLink::Doctor(doctor_id) => (Some(doctor_id), None, None),
+            UserLink::Pharmacist(pharmacist_id) => (None, Some(pharmacist_id), None),
+            UserLink::Patient(patient_id) => (None, None, Some(patient_id)),
+        };
         let new_user = NewUser::new(
             username,
             password,
             email,
             phone_number,
-            user_role,
+            user_link.role(),
             doctor_id,
             pharmacist_id,
+            patient_id,
         )
         .map_err(|err| CreateUserError::DomainError(err.to_string()))?;
 
@@ -59,6 +106,57 @@ impl AuthenticationService {
         Ok(created_user)
     }
 
+    // Keeps the admin created on a previous start, fails if the username belongs to another user
+    pub async fn register_admin(
+        &self,
+        username: String,
+        password: String,
+        email: String,
+        phone_number: String,
+    ) -> Result<User, CreateUserError> {
+        match self
+            .authentication_repository
+            .get_user_by_username(&username)
+            .await
+        {
+            Ok(user) if user.role == UserRole::Admin => return Ok(user),
+            Ok(_) => {
+                return Err(CreateUserError::DomainError(format!(
+                    "User {} already exists and isn't an admin",
+                    username
+                )))
+            }
+            Err(GetUserRepositoryError::NotFound(_)) => {}
+            Err(GetUserRepositoryError::DatabaseError(message)) => {
+                return Err(CreateUserError::RepositoryError(
+                    CreateUserRepositoryError::DatabaseError(message),
+                ))
+            }
+            Err(GetUserRepositoryError::Unavailable) => {
+                return Err(CreateUserError::RepositoryError(
+                    CreateUserRepositoryError::Unavailable,
+                ))
+            }
+        }
+
+        let new_user = NewUser::new(
+            username,
+            password,
+            email,
+            phone_number,
+            UserRole::Admin,
+            None,
+            None,
+            None,
+        )
+        .map_err(|err| CreateUserError::DomainError(err.to_string()))?;
+
+        self.authentication_repository
+            .create_user(new_user)
+            .await
+            .map_err(CreateUserError::RepositoryError)
+    }
+
     fn verify_user_password(&self, pass: &str, user: &User) -> bool {
         Hasher::verify_password(pass, &user.password_hash)
     }
@@ -68,32 +166,188 @@ impl AuthenticationService {
         username: String,
         pass: String,
         role: UserRole,
+        totp_code: Option<String>,
+        ip_address: IpAddr,
+        user_agent: &str,
     ) -> Result<User, AuthenticationWithCredentialsError> {
+        self.check_credentials(&username, &pass, role, totp_code)
+            .await
+            .map_err(|(err, reason)| {
+                log_authentication_failure(Some(&username), ip_address, user_agent, reason);
+                err
+            })
+    }
+
+    async fn check_credentials(
+        &self,
+        username: &str,
+        pass: &str,
+        role: UserRole,
+        totp_code: Option<String>,
+    ) -> Result<
+        User,
+        (
+            AuthenticationWithCredentialsError,
+            AuthenticationFailureReason,
+        ),
+    > {
+        let invalid_credentials = |reason| {
+            (
+                AuthenticationWithCredentialsError::InvalidCredentials,
+                reason,
+            )
+        };
+
         let user = self
             .authentication_repository
-            .get_user_by_username(&username)
+            .get_user_by_username(username)
             .await
-            .map_err(|_| AuthenticationWithCredentialsError::InvalidCredentials)?;
+            .map_err(|_| invalid_credentials(AuthenticationFailureReason::UnknownUser))?;
 
         if user.role != role {
-            Err(AuthenticationWithCredentialsError::InvalidCredentials)?;
+            Err(invalid_credentials(
+                AuthenticationFailureReason::RoleMismatch,
+            ))?;
+        }
+
+        if !self.verify_user_password(pass, &user) {
+            Err(invalid_credentials(
+                AuthenticationFailureReason::InvalidPassword,
+            ))?;
+        }
+
+        // Deactivated accounts get the same error, so it's not revealed that the account exists
+        if !user.is_active {
+            Err(invalid_credentials(
+                AuthenticationFailureReason::InactiveAccount,
+            ))?;
+        }
+
+        if user.totp_enabled {
+            let totp_code = totp_code.ok_or((
+                AuthenticationWithCredentialsError::TotpRequired,
+                AuthenticationFailureReason::TotpRequired,
+            ))?;
+            let totp_secret = user.totp_secret.as_deref().unwrap_or_default();
+            if !Totp::verify_code(totp_secret, &totp_code) {
+                Err((
+                    AuthenticationWithCredentialsError::InvalidTotpCode,
+                    AuthenticationFailureReason::InvalidTotpCode,
+                ))?;
+            }
+        }
+
+        Ok(user)
+    }
+
+    pub async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, GetUserByIdError> {
+        let user = self
+            .authentication_repository
+            .get_user_by_id(user_id)
+            .await
+            .map_err(GetUserByIdError::RepositoryError)?;
+
+        Ok(user)
+    }
+
+    pub async fn set_user_active(
+        &self,
+        user_id: Uuid,
+        active: bool,
+    ) -> Result<User, SetUserActiveError> {
+        let user = self
+            .authentication_repository
+            .set_user_active(user_id, active)
+            .await
+            .map_err(SetUserActiveError::RepositoryError)?;
+
+        Ok(user)
+    }
+
+    pub async fn record_login(
+        &self,
+        user_id: Uuid,
+        ip_address: IpAddr,
+    ) -> Result<User, RecordLoginError> {
+        let user = self
+            .authentication_repository
+            .update_last_login(user_id, ip_address)
+            .await
+            .map_err(RecordLoginError::RepositoryError)?;
+
+        Ok(user)
+    }
+
+    pub async fn enroll_totp(&self, user_id: Uuid) -> Result<TotpEnrollment, EnrollTotpError> {
+        let user = self
+            .authentication_repository
+            .get_user_by_id(user_id)
+            .await
+            .map_err(EnrollTotpError::UsersError)?;
+
+        // Otherwise anyone with access to a session could replace the secret
+        if user.totp_enabled {
+            Err(EnrollTotpError::DomainError(
+                "Two-factor authentication is already enabled".into(),
+            ))?;
         }
 
-        if !self.verify_user_password(&pass, &user) {
-            Err(AuthenticationWithCredentialsError::InvalidCredentials)?;
+        let secret = Totp::generate_secret();
+        self.authentication_repository
+            .set_totp_secret(user.id, secret.clone())
+            .await
+            .map_err(EnrollTotpError::RepositoryError)?;
+
+        Ok(TotpEnrollment {
+            otpauth_uri: Totp::get_otpauth_uri(&secret, &user.username),
+            secret,
+        })
+    }
+
+    pub async fn verify_totp(&self, user_id: Uuid, code: &str) -> Result<User, VerifyTotpError> {
+        let user = self
+            .authentication_repository
+            .get_user_by_id(user_id)
+            .await
+            .map_err(VerifyTotpError::UsersError)?;
+
+        let totp_secret = user.totp_secret.as_deref().ok_or_else(|| {
+            VerifyTotpError::DomainError("Two-factor authentication is not enrolled".into())
+        })?;
+
+        if !Totp::verify_code(totp_secret, code) {
+            Err(VerifyTotpError::DomainError(
+                "Invalid two-factor authentication code".into(),
+            ))?;
         }
 
+        let user = self
+            .authentication_repository
+            .enable_totp(user.id)
+            .await
+            .map_err(VerifyTotpError::RepositoryError)?;
+
         Ok(user)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rocket::serde::json::{self, Value};
     use uuid::Uuid;
 
-    use super::AuthenticationService;
-    use crate::application::authentication::{
-        entities::UserRole, repository::AuthenticationRepositoryFake,
+    use super::{
+        AuthenticationService, AuthenticationWithCredentialsError, CreateUserError, VerifyTotpError,
+    };
+    use crate::{
+        application::{
+            authentication::{
+                entities::{UserLink, UserRole},
+                repository::AuthenticationRepositoryFake,
+            },
+            helpers::totp::Totp,
+        },
+        captured_logs::capture_json_logs,
     };
 
     fn setup_service() -> AuthenticationService {
@@ -110,12 +364,100 @@ mod tests {
                 "password".to_string(),
                 "john.doe@gmail.com".to_string(),
                 "123456789".to_string(),
-                UserRole::Doctor,
-                Some(Uuid::default()),
-                None,
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn registers_admin_once() {
+        let service = setup_service();
+
+        let admin = service
+            .register_admin(
+                "admin".to_string(),
+                "password".to_string(),
+                "admin@gmail.com".to_string(),
+                "123456789".to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(admin.role, UserRole::Admin);
+
+        let admin_after_restart = service
+            .register_admin(
+                "admin".to_string(),
+                "other password".to_string(),
+                "admin@gmail.com".to_string(),
+                "123456789".to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(admin_after_restart.id, admin.id);
+    }
+
+    #[tokio::test]
+    async fn doesnt_register_admin_with_username_of_other_user() {
+        let service = setup_service();
+        service
+            .register_user(
+                "doctor".to_string(),
+                "password".to_string(),
+                "john.doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+
+        let result = service
+            .register_admin(
+                "doctor".to_string(),
+                "password".to_string(),
+                "admin@gmail.com".to_string(),
+                "123456789".to_string(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(CreateUserError::DomainError(_))));
+    }
+
+    #[tokio::test]
+    async fn stores_email_with_lowercased_domain() {
+        let service = setup_service();
+
+        let user = service
+            .register_user(
+                "username".to_string(),
+                "password".to_string(),
+                "John.Doe@GMAIL.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
             )
             .await
             .unwrap();
+
+        assert_eq!(user.email, "John.Doe@gmail.com");
+    }
+
+    #[tokio::test]
+    async fn doesnt_register_user_with_malformed_email() {
+        let service = setup_service();
+
+        let result = service
+            .register_user(
+                "username".to_string(),
+                "password".to_string(),
+                "john doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await;
+
+        assert!(matches!(result, Err(CreateUserError::DomainError(_))));
     }
 
     #[tokio::test]
@@ -127,9 +469,7 @@ mod tests {
                 "password123".to_string(),
                 "john.doe@gmail.com".to_string(),
                 "123456789".to_string(),
-                UserRole::Doctor,
-                Some(Uuid::default()),
-                None,
+                UserLink::Doctor(Uuid::default()),
             )
             .await
             .unwrap();
@@ -139,6 +479,9 @@ mod tests {
                 "username".to_string(),
                 "password123".to_string(),
                 UserRole::Doctor,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal",
             )
             .await;
 
@@ -154,20 +497,219 @@ mod tests {
                 "password123".to_string(),
                 "john.doe@gmail.com".to_string(),
                 "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+
+        service
+            .authenticate_with_credentials(
+                "username".to_string(),
+                "password124".to_string(),
                 UserRole::Doctor,
-                Some(Uuid::default()),
                 None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal",
+            )
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn logs_failed_authentication_without_password() {
+        let service = setup_service();
+        service
+            .register_user(
+                "username".to_string(), //
+                "password123".to_string(),
+                "john.doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
             )
             .await
             .unwrap();
+        let (logs, _guard) = capture_json_logs();
 
         service
             .authenticate_with_credentials(
                 "username".to_string(),
                 "password124".to_string(),
                 UserRole::Doctor,
+                None,
+                "192.168.1.10".parse().unwrap(),
+                "Clinic terminal",
             )
             .await
             .unwrap_err();
+
+        let logs = logs.contents();
+        let event: Value = json::from_str(logs.lines().next().unwrap()).unwrap();
+
+        assert_eq!(event["level"], "WARN");
+        assert_eq!(event["target"], "authentication");
+        assert_eq!(event["fields"]["message"], "Authentication failed");
+        assert_eq!(event["fields"]["username"], "username");
+        assert_eq!(event["fields"]["ip_address"], "192.168.1.10");
+        assert_eq!(event["fields"]["user_agent"], "Clinic terminal");
+        assert_eq!(event["fields"]["reason"], "invalid_password");
+        assert!(!logs.contains("password124"));
+    }
+
+    #[tokio::test]
+    async fn gets_user_by_id() {
+        let service = setup_service();
+        let seed_user = service
+            .register_user(
+                "username".to_string(), //
+                "password123".to_string(),
+                "john.doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+
+        let user = service.get_user_by_id(seed_user.id).await.unwrap();
+
+        assert_eq!(user, seed_user);
+    }
+
+    #[tokio::test]
+    async fn doesnt_authenticate_deactivated_user_until_reactivated() {
+        let service = setup_service();
+        let seed_user = service
+            .register_user(
+                "username".to_string(), //
+                "password123".to_string(),
+                "john.doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+
+        service.set_user_active(seed_user.id, false).await.unwrap();
+
+        let result = service
+            .authenticate_with_credentials(
+                "username".to_string(),
+                "password123".to_string(),
+                UserRole::Doctor,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal",
+            )
+            .await;
+
+        assert_eq!(
+            result,
+            Err(AuthenticationWithCredentialsError::InvalidCredentials)
+        );
+
+        service.set_user_active(seed_user.id, true).await.unwrap();
+
+        let user = service
+            .authenticate_with_credentials(
+                "username".to_string(),
+                "password123".to_string(),
+                UserRole::Doctor,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(user.id, seed_user.id);
+    }
+
+    #[tokio::test]
+    async fn records_last_login() {
+        let service = setup_service();
+        let seed_user = service
+            .register_user(
+                "username".to_string(), //
+                "password123".to_string(),
+                "john.doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(seed_user.last_login_at, None);
+        assert_eq!(seed_user.last_login_ip, None);
+
+        service
+            .record_login(seed_user.id, "192.168.1.10".parse().unwrap())
+            .await
+            .unwrap();
+
+        let user = service.get_user_by_id(seed_user.id).await.unwrap();
+
+        assert!(user.last_login_at.is_some());
+        assert_eq!(user.last_login_ip, Some("192.168.1.10".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn requires_totp_code_after_enrolling_and_verifying() {
+        let service = setup_service();
+        let seed_user = service
+            .register_user(
+                "username".to_string(), //
+                "password123".to_string(),
+                "john.doe@gmail.com".to_string(),
+                "123456789".to_string(),
+                UserLink::Doctor(Uuid::default()),
+            )
+            .await
+            .unwrap();
+
+        let enrollment = service.enroll_totp(seed_user.id).await.unwrap();
+
+        // Enrolled but not verified yet, so the code isn't required
+        service
+            .authenticate_with_credentials(
+                "username".to_string(),
+                "password123".to_string(),
+                UserRole::Doctor,
+                None,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal",
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service.verify_totp(seed_user.id, "abcdef").await,
+            Err(VerifyTotpError::DomainError(_))
+        ));
+
+        let code = Totp::generate_current_code(&enrollment.secret);
+        let user = service.verify_totp(seed_user.id, &code).await.unwrap();
+
+        assert!(user.totp_enabled);
+
+        let authenticate = |totp_code: Option<String>| {
+            service.authenticate_with_credentials(
+                "username".to_string(),
+                "password123".to_string(),
+                UserRole::Doctor,
+                totp_code,
+                "127.0.0.1".parse().unwrap(),
+                "Clinic terminal",
+            )
+        };
+
+        assert_eq!(
+            authenticate(None).await,
+            Err(AuthenticationWithCredentialsError::TotpRequired)
+        );
+        assert_eq!(
+            authenticate(Some("abcdef".into())).await,
+            Err(AuthenticationWithCredentialsError::InvalidTotpCode)
+        );
+        assert_eq!(authenticate(Some(code)).await.unwrap().id, seed_user.id);
+        assert!(service.enroll_totp(seed_user.id).await.is_err());
     }
 }
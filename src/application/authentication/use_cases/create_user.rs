@@ -1,16 +1,26 @@
 use uuid::Uuid;
 
-use crate::application::{authentication::entities::{NewUser, UserRole}, helpers::hashing::Hasher};
+use crate::{
+    application::{
+        authentication::entities::{NewUser, UserRole},
+        helpers::hashing::Hasher,
+    },
+    domain::utils::validators::validate_email::normalize_email,
+};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreateNewUserError {
     #[error("Doctor id is required for doctor user")]
     DoctorIdRequired,
     #[error("Pharmacist id is required for pharmacist user")]
     PharmacistIdRequired,
+    #[error("Patient id is required for patient user")]
+    PatientIdRequired,
 }
 
 impl NewUser {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         username: String,
         password: String,
@@ -19,6 +29,7 @@ impl NewUser {
         role: UserRole,
         doctor_id: Option<Uuid>,
         pharmacist_id: Option<Uuid>,
+        patient_id: Option<Uuid>,
     ) -> anyhow::Result<Self> {
         if role == UserRole::Doctor && doctor_id.is_none() {
             Err(CreateNewUserError::DoctorIdRequired)?;
@@ -26,6 +37,10 @@ impl NewUser {
         if role == UserRole::Pharmacist && pharmacist_id.is_none() {
             Err(CreateNewUserError::PharmacistIdRequired)?;
         }
+        if role == UserRole::Patient && patient_id.is_none() {
+            Err(CreateNewUserError::PatientIdRequired)?;
+        }
+        let email = normalize_email(&email)?;
 
         Ok(Self {
             id: Uuid::new_v4(),
@@ -36,6 +51,7 @@ impl NewUser {
             role,
             doctor_id,
             pharmacist_id,
+            patient_id,
         })
     }
 }
@@ -59,6 +75,7 @@ mod tests {
             UserRole::Doctor,
             Some(Uuid::default()),
             None,
+            None,
         )
         .unwrap();
 
@@ -70,10 +87,38 @@ mod tests {
             UserRole::Pharmacist,
             None,
             Some(Uuid::default()),
+            None,
+        )
+        .unwrap();
+
+        NewUser::new(
+            "username".to_string(),
+            "password".to_string(),
+            "email@gmail.com".to_string(),
+            "123456789".to_string(),
+            UserRole::Patient,
+            None,
+            None,
+            Some(Uuid::default()),
         )
         .unwrap();
     }
 
+    #[test]
+    fn requires_patient_id_for_patient() {
+        NewUser::new(
+            "username".to_string(),
+            "password".to_string(),
+            "email@gmail.com".to_string(),
+            "123456789".to_string(),
+            UserRole::Patient,
+            Some(Uuid::default()),
+            Some(Uuid::default()),
+            None,
+        )
+        .unwrap_err();
+    }
+
     #[test]
     fn requires_doctor_id_for_doctor_and_pharmacist_id_for_pharmacist() {
         NewUser::new(
@@ -84,6 +129,7 @@ mod tests {
             UserRole::Doctor,
             None,
             Some(Uuid::default()),
+            None,
         )
         .unwrap_err();
 
@@ -95,6 +141,7 @@ mod tests {
             UserRole::Doctor,
             None,
             Some(Uuid::default()),
+            None,
         )
         .unwrap_err();
     }
@@ -110,9 +157,42 @@ mod tests {
             UserRole::Doctor,
             Some(Uuid::default()),
             None,
+            None,
         )
         .unwrap();
 
         assert!(Hasher::verify_password(&pass, &user.password_hash));
     }
+
+    #[test]
+    fn normalizes_users_email() {
+        let user = NewUser::new(
+            "username".to_string(),
+            "password".to_string(),
+            " John.Doe@Gmail.COM ".to_string(),
+            "123456789".to_string(),
+            UserRole::Doctor,
+            Some(Uuid::default()),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(user.email, "John.Doe@gmail.com");
+    }
+
+    #[test]
+    fn rejects_malformed_email() {
+        NewUser::new(
+            "username".to_string(),
+            "password".to_string(),
+            "john.doe.gmail.com".to_string(),
+            "123456789".to_string(),
+            UserRole::Doctor,
+            Some(Uuid::default()),
+            None,
+            None,
+        )
+        .unwrap_err();
+    }
 }
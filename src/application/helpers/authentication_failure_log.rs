@@ -0,0 +1,52 @@
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthenticationFailureReason {
+    UnknownUser,
+    RoleMismatch,
+    InvalidPassword,
+    InactiveAccount,
+    TotpRequired,
+    InvalidTotpCode,
+    MissingToken,
+    InvalidToken,
+    SessionNotFound,
+    SessionExpired,
+    SessionInvalidated,
+}
+
+impl AuthenticationFailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnknownUser => "unknown_user",
+            Self::RoleMismatch => "role_mismatch",
+            Self::InvalidPassword => "invalid_password",
+            Self::InactiveAccount => "inactive_account",
+            Self::TotpRequired => "totp_required",
+            Self::InvalidTotpCode => "invalid_totp_code",
+            Self::MissingToken => "missing_token",
+            Self::InvalidToken => "invalid_token",
+            Self::SessionNotFound => "session_not_found",
+            Self::SessionExpired => "session_expired",
+            Self::SessionInvalidated => "session_invalidated",
+        }
+    }
+}
+
+// Structured events for intrusion detection, the clients still get the generic errors and the
+// attempted password is never logged
+pub fn log_authentication_failure(
+    username: Option<&str>,
+    ip_address: IpAddr,
+    user_agent: &str,
+    reason: AuthenticationFailureReason,
+) {
+    tracing::warn!(
+        target: "authentication",
+        username,
+        ip_address = %ip_address,
+        user_agent,
+        reason = reason.as_str(),
+        "Authentication failed"
+    );
+}
@@ -1 +1,3 @@
+pub(super) mod authentication_failure_log;
 pub(super) mod hashing;
+pub(super) mod totp;
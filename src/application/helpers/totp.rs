@@ -0,0 +1,76 @@
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "Prescriptions Management System";
+const DIGITS: usize = 6;
+// Codes from the previous and the next step are accepted as well to make up for clock drift
+const SKEW: u8 = 1;
+const STEP_SECONDS: u64 = 30;
+
+pub struct Totp {}
+
+impl Totp {
+    fn create(secret: &str, account_name: &str) -> Option<TOTP> {
+        let secret = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+
+        Some(TOTP::new_unchecked(
+            Algorithm::SHA1,
+            DIGITS,
+            SKEW,
+            STEP_SECONDS,
+            secret,
+            Some(ISSUER.to_string()),
+            account_name.to_string(),
+        ))
+    }
+
+    // Base32 encoded, as expected by the authenticator apps
+    pub fn generate_secret() -> String {
+        Secret::generate_secret().to_encoded().to_string()
+    }
+
+    pub fn get_otpauth_uri(secret: &str, account_name: &str) -> String {
+        Self::create(secret, account_name)
+            .map(|totp| totp.get_url())
+            .unwrap_or_default()
+    }
+
+    pub fn verify_code(secret: &str, code: &str) -> bool {
+        Self::create(secret, "")
+            .and_then(|totp| totp.check_current(code).ok())
+            .unwrap_or(false)
+    }
+
+    #[cfg(test)]
+    pub fn generate_current_code(secret: &str) -> String {
+        Self::create(secret, "")
+            .unwrap()
+            .generate_current()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Totp;
+
+    #[test]
+    fn verifies_current_code() {
+        let secret = Totp::generate_secret();
+        let code = Totp::generate_current_code(&secret);
+
+        assert!(Totp::verify_code(&secret, &code));
+        assert!(!Totp::verify_code(&Totp::generate_secret(), &code));
+        assert!(!Totp::verify_code(&secret, "abcdef"));
+    }
+
+    #[test]
+    fn creates_otpauth_uri() {
+        let secret = Totp::generate_secret();
+
+        let uri = Totp::get_otpauth_uri(&secret, "doctor");
+
+        assert!(uri.starts_with("otpauth://totp/"));
+        assert!(uri.contains(&format!("secret={}", secret)));
+        assert!(uri.contains("doctor"));
+    }
+}
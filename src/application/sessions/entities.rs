@@ -2,6 +2,8 @@ use std::net::IpAddr;
 
 use chrono::{DateTime, Utc};
 use rocket_okapi::OpenApiFromRequest;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -10,6 +12,7 @@ pub struct NewSession {
     pub user_id: Uuid,
     pub doctor_id: Option<Uuid>,
     pub pharmacist_id: Option<Uuid>,
+    pub patient_id: Option<Uuid>,
     pub ip_address: IpAddr,
     pub user_agent: String,
     pub expires_at: DateTime<Utc>,
@@ -21,6 +24,7 @@ pub struct Session {
     pub user_id: Uuid,
     pub doctor_id: Option<Uuid>,
     pub pharmacist_id: Option<Uuid>,
+    pub patient_id: Option<Uuid>,
     pub ip_address: IpAddr,
     pub user_agent: String,
     pub expires_at: DateTime<Utc>,
@@ -29,12 +33,20 @@ pub struct Session {
     pub invalidated_at: Option<DateTime<Utc>>,
 }
 
+// Expired sessions that weren't invalidated count as neither
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionsStats {
+    pub active: i64,
+    pub invalidated: i64,
+}
+
 impl PartialEq<NewSession> for Session {
     fn eq(&self, other: &NewSession) -> bool {
         self.id == other.id
             && self.user_id == other.user_id
             && self.doctor_id == other.doctor_id
             && self.pharmacist_id == other.pharmacist_id
+            && self.patient_id == other.patient_id
             && self.ip_address == other.ip_address
             && self.user_agent == other.user_agent
             && self.expires_at == other.expires_at
@@ -1,4 +1,5 @@
 pub mod entities;
 pub mod repository;
 pub mod service;
+pub mod token_codec;
 pub mod use_cases;
@@ -1,15 +1,17 @@
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 use chrono::Utc;
 use rocket::async_trait;
 use uuid::Uuid;
 
-use super::entities::{NewSession, Session};
+use super::entities::{NewSession, Session, SessionsStats};
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreateSessionRepositoryError {
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -18,6 +20,8 @@ pub enum GetSessionRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -26,6 +30,8 @@ pub enum UpdateSessionRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -39,17 +45,32 @@ pub trait SessionsRepository: Send + Sync + 'static {
         &self,
         session: Session,
     ) -> Result<Session, UpdateSessionRepositoryError>;
+    async fn invalidate_all_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, UpdateSessionRepositoryError>;
+    async fn count_active_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, GetSessionRepositoryError>;
+    async fn get_oldest_active_session_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<Session>, GetSessionRepositoryError>;
+    async fn count_sessions(&self) -> Result<SessionsStats, GetSessionRepositoryError>;
 }
 
+// Clones share the sessions, so tests can tamper with sessions of a repository owned by a service
+#[derive(Clone)]
 pub struct SessionsRepositoryFake {
-    sessions: RwLock<Vec<Session>>,
+    sessions: Arc<RwLock<Vec<Session>>>,
 }
 
 impl SessionsRepositoryFake {
     #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
-            sessions: RwLock::new(Vec::new()),
+            sessions: Arc::new(RwLock::new(Vec::new())),
         }
     }
 }
@@ -65,6 +86,7 @@ impl SessionsRepository for SessionsRepositoryFake {
             user_id: new_session.user_id,
             doctor_id: new_session.doctor_id,
             pharmacist_id: new_session.pharmacist_id,
+            patient_id: new_session.patient_id,
             ip_address: new_session.ip_address,
             user_agent: new_session.user_agent,
             expires_at: new_session.expires_at,
@@ -111,6 +133,83 @@ impl SessionsRepository for SessionsRepositoryFake {
             None => Err(UpdateSessionRepositoryError::NotFound(updated_session.id)),
         }
     }
+
+    async fn invalidate_all_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, UpdateSessionRepositoryError> {
+        let now = Utc::now();
+        let mut invalidated_count = 0;
+
+        for session in self.sessions.write().unwrap().iter_mut() {
+            if session.user_id == user_id && session.invalidated_at.is_none() {
+                session.invalidated_at = Some(now);
+                session.updated_at = now;
+                invalidated_count += 1;
+            }
+        }
+
+        Ok(invalidated_count)
+    }
+
+    async fn count_active_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, GetSessionRepositoryError> {
+        let now = Utc::now();
+        let count = self
+            .sessions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|session| {
+                session.user_id == user_id
+                    && session.invalidated_at.is_none()
+                    && session.expires_at > now
+            })
+            .count();
+
+        Ok(count as u64)
+    }
+
+    async fn get_oldest_active_session_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<Session>, GetSessionRepositoryError> {
+        let now = Utc::now();
+        let oldest_session = self
+            .sessions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|session| {
+                session.user_id == user_id
+                    && session.invalidated_at.is_none()
+                    && session.expires_at > now
+            })
+            .min_by_key(|session| session.created_at)
+            .cloned();
+
+        Ok(oldest_session)
+    }
+
+    async fn count_sessions(&self) -> Result<SessionsStats, GetSessionRepositoryError> {
+        let now = Utc::now();
+        let sessions = self.sessions.read().unwrap();
+        let active = sessions
+            .iter()
+            .filter(|session| session.invalidated_at.is_none() && session.expires_at > now)
+            .count();
+        let invalidated = sessions
+            .iter()
+            .filter(|session| session.invalidated_at.is_some())
+            .count();
+
+        Ok(SessionsStats {
+            active: active as i64,
+            invalidated: invalidated as i64,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -120,6 +219,7 @@ mod tests {
         str::FromStr,
     };
 
+    use chrono::Utc;
     use uuid::Uuid;
 
     use super::{SessionsRepository, SessionsRepositoryFake};
@@ -134,6 +234,7 @@ mod tests {
             Uuid::new_v4(),
             Some(Uuid::new_v4()),
             None,
+            None,
             IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
             "Mozilla/5.0".to_string(),
         )
@@ -176,7 +277,7 @@ mod tests {
 
         assert!(created_session.invalidated_at.is_none());
 
-        created_session_by_id.invalidate().unwrap();
+        created_session_by_id.invalidate(Utc::now()).unwrap();
 
         repository
             .update_session(created_session_by_id.clone())
@@ -190,4 +291,153 @@ mod tests {
 
         assert!(invalidated_session.invalidated_at.is_some());
     }
+
+    #[tokio::test]
+    async fn invalidates_all_sessions_for_user() {
+        let repository = setup_repository();
+        let user_id = Uuid::new_v4();
+        let new_session = |user_id| {
+            NewSession::new(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+        };
+
+        let first_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        let second_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        let other_user_session = repository
+            .create_session(new_session(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let invalidated_count = repository
+            .invalidate_all_sessions_for_user(user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(invalidated_count, 2);
+        for session_id in [first_session.id, second_session.id] {
+            let session = repository.get_session_by_id(session_id).await.unwrap();
+            assert!(session.invalidated_at.is_some());
+        }
+        let other_user_session = repository
+            .get_session_by_id(other_user_session.id)
+            .await
+            .unwrap();
+        assert!(other_user_session.invalidated_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn counts_and_gets_oldest_active_sessions_for_user() {
+        let repository = setup_repository();
+        let user_id = Uuid::new_v4();
+        let new_session = |user_id| {
+            NewSession::new(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+        };
+
+        let mut invalidated_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        invalidated_session.invalidate(Utc::now()).unwrap();
+        repository
+            .update_session(invalidated_session)
+            .await
+            .unwrap();
+        let oldest_active_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let active_count = repository
+            .count_active_sessions_for_user(user_id)
+            .await
+            .unwrap();
+        let oldest_session = repository
+            .get_oldest_active_session_for_user(user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(active_count, 2);
+        assert_eq!(oldest_session.unwrap().id, oldest_active_session.id);
+        assert!(repository
+            .get_oldest_active_session_for_user(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn counts_active_and_invalidated_sessions() {
+        let repository = setup_repository();
+        let user_id = Uuid::new_v4();
+        let new_session = |user_id| {
+            NewSession::new(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+        };
+
+        let mut invalidated_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        invalidated_session.invalidate(Utc::now()).unwrap();
+        repository
+            .update_session(invalidated_session)
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let stats = repository.count_sessions().await.unwrap();
+
+        assert_eq!(stats.active, 2);
+        assert_eq!(stats.invalidated, 1);
+
+        repository
+            .invalidate_all_sessions_for_user(user_id)
+            .await
+            .unwrap();
+
+        let stats = repository.count_sessions().await.unwrap();
+
+        assert_eq!(stats.active, 1);
+        assert_eq!(stats.invalidated, 2);
+    }
 }
@@ -1,22 +1,44 @@
-use std::net::IpAddr;
+use std::{net::IpAddr, sync::Arc};
 
+use chrono::Duration;
 use uuid::Uuid;
 
 use super::{
-    entities::{NewSession, Session},
+    entities::{NewSession, Session, SessionsStats},
     repository::{
         CreateSessionRepositoryError, GetSessionRepositoryError, SessionsRepository,
         UpdateSessionRepositoryError,
     },
-    use_cases::invalidate_session::InvalidateSessionDomainError,
+    token_codec::{TokenCodec, TokenDecodingError},
+    use_cases::{
+        create_session::DEFAULT_SESSION_LIFETIME_HOURS,
+        invalidate_session::InvalidateSessionDomainError, validate_session::SessionValidationError,
+    },
 };
+use crate::domain::utils::clock::{Clock, SystemClock};
+
+pub const DEFAULT_MAX_SESSIONS_PER_USER: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionLimitBehavior {
+    InvalidateOldest,
+    Reject,
+}
 
 pub struct SessionsService {
     sessions_repository: Box<dyn SessionsRepository>,
+    token_codec: Box<dyn TokenCodec>,
+    max_sessions_per_user: u64,
+    session_limit_behavior: SessionLimitBehavior,
+    session_lifetime: Duration,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug)]
 pub enum CreateSessionError {
+    SessionLimitReached(u64),
+    ActiveSessionsError(GetSessionRepositoryError),
+    InvalidateOldestError(UpdateSessionRepositoryError),
     RepositoryError(CreateSessionRepositoryError),
 }
 
@@ -26,28 +48,85 @@ pub enum InvalidateSessionError {
     RepositoryError(UpdateSessionRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum InvalidateAllSessionsForUserError {
+    RepositoryError(UpdateSessionRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum CountSessionsError {
+    RepositoryError(GetSessionRepositoryError),
+}
+
 #[derive(Debug, PartialEq)]
 pub enum GetSessionByIdError {
     RepositoryError(GetSessionRepositoryError),
 }
 
 impl SessionsService {
-    pub fn new(sessions_repository: Box<dyn SessionsRepository>) -> Self {
+    pub fn new(
+        sessions_repository: Box<dyn SessionsRepository>,
+        token_codec: Box<dyn TokenCodec>,
+    ) -> Self {
         Self {
             sessions_repository,
+            token_codec,
+            max_sessions_per_user: DEFAULT_MAX_SESSIONS_PER_USER,
+            session_limit_behavior: SessionLimitBehavior::InvalidateOldest,
+            session_lifetime: Duration::hours(DEFAULT_SESSION_LIFETIME_HOURS),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_session_lifetime(mut self, session_lifetime: Duration) -> Self {
+        self.session_lifetime = session_lifetime;
+        self
+    }
+
+    pub fn with_session_limit(
+        mut self,
+        max_sessions_per_user: u64,
+        session_limit_behavior: SessionLimitBehavior,
+    ) -> Self {
+        self.max_sessions_per_user = max_sessions_per_user;
+        self.session_limit_behavior = session_limit_behavior;
+        self
+    }
+
+    pub fn encode_session_token(&self, session: &Session) -> String {
+        self.token_codec.encode(session)
+    }
+
+    pub fn decode_session_token(&self, token: &str) -> Result<Uuid, TokenDecodingError> {
+        self.token_codec.decode(token)
+    }
+
     pub async fn create_session(
         &self,
         user_id: Uuid,
         doctor_id: Option<Uuid>,
         pharmacist_id: Option<Uuid>,
+        patient_id: Option<Uuid>,
         ip_address: IpAddr,
         user_agent: String,
     ) -> Result<Session, CreateSessionError> {
-        let new_session =
-            NewSession::new(user_id, doctor_id, pharmacist_id, ip_address, user_agent);
+        self.make_room_for_new_session(user_id).await?;
+
+        let new_session = NewSession::new_with_lifetime(
+            user_id,
+            doctor_id,
+            pharmacist_id,
+            patient_id,
+            ip_address,
+            user_agent,
+            self.session_lifetime,
+            self.clock.now(),
+        );
 
         let created_session = self
             .sessions_repository
@@ -58,6 +137,53 @@ impl SessionsService {
         Ok(created_session)
     }
 
+    // Once the user is at the limit, either the oldest active sessions are invalidated or the
+    // login is rejected, depending on the configured behavior
+    async fn make_room_for_new_session(&self, user_id: Uuid) -> Result<(), CreateSessionError> {
+        loop {
+            let active_count = self
+                .sessions_repository
+                .count_active_sessions_for_user(user_id)
+                .await
+                .map_err(CreateSessionError::ActiveSessionsError)?;
+
+            if active_count < self.max_sessions_per_user {
+                return Ok(());
+            }
+
+            if self.session_limit_behavior == SessionLimitBehavior::Reject {
+                Err(CreateSessionError::SessionLimitReached(
+                    self.max_sessions_per_user,
+                ))?;
+            }
+
+            let oldest_session = self
+                .sessions_repository
+                .get_oldest_active_session_for_user(user_id)
+                .await
+                .map_err(CreateSessionError::ActiveSessionsError)?;
+
+            // The limit of 0 can't be satisfied by evicting sessions
+            let Some(mut oldest_session) = oldest_session else {
+                Err(CreateSessionError::SessionLimitReached(
+                    self.max_sessions_per_user,
+                ))?
+            };
+
+            // Can only fail if the session got invalidated in the meantime
+            if oldest_session.invalidate(self.clock.now()).is_ok() {
+                self.sessions_repository
+                    .update_session(oldest_session)
+                    .await
+                    .map_err(CreateSessionError::InvalidateOldestError)?;
+            }
+        }
+    }
+
+    pub fn validate_session(&self, session: &Session) -> Result<(), SessionValidationError> {
+        session.validate(self.clock.now())
+    }
+
     pub async fn get_session_by_id(
         &self,
         session_id: Uuid,
@@ -76,7 +202,7 @@ impl SessionsService {
         mut session: Session,
     ) -> Result<Session, InvalidateSessionError> {
         session
-            .invalidate()
+            .invalidate(self.clock.now())
             .map_err(|err| InvalidateSessionError::DomainError(err))?;
 
         let invalidated_session = self
@@ -87,6 +213,29 @@ impl SessionsService {
 
         Ok(invalidated_session)
     }
+
+    pub async fn invalidate_all_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, InvalidateAllSessionsForUserError> {
+        let invalidated_count = self
+            .sessions_repository
+            .invalidate_all_sessions_for_user(user_id)
+            .await
+            .map_err(InvalidateAllSessionsForUserError::RepositoryError)?;
+
+        Ok(invalidated_count)
+    }
+
+    pub async fn count_sessions(&self) -> Result<SessionsStats, CountSessionsError> {
+        let stats = self
+            .sessions_repository
+            .count_sessions()
+            .await
+            .map_err(CountSessionsError::RepositoryError)?;
+
+        Ok(stats)
+    }
 }
 
 #[cfg(test)]
@@ -94,15 +243,26 @@ mod tests {
     use std::{
         net::{IpAddr, Ipv4Addr},
         str::FromStr,
+        sync::Arc,
     };
 
+    use chrono::Duration;
     use uuid::Uuid;
 
-    use super::SessionsService;
-    use crate::application::sessions::repository::SessionsRepositoryFake;
+    use super::{CreateSessionError, SessionLimitBehavior, SessionsService};
+    use crate::{
+        application::sessions::{
+            repository::SessionsRepositoryFake, token_codec::UuidTokenCodec,
+            use_cases::validate_session::SessionValidationError,
+        },
+        domain::utils::clock::{Clock, FixedClock},
+    };
 
     fn setup_service() -> SessionsService {
-        SessionsService::new(Box::new(SessionsRepositoryFake::new()))
+        SessionsService::new(
+            Box::new(SessionsRepositoryFake::new()),
+            Box::new(UuidTokenCodec),
+        )
     }
 
     #[tokio::test]
@@ -114,6 +274,7 @@ mod tests {
                 Uuid::new_v4(),
                 Some(Uuid::new_v4()),
                 None,
+                None,
                 IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
                 "Mozilla/5.0".to_string(),
             )
@@ -129,6 +290,7 @@ mod tests {
                 Uuid::new_v4(),
                 Some(Uuid::new_v4()),
                 None,
+                None,
                 IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
                 "Mozilla/5.0".to_string(),
             )
@@ -146,4 +308,166 @@ mod tests {
 
         assert!(invalidated_session_by_id.invalidated_at.is_some());
     }
+
+    #[tokio::test]
+    async fn invalidates_all_sessions_for_user() {
+        let service = setup_service();
+        let user_id = Uuid::new_v4();
+        for _ in 0..2 {
+            service
+                .create_session(
+                    user_id,
+                    Some(Uuid::new_v4()),
+                    None,
+                    None,
+                    IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                    "Mozilla/5.0".to_string(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let invalidated_count = service
+            .invalidate_all_sessions_for_user(user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(invalidated_count, 2);
+        assert_eq!(
+            service
+                .invalidate_all_sessions_for_user(user_id)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    async fn create_sessions(service: &SessionsService, user_id: Uuid, count: usize) -> Vec<Uuid> {
+        let mut session_ids = Vec::new();
+        for _ in 0..count {
+            let session = service
+                .create_session(
+                    user_id,
+                    Some(Uuid::new_v4()),
+                    None,
+                    None,
+                    IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                    "Mozilla/5.0".to_string(),
+                )
+                .await
+                .unwrap();
+            session_ids.push(session.id);
+        }
+        session_ids
+    }
+
+    #[tokio::test]
+    async fn invalidates_oldest_session_when_limit_is_reached() {
+        let service = setup_service().with_session_limit(2, SessionLimitBehavior::InvalidateOldest);
+        let user_id = Uuid::new_v4();
+
+        let session_ids = create_sessions(&service, user_id, 3).await;
+
+        let oldest_session = service.get_session_by_id(session_ids[0]).await.unwrap();
+        assert!(oldest_session.invalidated_at.is_some());
+        for session_id in &session_ids[1..] {
+            let session = service.get_session_by_id(*session_id).await.unwrap();
+            assert!(session.invalidated_at.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_new_session_when_limit_is_reached() {
+        let service = setup_service().with_session_limit(2, SessionLimitBehavior::Reject);
+        let user_id = Uuid::new_v4();
+
+        let session_ids = create_sessions(&service, user_id, 2).await;
+
+        let result = service
+            .create_session(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(CreateSessionError::SessionLimitReached(2))
+        ));
+        for session_id in session_ids {
+            let session = service.get_session_by_id(session_id).await.unwrap();
+            assert!(session.invalidated_at.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_session_limit_per_user() {
+        let service = setup_service().with_session_limit(1, SessionLimitBehavior::Reject);
+
+        create_sessions(&service, Uuid::new_v4(), 1).await;
+        create_sessions(&service, Uuid::new_v4(), 1).await;
+    }
+
+    #[tokio::test]
+    async fn expires_session_after_its_lifetime() {
+        let clock = Arc::new(FixedClock::new("2024-03-15T12:00:00Z".parse().unwrap()));
+        let service = setup_service()
+            .with_session_lifetime(Duration::hours(1))
+            .with_clock(clock.clone());
+
+        let session = service
+            .create_session(
+                Uuid::new_v4(),
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(session.expires_at, clock.now() + Duration::hours(1));
+
+        clock.advance(Duration::hours(1));
+
+        assert_eq!(service.validate_session(&session), Ok(()));
+
+        clock.advance(Duration::seconds(1));
+
+        assert_eq!(
+            service.validate_session(&session),
+            Err(SessionValidationError::SessionExpired)
+        );
+    }
+
+    #[tokio::test]
+    async fn invalidates_session_at_the_current_time_of_the_clock() {
+        let clock = Arc::new(FixedClock::new("2024-03-15T12:00:00Z".parse().unwrap()));
+        let service = setup_service().with_clock(clock.clone());
+        let session = service
+            .create_session(
+                Uuid::new_v4(),
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+            .await
+            .unwrap();
+        clock.advance(Duration::minutes(5));
+
+        let invalidated_session = service.invalidate_session(session).await.unwrap();
+
+        assert_eq!(invalidated_session.invalidated_at, Some(clock.now()));
+        assert_eq!(
+            service.validate_session(&invalidated_session),
+            Err(SessionValidationError::SessionInvalidated)
+        );
+    }
 }
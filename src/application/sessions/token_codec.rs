@@ -0,0 +1,181 @@
+use std::env;
+
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::entities::Session;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum TokenDecodingError {
+    #[error("Invalid session token: {0}")]
+    InvalidToken(String),
+    #[error("Session token is expired")]
+    ExpiredToken,
+}
+
+pub trait TokenCodec: Send + Sync + 'static {
+    fn encode(&self, session: &Session) -> String;
+    fn decode(&self, token: &str) -> Result<Uuid, TokenDecodingError>;
+}
+
+pub struct UuidTokenCodec;
+
+impl TokenCodec for UuidTokenCodec {
+    fn encode(&self, session: &Session) -> String {
+        session.id.to_string()
+    }
+
+    fn decode(&self, token: &str) -> Result<Uuid, TokenDecodingError> {
+        Uuid::parse_str(token).map_err(|err| TokenDecodingError::InvalidToken(err.to_string()))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JwtClaims {
+    sub: Uuid,
+    exp: i64,
+}
+
+pub struct JwtTokenCodec {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+impl JwtTokenCodec {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
+}
+
+impl TokenCodec for JwtTokenCodec {
+    fn encode(&self, session: &Session) -> String {
+        let claims = JwtClaims {
+            sub: session.id,
+            exp: session.expires_at.timestamp(),
+        };
+
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key).unwrap()
+    }
+
+    fn decode(&self, token: &str) -> Result<Uuid, TokenDecodingError> {
+        let token_data = jsonwebtoken::decode::<JwtClaims>(
+            token,
+            &self.decoding_key,
+            &Validation::new(Algorithm::HS256),
+        )
+        .map_err(|err| match err.kind() {
+            ErrorKind::ExpiredSignature => TokenDecodingError::ExpiredToken,
+            _ => TokenDecodingError::InvalidToken(err.to_string()),
+        })?;
+
+        Ok(token_data.claims.sub)
+    }
+}
+
+// SESSION_TOKEN_FORMAT=jwt requires SESSION_TOKEN_SECRET to be set, anything else falls back to
+// plain session ids
+pub fn create_token_codec_from_env() -> Box<dyn TokenCodec> {
+    match env::var("SESSION_TOKEN_FORMAT").as_deref() {
+        Ok("jwt") => {
+            let secret = env::var("SESSION_TOKEN_SECRET")
+                .expect("SESSION_TOKEN_SECRET must be set when SESSION_TOKEN_FORMAT=jwt");
+            Box::new(JwtTokenCodec::new(&secret))
+        }
+        _ => Box::new(UuidTokenCodec),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        str::FromStr,
+    };
+
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use super::{JwtTokenCodec, TokenCodec, TokenDecodingError, UuidTokenCodec};
+    use crate::application::sessions::entities::Session;
+
+    fn create_mock_session() -> Session {
+        Session {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            doctor_id: Some(Uuid::new_v4()),
+            pharmacist_id: None,
+            patient_id: None,
+            ip_address: IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+            user_agent: "Mozilla/5.0".to_string(),
+            expires_at: Utc::now() + Duration::days(2),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            invalidated_at: None,
+        }
+    }
+
+    #[test]
+    fn uuid_codec_round_trips_session_id() {
+        let codec = UuidTokenCodec;
+        let session = create_mock_session();
+
+        let token = codec.encode(&session);
+
+        assert_eq!(token, session.id.to_string());
+        assert_eq!(codec.decode(&token), Ok(session.id));
+    }
+
+    #[test]
+    fn uuid_codec_rejects_malformed_token() {
+        let codec = UuidTokenCodec;
+
+        assert!(codec.decode("not-a-uuid").is_err());
+    }
+
+    #[test]
+    fn jwt_codec_round_trips_session_id() {
+        let codec = JwtTokenCodec::new("secret");
+        let session = create_mock_session();
+
+        let token = codec.encode(&session);
+
+        assert_eq!(token.split('.').count(), 3);
+        assert_eq!(codec.decode(&token), Ok(session.id));
+    }
+
+    #[test]
+    fn jwt_codec_rejects_tampered_token() {
+        let codec = JwtTokenCodec::new("secret");
+        let token = codec.encode(&create_mock_session());
+
+        let other_token = codec.encode(&create_mock_session());
+        let other_payload = other_token.split('.').nth(1).unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        let tampered_token = format!("{}.{}.{}", parts[0], other_payload, parts[2]);
+
+        assert!(codec.decode(&tampered_token).is_err());
+    }
+
+    #[test]
+    fn jwt_codec_rejects_token_signed_with_different_secret() {
+        let codec = JwtTokenCodec::new("secret");
+        let token = JwtTokenCodec::new("other secret").encode(&create_mock_session());
+
+        assert!(codec.decode(&token).is_err());
+    }
+
+    #[test]
+    fn jwt_codec_rejects_expired_token() {
+        let codec = JwtTokenCodec::new("secret");
+        let mut session = create_mock_session();
+        session.expires_at = Utc::now() - Duration::days(1);
+
+        let token = codec.encode(&session);
+
+        assert_eq!(codec.decode(&token), Err(TokenDecodingError::ExpiredToken));
+    }
+}
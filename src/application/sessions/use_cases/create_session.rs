@@ -1,26 +1,53 @@
 use std::net::IpAddr;
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
 use crate::application::sessions::entities::NewSession;
 
+pub const DEFAULT_SESSION_LIFETIME_HOURS: i64 = 48;
+
 impl NewSession {
     pub fn new(
         user_id: Uuid,
         doctor_id: Option<Uuid>,
         pharmacist_id: Option<Uuid>,
+        patient_id: Option<Uuid>,
         ip_address: IpAddr,
         user_agent: String,
+    ) -> Self {
+        Self::new_with_lifetime(
+            user_id,
+            doctor_id,
+            pharmacist_id,
+            patient_id,
+            ip_address,
+            user_agent,
+            Duration::hours(DEFAULT_SESSION_LIFETIME_HOURS),
+            Utc::now(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_lifetime(
+        user_id: Uuid,
+        doctor_id: Option<Uuid>,
+        pharmacist_id: Option<Uuid>,
+        patient_id: Option<Uuid>,
+        ip_address: IpAddr,
+        user_agent: String,
+        lifetime: Duration,
+        now: DateTime<Utc>,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
             user_id,
             doctor_id,
             pharmacist_id,
+            patient_id,
             ip_address,
             user_agent,
-            expires_at: Utc::now() + Duration::days(2),
+            expires_at: now + lifetime,
         }
     }
 }
@@ -32,7 +59,7 @@ mod tests {
         str::FromStr,
     };
 
-    use chrono::Utc;
+    use chrono::{Duration, Utc};
     use uuid::Uuid;
 
     use crate::application::sessions::entities::NewSession;
@@ -45,6 +72,7 @@ mod tests {
             Uuid::new_v4(),
             Some(Uuid::new_v4()),
             None,
+            None,
             IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
             "Mozilla/5.0".to_string(),
         );
@@ -53,4 +81,24 @@ mod tests {
 
         assert_eq!(session_duration.num_hours(), 48);
     }
+
+    #[test]
+    fn creates_new_session_with_given_lifetime() {
+        let now = Utc::now();
+
+        let new_session = NewSession::new_with_lifetime(
+            Uuid::new_v4(),
+            Some(Uuid::new_v4()),
+            None,
+            None,
+            IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+            "Mozilla/5.0".to_string(),
+            Duration::minutes(15),
+            now,
+        );
+
+        let session_duration = new_session.expires_at - now;
+
+        assert_eq!(session_duration.num_minutes(), 15);
+    }
 }
@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use crate::application::sessions::entities::Session;
 
@@ -9,11 +9,10 @@ pub enum InvalidateSessionDomainError {
 }
 
 impl Session {
-    pub fn invalidate(&mut self) -> Result<(), InvalidateSessionDomainError> {
+    pub fn invalidate(&mut self, now: DateTime<Utc>) -> Result<(), InvalidateSessionDomainError> {
         if self.invalidated_at.is_some() {
             Err(InvalidateSessionDomainError::AlreadyInvalidated)?;
         }
-        let now = Utc::now();
         self.invalidated_at = Some(now);
         self.updated_at = now;
         Ok(())
@@ -38,6 +37,7 @@ mod tests {
             user_id: Uuid::new_v4(),
             doctor_id: Some(Uuid::new_v4()),
             pharmacist_id: None,
+            patient_id: None,
             ip_address: IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
             user_agent: "Mozilla/5.0".to_string(),
             expires_at: Utc::now() + chrono::Duration::days(2),
@@ -51,7 +51,7 @@ mod tests {
     fn invalidates_session() {
         let mut session = create_mock_session();
 
-        session.invalidate().unwrap();
+        session.invalidate(Utc::now()).unwrap();
 
         assert_eq!(session.invalidated_at.unwrap(), session.updated_at)
     }
@@ -61,7 +61,7 @@ mod tests {
         let mut session = create_mock_session();
         session.invalidated_at = Some(Utc::now());
 
-        let result = session.invalidate();
+        let result = session.invalidate(Utc::now());
 
         assert!(result.is_err());
     }
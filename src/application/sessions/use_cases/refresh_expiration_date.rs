@@ -1,10 +1,9 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 
 use crate::application::sessions::entities::Session;
 
 impl Session {
-    pub fn refresh_expiration_date(&mut self) {
-        let now = Utc::now();
+    pub fn refresh_expiration_date(&mut self, now: DateTime<Utc>) {
         self.expires_at = now + Duration::days(2);
         self.updated_at = now;
     }
@@ -28,6 +27,7 @@ mod tests {
             user_id: Uuid::new_v4(),
             doctor_id: Some(Uuid::new_v4()),
             pharmacist_id: None,
+            patient_id: None,
             ip_address: IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
             user_agent: "Mozilla/5.0".to_string(),
             expires_at: Utc::now() + chrono::Duration::days(2),
@@ -43,7 +43,7 @@ mod tests {
         let mut session = create_mock_session();
         session.expires_at = Utc::now() + Duration::hours(1);
 
-        session.refresh_expiration_date();
+        session.refresh_expiration_date(now);
 
         let session_duration = session.expires_at - now;
 
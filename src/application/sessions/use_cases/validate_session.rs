@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use crate::application::sessions::entities::Session;
 
@@ -11,12 +11,12 @@ pub enum SessionValidationError {
 }
 
 impl Session {
-    pub fn validate(&self) -> Result<(), SessionValidationError> {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), SessionValidationError> {
         if self.invalidated_at.is_some() {
             Err(SessionValidationError::SessionInvalidated)?;
         }
 
-        if self.expires_at < Utc::now() {
+        if self.expires_at < now {
             Err(SessionValidationError::SessionExpired)?;
         }
 
@@ -44,6 +44,7 @@ mod tests {
             user_id: Uuid::new_v4(),
             doctor_id: Some(Uuid::new_v4()),
             pharmacist_id: None,
+            patient_id: None,
             ip_address: IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
             user_agent: "Mozilla/5.0".to_string(),
             expires_at: Utc::now() + chrono::Duration::days(2),
@@ -59,7 +60,7 @@ mod tests {
         session.expires_at = Utc::now() + chrono::Duration::days(2);
         session.invalidated_at = None;
 
-        let result = session.validate();
+        let result = session.validate(Utc::now());
 
         assert!(result.is_ok());
     }
@@ -69,7 +70,7 @@ mod tests {
         let mut session = create_mock_session();
         session.invalidated_at = Some(Utc::now());
 
-        let result = session.validate();
+        let result = session.validate(Utc::now());
 
         assert_eq!(result, Err(SessionValidationError::SessionInvalidated));
     }
@@ -79,7 +80,7 @@ mod tests {
         let mut session = create_mock_session();
         session.expires_at = Utc::now() - chrono::Duration::minutes(10);
 
-        let result = session.validate();
+        let result = session.validate(Utc::now());
 
         assert_eq!(result, Err(SessionValidationError::SessionExpired));
     }
@@ -3,12 +3,15 @@ use rocket_okapi::{okapi::schemars, JsonSchema};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::pwz_number::PwzNumber;
+
 #[derive(Clone, Debug)]
 pub struct NewDoctor {
     pub id: Uuid,
     pub name: String,
-    pub pwz_number: String,
+    pub pwz_number: PwzNumber,
     pub pesel_number: String,
+    pub specialization: Option<String>,
 }
 
 fn example_name() -> &'static str {
@@ -20,6 +23,9 @@ fn example_pesel_number() -> &'static str {
 fn example_pwz_number() -> &'static str {
     "5425740"
 }
+fn example_specialization() -> Option<&'static str> {
+    Some("cardiology")
+}
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, JsonSchema)]
 pub struct Doctor {
@@ -27,19 +33,33 @@ pub struct Doctor {
     #[schemars(example = "example_name")]
     pub name: String,
     #[schemars(example = "example_pwz_number")]
-    pub pwz_number: String,
+    pub pwz_number: PwzNumber,
     #[schemars(example = "example_pesel_number")]
     pub pesel_number: String,
+    #[schemars(example = "example_specialization")]
+    pub specialization: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+// Audit entry written whenever a doctor's PWZ number is corrected
+#[derive(Debug, PartialEq, Clone)]
+pub struct DoctorPwzNumberChange {
+    pub id: Uuid,
+    pub doctor_id: Uuid,
+    pub old_pwz_number: PwzNumber,
+    pub new_pwz_number: PwzNumber,
+    pub changed_by: Uuid,
+    pub changed_at: DateTime<Utc>,
+}
+
 impl PartialEq<NewDoctor> for Doctor {
     fn eq(&self, other: &NewDoctor) -> bool {
         self.id == other.id
             && self.name == other.name
             && self.pesel_number == other.pesel_number
             && self.pwz_number == other.pwz_number
+            && self.specialization == other.specialization
     }
 }
 
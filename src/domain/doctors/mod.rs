@@ -1,4 +1,5 @@
 pub mod entities;
+pub mod pwz_number;
 pub mod repository;
 pub mod service;
 pub mod use_cases;
@@ -0,0 +1,122 @@
+use std::{fmt, ops::Deref};
+
+use rocket_okapi::{okapi::schemars, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::utils::validators::validate_pwz_number::validate_pwz_number;
+
+// PWZ number of a doctor, only constructed after passing validation. Serialized as a plain string
+#[derive(Debug, Clone, PartialEq, Eq, Hash, sqlx::Type, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct PwzNumber(String);
+
+impl PwzNumber {
+    pub fn new(pwz_number: String) -> anyhow::Result<Self> {
+        validate_pwz_number(&pwz_number)?;
+
+        Ok(Self(pwz_number))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for PwzNumber {
+    type Error = anyhow::Error;
+
+    fn try_from(pwz_number: String) -> Result<Self, Self::Error> {
+        Self::new(pwz_number)
+    }
+}
+
+impl TryFrom<&str> for PwzNumber {
+    type Error = anyhow::Error;
+
+    fn try_from(pwz_number: &str) -> Result<Self, Self::Error> {
+        Self::new(pwz_number.to_string())
+    }
+}
+
+impl From<PwzNumber> for String {
+    fn from(pwz_number: PwzNumber) -> Self {
+        pwz_number.0
+    }
+}
+
+impl Deref for PwzNumber {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PwzNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for PwzNumber {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for PwzNumber {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for PwzNumber {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::serde::json;
+    use rocket_okapi::okapi::schemars::{
+        schema::{InstanceType, SingleOrVec},
+        schema_for,
+    };
+    use rstest::rstest;
+
+    use super::PwzNumber;
+
+    #[rstest]
+    #[case("5425740", true)]
+    #[case("8463856", true)]
+    #[case("5425741", false)]
+    #[case("542574", false)]
+    #[case("54257400", false)]
+    #[case("542574a", false)]
+    #[case("", false)]
+    fn validates_pwz_number_on_construction(#[case] pwz_number: &str, #[case] expected: bool) {
+        assert_eq!(PwzNumber::new(pwz_number.to_string()).is_ok(), expected);
+    }
+
+    #[test]
+    fn serializes_as_plain_string() {
+        let pwz_number = PwzNumber::new("5425740".to_string()).unwrap();
+
+        assert_eq!(json::to_string(&pwz_number).unwrap(), r#""5425740""#);
+        assert_eq!(
+            json::from_str::<PwzNumber>(r#""5425740""#).unwrap(),
+            pwz_number
+        );
+        assert_eq!(
+            schema_for!(PwzNumber).schema.instance_type,
+            Some(SingleOrVec::Single(Box::new(InstanceType::String)))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_pwz_number_on_deserialization() {
+        assert!(json::from_str::<PwzNumber>(r#""5425741""#).is_err());
+    }
+}
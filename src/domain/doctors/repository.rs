@@ -5,8 +5,11 @@ use chrono::Utc;
 use uuid::Uuid;
 
 use crate::domain::{
-    doctors::entities::{Doctor, NewDoctor},
-    utils::pagination::get_pagination_params,
+    doctors::{
+        entities::{Doctor, DoctorPwzNumberChange, NewDoctor},
+        pwz_number::PwzNumber,
+    },
+    utils::pagination::{get_pagination_params, PaginationError, DEFAULT_PAGE_SIZE},
 };
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -17,14 +20,18 @@ pub enum CreateDoctorRepositoryError {
     DuplicatedPeselNumber,
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum GetDoctorsRepositoryError {
     #[error("Invalid pagination parameters: {0}")]
-    InvalidPaginationParams(String),
+    InvalidPaginationParams(PaginationError),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -33,6 +40,38 @@ pub enum GetDoctorByIdRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetDoctorByPwzNumberRepositoryError {
+    #[error("Doctor with this PWZ number not found ({0})")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum UpdateDoctorPwzNumberRepositoryError {
+    #[error("Doctor with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("PWZ number already exists")]
+    DuplicatedPwzNumber,
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetDoctorPwzNumberChangesRepositoryError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -43,15 +82,31 @@ pub trait DoctorsRepository: Send + Sync + 'static {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        specialization: Option<&str>,
     ) -> Result<Vec<Doctor>, GetDoctorsRepositoryError>;
     async fn get_doctor_by_id(
         &self,
         doctor_id: Uuid,
     ) -> Result<Doctor, GetDoctorByIdRepositoryError>;
+    async fn get_doctor_by_pwz_number(
+        &self,
+        pwz_number: &PwzNumber,
+    ) -> Result<Doctor, GetDoctorByPwzNumberRepositoryError>;
+    async fn update_doctor_pwz_number(
+        &self,
+        doctor_id: Uuid,
+        new_pwz_number: &PwzNumber,
+        changed_by: Uuid,
+    ) -> Result<Doctor, UpdateDoctorPwzNumberRepositoryError>;
+    async fn get_doctor_pwz_number_changes(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<DoctorPwzNumberChange>, GetDoctorPwzNumberChangesRepositoryError>;
 }
 
 pub struct DoctorsRepositoryFake {
     doctors: RwLock<Vec<Doctor>>,
+    pwz_number_changes: RwLock<Vec<DoctorPwzNumberChange>>,
 }
 
 impl DoctorsRepositoryFake {
@@ -59,6 +114,7 @@ impl DoctorsRepositoryFake {
     pub fn new() -> Self {
         Self {
             doctors: RwLock::new(Vec::new()),
+            pwz_number_changes: RwLock::new(Vec::new()),
         }
     }
 }
@@ -83,6 +139,7 @@ impl DoctorsRepository for DoctorsRepositoryFake {
             name: new_doctor.name,
             pwz_number: new_doctor.pwz_number,
             pesel_number: new_doctor.pesel_number,
+            specialization: new_doctor.specialization,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -96,19 +153,25 @@ impl DoctorsRepository for DoctorsRepositoryFake {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        specialization: Option<&str>,
     ) -> Result<Vec<Doctor>, GetDoctorsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size)
-            .map_err(|err| GetDoctorsRepositoryError::InvalidPaginationParams(err.to_string()))?;
-        let a = offset;
-        let b = offset + page_size;
-
-        let mut doctors: Vec<Doctor> = vec![];
-        for i in a..b {
-            match self.doctors.read().unwrap().get(i as usize) {
-                Some(doctor) => doctors.push(doctor.clone()),
-                None => {}
-            }
-        }
+        let (page_size, offset) = get_pagination_params(page, page_size, DEFAULT_PAGE_SIZE)
+            .map_err(GetDoctorsRepositoryError::InvalidPaginationParams)?;
+
+        let doctors = self
+            .doctors
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|doctor| {
+                specialization.is_none_or(|specialization| {
+                    doctor.specialization.as_deref() == Some(specialization)
+                })
+            })
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .cloned()
+            .collect();
 
         Ok(doctors)
     }
@@ -128,6 +191,74 @@ impl DoctorsRepository for DoctorsRepositoryFake {
             None => Err(GetDoctorByIdRepositoryError::NotFound(doctor_id)),
         }
     }
+
+    async fn get_doctor_by_pwz_number(
+        &self,
+        pwz_number: &PwzNumber,
+    ) -> Result<Doctor, GetDoctorByPwzNumberRepositoryError> {
+        match self
+            .doctors
+            .read()
+            .unwrap()
+            .iter()
+            .find(|doctor| &doctor.pwz_number == pwz_number)
+        {
+            Some(doctor) => Ok(doctor.clone()),
+            None => Err(GetDoctorByPwzNumberRepositoryError::NotFound(
+                pwz_number.to_string(),
+            )),
+        }
+    }
+
+    async fn update_doctor_pwz_number(
+        &self,
+        doctor_id: Uuid,
+        new_pwz_number: &PwzNumber,
+        changed_by: Uuid,
+    ) -> Result<Doctor, UpdateDoctorPwzNumberRepositoryError> {
+        let mut doctors = self.doctors.write().unwrap();
+        if doctors
+            .iter()
+            .any(|doctor| doctor.id != doctor_id && &doctor.pwz_number == new_pwz_number)
+        {
+            return Err(UpdateDoctorPwzNumberRepositoryError::DuplicatedPwzNumber);
+        }
+
+        let doctor = doctors
+            .iter_mut()
+            .find(|doctor| doctor.id == doctor_id)
+            .ok_or(UpdateDoctorPwzNumberRepositoryError::NotFound(doctor_id))?;
+        let now = Utc::now();
+        self.pwz_number_changes
+            .write()
+            .unwrap()
+            .push(DoctorPwzNumberChange {
+                id: Uuid::new_v4(),
+                doctor_id,
+                old_pwz_number: doctor.pwz_number.clone(),
+                new_pwz_number: new_pwz_number.clone(),
+                changed_by,
+                changed_at: now,
+            });
+        doctor.pwz_number = new_pwz_number.clone();
+        doctor.updated_at = now;
+
+        Ok(doctor.clone())
+    }
+
+    async fn get_doctor_pwz_number_changes(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<DoctorPwzNumberChange>, GetDoctorPwzNumberChangesRepositoryError> {
+        Ok(self
+            .pwz_number_changes
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|change| change.doctor_id == doctor_id)
+            .cloned()
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -142,7 +273,8 @@ mod tests {
             entities::NewDoctor,
             repository::{
                 CreateDoctorRepositoryError, DoctorsRepository, GetDoctorByIdRepositoryError,
-                GetDoctorsRepositoryError,
+                GetDoctorByPwzNumberRepositoryError, GetDoctorsRepositoryError,
+                UpdateDoctorPwzNumberRepositoryError,
             },
         },
         utils::pagination::PaginationError,
@@ -179,6 +311,39 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn create_and_read_doctor_by_pwz_number() {
+        let repository = setup_repository();
+
+        let new_doctor =
+            NewDoctor::new("John Does".into(), "5425740".into(), "96021817257".into()).unwrap();
+
+        repository.create_doctor(new_doctor.clone()).await.unwrap();
+
+        let doctor_from_repo = repository
+            .get_doctor_by_pwz_number(&"5425740".try_into().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(doctor_from_repo, new_doctor);
+    }
+
+    #[tokio::test]
+    async fn returns_error_if_doctor_with_given_pwz_number_doesnt_exist() {
+        let repository = setup_repository();
+
+        let doctor_from_repo = repository
+            .get_doctor_by_pwz_number(&"5425740".try_into().unwrap())
+            .await;
+
+        assert_eq!(
+            doctor_from_repo,
+            Err(GetDoctorByPwzNumberRepositoryError::NotFound(
+                "5425740".into()
+            ))
+        );
+    }
+
     #[tokio::test]
     async fn create_and_read_doctors_from_database() {
         let repository = setup_repository();
@@ -209,7 +374,7 @@ mod tests {
             .await
             .unwrap();
 
-        let doctors = repository.get_doctors(None, Some(10)).await.unwrap();
+        let doctors = repository.get_doctors(None, Some(10), None).await.unwrap();
 
         assert_eq!(doctors.len(), 4);
         assert_eq!(doctors[0], new_doctor_0);
@@ -217,35 +382,79 @@ mod tests {
         assert_eq!(doctors[2], new_doctor_2);
         assert_eq!(doctors[3], new_doctor_3);
 
-        let doctors = repository.get_doctors(None, Some(2)).await.unwrap();
+        let doctors = repository.get_doctors(None, Some(2), None).await.unwrap();
 
         assert_eq!(doctors.len(), 2);
         assert_eq!(doctors[0], new_doctor_0);
         assert_eq!(doctors[1], new_doctor_1);
 
-        let doctors = repository.get_doctors(Some(1), Some(3)).await.unwrap();
+        let doctors = repository
+            .get_doctors(Some(1), Some(3), None)
+            .await
+            .unwrap();
 
         assert_eq!(doctors.len(), 1);
         assert_eq!(doctors[0], new_doctor_3);
 
-        let doctors = repository.get_doctors(Some(2), Some(3)).await.unwrap();
+        let doctors = repository
+            .get_doctors(Some(2), Some(3), None)
+            .await
+            .unwrap();
 
         assert_eq!(doctors.len(), 0);
     }
 
+    #[tokio::test]
+    async fn filters_doctors_by_specialization() {
+        let repository = setup_repository();
+
+        let cardiologist =
+            NewDoctor::new("John First".into(), "5425740".into(), "96021817257".into())
+                .unwrap()
+                .with_specialization(Some("Cardiology".into()))
+                .unwrap();
+        let dermatologist =
+            NewDoctor::new("John Second".into(), "8463856".into(), "99031301347".into())
+                .unwrap()
+                .with_specialization(Some("dermatology".into()))
+                .unwrap();
+        let general_practitioner =
+            NewDoctor::new("John Third".into(), "3123456".into(), "92022900002".into()).unwrap();
+        for new_doctor in [&cardiologist, &dermatologist, &general_practitioner] {
+            repository.create_doctor(new_doctor.clone()).await.unwrap();
+        }
+
+        let doctors = repository
+            .get_doctors(None, None, Some("cardiology"))
+            .await
+            .unwrap();
+
+        assert_eq!(doctors.len(), 1);
+        assert_eq!(doctors[0], cardiologist);
+
+        let doctors = repository
+            .get_doctors(None, None, Some("neurology"))
+            .await
+            .unwrap();
+
+        assert!(doctors.is_empty());
+    }
+
     #[tokio::test]
     async fn get_doctors_returns_error_if_pagination_params_are_incorrect() {
         let repository = setup_repository();
 
-        assert!(match repository.get_doctors(Some(-1), Some(10)).await {
-            Err(GetDoctorsRepositoryError::InvalidPaginationParams(_)) => true,
-            _ => false,
-        },);
+        assert!(
+            match repository.get_doctors(Some(-1), Some(10), None).await {
+                Err(GetDoctorsRepositoryError::InvalidPaginationParams(_)) => true,
+                _ => false,
+            },
+        );
 
         assert_eq!(
-            repository.get_doctors(Some(0), Some(0)).await,
+            repository.get_doctors(Some(0), Some(0), None).await,
             Err(GetDoctorsRepositoryError::InvalidPaginationParams(
-                PaginationError::InvalidPageSize.to_string()
+                PaginationError::ZeroOrNegativePageSize
             ))
         );
     }
@@ -279,4 +488,90 @@ mod tests {
             Err(CreateDoctorRepositoryError::DuplicatedPeselNumber)
         );
     }
+
+    #[tokio::test]
+    async fn updates_doctor_pwz_number_and_records_change() {
+        let repository = setup_repository();
+        let doctor = repository
+            .create_doctor(
+                NewDoctor::new("John Doe".into(), "5425740".into(), "96021817257".into()).unwrap(),
+            )
+            .await
+            .unwrap();
+        let changed_by = Uuid::new_v4();
+
+        let updated_doctor = repository
+            .update_doctor_pwz_number(doctor.id, &"8463856".try_into().unwrap(), changed_by)
+            .await
+            .unwrap();
+
+        assert_eq!(updated_doctor.pwz_number, "8463856");
+        assert!(updated_doctor.updated_at >= doctor.updated_at);
+        assert_eq!(
+            repository
+                .get_doctor_pwz_number_changes(doctor.id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|change| (
+                    change.old_pwz_number.to_string(),
+                    change.new_pwz_number.to_string(),
+                    change.changed_by
+                ))
+                .collect::<Vec<_>>(),
+            vec![("5425740".to_string(), "8463856".to_string(), changed_by)]
+        );
+        assert_eq!(
+            repository
+                .get_doctor_by_pwz_number(&"8463856".try_into().unwrap())
+                .await
+                .unwrap()
+                .id,
+            doctor.id
+        );
+    }
+
+    #[tokio::test]
+    async fn doesnt_update_doctor_pwz_number_if_doctor_doesnt_exist_or_number_is_taken() {
+        let repository = setup_repository();
+        let doctor = repository
+            .create_doctor(
+                NewDoctor::new("John Doe".into(), "5425740".into(), "96021817257".into()).unwrap(),
+            )
+            .await
+            .unwrap();
+        repository
+            .create_doctor(
+                NewDoctor::new("Jane Doe".into(), "8463856".into(), "99031301347".into()).unwrap(),
+            )
+            .await
+            .unwrap();
+        let unknown_doctor_id = Uuid::new_v4();
+
+        assert_eq!(
+            repository
+                .update_doctor_pwz_number(doctor.id, &"8463856".try_into().unwrap(), doctor.id)
+                .await,
+            Err(UpdateDoctorPwzNumberRepositoryError::DuplicatedPwzNumber)
+        );
+        assert_eq!(
+            repository
+                .update_doctor_pwz_number(
+                    unknown_doctor_id,
+                    &"3123456".try_into().unwrap(),
+                    doctor.id
+                )
+                .await,
+            Err(UpdateDoctorPwzNumberRepositoryError::NotFound(
+                unknown_doctor_id
+            ))
+        );
+        assert_eq!(
+            repository
+                .get_doctor_pwz_number_changes(doctor.id)
+                .await
+                .unwrap(),
+            vec![]
+        );
+    }
 }
@@ -2,11 +2,14 @@ use uuid::Uuid;
 
 use super::{
     entities::{Doctor, NewDoctor},
+    pwz_number::PwzNumber,
     repository::{
         CreateDoctorRepositoryError, DoctorsRepository, GetDoctorByIdRepositoryError,
-        GetDoctorsRepositoryError,
+        GetDoctorByPwzNumberRepositoryError, GetDoctorsRepositoryError,
+        UpdateDoctorPwzNumberRepositoryError,
     },
 };
+use crate::domain::utils::validators::validate_specialization::normalize_specialization;
 
 #[derive(Debug)]
 pub enum CreateDoctorError {
@@ -19,11 +22,25 @@ pub enum GetDoctorByIdError {
     RepositoryError(GetDoctorByIdRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum GetDoctorByPwzNumberError {
+    DomainError(String),
+    RepositoryError(GetDoctorByPwzNumberRepositoryError),
+}
+
 #[derive(Debug)]
 pub enum GetDoctorsWithPaginationError {
+    DomainError(String),
     RepositoryError(GetDoctorsRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum UpdateDoctorPwzError {
+    DomainError(String),
+    NotSameDoctor,
+    RepositoryError(UpdateDoctorPwzNumberRepositoryError),
+}
+
 pub struct DoctorsService {
     repository: Box<dyn DoctorsRepository>,
 }
@@ -38,8 +55,10 @@ impl DoctorsService {
         name: String,
         pesel_number: String,
         pwz_number: String,
+        specialization: Option<String>,
     ) -> Result<Doctor, CreateDoctorError> {
         let new_doctor = NewDoctor::new(name, pwz_number, pesel_number)
+            .and_then(|new_doctor| new_doctor.with_specialization(specialization))
             .map_err(|err| CreateDoctorError::DomainError(err.to_string()))?;
 
         let created_doctor = self
@@ -61,14 +80,58 @@ impl DoctorsService {
         Ok(doctor)
     }
 
+    pub async fn get_doctor_by_pwz_number(
+        &self,
+        pwz_number: &str,
+    ) -> Result<Doctor, GetDoctorByPwzNumberError> {
+        let pwz_number = PwzNumber::new(pwz_number.to_string())
+            .map_err(|err| GetDoctorByPwzNumberError::DomainError(err.to_string()))?;
+
+        let doctor = self
+            .repository
+            .get_doctor_by_pwz_number(&pwz_number)
+            .await
+            .map_err(GetDoctorByPwzNumberError::RepositoryError)?;
+
+        Ok(doctor)
+    }
+
+    // Doctors can only correct their own PWZ number, the replaced number is kept in the audit
+    pub async fn update_doctor_pwz(
+        &self,
+        doctor_id: Uuid,
+        new_pwz_number: &str,
+        changed_by: Uuid,
+    ) -> Result<Doctor, UpdateDoctorPwzError> {
+        if doctor_id != changed_by {
+            Err(UpdateDoctorPwzError::NotSameDoctor)?;
+        }
+        let new_pwz_number = PwzNumber::new(new_pwz_number.to_string())
+            .map_err(|err| UpdateDoctorPwzError::DomainError(err.to_string()))?;
+
+        let doctor = self
+            .repository
+            .update_doctor_pwz_number(doctor_id, &new_pwz_number, changed_by)
+            .await
+            .map_err(UpdateDoctorPwzError::RepositoryError)?;
+
+        Ok(doctor)
+    }
+
     pub async fn get_doctors_with_pagination(
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        specialization: Option<String>,
     ) -> Result<Vec<Doctor>, GetDoctorsWithPaginationError> {
+        let specialization = specialization
+            .map(|specialization| normalize_specialization(&specialization))
+            .transpose()
+            .map_err(|err| GetDoctorsWithPaginationError::DomainError(err.to_string()))?;
+
         let doctors = self
             .repository
-            .get_doctors(page, page_size)
+            .get_doctors(page, page_size, specialization.as_deref())
             .await
             .map_err(|err| GetDoctorsWithPaginationError::RepositoryError(err))?;
 
@@ -80,8 +143,13 @@ impl DoctorsService {
 mod tests {
     use uuid::Uuid;
 
-    use super::{CreateDoctorError, DoctorsService, GetDoctorByIdError};
-    use crate::domain::doctors::repository::DoctorsRepositoryFake;
+    use super::{
+        CreateDoctorError, DoctorsService, GetDoctorByIdError, GetDoctorByPwzNumberError,
+        GetDoctorsWithPaginationError, UpdateDoctorPwzError,
+    };
+    use crate::domain::doctors::repository::{
+        DoctorsRepositoryFake, UpdateDoctorPwzNumberRepositoryError,
+    };
 
     fn setup_service() -> DoctorsService {
         DoctorsService::new(Box::new(DoctorsRepositoryFake::new()))
@@ -92,7 +160,12 @@ mod tests {
         let service = setup_service();
 
         let created_doctor = service
-            .create_doctor("John Doex".into(), "96021807250".into(), "5425740".into())
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
             .await
             .unwrap();
 
@@ -112,7 +185,12 @@ mod tests {
         let service = setup_service();
 
         let result = service
-            .create_doctor("John Doex".into(), "96021807251".into(), "5425740".into()) // invalid pesel
+            .create_doctor(
+                "John Doex".into(),
+                "96021807251".into(),
+                "5425740".into(),
+                None,
+            ) // invalid pesel
             .await;
 
         assert!(match result {
@@ -126,12 +204,22 @@ mod tests {
         let service = setup_service();
 
         service
-            .create_doctor("John Doex".into(), "96021807250".into(), "5425740".into())
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
             .await
             .unwrap();
 
         let duplicated_pesel_number_result = service
-            .create_doctor("John Doex".into(), "96021807250".into(), "8463856".into())
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "8463856".into(),
+                None,
+            )
             .await;
 
         assert!(match duplicated_pesel_number_result {
@@ -140,7 +228,12 @@ mod tests {
         });
 
         let duplicated_pwz_number_result = service
-            .create_doctor("John Doex".into(), "99031301347".into(), "5425740".into())
+            .create_doctor(
+                "John Doex".into(),
+                "99031301347".into(),
+                "5425740".into(),
+                None,
+            )
             .await;
 
         assert!(match duplicated_pwz_number_result {
@@ -161,64 +254,117 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn gets_doctor_by_pwz_number() {
+        let service = setup_service();
+
+        let created_doctor = service
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let doctor = service.get_doctor_by_pwz_number("5425740").await.unwrap();
+
+        assert_eq!(doctor.id, created_doctor.id);
+    }
+
+    #[tokio::test]
+    async fn get_doctor_by_pwz_number_returns_error_if_pwz_number_is_invalid_or_unknown() {
+        let service = setup_service();
+
+        assert!(matches!(
+            service.get_doctor_by_pwz_number("12345").await,
+            Err(GetDoctorByPwzNumberError::DomainError(_))
+        ));
+        assert!(matches!(
+            service.get_doctor_by_pwz_number("5425740").await,
+            Err(GetDoctorByPwzNumberError::RepositoryError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn gets_doctors_with_pagination() {
         let service = setup_service();
 
         service
-            .create_doctor("John Doex".into(), "96021817257".into(), "5425740".into())
+            .create_doctor(
+                "John Doex".into(),
+                "96021817257".into(),
+                "5425740".into(),
+                None,
+            )
             .await
             .unwrap();
         service
-            .create_doctor("John Doey".into(), "99031301347".into(), "8463856".into())
+            .create_doctor(
+                "John Doey".into(),
+                "99031301347".into(),
+                "8463856".into(),
+                None,
+            )
             .await
             .unwrap();
         service
-            .create_doctor("John Doez".into(), "92022900002".into(), "3123456".into())
+            .create_doctor(
+                "John Doez".into(),
+                "92022900002".into(),
+                "3123456".into(),
+                None,
+            )
             .await
             .unwrap();
         service
-            .create_doctor("John Doeq".into(), "96021807250".into(), "5425751".into())
+            .create_doctor(
+                "John Doeq".into(),
+                "96021807250".into(),
+                "5425751".into(),
+                None,
+            )
             .await
             .unwrap();
 
         let doctors = service
-            .get_doctors_with_pagination(Some(1), Some(2))
+            .get_doctors_with_pagination(Some(1), Some(2), None)
             .await
             .unwrap();
 
         assert_eq!(doctors.len(), 2);
 
         let doctors = service
-            .get_doctors_with_pagination(Some(1), Some(3))
+            .get_doctors_with_pagination(Some(1), Some(3), None)
             .await
             .unwrap();
 
         assert_eq!(doctors.len(), 1);
 
         let doctors = service
-            .get_doctors_with_pagination(None, Some(10))
+            .get_doctors_with_pagination(None, Some(10), None)
             .await
             .unwrap();
 
         assert_eq!(doctors.len(), 4);
 
         let doctors = service
-            .get_doctors_with_pagination(Some(1), None)
+            .get_doctors_with_pagination(Some(1), None, None)
             .await
             .unwrap();
 
         assert_eq!(doctors.len(), 0);
 
         let doctors = service
-            .get_doctors_with_pagination(None, None)
+            .get_doctors_with_pagination(None, None, None)
             .await
             .unwrap();
 
         assert_eq!(doctors.len(), 4);
 
         let doctors = service
-            .get_doctors_with_pagination(Some(2), Some(3))
+            .get_doctors_with_pagination(Some(2), Some(3), None)
             .await
             .unwrap();
 
@@ -230,13 +376,123 @@ mod tests {
         let service = setup_service();
 
         assert!(service
-            .get_doctors_with_pagination(Some(-1), None)
+            .get_doctors_with_pagination(Some(-1), None, None)
             .await
             .is_err());
 
         assert!(service
-            .get_doctors_with_pagination(None, Some(0))
+            .get_doctors_with_pagination(None, Some(0), None)
             .await
             .is_err());
     }
+
+    #[tokio::test]
+    async fn filters_doctors_by_normalized_specialization() {
+        let service = setup_service();
+
+        let cardiologist = service
+            .create_doctor(
+                "John Doex".into(),
+                "96021817257".into(),
+                "5425740".into(),
+                Some("Cardiology".into()),
+            )
+            .await
+            .unwrap();
+        service
+            .create_doctor(
+                "John Doey".into(),
+                "99031301347".into(),
+                "8463856".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let doctors = service
+            .get_doctors_with_pagination(None, None, Some("  CARDIOLOGY ".into()))
+            .await
+            .unwrap();
+
+        assert_eq!(doctors, vec![cardiologist]);
+        assert!(matches!(
+            service
+                .get_doctors_with_pagination(None, None, Some("cardiology 2".into()))
+                .await,
+            Err(GetDoctorsWithPaginationError::DomainError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn updates_doctor_pwz_number() {
+        let service = setup_service();
+        let doctor = service
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let updated_doctor = service
+            .update_doctor_pwz(doctor.id, "8463856", doctor.id)
+            .await
+            .unwrap();
+
+        assert_eq!(updated_doctor.pwz_number, "8463856");
+        assert_eq!(
+            service
+                .get_doctor_by_pwz_number("8463856")
+                .await
+                .unwrap()
+                .id,
+            doctor.id
+        );
+    }
+
+    #[tokio::test]
+    async fn update_doctor_pwz_returns_error_if_pwz_number_is_invalid_taken_or_not_own() {
+        let service = setup_service();
+        let doctor = service
+            .create_doctor(
+                "John Doex".into(),
+                "96021807250".into(),
+                "5425740".into(),
+                None,
+            )
+            .await
+            .unwrap();
+        let other_doctor = service
+            .create_doctor(
+                "Jane Doex".into(),
+                "99031301347".into(),
+                "8463856".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .update_doctor_pwz(doctor.id, "5425741", doctor.id)
+                .await,
+            Err(UpdateDoctorPwzError::DomainError(_))
+        ));
+        assert!(matches!(
+            service
+                .update_doctor_pwz(doctor.id, "8463856", doctor.id)
+                .await,
+            Err(UpdateDoctorPwzError::RepositoryError(
+                UpdateDoctorPwzNumberRepositoryError::DuplicatedPwzNumber
+            ))
+        ));
+        assert!(matches!(
+            service
+                .update_doctor_pwz(doctor.id, "3123456", other_doctor.id)
+                .await,
+            Err(UpdateDoctorPwzError::NotSameDoctor)
+        ));
+    }
 }
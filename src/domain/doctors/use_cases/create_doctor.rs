@@ -1,26 +1,35 @@
 use uuid::Uuid;
 
 use crate::domain::{
-    doctors::entities::NewDoctor,
+    doctors::{entities::NewDoctor, pwz_number::PwzNumber},
     utils::validators::{
-        validate_name::validate_name, validate_pesel_number::validate_pesel_number,
-        validate_pwz_number::validate_pwz_number,
+        validate_name::normalize_name, validate_pesel_number::validate_pesel_number,
+        validate_specialization::normalize_specialization,
     },
 };
 
 impl NewDoctor {
     pub fn new(name: String, pwz_number: String, pesel_number: String) -> anyhow::Result<Self> {
-        validate_name(&name)?;
+        let name = normalize_name(&name)?;
         validate_pesel_number(&pesel_number)?;
-        validate_pwz_number(&pwz_number)?;
+        let pwz_number = PwzNumber::new(pwz_number)?;
 
         Ok(NewDoctor {
             id: Uuid::new_v4(),
             name,
             pwz_number,
             pesel_number,
+            specialization: None,
         })
     }
+
+    pub fn with_specialization(mut self, specialization: Option<String>) -> anyhow::Result<Self> {
+        self.specialization = specialization
+            .map(|specialization| normalize_specialization(&specialization))
+            .transpose()?;
+
+        Ok(self)
+    }
 }
 
 #[cfg(test)]
@@ -37,6 +46,26 @@ mod tests {
         assert_eq!(sut.pesel_number, "96021817257");
     }
 
+    #[test]
+    fn creates_doctor_with_normalized_specialization() {
+        let sut = NewDoctor::new("John Doe".into(), "5425740".into(), "96021817257".into())
+            .unwrap()
+            .with_specialization(Some("  Family  Medicine ".into()))
+            .unwrap();
+
+        assert_eq!(sut.specialization, Some("family medicine".into()));
+    }
+
+    #[test]
+    fn doesnt_create_doctor_if_specialization_is_invalid() {
+        assert!(
+            NewDoctor::new("John Doe".into(), "5425740".into(), "96021817257".into())
+                .unwrap()
+                .with_specialization(Some("x".into()))
+                .is_err()
+        );
+    }
+
     #[test]
     fn doesnt_create_doctor_if_pesel_number_is_invalid() {
         assert!(NewDoctor::new("John Doe".into(), "4123456".into(), "92223300009".into()).is_err());
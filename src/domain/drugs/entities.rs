@@ -3,13 +3,39 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, sqlx::Type, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+use crate::domain::prescriptions::entities::PrescriptionType;
+
+#[derive(
+    Debug, PartialEq, Eq, Hash, sqlx::Type, Clone, Copy, Deserialize, Serialize, JsonSchema,
+)]
 #[sqlx(type_name = "drug_content_type", rename_all = "snake_case")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum DrugContentType {
     BottleOfLiquid,
     SolidPills,
     LiquidPills,
+    Inhaler,
+    Injection,
+}
+
+impl DrugContentType {
+    pub const ALL: [DrugContentType; 5] = [
+        DrugContentType::BottleOfLiquid,
+        DrugContentType::SolidPills,
+        DrugContentType::LiquidPills,
+        DrugContentType::Inhaler,
+        DrugContentType::Injection,
+    ];
+}
+
+// Prescriptions of the dedicated types can only contain drugs of the matching category
+#[derive(Debug, PartialEq, sqlx::Type, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[sqlx(type_name = "drug_category", rename_all = "snake_case")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DrugCategory {
+    Antibiotic,
+    ChronicDisease,
+    Immunological,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -21,6 +47,15 @@ pub struct NewDrug {
     pub mg_per_pill: Option<i32>,
     pub ml_per_pill: Option<i32>,
     pub volume_ml: Option<i32>,
+    pub doses_count: Option<i32>,
+    pub mcg_per_dose: Option<i32>,
+    pub ml_per_dose: Option<i32>,
+    pub unit_price_cents: Option<i32>,
+    pub manufacturer: Option<String>,
+    pub barcode: Option<String>,
+    pub category: Option<DrugCategory>,
+    pub allowed_prescription_types: Vec<PrescriptionType>,
+    pub created_by: Option<Uuid>,
 }
 
 fn example_drug_name() -> &'static str {
@@ -41,6 +76,33 @@ fn example_ml_per_pill() -> Option<i32> {
 fn example_volume_ml() -> Option<i32> {
     None
 }
+fn example_doses_count() -> Option<i32> {
+    None
+}
+fn example_mcg_per_dose() -> Option<i32> {
+    None
+}
+fn example_ml_per_dose() -> Option<i32> {
+    None
+}
+fn example_unit_price_cents() -> Option<i32> {
+    Some(1299)
+}
+fn example_manufacturer() -> Option<&'static str> {
+    Some("US Pharmacia")
+}
+fn example_barcode() -> Option<&'static str> {
+    Some("5909990733828")
+}
+fn example_stock() -> i32 {
+    12
+}
+fn example_category() -> Option<DrugCategory> {
+    None
+}
+fn example_allowed_prescription_types() -> Vec<PrescriptionType> {
+    vec![]
+}
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Drug {
@@ -57,8 +119,60 @@ pub struct Drug {
     pub ml_per_pill: Option<i32>,
     #[schemars(example = "example_volume_ml")]
     pub volume_ml: Option<i32>,
+    #[schemars(example = "example_doses_count")]
+    pub doses_count: Option<i32>,
+    #[schemars(example = "example_mcg_per_dose")]
+    pub mcg_per_dose: Option<i32>,
+    #[schemars(example = "example_ml_per_dose")]
+    pub ml_per_dose: Option<i32>,
+    #[schemars(example = "example_unit_price_cents")]
+    pub unit_price_cents: Option<i32>,
+    #[schemars(example = "example_manufacturer")]
+    pub manufacturer: Option<String>,
+    #[schemars(example = "example_barcode")]
+    pub barcode: Option<String>,
+    // Uncategorized drugs can be prescribed on any prescription
+    #[schemars(example = "example_category")]
+    pub category: Option<DrugCategory>,
+    // Prescription types the drug can be prescribed on, any type when empty
+    #[schemars(example = "example_allowed_prescription_types")]
+    pub allowed_prescription_types: Vec<PrescriptionType>,
+    // Number of packages available in the pharmacy, never below zero
+    #[schemars(example = "example_stock")]
+    pub stock: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // Deactivated drugs are kept for the prescriptions referencing them but hidden from the list
+    pub deactivated_at: Option<DateTime<Utc>>,
+    // User who created the drug, empty for drugs created without a session
+    pub created_by: Option<Uuid>,
+}
+
+impl Drug {
+    // e.g. "30 pills, 300 mg each", falls back to the content type if its quantities are missing
+    pub fn describe_dosage(&self) -> String {
+        let dosage = match self.content_type {
+            DrugContentType::SolidPills => self
+                .pills_count
+                .zip(self.mg_per_pill)
+                .map(|(count, mg)| format!("{} pills, {} mg each", count, mg)),
+            DrugContentType::LiquidPills => self
+                .pills_count
+                .zip(self.ml_per_pill)
+                .map(|(count, ml)| format!("{} pills, {} ml each", count, ml)),
+            DrugContentType::BottleOfLiquid => self.volume_ml.map(|ml| format!("{} ml bottle", ml)),
+            DrugContentType::Inhaler => self
+                .doses_count
+                .zip(self.mcg_per_dose)
+                .map(|(count, mcg)| format!("{} doses, {} mcg each", count, mcg)),
+            DrugContentType::Injection => self
+                .doses_count
+                .zip(self.ml_per_dose)
+                .map(|(count, ml)| format!("{} doses, {} ml each", count, ml)),
+        };
+
+        dosage.unwrap_or_else(|| self.content_type.description().to_string())
+    }
 }
 
 impl PartialEq<NewDrug> for Drug {
@@ -70,6 +184,15 @@ impl PartialEq<NewDrug> for Drug {
             && self.mg_per_pill == other.mg_per_pill
             && self.ml_per_pill == other.ml_per_pill
             && self.volume_ml == other.volume_ml
+            && self.doses_count == other.doses_count
+            && self.mcg_per_dose == other.mcg_per_dose
+            && self.ml_per_dose == other.ml_per_dose
+            && self.unit_price_cents == other.unit_price_cents
+            && self.manufacturer == other.manufacturer
+            && self.barcode == other.barcode
+            && self.category == other.category
+            && self.allowed_prescription_types == other.allowed_prescription_types
+            && self.created_by == other.created_by
     }
 }
 
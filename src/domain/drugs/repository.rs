@@ -1,26 +1,36 @@
-use std::sync::RwLock;
+use std::{collections::HashMap, sync::RwLock};
 
 use async_trait::async_trait;
 use chrono::Utc;
 use uuid::Uuid;
 
 use crate::domain::{
-    drugs::entities::{Drug, NewDrug},
-    utils::pagination::get_pagination_params,
+    drugs::entities::{Drug, DrugContentType, NewDrug},
+    utils::pagination::{get_pagination_params, PaginationError},
 };
 
+pub const DRUGS_DEFAULT_PAGE_SIZE: i64 = 50;
+
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreateDrugRepositoryError {
+    #[error("Barcode already exists")]
+    DuplicatedBarcode,
+    #[error("Drug with this name and strength already exists")]
+    DuplicatedDrug,
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum GetDrugsRepositoryError {
     #[error("Invalid pagination parameters: {0}")]
-    InvalidPaginationParams(String),
+    InvalidPaginationParams(PaginationError),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -29,6 +39,40 @@ pub enum GetDrugByIdRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetDrugByBarcodeRepositoryError {
+    #[error("Drug with this barcode not found ({0})")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum DeactivateDrugRepositoryError {
+    #[error("Drug with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AdjustDrugStockRepositoryError {
+    #[error("Drug with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Stock of drug with id {0} can't go below zero")]
+    InsufficientStock(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -38,8 +82,24 @@ pub trait DrugsRepository: Send + Sync + 'static {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        include_inactive: bool,
     ) -> Result<Vec<Drug>, GetDrugsRepositoryError>;
     async fn get_drug_by_id(&self, drug_id: Uuid) -> Result<Drug, GetDrugByIdRepositoryError>;
+    async fn get_drug_by_barcode(
+        &self,
+        barcode: &str,
+    ) -> Result<Drug, GetDrugByBarcodeRepositoryError>;
+    async fn deactivate_drug(&self, drug_id: Uuid) -> Result<Drug, DeactivateDrugRepositoryError>;
+    // Adds the delta to the current stock in one step, so concurrent adjustments don't overwrite each other
+    async fn adjust_drug_stock(
+        &self,
+        drug_id: Uuid,
+        delta: i32,
+    ) -> Result<Drug, AdjustDrugStockRepositoryError>;
+    // Deactivated drugs aren't counted, every content type is present even if it has no drugs
+    async fn count_drugs_by_content_type(
+        &self,
+    ) -> Result<HashMap<DrugContentType, i64>, GetDrugsRepositoryError>;
 }
 
 pub struct DrugsRepositoryFake {
@@ -58,6 +118,30 @@ impl DrugsRepositoryFake {
 #[async_trait]
 impl DrugsRepository for DrugsRepositoryFake {
     async fn create_drug(&self, new_drug: NewDrug) -> Result<Drug, CreateDrugRepositoryError> {
+        if new_drug.barcode.is_some()
+            && self
+                .drugs
+                .read()
+                .unwrap()
+                .iter()
+                .any(|drug| drug.barcode == new_drug.barcode)
+        {
+            return Err(CreateDrugRepositoryError::DuplicatedBarcode);
+        }
+
+        if self.drugs.read().unwrap().iter().any(|drug| {
+            drug.name == new_drug.name
+                && drug.content_type == new_drug.content_type
+                && drug.mg_per_pill == new_drug.mg_per_pill
+                && drug.ml_per_pill == new_drug.ml_per_pill
+                && drug.volume_ml == new_drug.volume_ml
+                && drug.doses_count == new_drug.doses_count
+                && drug.mcg_per_dose == new_drug.mcg_per_dose
+                && drug.ml_per_dose == new_drug.ml_per_dose
+        }) {
+            return Err(CreateDrugRepositoryError::DuplicatedDrug);
+        }
+
         let drug = Drug {
             id: new_drug.id,
             name: new_drug.name,
@@ -66,8 +150,19 @@ impl DrugsRepository for DrugsRepositoryFake {
             ml_per_pill: new_drug.ml_per_pill,
             pills_count: new_drug.pills_count,
             volume_ml: new_drug.volume_ml,
+            doses_count: new_drug.doses_count,
+            mcg_per_dose: new_drug.mcg_per_dose,
+            ml_per_dose: new_drug.ml_per_dose,
+            unit_price_cents: new_drug.unit_price_cents,
+            manufacturer: new_drug.manufacturer,
+            barcode: new_drug.barcode,
+            category: new_drug.category,
+            allowed_prescription_types: new_drug.allowed_prescription_types,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            stock: 0,
+            deactivated_at: None,
+            created_by: new_drug.created_by,
         };
 
         self.drugs.write().unwrap().push(drug.clone());
@@ -79,19 +174,22 @@ impl DrugsRepository for DrugsRepositoryFake {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        include_inactive: bool,
     ) -> Result<Vec<Drug>, GetDrugsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size)
-            .map_err(|err| GetDrugsRepositoryError::InvalidPaginationParams(err.to_string()))?;
-        let a = offset;
-        let b = offset + page_size;
-
-        let mut drugs: Vec<Drug> = vec![];
-        for i in a..b {
-            match self.drugs.read().unwrap().get(i as usize) {
-                Some(drug) => drugs.push(drug.clone()),
-                None => {}
-            }
-        }
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, DRUGS_DEFAULT_PAGE_SIZE)
+                .map_err(GetDrugsRepositoryError::InvalidPaginationParams)?;
+
+        let drugs = self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|drug| include_inactive || drug.deactivated_at.is_none())
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .cloned()
+            .collect();
 
         Ok(drugs)
     }
@@ -108,6 +206,74 @@ impl DrugsRepository for DrugsRepositoryFake {
             None => Err(GetDrugByIdRepositoryError::NotFound(drug_id)),
         }
     }
+
+    async fn get_drug_by_barcode(
+        &self,
+        barcode: &str,
+    ) -> Result<Drug, GetDrugByBarcodeRepositoryError> {
+        match self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|drug| drug.barcode.as_deref() == Some(barcode))
+        {
+            Some(drug) => Ok(drug.clone()),
+            None => Err(GetDrugByBarcodeRepositoryError::NotFound(
+                barcode.to_string(),
+            )),
+        }
+    }
+
+    async fn deactivate_drug(&self, drug_id: Uuid) -> Result<Drug, DeactivateDrugRepositoryError> {
+        let mut drugs = self.drugs.write().unwrap();
+        let drug = drugs
+            .iter_mut()
+            .find(|drug| drug.id == drug_id)
+            .ok_or(DeactivateDrugRepositoryError::NotFound(drug_id))?;
+        if drug.deactivated_at.is_none() {
+            drug.deactivated_at = Some(Utc::now());
+            drug.updated_at = Utc::now();
+        }
+
+        Ok(drug.clone())
+    }
+
+    async fn adjust_drug_stock(
+        &self,
+        drug_id: Uuid,
+        delta: i32,
+    ) -> Result<Drug, AdjustDrugStockRepositoryError> {
+        let mut drugs = self.drugs.write().unwrap();
+        let drug = drugs
+            .iter_mut()
+            .find(|drug| drug.id == drug_id)
+            .ok_or(AdjustDrugStockRepositoryError::NotFound(drug_id))?;
+        drug.stock = drug
+            .stock
+            .checked_add(delta)
+            .filter(|stock| *stock >= 0)
+            .ok_or(AdjustDrugStockRepositoryError::InsufficientStock(drug_id))?;
+        drug.updated_at = Utc::now();
+
+        Ok(drug.clone())
+    }
+
+    async fn count_drugs_by_content_type(
+        &self,
+    ) -> Result<HashMap<DrugContentType, i64>, GetDrugsRepositoryError> {
+        let mut counts: HashMap<DrugContentType, i64> = DrugContentType::ALL
+            .into_iter()
+            .map(|content_type| (content_type, 0))
+            .collect();
+        for drug in self.drugs.read().unwrap().iter() {
+            if drug.deactivated_at.is_none() {
+                *counts.entry(drug.content_type).or_default() += 1;
+            }
+        }
+
+        Ok(counts)
+    }
 }
 
 #[cfg(test)]
@@ -115,7 +281,9 @@ mod tests {
     use uuid::Uuid;
 
     use super::{
-        DrugsRepository, DrugsRepositoryFake, GetDrugByIdRepositoryError, GetDrugsRepositoryError,
+        AdjustDrugStockRepositoryError, CreateDrugRepositoryError, DeactivateDrugRepositoryError,
+        DrugsRepository, DrugsRepositoryFake, GetDrugByBarcodeRepositoryError,
+        GetDrugByIdRepositoryError, GetDrugsRepositoryError, DRUGS_DEFAULT_PAGE_SIZE,
     };
     use crate::domain::drugs::entities::{DrugContentType, NewDrug};
 
@@ -134,6 +302,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -170,6 +344,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
         let new_drug_1 = NewDrug::new(
@@ -179,6 +359,12 @@ mod tests {
             Some(400),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
         let new_drug_2 = NewDrug::new(
@@ -188,6 +374,12 @@ mod tests {
             Some(200),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
         let new_drug_3 = NewDrug::new(
@@ -197,6 +389,12 @@ mod tests {
             None,
             None,
             Some(400),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -205,7 +403,7 @@ mod tests {
         repository.create_drug(new_drug_2.clone()).await.unwrap();
         repository.create_drug(new_drug_3.clone()).await.unwrap();
 
-        let drugs = repository.get_drugs(None, Some(10)).await.unwrap();
+        let drugs = repository.get_drugs(None, Some(10), false).await.unwrap();
 
         assert_eq!(drugs.len(), 4);
         assert_eq!(drugs[0], new_drug_0);
@@ -213,34 +411,387 @@ mod tests {
         assert_eq!(drugs[2], new_drug_2);
         assert_eq!(drugs[3], new_drug_3);
 
-        let drugs = repository.get_drugs(None, Some(2)).await.unwrap();
+        let drugs = repository.get_drugs(None, Some(2), false).await.unwrap();
 
         assert_eq!(drugs.len(), 2);
         assert_eq!(drugs[0], new_drug_0);
         assert_eq!(drugs[1], new_drug_1);
 
-        let drugs = repository.get_drugs(Some(1), Some(3)).await.unwrap();
+        let drugs = repository.get_drugs(Some(1), Some(3), false).await.unwrap();
 
         assert_eq!(drugs.len(), 1);
         assert_eq!(drugs[0], new_drug_3);
 
-        let drugs = repository.get_drugs(Some(2), Some(3)).await.unwrap();
+        let drugs = repository.get_drugs(Some(2), Some(3), false).await.unwrap();
 
         assert_eq!(drugs.len(), 0);
     }
 
+    #[tokio::test]
+    async fn create_and_read_drug_by_barcode() {
+        let repository = setup_repository();
+
+        let drug = NewDrug::new(
+            "Gripex Max".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("US Pharmacia".into()),
+            Some("5909990733828".into()),
+        )
+        .unwrap();
+
+        repository.create_drug(drug.clone()).await.unwrap();
+
+        let drug_from_repo = repository
+            .get_drug_by_barcode("5909990733828")
+            .await
+            .unwrap();
+
+        assert_eq!(drug, drug_from_repo);
+
+        assert_eq!(
+            repository.get_drug_by_barcode("5900017304007").await,
+            Err(GetDrugByBarcodeRepositoryError::NotFound(
+                "5900017304007".into()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_error_if_barcode_is_duplicated() {
+        let repository = setup_repository();
+
+        let create_drug_with_barcode = |name: &str, barcode: Option<&str>| {
+            NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                barcode.map(Into::into),
+            )
+            .unwrap()
+        };
+
+        repository
+            .create_drug(create_drug_with_barcode("Gripex", Some("5909990733828")))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_drug_with_barcode("Apap", None))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_drug_with_barcode("Flegamax", None))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .create_drug(create_drug_with_barcode(
+                    "Gripex Max",
+                    Some("5909990733828")
+                ))
+                .await,
+            Err(CreateDrugRepositoryError::DuplicatedBarcode)
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_error_if_drug_with_same_name_and_strength_exists() {
+        let repository = setup_repository();
+
+        let create_drug_with_strength = |mg_per_pill: i32| {
+            NewDrug::new(
+                "Apap".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(mg_per_pill),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        repository
+            .create_drug(create_drug_with_strength(300))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_drug_with_strength(500))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository.create_drug(create_drug_with_strength(300)).await,
+            Err(CreateDrugRepositoryError::DuplicatedDrug)
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_error_if_inhaler_with_same_name_and_dose_exists() {
+        let repository = setup_repository();
+
+        let create_inhaler_with_dose = |mcg_per_dose: i32| {
+            NewDrug::new(
+                "Ventolin".into(),
+                DrugContentType::Inhaler,
+                None,
+                None,
+                None,
+                None,
+                Some(200),
+                Some(mcg_per_dose),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        repository
+            .create_drug(create_inhaler_with_dose(100))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_inhaler_with_dose(200))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository.create_drug(create_inhaler_with_dose(100)).await,
+            Err(CreateDrugRepositoryError::DuplicatedDrug)
+        );
+    }
+
     #[tokio::test]
     async fn get_drugs_returns_error_if_pagination_params_are_incorrect() {
         let repository = setup_repository();
 
-        assert!(match repository.get_drugs(Some(-1), Some(10)).await {
-            Err(GetDrugsRepositoryError::InvalidPaginationParams(_)) => true,
-            _ => false,
-        });
+        assert!(
+            match repository.get_drugs(Some(-1), Some(10), false).await {
+                Err(GetDrugsRepositoryError::InvalidPaginationParams(_)) => true,
+                _ => false,
+            }
+        );
 
-        assert!(match repository.get_drugs(Some(0), Some(0)).await {
+        assert!(match repository.get_drugs(Some(0), Some(0), false).await {
             Err(GetDrugsRepositoryError::InvalidPaginationParams(_)) => true,
             _ => false,
         });
     }
+
+    #[tokio::test]
+    async fn hides_deactivated_drugs_unless_inactive_are_included() {
+        let repository = setup_repository();
+
+        let mut new_drugs = vec![];
+        for name in ["Gripex", "Apap", "Flegamax"] {
+            let new_drug = NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            repository.create_drug(new_drug.clone()).await.unwrap();
+            new_drugs.push(new_drug);
+        }
+
+        let deactivated_drug = repository.deactivate_drug(new_drugs[1].id).await.unwrap();
+
+        assert!(deactivated_drug.deactivated_at.is_some());
+
+        let drugs = repository.get_drugs(None, None, false).await.unwrap();
+
+        assert_eq!(drugs.len(), 2);
+        assert_eq!(drugs[0], new_drugs[0]);
+        assert_eq!(drugs[1], new_drugs[2]);
+
+        let drugs = repository.get_drugs(None, None, true).await.unwrap();
+
+        let deactivated_drugs: Vec<_> = drugs
+            .iter()
+            .filter(|drug| drug.deactivated_at.is_some())
+            .collect();
+
+        assert_eq!(drugs.len(), 3);
+        assert_eq!(deactivated_drugs.len(), 1);
+        assert_eq!(deactivated_drugs[0].id, new_drugs[1].id);
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository.deactivate_drug(nonexistent_drug_id).await,
+            Err(DeactivateDrugRepositoryError::NotFound(nonexistent_drug_id))
+        );
+    }
+
+    #[tokio::test]
+    async fn adjusts_drug_stock_without_going_below_zero() {
+        let repository = setup_repository();
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        repository.create_drug(new_drug.clone()).await.unwrap();
+
+        let drug = repository.adjust_drug_stock(new_drug.id, 5).await.unwrap();
+
+        assert_eq!(drug.stock, 5);
+
+        let drug = repository.adjust_drug_stock(new_drug.id, -3).await.unwrap();
+
+        assert_eq!(drug.stock, 2);
+        assert_eq!(
+            repository.adjust_drug_stock(new_drug.id, -3).await,
+            Err(AdjustDrugStockRepositoryError::InsufficientStock(
+                new_drug.id
+            ))
+        );
+        assert_eq!(
+            repository.get_drug_by_id(new_drug.id).await.unwrap().stock,
+            2
+        );
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository.adjust_drug_stock(nonexistent_drug_id, 1).await,
+            Err(AdjustDrugStockRepositoryError::NotFound(
+                nonexistent_drug_id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn uses_drugs_default_page_size_if_page_size_is_omitted() {
+        let repository = setup_repository();
+        for i in 0..DRUGS_DEFAULT_PAGE_SIZE + 1 {
+            let new_drug = NewDrug::new(
+                format!("Gripex {}", i),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            repository.create_drug(new_drug).await.unwrap();
+        }
+
+        let drugs = repository.get_drugs(None, None, false).await.unwrap();
+
+        assert_eq!(drugs.len(), DRUGS_DEFAULT_PAGE_SIZE as usize);
+
+        let drugs = repository.get_drugs(Some(1), None, false).await.unwrap();
+
+        assert_eq!(drugs.len(), 1);
+
+        let drugs = repository.get_drugs(None, Some(5), false).await.unwrap();
+
+        assert_eq!(drugs.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn counts_active_drugs_by_content_type() {
+        let repository = setup_repository();
+        let solid_pills = |name: &str| {
+            NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        let bottle_of_liquid = NewDrug::new(
+            "Flegamax".into(),
+            DrugContentType::BottleOfLiquid,
+            None,
+            None,
+            None,
+            Some(150),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let deactivated_drug = solid_pills("Ibuprom");
+        for new_drug in [
+            solid_pills("Apap"),
+            solid_pills("Gripex"),
+            bottle_of_liquid,
+            deactivated_drug.clone(),
+        ] {
+            repository.create_drug(new_drug).await.unwrap();
+        }
+        repository
+            .deactivate_drug(deactivated_drug.id)
+            .await
+            .unwrap();
+
+        let counts = repository.count_drugs_by_content_type().await.unwrap();
+
+        assert_eq!(counts.len(), DrugContentType::ALL.len());
+        assert_eq!(counts[&DrugContentType::SolidPills], 2);
+        assert_eq!(counts[&DrugContentType::BottleOfLiquid], 1);
+        assert_eq!(counts[&DrugContentType::LiquidPills], 0);
+        assert_eq!(counts[&DrugContentType::Inhaler], 0);
+        assert_eq!(counts[&DrugContentType::Injection], 0);
+    }
 }
@@ -1,12 +1,19 @@
+use std::collections::HashMap;
+
 use uuid::Uuid;
 
 use super::{
-    entities::{Drug, DrugContentType, NewDrug},
+    entities::{Drug, DrugCategory, DrugContentType, NewDrug},
     repository::{
-        CreateDrugRepositoryError, DrugsRepository, GetDrugByIdRepositoryError,
+        AdjustDrugStockRepositoryError, CreateDrugRepositoryError, DeactivateDrugRepositoryError,
+        DrugsRepository, GetDrugByBarcodeRepositoryError, GetDrugByIdRepositoryError,
         GetDrugsRepositoryError,
     },
 };
+use crate::domain::{
+    prescriptions::entities::PrescriptionType,
+    utils::validators::validate_barcode::validate_barcode,
+};
 
 pub struct DrugsService {
     repository: Box<dyn DrugsRepository>,
@@ -23,16 +30,39 @@ pub enum GetDrugByIdError {
     RepositoryError(GetDrugByIdRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum GetDrugByBarcodeError {
+    DomainError(String),
+    RepositoryError(GetDrugByBarcodeRepositoryError),
+}
+
 #[derive(Debug)]
 pub enum GetDrugsWithPaginationError {
     RepositoryError(GetDrugsRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum CountDrugsByContentTypeError {
+    RepositoryError(GetDrugsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum DeactivateDrugError {
+    RepositoryError(DeactivateDrugRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum AdjustDrugStockError {
+    DomainError(String),
+    RepositoryError(AdjustDrugStockRepositoryError),
+}
+
 impl DrugsService {
     pub fn new(repository: Box<dyn DrugsRepository>) -> Self {
         Self { repository }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_drug(
         &self,
         name: String,
@@ -41,6 +71,15 @@ impl DrugsService {
         mg_per_pill: Option<i32>,
         ml_per_pill: Option<i32>,
         volume_ml: Option<i32>,
+        doses_count: Option<i32>,
+        mcg_per_dose: Option<i32>,
+        ml_per_dose: Option<i32>,
+        unit_price_cents: Option<i32>,
+        manufacturer: Option<String>,
+        barcode: Option<String>,
+        category: Option<DrugCategory>,
+        allowed_prescription_types: Vec<PrescriptionType>,
+        created_by: Option<Uuid>,
     ) -> Result<Drug, CreateDrugError> {
         let new_drug = NewDrug::new(
             name,
@@ -49,8 +88,20 @@ impl DrugsService {
             mg_per_pill,
             ml_per_pill,
             volume_ml,
+            doses_count,
+            mcg_per_dose,
+            ml_per_dose,
+            unit_price_cents,
+            manufacturer,
+            barcode,
         )
         .map_err(|err| CreateDrugError::DomainError(err.to_string()))?;
+        let new_drug = NewDrug {
+            category,
+            allowed_prescription_types,
+            created_by,
+            ..new_drug
+        };
 
         let created_drug = self
             .repository
@@ -71,26 +122,81 @@ impl DrugsService {
         Ok(doctor)
     }
 
+    pub async fn get_drug_by_barcode(&self, barcode: &str) -> Result<Drug, GetDrugByBarcodeError> {
+        validate_barcode(barcode)
+            .map_err(|err| GetDrugByBarcodeError::DomainError(err.to_string()))?;
+
+        let drug = self
+            .repository
+            .get_drug_by_barcode(barcode)
+            .await
+            .map_err(GetDrugByBarcodeError::RepositoryError)?;
+
+        Ok(drug)
+    }
+
     pub async fn get_drugs_with_pagination(
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        include_inactive: bool,
     ) -> Result<Vec<Drug>, GetDrugsWithPaginationError> {
         let result = self
             .repository
-            .get_drugs(page, page_size)
+            .get_drugs(page, page_size, include_inactive)
             .await
             .map_err(|err| GetDrugsWithPaginationError::RepositoryError(err))?;
 
         Ok(result)
     }
+
+    pub async fn deactivate_drug(&self, drug_id: Uuid) -> Result<Drug, DeactivateDrugError> {
+        let drug = self
+            .repository
+            .deactivate_drug(drug_id)
+            .await
+            .map_err(DeactivateDrugError::RepositoryError)?;
+
+        Ok(drug)
+    }
+
+    pub async fn adjust_drug_stock(
+        &self,
+        drug_id: Uuid,
+        delta: i32,
+    ) -> Result<Drug, AdjustDrugStockError> {
+        let drug = self
+            .repository
+            .adjust_drug_stock(drug_id, delta)
+            .await
+            .map_err(|err| match err {
+                AdjustDrugStockRepositoryError::InsufficientStock(_) => {
+                    AdjustDrugStockError::DomainError(err.to_string())
+                }
+                err => AdjustDrugStockError::RepositoryError(err),
+            })?;
+
+        Ok(drug)
+    }
+
+    pub async fn count_drugs_by_content_type(
+        &self,
+    ) -> Result<HashMap<DrugContentType, i64>, CountDrugsByContentTypeError> {
+        let counts = self
+            .repository
+            .count_drugs_by_content_type()
+            .await
+            .map_err(CountDrugsByContentTypeError::RepositoryError)?;
+
+        Ok(counts)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use uuid::Uuid;
 
-    use super::DrugsService;
+    use super::{AdjustDrugStockError, DrugsService, GetDrugByBarcodeError};
     use crate::domain::drugs::{entities::DrugContentType, repository::DrugsRepositoryFake};
 
     fn setup_service() -> DrugsService {
@@ -109,6 +215,15 @@ mod tests {
                 Some(300),
                 None,
                 None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
             )
             .await
             .unwrap();
@@ -142,6 +257,51 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn gets_drug_by_barcode() {
+        let service = setup_service();
+
+        let created_drug = service
+            .create_drug(
+                "Gripex".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("US Pharmacia".into()),
+                Some("5909990733828".into()),
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let drug = service.get_drug_by_barcode("5909990733828").await.unwrap();
+
+        assert_eq!(drug.id, created_drug.id);
+        assert_eq!(drug.manufacturer, Some("US Pharmacia".into()));
+    }
+
+    #[tokio::test]
+    async fn get_drug_by_barcode_returns_error_if_barcode_is_invalid_or_unknown() {
+        let service = setup_service();
+
+        assert!(matches!(
+            service.get_drug_by_barcode("12345").await,
+            Err(GetDrugByBarcodeError::DomainError(_))
+        ));
+        assert!(matches!(
+            service.get_drug_by_barcode("5909990733828").await,
+            Err(GetDrugByBarcodeError::RepositoryError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn gets_drugs_with_pagination() {
         let service = setup_service();
@@ -154,6 +314,15 @@ mod tests {
                 Some(300),
                 None,
                 None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
             )
             .await
             .unwrap();
@@ -173,6 +342,15 @@ mod tests {
                 Some(400),
                 None,
                 None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
             )
             .await
             .unwrap();
@@ -184,6 +362,15 @@ mod tests {
                 Some(200),
                 None,
                 None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
             )
             .await
             .unwrap();
@@ -195,44 +382,56 @@ mod tests {
                 None,
                 None,
                 Some(400),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
             )
             .await
             .unwrap();
 
         let drugs = service
-            .get_drugs_with_pagination(Some(1), Some(2))
+            .get_drugs_with_pagination(Some(1), Some(2), false)
             .await
             .unwrap();
 
         assert_eq!(drugs.len(), 2);
 
         let drugs = service
-            .get_drugs_with_pagination(Some(1), Some(3))
+            .get_drugs_with_pagination(Some(1), Some(3), false)
             .await
             .unwrap();
 
         assert_eq!(drugs.len(), 1);
 
         let drugs = service
-            .get_drugs_with_pagination(None, Some(10))
+            .get_drugs_with_pagination(None, Some(10), false)
             .await
             .unwrap();
 
         assert_eq!(drugs.len(), 4);
 
         let drugs = service
-            .get_drugs_with_pagination(Some(1), None)
+            .get_drugs_with_pagination(Some(1), None, false)
             .await
             .unwrap();
 
         assert_eq!(drugs.len(), 0);
 
-        let drugs = service.get_drugs_with_pagination(None, None).await.unwrap();
+        let drugs = service
+            .get_drugs_with_pagination(None, None, false)
+            .await
+            .unwrap();
 
         assert_eq!(drugs.len(), 4);
 
         let drugs = service
-            .get_drugs_with_pagination(Some(2), Some(3))
+            .get_drugs_with_pagination(Some(2), Some(3), false)
             .await
             .unwrap();
 
@@ -244,13 +443,100 @@ mod tests {
         let service = setup_service();
 
         assert!(service
-            .get_drugs_with_pagination(Some(-1), None)
+            .get_drugs_with_pagination(Some(-1), None, false)
             .await
             .is_err());
 
         assert!(service
-            .get_drugs_with_pagination(None, Some(0))
+            .get_drugs_with_pagination(None, Some(0), false)
             .await
             .is_err());
     }
+
+    #[tokio::test]
+    async fn deactivates_drug_and_hides_it_from_the_list() {
+        let service = setup_service();
+
+        let created_drug = service
+            .create_drug(
+                "Gripex".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let deactivated_drug = service.deactivate_drug(created_drug.id).await.unwrap();
+
+        assert!(deactivated_drug.deactivated_at.is_some());
+        assert!(service
+            .get_drugs_with_pagination(None, None, false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            service
+                .get_drugs_with_pagination(None, None, true)
+                .await
+                .unwrap(),
+            vec![deactivated_drug]
+        );
+        assert!(service.deactivate_drug(Uuid::new_v4()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn doesnt_adjust_drug_stock_below_zero() {
+        let service = setup_service();
+        let created_drug = service
+            .create_drug(
+                "Gripex".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let drug = service.adjust_drug_stock(created_drug.id, 3).await.unwrap();
+
+        assert_eq!(drug.stock, 3);
+
+        let drug = service
+            .adjust_drug_stock(created_drug.id, -3)
+            .await
+            .unwrap();
+
+        assert_eq!(drug.stock, 0);
+        assert!(matches!(
+            service.adjust_drug_stock(created_drug.id, -1).await,
+            Err(AdjustDrugStockError::DomainError(_))
+        ));
+        assert!(matches!(
+            service.adjust_drug_stock(Uuid::new_v4(), 1).await,
+            Err(AdjustDrugStockError::RepositoryError(_))
+        ));
+    }
 }
@@ -1,18 +1,44 @@
 use uuid::Uuid;
 
-use crate::domain::drugs::entities::{DrugContentType, NewDrug};
+use crate::domain::{
+    drugs::entities::{DrugContentType, NewDrug},
+    utils::validators::validate_barcode::validate_barcode,
+};
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreateNewDrugDomainError {
-    #[error("Pills count and mg per pill must be provided for solid pills")]
-    InvalidSolidPillsDescription,
-    #[error("Pills count and ml per pill must be provided for liquid pills")]
-    InvalidLiquidPillsDescription,
-    #[error("Volume in ml must be provided for bottle of liquid")]
-    InvalidBottleOfLiquidDescription,
+    #[error("{0} must be provided as a positive number for {1}")]
+    InvalidRequiredField(&'static str, &'static str),
+    #[error("{0} can't be provided for {1}")]
+    ForbiddenField(&'static str, &'static str),
+    #[error("Unit price can't be negative")]
+    InvalidUnitPrice,
+}
+
+impl DrugContentType {
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            DrugContentType::SolidPills => "solid pills",
+            DrugContentType::LiquidPills => "liquid pills",
+            DrugContentType::BottleOfLiquid => "bottle of liquid",
+            DrugContentType::Inhaler => "inhaler",
+            DrugContentType::Injection => "injection",
+        }
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        match self {
+            DrugContentType::SolidPills => &["pills_count", "mg_per_pill"],
+            DrugContentType::LiquidPills => &["pills_count", "ml_per_pill"],
+            DrugContentType::BottleOfLiquid => &["volume_ml"],
+            DrugContentType::Inhaler => &["doses_count", "mcg_per_dose"],
+            DrugContentType::Injection => &["doses_count", "ml_per_dose"],
+        }
+    }
 }
 
 impl NewDrug {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         content_type: DrugContentType,
@@ -20,69 +46,74 @@ impl NewDrug {
         mg_per_pill: Option<i32>,
         ml_per_pill: Option<i32>,
         volume_ml: Option<i32>,
+        doses_count: Option<i32>,
+        mcg_per_dose: Option<i32>,
+        ml_per_dose: Option<i32>,
+        unit_price_cents: Option<i32>,
+        manufacturer: Option<String>,
+        barcode: Option<String>,
     ) -> anyhow::Result<NewDrug> {
-        match content_type {
-            DrugContentType::SolidPills => {
-                if pills_count.is_none()
-                    || pills_count.unwrap() <= 0
-                    || mg_per_pill.is_none()
-                    || mg_per_pill.unwrap() <= 0
-                {
-                    Err(CreateNewDrugDomainError::InvalidSolidPillsDescription)?;
-                }
-
-                Ok(NewDrug {
-                    id: Uuid::new_v4(),
-                    name,
-                    content_type,
-                    pills_count,
-                    mg_per_pill,
-                    ml_per_pill: None,
-                    volume_ml: None,
-                })
-            }
-            DrugContentType::LiquidPills => {
-                if pills_count.is_none()
-                    || pills_count.unwrap() <= 0
-                    || ml_per_pill.is_none()
-                    || ml_per_pill.unwrap() <= 0
-                {
-                    Err(CreateNewDrugDomainError::InvalidLiquidPillsDescription)?;
-                }
-
-                Ok(NewDrug {
-                    id: Uuid::new_v4(),
-                    name,
-                    content_type,
-                    pills_count,
-                    mg_per_pill: None,
-                    ml_per_pill,
-                    volume_ml: None,
-                })
-            }
-            DrugContentType::BottleOfLiquid => {
-                if volume_ml.is_none() || volume_ml.unwrap() <= 0 {
-                    Err(CreateNewDrugDomainError::InvalidBottleOfLiquidDescription)?;
-                }
-
-                Ok(NewDrug {
-                    id: Uuid::new_v4(),
-                    name,
-                    content_type,
-                    pills_count: None,
-                    mg_per_pill: None,
-                    ml_per_pill: None,
-                    volume_ml,
-                })
+        if unit_price_cents.is_some_and(|unit_price_cents| unit_price_cents < 0) {
+            Err(CreateNewDrugDomainError::InvalidUnitPrice)?;
+        }
+
+        // Each content type requires its own quantity fields and forbids all the others
+        let fields = [
+            ("pills_count", pills_count),
+            ("mg_per_pill", mg_per_pill),
+            ("ml_per_pill", ml_per_pill),
+            ("volume_ml", volume_ml),
+            ("doses_count", doses_count),
+            ("mcg_per_dose", mcg_per_dose),
+            ("ml_per_dose", ml_per_dose),
+        ];
+        for (field, value) in fields {
+            let is_required = content_type.required_fields().contains(&field);
+            match value {
+                Some(value) if is_required && value > 0 => {}
+                None if !is_required => {}
+                Some(_) if !is_required => Err(CreateNewDrugDomainError::ForbiddenField(
+                    field,
+                    content_type.description(),
+                ))?,
+                _ => Err(CreateNewDrugDomainError::InvalidRequiredField(
+                    field,
+                    content_type.description(),
+                ))?,
             }
         }
+
+        if let Some(barcode) = &barcode {
+            validate_barcode(barcode)?;
+        }
+
+        Ok(NewDrug {
+            id: Uuid::new_v4(),
+            name,
+            content_type,
+            pills_count,
+            mg_per_pill,
+            ml_per_pill,
+            volume_ml,
+            doses_count,
+            mcg_per_dose,
+            ml_per_dose,
+            unit_price_cents,
+            manufacturer,
+            barcode,
+            category: None,
+            allowed_prescription_types: vec![],
+            created_by: None,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
     use uuid::Uuid;
 
+    use super::CreateNewDrugDomainError;
     use crate::domain::drugs::entities::{DrugContentType, NewDrug};
 
     #[test]
@@ -94,6 +125,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_ok());
     }
@@ -108,6 +145,15 @@ mod tests {
             mg_per_pill: Some(300),
             ml_per_pill: None,
             volume_ml: None,
+            doses_count: None,
+            mcg_per_dose: None,
+            ml_per_dose: None,
+            unit_price_cents: None,
+            manufacturer: None,
+            barcode: None,
+            category: None,
+            allowed_prescription_types: vec![],
+            created_by: None,
         };
 
         let mut new_drug = NewDrug::new(
@@ -115,8 +161,14 @@ mod tests {
             DrugContentType::SolidPills,
             Some(20),
             Some(300),
-            Some(300),
-            Some(1000),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -133,6 +185,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -143,6 +201,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -153,6 +217,12 @@ mod tests {
             None,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -163,6 +233,12 @@ mod tests {
             Some(0),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
     }
@@ -177,15 +253,30 @@ mod tests {
             mg_per_pill: None,
             ml_per_pill: Some(300),
             volume_ml: None,
+            doses_count: None,
+            mcg_per_dose: None,
+            ml_per_dose: None,
+            unit_price_cents: None,
+            manufacturer: None,
+            barcode: None,
+            category: None,
+            allowed_prescription_types: vec![],
+            created_by: None,
         };
 
         let mut new_drug = NewDrug::new(
             "Gripex".into(),
             DrugContentType::LiquidPills,
             Some(20),
+            None,
             Some(300),
-            Some(300),
-            Some(1000),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -202,6 +293,12 @@ mod tests {
             None,
             Some(300),
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -212,6 +309,12 @@ mod tests {
             None,
             Some(300),
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -222,6 +325,12 @@ mod tests {
             None,
             Some(0),
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -232,6 +341,12 @@ mod tests {
             None,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
     }
@@ -246,15 +361,30 @@ mod tests {
             mg_per_pill: None,
             ml_per_pill: None,
             volume_ml: Some(1000),
+            doses_count: None,
+            mcg_per_dose: None,
+            ml_per_dose: None,
+            unit_price_cents: None,
+            manufacturer: None,
+            barcode: None,
+            category: None,
+            allowed_prescription_types: vec![],
+            created_by: None,
         };
 
         let mut new_drug = NewDrug::new(
             "Gripex".into(),
             DrugContentType::BottleOfLiquid,
-            Some(20),
-            Some(300),
-            Some(300),
+            None,
+            None,
+            None,
             Some(1000),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -271,6 +401,12 @@ mod tests {
             None,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
         assert!(new_drug.is_err());
 
@@ -281,7 +417,222 @@ mod tests {
             None,
             None,
             Some(0),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(new_drug.is_err());
+    }
+
+    #[test]
+    fn creates_inhaler_drug() {
+        let new_drug = NewDrug::new(
+            "Ventolin".into(),
+            DrugContentType::Inhaler,
+            None,
+            None,
+            None,
+            None,
+            Some(200),
+            Some(100),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(new_drug.content_type, DrugContentType::Inhaler);
+        assert_eq!(new_drug.doses_count, Some(200));
+        assert_eq!(new_drug.mcg_per_dose, Some(100));
+    }
+
+    #[test]
+    fn creates_injection_drug() {
+        let new_drug = NewDrug::new(
+            "Clexane".into(),
+            DrugContentType::Injection,
+            None,
+            None,
+            None,
+            None,
+            Some(10),
+            None,
+            Some(1),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(new_drug.content_type, DrugContentType::Injection);
+        assert_eq!(new_drug.doses_count, Some(10));
+        assert_eq!(new_drug.ml_per_dose, Some(1));
+    }
+
+    #[test]
+    fn creates_drug_with_unit_price() {
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(1299),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(new_drug.unit_price_cents, Some(1299));
+    }
+
+    #[test]
+    fn doesnt_create_drug_if_unit_price_is_negative() {
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(-1),
+            None,
+            None,
         );
+
         assert!(new_drug.is_err());
     }
+
+    #[test]
+    fn creates_drug_with_manufacturer_and_barcode() {
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("US Pharmacia".into()),
+            Some("5909990733828".into()),
+        )
+        .unwrap();
+
+        assert_eq!(new_drug.manufacturer, Some("US Pharmacia".into()));
+        assert_eq!(new_drug.barcode, Some("5909990733828".into()));
+    }
+
+    #[test]
+    fn doesnt_create_drug_if_barcode_is_invalid() {
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("5909990733827".into()),
+        );
+
+        assert!(new_drug.is_err());
+    }
+
+    #[rstest]
+    #[case(DrugContentType::SolidPills, [None, Some(300), None, None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("pills_count", "solid pills"))]
+    #[case(DrugContentType::SolidPills, [Some(0), Some(300), None, None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("pills_count", "solid pills"))]
+    #[case(DrugContentType::SolidPills, [Some(20), None, None, None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("mg_per_pill", "solid pills"))]
+    #[case(DrugContentType::SolidPills, [Some(20), Some(-300), None, None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("mg_per_pill", "solid pills"))]
+    #[case(DrugContentType::SolidPills, [Some(20), Some(300), Some(300), None, None, None, None], CreateNewDrugDomainError::ForbiddenField("ml_per_pill", "solid pills"))]
+    #[case(DrugContentType::SolidPills, [Some(20), Some(300), None, Some(1000), None, None, None], CreateNewDrugDomainError::ForbiddenField("volume_ml", "solid pills"))]
+    #[case(DrugContentType::LiquidPills, [None, None, Some(300), None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("pills_count", "liquid pills"))]
+    #[case(DrugContentType::LiquidPills, [Some(0), None, Some(300), None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("pills_count", "liquid pills"))]
+    #[case(DrugContentType::LiquidPills, [Some(20), None, None, None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("ml_per_pill", "liquid pills"))]
+    #[case(DrugContentType::LiquidPills, [Some(20), None, Some(0), None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("ml_per_pill", "liquid pills"))]
+    #[case(DrugContentType::LiquidPills, [Some(20), Some(300), Some(300), None, None, None, None], CreateNewDrugDomainError::ForbiddenField("mg_per_pill", "liquid pills"))]
+    #[case(DrugContentType::LiquidPills, [Some(20), None, Some(300), Some(1000), None, None, None], CreateNewDrugDomainError::ForbiddenField("volume_ml", "liquid pills"))]
+    #[case(DrugContentType::BottleOfLiquid, [None, None, None, None, None, None, None], CreateNewDrugDomainError::InvalidRequiredField("volume_ml", "bottle of liquid"))]
+    #[case(DrugContentType::BottleOfLiquid, [None, None, None, Some(0), None, None, None], CreateNewDrugDomainError::InvalidRequiredField("volume_ml", "bottle of liquid"))]
+    #[case(DrugContentType::BottleOfLiquid, [Some(20), None, None, Some(1000), None, None, None], CreateNewDrugDomainError::ForbiddenField("pills_count", "bottle of liquid"))]
+    #[case(DrugContentType::BottleOfLiquid, [None, Some(300), None, Some(1000), None, None, None], CreateNewDrugDomainError::ForbiddenField("mg_per_pill", "bottle of liquid"))]
+    #[case(DrugContentType::BottleOfLiquid, [None, None, Some(300), Some(1000), None, None, None], CreateNewDrugDomainError::ForbiddenField("ml_per_pill", "bottle of liquid"))]
+    #[case(DrugContentType::Inhaler, [None, None, None, None, None, Some(100), None], CreateNewDrugDomainError::InvalidRequiredField("doses_count", "inhaler"))]
+    #[case(DrugContentType::Inhaler, [None, None, None, None, Some(200), Some(0), None], CreateNewDrugDomainError::InvalidRequiredField("mcg_per_dose", "inhaler"))]
+    #[case(DrugContentType::Inhaler, [None, Some(300), None, None, Some(200), Some(100), None], CreateNewDrugDomainError::ForbiddenField("mg_per_pill", "inhaler"))]
+    #[case(DrugContentType::Inhaler, [None, None, None, None, Some(200), Some(100), Some(1)], CreateNewDrugDomainError::ForbiddenField("ml_per_dose", "inhaler"))]
+    #[case(DrugContentType::Injection, [None, None, None, None, Some(0), None, Some(1)], CreateNewDrugDomainError::InvalidRequiredField("doses_count", "injection"))]
+    #[case(DrugContentType::Injection, [None, None, None, None, Some(10), None, None], CreateNewDrugDomainError::InvalidRequiredField("ml_per_dose", "injection"))]
+    #[case(DrugContentType::Injection, [None, None, None, Some(1000), Some(10), None, Some(1)], CreateNewDrugDomainError::ForbiddenField("volume_ml", "injection"))]
+    #[case(DrugContentType::Injection, [None, None, None, None, Some(10), Some(100), Some(1)], CreateNewDrugDomainError::ForbiddenField("mcg_per_dose", "injection"))]
+    fn validates_quantity_fields_of_content_type(
+        #[case] content_type: DrugContentType,
+        #[case] quantity_fields: [Option<i32>; 7],
+        #[case] expected_error: CreateNewDrugDomainError,
+    ) {
+        let [pills_count, mg_per_pill, ml_per_pill, volume_ml, doses_count, mcg_per_dose, ml_per_dose] =
+            quantity_fields;
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            content_type,
+            pills_count,
+            mg_per_pill,
+            ml_per_pill,
+            volume_ml,
+            doses_count,
+            mcg_per_dose,
+            ml_per_dose,
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(
+            new_drug
+                .unwrap_err()
+                .downcast::<CreateNewDrugDomainError>()
+                .unwrap(),
+            expected_error
+        );
+    }
+
+    #[test]
+    fn names_offending_field_in_error_message() {
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::LiquidPills,
+            Some(20),
+            None,
+            Some(300),
+            Some(1000),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(
+            new_drug.unwrap_err().to_string(),
+            "volume_ml can't be provided for liquid pills"
+        );
+    }
 }
@@ -1,13 +1,18 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Months, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::domain::utils::validators::validate_pesel_number::get_birth_date_from_pesel_number;
+
+pub const ADULT_AGE: u32 = 18;
+
 #[derive(Clone, Debug)]
 pub struct NewPatient {
     pub id: Uuid,
     pub name: String,
     pub pesel_number: String,
+    pub created_by: Option<Uuid>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
@@ -17,11 +22,35 @@ pub struct Patient {
     pub pesel_number: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // User who created the patient, empty for patients created without a session
+    pub created_by: Option<Uuid>,
+}
+
+// Birthdays on 29 February fall on 28 February in common years, as in the civil code
+pub fn get_age_from_pesel_number(pesel_number: &str, now: DateTime<Utc>) -> Option<u32> {
+    let birth_date = get_birth_date_from_pesel_number(pesel_number)?;
+    let today = now.date_naive();
+    let years = u32::try_from(today.year() - birth_date.year()).ok()?;
+    let last_birthday = birth_date.checked_add_months(Months::new(years * 12))?;
+    if last_birthday > today {
+        years.checked_sub(1)
+    } else {
+        Some(years)
+    }
+}
+
+impl Patient {
+    pub fn age(&self, now: DateTime<Utc>) -> Option<u32> {
+        get_age_from_pesel_number(&self.pesel_number, now)
+    }
 }
 
 impl PartialEq<NewPatient> for Patient {
     fn eq(&self, other: &NewPatient) -> bool {
-        self.id == other.id && self.name == other.name && self.pesel_number == other.pesel_number
+        self.id == other.id
+            && self.name == other.name
+            && self.pesel_number == other.pesel_number
+            && self.created_by == other.created_by
     }
 }
 
@@ -30,3 +59,56 @@ impl PartialEq<Patient> for NewPatient {
         other.eq(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use rstest::rstest;
+    use uuid::Uuid;
+
+    use super::{get_age_from_pesel_number, Patient};
+
+    fn at(date: &str) -> DateTime<Utc> {
+        format!("{}T12:00:00Z", date).parse().unwrap()
+    }
+
+    #[rstest]
+    #[case("96021817257", "2026-02-17", Some(29))]
+    #[case("96021817257", "2026-02-18", Some(30))]
+    #[case("99123105552", "2017-12-30", Some(17))]
+    #[case("99123105552", "2017-12-31", Some(18))]
+    #[case("99123105552", "2018-01-01", Some(18))]
+    #[case("10321504565", "2028-12-14", Some(17))]
+    #[case("10321504565", "2028-12-15", Some(18))]
+    #[case("04222901233", "2022-02-27", Some(17))]
+    #[case("04222901233", "2022-02-28", Some(18))]
+    #[case("04222901233", "2024-02-28", Some(19))]
+    #[case("04222901233", "2024-02-29", Some(20))]
+    #[case("00230104446", "2018-02-28", Some(17))]
+    #[case("00230104446", "2018-03-01", Some(18))]
+    #[case("10321504565", "2010-12-15", Some(0))]
+    #[case("10321504565", "2010-12-14", None)]
+    #[case("93022900005", "2026-01-01", None)]
+    fn gets_age_from_pesel_number(
+        #[case] pesel_number: &str,
+        #[case] today: &str,
+        #[case] expected: Option<u32>,
+    ) {
+        assert_eq!(get_age_from_pesel_number(pesel_number, at(today)), expected);
+    }
+
+    #[test]
+    fn gets_age_of_patient() {
+        let patient = Patient {
+            id: Uuid::new_v4(),
+            name: "John Doe".into(),
+            pesel_number: "08222907893".into(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: None,
+        };
+
+        assert_eq!(patient.age(at("2026-02-28")), Some(18));
+        assert_eq!(patient.age(at("2026-02-27")), Some(17));
+    }
+}
@@ -6,7 +6,7 @@ use uuid::Uuid;
 
 use crate::domain::{
     patients::entities::{NewPatient, Patient},
-    utils::pagination::get_pagination_params,
+    utils::pagination::{get_pagination_params, PaginationError, DEFAULT_PAGE_SIZE},
 };
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -15,14 +15,18 @@ pub enum CreatePatientRepositoryError {
     DuplicatedPeselNumber,
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum GetPatientsRepositoryError {
     #[error("Invalid pagination parameters: {0}")]
-    InvalidPaginationParams(String),
+    InvalidPaginationParams(PaginationError),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -31,6 +35,28 @@ pub enum GetPatientByIdRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetPatientByPeselNumberRepositoryError {
+    #[error("Patient with this PESEL number not found ({0})")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum MergePatientsRepositoryError {
+    #[error("Patient with this id not found ({0})")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -48,6 +74,16 @@ pub trait PatientsRepository: Send + Sync + 'static {
         &self,
         patient_id: Uuid,
     ) -> Result<Patient, GetPatientByIdRepositoryError>;
+    async fn get_patient_by_pesel_number(
+        &self,
+        pesel_number: &str,
+    ) -> Result<Patient, GetPatientByPeselNumberRepositoryError>;
+    // Moves the prescriptions of the merged patient to the kept one and deletes the merged patient
+    async fn merge_patients(
+        &self,
+        keep_id: Uuid,
+        merge_id: Uuid,
+    ) -> Result<Patient, MergePatientsRepositoryError>;
 }
 
 pub struct PatientsRepositoryFake {
@@ -86,6 +122,7 @@ impl PatientsRepository for PatientsRepositoryFake {
             pesel_number: new_patient.pesel_number,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            created_by: new_patient.created_by,
         };
 
         self.patients.write().unwrap().push(patient.clone());
@@ -98,8 +135,8 @@ impl PatientsRepository for PatientsRepositoryFake {
         page: Option<i64>,
         page_size: Option<i64>,
     ) -> Result<Vec<Patient>, GetPatientsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size)
-            .map_err(|err| GetPatientsRepositoryError::InvalidPaginationParams(err.to_string()))?;
+        let (page_size, offset) = get_pagination_params(page, page_size, DEFAULT_PAGE_SIZE)
+            .map_err(GetPatientsRepositoryError::InvalidPaginationParams)?;
         let a = offset;
         let b = offset + page_size;
 
@@ -129,6 +166,45 @@ impl PatientsRepository for PatientsRepositoryFake {
             None => Err(GetPatientByIdRepositoryError::NotFound(patient_id)),
         }
     }
+
+    async fn get_patient_by_pesel_number(
+        &self,
+        pesel_number: &str,
+    ) -> Result<Patient, GetPatientByPeselNumberRepositoryError> {
+        match self
+            .patients
+            .read()
+            .unwrap()
+            .iter()
+            .find(|patient| patient.pesel_number == pesel_number)
+        {
+            Some(patient) => Ok(patient.clone()),
+            None => Err(GetPatientByPeselNumberRepositoryError::NotFound(
+                pesel_number.to_string(),
+            )),
+        }
+    }
+
+    async fn merge_patients(
+        &self,
+        keep_id: Uuid,
+        merge_id: Uuid,
+    ) -> Result<Patient, MergePatientsRepositoryError> {
+        let mut patients = self.patients.write().unwrap();
+        let find_patient = |patient_id: Uuid| {
+            patients
+                .iter()
+                .find(|patient| patient.id == patient_id)
+                .cloned()
+                .ok_or(MergePatientsRepositoryError::NotFound(patient_id))
+        };
+        let kept_patient = find_patient(keep_id)?;
+        find_patient(merge_id)?;
+
+        patients.retain(|patient| patient.id != merge_id);
+
+        Ok(kept_patient)
+    }
 }
 
 #[cfg(test)]
@@ -140,7 +216,8 @@ mod tests {
         entities::NewPatient,
         repository::{
             CreatePatientRepositoryError, GetPatientByIdRepositoryError,
-            GetPatientsRepositoryError, PatientsRepository,
+            GetPatientByPeselNumberRepositoryError, GetPatientsRepositoryError,
+            MergePatientsRepositoryError, PatientsRepository,
         },
     };
 
@@ -177,6 +254,30 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn reads_patient_by_pesel_number() {
+        let repository = setup_repository();
+
+        let new_patient = NewPatient::new("John Doe".into(), "96021817257".into()).unwrap();
+        repository
+            .create_patient(new_patient.clone())
+            .await
+            .unwrap();
+
+        let patient_from_repo = repository
+            .get_patient_by_pesel_number("96021817257")
+            .await
+            .unwrap();
+
+        assert_eq!(patient_from_repo, new_patient);
+        assert_eq!(
+            repository.get_patient_by_pesel_number("99031301347").await,
+            Err(GetPatientByPeselNumberRepositoryError::NotFound(
+                "99031301347".into()
+            ))
+        );
+    }
+
     #[tokio::test]
     async fn create_and_read_patients_from_database() {
         let repository = setup_repository();
@@ -258,4 +359,37 @@ mod tests {
             Err(CreatePatientRepositoryError::DuplicatedPeselNumber)
         );
     }
+
+    #[tokio::test]
+    async fn merges_patients_and_deletes_the_duplicate() {
+        let repository = setup_repository();
+
+        let kept_patient = NewPatient::new("John Doe".into(), "96021817257".into()).unwrap();
+        let merged_patient = NewPatient::new("Jon Doe".into(), "99031301347".into()).unwrap();
+        repository
+            .create_patient(kept_patient.clone())
+            .await
+            .unwrap();
+        repository
+            .create_patient(merged_patient.clone())
+            .await
+            .unwrap();
+
+        let missing_id = Uuid::new_v4();
+        assert_eq!(
+            repository.merge_patients(kept_patient.id, missing_id).await,
+            Err(MergePatientsRepositoryError::NotFound(missing_id))
+        );
+
+        let patient = repository
+            .merge_patients(kept_patient.id, merged_patient.id)
+            .await
+            .unwrap();
+
+        assert_eq!(patient, kept_patient);
+        assert_eq!(
+            repository.get_patient_by_id(merged_patient.id).await,
+            Err(GetPatientByIdRepositoryError::NotFound(merged_patient.id))
+        );
+    }
 }
@@ -1,11 +1,16 @@
 use uuid::Uuid;
 
 use super::repository::{
-    CreatePatientRepositoryError, GetPatientByIdRepositoryError, GetPatientsRepositoryError,
+    CreatePatientRepositoryError, GetPatientByIdRepositoryError,
+    GetPatientByPeselNumberRepositoryError, GetPatientsRepositoryError,
+    MergePatientsRepositoryError,
 };
-use crate::domain::patients::{
-    entities::{NewPatient, Patient},
-    repository::PatientsRepository,
+use crate::domain::{
+    patients::{
+        entities::{NewPatient, Patient},
+        repository::PatientsRepository,
+    },
+    utils::validators::validate_pesel_number::validate_pesel_number,
 };
 
 #[derive(Debug)]
@@ -24,6 +29,19 @@ pub enum GetPatientsWithPaginationError {
     RepositoryError(GetPatientsRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum FindPatientIdByPeselNumberError {
+    DomainError(String),
+    RepositoryError(GetPatientByPeselNumberRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum MergePatientsError {
+    DomainError(String),
+    NotConfirmed,
+    RepositoryError(MergePatientsRepositoryError),
+}
+
 pub struct PatientsService {
     repository: Box<dyn PatientsRepository>,
 }
@@ -37,9 +55,14 @@ impl PatientsService {
         &self,
         name: String,
         pesel_number: String,
+        created_by: Option<Uuid>,
     ) -> Result<Patient, CreatePatientError> {
         let new_patient = NewPatient::new(name, pesel_number)
             .map_err(|err| CreatePatientError::DomainError(err.to_string()))?;
+        let new_patient = NewPatient {
+            created_by,
+            ..new_patient
+        };
 
         let created_patient = self
             .repository
@@ -76,14 +99,57 @@ impl PatientsService {
 
         Ok(patients)
     }
+
+    pub async fn find_patient_id_by_pesel_number(
+        &self,
+        pesel_number: &str,
+    ) -> Result<Option<Uuid>, FindPatientIdByPeselNumberError> {
+        validate_pesel_number(pesel_number)
+            .map_err(|err| FindPatientIdByPeselNumberError::DomainError(err.to_string()))?;
+
+        match self
+            .repository
+            .get_patient_by_pesel_number(pesel_number)
+            .await
+        {
+            Ok(patient) => Ok(Some(patient.id)),
+            Err(GetPatientByPeselNumberRepositoryError::NotFound(_)) => Ok(None),
+            Err(err) => Err(FindPatientIdByPeselNumberError::RepositoryError(err)),
+        }
+    }
+
+    pub async fn merge_patients(
+        &self,
+        keep_id: Uuid,
+        merge_id: Uuid,
+        confirmed: bool,
+    ) -> Result<Patient, MergePatientsError> {
+        if keep_id == merge_id {
+            return Err(MergePatientsError::DomainError(
+                "Patient can't be merged into itself".into(),
+            ));
+        }
+        // PESEL numbers are unique, so duplicates can't be detected and the caller has to confirm
+        // the patients are the same person
+        if !confirmed {
+            return Err(MergePatientsError::NotConfirmed);
+        }
+
+        self.repository
+            .merge_patients(keep_id, merge_id)
+            .await
+            .map_err(MergePatientsError::RepositoryError)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use uuid::Uuid;
 
-    use super::PatientsService;
-    use crate::domain::patients::repository::PatientsRepositoryFake;
+    use super::{FindPatientIdByPeselNumberError, MergePatientsError, PatientsService};
+    use crate::domain::patients::repository::{
+        MergePatientsRepositoryError, PatientsRepositoryFake,
+    };
 
     fn setup_service() -> PatientsService {
         PatientsService::new(Box::new(PatientsRepositoryFake::new()))
@@ -94,7 +160,7 @@ mod tests {
         let service = setup_service();
 
         let created_patient = service
-            .create_patient("John Doex".into(), "96021807250".into())
+            .create_patient("John Doex".into(), "96021807250".into(), None)
             .await
             .unwrap();
 
@@ -112,7 +178,7 @@ mod tests {
         let service = setup_service();
 
         let result = service
-            .create_patient("John Doex".into(), "96021807251".into()) // invalid pesel
+            .create_patient("John Doex".into(), "96021807251".into(), None) // invalid pesel
             .await;
 
         assert!(result.is_err());
@@ -123,12 +189,12 @@ mod tests {
         let service = setup_service();
 
         service
-            .create_patient("John Doex".into(), "96021807250".into())
+            .create_patient("John Doex".into(), "96021807250".into(), None)
             .await
             .unwrap();
 
         let duplicated_pesel_number_result = service
-            .create_patient("John Doex".into(), "96021807250".into())
+            .create_patient("John Doex".into(), "96021807250".into(), None)
             .await;
 
         assert!(duplicated_pesel_number_result.is_err());
@@ -143,24 +209,52 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn finds_patient_id_by_pesel_number() {
+        let service = setup_service();
+        let created_patient = service
+            .create_patient("John Doe".into(), "96021817257".into(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service
+                .find_patient_id_by_pesel_number("96021817257")
+                .await
+                .unwrap(),
+            Some(created_patient.id)
+        );
+        assert_eq!(
+            service
+                .find_patient_id_by_pesel_number("99031301347")
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(matches!(
+            service.find_patient_id_by_pesel_number("960218").await,
+            Err(FindPatientIdByPeselNumberError::DomainError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn gets_patients_with_pagination() {
         let service = setup_service();
 
         service
-            .create_patient("John Doex".into(), "96021817257".into())
+            .create_patient("John Doex".into(), "96021817257".into(), None)
             .await
             .unwrap();
         service
-            .create_patient("John Doey".into(), "99031301347".into())
+            .create_patient("John Doey".into(), "99031301347".into(), None)
             .await
             .unwrap();
         service
-            .create_patient("John Doez".into(), "92022900002".into())
+            .create_patient("John Doez".into(), "92022900002".into(), None)
             .await
             .unwrap();
         service
-            .create_patient("John Doeq".into(), "96021807250".into())
+            .create_patient("John Doeq".into(), "96021807250".into(), None)
             .await
             .unwrap();
 
@@ -221,4 +315,46 @@ mod tests {
             .await
             .is_err());
     }
+
+    #[tokio::test]
+    async fn doesnt_merge_patient_into_itself_or_without_confirmation() {
+        let service = setup_service();
+        let kept_patient = service
+            .create_patient("John Doe".into(), "96021817257".into(), None)
+            .await
+            .unwrap();
+        let merged_patient = service
+            .create_patient("John Doe".into(), "99031301347".into(), None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .merge_patients(kept_patient.id, kept_patient.id, true)
+                .await,
+            Err(MergePatientsError::DomainError(_))
+        ));
+        assert!(matches!(
+            service
+                .merge_patients(kept_patient.id, merged_patient.id, false)
+                .await,
+            Err(MergePatientsError::NotConfirmed)
+        ));
+        assert!(matches!(
+            service
+                .merge_patients(kept_patient.id, Uuid::new_v4(), true)
+                .await,
+            Err(MergePatientsError::RepositoryError(
+                MergePatientsRepositoryError::NotFound(_)
+            ))
+        ));
+        assert_eq!(
+            service
+                .merge_patients(kept_patient.id, merged_patient.id, true)
+                .await
+                .unwrap()
+                .id,
+            kept_patient.id
+        );
+    }
 }
@@ -3,19 +3,20 @@ use uuid::Uuid;
 use crate::domain::{
     patients::entities::NewPatient,
     utils::validators::{
-        validate_name::validate_name, validate_pesel_number::validate_pesel_number,
+        validate_name::normalize_name, validate_pesel_number::validate_pesel_number,
     },
 };
 
 impl NewPatient {
     pub fn new(name: String, pesel_number: String) -> anyhow::Result<Self> {
-        validate_name(&name)?;
+        let name = normalize_name(&name)?;
         validate_pesel_number(&pesel_number)?;
 
         Ok(NewPatient {
             id: Uuid::new_v4(),
             name,
             pesel_number,
+            created_by: None,
         })
     }
 }
@@ -32,6 +33,13 @@ mod tests {
         assert_eq!(sut.pesel_number, "96021817257");
     }
 
+    #[test]
+    fn normalizes_name_of_patient() {
+        let sut = NewPatient::new("  John   Doe ".into(), "96021817257".into()).unwrap();
+
+        assert_eq!(sut.name, "John Doe");
+    }
+
     #[test]
     fn doesnt_create_patient_if_name_is_invalid() {
         assert!(NewPatient::new("John".into(), "96021817257".into()).is_err());
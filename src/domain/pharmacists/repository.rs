@@ -6,7 +6,7 @@ use uuid::Uuid;
 
 use crate::domain::{
     pharmacists::entities::{NewPharmacist, Pharmacist},
-    utils::pagination::get_pagination_params,
+    utils::pagination::{get_pagination_params, PaginationError, DEFAULT_PAGE_SIZE},
 };
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -15,14 +15,18 @@ pub enum CreatePharmacistRepositoryError {
     DuplicatedPeselNumber,
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum GetPharmacistsRepositoryError {
     #[error("Invalid pagination parameters: {0}")]
-    InvalidPaginationParams(String),
+    InvalidPaginationParams(PaginationError),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -31,6 +35,8 @@ pub enum GetPharmacistByIdRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -98,9 +104,8 @@ impl PharmacistsRepository for PharmacistsRepositoryFake {
         page: Option<i64>,
         page_size: Option<i64>,
     ) -> Result<Vec<Pharmacist>, GetPharmacistsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size).map_err(|err| {
-            GetPharmacistsRepositoryError::InvalidPaginationParams(err.to_string())
-        })?;
+        let (page_size, offset) = get_pagination_params(page, page_size, DEFAULT_PAGE_SIZE)
+            .map_err(GetPharmacistsRepositoryError::InvalidPaginationParams)?;
         let a = offset;
         let b = offset + page_size;
 
@@ -3,13 +3,13 @@ use uuid::Uuid;
 use crate::domain::{
     pharmacists::entities::NewPharmacist,
     utils::validators::{
-        validate_name::validate_name, validate_pesel_number::validate_pesel_number,
+        validate_name::normalize_name, validate_pesel_number::validate_pesel_number,
     },
 };
 
 impl NewPharmacist {
     pub fn new(name: String, pesel_number: String) -> anyhow::Result<Self> {
-        validate_name(&name)?;
+        let name = normalize_name(&name)?;
         validate_pesel_number(&pesel_number)?;
 
         Ok(NewPharmacist {
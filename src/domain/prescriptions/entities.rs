@@ -1,9 +1,14 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, sqlx::Type, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+use super::prescription_code::PrescriptionCode;
+use crate::domain::doctors::pwz_number::PwzNumber;
+
+#[derive(
+    Debug, PartialEq, Eq, Hash, sqlx::Type, Clone, Copy, Serialize, Deserialize, JsonSchema,
+)]
 #[sqlx(type_name = "prescription_type", rename_all = "snake_case")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PrescriptionType {
@@ -13,6 +18,29 @@ pub enum PrescriptionType {
     ForChronicDiseaseDrugs,
 }
 
+impl PrescriptionType {
+    pub const ALL: [PrescriptionType; 4] = [
+        PrescriptionType::Regular,
+        PrescriptionType::ForAntibiotics,
+        PrescriptionType::ForImmunologicalDrugs,
+        PrescriptionType::ForChronicDiseaseDrugs,
+    ];
+}
+
+// Needed for the drugs' allowed prescription types column
+impl sqlx::postgres::PgHasArrayType for PrescriptionType {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_prescription_type")
+    }
+}
+
+// Prescription types a drug is restricted to, named so that the rejection can point at the drug
+#[derive(Debug, PartialEq, Clone)]
+pub struct DrugPrescriptionTypeRestriction {
+    pub drug_name: String,
+    pub allowed_prescription_types: Vec<PrescriptionType>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct NewPrescribedDrug {
     pub drug_id: Uuid,
@@ -25,10 +53,15 @@ pub struct NewPrescription {
     pub doctor_id: Uuid,
     pub patient_id: Uuid,
     pub prescribed_drugs: Vec<NewPrescribedDrug>,
+    // ICD-10 codes of the diagnoses, in the order given by the doctor
+    pub diagnoses: Vec<String>,
     pub prescription_type: PrescriptionType,
-    pub code: String,
+    pub code: PrescriptionCode,
     pub start_date: DateTime<Utc>,
     pub end_date: DateTime<Utc>,
+    // The validity window restarts at the first fill, which moves end_date to the fill date plus
+    // the window length, until then end_date is the deadline for the first fill
+    pub window_from_fill: bool,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
@@ -37,6 +70,9 @@ pub struct PrescribedDrug {
     pub prescription_id: Uuid,
     pub drug_id: Uuid,
     pub quantity: i32,
+    // Sum of the quantities dispensed by all fills of the prescription so far
+    pub dispensed_quantity: i32,
+    pub unit_price_cents: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -53,7 +89,26 @@ pub struct PrescriptionDoctor {
     pub id: Uuid,
     pub name: String,
     pub pesel_number: String,
-    pub pwz_number: String,
+    pub pwz_number: PwzNumber,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PrescriptionFillStatus {
+    Unfilled,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PrescriptionVerificationStatus {
+    Valid,
+    AlreadyFilled,
+    Expired,
+    CodeMismatch,
+    Cancelled,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
@@ -62,9 +117,18 @@ pub struct Prescription {
     pub doctor: PrescriptionDoctor,
     pub patient: PrescriptionPatient,
     pub prescribed_drugs: Vec<PrescribedDrug>,
+    pub diagnoses: Vec<String>,
     pub prescription_type: PrescriptionType,
-    pub code: String,
-    pub fill: Option<PrescriptionFill>,
+    pub code: PrescriptionCode,
+    pub fills: Vec<PrescriptionFill>,
+    pub fill_status: PrescriptionFillStatus,
+    // Cancelled prescriptions are kept for the audit trail, but can't be filled anymore
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub window_from_fill: bool,
+    pub estimated_cost_cents: Option<i64>,
+    // Set by the service against its clock when the prescription is returned, so clients don't
+    // have to combine the validity window with the fill status themselves
+    pub fillable_now: bool,
     pub start_date: DateTime<Utc>,
     pub end_date: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
@@ -80,7 +144,9 @@ impl PartialEq<NewPrescription> for Prescription {
             && self.code == other.code
             && self.start_date == other.start_date
             && self.end_date == other.end_date
+            && self.window_from_fill == other.window_from_fill
             && self.prescribed_drugs.len() == other.prescribed_drugs.len()
+            && self.diagnoses == other.diagnoses
     }
 }
 
@@ -90,11 +156,133 @@ impl PartialEq<Prescription> for NewPrescription {
     }
 }
 
+// Result of checking whether a prescription could be filled, without filling it
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionVerification {
+    pub prescription: Prescription,
+    pub verification: PrescriptionVerificationStatus,
+}
+
+// One drug prescribed to a patient, dated with the start date of its prescription
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DrugHistoryEntry {
+    pub prescription_id: Uuid,
+    pub prescription_code: PrescriptionCode,
+    pub drug_id: Uuid,
+    pub drug_name: String,
+    pub quantity: i32,
+    pub start_date: DateTime<Utc>,
+}
+
+// Text printed on the package of one dispensed drug
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DrugLabel {
+    pub drug_id: Uuid,
+    pub drug_name: String,
+    pub quantity: i32,
+    pub dosage: Option<String>,
+    pub patient_name: String,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DrugUsage {
+    pub drug_id: Uuid,
+    pub prescriptions_count: i64,
+    pub total_quantity: i64,
+}
+
+// Patient with a prescription for a given drug, patients have no contact details stored besides
+// the PESEL number
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AffectedPatient {
+    pub id: Uuid,
+    pub name: String,
+    pub pesel_number: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DoctorPrescriptionCount {
+    pub doctor_id: Uuid,
+    pub doctor_name: String,
+    pub prescriptions_count: i64,
+}
+
+// Days are UTC calendar days
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailyFillCount {
+    pub date: NaiveDate,
+    pub fills_count: i64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PharmacistFillStats {
+    pub pharmacist_id: Uuid,
+    pub days: Vec<DailyFillCount>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewPrescriptionTemplate {
+    pub id: Uuid,
+    pub doctor_id: Uuid,
+    pub name: String,
+    pub prescribed_drugs: Vec<NewPrescribedDrug>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionTemplateDrug {
+    pub drug_id: Uuid,
+    pub quantity: i32,
+}
+
+// Named set of drugs a doctor prescribes often, only usable by the doctor who created it
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescriptionTemplate {
+    pub id: Uuid,
+    pub doctor_id: Uuid,
+    pub name: String,
+    pub prescribed_drugs: Vec<PrescriptionTemplateDrug>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PartialEq<NewPrescriptionTemplate> for PrescriptionTemplate {
+    fn eq(&self, other: &NewPrescriptionTemplate) -> bool {
+        self.id == other.id
+            && self.doctor_id == other.doctor_id
+            && self.name == other.name
+            && self.prescribed_drugs.len() == other.prescribed_drugs.len()
+            && self
+                .prescribed_drugs
+                .iter()
+                .zip(&other.prescribed_drugs)
+                .all(|(drug, new_drug)| {
+                    drug.drug_id == new_drug.drug_id && drug.quantity as u32 == new_drug.quantity
+                })
+    }
+}
+
+impl PartialEq<PrescriptionTemplate> for NewPrescriptionTemplate {
+    fn eq(&self, other: &PrescriptionTemplate) -> bool {
+        other.eq(self)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewDispensedDrug {
+    pub prescribed_drug_id: Uuid,
+    pub quantity: u32,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct NewPrescriptionFill {
     pub id: Uuid,
     pub prescription_id: Uuid,
     pub pharmacist_id: Uuid,
+    pub dispensed_drugs: Vec<NewDispensedDrug>,
+    // Set on the first fill of a prescription with window_from_fill, its end date becomes the
+    // fill's created_at plus this window
+    pub restarted_window: Option<Duration>,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, JsonSchema)]
@@ -119,3 +307,11 @@ impl PartialEq<PrescriptionFill> for NewPrescriptionFill {
         other.eq(self)
     }
 }
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PrescriptionFillNotification {
+    pub prescription_id: Uuid,
+    pub code: PrescriptionCode,
+    pub pharmacist_id: Uuid,
+    pub filled_at: DateTime<Utc>,
+}
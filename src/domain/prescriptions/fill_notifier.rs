@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use super::entities::PrescriptionFillNotification;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("Failed to send prescription fill notification: {0}")]
+pub struct NotifyPrescriptionFillError(pub String);
+
+// Notifications are sent after the fill is saved, so failing to deliver one doesn't undo the fill
+#[async_trait]
+pub trait PrescriptionFillNotifier: Send + Sync + 'static {
+    async fn notify_fill(
+        &self,
+        notification: &PrescriptionFillNotification,
+    ) -> Result<(), NotifyPrescriptionFillError>;
+}
@@ -1,4 +1,7 @@
 pub mod entities;
+pub mod fill_notifier;
+pub mod policy;
+pub mod prescription_code;
 pub mod repository;
 pub mod service;
 pub mod use_cases;
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use chrono::Duration;
+
+use super::{
+    entities::PrescriptionType,
+    use_cases::create_prescription::{DEFAULT_MAX_PRESCRIBED_DRUGS, MAX_ANTIBIOTICS_VALIDITY_DAYS},
+};
+use crate::domain::drugs::entities::DrugCategory;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum PrescriptionPolicyError {
+    #[error("Prescriptions for antibiotics can't be valid for more than {max} days, got {0}", max = MAX_ANTIBIOTICS_VALIDITY_DAYS)]
+    AntibioticsDurationTooLong(i64),
+}
+
+// Tunable rules of issuing prescriptions, loaded at startup so they can change without recompiling
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrescriptionPolicy {
+    pub max_prescribed_drugs: usize,
+    pub regular_duration: Duration,
+    pub for_antibiotics_duration: Duration,
+    pub for_immunological_drugs_duration: Duration,
+    pub for_chronic_disease_drugs_duration: Duration,
+    pub max_start_date_in_past: Duration,
+    pub max_start_date_in_future: Duration,
+    // Rejects drugs whose category doesn't match the prescription type
+    pub forbid_mixed_drug_categories: bool,
+    // Prescriptions of these types can't be issued to minors, whose age is derived from the PESEL
+    pub adult_only_prescription_types: Vec<PrescriptionType>,
+    // Doctors can't prescribe drugs of these categories to themselves, checked by comparing PESELs
+    pub self_prescription_restricted_categories: Vec<DrugCategory>,
+}
+
+impl Default for PrescriptionPolicy {
+    fn default() -> Self {
+        Self {
+            max_prescribed_drugs: DEFAULT_MAX_PRESCRIBED_DRUGS,
+            regular_duration: Duration::days(30),
+            for_antibiotics_duration: Duration::days(7),
+            for_immunological_drugs_duration: Duration::days(120),
+            for_chronic_disease_drugs_duration: Duration::days(365),
+            max_start_date_in_past: Duration::days(1),
+            max_start_date_in_future: Duration::days(30),
+            forbid_mixed_drug_categories: true,
+            adult_only_prescription_types: vec![],
+            self_prescription_restricted_categories: vec![],
+        }
+    }
+}
+
+impl PrescriptionPolicy {
+    // Antibiotics prescriptions longer than the legal limit are rejected on creation, so such policy
+    // would reject all of them
+    pub fn validate(&self) -> Result<(), PrescriptionPolicyError> {
+        let antibiotics_days = self.for_antibiotics_duration.num_days();
+        if antibiotics_days > MAX_ANTIBIOTICS_VALIDITY_DAYS {
+            return Err(PrescriptionPolicyError::AntibioticsDurationTooLong(
+                antibiotics_days,
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_duration(&self, prescription_type: &PrescriptionType) -> Duration {
+        match prescription_type {
+            PrescriptionType::Regular => self.regular_duration,
+            PrescriptionType::ForAntibiotics => self.for_antibiotics_duration,
+            PrescriptionType::ForImmunologicalDrugs => self.for_immunological_drugs_duration,
+            PrescriptionType::ForChronicDiseaseDrugs => self.for_chronic_disease_drugs_duration,
+        }
+    }
+
+    pub fn get_durations_in_days(&self) -> HashMap<PrescriptionType, i64> {
+        PrescriptionType::ALL
+            .iter()
+            .map(|prescription_type| {
+                (
+                    *prescription_type,
+                    self.get_duration(prescription_type).num_days(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Duration;
+
+    use super::{PrescriptionPolicy, PrescriptionPolicyError};
+    use crate::domain::prescriptions::entities::PrescriptionType;
+
+    #[test]
+    fn default_policy_keeps_standard_durations() {
+        let policy = PrescriptionPolicy::default();
+
+        assert_eq!(
+            policy.get_duration(&PrescriptionType::Regular),
+            Duration::days(30)
+        );
+        assert_eq!(
+            policy.get_duration(&PrescriptionType::ForAntibiotics),
+            Duration::days(7)
+        );
+        assert_eq!(
+            policy.get_duration(&PrescriptionType::ForImmunologicalDrugs),
+            Duration::days(120)
+        );
+        assert_eq!(
+            policy.get_duration(&PrescriptionType::ForChronicDiseaseDrugs),
+            Duration::days(365)
+        );
+    }
+
+    #[test]
+    fn uses_custom_durations() {
+        let policy = PrescriptionPolicy {
+            for_antibiotics_duration: Duration::days(10),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.get_duration(&PrescriptionType::ForAntibiotics),
+            Duration::days(10)
+        );
+        assert_eq!(
+            policy.get_duration(&PrescriptionType::Regular),
+            Duration::days(30)
+        );
+    }
+
+    #[test]
+    fn lists_durations_of_all_types_in_days() {
+        let policy = PrescriptionPolicy {
+            for_antibiotics_duration: Duration::days(10),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.get_durations_in_days(),
+            HashMap::from([
+                (PrescriptionType::Regular, 30),
+                (PrescriptionType::ForAntibiotics, 10),
+                (PrescriptionType::ForImmunologicalDrugs, 120),
+                (PrescriptionType::ForChronicDiseaseDrugs, 365),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_antibiotics_duration_over_legal_limit() {
+        let policy = PrescriptionPolicy {
+            for_antibiotics_duration: Duration::days(14),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.validate(),
+            Err(PrescriptionPolicyError::AntibioticsDurationTooLong(14))
+        );
+        assert_eq!(PrescriptionPolicy::default().validate(), Ok(()));
+    }
+}
@@ -0,0 +1,150 @@
+use std::{fmt, ops::Deref};
+
+use rocket_okapi::{okapi::schemars, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+pub const PRESCRIPTION_CODE_LENGTH: usize = 8;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PrescriptionCodeValidationError {
+    #[error("Prescription code must be 8 characters long and contain only digits")]
+    InvalidFormat,
+}
+
+// Code the patient hands to the pharmacist, only constructed after passing validation.
+// Serialized as a plain string
+#[derive(Debug, Clone, PartialEq, Eq, Hash, sqlx::Type, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct PrescriptionCode(String);
+
+impl PrescriptionCode {
+    pub fn new(code: String) -> anyhow::Result<Self> {
+        if code.len() != PRESCRIPTION_CODE_LENGTH || !code.chars().all(|c| c.is_ascii_digit()) {
+            Err(PrescriptionCodeValidationError::InvalidFormat)?;
+        }
+
+        Ok(Self(code))
+    }
+
+    pub fn generate() -> Self {
+        let code = rand::random::<u32>() % 10u32.pow(PRESCRIPTION_CODE_LENGTH as u32);
+
+        Self(format!(
+            "{:0width$}",
+            code,
+            width = PRESCRIPTION_CODE_LENGTH
+        ))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for PrescriptionCode {
+    type Error = anyhow::Error;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Self::new(code)
+    }
+}
+
+impl TryFrom<&str> for PrescriptionCode {
+    type Error = anyhow::Error;
+
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        Self::new(code.to_string())
+    }
+}
+
+impl From<PrescriptionCode> for String {
+    fn from(code: PrescriptionCode) -> Self {
+        code.0
+    }
+}
+
+impl Deref for PrescriptionCode {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PrescriptionCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for PrescriptionCode {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for PrescriptionCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for PrescriptionCode {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::serde::json;
+    use rocket_okapi::okapi::schemars::{
+        schema::{InstanceType, SingleOrVec},
+        schema_for,
+    };
+    use rstest::rstest;
+
+    use super::PrescriptionCode;
+
+    #[rstest]
+    #[case("12345678", true)]
+    #[case("00000000", true)]
+    #[case("1234567", false)]
+    #[case("123456789", false)]
+    #[case("1234567a", false)]
+    #[case("+1234567", false)]
+    #[case("", false)]
+    fn validates_prescription_code_on_construction(#[case] code: &str, #[case] expected: bool) {
+        assert_eq!(PrescriptionCode::new(code.to_string()).is_ok(), expected);
+    }
+
+    #[test]
+    fn generates_valid_codes() {
+        for _ in 0..100 {
+            let code = PrescriptionCode::generate();
+
+            assert!(PrescriptionCode::new(code.to_string()).is_ok());
+        }
+    }
+
+    #[test]
+    fn serializes_as_plain_string() {
+        let code = PrescriptionCode::new("12345678".to_string()).unwrap();
+
+        assert_eq!(json::to_string(&code).unwrap(), r#""12345678""#);
+        assert_eq!(
+            json::from_str::<PrescriptionCode>(r#""12345678""#).unwrap(),
+            code
+        );
+        assert_eq!(
+            schema_for!(PrescriptionCode).schema.instance_type,
+            Some(SingleOrVec::Single(Box::new(InstanceType::String)))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_prescription_code_on_deserialization() {
+        assert!(json::from_str::<PrescriptionCode>(r#""1234567a""#).is_err());
+    }
+}
@@ -1,21 +1,37 @@
-use std::sync::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::RwLock,
+};
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, Utc};
 use uuid::Uuid;
 
 use super::entities::{PrescribedDrug, PrescriptionDoctor, PrescriptionPatient};
 use crate::domain::{
     doctors::entities::Doctor,
-    drugs::entities::Drug,
+    drugs::{
+        entities::{Drug, DrugCategory},
+        repository::DRUGS_DEFAULT_PAGE_SIZE,
+    },
     patients::entities::Patient,
     pharmacists::entities::Pharmacist,
     prescriptions::entities::{
-        NewPrescription, NewPrescriptionFill, Prescription, PrescriptionFill,
+        AffectedPatient, DoctorPrescriptionCount, DrugHistoryEntry,
+        DrugPrescriptionTypeRestriction, DrugUsage, NewPrescribedDrug, NewPrescription,
+        NewPrescriptionFill, NewPrescriptionTemplate, Prescription, PrescriptionFill,
+        PrescriptionFillStatus, PrescriptionTemplate, PrescriptionTemplateDrug, PrescriptionType,
     },
-    utils::pagination::get_pagination_params,
+    prescriptions::use_cases::create_prescription::generate_prescription_code,
+    utils::pagination::{get_pagination_params, PaginationError},
 };
 
+// Also used for the patient's drug history
+pub const PRESCRIPTIONS_DEFAULT_PAGE_SIZE: i64 = 20;
+
+// Counting the code generated when the new prescription was built
+pub const MAX_CODE_GENERATION_ATTEMPTS: u32 = 5;
+
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreatePrescriptionRepositoryError {
     #[error("Doctor with id {0} not found")]
@@ -24,16 +40,22 @@ pub enum CreatePrescriptionRepositoryError {
     PatientNotFound(Uuid),
     #[error("Drug with id {0} not found")]
     DrugNotFound(Uuid),
+    #[error("Failed to generate a unique prescription code")]
+    CodeGenerationFailed,
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum GetPrescriptionsRepositoryError {
     #[error("Invalid pagination parameters: {0}")]
-    InvalidPaginationParams(String),
+    InvalidPaginationParams(PaginationError),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -42,6 +64,28 @@ pub enum GetPrescriptionByIdRepositoryError {
     NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetDrugUsageRepositoryError {
+    #[error("Drug with id {0} not found")]
+    DrugNotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetPharmacistFillStatsRepositoryError {
+    #[error("Pharmacist with id {0} not found")]
+    PharmacistNotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -50,8 +94,92 @@ pub enum FillPrescriptionRepositoryError {
     PharmacistNotFound(Uuid),
     #[error("Prescription with id {0} not found")]
     PrescriptionNotFound(Uuid),
+    #[error("Prescription with id {0} doesn't have enough left to dispense")]
+    QuantityExceeded(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ReassignPrescriptionPatientRepositoryError {
+    #[error("Prescription with id {0} not found")]
+    PrescriptionNotFound(Uuid),
+    #[error("Patient with id {0} not found")]
+    PatientNotFound(Uuid),
+    #[error("Prescription with id {0} is already filled")]
+    AlreadyFilled(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum CancelPrescriptionRepositoryError {
+    #[error("Prescription with id {0} not found")]
+    PrescriptionNotFound(Uuid),
+    #[error("Prescription with id {0} is already filled")]
+    AlreadyFilled(Uuid),
+    #[error("Prescription with id {0} is already cancelled")]
+    AlreadyCancelled(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum CreatePrescriptionTemplateRepositoryError {
+    #[error("Doctor with id {0} not found")]
+    DoctorNotFound(Uuid),
+    #[error("Drug with id {0} not found")]
+    DrugNotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetPrescriptionTemplateByIdRepositoryError {
+    #[error("Prescription template with id {0} not found")]
+    NotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GetPrescriptionTemplatesRepositoryError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum UpdatePrescriptionTemplateRepositoryError {
+    #[error("Prescription template with id {0} not found")]
+    NotFound(Uuid),
+    #[error("Drug with id {0} not found")]
+    DrugNotFound(Uuid),
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum DeletePrescriptionTemplateRepositoryError {
+    #[error("Prescription template with id {0} not found")]
+    NotFound(Uuid),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
 }
 
 #[async_trait]
@@ -60,19 +188,181 @@ pub trait PrescriptionsRepository: Send + Sync + 'static {
         &self,
         prescription: NewPrescription,
     ) -> Result<Prescription, CreatePrescriptionRepositoryError>;
+    // Checks the doctor, patient and drugs like create_prescription and assembles the prescription
+    // it would create, without saving anything. The code isn't checked for collisions
+    async fn preview_prescription(
+        &self,
+        prescription: NewPrescription,
+    ) -> Result<Prescription, CreatePrescriptionRepositoryError>;
+    // `filled` narrows the results down to fully filled or not fully filled prescriptions, all are
+    // returned if None. `created_from` and `created_to` are inclusive bounds of created_at
     async fn get_prescriptions(
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
     ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError>;
+    // Same filtering and pagination as get_prescriptions, without loading the prescriptions
+    async fn get_prescription_ids(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Uuid>, GetPrescriptionsRepositoryError>;
     async fn get_prescription_by_id(
         &self,
         prescription_id: Uuid,
     ) -> Result<Prescription, GetPrescriptionByIdRepositoryError>;
+    // Unknown ids are left out, the prescriptions are in the order of their first id occurrence
+    async fn get_prescriptions_by_ids(
+        &self,
+        prescription_ids: &[Uuid],
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError>;
+    async fn get_prescriptions_expiring_before(
+        &self,
+        patient_id: Uuid,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError>;
+    // Not fully filled prescriptions of the patient that can be filled at `now`, soonest to expire
+    // first
+    async fn get_active_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError>;
+    // All prescriptions of the patient, newest first
+    async fn get_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError>;
+    // Prescriptions whose patient name, doctor name or code contains the query, case-insensitive,
+    // newest first
+    async fn search_prescriptions(
+        &self,
+        query: &str,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError>;
+    async fn get_drug_history_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DrugHistoryEntry>, GetPrescriptionsRepositoryError>;
+    // Counts prescriptions created within the range, every type is present even if none were created
+    async fn count_prescriptions_by_type(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<PrescriptionType, i64>, GetPrescriptionsRepositoryError>;
+    // Counts prescriptions created within the range per doctor, busiest doctors first; doctors
+    // without prescriptions in the range are left out
+    async fn count_prescriptions_per_doctor(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DoctorPrescriptionCount>, GetPrescriptionsRepositoryError>;
+    // Counts prescriptions starting within the range that include the drug, along with the total
+    // quantity prescribed
+    async fn count_prescriptions_for_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<DrugUsage, GetDrugUsageRepositoryError>;
+    // Distinct patients of prescriptions starting within the range that include the drug, ordered
+    // by name
+    async fn get_patients_prescribed_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<AffectedPatient>, GetDrugUsageRepositoryError>;
+    // Counts fills made by the pharmacist within the range per UTC day, days without fills are left
+    // out
+    async fn count_fills_by_pharmacist_per_day(
+        &self,
+        pharmacist_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<NaiveDate, i64>, GetPharmacistFillStatsRepositoryError>;
+    // Drugs that aren't included in any prescription, ordered by name
+    async fn get_unprescribed_drugs(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Drug>, GetPrescriptionsRepositoryError>;
+    // Used when creating prescriptions, uncategorized and missing drugs are left out
+    async fn get_drug_categories(
+        &self,
+        drug_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, DrugCategory>, CreatePrescriptionRepositoryError>;
+    // Used when creating prescriptions, unrestricted and missing drugs are left out
+    async fn get_drug_prescription_type_restrictions(
+        &self,
+        drug_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, DrugPrescriptionTypeRestriction>, CreatePrescriptionRepositoryError>;
+    // Used when creating prescriptions, empty if the patient doesn't exist
+    async fn get_patient_pesel_number(
+        &self,
+        patient_id: Uuid,
+    ) -> Result<Option<String>, CreatePrescriptionRepositoryError>;
+    // Used when creating prescriptions, empty if the doctor doesn't exist
+    async fn get_doctor_pesel_number(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Option<String>, CreatePrescriptionRepositoryError>;
+    // Also moves the end date of the prescription when the fill restarts its window
     async fn fill_prescription(
         &self,
         prescription_fill: NewPrescriptionFill,
     ) -> Result<PrescriptionFill, FillPrescriptionRepositoryError>;
+    // Fills all the prescriptions or none of them
+    async fn fill_prescriptions(
+        &self,
+        prescription_fills: Vec<NewPrescriptionFill>,
+    ) -> Result<Vec<PrescriptionFill>, FillPrescriptionRepositoryError>;
+    // Filled prescriptions keep their patient
+    async fn reassign_prescription_patient(
+        &self,
+        prescription_id: Uuid,
+        new_patient_id: Uuid,
+    ) -> Result<Prescription, ReassignPrescriptionPatientRepositoryError>;
+    // Only unfilled prescriptions which aren't cancelled yet can be cancelled
+    async fn cancel_prescription(
+        &self,
+        prescription_id: Uuid,
+    ) -> Result<Prescription, CancelPrescriptionRepositoryError>;
+    async fn create_prescription_template(
+        &self,
+        template: NewPrescriptionTemplate,
+    ) -> Result<PrescriptionTemplate, CreatePrescriptionTemplateRepositoryError>;
+    async fn get_prescription_template_by_id(
+        &self,
+        template_id: Uuid,
+    ) -> Result<PrescriptionTemplate, GetPrescriptionTemplateByIdRepositoryError>;
+    // Ordered from the oldest
+    async fn get_prescription_templates_for_doctor(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<PrescriptionTemplate>, GetPrescriptionTemplatesRepositoryError>;
+    // Replaces the name and drugs of the template with the id of the given one, its owner is kept
+    async fn update_prescription_template(
+        &self,
+        template: NewPrescriptionTemplate,
+    ) -> Result<PrescriptionTemplate, UpdatePrescriptionTemplateRepositoryError>;
+    async fn delete_prescription_template(
+        &self,
+        template_id: Uuid,
+    ) -> Result<(), DeletePrescriptionTemplateRepositoryError>;
     // async fn get_prescriptions_by_prescription_id(&self, prescription_id: Uuid) ->
     // Result<Vec<Prescription>>; async fn get_prescriptions_by_patient_id(&self, patient_id:
     // Uuid) -> Result<Vec<Prescription>>; async fn update_prescription(&self, prescription:
@@ -86,6 +376,8 @@ pub struct PrescriptionsRepositoryFake {
     pharmacists: RwLock<Vec<Pharmacist>>,
     patients: RwLock<Vec<Patient>>,
     drugs: RwLock<Vec<Drug>>,
+    prescription_templates: RwLock<Vec<PrescriptionTemplate>>,
+    unavailable: bool,
 }
 
 impl PrescriptionsRepositoryFake {
@@ -103,6 +395,17 @@ impl PrescriptionsRepositoryFake {
             patients: RwLock::new(initial_patients.unwrap_or(Vec::new())),
             pharmacists: RwLock::new(initial_pharmacists.unwrap_or(Vec::new())),
             drugs: RwLock::new(initial_drugs.unwrap_or(Vec::new())),
+            prescription_templates: RwLock::new(Vec::new()),
+            unavailable: false,
+        }
+    }
+
+    // Makes reading a prescription by id fail as if the database pool was exhausted
+    #[allow(dead_code)]
+    pub fn unavailable(self) -> Self {
+        Self {
+            unavailable: true,
+            ..self
         }
     }
 }
@@ -112,6 +415,36 @@ impl PrescriptionsRepository for PrescriptionsRepositoryFake {
     async fn create_prescription(
         &self,
         new_prescription: NewPrescription,
+    ) -> Result<Prescription, CreatePrescriptionRepositoryError> {
+        let mut prescription = self.preview_prescription(new_prescription).await?;
+
+        // Prescription codes are unique, a colliding code is replaced with a new one
+        let mut code_generation_attempts = 1;
+        while self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .any(|existing_prescription| existing_prescription.code == prescription.code)
+        {
+            if code_generation_attempts >= MAX_CODE_GENERATION_ATTEMPTS {
+                Err(CreatePrescriptionRepositoryError::CodeGenerationFailed)?;
+            }
+            code_generation_attempts += 1;
+            prescription.code = generate_prescription_code();
+        }
+
+        self.prescriptions
+            .write()
+            .unwrap()
+            .push(prescription.clone());
+
+        Ok(prescription)
+    }
+
+    async fn preview_prescription(
+        &self,
+        new_prescription: NewPrescription,
     ) -> Result<Prescription, CreatePrescriptionRepositoryError> {
         let patients = self.patients.read().unwrap();
         let found_patient = patients
@@ -130,16 +463,28 @@ impl PrescriptionsRepository for PrescriptionsRepositoryFake {
             ))?;
 
         let drugs = self.drugs.read().unwrap();
+        let mut prescribed_drugs = vec![];
         for new_prescribed_drug in &new_prescription.prescribed_drugs {
-            drugs
+            let found_drug = drugs
                 .iter()
                 .find(|drug| drug.id == new_prescribed_drug.drug_id)
                 .ok_or(CreatePrescriptionRepositoryError::DrugNotFound(
                     new_prescribed_drug.drug_id,
                 ))?;
+
+            prescribed_drugs.push(PrescribedDrug {
+                id: Uuid::new_v4(),
+                drug_id: new_prescribed_drug.drug_id,
+                prescription_id: new_prescription.id,
+                quantity: new_prescribed_drug.quantity as i32,
+                dispensed_quantity: 0,
+                unit_price_cents: found_drug.unit_price_cents,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            });
         }
 
-        let prescription = Prescription {
+        let mut prescription = Prescription {
             id: new_prescription.id,
             doctor: PrescriptionDoctor {
                 id: found_doctor.id.clone(),
@@ -152,31 +497,22 @@ impl PrescriptionsRepository for PrescriptionsRepositoryFake {
                 name: found_patient.name.clone(),
                 pesel_number: found_patient.name.clone(),
             },
-            prescribed_drugs: new_prescription
-                .prescribed_drugs
-                .iter()
-                .map(|new_prescibed_drug| PrescribedDrug {
-                    id: Uuid::new_v4(),
-                    drug_id: new_prescibed_drug.drug_id,
-                    prescription_id: new_prescription.id,
-                    quantity: new_prescibed_drug.quantity as i32,
-                    created_at: Utc::now(),
-                    updated_at: Utc::now(),
-                })
-                .collect(),
+            prescribed_drugs,
+            diagnoses: new_prescription.diagnoses,
             prescription_type: new_prescription.prescription_type,
             code: new_prescription.code,
-            fill: None,
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: new_prescription.window_from_fill,
+            estimated_cost_cents: None,
+            fillable_now: false,
             start_date: new_prescription.start_date,
             end_date: new_prescription.end_date,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
-
-        self.prescriptions
-            .write()
-            .unwrap()
-            .push(prescription.clone());
+        prescription.estimated_cost_cents = prescription.estimate_cost_cents();
 
         Ok(prescription)
     }
@@ -185,28 +521,69 @@ impl PrescriptionsRepository for PrescriptionsRepositoryFake {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
     ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size).map_err(|err| {
-            GetPrescriptionsRepositoryError::InvalidPaginationParams(err.to_string())
-        })?;
-        let a = offset;
-        let b = offset + page_size;
-
-        let mut prescriptions: Vec<Prescription> = vec![];
-        for i in a..b {
-            match self.prescriptions.read().unwrap().get(i as usize) {
-                Some(prescription) => prescriptions.push(prescription.clone()),
-                None => {}
-            }
-        }
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+        let prescriptions = self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|prescription| {
+                filled.is_none_or(|filled| {
+                    (prescription.fill_status == PrescriptionFillStatus::Filled) == filled
+                }) && created_from.is_none_or(|from| prescription.created_at >= from)
+                    && created_to.is_none_or(|to| prescription.created_at <= to)
+            })
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .cloned()
+            .collect();
 
         Ok(prescriptions)
     }
 
+    async fn get_prescription_ids(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Uuid>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+        let prescription_ids = self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|prescription| {
+                filled.is_none_or(|filled| {
+                    (prescription.fill_status == PrescriptionFillStatus::Filled) == filled
+                }) && created_from.is_none_or(|from| prescription.created_at >= from)
+                    && created_to.is_none_or(|to| prescription.created_at <= to)
+            })
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .map(|prescription| prescription.id)
+            .collect();
+
+        Ok(prescription_ids)
+    }
+
     async fn get_prescription_by_id(
         &self,
         prescription_id: Uuid,
     ) -> Result<Prescription, GetPrescriptionByIdRepositoryError> {
+        if self.unavailable {
+            return Err(GetPrescriptionByIdRepositoryError::Unavailable);
+        }
         match self
             .prescriptions
             .read()
@@ -221,168 +598,2136 @@ impl PrescriptionsRepository for PrescriptionsRepositoryFake {
         }
     }
 
-    async fn fill_prescription(
+    async fn get_prescriptions_by_ids(
         &self,
-        new_prescription_fill: NewPrescriptionFill,
-    ) -> Result<PrescriptionFill, FillPrescriptionRepositoryError> {
-        let pharmacists = self.pharmacists.read().unwrap();
-        pharmacists
+        prescription_ids: &[Uuid],
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let prescriptions = self.prescriptions.read().unwrap();
+        let mut seen_ids = HashSet::new();
+
+        Ok(prescription_ids
             .iter()
-            .find(|pharmacist| pharmacist.id == new_prescription_fill.pharmacist_id)
-            .ok_or(FillPrescriptionRepositoryError::PharmacistNotFound(
-                new_prescription_fill.pharmacist_id,
-            ))?;
+            .filter(|prescription_id| seen_ids.insert(**prescription_id))
+            .filter_map(|prescription_id| {
+                prescriptions
+                    .iter()
+                    .find(|prescription| prescription.id == *prescription_id)
+                    .cloned()
+            })
+            .collect())
+    }
 
-        let prescription_fill = PrescriptionFill {
-            id: new_prescription_fill.id,
-            prescription_id: new_prescription_fill.prescription_id,
-            pharmacist_id: new_prescription_fill.pharmacist_id,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+    async fn get_prescriptions_expiring_before(
+        &self,
+        patient_id: Uuid,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let now = Utc::now();
 
-        let prescriptions = self.prescriptions.read().unwrap().to_owned();
-        let (index, prescription) = prescriptions
+        let mut prescriptions: Vec<Prescription> = self
+            .prescriptions
+            .read()
+            .unwrap()
             .iter()
-            .enumerate()
-            .find(|(_, prescription)| prescription.id == new_prescription_fill.prescription_id)
-            .map(|(index, prescription)| {
-                (index, {
-                    let mut prescription = prescription.clone();
-                    prescription.fill = Some(prescription_fill.clone());
-                    prescription
-                })
+            .filter(|prescription| {
+                prescription.patient.id == patient_id
+                    && prescription.fill_status != PrescriptionFillStatus::Filled
+                    && prescription.end_date >= now
+                    && prescription.end_date < before
             })
-            .unwrap();
+            .cloned()
+            .collect();
+        prescriptions.sort_by_key(|prescription| prescription.end_date);
 
-        self.prescriptions
-            .write()
+        Ok(prescriptions)
+    }
+
+    async fn get_active_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let mut prescriptions: Vec<Prescription> = self
+            .prescriptions
+            .read()
             .unwrap()
-            .insert(index, prescription);
+            .iter()
+            .filter(|prescription| {
+                prescription.patient.id == patient_id
+                    && prescription.fill_status != PrescriptionFillStatus::Filled
+                    && prescription.start_date <= now
+                    && prescription.end_date >= now
+            })
+            .cloned()
+            .collect();
+        prescriptions.sort_by_key(|prescription| prescription.end_date);
 
-        Ok(prescription_fill)
+        Ok(prescriptions)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use uuid::Uuid;
+    async fn get_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
 
-    use crate::domain::{
-        doctors::{
-            entities::NewDoctor,
-            repository::{DoctorsRepository, DoctorsRepositoryFake},
-        },
-        drugs::{
-            entities::{DrugContentType, NewDrug},
-            repository::{DrugsRepository, DrugsRepositoryFake},
-        },
-        patients::{
-            entities::NewPatient,
-            repository::{PatientsRepository, PatientsRepositoryFake},
-        },
-        pharmacists::{
-            entities::NewPharmacist,
-            repository::{PharmacistsRepository, PharmacistsRepositoryFake},
-        },
-        prescriptions::{
-            entities::{NewPrescribedDrug, NewPrescription},
-            repository::{
-                CreatePrescriptionRepositoryError, FillPrescriptionRepositoryError,
-                GetPrescriptionByIdRepositoryError, GetPrescriptionsRepositoryError,
-                PrescriptionsRepository, PrescriptionsRepositoryFake,
-            },
-        },
-    };
+        let mut prescriptions: Vec<Prescription> = self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|prescription| prescription.patient.id == patient_id)
+            .cloned()
+            .collect();
+        prescriptions.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+
+        Ok(prescriptions
+            .into_iter()
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .collect())
+    }
 
-    struct DatabaseSeeds {
-        doctor: NewDoctor,
-        pharmacist: NewPharmacist,
-        patient: NewPatient,
-        drugs: Vec<NewDrug>,
+    async fn search_prescriptions(
+        &self,
+        query: &str,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let query = query.to_lowercase();
+        let mut prescriptions: Vec<Prescription> = self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|prescription| {
+                prescription.patient.name.to_lowercase().contains(&query)
+                    || prescription.doctor.name.to_lowercase().contains(&query)
+                    || prescription.code.to_lowercase().contains(&query)
+            })
+            .cloned()
+            .collect();
+        prescriptions.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+
+        Ok(prescriptions
+            .into_iter()
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .collect())
     }
 
-    async fn seed_in_memory_database(
-        prescriptions_repo: &PrescriptionsRepositoryFake,
-    ) -> DatabaseSeeds {
-        let pharmacists_repo = PharmacistsRepositoryFake::new();
-        let pharmacist = NewPharmacist::new(
-            "John Pharmacist".into(), //
+    async fn get_drug_history_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DrugHistoryEntry>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let drugs = self.drugs.read().unwrap();
+        let mut drug_history: Vec<DrugHistoryEntry> = self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|prescription| prescription.patient.id == patient_id)
+            .flat_map(|prescription| {
+                prescription
+                    .prescribed_drugs
+                    .iter()
+                    .map(|prescribed_drug| DrugHistoryEntry {
+                        prescription_id: prescription.id,
+                        prescription_code: prescription.code.clone(),
+                        drug_id: prescribed_drug.drug_id,
+                        drug_name: drugs
+                            .iter()
+                            .find(|drug| drug.id == prescribed_drug.drug_id)
+                            .map(|drug| drug.name.clone())
+                            .unwrap_or_default(),
+                        quantity: prescribed_drug.quantity,
+                        start_date: prescription.start_date,
+                    })
+            })
+            .collect();
+        drug_history.sort_by(|a, b| {
+            b.start_date
+                .cmp(&a.start_date)
+                .then_with(|| a.prescription_id.cmp(&b.prescription_id))
+                .then_with(|| a.drug_name.cmp(&b.drug_name))
+        });
+
+        Ok(drug_history
+            .into_iter()
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .collect())
+    }
+
+    async fn count_prescriptions_by_type(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<PrescriptionType, i64>, GetPrescriptionsRepositoryError> {
+        let mut counts: HashMap<PrescriptionType, i64> = PrescriptionType::ALL
+            .into_iter()
+            .map(|prescription_type| (prescription_type, 0))
+            .collect();
+        for prescription in self.prescriptions.read().unwrap().iter() {
+            if prescription.created_at >= from && prescription.created_at <= to {
+                *counts.entry(prescription.prescription_type).or_default() += 1;
+            }
+        }
+
+        Ok(counts)
+    }
+
+    async fn count_prescriptions_per_doctor(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DoctorPrescriptionCount>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let mut counts: HashMap<Uuid, DoctorPrescriptionCount> = HashMap::new();
+        for prescription in self.prescriptions.read().unwrap().iter() {
+            if prescription.created_at >= from && prescription.created_at <= to {
+                counts
+                    .entry(prescription.doctor.id)
+                    .or_insert_with(|| DoctorPrescriptionCount {
+                        doctor_id: prescription.doctor.id,
+                        doctor_name: prescription.doctor.name.clone(),
+                        prescriptions_count: 0,
+                    })
+                    .prescriptions_count += 1;
+            }
+        }
+        let mut counts: Vec<DoctorPrescriptionCount> = counts.into_values().collect();
+        counts.sort_by(|a, b| {
+            b.prescriptions_count
+                .cmp(&a.prescriptions_count)
+                .then_with(|| a.doctor_name.cmp(&b.doctor_name))
+                .then_with(|| a.doctor_id.cmp(&b.doctor_id))
+        });
+
+        Ok(counts
+            .into_iter()
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .collect())
+    }
+
+    async fn count_prescriptions_for_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<DrugUsage, GetDrugUsageRepositoryError> {
+        if !self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .any(|drug| drug.id == drug_id)
+        {
+            Err(GetDrugUsageRepositoryError::DrugNotFound(drug_id))?;
+        }
+
+        let mut drug_usage = DrugUsage {
+            drug_id,
+            prescriptions_count: 0,
+            total_quantity: 0,
+        };
+        for prescription in self.prescriptions.read().unwrap().iter() {
+            if prescription.start_date < from || prescription.start_date > to {
+                continue;
+            }
+            let quantities: Vec<i64> = prescription
+                .prescribed_drugs
+                .iter()
+                .filter(|prescribed_drug| prescribed_drug.drug_id == drug_id)
+                .map(|prescribed_drug| prescribed_drug.quantity as i64)
+                .collect();
+            if !quantities.is_empty() {
+                drug_usage.prescriptions_count += 1;
+                drug_usage.total_quantity += quantities.iter().sum::<i64>();
+            }
+        }
+
+        Ok(drug_usage)
+    }
+
+    async fn get_patients_prescribed_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<AffectedPatient>, GetDrugUsageRepositoryError> {
+        if !self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .any(|drug| drug.id == drug_id)
+        {
+            Err(GetDrugUsageRepositoryError::DrugNotFound(drug_id))?;
+        }
+
+        let patient_ids: HashSet<Uuid> = self
+            .prescriptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|prescription| prescription.start_date >= from && prescription.start_date <= to)
+            .filter(|prescription| {
+                prescription
+                    .prescribed_drugs
+                    .iter()
+                    .any(|prescribed_drug| prescribed_drug.drug_id == drug_id)
+            })
+            .map(|prescription| prescription.patient.id)
+            .collect();
+        let mut affected_patients: Vec<AffectedPatient> = self
+            .patients
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|patient| patient_ids.contains(&patient.id))
+            .map(|patient| AffectedPatient {
+                id: patient.id,
+                name: patient.name.clone(),
+                pesel_number: patient.pesel_number.clone(),
+            })
+            .collect();
+        affected_patients.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+
+        Ok(affected_patients)
+    }
+
+    async fn count_fills_by_pharmacist_per_day(
+        &self,
+        pharmacist_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<NaiveDate, i64>, GetPharmacistFillStatsRepositoryError> {
+        if !self
+            .pharmacists
+            .read()
+            .unwrap()
+            .iter()
+            .any(|pharmacist| pharmacist.id == pharmacist_id)
+        {
+            Err(GetPharmacistFillStatsRepositoryError::PharmacistNotFound(
+                pharmacist_id,
+            ))?;
+        }
+
+        let mut counts: HashMap<NaiveDate, i64> = HashMap::new();
+        for prescription in self.prescriptions.read().unwrap().iter() {
+            for fill in &prescription.fills {
+                if fill.pharmacist_id == pharmacist_id
+                    && fill.created_at >= from
+                    && fill.created_at <= to
+                {
+                    *counts.entry(fill.created_at.date_naive()).or_default() += 1;
+                }
+            }
+        }
+
+        Ok(counts)
+    }
+
+    async fn get_unprescribed_drugs(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Drug>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, DRUGS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+        let prescriptions = self.prescriptions.read().unwrap();
+        let mut drugs: Vec<Drug> = self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|drug| {
+                !prescriptions.iter().any(|prescription| {
+                    prescription
+                        .prescribed_drugs
+                        .iter()
+                        .any(|prescribed_drug| prescribed_drug.drug_id == drug.id)
+                })
+            })
+            .cloned()
+            .collect();
+        drugs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(drugs
+            .into_iter()
+            .skip(offset as usize)
+            .take(page_size as usize)
+            .collect())
+    }
+
+    async fn get_drug_categories(
+        &self,
+        drug_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, DrugCategory>, CreatePrescriptionRepositoryError> {
+        let drug_categories = self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|drug| drug_ids.contains(&drug.id))
+            .filter_map(|drug| drug.category.map(|category| (drug.id, category)))
+            .collect();
+
+        Ok(drug_categories)
+    }
+
+    async fn get_drug_prescription_type_restrictions(
+        &self,
+        drug_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, DrugPrescriptionTypeRestriction>, CreatePrescriptionRepositoryError>
+    {
+        let restrictions = self
+            .drugs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|drug| {
+                drug_ids.contains(&drug.id) && !drug.allowed_prescription_types.is_empty()
+            })
+            .map(|drug| {
+                (
+                    drug.id,
+                    DrugPrescriptionTypeRestriction {
+                        drug_name: drug.name.clone(),
+                        allowed_prescription_types: drug.allowed_prescription_types.clone(),
+                    },
+                )
+            })
+            .collect();
+
+        Ok(restrictions)
+    }
+
+    async fn get_patient_pesel_number(
+        &self,
+        patient_id: Uuid,
+    ) -> Result<Option<String>, CreatePrescriptionRepositoryError> {
+        let pesel_number = self
+            .patients
+            .read()
+            .unwrap()
+            .iter()
+            .find(|patient| patient.id == patient_id)
+            .map(|patient| patient.pesel_number.clone());
+
+        Ok(pesel_number)
+    }
+
+    async fn get_doctor_pesel_number(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Option<String>, CreatePrescriptionRepositoryError> {
+        let pesel_number = self
+            .doctors
+            .read()
+            .unwrap()
+            .iter()
+            .find(|doctor| doctor.id == doctor_id)
+            .map(|doctor| doctor.pesel_number.clone());
+
+        Ok(pesel_number)
+    }
+
+    async fn fill_prescription(
+        &self,
+        new_prescription_fill: NewPrescriptionFill,
+    ) -> Result<PrescriptionFill, FillPrescriptionRepositoryError> {
+        let mut prescription_fills = self.fill_prescriptions(vec![new_prescription_fill]).await?;
+
+        Ok(prescription_fills.remove(0))
+    }
+
+    async fn fill_prescriptions(
+        &self,
+        new_prescription_fills: Vec<NewPrescriptionFill>,
+    ) -> Result<Vec<PrescriptionFill>, FillPrescriptionRepositoryError> {
+        let pharmacists = self.pharmacists.read().unwrap();
+        let mut prescriptions = self.prescriptions.write().unwrap();
+
+        // The fills are applied to a copy, so a failing fill leaves all prescriptions untouched
+        let mut filled_prescriptions = prescriptions.clone();
+        let mut prescription_fills = vec![];
+        for new_prescription_fill in &new_prescription_fills {
+            pharmacists
+                .iter()
+                .find(|pharmacist| pharmacist.id == new_prescription_fill.pharmacist_id)
+                .ok_or(FillPrescriptionRepositoryError::PharmacistNotFound(
+                    new_prescription_fill.pharmacist_id,
+                ))?;
+
+            let prescription = filled_prescriptions
+                .iter_mut()
+                .find(|prescription| prescription.id == new_prescription_fill.prescription_id)
+                .ok_or(FillPrescriptionRepositoryError::PrescriptionNotFound(
+                    new_prescription_fill.prescription_id,
+                ))?;
+            for dispensed_drug in &new_prescription_fill.dispensed_drugs {
+                if !prescription.prescribed_drugs.iter().any(|prescribed_drug| {
+                    prescribed_drug.id == dispensed_drug.prescribed_drug_id
+                        && prescribed_drug.dispensed_quantity + dispensed_drug.quantity as i32
+                            <= prescribed_drug.quantity
+                }) {
+                    Err(FillPrescriptionRepositoryError::QuantityExceeded(
+                        new_prescription_fill.prescription_id,
+                    ))?;
+                }
+            }
+
+            let prescription_fill = PrescriptionFill {
+                id: new_prescription_fill.id,
+                prescription_id: new_prescription_fill.prescription_id,
+                pharmacist_id: new_prescription_fill.pharmacist_id,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            prescription.record_fill(new_prescription_fill, prescription_fill);
+            prescription_fills.push(prescription_fill);
+        }
+
+        *prescriptions = filled_prescriptions;
+
+        Ok(prescription_fills)
+    }
+
+    async fn reassign_prescription_patient(
+        &self,
+        prescription_id: Uuid,
+        new_patient_id: Uuid,
+    ) -> Result<Prescription, ReassignPrescriptionPatientRepositoryError> {
+        let patients = self.patients.read().unwrap();
+        let found_patient = patients
+            .iter()
+            .find(|patient| patient.id == new_patient_id)
+            .ok_or(ReassignPrescriptionPatientRepositoryError::PatientNotFound(
+                new_patient_id,
+            ))?;
+
+        let mut prescriptions = self.prescriptions.write().unwrap();
+        let prescription = prescriptions
+            .iter_mut()
+            .find(|prescription| prescription.id == prescription_id)
+            .ok_or(
+                ReassignPrescriptionPatientRepositoryError::PrescriptionNotFound(prescription_id),
+            )?;
+        if !prescription.fills.is_empty() {
+            Err(ReassignPrescriptionPatientRepositoryError::AlreadyFilled(
+                prescription_id,
+            ))?;
+        }
+
+        prescription.patient = PrescriptionPatient {
+            id: found_patient.id,
+            name: found_patient.name.clone(),
+            pesel_number: found_patient.pesel_number.clone(),
+        };
+        prescription.updated_at = Utc::now();
+
+        Ok(prescription.clone())
+    }
+
+    async fn cancel_prescription(
+        &self,
+        prescription_id: Uuid,
+    ) -> Result<Prescription, CancelPrescriptionRepositoryError> {
+        let mut prescriptions = self.prescriptions.write().unwrap();
+        let prescription = prescriptions
+            .iter_mut()
+            .find(|prescription| prescription.id == prescription_id)
+            .ok_or(CancelPrescriptionRepositoryError::PrescriptionNotFound(
+                prescription_id,
+            ))?;
+        if prescription.cancelled_at.is_some() {
+            Err(CancelPrescriptionRepositoryError::AlreadyCancelled(
+                prescription_id,
+            ))?;
+        }
+        if !prescription.fills.is_empty() {
+            Err(CancelPrescriptionRepositoryError::AlreadyFilled(
+                prescription_id,
+            ))?;
+        }
+
+        let now = Utc::now();
+        prescription.cancelled_at = Some(now);
+        prescription.fill_status = prescription.compute_fill_status();
+        prescription.updated_at = now;
+
+        Ok(prescription.clone())
+    }
+
+    async fn create_prescription_template(
+        &self,
+        new_template: NewPrescriptionTemplate,
+    ) -> Result<PrescriptionTemplate, CreatePrescriptionTemplateRepositoryError> {
+        if !self
+            .doctors
+            .read()
+            .unwrap()
+            .iter()
+            .any(|doctor| doctor.id == new_template.doctor_id)
+        {
+            Err(CreatePrescriptionTemplateRepositoryError::DoctorNotFound(
+                new_template.doctor_id,
+            ))?;
+        }
+        if let Some(drug_id) = self.find_missing_drug_id(&new_template.prescribed_drugs) {
+            Err(CreatePrescriptionTemplateRepositoryError::DrugNotFound(
+                drug_id,
+            ))?;
+        }
+
+        let template = PrescriptionTemplate {
+            id: new_template.id,
+            doctor_id: new_template.doctor_id,
+            name: new_template.name,
+            prescribed_drugs: to_template_drugs(&new_template.prescribed_drugs),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        self.prescription_templates
+            .write()
+            .unwrap()
+            .push(template.clone());
+
+        Ok(template)
+    }
+
+    async fn get_prescription_template_by_id(
+        &self,
+        template_id: Uuid,
+    ) -> Result<PrescriptionTemplate, GetPrescriptionTemplateByIdRepositoryError> {
+        self.prescription_templates
+            .read()
+            .unwrap()
+            .iter()
+            .find(|template| template.id == template_id)
+            .cloned()
+            .ok_or(GetPrescriptionTemplateByIdRepositoryError::NotFound(
+                template_id,
+            ))
+    }
+
+    async fn get_prescription_templates_for_doctor(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<PrescriptionTemplate>, GetPrescriptionTemplatesRepositoryError> {
+        let templates = self
+            .prescription_templates
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|template| template.doctor_id == doctor_id)
+            .cloned()
+            .collect();
+
+        Ok(templates)
+    }
+
+    async fn update_prescription_template(
+        &self,
+        new_template: NewPrescriptionTemplate,
+    ) -> Result<PrescriptionTemplate, UpdatePrescriptionTemplateRepositoryError> {
+        if let Some(drug_id) = self.find_missing_drug_id(&new_template.prescribed_drugs) {
+            Err(UpdatePrescriptionTemplateRepositoryError::DrugNotFound(
+                drug_id,
+            ))?;
+        }
+
+        let mut templates = self.prescription_templates.write().unwrap();
+        let template = templates
+            .iter_mut()
+            .find(|template| template.id == new_template.id)
+            .ok_or(UpdatePrescriptionTemplateRepositoryError::NotFound(
+                new_template.id,
+            ))?;
+
+        template.name = new_template.name;
+        template.prescribed_drugs = to_template_drugs(&new_template.prescribed_drugs);
+        template.updated_at = Utc::now();
+
+        Ok(template.clone())
+    }
+
+    async fn delete_prescription_template(
+        &self,
+        template_id: Uuid,
+    ) -> Result<(), DeletePrescriptionTemplateRepositoryError> {
+        let mut templates = self.prescription_templates.write().unwrap();
+        let index = templates
+            .iter()
+            .position(|template| template.id == template_id)
+            .ok_or(DeletePrescriptionTemplateRepositoryError::NotFound(
+                template_id,
+            ))?;
+        templates.remove(index);
+
+        Ok(())
+    }
+}
+
+impl PrescriptionsRepositoryFake {
+    fn find_missing_drug_id(&self, prescribed_drugs: &[NewPrescribedDrug]) -> Option<Uuid> {
+        let drugs = self.drugs.read().unwrap();
+        prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| prescribed_drug.drug_id)
+            .find(|drug_id| !drugs.iter().any(|drug| drug.id == *drug_id))
+    }
+}
+
+fn to_template_drugs(prescribed_drugs: &[NewPrescribedDrug]) -> Vec<PrescriptionTemplateDrug> {
+    prescribed_drugs
+        .iter()
+        .map(|prescribed_drug| PrescriptionTemplateDrug {
+            drug_id: prescribed_drug.drug_id,
+            quantity: prescribed_drug.quantity as i32,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::{
+        doctors::{
+            entities::NewDoctor,
+            repository::{DoctorsRepository, DoctorsRepositoryFake},
+        },
+        drugs::{
+            entities::{DrugContentType, NewDrug},
+            repository::{DrugsRepository, DrugsRepositoryFake},
+        },
+        patients::{
+            entities::NewPatient,
+            repository::{PatientsRepository, PatientsRepositoryFake},
+        },
+        pharmacists::{
+            entities::NewPharmacist,
+            repository::{PharmacistsRepository, PharmacistsRepositoryFake},
+        },
+        prescriptions::{
+            entities::{
+                DoctorPrescriptionCount, NewPrescribedDrug, NewPrescription,
+                NewPrescriptionTemplate, PrescriptionFillStatus, PrescriptionType,
+            },
+            policy::PrescriptionPolicy,
+            repository::{
+                CancelPrescriptionRepositoryError, CreatePrescriptionRepositoryError,
+                CreatePrescriptionTemplateRepositoryError,
+                DeletePrescriptionTemplateRepositoryError, FillPrescriptionRepositoryError,
+                GetDrugUsageRepositoryError, GetPharmacistFillStatsRepositoryError,
+                GetPrescriptionByIdRepositoryError, GetPrescriptionTemplateByIdRepositoryError,
+                GetPrescriptionsRepositoryError, PrescriptionsRepository,
+                PrescriptionsRepositoryFake, ReassignPrescriptionPatientRepositoryError,
+                UpdatePrescriptionTemplateRepositoryError, PRESCRIPTIONS_DEFAULT_PAGE_SIZE,
+            },
+        },
+    };
+
+    struct DatabaseSeeds {
+        doctor: NewDoctor,
+        pharmacist: NewPharmacist,
+        patient: NewPatient,
+        drugs: Vec<NewDrug>,
+    }
+
+    async fn seed_in_memory_database(
+        prescriptions_repo: &PrescriptionsRepositoryFake,
+    ) -> DatabaseSeeds {
+        let pharmacists_repo = PharmacistsRepositoryFake::new();
+        let pharmacist = NewPharmacist::new(
+            "John Pharmacist".into(), //
+            "96021807250".into(),
+        )
+        .unwrap();
+        let created_pharmacist = pharmacists_repo
+            .create_pharmacist(pharmacist.clone())
+            .await
+            .unwrap();
+        prescriptions_repo
+            .pharmacists
+            .write()
+            .unwrap()
+            .push(created_pharmacist);
+
+        let patients_repo = PatientsRepositoryFake::new();
+        let patient = NewPatient::new(
+            "John Patient".into(), //
+            "96021807250".into(),
+        )
+        .unwrap();
+        let created_patient = patients_repo.create_patient(patient.clone()).await.unwrap();
+        prescriptions_repo
+            .patients
+            .write()
+            .unwrap()
+            .push(created_patient);
+
+        let doctors_repo = DoctorsRepositoryFake::new();
+        let doctor = NewDoctor::new(
+            "John Doctor".into(), //
+            "3123456".into(),
             "96021807250".into(),
         )
         .unwrap();
-        let created_pharmacist = pharmacists_repo
-            .create_pharmacist(pharmacist.clone())
+        let created_doctor = doctors_repo.create_doctor(doctor.clone()).await.unwrap();
+        prescriptions_repo
+            .doctors
+            .write()
+            .unwrap()
+            .push(created_doctor);
+
+        let drugs_repo = DrugsRepositoryFake::new();
+        let mut drugs = vec![];
+        for (name, unit_price_cents) in [
+            ("Gripex", Some(1000)),
+            ("Apap", None),
+            ("Flegamax", Some(250)),
+            ("Rutinoscorbin", None),
+        ] {
+            let drug = NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                unit_price_cents,
+                None,
+                None,
+            )
+            .unwrap();
+            drugs.push(drug.clone());
+            let created_drug = drugs_repo.create_drug(drug).await.unwrap();
+            prescriptions_repo.drugs.write().unwrap().push(created_drug);
+        }
+
+        DatabaseSeeds {
+            doctor,
+            pharmacist,
+            patient,
+            drugs,
+        }
+    }
+
+    async fn setup_repository() -> (PrescriptionsRepositoryFake, DatabaseSeeds) {
+        let repository = PrescriptionsRepositoryFake::new(None, None, None, None, None);
+        let seeds = seed_in_memory_database(&repository).await;
+        (repository, seeds)
+    }
+
+    #[tokio::test]
+    async fn creates_and_reads_prescription_by_id() {
+        let (repository, seeds) = setup_repository().await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(new_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db, new_prescription);
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_prescription_if_relations_dont_exist() {
+        let (repository, seeds) = setup_repository().await;
+
+        let nonexistent_doctor_id = Uuid::new_v4();
+        let new_prescription_with_nonexisting_doctor_id = NewPrescription::new(
+            nonexistent_doctor_id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription_with_nonexisting_doctor_id)
+                .await,
+            Err(CreatePrescriptionRepositoryError::DoctorNotFound(
+                nonexistent_doctor_id
+            ))
+        );
+
+        let nonexistent_patient_id = Uuid::new_v4();
+        let new_prescription_with_nonexisting_patient_id = NewPrescription::new(
+            seeds.patient.id,
+            nonexistent_patient_id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription_with_nonexisting_patient_id)
+                .await,
+            Err(CreatePrescriptionRepositoryError::PatientNotFound(
+                nonexistent_patient_id
+            ))
+        );
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        let new_prescription_with_nonexisting_drug_id = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: nonexistent_drug_id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription_with_nonexisting_drug_id)
+                .await,
+            Err(CreatePrescriptionRepositoryError::DrugNotFound(
+                nonexistent_drug_id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn get_prescription_by_id_returns_error_if_prescription_doesnt_exist() {
+        let (repository, _) = setup_repository().await;
+        let prescription_id = Uuid::new_v4();
+
+        let prescription_from_db = repository.get_prescription_by_id(prescription_id).await;
+
+        assert_eq!(
+            prescription_from_db,
+            Err(GetPrescriptionByIdRepositoryError::NotFound(
+                prescription_id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn creates_and_reads_prescriptions_from_database() {
+        let (repository, seeds) = setup_repository().await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[3].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        for _ in 0..10 {
+            let another_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(another_prescription)
+                .await
+                .unwrap();
+        }
+
+        let prescriptions = repository
+            .get_prescriptions(None, Some(7), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 7);
+        assert_eq!(prescriptions[0], new_prescription);
+
+        let prescriptions = repository
+            .get_prescriptions(None, Some(20), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(prescriptions.len(), 11);
+
+        let prescriptions = repository
+            .get_prescriptions(Some(1), Some(10), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(prescriptions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_prescriptions_returns_error_if_pagination_params_are_incorrect() {
+        let (repository, _) = setup_repository().await;
+
+        assert!(match repository
+            .get_prescriptions(Some(-1), Some(10), None, None, None)
+            .await
+        {
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
+            _ => false,
+        },);
+
+        assert!(match repository
+            .get_prescriptions(Some(0), Some(0), None, None, None)
+            .await
+        {
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[tokio::test]
+    async fn fills_prescription_and_saves_to_database() {
+        let (repository, seeds) = setup_repository().await;
+
+        let prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        repository
+            .create_prescription(prescription.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert!(prescription_from_db.fills.is_empty());
+
+        let code = prescription_from_db.code.clone();
+        let new_prescription_fill = prescription_from_db
+            .fill(seeds.pharmacist.id, &code, None, Utc::now())
+            .unwrap();
+        let created_prescription_fill = repository
+            .fill_prescription(new_prescription_fill.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(created_prescription_fill, new_prescription_fill);
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db.fills[0], new_prescription_fill);
+    }
+
+    #[tokio::test]
+    async fn doesnt_fill_if_pharmacist_relation_doesnt_exist() {
+        let (repository, seeds) = setup_repository().await;
+
+        let nonexistent_pharmacist_id = Uuid::new_v4();
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        let prescription_from_db = repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let code = prescription_from_db.code.clone();
+        let new_prescription_fill_with_nonexistent_pharmacist_id = prescription_from_db
+            .fill(nonexistent_pharmacist_id, &code, None, Utc::now())
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .fill_prescription(new_prescription_fill_with_nonexistent_pharmacist_id)
+                .await,
+            Err(FillPrescriptionRepositoryError::PharmacistNotFound(
+                nonexistent_pharmacist_id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn fills_all_prescriptions_in_batch_or_none() {
+        let (repository, seeds) = setup_repository().await;
+
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let new_prescription_fills: Vec<_> = prescriptions
+            .iter()
+            .map(|prescription| {
+                prescription
+                    .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+                    .unwrap()
+            })
+            .collect();
+
+        let nonexistent_pharmacist_id = Uuid::new_v4();
+        let mut fills_with_invalid_last_item = new_prescription_fills.clone();
+        fills_with_invalid_last_item[2].pharmacist_id = nonexistent_pharmacist_id;
+
+        assert_eq!(
+            repository
+                .fill_prescriptions(fills_with_invalid_last_item)
+                .await,
+            Err(FillPrescriptionRepositoryError::PharmacistNotFound(
+                nonexistent_pharmacist_id
+            ))
+        );
+        for prescription in &prescriptions {
+            let prescription_from_db = repository
+                .get_prescription_by_id(prescription.id)
+                .await
+                .unwrap();
+            assert!(prescription_from_db.fills.is_empty());
+        }
+
+        let prescription_fills = repository
+            .fill_prescriptions(new_prescription_fills.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_fills, new_prescription_fills);
+        for (prescription, new_prescription_fill) in
+            prescriptions.iter().zip(&new_prescription_fills)
+        {
+            let prescription_from_db = repository
+                .get_prescription_by_id(prescription.id)
+                .await
+                .unwrap();
+            assert_eq!(prescription_from_db.fills[0], *new_prescription_fill);
+        }
+
+        assert_eq!(
+            repository
+                .fill_prescriptions(vec![prescriptions[0]
+                    .fill(
+                        seeds.pharmacist.id,
+                        &prescriptions[0].code,
+                        None,
+                        Utc::now()
+                    )
+                    .unwrap()])
+                .await,
+            Err(FillPrescriptionRepositoryError::QuantityExceeded(
+                prescriptions[0].id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn fills_prescription_partially_and_then_dispenses_the_remainder() {
+        let (repository, seeds) = setup_repository().await;
+        let prescription = repository
+            .create_prescription(
+                NewPrescription::new(
+                    seeds.doctor.id,
+                    seeds.patient.id,
+                    None,
+                    None,
+                    vec![
+                        NewPrescribedDrug {
+                            drug_id: seeds.drugs[0].id,
+                            quantity: 3,
+                        },
+                        NewPrescribedDrug {
+                            drug_id: seeds.drugs[1].id,
+                            quantity: 1,
+                        },
+                    ],
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let partial_fill = prescription
+            .fill(
+                seeds.pharmacist.id,
+                &prescription.code,
+                Some(vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 2,
+                }]),
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(partial_fill.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            prescription_from_db.fill_status,
+            PrescriptionFillStatus::PartiallyFilled
+        );
+        assert_eq!(prescription_from_db.fills, vec![partial_fill.clone()]);
+        let dispensed_quantities: Vec<_> = prescription_from_db
+            .prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| (prescribed_drug.drug_id, prescribed_drug.dispensed_quantity))
+            .collect();
+        assert!(dispensed_quantities.contains(&(seeds.drugs[0].id, 2)));
+        assert!(dispensed_quantities.contains(&(seeds.drugs[1].id, 0)));
+
+        // Was built before the partial fill, so it dispenses more than remains
+        let stale_fill = prescription
+            .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+            .unwrap();
+
+        assert_eq!(
+            repository.fill_prescription(stale_fill).await,
+            Err(FillPrescriptionRepositoryError::QuantityExceeded(
+                prescription.id
+            ))
+        );
+
+        let remainder_fill = prescription_from_db
+            .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+            .unwrap();
+        repository
+            .fill_prescription(remainder_fill.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            prescription_from_db.fill_status,
+            PrescriptionFillStatus::Filled
+        );
+        assert_eq!(prescription_from_db.fills.len(), 2);
+        assert!(prescription_from_db
+            .prescribed_drugs
+            .iter()
+            .all(|prescribed_drug| prescribed_drug.dispensed_quantity == prescribed_drug.quantity));
+    }
+
+    #[tokio::test]
+    async fn gets_unfilled_prescriptions_expiring_before_given_date() {
+        let (repository, seeds) = setup_repository().await;
+        let now = Utc::now();
+        // Prescriptions are issued on their start date, so they can be backdated
+        let create_prescription = |start_date, prescription_type| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                Some(prescription_type),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let expired_prescription =
+            create_prescription(now - Duration::days(60), PrescriptionType::Regular);
+        let expiring_prescription =
+            create_prescription(now - Duration::days(25), PrescriptionType::Regular);
+        let filled_expiring_prescription =
+            create_prescription(now - Duration::days(26), PrescriptionType::Regular);
+        let far_future_prescription =
+            create_prescription(now, PrescriptionType::ForChronicDiseaseDrugs);
+        for prescription in [
+            &expired_prescription,
+            &expiring_prescription,
+            &filled_expiring_prescription,
+            &far_future_prescription,
+        ] {
+            repository
+                .create_prescription(prescription.clone())
+                .await
+                .unwrap();
+        }
+        let prescription_to_fill = repository
+            .get_prescription_by_id(filled_expiring_prescription.id)
+            .await
+            .unwrap();
+        let code = prescription_to_fill.code.clone();
+        repository
+            .fill_prescription(
+                prescription_to_fill
+                    .fill(seeds.pharmacist.id, &code, None, Utc::now())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let prescriptions = repository
+            .get_prescriptions_expiring_before(seeds.patient.id, now + Duration::days(7))
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, expiring_prescription.id);
+
+        let prescriptions = repository
+            .get_prescriptions_expiring_before(Uuid::new_v4(), now + Duration::days(7))
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gets_active_unfilled_prescriptions_for_patient() {
+        let (repository, seeds) = setup_repository().await;
+        let now = Utc::now();
+        // Prescriptions are issued on their start date, so they can be backdated
+        let create_prescription = |start_date| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                Some(PrescriptionType::Regular),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let pending_prescription = create_prescription(now + Duration::days(2));
+        let active_prescription = create_prescription(now - Duration::days(2));
+        let expired_prescription = create_prescription(now - Duration::days(60));
+        let filled_prescription = create_prescription(now - Duration::days(1));
+        for prescription in [
+            &pending_prescription,
+            &active_prescription,
+            &expired_prescription,
+            &filled_prescription,
+        ] {
+            repository
+                .create_prescription(prescription.clone())
+                .await
+                .unwrap();
+        }
+        let prescription_to_fill = repository
+            .get_prescription_by_id(filled_prescription.id)
+            .await
+            .unwrap();
+        let code = prescription_to_fill.code.clone();
+        repository
+            .fill_prescription(
+                prescription_to_fill
+                    .fill(seeds.pharmacist.id, &code, None, Utc::now())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let prescriptions = repository
+            .get_active_prescriptions_for_patient(seeds.patient.id, now)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, active_prescription.id);
+
+        let prescriptions = repository
+            .get_active_prescriptions_for_patient(Uuid::new_v4(), now)
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gets_prescriptions_for_patient_newest_first() {
+        let (repository, seeds) = setup_repository().await;
+        let mut created_prescription_ids = vec![];
+        for drug in &seeds.drugs[..3] {
+            let new_prescription = NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: drug.id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                Utc::now(),
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription.clone())
+                .await
+                .unwrap();
+            created_prescription_ids.push(new_prescription.id);
+        }
+
+        let prescriptions = repository
+            .get_prescriptions_for_patient(seeds.patient.id, None, None)
+            .await
+            .unwrap();
+
+        let prescription_ids: Vec<Uuid> = prescriptions
+            .iter()
+            .map(|prescription| prescription.id)
+            .collect();
+        assert_eq!(
+            prescription_ids,
+            created_prescription_ids
+                .iter()
+                .rev()
+                .copied()
+                .collect::<Vec<_>>()
+        );
+
+        let prescriptions = repository
+            .get_prescriptions_for_patient(seeds.patient.id, Some(1), Some(2))
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, created_prescription_ids[0]);
+
+        let prescriptions = repository
+            .get_prescriptions_for_patient(Uuid::new_v4(), None, None)
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn searches_prescriptions_by_patient_name_doctor_name_and_code() {
+        let (repository, seeds) = setup_repository().await;
+        let other_patient = PatientsRepositoryFake::new()
+            .create_patient(NewPatient::new("Jane Patient".into(), "92022900002".into()).unwrap())
+            .await
+            .unwrap();
+        repository
+            .patients
+            .write()
+            .unwrap()
+            .push(other_patient.clone());
+        let mut created_prescriptions = vec![];
+        for patient_id in [seeds.patient.id, other_patient.id] {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                patient_id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            created_prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let prescriptions = repository
+            .search_prescriptions("jAnE", None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, created_prescriptions[1].id);
+
+        let prescriptions = repository
+            .search_prescriptions(&created_prescriptions[0].code, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, created_prescriptions[0].id);
+
+        let prescriptions = repository
+            .search_prescriptions("John Doc", None, Some(1))
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+
+        let prescriptions = repository
+            .search_prescriptions("Nobody", None, None)
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gets_drug_history_for_patient_ordered_by_date() {
+        let (repository, seeds) = setup_repository().await;
+        let now = Utc::now();
+        let create_prescription = |start_date, prescribed_drugs| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                None,
+                prescribed_drugs,
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let oldest_prescription = create_prescription(
+            now - Duration::days(20),
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        );
+        let newest_prescription = create_prescription(
+            now,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[1].id,
+                quantity: 2,
+            }],
+        );
+        let middle_prescription = create_prescription(
+            now - Duration::days(10),
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 3,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[3].id,
+                    quantity: 1,
+                },
+            ],
+        );
+        for prescription in [
+            &oldest_prescription,
+            &newest_prescription,
+            &middle_prescription,
+        ] {
+            repository
+                .create_prescription(prescription.clone())
+                .await
+                .unwrap();
+        }
+
+        let drug_history = repository
+            .get_drug_history_for_patient(seeds.patient.id, None, None)
+            .await
+            .unwrap();
+
+        let prescription_ids: Vec<Uuid> = drug_history
+            .iter()
+            .map(|entry| entry.prescription_id)
+            .collect();
+        assert_eq!(
+            prescription_ids,
+            vec![
+                newest_prescription.id,
+                middle_prescription.id,
+                middle_prescription.id,
+                oldest_prescription.id
+            ]
+        );
+        assert_eq!(drug_history[0].drug_id, seeds.drugs[1].id);
+        assert_eq!(drug_history[0].drug_name, seeds.drugs[1].name);
+        assert_eq!(drug_history[0].quantity, 2);
+        assert_eq!(drug_history[0].prescription_code, newest_prescription.code);
+        assert_eq!(drug_history[3].drug_id, seeds.drugs[0].id);
+
+        let second_page = repository
+            .get_drug_history_for_patient(seeds.patient.id, Some(1), Some(3))
+            .await
+            .unwrap();
+
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].prescription_id, oldest_prescription.id);
+
+        assert!(repository
+            .get_drug_history_for_patient(Uuid::new_v4(), None, None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            repository
+                .get_drug_history_for_patient(seeds.patient.id, Some(-1), None)
+                .await,
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn estimates_prescription_cost_from_priced_drugs() {
+        let (repository, seeds) = setup_repository().await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 2,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 3,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(new_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db.estimated_cost_cents, Some(2250));
+
+        let new_unpriced_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[3].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+        let unpriced_prescription = repository
+            .create_prescription(new_unpriced_prescription)
+            .await
+            .unwrap();
+
+        assert_eq!(unpriced_prescription.estimated_cost_cents, None);
+    }
+
+    #[tokio::test]
+    async fn reassigns_patient_of_unfilled_prescription() {
+        let (repository, seeds) = setup_repository().await;
+        let other_patient = PatientsRepositoryFake::new()
+            .create_patient(NewPatient::new("Jane Patient".into(), "92022900002".into()).unwrap())
+            .await
+            .unwrap();
+        repository
+            .patients
+            .write()
+            .unwrap()
+            .push(other_patient.clone());
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+        let prescription = repository
+            .create_prescription(new_prescription)
+            .await
+            .unwrap();
+
+        let reassigned_prescription = repository
+            .reassign_prescription_patient(prescription.id, other_patient.id)
+            .await
+            .unwrap();
+
+        assert_eq!(reassigned_prescription.patient.id, other_patient.id);
+        assert_eq!(
+            repository
+                .get_prescription_by_id(prescription.id)
+                .await
+                .unwrap()
+                .patient
+                .id,
+            other_patient.id
+        );
+
+        let nonexistent_patient_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .reassign_prescription_patient(prescription.id, nonexistent_patient_id)
+                .await,
+            Err(ReassignPrescriptionPatientRepositoryError::PatientNotFound(
+                nonexistent_patient_id
+            ))
+        );
+
+        let new_prescription_fill = reassigned_prescription
+            .fill(
+                seeds.pharmacist.id,
+                &reassigned_prescription.code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .reassign_prescription_patient(prescription.id, seeds.patient.id)
+                .await,
+            Err(ReassignPrescriptionPatientRepositoryError::AlreadyFilled(
+                prescription.id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn cancels_only_unfilled_prescription() {
+        let (repository, seeds) = setup_repository().await;
+        let mut prescriptions = vec![];
+        for _ in 0..2 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let cancelled_prescription = repository
+            .cancel_prescription(prescriptions[0].id)
+            .await
+            .unwrap();
+
+        assert!(cancelled_prescription.cancelled_at.is_some());
+        assert_eq!(
+            cancelled_prescription.fill_status,
+            PrescriptionFillStatus::Cancelled
+        );
+        assert_eq!(
+            repository.cancel_prescription(prescriptions[0].id).await,
+            Err(CancelPrescriptionRepositoryError::AlreadyCancelled(
+                prescriptions[0].id
+            ))
+        );
+
+        let new_prescription_fill = prescriptions[1]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[1].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository.cancel_prescription(prescriptions[1].id).await,
+            Err(CancelPrescriptionRepositoryError::AlreadyFilled(
+                prescriptions[1].id
+            ))
+        );
+
+        let nonexistent_prescription_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .cancel_prescription(nonexistent_prescription_id)
+                .await,
+            Err(CancelPrescriptionRepositoryError::PrescriptionNotFound(
+                nonexistent_prescription_id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn uses_prescriptions_default_page_size_if_page_size_is_omitted() {
+        let (repository, seeds) = setup_repository().await;
+        for _ in 0..PRESCRIPTIONS_DEFAULT_PAGE_SIZE + 1 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription)
+                .await
+                .unwrap();
+        }
+
+        let prescriptions = repository
+            .get_prescriptions(None, None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            prescriptions.len(),
+            PRESCRIPTIONS_DEFAULT_PAGE_SIZE as usize
+        );
+
+        let prescriptions = repository
+            .get_prescriptions(Some(1), None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+
+        let prescriptions = repository
+            .get_prescriptions(None, Some(5), None, None, None)
             .await
             .unwrap();
-        prescriptions_repo
-            .pharmacists
-            .write()
-            .unwrap()
-            .push(created_pharmacist);
 
-        let patients_repo = PatientsRepositoryFake::new();
-        let patient = NewPatient::new(
-            "John Patient".into(), //
-            "96021807250".into(),
-        )
-        .unwrap();
-        let created_patient = patients_repo.create_patient(patient.clone()).await.unwrap();
-        prescriptions_repo
-            .patients
-            .write()
-            .unwrap()
-            .push(created_patient);
+        assert_eq!(prescriptions.len(), 5);
 
-        let doctors_repo = DoctorsRepositoryFake::new();
-        let doctor = NewDoctor::new(
-            "John Doctor".into(), //
-            "3123456".into(),
-            "96021807250".into(),
-        )
-        .unwrap();
-        let created_doctor = doctors_repo.create_doctor(doctor.clone()).await.unwrap();
-        prescriptions_repo
+        let drug_history = repository
+            .get_drug_history_for_patient(seeds.patient.id, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(drug_history.len(), PRESCRIPTIONS_DEFAULT_PAGE_SIZE as usize);
+    }
+
+    #[tokio::test]
+    async fn counts_prescriptions_by_type() {
+        let (repository, seeds) = setup_repository().await;
+        for prescription_type in [
+            PrescriptionType::Regular,
+            PrescriptionType::ForAntibiotics,
+            PrescriptionType::Regular,
+        ] {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(prescription_type),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription)
+                .await
+                .unwrap();
+        }
+
+        let now = Utc::now();
+        let counts = repository
+            .count_prescriptions_by_type(now - Duration::days(1), now)
+            .await
+            .unwrap();
+
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts[&PrescriptionType::Regular], 2);
+        assert_eq!(counts[&PrescriptionType::ForAntibiotics], 1);
+        assert_eq!(counts[&PrescriptionType::ForImmunologicalDrugs], 0);
+        assert_eq!(counts[&PrescriptionType::ForChronicDiseaseDrugs], 0);
+
+        let counts = repository
+            .count_prescriptions_by_type(now - Duration::days(2), now - Duration::days(1))
+            .await
+            .unwrap();
+
+        assert!(counts.values().all(|&count| count == 0));
+    }
+
+    #[tokio::test]
+    async fn counts_prescriptions_per_doctor_busiest_first() {
+        let (repository, seeds) = setup_repository().await;
+        let other_doctor = DoctorsRepositoryFake::new()
+            .create_doctor(
+                NewDoctor::new("Jane Doctor".into(), "8463856".into(), "92022900002".into())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        repository
             .doctors
             .write()
             .unwrap()
-            .push(created_doctor);
-
-        let drugs_repo = DrugsRepositoryFake::new();
-        let mut drugs = vec![];
-        for _ in 0..4 {
-            let drug = NewDrug::new(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+            .push(other_doctor.clone());
+        for doctor_id in [seeds.doctor.id, other_doctor.id, other_doctor.id] {
+            let new_prescription = NewPrescription::new(
+                doctor_id,
+                seeds.patient.id,
                 None,
                 None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
             )
             .unwrap();
-            drugs.push(drug.clone());
-            let created_drug = drugs_repo.create_drug(drug).await.unwrap();
-            prescriptions_repo.drugs.write().unwrap().push(created_drug);
+            repository
+                .create_prescription(new_prescription)
+                .await
+                .unwrap();
         }
 
-        DatabaseSeeds {
-            doctor,
-            pharmacist,
-            patient,
-            drugs,
-        }
-    }
+        let now = Utc::now();
+        let counts = repository
+            .count_prescriptions_per_doctor(now - Duration::days(1), now, None, None)
+            .await
+            .unwrap();
 
-    async fn setup_repository() -> (PrescriptionsRepositoryFake, DatabaseSeeds) {
-        let repository = PrescriptionsRepositoryFake::new(None, None, None, None, None);
-        let seeds = seed_in_memory_database(&repository).await;
-        (repository, seeds)
+        assert_eq!(
+            counts,
+            vec![
+                DoctorPrescriptionCount {
+                    doctor_id: other_doctor.id,
+                    doctor_name: "Jane Doctor".to_string(),
+                    prescriptions_count: 2,
+                },
+                DoctorPrescriptionCount {
+                    doctor_id: seeds.doctor.id,
+                    doctor_name: "John Doctor".to_string(),
+                    prescriptions_count: 1,
+                },
+            ]
+        );
+
+        let second_page = repository
+            .count_prescriptions_per_doctor(now - Duration::days(1), now, Some(1), Some(1))
+            .await
+            .unwrap();
+
+        assert_eq!(second_page, counts[1..]);
+
+        let counts = repository
+            .count_prescriptions_per_doctor(
+                now - Duration::days(2),
+                now - Duration::days(1),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(counts.is_empty());
     }
 
     #[tokio::test]
-    async fn creates_and_reads_prescription_by_id() {
+    async fn gets_prescriptions_by_ids_leaving_out_unknown_ones() {
         let (repository, seeds) = setup_repository().await;
+        let mut prescription_ids = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription.clone())
+                .await
+                .unwrap();
+            prescription_ids.push(new_prescription.id);
+        }
 
+        let prescriptions = repository
+            .get_prescriptions_by_ids(&[
+                prescription_ids[2],
+                Uuid::new_v4(),
+                prescription_ids[0],
+                prescription_ids[2],
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 2);
+        assert_eq!(prescriptions[0].id, prescription_ids[2]);
+        assert_eq!(prescriptions[1].id, prescription_ids[0]);
+        assert_eq!(prescriptions[0].prescribed_drugs.len(), 1);
+
+        let prescriptions = repository
+            .get_prescriptions_by_ids(&[Uuid::new_v4()])
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gets_only_drugs_that_were_never_prescribed() {
+        let (repository, seeds) = setup_repository().await;
         let new_prescription = NewPrescription::new(
             seeds.doctor.id,
             seeds.patient.id,
@@ -394,149 +2739,272 @@ mod tests {
                     quantity: 1,
                 },
                 NewPrescribedDrug {
-                    drug_id: seeds.drugs[1].id,
-                    quantity: 1,
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 2,
                 },
             ],
         )
         .unwrap();
-
         repository
-            .create_prescription(new_prescription.clone())
+            .create_prescription(new_prescription)
             .await
             .unwrap();
 
-        let prescription_from_db = repository
-            .get_prescription_by_id(new_prescription.id)
+        let drugs = repository.get_unprescribed_drugs(None, None).await.unwrap();
+
+        assert_eq!(drugs.len(), 2);
+        assert_eq!(drugs[0].id, seeds.drugs[1].id);
+        assert_eq!(drugs[1].id, seeds.drugs[3].id);
+
+        let drugs = repository
+            .get_unprescribed_drugs(Some(1), Some(1))
             .await
             .unwrap();
 
-        assert_eq!(prescription_from_db, new_prescription);
+        assert_eq!(drugs.len(), 1);
+        assert_eq!(drugs[0].id, seeds.drugs[3].id);
     }
 
     #[tokio::test]
-    async fn doesnt_create_prescription_if_relations_dont_exist() {
+    async fn counts_fills_by_pharmacist_per_day() {
         let (repository, seeds) = setup_repository().await;
-
-        let nonexistent_doctor_id = Uuid::new_v4();
-        let new_prescription_with_nonexisting_doctor_id = NewPrescription::new(
-            nonexistent_doctor_id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: seeds.drugs[0].id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
-
-        assert_eq!(
+        let now = Utc::now();
+        let mut prescription_fill_ids = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
             repository
-                .create_prescription(new_prescription_with_nonexisting_doctor_id)
-                .await,
-            Err(CreatePrescriptionRepositoryError::DoctorNotFound(
-                nonexistent_doctor_id
-            ))
-        );
+                .create_prescription(new_prescription.clone())
+                .await
+                .unwrap();
+            let prescription = repository
+                .get_prescription_by_id(new_prescription.id)
+                .await
+                .unwrap();
+            let new_prescription_fill = prescription
+                .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+                .unwrap();
+            repository
+                .fill_prescription(new_prescription_fill.clone())
+                .await
+                .unwrap();
+            prescription_fill_ids.push(new_prescription_fill.id);
+        }
+        let two_days_ago = now - Duration::days(2);
+        for prescription in repository.prescriptions.write().unwrap().iter_mut() {
+            for fill in prescription.fills.iter_mut() {
+                if fill.id == prescription_fill_ids[0] {
+                    fill.created_at = two_days_ago;
+                }
+            }
+        }
 
-        let nonexistent_patient_id = Uuid::new_v4();
-        let new_prescription_with_nonexisting_patient_id = NewPrescription::new(
-            seeds.patient.id,
-            nonexistent_patient_id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: seeds.drugs[0].id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
+        let counts = repository
+            .count_fills_by_pharmacist_per_day(
+                seeds.pharmacist.id,
+                now - Duration::days(3),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
 
-        assert_eq!(
-            repository
-                .create_prescription(new_prescription_with_nonexisting_patient_id)
-                .await,
-            Err(CreatePrescriptionRepositoryError::PatientNotFound(
-                nonexistent_patient_id
-            ))
-        );
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&two_days_ago.date_naive()], 1);
+        assert_eq!(counts[&now.date_naive()], 2);
 
-        let nonexistent_drug_id = Uuid::new_v4();
-        let new_prescription_with_nonexisting_drug_id = NewPrescription::new(
-            seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: nonexistent_drug_id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
+        let counts = repository
+            .count_fills_by_pharmacist_per_day(
+                seeds.pharmacist.id,
+                now - Duration::days(1),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
 
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[&now.date_naive()], 2);
+
+        let pharmacist_id = Uuid::new_v4();
         assert_eq!(
             repository
-                .create_prescription(new_prescription_with_nonexisting_drug_id)
+                .count_fills_by_pharmacist_per_day(pharmacist_id, now - Duration::days(3), now)
                 .await,
-            Err(CreatePrescriptionRepositoryError::DrugNotFound(
-                nonexistent_drug_id
+            Err(GetPharmacistFillStatsRepositoryError::PharmacistNotFound(
+                pharmacist_id
             ))
         );
     }
 
-    #[tokio::test]
-    async fn get_prescription_by_id_returns_error_if_prescription_doesnt_exist() {
-        let (repository, _) = setup_repository().await;
-        let prescription_id = Uuid::new_v4();
+    #[tokio::test]
+    async fn filters_prescriptions_by_filled_status() {
+        let (repository, seeds) = setup_repository().await;
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let new_prescription_fill = prescriptions[1]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[1].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        let filled_prescriptions = repository
+            .get_prescriptions(None, None, Some(true), None, None)
+            .await
+            .unwrap();
 
-        let prescription_from_db = repository.get_prescription_by_id(prescription_id).await;
+        assert_eq!(filled_prescriptions.len(), 1);
+        assert_eq!(filled_prescriptions[0].id, prescriptions[1].id);
+
+        let unfilled_prescriptions = repository
+            .get_prescriptions(None, None, Some(false), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(unfilled_prescriptions.len(), 2);
+        assert_eq!(unfilled_prescriptions[0].id, prescriptions[0].id);
+        assert_eq!(unfilled_prescriptions[1].id, prescriptions[2].id);
+
+        let second_unfilled_page = repository
+            .get_prescriptions(Some(1), Some(1), Some(false), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(second_unfilled_page.len(), 1);
+        assert_eq!(second_unfilled_page[0].id, prescriptions[2].id);
 
         assert_eq!(
-            prescription_from_db,
-            Err(GetPrescriptionByIdRepositoryError::NotFound(
-                prescription_id
-            ))
+            repository
+                .get_prescriptions(None, None, None, None, None)
+                .await
+                .unwrap()
+                .len(),
+            3
         );
     }
 
     #[tokio::test]
-    async fn creates_and_reads_prescriptions_from_database() {
+    async fn filters_prescriptions_by_created_at_window() {
         let (repository, seeds) = setup_repository().await;
-
-        let new_prescription = NewPrescription::new(
-            seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![
-                NewPrescribedDrug {
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
                     drug_id: seeds.drugs[0].id,
                     quantity: 1,
-                },
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[1].id,
-                    quantity: 1,
-                },
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[2].id,
-                    quantity: 1,
-                },
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[3].id,
-                    quantity: 1,
-                },
-            ],
-        )
-        .unwrap();
-
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        let new_prescription_fill = prescriptions[2]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[2].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
         repository
-            .create_prescription(new_prescription.clone())
+            .fill_prescription(new_prescription_fill)
             .await
             .unwrap();
+        let ids = |indexes: &[usize]| -> Vec<Uuid> {
+            indexes.iter().map(|&i| prescriptions[i].id).collect()
+        };
+        let created_at = |i: usize| Some(prescriptions[i].created_at);
 
-        for _ in 0..10 {
-            let another_prescription = NewPrescription::new(
+        assert_eq!(
+            repository
+                .get_prescription_ids(None, None, None, created_at(1), None)
+                .await
+                .unwrap(),
+            ids(&[1, 2])
+        );
+        assert_eq!(
+            repository
+                .get_prescription_ids(None, None, None, None, created_at(1))
+                .await
+                .unwrap(),
+            ids(&[0, 1])
+        );
+        assert_eq!(
+            repository
+                .get_prescription_ids(None, None, None, created_at(1), created_at(1))
+                .await
+                .unwrap(),
+            ids(&[1])
+        );
+        assert_eq!(
+            repository
+                .get_prescriptions(None, None, Some(false), created_at(1), created_at(2))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|prescription| prescription.id)
+                .collect::<Vec<_>>(),
+            ids(&[1])
+        );
+        assert_eq!(
+            repository
+                .get_prescriptions(Some(1), Some(1), None, created_at(0), created_at(2))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|prescription| prescription.id)
+                .collect::<Vec<_>>(),
+            ids(&[1])
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_empty_page_when_page_is_beyond_the_data() {
+        let (repository, seeds) = setup_repository().await;
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
@@ -547,101 +3015,179 @@ mod tests {
                 }],
             )
             .unwrap();
-            repository
-                .create_prescription(another_prescription)
-                .await
-                .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
         }
-
-        let prescriptions = repository.get_prescriptions(None, Some(7)).await.unwrap();
-
-        assert_eq!(prescriptions.len(), 7);
-        assert_eq!(prescriptions[0], new_prescription);
-
-        let prescriptions = repository.get_prescriptions(None, Some(20)).await.unwrap();
-        assert_eq!(prescriptions.len(), 11);
-
-        let prescriptions = repository
-            .get_prescriptions(Some(1), Some(10))
+        let new_prescription_fill = prescriptions[0]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[0].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
             .await
             .unwrap();
-        assert_eq!(prescriptions.len(), 1);
-    }
-
-    #[tokio::test]
-    async fn get_prescriptions_returns_error_if_pagination_params_are_incorrect() {
-        let (repository, _) = setup_repository().await;
 
-        assert!(
-            match repository.get_prescriptions(Some(-1), Some(10)).await {
-                Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
-                _ => false,
-            },
-        );
+        let last_page = repository
+            .get_prescriptions(Some(2), Some(1), None, None, None)
+            .await
+            .unwrap();
 
-        assert!(match repository.get_prescriptions(Some(0), Some(0)).await {
-            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
-            _ => false,
-        });
+        assert_eq!(last_page.len(), 1);
+
+        for filled in [None, Some(true), Some(false)] {
+            for (page, page_size) in [
+                (Some(1), None),
+                (Some(3), Some(1)),
+                (Some(100), Some(2)),
+                (Some(i64::MAX), Some(10)),
+            ] {
+                assert!(repository
+                    .get_prescriptions(page, page_size, filled, None, None)
+                    .await
+                    .unwrap()
+                    .is_empty());
+                assert!(repository
+                    .get_prescription_ids(page, page_size, filled, None, None)
+                    .await
+                    .unwrap()
+                    .is_empty());
+            }
+        }
     }
 
     #[tokio::test]
-    async fn fills_prescription_and_saves_to_database() {
+    async fn counts_prescriptions_for_drug_within_range() {
         let (repository, seeds) = setup_repository().await;
+        let now = Utc::now();
+        let create_prescription = |start_date, prescribed_drugs| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                None,
+                prescribed_drugs,
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let prescriptions = [
+            create_prescription(
+                now - Duration::days(20),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            ),
+            create_prescription(
+                now - Duration::days(10),
+                vec![
+                    NewPrescribedDrug {
+                        drug_id: seeds.drugs[0].id,
+                        quantity: 3,
+                    },
+                    NewPrescribedDrug {
+                        drug_id: seeds.drugs[1].id,
+                        quantity: 2,
+                    },
+                ],
+            ),
+        ];
+        for prescription in prescriptions {
+            repository.create_prescription(prescription).await.unwrap();
+        }
 
-        let prescription = NewPrescription::new(
-            seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: seeds.drugs[0].id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
-
-        repository
-            .create_prescription(prescription.clone())
+        let drug_usage = repository
+            .count_prescriptions_for_drug(
+                seeds.drugs[0].id,
+                now - Duration::days(30),
+                now + Duration::minutes(1),
+            )
             .await
             .unwrap();
 
-        let prescription_from_db = repository
-            .get_prescription_by_id(prescription.id)
+        assert_eq!(drug_usage.prescriptions_count, 2);
+        assert_eq!(drug_usage.total_quantity, 4);
+
+        let drug_usage = repository
+            .count_prescriptions_for_drug(
+                seeds.drugs[0].id,
+                now - Duration::days(15),
+                now + Duration::minutes(1),
+            )
             .await
             .unwrap();
 
-        assert!(prescription_from_db.fill.is_none());
+        assert_eq!(drug_usage.prescriptions_count, 1);
+        assert_eq!(drug_usage.total_quantity, 3);
 
-        let code = prescription_from_db.code.clone();
-        let new_prescription_fill = prescription_from_db
-            .fill(seeds.pharmacist.id, code)
-            .unwrap();
-        let created_prescription_fill = repository
-            .fill_prescription(new_prescription_fill.clone())
+        let drug_usage = repository
+            .count_prescriptions_for_drug(
+                seeds.drugs[2].id,
+                now - Duration::days(30),
+                now + Duration::minutes(1),
+            )
             .await
             .unwrap();
 
-        assert_eq!(created_prescription_fill, new_prescription_fill);
+        assert_eq!(drug_usage.prescriptions_count, 0);
+        assert_eq!(drug_usage.total_quantity, 0);
 
-        let prescription_from_db = repository
-            .get_prescription_by_id(prescription.id)
+        let drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .count_prescriptions_for_drug(drug_id, now - Duration::days(30), now)
+                .await,
+            Err(GetDrugUsageRepositoryError::DrugNotFound(drug_id))
+        );
+    }
+
+    #[tokio::test]
+    async fn replaces_colliding_prescription_code() {
+        let (repository, seeds) = setup_repository().await;
+        let create_new_prescription = || {
+            NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap()
+        };
+        let existing_prescription = repository
+            .create_prescription(create_new_prescription())
+            .await
+            .unwrap();
+        let mut new_prescription = create_new_prescription();
+        new_prescription.code = existing_prescription.code.clone();
+
+        let created_prescription = repository
+            .create_prescription(new_prescription)
             .await
             .unwrap();
 
-        assert_eq!(prescription_from_db.fill.unwrap(), new_prescription_fill);
+        assert_ne!(created_prescription.code, existing_prescription.code);
+        assert_eq!(created_prescription.code.len(), 8);
     }
 
     #[tokio::test]
-    async fn doesnt_fill_if_pharmacist_relation_doesnt_exist() {
+    async fn creates_reads_updates_and_deletes_prescription_template() {
         let (repository, seeds) = setup_repository().await;
-
-        let nonexistent_pharmacist_id = Uuid::new_v4();
-        let new_prescription = NewPrescription::new(
+        let new_template = NewPrescriptionTemplate::new(
             seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
+            "Flu".into(),
             vec![
                 NewPrescribedDrug {
                     drug_id: seeds.drugs[0].id,
@@ -649,28 +3195,131 @@ mod tests {
                 },
                 NewPrescribedDrug {
                     drug_id: seeds.drugs[1].id,
-                    quantity: 1,
+                    quantity: 2,
                 },
             ],
+            &PrescriptionPolicy::default(),
         )
         .unwrap();
 
-        let prescription_from_db = repository
-            .create_prescription(new_prescription.clone())
+        let created_template = repository
+            .create_prescription_template(new_template.clone())
             .await
             .unwrap();
 
-        let code = prescription_from_db.code.clone();
-        let new_prescription_fill_with_nonexistent_pharmacist_id = prescription_from_db
-            .fill(nonexistent_pharmacist_id, code)
+        assert_eq!(created_template, new_template);
+        assert_eq!(
+            repository
+                .get_prescription_template_by_id(new_template.id)
+                .await
+                .unwrap(),
+            new_template
+        );
+        assert_eq!(
+            repository
+                .get_prescription_templates_for_doctor(seeds.doctor.id)
+                .await
+                .unwrap(),
+            vec![created_template.clone()]
+        );
+        assert!(repository
+            .get_prescription_templates_for_doctor(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty());
+
+        let updated_template = NewPrescriptionTemplate {
+            id: new_template.id,
+            ..NewPrescriptionTemplate::new(
+                seeds.doctor.id,
+                "Cold".into(),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 3,
+                }],
+                &PrescriptionPolicy::default(),
+            )
+            .unwrap()
+        };
+
+        let template = repository
+            .update_prescription_template(updated_template.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(template, updated_template);
+        assert_eq!(template.created_at, created_template.created_at);
+
+        repository
+            .delete_prescription_template(new_template.id)
+            .await
             .unwrap();
 
         assert_eq!(
             repository
-                .fill_prescription(new_prescription_fill_with_nonexistent_pharmacist_id)
+                .get_prescription_template_by_id(new_template.id)
                 .await,
-            Err(FillPrescriptionRepositoryError::PharmacistNotFound(
-                nonexistent_pharmacist_id
+            Err(GetPrescriptionTemplateByIdRepositoryError::NotFound(
+                new_template.id
+            ))
+        );
+        assert_eq!(
+            repository
+                .delete_prescription_template(new_template.id)
+                .await,
+            Err(DeletePrescriptionTemplateRepositoryError::NotFound(
+                new_template.id
+            ))
+        );
+        assert_eq!(
+            repository
+                .update_prescription_template(updated_template.clone())
+                .await,
+            Err(UpdatePrescriptionTemplateRepositoryError::NotFound(
+                new_template.id
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_prescription_template_if_relations_dont_exist() {
+        let (repository, seeds) = setup_repository().await;
+        let create_new_template = |doctor_id, drug_id| {
+            NewPrescriptionTemplate::new(
+                doctor_id,
+                "Flu".into(),
+                vec![NewPrescribedDrug {
+                    drug_id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+            )
+            .unwrap()
+        };
+
+        let nonexistent_doctor_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .create_prescription_template(create_new_template(
+                    nonexistent_doctor_id,
+                    seeds.drugs[0].id
+                ))
+                .await,
+            Err(CreatePrescriptionTemplateRepositoryError::DoctorNotFound(
+                nonexistent_doctor_id
+            ))
+        );
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .create_prescription_template(create_new_template(
+                    seeds.doctor.id,
+                    nonexistent_drug_id
+                ))
+                .await,
+            Err(CreatePrescriptionTemplateRepositoryError::DrugNotFound(
+                nonexistent_drug_id
             ))
         );
     }
@@ -1,17 +1,47 @@
-use chrono::{DateTime, Utc};
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use super::{
-    entities::{NewPrescribedDrug, NewPrescription, Prescription, PrescriptionType},
+    entities::{
+        AffectedPatient, DailyFillCount, DoctorPrescriptionCount, DrugHistoryEntry, DrugUsage,
+        NewPrescribedDrug, NewPrescription, NewPrescriptionTemplate, PharmacistFillStats,
+        Prescription, PrescriptionFill, PrescriptionFillNotification, PrescriptionTemplate,
+        PrescriptionType, PrescriptionVerification,
+    },
+    fill_notifier::PrescriptionFillNotifier,
+    policy::PrescriptionPolicy,
+    prescription_code::PrescriptionCode,
     repository::{
-        CreatePrescriptionRepositoryError, FillPrescriptionRepositoryError,
-        GetPrescriptionByIdRepositoryError, GetPrescriptionsRepositoryError,
-        PrescriptionsRepository,
+        CancelPrescriptionRepositoryError, CreatePrescriptionRepositoryError,
+        CreatePrescriptionTemplateRepositoryError, DeletePrescriptionTemplateRepositoryError,
+        FillPrescriptionRepositoryError, GetDrugUsageRepositoryError,
+        GetPharmacistFillStatsRepositoryError, GetPrescriptionByIdRepositoryError,
+        GetPrescriptionTemplateByIdRepositoryError, GetPrescriptionTemplatesRepositoryError,
+        GetPrescriptionsRepositoryError, PrescriptionsRepository,
+        ReassignPrescriptionPatientRepositoryError, UpdatePrescriptionTemplateRepositoryError,
     },
 };
+use crate::domain::{
+    drugs::entities::Drug,
+    patients::entities::get_age_from_pesel_number,
+    utils::clock::{Clock, SystemClock},
+};
+
+// Subscribers lagging behind by more than this many prescriptions miss the oldest ones
+const CREATED_PRESCRIPTIONS_CHANNEL_CAPACITY: usize = 100;
+
+// Fill stats list every day of the range, so it's bounded to keep the response small
+pub const MAX_FILL_STATS_DAYS: i64 = 366;
 
 pub struct PrescriptionsService {
     repository: Box<dyn PrescriptionsRepository>,
+    policy: PrescriptionPolicy,
+    created_prescriptions: broadcast::Sender<Prescription>,
+    fill_notifier: Option<Arc<dyn PrescriptionFillNotifier>>,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug)]
@@ -27,18 +57,202 @@ pub enum GetPrescriptionByIdError {
 
 #[derive(Debug)]
 pub enum GetPrescriptionsWithPaginationError {
+    DomainError(String),
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetPrescriptionsExpiringBeforeError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetActivePrescriptionsForPatientError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetPrescriptionsForPatientError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum SearchPrescriptionsError {
+    DomainError(String),
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetDrugHistoryForPatientError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum CountPrescriptionsByTypeError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum CountPrescriptionsPerDoctorError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetUnprescribedDrugsError {
+    RepositoryError(GetPrescriptionsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetPrescriptionsByIdsError {
     RepositoryError(GetPrescriptionsRepositoryError),
 }
 
+#[derive(Debug)]
+pub enum CountFillsByPharmacistError {
+    DomainError(String),
+    RepositoryError(GetPharmacistFillStatsRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum CountPrescriptionsForDrugError {
+    RepositoryError(GetDrugUsageRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetPatientsPrescribedDrugError {
+    RepositoryError(GetDrugUsageRepositoryError),
+}
+
 #[derive(Debug, PartialEq)]
 pub enum FillPrescriptionError {
     DomainError(String),
     RepositoryError(FillPrescriptionRepositoryError),
 }
 
+#[derive(Debug, PartialEq)]
+pub enum FillPrescriptionsBatchError {
+    DomainError(String),
+    ItemError(usize, FillPrescriptionError),
+    RepositoryError(FillPrescriptionRepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReassignPrescriptionPatientError {
+    DomainError(String),
+    NotPrescribingDoctor,
+    RepositoryError(ReassignPrescriptionPatientRepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CancelPrescriptionError {
+    DomainError(String),
+    NotPrescribingDoctor,
+    RepositoryError(CancelPrescriptionRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum CreatePrescriptionTemplateError {
+    DomainError(String),
+    RepositoryError(CreatePrescriptionTemplateRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum GetPrescriptionTemplatesError {
+    RepositoryError(GetPrescriptionTemplatesRepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpdatePrescriptionTemplateError {
+    DomainError(String),
+    NotTemplateOwner,
+    RepositoryError(UpdatePrescriptionTemplateRepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeletePrescriptionTemplateError {
+    NotTemplateOwner,
+    RepositoryError(DeletePrescriptionTemplateRepositoryError),
+}
+
+#[derive(Debug)]
+pub enum CreatePrescriptionFromTemplateError {
+    NotTemplateOwner,
+    TemplateRepositoryError(GetPrescriptionTemplateByIdRepositoryError),
+    CreatePrescriptionError(CreatePrescriptionError),
+}
+
+fn validate_created_range(
+    created_from: Option<DateTime<Utc>>,
+    created_to: Option<DateTime<Utc>>,
+) -> Result<(), GetPrescriptionsWithPaginationError> {
+    match (created_from, created_to) {
+        (Some(from), Some(to)) if from > to => {
+            Err(GetPrescriptionsWithPaginationError::DomainError(
+                "created_from can't be later than created_to".into(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
 impl PrescriptionsService {
-    pub fn new(repository: Box<dyn PrescriptionsRepository>) -> Self {
-        Self { repository }
+    pub fn new(repository: Box<dyn PrescriptionsRepository>, policy: PrescriptionPolicy) -> Self {
+        let (created_prescriptions, _) = broadcast::channel(CREATED_PRESCRIPTIONS_CHANNEL_CAPACITY);
+
+        Self {
+            repository,
+            policy,
+            created_prescriptions,
+            fill_notifier: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Fills aren't announced to anyone without a notifier
+    pub fn with_fill_notifier(
+        mut self,
+        fill_notifier: Option<Arc<dyn PrescriptionFillNotifier>>,
+    ) -> Self {
+        self.fill_notifier = fill_notifier;
+        self
+    }
+
+    // Sent in the background, so that a slow or unavailable receiver doesn't delay the fill
+    fn notify_fills(&self, notifications: Vec<PrescriptionFillNotification>) {
+        if let Some(fill_notifier) = self.fill_notifier.clone() {
+            tokio::spawn(async move {
+                for notification in notifications {
+                    if let Err(err) = fill_notifier.notify_fill(&notification).await {
+                        tracing::warn!(
+                            prescription_id = %notification.prescription_id,
+                            error = %err,
+                            "Prescription fill notification not delivered"
+                        );
+                    }
+                }
+            });
+        }
+    }
+
+    fn set_fillable_now(&self, prescription: &mut Prescription) {
+        prescription.fillable_now = prescription.is_fillable_at(self.clock.now());
+    }
+
+    // Prescriptions returned together are checked against the same moment
+    fn set_all_fillable_now(&self, prescriptions: &mut [Prescription]) {
+        let now = self.clock.now();
+        for prescription in prescriptions {
+            prescription.fillable_now = prescription.is_fillable_at(now);
+        }
+    }
+
+    pub fn subscribe_to_created_prescriptions(&self) -> broadcast::Receiver<Prescription> {
+        self.created_prescriptions.subscribe()
     }
 
     pub async fn create_prescription(
@@ -49,32 +263,202 @@ impl PrescriptionsService {
         prescription_type: Option<PrescriptionType>,
         prescribed_drugs: Vec<(Uuid, u32)>,
     ) -> Result<Prescription, CreatePrescriptionError> {
-        let new_prescription = NewPrescription::new(
+        self.create_prescription_with_diagnoses(
             doctor_id,
             patient_id,
             start_date,
             prescription_type,
-            prescribed_drugs
-                .iter()
-                .map(|&(drug_id, quantity)| NewPrescribedDrug { drug_id, quantity })
-                .collect(),
+            prescribed_drugs,
+            vec![],
+            false,
         )
-        .map_err(|err| CreatePrescriptionError::DomainError(err.to_string()))?;
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_prescription_with_diagnoses(
+        &self,
+        doctor_id: Uuid,
+        patient_id: Uuid,
+        start_date: Option<DateTime<Utc>>,
+        prescription_type: Option<PrescriptionType>,
+        prescribed_drugs: Vec<(Uuid, u32)>,
+        diagnoses: Vec<String>,
+        window_from_fill: bool,
+    ) -> Result<Prescription, CreatePrescriptionError> {
+        let new_prescription = self
+            .validate_new_prescription(
+                doctor_id,
+                patient_id,
+                start_date,
+                prescription_type,
+                prescribed_drugs,
+                diagnoses,
+                window_from_fill,
+            )
+            .await?;
 
-        let created_prescription = self
+        let mut created_prescription = self
             .repository
             .create_prescription(new_prescription)
             .await
             .map_err(|err| CreatePrescriptionError::RepositoryError(err))?;
+        self.set_fillable_now(&mut created_prescription);
+
+        // Sending fails only when nobody is subscribed, which is fine
+        let _ = self
+            .created_prescriptions
+            .send(created_prescription.clone());
 
         Ok(created_prescription)
     }
 
+    // Runs the same validation as create_prescription_with_diagnoses and returns the prescription
+    // it would create, nothing is saved or broadcast
+    #[allow(clippy::too_many_arguments)]
+    pub async fn preview_prescription_with_diagnoses(
+        &self,
+        doctor_id: Uuid,
+        patient_id: Uuid,
+        start_date: Option<DateTime<Utc>>,
+        prescription_type: Option<PrescriptionType>,
+        prescribed_drugs: Vec<(Uuid, u32)>,
+        diagnoses: Vec<String>,
+        window_from_fill: bool,
+    ) -> Result<Prescription, CreatePrescriptionError> {
+        let new_prescription = self
+            .validate_new_prescription(
+                doctor_id,
+                patient_id,
+                start_date,
+                prescription_type,
+                prescribed_drugs,
+                diagnoses,
+                window_from_fill,
+            )
+            .await?;
+
+        let mut prescription = self
+            .repository
+            .preview_prescription(new_prescription)
+            .await
+            .map_err(CreatePrescriptionError::RepositoryError)?;
+        self.set_fillable_now(&mut prescription);
+
+        Ok(prescription)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn validate_new_prescription(
+        &self,
+        doctor_id: Uuid,
+        patient_id: Uuid,
+        start_date: Option<DateTime<Utc>>,
+        prescription_type: Option<PrescriptionType>,
+        prescribed_drugs: Vec<(Uuid, u32)>,
+        diagnoses: Vec<String>,
+        window_from_fill: bool,
+    ) -> Result<NewPrescription, CreatePrescriptionError> {
+        let new_prescription = NewPrescription::new_with_policy(
+            doctor_id,
+            patient_id,
+            start_date,
+            prescription_type,
+            to_new_prescribed_drugs(prescribed_drugs),
+            &self.policy,
+            self.clock.now(),
+        )
+        .and_then(|new_prescription| new_prescription.with_diagnoses(diagnoses))
+        .map(|new_prescription| new_prescription.with_window_from_fill(window_from_fill))
+        .map_err(|err| CreatePrescriptionError::DomainError(err.to_string()))?;
+
+        let drug_ids: Vec<Uuid> = new_prescription
+            .prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| prescribed_drug.drug_id)
+            .collect();
+
+        let checks_self_prescription = !self
+            .policy
+            .self_prescription_restricted_categories
+            .is_empty();
+        if self.policy.forbid_mixed_drug_categories || checks_self_prescription {
+            let drug_categories = self
+                .repository
+                .get_drug_categories(drug_ids.clone())
+                .await
+                .map_err(CreatePrescriptionError::RepositoryError)?;
+            if self.policy.forbid_mixed_drug_categories {
+                new_prescription
+                    .check_drug_categories(&drug_categories)
+                    .map_err(|err| CreatePrescriptionError::DomainError(err.to_string()))?;
+            }
+            if checks_self_prescription {
+                // Missing doctors and patients are left for the repository to reject
+                let doctor_pesel_number = self
+                    .repository
+                    .get_doctor_pesel_number(new_prescription.doctor_id)
+                    .await
+                    .map_err(CreatePrescriptionError::RepositoryError)?;
+                let patient_pesel_number = self
+                    .repository
+                    .get_patient_pesel_number(new_prescription.patient_id)
+                    .await
+                    .map_err(CreatePrescriptionError::RepositoryError)?;
+                if let (Some(doctor_pesel_number), Some(patient_pesel_number)) =
+                    (doctor_pesel_number, patient_pesel_number)
+                {
+                    new_prescription
+                        .check_self_prescription(
+                            &doctor_pesel_number,
+                            &patient_pesel_number,
+                            &drug_categories,
+                            &self.policy,
+                        )
+                        .map_err(|err| CreatePrescriptionError::DomainError(err.to_string()))?;
+                }
+            }
+        }
+
+        if self
+            .policy
+            .adult_only_prescription_types
+            .contains(&new_prescription.prescription_type)
+        {
+            // Missing patients are left for the repository to reject
+            if let Some(pesel_number) = self
+                .repository
+                .get_patient_pesel_number(new_prescription.patient_id)
+                .await
+                .map_err(CreatePrescriptionError::RepositoryError)?
+            {
+                new_prescription
+                    .check_patient_age(
+                        get_age_from_pesel_number(&pesel_number, self.clock.now()),
+                        &self.policy,
+                    )
+                    .map_err(|err| CreatePrescriptionError::DomainError(err.to_string()))?;
+            }
+        }
+
+        let drug_restrictions = self
+            .repository
+            .get_drug_prescription_type_restrictions(drug_ids)
+            .await
+            .map_err(CreatePrescriptionError::RepositoryError)?;
+        new_prescription
+            .check_drug_prescription_types(&drug_restrictions)
+            .map_err(|err| CreatePrescriptionError::DomainError(err.to_string()))?;
+
+        Ok(new_prescription)
+    }
+
     pub async fn fill_prescription(
         &self,
         prescription_id: Uuid,
         pharmacist_id: Uuid,
-        prescription_code: String,
+        prescription_code: PrescriptionCode,
+        dispensed_drugs: Option<Vec<(Uuid, u32)>>,
     ) -> Result<Prescription, FillPrescriptionError> {
         let mut prescription = self
             .repository
@@ -86,378 +470,2165 @@ impl PrescriptionsService {
                         FillPrescriptionRepositoryError::PrescriptionNotFound(id),
                     )
                 }
-                _ => FillPrescriptionError::RepositoryError(
-                    FillPrescriptionRepositoryError::DatabaseError(err.to_string()),
-                ),
+                GetPrescriptionByIdRepositoryError::DatabaseError(message) => {
+                    FillPrescriptionError::RepositoryError(
+                        FillPrescriptionRepositoryError::DatabaseError(message),
+                    )
+                }
+                GetPrescriptionByIdRepositoryError::Unavailable => {
+                    FillPrescriptionError::RepositoryError(
+                        FillPrescriptionRepositoryError::Unavailable,
+                    )
+                }
             })?;
 
         let new_prescription_fill = prescription
-            .fill(pharmacist_id, prescription_code)
+            .fill(
+                pharmacist_id,
+                &prescription_code,
+                dispensed_drugs.map(to_new_prescribed_drugs),
+                self.clock.now(),
+            )
             .map_err(|err| FillPrescriptionError::DomainError(err.to_string()))?;
 
         let prescription_fill = self
             .repository
-            .fill_prescription(new_prescription_fill)
+            .fill_prescription(new_prescription_fill.clone())
             .await
             .map_err(|err| FillPrescriptionError::RepositoryError(err))?;
-        prescription.fill = Some(prescription_fill);
+        prescription.record_fill(&new_prescription_fill, prescription_fill);
+        self.set_fillable_now(&mut prescription);
+        self.notify_fills(vec![to_fill_notification(
+            &prescription,
+            &prescription_fill,
+        )]);
 
         Ok(prescription)
     }
 
-    pub async fn get_prescription_by_id(
+    pub async fn fill_prescriptions(
+        &self,
+        pharmacist_id: Uuid,
+        prescription_ids_and_codes: Vec<(Uuid, PrescriptionCode)>,
+    ) -> Result<Vec<Prescription>, FillPrescriptionsBatchError> {
+        if prescription_ids_and_codes.is_empty() {
+            Err(FillPrescriptionsBatchError::DomainError(
+                "Batch must contain at least one prescription".into(),
+            ))?;
+        }
+
+        // All prescriptions of the batch are checked against the same moment
+        let now = self.clock.now();
+        let mut prescriptions = vec![];
+        let mut new_prescription_fills = vec![];
+        for (index, (prescription_id, prescription_code)) in
+            prescription_ids_and_codes.into_iter().enumerate()
+        {
+            if prescriptions
+                .iter()
+                .any(|prescription: &Prescription| prescription.id == prescription_id)
+            {
+                Err(FillPrescriptionsBatchError::ItemError(
+                    index,
+                    FillPrescriptionError::DomainError(
+                        "Prescription appears in the batch more than once".into(),
+                    ),
+                ))?;
+            }
+
+            let prescription = self
+                .repository
+                .get_prescription_by_id(prescription_id)
+                .await
+                .map_err(|err| match err {
+                    GetPrescriptionByIdRepositoryError::NotFound(id) => {
+                        FillPrescriptionsBatchError::ItemError(
+                            index,
+                            FillPrescriptionError::RepositoryError(
+                                FillPrescriptionRepositoryError::PrescriptionNotFound(id),
+                            ),
+                        )
+                    }
+                    GetPrescriptionByIdRepositoryError::DatabaseError(message) => {
+                        FillPrescriptionsBatchError::RepositoryError(
+                            FillPrescriptionRepositoryError::DatabaseError(message),
+                        )
+                    }
+                    GetPrescriptionByIdRepositoryError::Unavailable => {
+                        FillPrescriptionsBatchError::RepositoryError(
+                            FillPrescriptionRepositoryError::Unavailable,
+                        )
+                    }
+                })?;
+
+            let new_prescription_fill = prescription
+                .fill(pharmacist_id, &prescription_code, None, now)
+                .map_err(|err| {
+                    FillPrescriptionsBatchError::ItemError(
+                        index,
+                        FillPrescriptionError::DomainError(err.to_string()),
+                    )
+                })?;
+
+            prescriptions.push(prescription);
+            new_prescription_fills.push(new_prescription_fill);
+        }
+
+        let prescription_fills = self
+            .repository
+            .fill_prescriptions(new_prescription_fills.clone())
+            .await
+            .map_err(|err| {
+                let failed_index = match err {
+                    FillPrescriptionRepositoryError::PrescriptionNotFound(id)
+                    | FillPrescriptionRepositoryError::QuantityExceeded(id) => prescriptions
+                        .iter()
+                        .position(|prescription| prescription.id == id),
+                    _ => None,
+                };
+                match failed_index {
+                    Some(index) => FillPrescriptionsBatchError::ItemError(
+                        index,
+                        FillPrescriptionError::RepositoryError(err),
+                    ),
+                    None => FillPrescriptionsBatchError::RepositoryError(err),
+                }
+            })?;
+
+        let mut notifications = vec![];
+        for ((prescription, new_prescription_fill), prescription_fill) in prescriptions
+            .iter_mut()
+            .zip(&new_prescription_fills)
+            .zip(prescription_fills)
+        {
+            prescription.record_fill(new_prescription_fill, prescription_fill);
+            notifications.push(to_fill_notification(prescription, &prescription_fill));
+        }
+        self.notify_fills(notifications);
+        self.set_all_fillable_now(&mut prescriptions);
+
+        Ok(prescriptions)
+    }
+
+    pub async fn reassign_prescription_patient(
         &self,
         prescription_id: Uuid,
-    ) -> Result<Prescription, GetPrescriptionByIdError> {
+        new_patient_id: Uuid,
+        doctor_id: Uuid,
+    ) -> Result<Prescription, ReassignPrescriptionPatientError> {
         let prescription = self
             .repository
             .get_prescription_by_id(prescription_id)
             .await
-            .map_err(|err| GetPrescriptionByIdError::RepositoryError(err))?;
+            .map_err(|err| match err {
+                GetPrescriptionByIdRepositoryError::NotFound(id) => {
+                    ReassignPrescriptionPatientError::RepositoryError(
+                        ReassignPrescriptionPatientRepositoryError::PrescriptionNotFound(id),
+                    )
+                }
+                GetPrescriptionByIdRepositoryError::DatabaseError(message) => {
+                    ReassignPrescriptionPatientError::RepositoryError(
+                        ReassignPrescriptionPatientRepositoryError::DatabaseError(message),
+                    )
+                }
+                GetPrescriptionByIdRepositoryError::Unavailable => {
+                    ReassignPrescriptionPatientError::RepositoryError(
+                        ReassignPrescriptionPatientRepositoryError::Unavailable,
+                    )
+                }
+            })?;
 
-        Ok(prescription)
+        if prescription.doctor.id != doctor_id {
+            Err(ReassignPrescriptionPatientError::NotPrescribingDoctor)?;
+        }
+        if !prescription.fills.is_empty() {
+            Err(ReassignPrescriptionPatientError::DomainError(
+                "Filled prescription can't be reassigned to another patient".into(),
+            ))?;
+        }
+
+        let mut reassigned_prescription = self
+            .repository
+            .reassign_prescription_patient(prescription_id, new_patient_id)
+            .await
+            .map_err(ReassignPrescriptionPatientError::RepositoryError)?;
+        self.set_fillable_now(&mut reassigned_prescription);
+
+        Ok(reassigned_prescription)
     }
 
-    pub async fn get_prescriptions_with_pagination(
+    // Cancelled prescriptions stay in the database, so the audit trail keeps them
+    pub async fn cancel_prescription(
         &self,
-        page: Option<i64>,
-        page_size: Option<i64>,
-    ) -> Result<Vec<Prescription>, GetPrescriptionsWithPaginationError> {
-        let result = self
+        prescription_id: Uuid,
+        doctor_id: Uuid,
+    ) -> Result<Prescription, CancelPrescriptionError> {
+        let prescription = self
             .repository
-            .get_prescriptions(page, page_size)
+            .get_prescription_by_id(prescription_id)
             .await
-            .map_err(|err| GetPrescriptionsWithPaginationError::RepositoryError(err))?;
+            .map_err(|err| match err {
+                GetPrescriptionByIdRepositoryError::NotFound(id) => {
+                    CancelPrescriptionError::RepositoryError(
+                        CancelPrescriptionRepositoryError::PrescriptionNotFound(id),
+                    )
+                }
+                GetPrescriptionByIdRepositoryError::DatabaseError(message) => {
+                    CancelPrescriptionError::RepositoryError(
+                        CancelPrescriptionRepositoryError::DatabaseError(message),
+                    )
+                }
+                GetPrescriptionByIdRepositoryError::Unavailable => {
+                    CancelPrescriptionError::RepositoryError(
+                        CancelPrescriptionRepositoryError::Unavailable,
+                    )
+                }
+            })?;
 
-        Ok(result)
-    }
-}
+        if prescription.doctor.id != doctor_id {
+            Err(CancelPrescriptionError::NotPrescribingDoctor)?;
+        }
+        if prescription.cancelled_at.is_some() {
+            Err(CancelPrescriptionError::DomainError(
+                "Prescription is already cancelled".into(),
+            ))?;
+        }
+        if !prescription.fills.is_empty() {
+            Err(CancelPrescriptionError::DomainError(
+                "Filled prescription can't be cancelled".into(),
+            ))?;
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::{FillPrescriptionError, PrescriptionsService};
-    use crate::domain::{
-        doctors::{entities::Doctor, repository::DoctorsRepositoryFake, service::DoctorsService},
-        drugs::{
-            entities::{Drug, DrugContentType},
-            repository::DrugsRepositoryFake,
-            service::DrugsService,
-        },
-        patients::{
-            entities::Patient, repository::PatientsRepositoryFake, service::PatientsService,
-        },
-        pharmacists::{
-            entities::Pharmacist, repository::PharmacistsRepositoryFake,
-            service::PharmacistsService,
-        },
-        prescriptions::{entities::PrescriptionType, repository::PrescriptionsRepositoryFake},
-    };
+        let mut cancelled_prescription = self
+            .repository
+            .cancel_prescription(prescription_id)
+            .await
+            .map_err(CancelPrescriptionError::RepositoryError)?;
+        self.set_fillable_now(&mut cancelled_prescription);
 
-    struct DatabaseSeeds {
-        doctor: Doctor,
-        pharmacist: Pharmacist,
-        patient: Patient,
-        drugs: Vec<Drug>,
+        Ok(cancelled_prescription)
     }
 
-    async fn setup_services_and_seed_database() -> (PrescriptionsService, DatabaseSeeds) {
-        let doctors_service = DoctorsService::new(Box::new(DoctorsRepositoryFake::new()));
-        let created_doctor = doctors_service
-            .create_doctor("John Doctor".into(), "92022900002".into(), "3123456".into())
-            .await
-            .unwrap();
+    pub async fn create_prescription_template(
+        &self,
+        doctor_id: Uuid,
+        name: String,
+        prescribed_drugs: Vec<(Uuid, u32)>,
+    ) -> Result<PrescriptionTemplate, CreatePrescriptionTemplateError> {
+        let new_template = NewPrescriptionTemplate::new(
+            doctor_id,
+            name,
+            to_new_prescribed_drugs(prescribed_drugs),
+            &self.policy,
+        )
+        .map_err(|err| CreatePrescriptionTemplateError::DomainError(err.to_string()))?;
 
-        let pharmacist_service =
-            PharmacistsService::new(Box::new(PharmacistsRepositoryFake::new()));
-        let created_pharmacist = pharmacist_service
-            .create_pharmacist("John Pharmacist".into(), "92022900002".into())
+        let created_template = self
+            .repository
+            .create_prescription_template(new_template)
             .await
-            .unwrap();
+            .map_err(CreatePrescriptionTemplateError::RepositoryError)?;
 
-        let patients_service = PatientsService::new(Box::new(PatientsRepositoryFake::new()));
-        let created_patient = patients_service
-            .create_patient("John Patient".into(), "92022900002".into())
+        Ok(created_template)
+    }
+
+    pub async fn get_prescription_templates_for_doctor(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<PrescriptionTemplate>, GetPrescriptionTemplatesError> {
+        let templates = self
+            .repository
+            .get_prescription_templates_for_doctor(doctor_id)
+            .await
+            .map_err(GetPrescriptionTemplatesError::RepositoryError)?;
+
+        Ok(templates)
+    }
+
+    pub async fn update_prescription_template(
+        &self,
+        template_id: Uuid,
+        doctor_id: Uuid,
+        name: String,
+        prescribed_drugs: Vec<(Uuid, u32)>,
+    ) -> Result<PrescriptionTemplate, UpdatePrescriptionTemplateError> {
+        let template = self
+            .repository
+            .get_prescription_template_by_id(template_id)
+            .await
+            .map_err(|err| match err {
+                GetPrescriptionTemplateByIdRepositoryError::NotFound(id) => {
+                    UpdatePrescriptionTemplateError::RepositoryError(
+                        UpdatePrescriptionTemplateRepositoryError::NotFound(id),
+                    )
+                }
+                GetPrescriptionTemplateByIdRepositoryError::DatabaseError(message) => {
+                    UpdatePrescriptionTemplateError::RepositoryError(
+                        UpdatePrescriptionTemplateRepositoryError::DatabaseError(message),
+                    )
+                }
+                GetPrescriptionTemplateByIdRepositoryError::Unavailable => {
+                    UpdatePrescriptionTemplateError::RepositoryError(
+                        UpdatePrescriptionTemplateRepositoryError::Unavailable,
+                    )
+                }
+            })?;
+
+        if template.doctor_id != doctor_id {
+            Err(UpdatePrescriptionTemplateError::NotTemplateOwner)?;
+        }
+
+        let new_template = NewPrescriptionTemplate::new(
+            doctor_id,
+            name,
+            to_new_prescribed_drugs(prescribed_drugs),
+            &self.policy,
+        )
+        .map_err(|err| UpdatePrescriptionTemplateError::DomainError(err.to_string()))?;
+
+        let updated_template = self
+            .repository
+            .update_prescription_template(NewPrescriptionTemplate {
+                id: template_id,
+                ..new_template
+            })
+            .await
+            .map_err(UpdatePrescriptionTemplateError::RepositoryError)?;
+
+        Ok(updated_template)
+    }
+
+    pub async fn delete_prescription_template(
+        &self,
+        template_id: Uuid,
+        doctor_id: Uuid,
+    ) -> Result<(), DeletePrescriptionTemplateError> {
+        let template = self
+            .repository
+            .get_prescription_template_by_id(template_id)
+            .await
+            .map_err(|err| match err {
+                GetPrescriptionTemplateByIdRepositoryError::NotFound(id) => {
+                    DeletePrescriptionTemplateError::RepositoryError(
+                        DeletePrescriptionTemplateRepositoryError::NotFound(id),
+                    )
+                }
+                GetPrescriptionTemplateByIdRepositoryError::DatabaseError(message) => {
+                    DeletePrescriptionTemplateError::RepositoryError(
+                        DeletePrescriptionTemplateRepositoryError::DatabaseError(message),
+                    )
+                }
+                GetPrescriptionTemplateByIdRepositoryError::Unavailable => {
+                    DeletePrescriptionTemplateError::RepositoryError(
+                        DeletePrescriptionTemplateRepositoryError::Unavailable,
+                    )
+                }
+            })?;
+
+        if template.doctor_id != doctor_id {
+            Err(DeletePrescriptionTemplateError::NotTemplateOwner)?;
+        }
+
+        self.repository
+            .delete_prescription_template(template_id)
+            .await
+            .map_err(DeletePrescriptionTemplateError::RepositoryError)?;
+
+        Ok(())
+    }
+
+    pub async fn create_prescription_from_template(
+        &self,
+        template_id: Uuid,
+        doctor_id: Uuid,
+        patient_id: Uuid,
+    ) -> Result<Prescription, CreatePrescriptionFromTemplateError> {
+        let template = self
+            .repository
+            .get_prescription_template_by_id(template_id)
+            .await
+            .map_err(CreatePrescriptionFromTemplateError::TemplateRepositoryError)?;
+
+        if template.doctor_id != doctor_id {
+            Err(CreatePrescriptionFromTemplateError::NotTemplateOwner)?;
+        }
+
+        let prescribed_drugs = template
+            .prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| (prescribed_drug.drug_id, prescribed_drug.quantity as u32))
+            .collect();
+
+        let prescription = self
+            .create_prescription(doctor_id, patient_id, None, None, prescribed_drugs)
+            .await
+            .map_err(CreatePrescriptionFromTemplateError::CreatePrescriptionError)?;
+
+        Ok(prescription)
+    }
+
+    pub async fn get_prescription_by_id(
+        &self,
+        prescription_id: Uuid,
+    ) -> Result<Prescription, GetPrescriptionByIdError> {
+        let mut prescription = self
+            .repository
+            .get_prescription_by_id(prescription_id)
+            .await
+            .map_err(|err| GetPrescriptionByIdError::RepositoryError(err))?;
+        self.set_fillable_now(&mut prescription);
+
+        Ok(prescription)
+    }
+
+    // Runs the same checks as filling the prescription, without saving anything
+    pub async fn verify_prescription(
+        &self,
+        prescription_id: Uuid,
+        prescription_code: PrescriptionCode,
+    ) -> Result<PrescriptionVerification, GetPrescriptionByIdError> {
+        let mut prescription = self
+            .repository
+            .get_prescription_by_id(prescription_id)
+            .await
+            .map_err(GetPrescriptionByIdError::RepositoryError)?;
+        let verification = prescription.verify(&prescription_code, self.clock.now());
+        self.set_fillable_now(&mut prescription);
+
+        Ok(PrescriptionVerification {
+            prescription,
+            verification,
+        })
+    }
+
+    pub async fn get_prescriptions_by_ids(
+        &self,
+        prescription_ids: &[Uuid],
+    ) -> Result<Vec<Prescription>, GetPrescriptionsByIdsError> {
+        let mut prescriptions = self
+            .repository
+            .get_prescriptions_by_ids(prescription_ids)
+            .await
+            .map_err(GetPrescriptionsByIdsError::RepositoryError)?;
+        self.set_all_fillable_now(&mut prescriptions);
+
+        Ok(prescriptions)
+    }
+
+    pub async fn get_prescriptions_with_pagination(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsWithPaginationError> {
+        validate_created_range(created_from, created_to)?;
+
+        let mut result = self
+            .repository
+            .get_prescriptions(page, page_size, filled, created_from, created_to)
+            .await
+            .map_err(|err| GetPrescriptionsWithPaginationError::RepositoryError(err))?;
+        self.set_all_fillable_now(&mut result);
+
+        Ok(result)
+    }
+
+    pub async fn get_prescription_ids_with_pagination(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Uuid>, GetPrescriptionsWithPaginationError> {
+        validate_created_range(created_from, created_to)?;
+
+        let prescription_ids = self
+            .repository
+            .get_prescription_ids(page, page_size, filled, created_from, created_to)
+            .await
+            .map_err(GetPrescriptionsWithPaginationError::RepositoryError)?;
+
+        Ok(prescription_ids)
+    }
+
+    pub async fn get_prescriptions_expiring_before(
+        &self,
+        patient_id: Uuid,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsExpiringBeforeError> {
+        let mut prescriptions = self
+            .repository
+            .get_prescriptions_expiring_before(patient_id, before)
+            .await
+            .map_err(GetPrescriptionsExpiringBeforeError::RepositoryError)?;
+        self.set_all_fillable_now(&mut prescriptions);
+
+        Ok(prescriptions)
+    }
+
+    pub async fn get_active_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetActivePrescriptionsForPatientError> {
+        let mut prescriptions = self
+            .repository
+            .get_active_prescriptions_for_patient(patient_id, now)
+            .await
+            .map_err(GetActivePrescriptionsForPatientError::RepositoryError)?;
+        self.set_all_fillable_now(&mut prescriptions);
+
+        Ok(prescriptions)
+    }
+
+    pub async fn get_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsForPatientError> {
+        let mut prescriptions = self
+            .repository
+            .get_prescriptions_for_patient(patient_id, page, page_size)
+            .await
+            .map_err(GetPrescriptionsForPatientError::RepositoryError)?;
+        self.set_all_fillable_now(&mut prescriptions);
+
+        Ok(prescriptions)
+    }
+
+    pub async fn search_prescriptions(
+        &self,
+        query: &str,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, SearchPrescriptionsError> {
+        let query = query.trim();
+        if query.is_empty() {
+            Err(SearchPrescriptionsError::DomainError(
+                "Search query can't be empty".into(),
+            ))?;
+        }
+
+        let mut prescriptions = self
+            .repository
+            .search_prescriptions(query, page, page_size)
+            .await
+            .map_err(SearchPrescriptionsError::RepositoryError)?;
+        self.set_all_fillable_now(&mut prescriptions);
+
+        Ok(prescriptions)
+    }
+
+    // Durations come from the configured policy, so they match the end dates of new prescriptions
+    pub fn get_prescription_durations(&self) -> HashMap<PrescriptionType, i64> {
+        self.policy.get_durations_in_days()
+    }
+
+    pub async fn count_prescriptions_by_type(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<PrescriptionType, i64>, CountPrescriptionsByTypeError> {
+        let counts = self
+            .repository
+            .count_prescriptions_by_type(from, to)
+            .await
+            .map_err(CountPrescriptionsByTypeError::RepositoryError)?;
+
+        Ok(counts)
+    }
+
+    pub async fn count_prescriptions_per_doctor(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DoctorPrescriptionCount>, CountPrescriptionsPerDoctorError> {
+        let counts = self
+            .repository
+            .count_prescriptions_per_doctor(from, to, page, page_size)
+            .await
+            .map_err(CountPrescriptionsPerDoctorError::RepositoryError)?;
+
+        Ok(counts)
+    }
+
+    pub async fn get_unprescribed_drugs(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Drug>, GetUnprescribedDrugsError> {
+        let drugs = self
+            .repository
+            .get_unprescribed_drugs(page, page_size)
+            .await
+            .map_err(GetUnprescribedDrugsError::RepositoryError)?;
+
+        Ok(drugs)
+    }
+
+    pub async fn count_fills_by_pharmacist_per_day(
+        &self,
+        pharmacist_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<PharmacistFillStats, CountFillsByPharmacistError> {
+        if (to.date_naive() - from.date_naive()).num_days() >= MAX_FILL_STATS_DAYS {
+            Err(CountFillsByPharmacistError::DomainError(format!(
+                "Range can't span more than {} days",
+                MAX_FILL_STATS_DAYS
+            )))?;
+        }
+
+        let counts = self
+            .repository
+            .count_fills_by_pharmacist_per_day(pharmacist_id, from, to)
+            .await
+            .map_err(CountFillsByPharmacistError::RepositoryError)?;
+
+        Ok(PharmacistFillStats {
+            pharmacist_id,
+            days: to_daily_fill_counts(counts, from.date_naive(), to.date_naive()),
+        })
+    }
+
+    pub async fn count_prescriptions_for_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<DrugUsage, CountPrescriptionsForDrugError> {
+        let drug_usage = self
+            .repository
+            .count_prescriptions_for_drug(drug_id, from, to)
+            .await
+            .map_err(CountPrescriptionsForDrugError::RepositoryError)?;
+
+        Ok(drug_usage)
+    }
+
+    pub async fn get_patients_prescribed_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<AffectedPatient>, GetPatientsPrescribedDrugError> {
+        let affected_patients = self
+            .repository
+            .get_patients_prescribed_drug(drug_id, from, to)
+            .await
+            .map_err(GetPatientsPrescribedDrugError::RepositoryError)?;
+
+        Ok(affected_patients)
+    }
+
+    pub async fn get_drug_history_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DrugHistoryEntry>, GetDrugHistoryForPatientError> {
+        let drug_history = self
+            .repository
+            .get_drug_history_for_patient(patient_id, page, page_size)
+            .await
+            .map_err(GetDrugHistoryForPatientError::RepositoryError)?;
+
+        Ok(drug_history)
+    }
+}
+
+fn to_fill_notification(
+    prescription: &Prescription,
+    prescription_fill: &PrescriptionFill,
+) -> PrescriptionFillNotification {
+    PrescriptionFillNotification {
+        prescription_id: prescription.id,
+        code: prescription.code.clone(),
+        pharmacist_id: prescription_fill.pharmacist_id,
+        filled_at: prescription_fill.created_at,
+    }
+}
+
+// Every day from `first_day` to `last_day` inclusive, days without fills are counted as zero
+fn to_daily_fill_counts(
+    counts: HashMap<NaiveDate, i64>,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+) -> Vec<DailyFillCount> {
+    first_day
+        .iter_days()
+        .take_while(|date| *date <= last_day)
+        .map(|date| DailyFillCount {
+            date,
+            fills_count: counts.get(&date).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+fn to_new_prescribed_drugs(prescribed_drugs: Vec<(Uuid, u32)>) -> Vec<NewPrescribedDrug> {
+    prescribed_drugs
+        .into_iter()
+        .map(|(drug_id, quantity)| NewPrescribedDrug { drug_id, quantity })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use chrono::{Duration, Utc};
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::{
+        CancelPrescriptionError, CreatePrescriptionError, CreatePrescriptionFromTemplateError,
+        DeletePrescriptionTemplateError, FillPrescriptionError, FillPrescriptionsBatchError,
+        GetPrescriptionsWithPaginationError, PrescriptionsService,
+        ReassignPrescriptionPatientError, SearchPrescriptionsError,
+        UpdatePrescriptionTemplateError,
+    };
+    use crate::domain::{
+        doctors::{entities::Doctor, repository::DoctorsRepositoryFake, service::DoctorsService},
+        drugs::{
+            entities::{Drug, DrugCategory, DrugContentType},
+            repository::DrugsRepositoryFake,
+            service::DrugsService,
+        },
+        patients::{
+            entities::Patient, repository::PatientsRepositoryFake, service::PatientsService,
+        },
+        pharmacists::{
+            entities::Pharmacist, repository::PharmacistsRepositoryFake,
+            service::PharmacistsService,
+        },
+        prescriptions::{
+            entities::{
+                PrescriptionFillNotification, PrescriptionFillStatus, PrescriptionType,
+                PrescriptionVerificationStatus,
+            },
+            fill_notifier::{NotifyPrescriptionFillError, PrescriptionFillNotifier},
+            policy::PrescriptionPolicy,
+            repository::{PrescriptionsRepositoryFake, ReassignPrescriptionPatientRepositoryError},
+        },
+        utils::clock::{Clock, FixedClock},
+    };
+
+    struct DatabaseSeeds {
+        doctor: Doctor,
+        pharmacist: Pharmacist,
+        patient: Patient,
+        drugs: Vec<Drug>,
+    }
+
+    async fn setup_services_and_seed_database() -> (PrescriptionsService, DatabaseSeeds) {
+        setup_services_and_seed_database_with_policy(PrescriptionPolicy::default()).await
+    }
+
+    async fn setup_services_and_seed_database_with_policy(
+        policy: PrescriptionPolicy,
+    ) -> (PrescriptionsService, DatabaseSeeds) {
+        let doctors_service = DoctorsService::new(Box::new(DoctorsRepositoryFake::new()));
+        let created_doctor = doctors_service
+            .create_doctor(
+                "John Doctor".into(),
+                "92022900002".into(),
+                "3123456".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let pharmacist_service =
+            PharmacistsService::new(Box::new(PharmacistsRepositoryFake::new()));
+        let created_pharmacist = pharmacist_service
+            .create_pharmacist("John Pharmacist".into(), "92022900002".into())
+            .await
+            .unwrap();
+
+        let patients_service = PatientsService::new(Box::new(PatientsRepositoryFake::new()));
+        let created_patient = patients_service
+            .create_patient("John Patient".into(), "92022900002".into(), None)
+            .await
+            .unwrap();
+
+        let drugs_service = DrugsService::new(Box::new(DrugsRepositoryFake::new()));
+        let created_drug_0 = drugs_service
+            .create_drug(
+                "Gripex".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        let created_drug_1 = drugs_service
+            .create_drug(
+                "Apap".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        let created_drug_2 = drugs_service
+            .create_drug(
+                "Flegamax".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        let created_drug_3 = drugs_service
+            .create_drug(
+                "Rutinoscorbin".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        (
+            PrescriptionsService::new(
+                Box::new(PrescriptionsRepositoryFake::new(
+                    None,
+                    Some(vec![created_doctor.clone()]),
+                    Some(vec![created_patient.clone()]),
+                    Some(vec![created_pharmacist.clone()]),
+                    Some(vec![
+                        created_drug_0.clone(),
+                        created_drug_1.clone(),
+                        created_drug_2.clone(),
+                        created_drug_3.clone(),
+                    ]),
+                )),
+                policy,
+            ),
+            DatabaseSeeds {
+                doctor: created_doctor,
+                pharmacist: created_pharmacist,
+                patient: created_patient,
+                drugs: vec![
+                    created_drug_0,
+                    created_drug_1,
+                    created_drug_2,
+                    created_drug_3,
+                ],
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn publishes_created_prescriptions_to_subscribers() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let mut created_prescriptions = service.subscribe_to_created_prescriptions();
+
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            created_prescriptions.try_recv().unwrap(),
+            created_prescription
+        );
+        assert!(created_prescriptions.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn creates_and_reads_prescription_by_id() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            created_prescription.prescription_type,
+            PrescriptionType::ForChronicDiseaseDrugs
+        );
+        assert_eq!(created_prescription.prescribed_drugs.len(), 2);
+
+        let prescription_from_repository = service
+            .get_prescription_by_id(created_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(created_prescription, prescription_from_repository);
+    }
+
+    #[tokio::test]
+    async fn computes_end_date_from_injected_policy() {
+        let policy = PrescriptionPolicy {
+            for_chronic_disease_drugs_duration: Duration::days(180),
+            ..Default::default()
+        };
+        let (service, seeds) = setup_services_and_seed_database_with_policy(policy).await;
+
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            created_prescription.end_date - created_prescription.start_date,
+            Duration::days(180)
+        );
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_adult_only_prescription_for_minor() {
+        let policy = PrescriptionPolicy {
+            adult_only_prescription_types: vec![PrescriptionType::ForAntibiotics],
+            ..Default::default()
+        };
+        let (_, seeds) = setup_services_and_seed_database().await;
+        let minor = Patient {
+            id: Uuid::new_v4(),
+            pesel_number: "20251501237".into(),
+            ..seeds.patient.clone()
+        };
+        let service = PrescriptionsService::new(
+            Box::new(PrescriptionsRepositoryFake::new(
+                None,
+                Some(vec![seeds.doctor.clone()]),
+                Some(vec![seeds.patient.clone(), minor.clone()]),
+                None,
+                Some(seeds.drugs.clone()),
+            )),
+            policy,
+        );
+
+        assert!(matches!(
+            service
+                .create_prescription(
+                    seeds.doctor.id,
+                    minor.id,
+                    None,
+                    Some(PrescriptionType::ForAntibiotics),
+                    vec![(seeds.drugs[0].id, 1)],
+                )
+                .await,
+            Err(CreatePrescriptionError::DomainError(_))
+        ));
+        assert!(service
+            .create_prescription(
+                seeds.doctor.id,
+                minor.id,
+                None,
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await
+            .is_ok());
+        assert!(service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForAntibiotics),
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_prescription_of_restricted_drug_for_doctor_themselves() {
+        let policy = PrescriptionPolicy {
+            self_prescription_restricted_categories: vec![DrugCategory::Antibiotic],
+            ..Default::default()
+        };
+        let (service, seeds) = setup_service_with_antibiotic(policy).await;
+        assert_eq!(seeds.doctor.pesel_number, seeds.patient.pesel_number);
+
+        assert!(matches!(
+            service
+                .create_prescription(
+                    seeds.doctor.id,
+                    seeds.patient.id,
+                    None,
+                    Some(PrescriptionType::ForAntibiotics),
+                    vec![(seeds.drugs[0].id, 1)],
+                )
+                .await,
+            Err(CreatePrescriptionError::DomainError(_))
+        ));
+        assert!(service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[1].id, 1)],
+            )
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn creates_prescription_of_restricted_drug_for_other_patient() {
+        let policy = PrescriptionPolicy {
+            self_prescription_restricted_categories: vec![DrugCategory::Antibiotic],
+            ..Default::default()
+        };
+        let (_, mut seeds) = setup_service_with_antibiotic(PrescriptionPolicy::default()).await;
+        seeds.patient.pesel_number = "99031301347".into();
+        let service = PrescriptionsService::new(
+            Box::new(PrescriptionsRepositoryFake::new(
+                None,
+                Some(vec![seeds.doctor.clone()]),
+                Some(vec![seeds.patient.clone()]),
+                None,
+                Some(seeds.drugs.clone()),
+            )),
+            policy,
+        );
+
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForAntibiotics),
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await;
+
+        assert!(created_prescription.is_ok());
+    }
+
+    #[tokio::test]
+    async fn get_prescription_by_id_returns_error_if_prescription_doesnt_exist() {
+        let (service, _) = setup_services_and_seed_database().await;
+        let prescription_id = uuid::Uuid::new_v4();
+
+        let prescription = service.get_prescription_by_id(prescription_id).await;
+
+        assert!(prescription.is_err());
+    }
+
+    #[tokio::test]
+    async fn fills_prescription() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let seed_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+
+        let filled_prescription = service
+            .fill_prescription(
+                seed_prescription.id,
+                seeds.pharmacist.id,
+                seed_prescription.code,
+                None,
+            )
+            .await
+            .unwrap();
+        let fill = filled_prescription.fills[0];
+
+        assert!(fill.prescription_id == seed_prescription.id);
+        assert!(fill.pharmacist_id == seeds.pharmacist.id);
+    }
+
+    #[tokio::test]
+    async fn doesnt_fill_if_already_filled() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let seed_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+
+        let filled_prescription = service
+            .fill_prescription(
+                seed_prescription.id,
+                seeds.pharmacist.id,
+                seed_prescription.code,
+                None,
+            )
+            .await
+            .unwrap();
+        let fill = filled_prescription.fills[0];
+
+        assert!(fill.prescription_id == seed_prescription.id);
+        assert!(fill.pharmacist_id == seeds.pharmacist.id);
+
+        let code = filled_prescription.code.clone();
+        let prescription_filled_again = service
+            .fill_prescription(filled_prescription.id, seeds.pharmacist.id, code, None)
+            .await;
+
+        assert!(match prescription_filled_again {
+            Err(FillPrescriptionError::DomainError(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[tokio::test]
+    async fn fills_prescription_partially_and_then_dispenses_the_remainder() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let seed_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![(seeds.drugs[0].id, 3), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+
+        let partially_filled_prescription = service
+            .fill_prescription(
+                seed_prescription.id,
+                seeds.pharmacist.id,
+                seed_prescription.code.clone(),
+                Some(vec![(seeds.drugs[0].id, 1)]),
+            )
             .await
             .unwrap();
 
-        let drugs_service = DrugsService::new(Box::new(DrugsRepositoryFake::new()));
-        let created_drug_0 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+        assert_eq!(
+            partially_filled_prescription.fill_status,
+            PrescriptionFillStatus::PartiallyFilled
+        );
+        assert_eq!(
+            partially_filled_prescription.prescribed_drugs[0].dispensed_quantity,
+            1
+        );
+
+        assert!(matches!(
+            service
+                .fill_prescription(
+                    seed_prescription.id,
+                    seeds.pharmacist.id,
+                    seed_prescription.code.clone(),
+                    Some(vec![(seeds.drugs[0].id, 3)]),
+                )
+                .await,
+            Err(FillPrescriptionError::DomainError(_))
+        ));
+
+        let filled_prescription = service
+            .fill_prescription(
+                seed_prescription.id,
+                seeds.pharmacist.id,
+                seed_prescription.code.clone(),
+                Some(vec![(seeds.drugs[0].id, 2), (seeds.drugs[1].id, 2)]),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            filled_prescription.fill_status,
+            PrescriptionFillStatus::Filled
+        );
+        assert_eq!(filled_prescription.fills.len(), 2);
+        assert_eq!(
+            service
+                .get_prescription_by_id(seed_prescription.id)
+                .await
+                .unwrap(),
+            filled_prescription
+        );
+    }
+
+    #[tokio::test]
+    async fn verifies_prescription_without_filling_it() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
                 None,
                 None,
+                vec![(seeds.drugs[0].id, 1)],
             )
             .await
             .unwrap();
-        let created_drug_1 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+
+        let verification = service
+            .verify_prescription(prescription.id, prescription.code.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            verification.verification,
+            PrescriptionVerificationStatus::Valid
+        );
+        assert_eq!(verification.prescription, prescription);
+        assert_eq!(
+            service
+                .verify_prescription(prescription.id, "00000000".try_into().unwrap())
+                .await
+                .unwrap()
+                .verification,
+            PrescriptionVerificationStatus::CodeMismatch
+        );
+        assert!(service
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap()
+            .fills
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn checks_validity_window_against_the_clock() {
+        let clock = Arc::new(FixedClock::new("2024-03-15T12:00:00Z".parse().unwrap()));
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let service = service.with_clock(clock.clone());
+        let prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(prescription.start_date, clock.now());
+        assert_eq!(prescription.end_date, clock.now() + Duration::days(30));
+
+        clock.advance(Duration::days(30));
+
+        assert_eq!(
+            service
+                .verify_prescription(prescription.id, prescription.code.clone())
+                .await
+                .unwrap()
+                .verification,
+            PrescriptionVerificationStatus::Valid
+        );
+
+        clock.advance(Duration::seconds(1));
+
+        assert_eq!(
+            service
+                .verify_prescription(prescription.id, prescription.code.clone())
+                .await
+                .unwrap()
+                .verification,
+            PrescriptionVerificationStatus::Expired
+        );
+        assert!(matches!(
+            service
+                .fill_prescription(
+                    prescription.id,
+                    seeds.pharmacist.id,
+                    prescription.code.clone(),
+                    None,
+                )
+                .await,
+            Err(FillPrescriptionError::DomainError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn sets_fillable_now_against_the_clock() {
+        let clock = Arc::new(FixedClock::new("2024-03-15T12:00:00Z".parse().unwrap()));
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let service = service.with_clock(clock.clone());
+        let pending_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(clock.now() + Duration::days(1)),
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1)],
+            )
+            .await
+            .unwrap();
+
+        assert!(!pending_prescription.fillable_now);
+
+        clock.advance(Duration::days(1));
+        let prescription = service
+            .get_prescription_by_id(pending_prescription.id)
+            .await
+            .unwrap();
+
+        assert!(prescription.fillable_now);
+
+        let filled_prescription = service
+            .fill_prescription(
+                prescription.id,
+                seeds.pharmacist.id,
+                prescription.code.clone(),
                 None,
+            )
+            .await
+            .unwrap();
+
+        assert!(!filled_prescription.fillable_now);
+
+        let prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
                 None,
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1)],
             )
             .await
             .unwrap();
-        let created_drug_2 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+
+        assert!(prescription.fillable_now);
+
+        clock.advance(Duration::days(31));
+        let prescriptions = service
+            .get_prescriptions_by_ids(&[prescription.id])
+            .await
+            .unwrap();
+
+        assert!(!prescriptions[0].fillable_now);
+    }
+
+    #[tokio::test]
+    async fn fills_prescriptions_in_batch() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let mut seed_prescriptions = vec![];
+        for _ in 0..2 {
+            seed_prescriptions.push(
+                service
+                    .create_prescription(
+                        seeds.doctor.id,
+                        seeds.patient.id,
+                        None,
+                        None,
+                        vec![(seeds.drugs[0].id, 1)],
+                    )
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let filled_prescriptions = service
+            .fill_prescriptions(
+                seeds.pharmacist.id,
+                seed_prescriptions
+                    .iter()
+                    .map(|prescription| (prescription.id, prescription.code.clone()))
+                    .collect(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(filled_prescriptions.len(), 2);
+        for (filled_prescription, seed_prescription) in
+            filled_prescriptions.iter().zip(&seed_prescriptions)
+        {
+            let fill = filled_prescription.fills[0];
+            assert_eq!(fill.prescription_id, seed_prescription.id);
+            assert_eq!(fill.pharmacist_id, seeds.pharmacist.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn doesnt_fill_any_prescription_in_batch_if_one_fails() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let mut seed_prescriptions = vec![];
+        for _ in 0..2 {
+            seed_prescriptions.push(
+                service
+                    .create_prescription(
+                        seeds.doctor.id,
+                        seeds.patient.id,
+                        None,
+                        None,
+                        vec![(seeds.drugs[0].id, 1)],
+                    )
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let result = service
+            .fill_prescriptions(
+                seeds.pharmacist.id,
+                vec![
+                    (seed_prescriptions[0].id, seed_prescriptions[0].code.clone()),
+                    (seed_prescriptions[1].id, "00000000".try_into().unwrap()),
+                ],
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(FillPrescriptionsBatchError::ItemError(
+                1,
+                FillPrescriptionError::DomainError(_),
+            ))
+        ));
+        for seed_prescription in &seed_prescriptions {
+            let prescription = service
+                .get_prescription_by_id(seed_prescription.id)
+                .await
+                .unwrap();
+            assert!(prescription.fills.is_empty());
+        }
+
+        let duplicated = service
+            .fill_prescriptions(
+                seeds.pharmacist.id,
+                vec![
+                    (seed_prescriptions[0].id, seed_prescriptions[0].code.clone()),
+                    (seed_prescriptions[0].id, seed_prescriptions[0].code.clone()),
+                ],
+            )
+            .await;
+
+        assert!(matches!(
+            duplicated,
+            Err(FillPrescriptionsBatchError::ItemError(
+                1,
+                FillPrescriptionError::DomainError(_),
+            ))
+        ));
+
+        assert!(matches!(
+            service
+                .fill_prescriptions(seeds.pharmacist.id, vec![])
+                .await,
+            Err(FillPrescriptionsBatchError::DomainError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn gets_pharmacists_with_pagination() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+
+        service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+        service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
                 None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+        service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
                 None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
             )
             .await
             .unwrap();
-        let created_drug_3 = drugs_service
-            .create_drug(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
+        service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+            )
+            .await
+            .unwrap();
+
+        let prescriptions = service
+            .get_prescriptions_with_pagination(Some(1), Some(2), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 2);
+
+        let prescriptions = service
+            .get_prescriptions_with_pagination(Some(1), Some(3), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+
+        let prescriptions = service
+            .get_prescriptions_with_pagination(None, Some(10), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 4);
+
+        let prescriptions = service
+            .get_prescriptions_with_pagination(Some(1), None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 0);
+
+        let prescriptions = service
+            .get_prescriptions_with_pagination(None, None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 4);
+
+        let prescriptions = service
+            .get_prescriptions_with_pagination(Some(2), Some(3), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn get_pharmacists_with_pagination_returns_error_if_params_are_invalid() {
+        let (service, _) = setup_services_and_seed_database().await;
+
+        assert!(service
+            .get_prescriptions_with_pagination(Some(-1), None, None, None, None)
+            .await
+            .is_err());
+
+        assert!(service
+            .get_prescriptions_with_pagination(None, Some(0), None, None, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn get_prescriptions_with_pagination_returns_error_if_created_window_is_inverted() {
+        let (service, _) = setup_services_and_seed_database().await;
+        let now = Utc::now();
+
+        assert!(matches!(
+            service
+                .get_prescriptions_with_pagination(
+                    None,
+                    None,
+                    None,
+                    Some(now),
+                    Some(now - Duration::days(1))
+                )
+                .await,
+            Err(GetPrescriptionsWithPaginationError::DomainError(_))
+        ));
+        assert!(matches!(
+            service
+                .get_prescription_ids_with_pagination(
+                    None,
+                    None,
+                    None,
+                    Some(now),
+                    Some(now - Duration::days(1))
+                )
+                .await,
+            Err(GetPrescriptionsWithPaginationError::DomainError(_))
+        ));
+        assert!(service
+            .get_prescriptions_with_pagination(None, None, None, Some(now), Some(now))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn search_prescriptions_returns_error_if_query_is_blank() {
+        let (service, _) = setup_services_and_seed_database().await;
+
+        assert!(matches!(
+            service.search_prescriptions("  ", None, None).await,
+            Err(SearchPrescriptionsError::DomainError(_))
+        ));
+        assert!(service
+            .search_prescriptions(" John ", None, None)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn reassigns_prescription_patient_only_for_prescribing_doctor_and_unfilled_prescription()
+    {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
                 None,
                 None,
+                vec![(seeds.drugs[0].id, 1)],
             )
             .await
             .unwrap();
 
-        (
-            PrescriptionsService::new(Box::new(PrescriptionsRepositoryFake::new(
-                None,
-                Some(vec![created_doctor.clone()]),
-                Some(vec![created_patient.clone()]),
-                Some(vec![created_pharmacist.clone()]),
-                Some(vec![
-                    created_drug_0.clone(),
-                    created_drug_1.clone(),
-                    created_drug_2.clone(),
-                    created_drug_3.clone(),
-                ]),
-            ))),
-            DatabaseSeeds {
-                doctor: created_doctor,
-                pharmacist: created_pharmacist,
-                patient: created_patient,
-                drugs: vec![
-                    created_drug_0,
-                    created_drug_1,
-                    created_drug_2,
-                    created_drug_3,
-                ],
-            },
-        )
+        assert_eq!(
+            service
+                .reassign_prescription_patient(prescription.id, seeds.patient.id, Uuid::new_v4())
+                .await,
+            Err(ReassignPrescriptionPatientError::NotPrescribingDoctor)
+        );
+
+        let nonexistent_patient_id = Uuid::new_v4();
+        assert_eq!(
+            service
+                .reassign_prescription_patient(
+                    prescription.id,
+                    nonexistent_patient_id,
+                    seeds.doctor.id
+                )
+                .await,
+            Err(ReassignPrescriptionPatientError::RepositoryError(
+                ReassignPrescriptionPatientRepositoryError::PatientNotFound(nonexistent_patient_id)
+            ))
+        );
+
+        service
+            .fill_prescription(
+                prescription.id,
+                seeds.pharmacist.id,
+                prescription.code,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .reassign_prescription_patient(prescription.id, seeds.patient.id, seeds.doctor.id)
+                .await,
+            Err(ReassignPrescriptionPatientError::DomainError(_))
+        ));
     }
 
     #[tokio::test]
-    async fn creates_and_reads_prescription_by_id() {
+    async fn cancels_prescription_only_for_prescribing_doctor_and_blocks_its_fill() {
         let (service, seeds) = setup_services_and_seed_database().await;
-
-        let created_prescription = service
+        let prescription = service
             .create_prescription(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                None,
+                vec![(seeds.drugs[0].id, 1)],
             )
             .await
             .unwrap();
 
         assert_eq!(
-            created_prescription.prescription_type,
-            PrescriptionType::ForChronicDiseaseDrugs
+            service
+                .cancel_prescription(prescription.id, Uuid::new_v4())
+                .await,
+            Err(CancelPrescriptionError::NotPrescribingDoctor)
         );
-        assert_eq!(created_prescription.prescribed_drugs.len(), 2);
 
-        let prescription_from_repository = service
-            .get_prescription_by_id(created_prescription.id)
+        let cancelled_prescription = service
+            .cancel_prescription(prescription.id, seeds.doctor.id)
             .await
             .unwrap();
 
-        assert_eq!(created_prescription, prescription_from_repository);
-    }
-
-    #[tokio::test]
-    async fn get_prescription_by_id_returns_error_if_prescription_doesnt_exist() {
-        let (service, _) = setup_services_and_seed_database().await;
-        let prescription_id = uuid::Uuid::new_v4();
-
-        let prescription = service.get_prescription_by_id(prescription_id).await;
-
-        assert!(prescription.is_err());
+        assert_eq!(
+            cancelled_prescription.fill_status,
+            PrescriptionFillStatus::Cancelled
+        );
+        assert!(!cancelled_prescription.fillable_now);
+        assert!(matches!(
+            service
+                .cancel_prescription(prescription.id, seeds.doctor.id)
+                .await,
+            Err(CancelPrescriptionError::DomainError(_))
+        ));
+        assert!(matches!(
+            service
+                .fill_prescription(
+                    prescription.id,
+                    seeds.pharmacist.id,
+                    prescription.code.clone(),
+                    None
+                )
+                .await,
+            Err(FillPrescriptionError::DomainError(message)) if message == "Prescription is cancelled"
+        ));
+        assert_eq!(
+            service
+                .verify_prescription(prescription.id, prescription.code)
+                .await
+                .unwrap()
+                .verification,
+            PrescriptionVerificationStatus::Cancelled
+        );
     }
 
     #[tokio::test]
-    async fn fills_prescription() {
+    async fn doesnt_cancel_filled_prescription() {
         let (service, seeds) = setup_services_and_seed_database().await;
-        let seed_prescription = service
+        let prescription = service
             .create_prescription(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                None,
+                vec![(seeds.drugs[0].id, 1)],
             )
             .await
             .unwrap();
-
-        let filled_prescription = service
+        service
             .fill_prescription(
-                seed_prescription.id,
+                prescription.id,
                 seeds.pharmacist.id,
-                seed_prescription.code,
+                prescription.code,
+                None,
             )
             .await
             .unwrap();
-        let fill = filled_prescription.fill.unwrap();
 
-        assert!(fill.prescription_id == seed_prescription.id);
-        assert!(fill.pharmacist_id == seeds.pharmacist.id);
+        assert!(matches!(
+            service
+                .cancel_prescription(prescription.id, seeds.doctor.id)
+                .await,
+            Err(CancelPrescriptionError::DomainError(message)) if message == "Filled prescription can't be cancelled"
+        ));
     }
 
     #[tokio::test]
-    async fn doesnt_fill_if_already_filled() {
+    async fn creates_prescription_from_template_with_the_same_drugs_and_quantities() {
         let (service, seeds) = setup_services_and_seed_database().await;
-        let seed_prescription = service
-            .create_prescription(
+        let template = service
+            .create_prescription_template(
                 seeds.doctor.id,
-                seeds.patient.id,
-                None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                "Flu season".into(),
+                vec![(seeds.drugs[0].id, 2), (seeds.drugs[1].id, 1)],
             )
             .await
             .unwrap();
 
-        let filled_prescription = service
-            .fill_prescription(
-                seed_prescription.id,
-                seeds.pharmacist.id,
-                seed_prescription.code,
+        let prescription = service
+            .create_prescription_from_template(template.id, seeds.doctor.id, seeds.patient.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription.doctor.id, seeds.doctor.id);
+        assert_eq!(prescription.patient.id, seeds.patient.id);
+        assert_eq!(
+            prescription
+                .prescribed_drugs
+                .iter()
+                .map(|prescribed_drug| (prescribed_drug.drug_id, prescribed_drug.quantity))
+                .collect::<Vec<_>>(),
+            vec![(seeds.drugs[0].id, 2), (seeds.drugs[1].id, 1)]
+        );
+        assert_eq!(
+            service
+                .get_prescription_templates_for_doctor(seeds.doctor.id)
+                .await
+                .unwrap(),
+            vec![template]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_template_owner_can_use_update_and_delete_template() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let template = service
+            .create_prescription_template(
+                seeds.doctor.id,
+                "Flu season".into(),
+                vec![(seeds.drugs[0].id, 2)],
             )
             .await
             .unwrap();
-        let fill = filled_prescription.fill.unwrap();
+        let other_doctor_id = Uuid::new_v4();
 
-        assert!(fill.prescription_id == seed_prescription.id);
-        assert!(fill.pharmacist_id == seeds.pharmacist.id);
+        assert!(matches!(
+            service
+                .create_prescription_from_template(template.id, other_doctor_id, seeds.patient.id)
+                .await,
+            Err(CreatePrescriptionFromTemplateError::NotTemplateOwner)
+        ));
+        assert_eq!(
+            service
+                .update_prescription_template(
+                    template.id,
+                    other_doctor_id,
+                    "Cold".into(),
+                    vec![(seeds.drugs[1].id, 1)]
+                )
+                .await,
+            Err(UpdatePrescriptionTemplateError::NotTemplateOwner)
+        );
+        assert_eq!(
+            service
+                .delete_prescription_template(template.id, other_doctor_id)
+                .await,
+            Err(DeletePrescriptionTemplateError::NotTemplateOwner)
+        );
 
-        let code = filled_prescription.code.clone();
-        let prescription_filled_again = service
-            .fill_prescription(filled_prescription.id, seeds.pharmacist.id, code)
-            .await;
+        let updated_template = service
+            .update_prescription_template(
+                template.id,
+                seeds.doctor.id,
+                "Cold".into(),
+                vec![(seeds.drugs[1].id, 1)],
+            )
+            .await
+            .unwrap();
 
-        assert!(match prescription_filled_again {
-            Err(FillPrescriptionError::DomainError(_)) => true,
-            _ => false,
-        });
+        assert_eq!(updated_template.name, "Cold");
+        assert_eq!(
+            updated_template.prescribed_drugs[0].drug_id,
+            seeds.drugs[1].id
+        );
+
+        service
+            .delete_prescription_template(template.id, seeds.doctor.id)
+            .await
+            .unwrap();
+
+        assert!(service
+            .get_prescription_templates_for_doctor(seeds.doctor.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    // Seeded drugs with the first one being an antibiotic
+    async fn setup_service_with_antibiotic(
+        policy: PrescriptionPolicy,
+    ) -> (PrescriptionsService, DatabaseSeeds) {
+        let (_, mut seeds) = setup_services_and_seed_database().await;
+        seeds.drugs[0].category = Some(DrugCategory::Antibiotic);
+
+        let service = PrescriptionsService::new(
+            Box::new(PrescriptionsRepositoryFake::new(
+                None,
+                Some(vec![seeds.doctor.clone()]),
+                Some(vec![seeds.patient.clone()]),
+                Some(vec![seeds.pharmacist.clone()]),
+                Some(seeds.drugs.clone()),
+            )),
+            policy,
+        );
+
+        (service, seeds)
     }
 
     #[tokio::test]
-    async fn gets_pharmacists_with_pagination() {
-        let (service, seeds) = setup_services_and_seed_database().await;
+    async fn creates_prescription_with_drugs_matching_its_type() {
+        let (service, seeds) = setup_service_with_antibiotic(PrescriptionPolicy::default()).await;
 
-        service
+        let created_prescription = service
             .create_prescription(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                Some(PrescriptionType::ForAntibiotics),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 1)],
+            )
+            .await;
+
+        assert!(created_prescription.is_ok());
+    }
+
+    #[tokio::test]
+    async fn previews_prescription_without_saving_it() {
+        let (service, seeds) = setup_service_with_antibiotic(PrescriptionPolicy::default()).await;
+
+        let prescription = service
+            .preview_prescription_with_diagnoses(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForAntibiotics),
+                vec![(seeds.drugs[0].id, 2)],
+                vec!["J06.9".into()],
+                false,
             )
             .await
             .unwrap();
-        service
+
+        assert_eq!(prescription.doctor.id, seeds.doctor.id);
+        assert_eq!(prescription.prescribed_drugs[0].quantity, 2);
+        assert_eq!(prescription.diagnoses, vec!["J06.9"]);
+        assert!(service
+            .get_prescription_by_id(prescription.id)
+            .await
+            .is_err());
+
+        let invalid_prescription = service
+            .preview_prescription_with_diagnoses(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1)],
+                vec![],
+                false,
+            )
+            .await;
+
+        assert!(matches!(
+            invalid_prescription,
+            Err(CreatePrescriptionError::DomainError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn doesnt_create_prescription_with_drug_not_matching_its_type() {
+        let (service, seeds) = setup_service_with_antibiotic(PrescriptionPolicy::default()).await;
+
+        let created_prescription = service
             .create_prescription(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 1)],
             )
-            .await
-            .unwrap();
-        service
+            .await;
+
+        assert!(matches!(
+            created_prescription,
+            Err(CreatePrescriptionError::DomainError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn allows_mixed_drug_categories_when_policy_doesnt_forbid_them() {
+        let (service, seeds) = setup_service_with_antibiotic(PrescriptionPolicy {
+            forbid_mixed_drug_categories: false,
+            ..Default::default()
+        })
+        .await;
+
+        let created_prescription = service
             .create_prescription(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[0].id, 1)],
             )
-            .await
-            .unwrap();
-        service
+            .await;
+
+        assert!(created_prescription.is_ok());
+    }
+
+    struct PrescriptionFillNotifierFake {
+        notifications: mpsc::UnboundedSender<PrescriptionFillNotification>,
+    }
+
+    #[async_trait]
+    impl PrescriptionFillNotifier for PrescriptionFillNotifierFake {
+        async fn notify_fill(
+            &self,
+            notification: &PrescriptionFillNotification,
+        ) -> Result<(), NotifyPrescriptionFillError> {
+            self.notifications.send(notification.clone()).unwrap();
+            Err(NotifyPrescriptionFillError(
+                "Receiver is unavailable".into(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn notifies_about_fill_without_failing_it_when_notification_isnt_delivered() {
+        let (service, seeds) = setup_services_and_seed_database().await;
+        let (sender, mut notifications) = mpsc::unbounded_channel();
+        let service = service.with_fill_notifier(Some(Arc::new(PrescriptionFillNotifierFake {
+            notifications: sender,
+        })));
+        let seed_prescription = service
             .create_prescription(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
-                Some(PrescriptionType::ForChronicDiseaseDrugs),
-                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 2)],
+                None,
+                vec![(seeds.drugs[0].id, 1)],
             )
             .await
             .unwrap();
 
-        let prescriptions = service
-            .get_prescriptions_with_pagination(Some(1), Some(2))
+        let filled_prescription = service
+            .fill_prescription(
+                seed_prescription.id,
+                seeds.pharmacist.id,
+                seed_prescription.code.clone(),
+                None,
+            )
             .await
             .unwrap();
 
-        assert_eq!(prescriptions.len(), 2);
+        let notification =
+            tokio::time::timeout(std::time::Duration::from_secs(1), notifications.recv())
+                .await
+                .unwrap()
+                .unwrap();
 
-        let prescriptions = service
-            .get_prescriptions_with_pagination(Some(1), Some(3))
-            .await
-            .unwrap();
+        assert_eq!(
+            notification,
+            PrescriptionFillNotification {
+                prescription_id: seed_prescription.id,
+                code: seed_prescription.code,
+                pharmacist_id: seeds.pharmacist.id,
+                filled_at: filled_prescription.fills[0].created_at,
+            }
+        );
+    }
 
-        assert_eq!(prescriptions.len(), 1);
+    // Seeded drugs with the first one restricted to chronic disease prescriptions
+    async fn setup_service_with_restricted_drug() -> (PrescriptionsService, DatabaseSeeds) {
+        let (_, mut seeds) = setup_services_and_seed_database().await;
+        seeds.drugs[0].allowed_prescription_types = vec![PrescriptionType::ForChronicDiseaseDrugs];
 
-        let prescriptions = service
-            .get_prescriptions_with_pagination(None, Some(10))
-            .await
-            .unwrap();
+        let service = PrescriptionsService::new(
+            Box::new(PrescriptionsRepositoryFake::new(
+                None,
+                Some(vec![seeds.doctor.clone()]),
+                Some(vec![seeds.patient.clone()]),
+                Some(vec![seeds.pharmacist.clone()]),
+                Some(seeds.drugs.clone()),
+            )),
+            PrescriptionPolicy::default(),
+        );
 
-        assert_eq!(prescriptions.len(), 4);
+        (service, seeds)
+    }
 
-        let prescriptions = service
-            .get_prescriptions_with_pagination(Some(1), None)
-            .await
-            .unwrap();
+    #[tokio::test]
+    async fn creates_prescription_with_drug_allowed_on_its_type() {
+        let (service, seeds) = setup_service_with_restricted_drug().await;
 
-        assert_eq!(prescriptions.len(), 0);
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForChronicDiseaseDrugs),
+                vec![(seeds.drugs[0].id, 1), (seeds.drugs[1].id, 1)],
+            )
+            .await;
 
-        let prescriptions = service
-            .get_prescriptions_with_pagination(None, None)
-            .await
-            .unwrap();
+        assert!(created_prescription.is_ok());
+    }
 
-        assert_eq!(prescriptions.len(), 4);
+    #[tokio::test]
+    async fn doesnt_create_prescription_with_drug_not_allowed_on_its_type() {
+        let (service, seeds) = setup_service_with_restricted_drug().await;
 
-        let prescriptions = service
-            .get_prescriptions_with_pagination(Some(2), Some(3))
-            .await
-            .unwrap();
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::Regular),
+                vec![(seeds.drugs[1].id, 1), (seeds.drugs[0].id, 1)],
+            )
+            .await;
 
-        assert_eq!(prescriptions.len(), 0);
+        assert!(matches!(
+            created_prescription,
+            Err(CreatePrescriptionError::DomainError(message)) if message.contains(&seeds.drugs[0].name)
+        ));
     }
 
     #[tokio::test]
-    async fn get_pharmacists_with_pagination_returns_error_if_params_are_invalid() {
-        let (service, _) = setup_services_and_seed_database().await;
+    async fn creates_prescription_of_any_type_with_unrestricted_drugs() {
+        let (service, seeds) = setup_service_with_restricted_drug().await;
 
-        assert!(service
-            .get_prescriptions_with_pagination(Some(-1), None)
-            .await
-            .is_err());
+        let created_prescription = service
+            .create_prescription(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(PrescriptionType::ForImmunologicalDrugs),
+                vec![(seeds.drugs[1].id, 1), (seeds.drugs[2].id, 1)],
+            )
+            .await;
 
-        assert!(service
-            .get_prescriptions_with_pagination(None, Some(0))
-            .await
-            .is_err());
+        assert!(created_prescription.is_ok());
     }
 }
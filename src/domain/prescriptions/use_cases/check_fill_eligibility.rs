@@ -0,0 +1,233 @@
+// Prescription can be filled when:
+//  - it isn't cancelled
+//  - now is between its start and end date (both inclusive)
+//  - it isn't fully filled yet, partially filled prescriptions can be filled again
+//  - the given code matches the prescription's code
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::prescriptions::{
+    entities::{Prescription, PrescriptionFillStatus},
+    prescription_code::PrescriptionCode,
+};
+
+#[derive(thiserror::Error, Debug, PartialEq, Clone, Copy)]
+pub enum FillEligibilityError {
+    #[error("Prescription is cancelled")]
+    Cancelled,
+    #[error("Prescription is not valid yet")]
+    NotYetValid,
+    #[error("Prescription has expired")]
+    Expired,
+    #[error("Prescription is already filled")]
+    AlreadyFilled,
+    #[error("Prescription code is invalid")]
+    CodeMismatch,
+}
+
+impl Prescription {
+    pub fn check_fill_eligibility(
+        &self,
+        code: &PrescriptionCode,
+        now: DateTime<Utc>,
+    ) -> Result<(), FillEligibilityError> {
+        if self.cancelled_at.is_some() {
+            Err(FillEligibilityError::Cancelled)?;
+        }
+        if now < self.start_date {
+            Err(FillEligibilityError::NotYetValid)?;
+        }
+        if now > self.end_date {
+            Err(FillEligibilityError::Expired)?;
+        }
+        if self.fill_status == PrescriptionFillStatus::Filled {
+            Err(FillEligibilityError::AlreadyFilled)?;
+        }
+        if &self.code != code {
+            Err(FillEligibilityError::CodeMismatch)?;
+        }
+
+        Ok(())
+    }
+
+    // Same rule without the code, partially filled prescriptions are still fillable
+    pub fn is_fillable_at(&self, now: DateTime<Utc>) -> bool {
+        self.check_fill_eligibility(&self.code, now).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+    use rstest::rstest;
+    use uuid::Uuid;
+
+    use super::FillEligibilityError;
+    use crate::domain::prescriptions::{
+        entities::{
+            PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFillStatus,
+            PrescriptionPatient, PrescriptionType,
+        },
+        policy::PrescriptionPolicy,
+    };
+
+    fn get_start_date() -> DateTime<Utc> {
+        "2024-03-15T12:00:00Z".parse().unwrap()
+    }
+
+    fn create_mock_prescription(fill_status: PrescriptionFillStatus) -> Prescription {
+        let prescription_id = Uuid::new_v4();
+        let prescription_type = PrescriptionType::Regular;
+        let start_date = get_start_date();
+        let end_date = start_date + PrescriptionPolicy::default().get_duration(&prescription_type);
+
+        Prescription {
+            id: prescription_id,
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type,
+            start_date,
+            end_date,
+            prescribed_drugs: vec![PrescribedDrug {
+                id: Uuid::new_v4(),
+                drug_id: Uuid::new_v4(),
+                prescription_id,
+                quantity: 2,
+                dispensed_quantity: 0,
+                unit_price_cents: None,
+                created_at: start_date,
+                updated_at: start_date,
+            }],
+            fills: vec![],
+            fill_status,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at: start_date,
+            updated_at: start_date,
+        }
+    }
+
+    #[rstest]
+    #[case(PrescriptionFillStatus::Unfilled, Duration::zero())]
+    #[case(PrescriptionFillStatus::Unfilled, Duration::days(1))]
+    #[case(PrescriptionFillStatus::Unfilled, Duration::days(30))]
+    #[case(PrescriptionFillStatus::PartiallyFilled, Duration::days(1))]
+    fn prescription_is_eligible_for_fill(
+        #[case] fill_status: PrescriptionFillStatus,
+        #[case] since_start: Duration,
+    ) {
+        let prescription = create_mock_prescription(fill_status);
+
+        let result = prescription.check_fill_eligibility(
+            &"12345678".try_into().unwrap(),
+            get_start_date() + since_start,
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[rstest]
+    #[case(
+        PrescriptionFillStatus::Unfilled,
+        "12345678",
+        -Duration::seconds(1),
+        FillEligibilityError::NotYetValid
+    )]
+    #[case(
+        PrescriptionFillStatus::Unfilled,
+        "12345678",
+        Duration::days(30) + Duration::seconds(1),
+        FillEligibilityError::Expired
+    )]
+    #[case(
+        PrescriptionFillStatus::Filled,
+        "12345678",
+        Duration::days(1),
+        FillEligibilityError::AlreadyFilled
+    )]
+    #[case(
+        PrescriptionFillStatus::Unfilled,
+        "12345679",
+        Duration::days(1),
+        FillEligibilityError::CodeMismatch
+    )]
+    #[case(
+        PrescriptionFillStatus::PartiallyFilled,
+        "12345679",
+        Duration::days(1),
+        FillEligibilityError::CodeMismatch
+    )]
+    fn prescription_is_not_eligible_for_fill(
+        #[case] fill_status: PrescriptionFillStatus,
+        #[case] code: &str,
+        #[case] since_start: Duration,
+        #[case] expected_error: FillEligibilityError,
+    ) {
+        let prescription = create_mock_prescription(fill_status);
+
+        let result = prescription
+            .check_fill_eligibility(&code.try_into().unwrap(), get_start_date() + since_start);
+
+        assert_eq!(result, Err(expected_error));
+    }
+
+    #[rstest]
+    #[case(PrescriptionFillStatus::Unfilled, Duration::days(1), true)]
+    #[case(PrescriptionFillStatus::PartiallyFilled, Duration::days(1), true)]
+    #[case(PrescriptionFillStatus::Filled, Duration::days(1), false)]
+    #[case(PrescriptionFillStatus::Unfilled, -Duration::seconds(1), false)]
+    #[case(PrescriptionFillStatus::Filled, -Duration::seconds(1), false)]
+    #[case(PrescriptionFillStatus::Unfilled, Duration::days(30) + Duration::seconds(1), false)]
+    #[case(PrescriptionFillStatus::Filled, Duration::days(30) + Duration::seconds(1), false)]
+    fn prescription_is_fillable_only_when_not_filled_and_within_window(
+        #[case] fill_status: PrescriptionFillStatus,
+        #[case] since_start: Duration,
+        #[case] expected: bool,
+    ) {
+        let prescription = create_mock_prescription(fill_status);
+
+        assert_eq!(
+            prescription.is_fillable_at(get_start_date() + since_start),
+            expected
+        );
+    }
+
+    #[test]
+    fn cancelled_prescription_isnt_eligible_for_fill() {
+        let mut prescription = create_mock_prescription(PrescriptionFillStatus::Unfilled);
+        prescription.cancelled_at = Some(get_start_date());
+
+        let result = prescription.check_fill_eligibility(
+            &"12345678".try_into().unwrap(),
+            get_start_date() + Duration::days(1),
+        );
+
+        assert_eq!(result, Err(FillEligibilityError::Cancelled));
+        assert!(!prescription.is_fillable_at(get_start_date() + Duration::days(1)));
+    }
+
+    #[test]
+    fn checks_date_window_before_fill_status_and_code() {
+        let prescription = create_mock_prescription(PrescriptionFillStatus::Filled);
+
+        let result = prescription.check_fill_eligibility(
+            &"12345679".try_into().unwrap(),
+            get_start_date() + Duration::days(31),
+        );
+
+        assert_eq!(result, Err(FillEligibilityError::Expired));
+    }
+}
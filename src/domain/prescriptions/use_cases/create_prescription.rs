@@ -7,14 +7,33 @@
 //  - can have prescribed multiple different drugs, each with any quantity
 //  - has start date, which marks date from which it can be used
 //  - has end date, which marks date after which it can't be used anymore
+//  - can have its validity window restarted by the first fill, the end date is then moved to the
+//    fill date plus the window length
+//  - prescription for antibiotics can't be valid for more than 7 days
 //  - each prescription can be used only once
+//  - prescriptions for antibiotics, immunological or chronic disease drugs can only contain drugs
+//    of the matching category, regular prescriptions can't contain antibiotics
+//  - drugs restricted to some prescription types can only be prescribed on prescriptions of those types
+//  - can reference multiple diagnoses by their ICD-10 codes, each at most once
+//  - doctor can't prescribe drugs of restricted categories to themselves
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
-use crate::domain::prescriptions::entities::{NewPrescribedDrug, NewPrescription, PrescriptionType};
+use crate::domain::{
+    drugs::entities::DrugCategory,
+    patients::entities::ADULT_AGE,
+    prescriptions::{
+        entities::{
+            DrugPrescriptionTypeRestriction, NewPrescribedDrug, NewPrescription, PrescriptionType,
+        },
+        policy::PrescriptionPolicy,
+        prescription_code::PrescriptionCode,
+    },
+    utils::validators::validate_icd10_code::validate_icd10_code,
+};
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum CreateNewPrescriptionDomainError {
@@ -22,18 +41,40 @@ pub enum CreateNewPrescriptionDomainError {
     NoPrescribedDrugs,
     #[error("Quantity of drug with id {0} can't be 0")]
     InvalidDrugQuantity(Uuid),
+    #[error("Quantity of drug with id {0} can't be greater than {max}", max = i32::MAX)]
+    DrugQuantityTooLarge(Uuid),
     #[error("Can't prescribe two drugs with the same id {0}")]
     DuplicateDrugId(Uuid),
+    #[error("Can't prescribe more than {0} different drugs on one prescription")]
+    TooManyDrugs(usize),
+    #[error("Start date can't be more than {0} days in the past")]
+    StartDateTooFarInPast(i64),
+    #[error("Start date can't be more than {0} days in the future")]
+    StartDateTooFarInFuture(i64),
+    #[error("Prescription of this type can't be valid for more than {0} days")]
+    ValidityTooLong(i64),
+    #[error("Drug with id {0} of category {1:?} can't be prescribed on a {2:?} prescription")]
+    DrugCategoryMismatch(Uuid, DrugCategory, PrescriptionType),
+    #[error("Drug {0} can't be prescribed on a {1:?} prescription")]
+    DrugNotAllowedOnPrescriptionType(String, PrescriptionType),
+    #[error("ICD-10 code {0} has invalid format")]
+    InvalidDiagnosisCode(String),
+    #[error("Can't reference the same diagnosis {0} twice")]
+    DuplicateDiagnosisCode(String),
+    #[error("{0:?} prescription can't be issued to patients under {min_age}", min_age = ADULT_AGE)]
+    PatientUnderage(PrescriptionType),
+    #[error("Doctor can't prescribe drug with id {0} of category {1:?} to themselves")]
+    SelfPrescriptionOfRestrictedDrug(Uuid, DrugCategory),
 }
 
-impl PrescriptionType {
-    pub fn get_duration(&self) -> Duration {
-        match self {
-            PrescriptionType::Regular => Duration::days(30),
-            PrescriptionType::ForAntibiotics => Duration::days(7),
-            PrescriptionType::ForImmunologicalDrugs => Duration::days(120),
-            PrescriptionType::ForChronicDiseaseDrugs => Duration::days(365),
-        }
+pub const DEFAULT_MAX_PRESCRIBED_DRUGS: usize = 5;
+pub const MAX_ANTIBIOTICS_VALIDITY_DAYS: i64 = 7;
+
+// Upper bound of the validity regardless of the configured policy durations
+fn get_max_validity(prescription_type: &PrescriptionType) -> Option<Duration> {
+    match prescription_type {
+        PrescriptionType::ForAntibiotics => Some(Duration::days(MAX_ANTIBIOTICS_VALIDITY_DAYS)),
+        _ => None,
     }
 }
 
@@ -44,6 +85,26 @@ impl NewPrescription {
         start_date: Option<DateTime<Utc>>,
         prescription_type: Option<PrescriptionType>,
         prescribed_drugs: Vec<NewPrescribedDrug>,
+    ) -> Result<Self, CreateNewPrescriptionDomainError> {
+        Self::new_with_policy(
+            doctor_id,
+            patient_id,
+            start_date,
+            prescription_type,
+            prescribed_drugs,
+            &PrescriptionPolicy::default(),
+            Utc::now(),
+        )
+    }
+
+    pub fn new_with_policy(
+        doctor_id: Uuid,
+        patient_id: Uuid,
+        start_date: Option<DateTime<Utc>>,
+        prescription_type: Option<PrescriptionType>,
+        prescribed_drugs: Vec<NewPrescribedDrug>,
+        policy: &PrescriptionPolicy,
+        now: DateTime<Utc>,
     ) -> Result<Self, CreateNewPrescriptionDomainError> {
         if prescribed_drugs.is_empty() {
             Err(CreateNewPrescriptionDomainError::NoPrescribedDrugs)?;
@@ -56,6 +117,12 @@ impl NewPrescription {
                     prescribed_drug.drug_id,
                 ))?;
             }
+            // Quantities are stored in an INTEGER column
+            if i32::try_from(prescribed_drug.quantity).is_err() {
+                Err(CreateNewPrescriptionDomainError::DrugQuantityTooLarge(
+                    prescribed_drug.drug_id,
+                ))?;
+            }
             if ids_hashset.contains(&prescribed_drug.drug_id) {
                 Err(CreateNewPrescriptionDomainError::DuplicateDrugId(
                     prescribed_drug.drug_id,
@@ -65,33 +132,202 @@ impl NewPrescription {
             ids_hashset.insert(prescribed_drug.drug_id);
         }
 
-        let start_date = start_date.unwrap_or(Utc::now());
+        if ids_hashset.len() > policy.max_prescribed_drugs {
+            Err(CreateNewPrescriptionDomainError::TooManyDrugs(
+                policy.max_prescribed_drugs,
+            ))?;
+        }
+
+        let start_date = start_date.unwrap_or(now);
+        if start_date < now - policy.max_start_date_in_past {
+            Err(CreateNewPrescriptionDomainError::StartDateTooFarInPast(
+                policy.max_start_date_in_past.num_days(),
+            ))?;
+        }
+        if start_date > now + policy.max_start_date_in_future {
+            Err(CreateNewPrescriptionDomainError::StartDateTooFarInFuture(
+                policy.max_start_date_in_future.num_days(),
+            ))?;
+        }
+
         let prescription_type = prescription_type.unwrap_or(PrescriptionType::Regular);
-        let duration = prescription_type.get_duration();
+        let duration = policy.get_duration(&prescription_type);
+        if let Some(max_validity) = get_max_validity(&prescription_type) {
+            if duration > max_validity {
+                Err(CreateNewPrescriptionDomainError::ValidityTooLong(
+                    max_validity.num_days(),
+                ))?;
+            }
+        }
         let end_date = start_date + duration;
 
-        let code = rand::random::<u64>().to_string().chars().take(8).collect();
+        let code = generate_prescription_code();
 
         Ok(Self {
             id: Uuid::new_v4(),
             doctor_id,
             patient_id,
             prescribed_drugs,
+            diagnoses: vec![],
             prescription_type,
             code,
             start_date,
             end_date,
+            window_from_fill: false,
         })
     }
+
+    pub fn with_window_from_fill(mut self, window_from_fill: bool) -> Self {
+        self.window_from_fill = window_from_fill;
+        self
+    }
+
+    pub fn with_diagnoses(
+        mut self,
+        diagnoses: Vec<String>,
+    ) -> Result<Self, CreateNewPrescriptionDomainError> {
+        let mut codes_hashset: HashSet<&str> = HashSet::new();
+        for code in &diagnoses {
+            if validate_icd10_code(code).is_err() {
+                Err(CreateNewPrescriptionDomainError::InvalidDiagnosisCode(
+                    code.clone(),
+                ))?;
+            }
+            if !codes_hashset.insert(code) {
+                Err(CreateNewPrescriptionDomainError::DuplicateDiagnosisCode(
+                    code.clone(),
+                ))?;
+            }
+        }
+
+        self.diagnoses = diagnoses;
+        Ok(self)
+    }
+}
+
+fn is_category_allowed(category: DrugCategory, prescription_type: &PrescriptionType) -> bool {
+    match prescription_type {
+        PrescriptionType::Regular => category != DrugCategory::Antibiotic,
+        PrescriptionType::ForAntibiotics => category == DrugCategory::Antibiotic,
+        PrescriptionType::ForImmunologicalDrugs => category == DrugCategory::Immunological,
+        PrescriptionType::ForChronicDiseaseDrugs => category == DrugCategory::ChronicDisease,
+    }
+}
+
+impl NewPrescription {
+    // Drugs missing from the categories are uncategorized and can be prescribed on any prescription
+    pub fn check_drug_categories(
+        &self,
+        drug_categories: &HashMap<Uuid, DrugCategory>,
+    ) -> Result<(), CreateNewPrescriptionDomainError> {
+        for prescribed_drug in &self.prescribed_drugs {
+            if let Some(&category) = drug_categories.get(&prescribed_drug.drug_id) {
+                if !is_category_allowed(category, &self.prescription_type) {
+                    Err(CreateNewPrescriptionDomainError::DrugCategoryMismatch(
+                        prescribed_drug.drug_id,
+                        category,
+                        self.prescription_type,
+                    ))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Patients whose age can't be derived from the PESEL are treated as minors
+    pub fn check_patient_age(
+        &self,
+        patient_age: Option<u32>,
+        policy: &PrescriptionPolicy,
+    ) -> Result<(), CreateNewPrescriptionDomainError> {
+        let is_adult_only = policy
+            .adult_only_prescription_types
+            .contains(&self.prescription_type);
+        if is_adult_only && patient_age.is_none_or(|age| age < ADULT_AGE) {
+            Err(CreateNewPrescriptionDomainError::PatientUnderage(
+                self.prescription_type,
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    // The doctor is recognized as the patient when both have the same PESEL number
+    pub fn check_self_prescription(
+        &self,
+        doctor_pesel_number: &str,
+        patient_pesel_number: &str,
+        drug_categories: &HashMap<Uuid, DrugCategory>,
+        policy: &PrescriptionPolicy,
+    ) -> Result<(), CreateNewPrescriptionDomainError> {
+        if doctor_pesel_number != patient_pesel_number {
+            return Ok(());
+        }
+        for prescribed_drug in &self.prescribed_drugs {
+            if let Some(&category) = drug_categories.get(&prescribed_drug.drug_id) {
+                if policy
+                    .self_prescription_restricted_categories
+                    .contains(&category)
+                {
+                    Err(
+                        CreateNewPrescriptionDomainError::SelfPrescriptionOfRestrictedDrug(
+                            prescribed_drug.drug_id,
+                            category,
+                        ),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Drugs missing from the restrictions can be prescribed on any prescription
+    pub fn check_drug_prescription_types(
+        &self,
+        restrictions: &HashMap<Uuid, DrugPrescriptionTypeRestriction>,
+    ) -> Result<(), CreateNewPrescriptionDomainError> {
+        for prescribed_drug in &self.prescribed_drugs {
+            if let Some(restriction) = restrictions.get(&prescribed_drug.drug_id) {
+                if !restriction
+                    .allowed_prescription_types
+                    .contains(&self.prescription_type)
+                {
+                    Err(
+                        CreateNewPrescriptionDomainError::DrugNotAllowedOnPrescriptionType(
+                            restriction.drug_name.clone(),
+                            self.prescription_type,
+                        ),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn generate_prescription_code() -> PrescriptionCode {
+    PrescriptionCode::generate()
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::{Duration, Utc};
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, Duration, Utc};
+    use rstest::rstest;
     use uuid::Uuid;
 
-    use super::{CreateNewPrescriptionDomainError, NewPrescription, PrescriptionType};
-    use crate::domain::prescriptions::entities::NewPrescribedDrug;
+    use super::{
+        CreateNewPrescriptionDomainError, NewPrescription, PrescriptionPolicy, PrescriptionType,
+        DEFAULT_MAX_PRESCRIBED_DRUGS,
+    };
+    use crate::domain::{
+        drugs::entities::DrugCategory,
+        prescriptions::entities::{DrugPrescriptionTypeRestriction, NewPrescribedDrug},
+    };
 
     #[test]
     fn creates_prescription() {
@@ -242,7 +478,7 @@ mod tests {
         )
         .unwrap();
 
-        let sut = prescription.prescribed_drugs.get(0).unwrap();
+        let sut = prescription.prescribed_drugs.first().unwrap();
 
         assert_eq!(sut, &new_prescribed_drug);
     }
@@ -299,6 +535,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cant_add_drug_with_quantity_exceeding_i32_max() {
+        let drug_id = Uuid::new_v4();
+
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id,
+                quantity: u32::MAX,
+            }],
+        );
+
+        assert_eq!(
+            sut,
+            Err(CreateNewPrescriptionDomainError::DrugQuantityTooLarge(
+                drug_id
+            ))
+        );
+    }
+
+    #[test]
+    fn adds_drug_with_quantity_equal_to_i32_max() {
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: Uuid::new_v4(),
+                quantity: i32::MAX as u32,
+            }],
+        );
+
+        assert!(sut.is_ok());
+    }
+
     #[test]
     fn cant_add_two_drugs_with_the_same_id() {
         let drug_id = Uuid::new_v4();
@@ -335,4 +610,474 @@ mod tests {
             Err(CreateNewPrescriptionDomainError::NoPrescribedDrugs)
         );
     }
+
+    fn create_prescribed_drugs(count: usize) -> Vec<NewPrescribedDrug> {
+        (0..count)
+            .map(|_| NewPrescribedDrug {
+                drug_id: Uuid::new_v4(),
+                quantity: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn creates_prescription_with_max_number_of_drugs() {
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            create_prescribed_drugs(DEFAULT_MAX_PRESCRIBED_DRUGS),
+        )
+        .unwrap();
+
+        assert_eq!(sut.prescribed_drugs.len(), DEFAULT_MAX_PRESCRIBED_DRUGS);
+    }
+
+    #[test]
+    fn doesnt_create_prescription_with_more_than_max_number_of_drugs() {
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            create_prescribed_drugs(DEFAULT_MAX_PRESCRIBED_DRUGS + 1),
+        );
+
+        assert_eq!(
+            sut,
+            Err(CreateNewPrescriptionDomainError::TooManyDrugs(
+                DEFAULT_MAX_PRESCRIBED_DRUGS
+            ))
+        );
+    }
+
+    #[test]
+    fn respects_custom_max_number_of_drugs() {
+        let sut = NewPrescription::new_with_policy(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            create_prescribed_drugs(3),
+            &PrescriptionPolicy {
+                max_prescribed_drugs: 2,
+                ..Default::default()
+            },
+            Utc::now(),
+        );
+
+        assert_eq!(sut, Err(CreateNewPrescriptionDomainError::TooManyDrugs(2)));
+    }
+
+    #[test]
+    fn computes_end_date_from_policy_durations() {
+        let start_date = Utc::now();
+        let policy = PrescriptionPolicy {
+            regular_duration: Duration::days(45),
+            for_antibiotics_duration: Duration::days(3),
+            ..Default::default()
+        };
+
+        let regular = NewPrescription::new_with_policy(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Some(start_date),
+            Some(PrescriptionType::Regular),
+            create_prescribed_drugs(1),
+            &policy,
+            start_date,
+        )
+        .unwrap();
+        let for_antibiotics = NewPrescription::new_with_policy(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Some(start_date),
+            Some(PrescriptionType::ForAntibiotics),
+            create_prescribed_drugs(1),
+            &policy,
+            start_date,
+        )
+        .unwrap();
+
+        assert_eq!(regular.end_date, start_date + Duration::days(45));
+        assert_eq!(for_antibiotics.end_date, start_date + Duration::days(3));
+    }
+
+    #[test]
+    fn checks_for_duplicate_drug_ids_before_max_number_of_drugs() {
+        let drug_id = Uuid::new_v4();
+        let mut prescribed_drugs = create_prescribed_drugs(DEFAULT_MAX_PRESCRIBED_DRUGS + 1);
+        prescribed_drugs.push(NewPrescribedDrug {
+            drug_id,
+            quantity: 1,
+        });
+        prescribed_drugs.push(NewPrescribedDrug {
+            drug_id,
+            quantity: 2,
+        });
+
+        let sut =
+            NewPrescription::new(Uuid::new_v4(), Uuid::new_v4(), None, None, prescribed_drugs);
+
+        assert_eq!(
+            sut,
+            Err(CreateNewPrescriptionDomainError::DuplicateDrugId(drug_id))
+        );
+    }
+
+    fn create_prescription_starting_at(
+        start_date: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<NewPrescription, CreateNewPrescriptionDomainError> {
+        NewPrescription::new_with_policy(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            start_date,
+            None,
+            create_prescribed_drugs(1),
+            &PrescriptionPolicy::default(),
+            now,
+        )
+    }
+
+    #[rstest]
+    #[case(Duration::zero(), true)]
+    #[case(-Duration::days(1), true)]
+    #[case(-Duration::days(1) - Duration::seconds(1), false)]
+    #[case(Duration::days(30), true)]
+    #[case(Duration::days(30) + Duration::seconds(1), false)]
+    fn validates_start_date_relative_to_now(#[case] offset: Duration, #[case] is_valid: bool) {
+        let now = "2024-03-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let sut = create_prescription_starting_at(Some(now + offset), now);
+
+        assert_eq!(sut.is_ok(), is_valid);
+    }
+
+    #[test]
+    fn returns_error_if_start_date_is_too_far_in_past_or_future() {
+        let now = "2024-03-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            create_prescription_starting_at(Some(now - Duration::days(2)), now),
+            Err(CreateNewPrescriptionDomainError::StartDateTooFarInPast(1))
+        );
+        assert_eq!(
+            create_prescription_starting_at(Some(now + Duration::days(31)), now),
+            Err(CreateNewPrescriptionDomainError::StartDateTooFarInFuture(
+                30
+            ))
+        );
+    }
+
+    fn create_antibiotics_prescription_valid_for(
+        duration: Duration,
+    ) -> Result<NewPrescription, CreateNewPrescriptionDomainError> {
+        NewPrescription::new_with_policy(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::ForAntibiotics),
+            create_prescribed_drugs(1),
+            &PrescriptionPolicy {
+                for_antibiotics_duration: duration,
+                ..Default::default()
+            },
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn creates_antibiotics_prescription_valid_for_7_days() {
+        let sut = create_antibiotics_prescription_valid_for(Duration::days(7)).unwrap();
+
+        assert_eq!(sut.end_date - sut.start_date, Duration::days(7));
+    }
+
+    #[test]
+    fn doesnt_create_antibiotics_prescription_valid_for_more_than_7_days() {
+        assert_eq!(
+            create_antibiotics_prescription_valid_for(Duration::days(14)),
+            Err(CreateNewPrescriptionDomainError::ValidityTooLong(7))
+        );
+    }
+
+    #[test]
+    fn doesnt_cap_validity_of_other_prescription_types() {
+        let sut = NewPrescription::new_with_policy(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::Regular),
+            create_prescribed_drugs(1),
+            &PrescriptionPolicy {
+                regular_duration: Duration::days(60),
+                ..Default::default()
+            },
+            Utc::now(),
+        );
+
+        assert!(sut.is_ok());
+    }
+
+    #[test]
+    fn starts_prescription_now_by_default() {
+        let now = "2024-03-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let sut = create_prescription_starting_at(None, now).unwrap();
+
+        assert_eq!(sut.start_date, now);
+        assert_eq!(sut.end_date, now + Duration::days(30));
+    }
+
+    #[test]
+    fn accepts_drugs_of_category_matching_prescription_type() {
+        // The second drug is uncategorized
+        let prescribed_drugs = create_prescribed_drugs(2);
+        let drug_categories =
+            HashMap::from([(prescribed_drugs[0].drug_id, DrugCategory::Antibiotic)]);
+
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::ForAntibiotics),
+            prescribed_drugs,
+        )
+        .unwrap();
+
+        assert_eq!(sut.check_drug_categories(&drug_categories), Ok(()));
+    }
+
+    #[rstest]
+    #[case(PrescriptionType::Regular, DrugCategory::Antibiotic)]
+    #[case(PrescriptionType::ForAntibiotics, DrugCategory::ChronicDisease)]
+    #[case(PrescriptionType::ForImmunologicalDrugs, DrugCategory::Antibiotic)]
+    #[case(PrescriptionType::ForChronicDiseaseDrugs, DrugCategory::Immunological)]
+    fn rejects_drug_of_category_not_matching_prescription_type(
+        #[case] prescription_type: PrescriptionType,
+        #[case] category: DrugCategory,
+    ) {
+        let prescribed_drugs = create_prescribed_drugs(2);
+        let mismatched_drug_id = prescribed_drugs[1].drug_id;
+        let drug_categories = HashMap::from([(mismatched_drug_id, category)]);
+
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(prescription_type),
+            prescribed_drugs,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sut.check_drug_categories(&drug_categories),
+            Err(CreateNewPrescriptionDomainError::DrugCategoryMismatch(
+                mismatched_drug_id,
+                category,
+                prescription_type
+            ))
+        );
+    }
+
+    fn create_restriction(
+        allowed_prescription_types: Vec<PrescriptionType>,
+    ) -> DrugPrescriptionTypeRestriction {
+        DrugPrescriptionTypeRestriction {
+            drug_name: "Euthyrox".into(),
+            allowed_prescription_types,
+        }
+    }
+
+    #[test]
+    fn accepts_drugs_allowed_on_prescription_type() {
+        // The second drug is unrestricted
+        let prescribed_drugs = create_prescribed_drugs(2);
+        let restrictions = HashMap::from([(
+            prescribed_drugs[0].drug_id,
+            create_restriction(vec![
+                PrescriptionType::Regular,
+                PrescriptionType::ForChronicDiseaseDrugs,
+            ]),
+        )]);
+
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::ForChronicDiseaseDrugs),
+            prescribed_drugs,
+        )
+        .unwrap();
+
+        assert_eq!(sut.check_drug_prescription_types(&restrictions), Ok(()));
+        assert_eq!(sut.check_drug_prescription_types(&HashMap::new()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_drug_not_allowed_on_prescription_type() {
+        let prescribed_drugs = create_prescribed_drugs(2);
+        let restrictions = HashMap::from([(
+            prescribed_drugs[1].drug_id,
+            create_restriction(vec![PrescriptionType::ForChronicDiseaseDrugs]),
+        )]);
+
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::Regular),
+            prescribed_drugs,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sut.check_drug_prescription_types(&restrictions),
+            Err(
+                CreateNewPrescriptionDomainError::DrugNotAllowedOnPrescriptionType(
+                    "Euthyrox".into(),
+                    PrescriptionType::Regular
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn attaches_diagnoses_to_prescription() {
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            create_prescribed_drugs(1),
+        )
+        .unwrap()
+        .with_diagnoses(vec!["J06.9".into(), "R50".into()])
+        .unwrap();
+
+        assert_eq!(sut.diagnoses, vec!["J06.9", "R50"]);
+    }
+
+    #[rstest]
+    #[case("J06.")]
+    #[case("j06.9")]
+    #[case("J6")]
+    #[case("")]
+    fn rejects_diagnosis_code_with_invalid_format(#[case] code: &str) {
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            create_prescribed_drugs(1),
+        )
+        .unwrap();
+
+        assert_eq!(
+            sut.with_diagnoses(vec!["J06.9".into(), code.into()]),
+            Err(CreateNewPrescriptionDomainError::InvalidDiagnosisCode(
+                code.into()
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_diagnosis_code() {
+        let sut = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            None,
+            create_prescribed_drugs(1),
+        )
+        .unwrap();
+
+        assert_eq!(
+            sut.with_diagnoses(vec!["J06.9".into(), "J06.9".into()]),
+            Err(CreateNewPrescriptionDomainError::DuplicateDiagnosisCode(
+                "J06.9".into()
+            ))
+        );
+    }
+
+    #[rstest]
+    #[case(Some(17), false)]
+    #[case(Some(18), true)]
+    #[case(None, false)]
+    fn checks_age_of_patient_for_adult_only_prescription_types(
+        #[case] patient_age: Option<u32>,
+        #[case] is_allowed: bool,
+    ) {
+        let policy = PrescriptionPolicy {
+            adult_only_prescription_types: vec![PrescriptionType::ForAntibiotics],
+            ..Default::default()
+        };
+        let adult_only = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::ForAntibiotics),
+            create_prescribed_drugs(1),
+        )
+        .unwrap();
+        let unrestricted = NewPrescription::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            Some(PrescriptionType::Regular),
+            create_prescribed_drugs(1),
+        )
+        .unwrap();
+
+        assert_eq!(
+            adult_only.check_patient_age(patient_age, &policy).is_ok(),
+            is_allowed
+        );
+        assert_eq!(unrestricted.check_patient_age(patient_age, &policy), Ok(()));
+    }
+
+    #[rstest]
+    #[case("92022900002", Some(DrugCategory::Antibiotic), false)]
+    #[case("92022900002", Some(DrugCategory::ChronicDisease), true)]
+    #[case("92022900002", None, true)]
+    #[case("99031301347", Some(DrugCategory::Antibiotic), true)]
+    fn checks_self_prescription_of_restricted_drug_categories(
+        #[case] patient_pesel_number: &str,
+        #[case] drug_category: Option<DrugCategory>,
+        #[case] is_allowed: bool,
+    ) {
+        let policy = PrescriptionPolicy {
+            self_prescription_restricted_categories: vec![DrugCategory::Antibiotic],
+            ..Default::default()
+        };
+        let prescribed_drugs = create_prescribed_drugs(1);
+        let drug_categories = drug_category
+            .map(|category| HashMap::from([(prescribed_drugs[0].drug_id, category)]))
+            .unwrap_or_default();
+        let sut =
+            NewPrescription::new(Uuid::new_v4(), Uuid::new_v4(), None, None, prescribed_drugs)
+                .unwrap();
+
+        assert_eq!(
+            sut.check_self_prescription(
+                "92022900002",
+                patient_pesel_number,
+                &drug_categories,
+                &policy
+            )
+            .is_ok(),
+            is_allowed
+        );
+        assert_eq!(
+            sut.check_self_prescription(
+                "92022900002",
+                patient_pesel_number,
+                &drug_categories,
+                &PrescriptionPolicy::default()
+            ),
+            Ok(())
+        );
+    }
 }
@@ -0,0 +1,203 @@
+// Prescription template:
+//  - belongs to the doctor who created it, only they can use, update or delete it
+//  - has a name, so that the doctor can tell their templates apart
+//  - has the same prescribed drugs rules as a prescription, so that it can always be instantiated
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use crate::domain::prescriptions::{
+    entities::{NewPrescribedDrug, NewPrescriptionTemplate},
+    policy::PrescriptionPolicy,
+};
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum CreateNewPrescriptionTemplateDomainError {
+    #[error("Template name can't be empty")]
+    EmptyName,
+    #[error("Template name can't be longer than {0} characters")]
+    NameTooLong(usize),
+    #[error("Template must have at least one prescribed drug")]
+    NoPrescribedDrugs,
+    #[error("Quantity of drug with id {0} can't be 0")]
+    InvalidDrugQuantity(Uuid),
+    #[error("Quantity of drug with id {0} can't be greater than {max}", max = i32::MAX)]
+    DrugQuantityTooLarge(Uuid),
+    #[error("Can't add two drugs with the same id {0}")]
+    DuplicateDrugId(Uuid),
+    #[error("Can't add more than {0} different drugs to one template")]
+    TooManyDrugs(usize),
+}
+
+pub const MAX_TEMPLATE_NAME_LENGTH: usize = 100;
+
+impl NewPrescriptionTemplate {
+    pub fn new(
+        doctor_id: Uuid,
+        name: String,
+        prescribed_drugs: Vec<NewPrescribedDrug>,
+        policy: &PrescriptionPolicy,
+    ) -> Result<Self, CreateNewPrescriptionTemplateDomainError> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            Err(CreateNewPrescriptionTemplateDomainError::EmptyName)?;
+        }
+        if name.chars().count() > MAX_TEMPLATE_NAME_LENGTH {
+            Err(CreateNewPrescriptionTemplateDomainError::NameTooLong(
+                MAX_TEMPLATE_NAME_LENGTH,
+            ))?;
+        }
+
+        if prescribed_drugs.is_empty() {
+            Err(CreateNewPrescriptionTemplateDomainError::NoPrescribedDrugs)?;
+        }
+
+        let mut ids_hashset: HashSet<Uuid> = HashSet::new();
+        for prescribed_drug in &prescribed_drugs {
+            if prescribed_drug.quantity == 0 {
+                Err(
+                    CreateNewPrescriptionTemplateDomainError::InvalidDrugQuantity(
+                        prescribed_drug.drug_id,
+                    ),
+                )?;
+            }
+            if i32::try_from(prescribed_drug.quantity).is_err() {
+                Err(
+                    CreateNewPrescriptionTemplateDomainError::DrugQuantityTooLarge(
+                        prescribed_drug.drug_id,
+                    ),
+                )?;
+            }
+            if !ids_hashset.insert(prescribed_drug.drug_id) {
+                Err(CreateNewPrescriptionTemplateDomainError::DuplicateDrugId(
+                    prescribed_drug.drug_id,
+                ))?;
+            }
+        }
+
+        if ids_hashset.len() > policy.max_prescribed_drugs {
+            Err(CreateNewPrescriptionTemplateDomainError::TooManyDrugs(
+                policy.max_prescribed_drugs,
+            ))?;
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            doctor_id,
+            name,
+            prescribed_drugs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{CreateNewPrescriptionTemplateDomainError, MAX_TEMPLATE_NAME_LENGTH};
+    use crate::domain::prescriptions::{
+        entities::{NewPrescribedDrug, NewPrescriptionTemplate},
+        policy::PrescriptionPolicy,
+    };
+
+    fn create_prescribed_drug(quantity: u32) -> NewPrescribedDrug {
+        NewPrescribedDrug {
+            drug_id: Uuid::new_v4(),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn creates_prescription_template() {
+        let doctor_id = Uuid::new_v4();
+        let prescribed_drugs = vec![create_prescribed_drug(1), create_prescribed_drug(2)];
+
+        let sut = NewPrescriptionTemplate::new(
+            doctor_id,
+            "  Flu season ".into(),
+            prescribed_drugs.clone(),
+            &PrescriptionPolicy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(sut.doctor_id, doctor_id);
+        assert_eq!(sut.name, "Flu season");
+        assert_eq!(sut.prescribed_drugs, prescribed_drugs);
+    }
+
+    #[test]
+    fn doesnt_create_template_with_empty_or_too_long_name() {
+        let policy = PrescriptionPolicy::default();
+
+        assert_eq!(
+            NewPrescriptionTemplate::new(
+                Uuid::new_v4(),
+                "   ".into(),
+                vec![create_prescribed_drug(1)],
+                &policy,
+            ),
+            Err(CreateNewPrescriptionTemplateDomainError::EmptyName)
+        );
+        assert_eq!(
+            NewPrescriptionTemplate::new(
+                Uuid::new_v4(),
+                "a".repeat(MAX_TEMPLATE_NAME_LENGTH + 1),
+                vec![create_prescribed_drug(1)],
+                &policy,
+            ),
+            Err(CreateNewPrescriptionTemplateDomainError::NameTooLong(
+                MAX_TEMPLATE_NAME_LENGTH
+            ))
+        );
+    }
+
+    #[test]
+    fn doesnt_create_template_with_invalid_prescribed_drugs() {
+        let policy = PrescriptionPolicy::default();
+        let duplicated_drug = create_prescribed_drug(1);
+        let zero_quantity_drug = create_prescribed_drug(0);
+
+        assert_eq!(
+            NewPrescriptionTemplate::new(Uuid::new_v4(), "Flu".into(), vec![], &policy),
+            Err(CreateNewPrescriptionTemplateDomainError::NoPrescribedDrugs)
+        );
+        assert_eq!(
+            NewPrescriptionTemplate::new(
+                Uuid::new_v4(),
+                "Flu".into(),
+                vec![zero_quantity_drug.clone()],
+                &policy,
+            ),
+            Err(
+                CreateNewPrescriptionTemplateDomainError::InvalidDrugQuantity(
+                    zero_quantity_drug.drug_id
+                )
+            )
+        );
+        assert_eq!(
+            NewPrescriptionTemplate::new(
+                Uuid::new_v4(),
+                "Flu".into(),
+                vec![duplicated_drug.clone(), duplicated_drug.clone()],
+                &policy,
+            ),
+            Err(CreateNewPrescriptionTemplateDomainError::DuplicateDrugId(
+                duplicated_drug.drug_id
+            ))
+        );
+        assert_eq!(
+            NewPrescriptionTemplate::new(
+                Uuid::new_v4(),
+                "Flu".into(),
+                (0..=policy.max_prescribed_drugs)
+                    .map(|_| create_prescribed_drug(1))
+                    .collect(),
+                &policy,
+            ),
+            Err(CreateNewPrescriptionTemplateDomainError::TooManyDrugs(
+                policy.max_prescribed_drugs
+            ))
+        );
+    }
+}
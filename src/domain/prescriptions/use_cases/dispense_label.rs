@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::domain::{
+    drugs::entities::Drug,
+    prescriptions::entities::{DrugLabel, Prescription},
+};
+
+impl Prescription {
+    // One label per prescribed drug, in the order of the prescription. Drugs missing from `drugs`
+    // are labeled by their id and without a dosage
+    pub fn dispense_label(&self, drugs: &HashMap<Uuid, Drug>) -> Vec<DrugLabel> {
+        self.prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| {
+                let drug = drugs.get(&prescribed_drug.drug_id);
+                let drug_name = drug
+                    .map(|drug| drug.name.clone())
+                    .unwrap_or_else(|| prescribed_drug.drug_id.to_string());
+                let dosage = drug.map(Drug::describe_dosage);
+                let text = match &dosage {
+                    Some(dosage) => format!(
+                        "{} ({}) x{}, patient: {}",
+                        drug_name, dosage, prescribed_drug.quantity, self.patient.name
+                    ),
+                    None => format!(
+                        "{} x{}, patient: {}",
+                        drug_name, prescribed_drug.quantity, self.patient.name
+                    ),
+                };
+
+                DrugLabel {
+                    drug_id: prescribed_drug.drug_id,
+                    drug_name,
+                    quantity: prescribed_drug.quantity,
+                    dosage,
+                    patient_name: self.patient.name.clone(),
+                    text,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::{
+        drugs::entities::{Drug, DrugContentType},
+        prescriptions::entities::{
+            PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFillStatus,
+            PrescriptionPatient, PrescriptionType,
+        },
+    };
+
+    fn create_mock_drug(name: &str, content_type: DrugContentType) -> Drug {
+        let now = Utc::now();
+
+        Drug {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            content_type,
+            pills_count: None,
+            mg_per_pill: None,
+            ml_per_pill: None,
+            volume_ml: None,
+            doses_count: None,
+            mcg_per_dose: None,
+            ml_per_dose: None,
+            unit_price_cents: None,
+            manufacturer: None,
+            barcode: None,
+            category: None,
+            allowed_prescription_types: vec![],
+            stock: 0,
+            created_at: now,
+            updated_at: now,
+            deactivated_at: None,
+            created_by: None,
+        }
+    }
+
+    fn create_mock_prescription(prescribed_drugs: Vec<(Uuid, i32)>) -> Prescription {
+        let prescription_id = Uuid::new_v4();
+        let start_date = Utc::now();
+
+        Prescription {
+            id: prescription_id,
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type: PrescriptionType::Regular,
+            start_date,
+            end_date: start_date + Duration::days(30),
+            prescribed_drugs: prescribed_drugs
+                .into_iter()
+                .map(|(drug_id, quantity)| PrescribedDrug {
+                    id: Uuid::new_v4(),
+                    drug_id,
+                    prescription_id,
+                    quantity,
+                    dispensed_quantity: 0,
+                    unit_price_cents: None,
+                    created_at: start_date,
+                    updated_at: start_date,
+                })
+                .collect(),
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at: start_date,
+            updated_at: start_date,
+        }
+    }
+
+    #[test]
+    fn creates_label_for_each_prescribed_drug() {
+        let apap = Drug {
+            pills_count: Some(30),
+            mg_per_pill: Some(300),
+            ..create_mock_drug("Apap", DrugContentType::SolidPills)
+        };
+        let flegamax = Drug {
+            volume_ml: Some(200),
+            ..create_mock_drug("Flegamax", DrugContentType::BottleOfLiquid)
+        };
+        let ventolin = Drug {
+            doses_count: Some(200),
+            mcg_per_dose: Some(100),
+            ..create_mock_drug("Ventolin", DrugContentType::Inhaler)
+        };
+        let prescription =
+            create_mock_prescription(vec![(apap.id, 2), (flegamax.id, 1), (ventolin.id, 3)]);
+        let drugs = HashMap::from([
+            (apap.id, apap.clone()),
+            (flegamax.id, flegamax.clone()),
+            (ventolin.id, ventolin.clone()),
+        ]);
+
+        let labels = prescription.dispense_label(&drugs);
+
+        let texts: Vec<&str> = labels.iter().map(|label| label.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Apap (30 pills, 300 mg each) x2, patient: John Patient",
+                "Flegamax (200 ml bottle) x1, patient: John Patient",
+                "Ventolin (200 doses, 100 mcg each) x3, patient: John Patient",
+            ]
+        );
+        assert_eq!(labels[2].drug_id, ventolin.id);
+        assert_eq!(labels[2].quantity, 3);
+        assert_eq!(labels[2].patient_name, "John Patient");
+    }
+
+    #[test]
+    fn labels_unknown_drug_by_its_id() {
+        let drug_id = Uuid::new_v4();
+        let prescription = create_mock_prescription(vec![(drug_id, 1)]);
+
+        let labels = prescription.dispense_label(&HashMap::new());
+
+        assert_eq!(labels[0].drug_name, drug_id.to_string());
+        assert_eq!(labels[0].dosage, None);
+        assert_eq!(
+            labels[0].text,
+            format!("{} x1, patient: John Patient", drug_id)
+        );
+    }
+}
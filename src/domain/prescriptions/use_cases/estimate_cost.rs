@@ -0,0 +1,94 @@
+use crate::domain::prescriptions::entities::Prescription;
+
+impl Prescription {
+    // Drugs without a price are skipped, the estimate is None if none of the drugs has a price
+    pub fn estimate_cost_cents(&self) -> Option<i64> {
+        self.prescribed_drugs
+            .iter()
+            .filter_map(|prescribed_drug| {
+                prescribed_drug.unit_price_cents.map(|unit_price_cents| {
+                    prescribed_drug.quantity as i64 * unit_price_cents as i64
+                })
+            })
+            .reduce(|total, cost| total + cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::prescriptions::entities::{
+        PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFillStatus,
+        PrescriptionPatient, PrescriptionType,
+    };
+
+    fn create_mock_prescription(prescribed_drugs: Vec<(i32, Option<i32>)>) -> Prescription {
+        let prescription_id = Uuid::new_v4();
+        let start_date = Utc::now();
+
+        Prescription {
+            id: prescription_id,
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type: PrescriptionType::Regular,
+            start_date,
+            end_date: start_date + Duration::days(30),
+            prescribed_drugs: prescribed_drugs
+                .into_iter()
+                .map(|(quantity, unit_price_cents)| PrescribedDrug {
+                    id: Uuid::new_v4(),
+                    drug_id: Uuid::new_v4(),
+                    prescription_id,
+                    quantity,
+                    dispensed_quantity: 0,
+                    unit_price_cents,
+                    created_at: start_date,
+                    updated_at: start_date,
+                })
+                .collect(),
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at: start_date,
+            updated_at: start_date,
+        }
+    }
+
+    #[test]
+    fn estimates_cost_of_priced_drugs() {
+        let prescription = create_mock_prescription(vec![(2, Some(1000)), (3, Some(250))]);
+
+        assert_eq!(prescription.estimate_cost_cents(), Some(2750));
+    }
+
+    #[test]
+    fn skips_drugs_without_price() {
+        let prescription =
+            create_mock_prescription(vec![(2, Some(1000)), (3, None), (1, Some(99))]);
+
+        assert_eq!(prescription.estimate_cost_cents(), Some(2099));
+    }
+
+    #[test]
+    fn estimate_is_none_if_no_drug_has_price() {
+        let prescription = create_mock_prescription(vec![(2, None), (3, None)]);
+
+        assert_eq!(prescription.estimate_cost_cents(), None);
+    }
+}
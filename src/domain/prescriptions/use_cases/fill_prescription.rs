@@ -1,7 +1,23 @@
-use chrono::Utc;
+// Prescription fill:
+//  - dispenses the given quantities of prescribed drugs, or everything that remains if none are given
+//  - can dispense less than prescribed, the remainder can be dispensed by a subsequent fill
+//  - can't dispense more than remains of a prescribed drug
+//  - the first fill of a prescription with window from fill moves its end date to the fill date
+//    plus the window length
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::domain::prescriptions::entities::{NewPrescriptionFill, Prescription};
+use super::check_fill_eligibility::FillEligibilityError;
+use crate::domain::prescriptions::{
+    entities::{
+        NewDispensedDrug, NewPrescribedDrug, NewPrescriptionFill, Prescription, PrescriptionFill,
+        PrescriptionFillStatus,
+    },
+    prescription_code::PrescriptionCode,
+};
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum PrescriptionFillError {
@@ -9,33 +25,151 @@ pub enum PrescriptionFillError {
     InvalidDate,
     #[error("Prescription is already filled")]
     AlreadyFilled,
+    #[error("Prescription is cancelled")]
+    Cancelled,
     #[error("Prescription code is invalid")]
     InvalidCode,
+    #[error("Fill must dispense at least one drug")]
+    NoDispensedDrugs,
+    #[error("Drug with id {0} is not prescribed on this prescription")]
+    DrugNotPrescribed(Uuid),
+    #[error("Dispensed quantity of drug with id {0} can't be 0")]
+    InvalidDispensedQuantity(Uuid),
+    #[error("Can't dispense drug with id {0} twice in one fill")]
+    DuplicateDrugId(Uuid),
+    #[error("Can't dispense more of drug with id {0} than remains on the prescription")]
+    QuantityExceeded(Uuid),
 }
 
 impl Prescription {
     pub fn fill(
         &self,
         pharmacist_id: Uuid,
-        code: String,
+        code: &PrescriptionCode,
+        dispensed_drugs: Option<Vec<NewPrescribedDrug>>,
+        now: DateTime<Utc>,
     ) -> Result<NewPrescriptionFill, PrescriptionFillError> {
-        let now = Utc::now();
-        if now < self.start_date || now > self.end_date {
-            Err(PrescriptionFillError::InvalidDate)?;
-        }
-        if self.fill.is_some() {
-            Err(PrescriptionFillError::AlreadyFilled)?;
-        }
-        if self.code != code {
-            Err(PrescriptionFillError::InvalidCode)?;
-        }
+        self.check_fill_eligibility(code, now)
+            .map_err(|err| match err {
+                FillEligibilityError::NotYetValid | FillEligibilityError::Expired => {
+                    PrescriptionFillError::InvalidDate
+                }
+                FillEligibilityError::AlreadyFilled => PrescriptionFillError::AlreadyFilled,
+                FillEligibilityError::Cancelled => PrescriptionFillError::Cancelled,
+                FillEligibilityError::CodeMismatch => PrescriptionFillError::InvalidCode,
+            })?;
+
+        let dispensed_drugs = match dispensed_drugs {
+            Some(dispensed_drugs) => self.get_dispensed_drugs(dispensed_drugs)?,
+            None => self
+                .prescribed_drugs
+                .iter()
+                .filter(|prescribed_drug| {
+                    prescribed_drug.dispensed_quantity < prescribed_drug.quantity
+                })
+                .map(|prescribed_drug| NewDispensedDrug {
+                    prescribed_drug_id: prescribed_drug.id,
+                    quantity: (prescribed_drug.quantity - prescribed_drug.dispensed_quantity)
+                        as u32,
+                })
+                .collect(),
+        };
+
+        // The window length is kept from the one set on creation
+        let restarted_window = (self.window_from_fill && self.fills.is_empty())
+            .then(|| self.end_date - self.start_date);
 
         Ok(NewPrescriptionFill {
             id: Uuid::new_v4(),
             pharmacist_id,
             prescription_id: self.id,
+            dispensed_drugs,
+            restarted_window,
         })
     }
+
+    fn get_dispensed_drugs(
+        &self,
+        dispensed_drugs: Vec<NewPrescribedDrug>,
+    ) -> Result<Vec<NewDispensedDrug>, PrescriptionFillError> {
+        if dispensed_drugs.is_empty() {
+            Err(PrescriptionFillError::NoDispensedDrugs)?;
+        }
+
+        let mut ids_hashset: HashSet<Uuid> = HashSet::new();
+        let mut new_dispensed_drugs = vec![];
+        for dispensed_drug in dispensed_drugs {
+            let prescribed_drug = self
+                .prescribed_drugs
+                .iter()
+                .find(|prescribed_drug| prescribed_drug.drug_id == dispensed_drug.drug_id)
+                .ok_or(PrescriptionFillError::DrugNotPrescribed(
+                    dispensed_drug.drug_id,
+                ))?;
+            if dispensed_drug.quantity == 0 {
+                Err(PrescriptionFillError::InvalidDispensedQuantity(
+                    dispensed_drug.drug_id,
+                ))?;
+            }
+            if !ids_hashset.insert(dispensed_drug.drug_id) {
+                Err(PrescriptionFillError::DuplicateDrugId(
+                    dispensed_drug.drug_id,
+                ))?;
+            }
+            let remaining_quantity =
+                (prescribed_drug.quantity - prescribed_drug.dispensed_quantity) as u32;
+            if dispensed_drug.quantity > remaining_quantity {
+                Err(PrescriptionFillError::QuantityExceeded(
+                    dispensed_drug.drug_id,
+                ))?;
+            }
+
+            new_dispensed_drugs.push(NewDispensedDrug {
+                prescribed_drug_id: prescribed_drug.id,
+                quantity: dispensed_drug.quantity,
+            });
+        }
+
+        Ok(new_dispensed_drugs)
+    }
+
+    pub fn compute_fill_status(&self) -> PrescriptionFillStatus {
+        if self.cancelled_at.is_some() {
+            PrescriptionFillStatus::Cancelled
+        } else if self
+            .prescribed_drugs
+            .iter()
+            .all(|prescribed_drug| prescribed_drug.dispensed_quantity >= prescribed_drug.quantity)
+        {
+            PrescriptionFillStatus::Filled
+        } else if self
+            .prescribed_drugs
+            .iter()
+            .any(|prescribed_drug| prescribed_drug.dispensed_quantity > 0)
+        {
+            PrescriptionFillStatus::PartiallyFilled
+        } else {
+            PrescriptionFillStatus::Unfilled
+        }
+    }
+
+    // Updates the prescription in place after the fill has been saved
+    pub fn record_fill(&mut self, new_fill: &NewPrescriptionFill, fill: PrescriptionFill) {
+        for dispensed_drug in &new_fill.dispensed_drugs {
+            if let Some(prescribed_drug) = self
+                .prescribed_drugs
+                .iter_mut()
+                .find(|prescribed_drug| prescribed_drug.id == dispensed_drug.prescribed_drug_id)
+            {
+                prescribed_drug.dispensed_quantity += dispensed_drug.quantity as i32;
+            }
+        }
+        if let Some(window) = new_fill.restarted_window {
+            self.end_date = fill.created_at + window;
+        }
+        self.fills.push(fill);
+        self.fill_status = self.compute_fill_status();
+    }
 }
 
 #[cfg(test)]
@@ -45,9 +179,10 @@ mod tests {
 
     use crate::domain::prescriptions::{
         entities::{
-            PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFill,
-            PrescriptionPatient, PrescriptionType,
+            NewPrescribedDrug, PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFill,
+            PrescriptionFillStatus, PrescriptionPatient, PrescriptionType,
         },
+        policy::PrescriptionPolicy,
         use_cases::fill_prescription::PrescriptionFillError,
     };
 
@@ -55,7 +190,7 @@ mod tests {
         let prescription_id = Uuid::new_v4();
         let prescription_type = PrescriptionType::Regular;
         let start_date = Utc::now() - Duration::hours(1);
-        let end_date = start_date + prescription_type.get_duration();
+        let end_date = start_date + PrescriptionPolicy::default().get_duration(&prescription_type);
 
         Prescription {
             id: prescription_id,
@@ -63,14 +198,15 @@ mod tests {
                 id: Uuid::new_v4(),
                 name: "John Doctor".to_string(),
                 pesel_number: "99031301347".to_string(),
-                pwz_number: "8463856".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
             },
             patient: PrescriptionPatient {
                 id: Uuid::new_v4(),
                 name: "John Patient".to_string(),
                 pesel_number: "92022900002".to_string(),
             },
-            code: "12345678".to_string(),
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
             prescription_type,
             start_date,
             end_date,
@@ -78,21 +214,43 @@ mod tests {
                 id: Uuid::new_v4(),
                 drug_id: Uuid::new_v4(),
                 prescription_id,
-                quantity: 1,
+                quantity: 3,
+                dispensed_quantity: 0,
+                unit_price_cents: None,
                 created_at: start_date,
                 updated_at: start_date,
             }],
-            fill: None,
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
             created_at: start_date,
             updated_at: start_date,
         }
     }
 
+    fn create_mock_fill(prescription: &Prescription) -> PrescriptionFill {
+        PrescriptionFill {
+            id: Uuid::new_v4(),
+            pharmacist_id: Uuid::new_v4(),
+            prescription_id: prescription.id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
     #[test]
     fn fills_prescription() {
         let prescription = create_mock_prescription();
 
-        let sut = prescription.fill(Uuid::new_v4(), "12345678".into());
+        let sut = prescription.fill(
+            Uuid::new_v4(),
+            &"12345678".try_into().unwrap(),
+            None,
+            Utc::now(),
+        );
 
         assert!(sut.is_ok())
     }
@@ -100,9 +258,9 @@ mod tests {
     #[test]
     fn doesnt_fill_if_prescription_the_code_is_invalid() {
         let prescription = create_mock_prescription();
-        let code = "12345679".into();
+        let code = &"12345679".try_into().unwrap();
 
-        let sut = prescription.fill(Uuid::new_v4(), code);
+        let sut = prescription.fill(Uuid::new_v4(), code, None, Utc::now());
 
         assert_eq!(sut, Err(PrescriptionFillError::InvalidCode));
     }
@@ -112,7 +270,12 @@ mod tests {
         let mut prescription = create_mock_prescription();
         prescription.start_date = Utc::now() + Duration::minutes(1);
 
-        let sut = prescription.fill(Uuid::new_v4(), "12345678".into());
+        let sut = prescription.fill(
+            Uuid::new_v4(),
+            &"12345678".try_into().unwrap(),
+            None,
+            Utc::now(),
+        );
 
         assert_eq!(sut, Err(PrescriptionFillError::InvalidDate));
     }
@@ -122,7 +285,12 @@ mod tests {
         let mut prescription: Prescription = create_mock_prescription();
         prescription.end_date = Utc::now() - Duration::minutes(1);
 
-        let sut = prescription.fill(Uuid::new_v4(), "12345678".into());
+        let sut = prescription.fill(
+            Uuid::new_v4(),
+            &"12345678".try_into().unwrap(),
+            None,
+            Utc::now(),
+        );
 
         assert_eq!(sut, Err(PrescriptionFillError::InvalidDate));
     }
@@ -130,16 +298,159 @@ mod tests {
     #[test]
     fn doesnt_fill_if_prescription_is_filled() {
         let mut prescription = create_mock_prescription();
-        prescription.fill = Some(PrescriptionFill {
-            id: Uuid::new_v4(),
-            pharmacist_id: Uuid::new_v4(),
-            prescription_id: prescription.id,
-            created_at: Utc::now() - Duration::hours(1),
-            updated_at: Utc::now() - Duration::hours(1),
-        });
+        let new_fill = prescription
+            .fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        prescription.record_fill(&new_fill, create_mock_fill(&prescription));
 
-        let sut = prescription.fill(Uuid::new_v4(), "12345678".into());
+        let sut = prescription.fill(
+            Uuid::new_v4(),
+            &"12345678".try_into().unwrap(),
+            None,
+            Utc::now(),
+        );
 
         assert_eq!(sut, Err(PrescriptionFillError::AlreadyFilled));
     }
+
+    #[test]
+    fn fills_prescription_partially_and_then_dispenses_the_remainder() {
+        let mut prescription = create_mock_prescription();
+        let drug_id = prescription.prescribed_drugs[0].drug_id;
+
+        let new_fill = prescription
+            .fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                Some(vec![NewPrescribedDrug {
+                    drug_id,
+                    quantity: 1,
+                }]),
+                Utc::now(),
+            )
+            .unwrap();
+        prescription.record_fill(&new_fill, create_mock_fill(&prescription));
+
+        assert_eq!(new_fill.dispensed_drugs[0].quantity, 1);
+        assert_eq!(prescription.prescribed_drugs[0].dispensed_quantity, 1);
+        assert_eq!(
+            prescription.fill_status,
+            PrescriptionFillStatus::PartiallyFilled
+        );
+
+        let new_fill = prescription
+            .fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        prescription.record_fill(&new_fill, create_mock_fill(&prescription));
+
+        assert_eq!(new_fill.dispensed_drugs[0].quantity, 2);
+        assert_eq!(prescription.prescribed_drugs[0].dispensed_quantity, 3);
+        assert_eq!(prescription.fill_status, PrescriptionFillStatus::Filled);
+        assert_eq!(prescription.fills.len(), 2);
+    }
+
+    #[test]
+    fn first_fill_restarts_window_of_prescription_with_window_from_fill() {
+        let mut prescription = create_mock_prescription();
+        prescription.window_from_fill = true;
+        let window = prescription.end_date - prescription.start_date;
+        let drug_id = prescription.prescribed_drugs[0].drug_id;
+
+        let new_fill = prescription
+            .fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                Some(vec![NewPrescribedDrug {
+                    drug_id,
+                    quantity: 1,
+                }]),
+                Utc::now(),
+            )
+            .unwrap();
+        let fill = create_mock_fill(&prescription);
+        prescription.record_fill(&new_fill, fill);
+
+        assert_eq!(new_fill.restarted_window, Some(window));
+        assert_eq!(prescription.end_date, fill.created_at + window);
+
+        let new_fill = prescription
+            .fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        prescription.record_fill(&new_fill, create_mock_fill(&prescription));
+
+        assert_eq!(new_fill.restarted_window, None);
+        assert_eq!(prescription.end_date, fill.created_at + window);
+    }
+
+    #[test]
+    fn fill_doesnt_restart_window_of_prescription_valid_from_start_date() {
+        let mut prescription = create_mock_prescription();
+        let end_date = prescription.end_date;
+
+        let new_fill = prescription
+            .fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        prescription.record_fill(&new_fill, create_mock_fill(&prescription));
+
+        assert_eq!(new_fill.restarted_window, None);
+        assert_eq!(prescription.end_date, end_date);
+    }
+
+    #[test]
+    fn doesnt_fill_if_dispensed_drugs_are_invalid() {
+        let prescription = create_mock_prescription();
+        let drug_id = prescription.prescribed_drugs[0].drug_id;
+        let fill = |dispensed_drugs: Vec<(Uuid, u32)>| {
+            prescription.fill(
+                Uuid::new_v4(),
+                &"12345678".try_into().unwrap(),
+                Some(
+                    dispensed_drugs
+                        .into_iter()
+                        .map(|(drug_id, quantity)| NewPrescribedDrug { drug_id, quantity })
+                        .collect(),
+                ),
+                Utc::now(),
+            )
+        };
+
+        assert_eq!(fill(vec![]), Err(PrescriptionFillError::NoDispensedDrugs));
+        assert_eq!(
+            fill(vec![(drug_id, 4)]),
+            Err(PrescriptionFillError::QuantityExceeded(drug_id))
+        );
+        assert_eq!(
+            fill(vec![(drug_id, 0)]),
+            Err(PrescriptionFillError::InvalidDispensedQuantity(drug_id))
+        );
+        assert_eq!(
+            fill(vec![(drug_id, 1), (drug_id, 1)]),
+            Err(PrescriptionFillError::DuplicateDrugId(drug_id))
+        );
+        let other_drug_id = Uuid::new_v4();
+        assert_eq!(
+            fill(vec![(other_drug_id, 1)]),
+            Err(PrescriptionFillError::DrugNotPrescribed(other_drug_id))
+        );
+    }
 }
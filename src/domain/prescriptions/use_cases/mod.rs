@@ -1,2 +1,7 @@
+pub mod check_fill_eligibility;
 pub mod create_prescription;
+pub mod create_prescription_template;
+pub mod dispense_label;
+pub mod estimate_cost;
 pub mod fill_prescription;
+pub mod verify_prescription;
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+
+use super::check_fill_eligibility::FillEligibilityError;
+use crate::domain::prescriptions::{
+    entities::{Prescription, PrescriptionVerificationStatus},
+    prescription_code::PrescriptionCode,
+};
+
+impl Prescription {
+    // Shares the eligibility check with fill, so the status names the error fill would return
+    pub fn verify(
+        &self,
+        code: &PrescriptionCode,
+        now: DateTime<Utc>,
+    ) -> PrescriptionVerificationStatus {
+        match self.check_fill_eligibility(code, now) {
+            Ok(()) => PrescriptionVerificationStatus::Valid,
+            Err(FillEligibilityError::Cancelled) => PrescriptionVerificationStatus::Cancelled,
+            Err(FillEligibilityError::NotYetValid | FillEligibilityError::Expired) => {
+                PrescriptionVerificationStatus::Expired
+            }
+            Err(FillEligibilityError::AlreadyFilled) => {
+                PrescriptionVerificationStatus::AlreadyFilled
+            }
+            Err(FillEligibilityError::CodeMismatch) => PrescriptionVerificationStatus::CodeMismatch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::prescriptions::{
+        entities::{
+            PrescribedDrug, Prescription, PrescriptionDoctor, PrescriptionFillStatus,
+            PrescriptionPatient, PrescriptionType, PrescriptionVerificationStatus,
+        },
+        policy::PrescriptionPolicy,
+    };
+
+    fn create_mock_prescription() -> Prescription {
+        let prescription_id = Uuid::new_v4();
+        let prescription_type = PrescriptionType::Regular;
+        let start_date = Utc::now() - Duration::hours(1);
+        let end_date = start_date + PrescriptionPolicy::default().get_duration(&prescription_type);
+
+        Prescription {
+            id: prescription_id,
+            doctor: PrescriptionDoctor {
+                id: Uuid::new_v4(),
+                name: "John Doctor".to_string(),
+                pesel_number: "99031301347".to_string(),
+                pwz_number: "8463856".try_into().unwrap(),
+            },
+            patient: PrescriptionPatient {
+                id: Uuid::new_v4(),
+                name: "John Patient".to_string(),
+                pesel_number: "92022900002".to_string(),
+            },
+            code: "12345678".try_into().unwrap(),
+            diagnoses: vec![],
+            prescription_type,
+            start_date,
+            end_date,
+            prescribed_drugs: vec![PrescribedDrug {
+                id: Uuid::new_v4(),
+                drug_id: Uuid::new_v4(),
+                prescription_id,
+                quantity: 1,
+                dispensed_quantity: 0,
+                unit_price_cents: None,
+                created_at: start_date,
+                updated_at: start_date,
+            }],
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: false,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at: start_date,
+            updated_at: start_date,
+        }
+    }
+
+    #[test]
+    fn verifies_prescription() {
+        let mut prescription = create_mock_prescription();
+
+        assert_eq!(
+            prescription.verify(&"12345678".try_into().unwrap(), Utc::now()),
+            PrescriptionVerificationStatus::Valid
+        );
+        assert_eq!(
+            prescription.verify(&"12345679".try_into().unwrap(), Utc::now()),
+            PrescriptionVerificationStatus::CodeMismatch
+        );
+
+        prescription.fill_status = PrescriptionFillStatus::PartiallyFilled;
+        assert_eq!(
+            prescription.verify(&"12345678".try_into().unwrap(), Utc::now()),
+            PrescriptionVerificationStatus::Valid
+        );
+
+        prescription.fill_status = PrescriptionFillStatus::Filled;
+        assert_eq!(
+            prescription.verify(&"12345678".try_into().unwrap(), Utc::now()),
+            PrescriptionVerificationStatus::AlreadyFilled
+        );
+
+        prescription.end_date = Utc::now() - Duration::minutes(1);
+        assert_eq!(
+            prescription.verify(&"12345678".try_into().unwrap(), Utc::now()),
+            PrescriptionVerificationStatus::Expired
+        );
+    }
+}
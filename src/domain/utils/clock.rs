@@ -0,0 +1,61 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Duration, Utc};
+
+// Source of the current time, so that time-dependent logic can be tested deterministically
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+// Stands still until it's moved by hand
+pub struct FixedClock {
+    now: RwLock<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    #[allow(dead_code)]
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: RwLock::new(now),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn advance(&self, duration: Duration) {
+        *self.now.write().unwrap() += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.read().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::{Clock, FixedClock};
+
+    #[test]
+    fn fixed_clock_stands_still_until_advanced() {
+        let now: DateTime<Utc> = "2024-03-15T12:00:00Z".parse().unwrap();
+        let clock = FixedClock::new(now);
+
+        assert_eq!(clock.now(), now);
+        assert_eq!(clock.now(), now);
+
+        clock.advance(Duration::days(2));
+
+        assert_eq!(clock.now(), now + Duration::days(2));
+    }
+}
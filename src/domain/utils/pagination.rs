@@ -1,24 +1,38 @@
-#[derive(thiserror::Error, Debug, PartialEq)]
+#[derive(thiserror::Error, Debug, PartialEq, Clone, Copy)]
 pub enum PaginationError {
-    #[error("Invalid page size: page size must be at least 1")]
-    InvalidPageSize,
     #[error("Invalid page: page must be at least 0")]
-    InvalidPage,
+    NegativePage,
+    #[error("Invalid page size: page size must be at least 1")]
+    ZeroOrNegativePageSize,
+    #[error("Invalid page size: page size must be at most {MAX_PAGE_SIZE}")]
+    PageSizeTooLarge,
 }
 
+// Used by the resources that don't define their own default page size
+pub const DEFAULT_PAGE_SIZE: i64 = 10;
+
+// Larger pages would let a single request read whole tables
+pub const MAX_PAGE_SIZE: i64 = 100;
+
 pub fn get_pagination_params(
     page: Option<i64>,
     page_size: Option<i64>,
+    default_page_size: i64,
 ) -> Result<(i64, i64), PaginationError> {
     let page = page.unwrap_or(0);
-    let page_size = page_size.unwrap_or(10);
+    let page_size = page_size.unwrap_or(default_page_size);
     if page_size < 1 {
-        Err(PaginationError::InvalidPageSize)?;
+        Err(PaginationError::ZeroOrNegativePageSize)?;
+    }
+    if page_size > MAX_PAGE_SIZE {
+        Err(PaginationError::PageSizeTooLarge)?;
     }
     if page < 0 {
-        Err(PaginationError::InvalidPage)?;
+        Err(PaginationError::NegativePage)?;
     }
-    let offset = page * page_size;
+    // Pages far beyond the data would overflow the offset, saturating keeps them past the last row
+    // so they come back empty like any other page out of range
+    let offset = page.saturating_mul(page_size);
 
     Ok((page_size, offset))
 }
@@ -29,19 +43,84 @@ mod tests {
 
     #[test]
     fn test_get_pagination_params() {
-        assert_eq!(get_pagination_params(None, None).unwrap(), (10, 0));
-        assert_eq!(get_pagination_params(Some(0), Some(10)).unwrap(), (10, 0));
-        assert_eq!(get_pagination_params(Some(1), Some(10)).unwrap(), (10, 10));
-        assert_eq!(get_pagination_params(Some(1), Some(5)).unwrap(), (5, 5));
-        assert_eq!(get_pagination_params(Some(2), Some(5)).unwrap(), (5, 10));
-        assert_eq!(get_pagination_params(Some(13), Some(7)).unwrap(), (7, 91));
         assert_eq!(
-            get_pagination_params(Some(0), Some(0)),
-            Err(PaginationError::InvalidPageSize)
+            get_pagination_params(None, None, DEFAULT_PAGE_SIZE).unwrap(),
+            (10, 0)
+        );
+        assert_eq!(
+            get_pagination_params(Some(0), Some(10), DEFAULT_PAGE_SIZE).unwrap(),
+            (10, 0)
+        );
+        assert_eq!(
+            get_pagination_params(Some(1), Some(10), DEFAULT_PAGE_SIZE).unwrap(),
+            (10, 10)
+        );
+        assert_eq!(
+            get_pagination_params(Some(1), Some(5), DEFAULT_PAGE_SIZE).unwrap(),
+            (5, 5)
+        );
+        assert_eq!(
+            get_pagination_params(Some(2), Some(5), DEFAULT_PAGE_SIZE).unwrap(),
+            (5, 10)
+        );
+        assert_eq!(
+            get_pagination_params(Some(13), Some(7), DEFAULT_PAGE_SIZE).unwrap(),
+            (7, 91)
+        );
+    }
+
+    #[test]
+    fn returns_reason_of_invalid_pagination_params() {
+        assert_eq!(
+            get_pagination_params(Some(-1), Some(10), DEFAULT_PAGE_SIZE),
+            Err(PaginationError::NegativePage)
+        );
+        assert_eq!(
+            get_pagination_params(Some(i64::MIN), None, DEFAULT_PAGE_SIZE),
+            Err(PaginationError::NegativePage)
+        );
+        assert_eq!(
+            get_pagination_params(Some(0), Some(0), DEFAULT_PAGE_SIZE),
+            Err(PaginationError::ZeroOrNegativePageSize)
+        );
+        assert_eq!(
+            get_pagination_params(Some(0), Some(-5), DEFAULT_PAGE_SIZE),
+            Err(PaginationError::ZeroOrNegativePageSize)
+        );
+        assert_eq!(
+            get_pagination_params(Some(0), Some(MAX_PAGE_SIZE + 1), DEFAULT_PAGE_SIZE),
+            Err(PaginationError::PageSizeTooLarge)
+        );
+        assert_eq!(
+            get_pagination_params(Some(0), Some(i64::MAX), DEFAULT_PAGE_SIZE),
+            Err(PaginationError::PageSizeTooLarge)
+        );
+        assert_eq!(
+            get_pagination_params(Some(0), Some(MAX_PAGE_SIZE), DEFAULT_PAGE_SIZE).unwrap(),
+            (MAX_PAGE_SIZE, 0)
         );
+    }
+
+    #[test]
+    fn saturates_offset_of_pages_far_beyond_the_data() {
+        assert_eq!(
+            get_pagination_params(Some(i64::MAX), Some(10), DEFAULT_PAGE_SIZE).unwrap(),
+            (10, i64::MAX)
+        );
+        assert_eq!(
+            get_pagination_params(Some(i64::MAX / 2), Some(MAX_PAGE_SIZE), DEFAULT_PAGE_SIZE)
+                .unwrap(),
+            (MAX_PAGE_SIZE, i64::MAX)
+        );
+    }
+
+    #[test]
+    fn uses_given_default_page_size_if_page_size_is_omitted() {
+        assert_eq!(get_pagination_params(None, None, 50).unwrap(), (50, 0));
+        assert_eq!(get_pagination_params(Some(2), None, 20).unwrap(), (20, 40));
         assert_eq!(
-            get_pagination_params(Some(-1), Some(10)),
-            Err(PaginationError::InvalidPage)
+            get_pagination_params(Some(2), Some(5), 20).unwrap(),
+            (5, 10)
         );
     }
 }
@@ -1,3 +1,7 @@
+pub mod validate_barcode;
+pub mod validate_email;
+pub mod validate_icd10_code;
 pub mod validate_name;
 pub mod validate_pesel_number;
 pub mod validate_pwz_number;
+pub mod validate_specialization;
@@ -0,0 +1,59 @@
+#[derive(thiserror::Error, Debug)]
+pub enum BarcodeValidationError {
+    #[error("Barcode must be 8, 12, 13 or 14 characters long and contain only digits")]
+    InvalidFormat,
+    #[error("The checksum of barcode is incorrect")]
+    InvalidChecksum,
+}
+
+// Accepts GTIN-8, GTIN-12 (UPC-A), GTIN-13 (EAN-13) and GTIN-14 codes
+pub fn validate_barcode(barcode: &str) -> anyhow::Result<()> {
+    let allowed_lengths = [8, 12, 13, 14];
+    if !allowed_lengths.contains(&barcode.len()) || !barcode.chars().all(|c| c.is_ascii_digit()) {
+        Err(BarcodeValidationError::InvalidFormat)?;
+    }
+
+    let (checksum_components, control_digit_str) = barcode.split_at(barcode.len() - 1);
+
+    // Weights alternate between 3 and 1 starting from the digit next to the control digit
+    let sum = checksum_components
+        .chars()
+        .rev()
+        .enumerate()
+        .fold(0, |acc, (i, c)| {
+            let digit = c.to_digit(10).unwrap();
+            let weight = if i % 2 == 0 { 3 } else { 1 };
+            acc + digit * weight
+        });
+
+    let control_digit = control_digit_str.parse::<u32>().unwrap();
+    let checksum = (10 - sum % 10) % 10;
+    if checksum != control_digit {
+        Err(BarcodeValidationError::InvalidChecksum)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::validate_barcode;
+
+    #[rstest]
+    #[case("5909990733828", true)]
+    #[case("5900017304007", true)]
+    #[case("96385074", true)]
+    #[case("036000291452", true)]
+    #[case("15909990733825", true)]
+    #[case("5909990733827", false)]
+    #[case("96385075", false)]
+    #[case("590999073382a", false)]
+    #[case("59099907338", false)]
+    #[case("590999073382800", false)]
+    #[case("", false)]
+    fn validates_barcode(#[case] barcode: &str, #[case] expected: bool) {
+        assert_eq!(validate_barcode(barcode).is_ok(), expected)
+    }
+}
@@ -0,0 +1,144 @@
+#[derive(thiserror::Error, Debug)]
+pub enum EmailValidationError {
+    #[error("Email can't be longer than {0} characters")]
+    TooLong(usize),
+    #[error("Email can't contain whitespace")]
+    Whitespace,
+    #[error("Email must contain a single @")]
+    InvalidAtSign,
+    #[error("Email must have a non-empty part before @")]
+    EmptyLocalPart,
+    #[error("Email must have a valid domain after @")]
+    InvalidDomain,
+}
+
+pub fn validate_email(email: &str) -> anyhow::Result<()> {
+    let max_len: usize = 254;
+    if email.chars().count() > max_len {
+        Err(EmailValidationError::TooLong(max_len))?;
+    }
+
+    if email.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        Err(EmailValidationError::Whitespace)?;
+    }
+
+    let (local_part, domain) = match email.split_once('@') {
+        Some((local_part, domain)) if !domain.contains('@') => (local_part, domain),
+        _ => Err(EmailValidationError::InvalidAtSign)?,
+    };
+    if local_part.is_empty() {
+        Err(EmailValidationError::EmptyLocalPart)?;
+    }
+
+    // At least two labels, each made of letters, digits and inner hyphens
+    let labels: Vec<&str> = domain.split('.').collect();
+    let is_valid_label = |label: &&str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    };
+    if labels.len() < 2 || !labels.iter().all(is_valid_label) {
+        Err(EmailValidationError::InvalidDomain)?;
+    }
+
+    Ok(())
+}
+
+// Trims the email and lowercases its domain, which is case insensitive unlike the local part
+pub fn normalize_email(email: &str) -> anyhow::Result<String> {
+    let email = email.trim();
+    validate_email(email)?;
+
+    let (local_part, domain) = email.split_once('@').unwrap();
+
+    Ok(format!("{}@{}", local_part, domain.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{normalize_email, validate_email, EmailValidationError};
+
+    #[rstest]
+    #[case("john.doe@gmail.com")]
+    #[case("John.Doe+prescriptions@Gmail.com")]
+    #[case("j@o.pl")]
+    #[case("doctor_john_doe@mail.szpital-1.waw.pl")]
+    #[case("łukasz@poczta.pl")]
+    fn accepts_valid_email(#[case] email: &str) {
+        assert!(validate_email(email).is_ok());
+    }
+
+    #[rstest]
+    #[case("john.doe.gmail.com")]
+    #[case("john@doe@gmail.com")]
+    fn rejects_email_without_single_at_sign(#[case] email: &str) {
+        assert!(matches!(
+            validate_email(email)
+                .unwrap_err()
+                .downcast::<EmailValidationError>(),
+            Ok(EmailValidationError::InvalidAtSign)
+        ));
+    }
+
+    #[test]
+    fn rejects_email_with_empty_local_part() {
+        assert!(matches!(
+            validate_email("@gmail.com")
+                .unwrap_err()
+                .downcast::<EmailValidationError>(),
+            Ok(EmailValidationError::EmptyLocalPart)
+        ));
+    }
+
+    #[rstest]
+    #[case("john doe@gmail.com")]
+    #[case("john.doe@gmail .com")]
+    #[case("john.doe@gmail.com\n")]
+    fn rejects_email_with_whitespace(#[case] email: &str) {
+        assert!(matches!(
+            validate_email(email)
+                .unwrap_err()
+                .downcast::<EmailValidationError>(),
+            Ok(EmailValidationError::Whitespace)
+        ));
+    }
+
+    #[rstest]
+    #[case("john.doe@")]
+    #[case("john.doe@localhost")]
+    #[case("john.doe@gmail..com")]
+    #[case("john.doe@.gmail.com")]
+    #[case("john.doe@-gmail.com")]
+    #[case("john.doe@gmail_mail.com")]
+    fn rejects_email_with_invalid_domain(#[case] email: &str) {
+        assert!(matches!(
+            validate_email(email)
+                .unwrap_err()
+                .downcast::<EmailValidationError>(),
+            Ok(EmailValidationError::InvalidDomain)
+        ));
+    }
+
+    #[test]
+    fn rejects_too_long_email() {
+        let email = format!("{}@gmail.com", "a".repeat(245));
+
+        assert!(matches!(
+            validate_email(&email)
+                .unwrap_err()
+                .downcast::<EmailValidationError>(),
+            Ok(EmailValidationError::TooLong(254))
+        ));
+    }
+
+    #[rstest]
+    #[case("john.doe@gmail.com", "john.doe@gmail.com")]
+    #[case("John.Doe@GMAIL.Com", "John.Doe@gmail.com")]
+    #[case("  john.doe@Gmail.com ", "john.doe@gmail.com")]
+    fn lowercases_domain_of_email(#[case] email: &str, #[case] expected: &str) {
+        assert_eq!(normalize_email(email).unwrap(), expected);
+    }
+}
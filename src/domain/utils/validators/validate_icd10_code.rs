@@ -0,0 +1,44 @@
+use regex::Regex;
+
+// Category made of a letter and two characters, optionally followed by a dot and a subcategory
+const ICD10_CODE_PATTERN: &str = r"^[A-Z][0-9][0-9A-Z](\.[0-9A-Z]{1,4})?$";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Icd10CodeValidationError {
+    #[error("ICD-10 code {0} has invalid format")]
+    InvalidFormat(String),
+}
+
+pub fn validate_icd10_code(code: &str) -> anyhow::Result<()> {
+    let pattern = Regex::new(ICD10_CODE_PATTERN).unwrap();
+    if !pattern.is_match(code) {
+        Err(Icd10CodeValidationError::InvalidFormat(code.into()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::validate_icd10_code;
+
+    #[rstest]
+    #[case("J06", true)]
+    #[case("J06.9", true)]
+    #[case("E11.65", true)]
+    #[case("S72.001A", true)]
+    #[case("U07.1", true)]
+    #[case("j06.9", false)]
+    #[case("J6", false)]
+    #[case("J06.", false)]
+    #[case("J06.12345", false)]
+    #[case("006.9", false)]
+    #[case("J06 9", false)]
+    #[case(" J06.9", false)]
+    #[case("", false)]
+    fn validates_icd10_code(#[case] code: &str, #[case] expected: bool) {
+        assert_eq!(validate_icd10_code(code).is_ok(), expected)
+    }
+}
@@ -4,12 +4,17 @@ pub enum NameValidationError {
     InvalidLength(usize, usize),
     #[error("Name must be in format: Firstname Lastname")]
     InvalidFormat,
+    #[error("Name can't be empty")]
+    Empty,
+    #[error("Name can't contain control characters")]
+    ControlCharacters,
 }
 
 pub fn validate_name(name: &str) -> anyhow::Result<()> {
     let min_len: usize = 4;
     let max_len: usize = 100;
-    if name.len() < min_len || name.len() > max_len {
+    let len = name.chars().count();
+    if len < min_len || len > max_len {
         Err(NameValidationError::InvalidLength(min_len, max_len))?;
     }
 
@@ -31,11 +36,26 @@ pub fn validate_name(name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+// Trims the name and collapses whitespace inside it into single spaces before validating it
+pub fn normalize_name(name: &str) -> anyhow::Result<String> {
+    let normalized_name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if normalized_name.is_empty() {
+        Err(NameValidationError::Empty)?;
+    }
+    if normalized_name.chars().any(char::is_control) {
+        Err(NameValidationError::ControlCharacters)?;
+    }
+
+    validate_name(&normalized_name)?;
+
+    Ok(normalized_name)
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
 
-    use super::validate_name;
+    use super::{normalize_name, validate_name, NameValidationError};
 
     #[rstest]
     #[case("John Doe", true)]
@@ -58,4 +78,52 @@ mod tests {
     fn validates_name(#[case] name: &str, #[case] expected: bool) {
         assert_eq!(validate_name(name).is_ok(), expected)
     }
+
+    #[rstest]
+    #[case("  John Doe ", "John Doe")]
+    #[case("John   Doe", "John Doe")]
+    #[case("\tAnne  Pattison\nClark\r\n", "Anne Pattison Clark")]
+    fn trims_and_collapses_whitespace_of_name(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(normalize_name(name).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("   ")]
+    #[case("\t\n")]
+    fn rejects_empty_name(#[case] name: &str) {
+        assert!(matches!(
+            normalize_name(name)
+                .unwrap_err()
+                .downcast::<NameValidationError>(),
+            Ok(NameValidationError::Empty)
+        ));
+    }
+
+    #[test]
+    fn rejects_name_with_control_characters() {
+        assert!(matches!(
+            normalize_name("John\u{0}Doe")
+                .unwrap_err()
+                .downcast::<NameValidationError>(),
+            Ok(NameValidationError::ControlCharacters)
+        ));
+    }
+
+    #[test]
+    fn accepts_name_at_max_length_and_rejects_longer_one() {
+        let name_at_max_length = format!("Ą{} Ż{}", "ą".repeat(49), "ż".repeat(48));
+        let name_over_max_length = format!("  Ą{} Ż{}  ", "ą".repeat(49), "ż".repeat(49));
+
+        assert_eq!(
+            normalize_name(&name_at_max_length).unwrap(),
+            name_at_max_length
+        );
+        assert!(matches!(
+            normalize_name(&name_over_max_length)
+                .unwrap_err()
+                .downcast::<NameValidationError>(),
+            Ok(NameValidationError::InvalidLength(4, 100))
+        ));
+    }
 }
@@ -10,15 +10,31 @@ pub enum PeselNumberValidationError {
     InvalidChecksum,
 }
 
+// The century of the birth date is encoded in the month, 20 is added to it for 2000-2099, 40 for
+// 2100-2199, 60 for 2200-2299 and 80 for 1800-1899
+pub fn get_birth_date_from_pesel_number(pesel_number: &str) -> Option<NaiveDate> {
+    let year = pesel_number.get(0..2)?.parse::<i32>().ok()?;
+    let encoded_month = pesel_number.get(2..4)?.parse::<u32>().ok()?;
+    let day = pesel_number.get(4..6)?.parse::<u32>().ok()?;
+    let (century, month) = match encoded_month {
+        81..=92 => (1800, encoded_month - 80),
+        1..=12 => (1900, encoded_month),
+        21..=32 => (2000, encoded_month - 20),
+        41..=52 => (2100, encoded_month - 40),
+        61..=72 => (2200, encoded_month - 60),
+        _ => return None,
+    };
+
+    NaiveDate::from_ymd_opt(century + year, month, day)
+}
+
 pub fn validate_pesel_number(pesel_number: &str) -> anyhow::Result<()> {
     let pesel_length = 11;
     if pesel_number.len() != pesel_length || pesel_number.parse::<u64>().is_err() {
         Err(PeselNumberValidationError::InvalidFormat)?;
     }
 
-    let (date_part, _) = pesel_number.split_at(6);
-    let is_valid_date = NaiveDate::parse_from_str(&format!("19{}", date_part), "%Y%m%d").is_ok();
-    if !is_valid_date {
+    if get_birth_date_from_pesel_number(pesel_number).is_none() {
         Err(PeselNumberValidationError::InvalidDate)?;
     }
 
@@ -41,14 +57,17 @@ pub fn validate_pesel_number(pesel_number: &str) -> anyhow::Result<()> {
 
 #[cfg(test)]
 mod tests {
+    use chrono::NaiveDate;
     use rstest::rstest;
 
-    use super::validate_pesel_number;
+    use super::{get_birth_date_from_pesel_number, validate_pesel_number};
 
     #[rstest]
     #[case("96021817257", true)]
     #[case("99031301347", true)]
     #[case("92022900002", true)]
+    #[case("04222901233", true)]
+    #[case("10321504565", true)]
     #[case("96221807250", false)]
     #[case("96021807251", false)]
     #[case("93022900005", false)]
@@ -61,4 +80,23 @@ mod tests {
     fn validates_pesel_number(#[case] pesel_number: &str, #[case] expected: bool) {
         assert_eq!(validate_pesel_number(pesel_number).is_ok(), expected);
     }
+
+    #[rstest]
+    #[case("96021817257", Some((1996, 2, 18)))]
+    #[case("04222901233", Some((2004, 2, 29)))]
+    #[case("10321504565", Some((2010, 12, 15)))]
+    #[case("00101401113", Some((1900, 10, 14)))]
+    #[case("99831502222", Some((1899, 3, 15)))]
+    #[case("93022900005", None)]
+    #[case("96131807250", None)]
+    #[case("", None)]
+    fn gets_birth_date_from_pesel_number(
+        #[case] pesel_number: &str,
+        #[case] expected: Option<(i32, u32, u32)>,
+    ) {
+        assert_eq!(
+            get_birth_date_from_pesel_number(pesel_number),
+            expected.map(|(year, month, day)| NaiveDate::from_ymd_opt(year, month, day).unwrap())
+        );
+    }
 }
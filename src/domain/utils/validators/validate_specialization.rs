@@ -0,0 +1,77 @@
+#[derive(thiserror::Error, Debug)]
+pub enum SpecializationValidationError {
+    #[error("Specialization must be between {0} and {1} characters long")]
+    InvalidLength(usize, usize),
+    #[error("Specialization can only contain letters, spaces and hyphens")]
+    InvalidCharacters,
+}
+
+pub fn validate_specialization(specialization: &str) -> anyhow::Result<()> {
+    let min_len: usize = 3;
+    let max_len: usize = 100;
+    let len = specialization.chars().count();
+    if len < min_len || len > max_len {
+        Err(SpecializationValidationError::InvalidLength(
+            min_len, max_len,
+        ))?;
+    }
+
+    if !specialization
+        .chars()
+        .all(|c| c.is_alphabetic() || c == ' ' || c == '-')
+    {
+        Err(SpecializationValidationError::InvalidCharacters)?;
+    }
+
+    Ok(())
+}
+
+// Specializations are free text, lowercasing them and collapsing their whitespace lets the filter
+// match them however they were typed
+pub fn normalize_specialization(specialization: &str) -> anyhow::Result<String> {
+    let normalized_specialization = specialization
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    validate_specialization(&normalized_specialization)?;
+
+    Ok(normalized_specialization)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{normalize_specialization, validate_specialization};
+
+    #[rstest]
+    #[case("cardiology", true)]
+    #[case("family medicine", true)]
+    #[case("ear-nose-throat", true)]
+    #[case("kardiologia dziecięca", true)]
+    #[case("ent", true)]
+    #[case("ab", false)]
+    #[case("", false)]
+    #[case("cardiology 2", false)]
+    #[case("cardiology/surgery", false)]
+    #[case("cardiology\n", false)]
+    fn validates_specialization(#[case] specialization: &str, #[case] expected: bool) {
+        assert_eq!(validate_specialization(specialization).is_ok(), expected)
+    }
+
+    #[test]
+    fn rejects_too_long_specialization() {
+        assert!(validate_specialization(&"a".repeat(100)).is_ok());
+        assert!(validate_specialization(&"a".repeat(101)).is_err());
+    }
+
+    #[rstest]
+    #[case("Cardiology", "cardiology")]
+    #[case("  Family   Medicine ", "family medicine")]
+    #[case("\tPediatrics\n", "pediatrics")]
+    fn normalizes_specialization(#[case] specialization: &str, #[case] expected: &str) {
+        assert_eq!(normalize_specialization(specialization).unwrap(), expected);
+    }
+}
@@ -1 +1,2 @@
 pub mod postgres_repository_impl;
+pub mod prescription_fill_webhook;
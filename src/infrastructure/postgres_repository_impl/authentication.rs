@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use chrono::{DateTime, Utc};
 use rocket::async_trait;
 use sqlx::Row;
@@ -6,11 +8,21 @@ use uuid::Uuid;
 use crate::{
     application::authentication::{
         entities::{NewUser, User, UserRole},
-        repository::{AuthenticationRepository, CreateUserRepositoryError, GetUserRepositoryError},
+        repository::{
+            AuthenticationRepository, CreateUserRepositoryError, GetUserByIdRepositoryError,
+            GetUserRepositoryError, SetUserActiveRepositoryError, UpdateLastLoginRepositoryError,
+            UpdateTotpRepositoryError,
+        },
+    },
+    domain::{
+        doctors::{entities::Doctor, pwz_number::PwzNumber},
+        patients::entities::Patient,
+        pharmacists::entities::Pharmacist,
     },
-    domain::{doctors::entities::Doctor, pharmacists::entities::Pharmacist},
 };
 
+use super::database_error::map_database_error;
+
 pub struct PostgresAuthenticationRepository {
     pool: sqlx::PgPool,
 }
@@ -22,19 +34,31 @@ struct UsersRow {
     user_email: String,
     user_phone_number: String,
     user_role: UserRole,
+    user_is_active: bool,
+    user_last_login_at: Option<DateTime<Utc>>,
+    user_last_login_ip: Option<IpAddr>,
+    user_totp_secret: Option<String>,
+    user_totp_enabled: bool,
     user_created_at: DateTime<Utc>,
     user_updated_at: DateTime<Utc>,
     doctor_id: Option<Uuid>,
     doctor_name: Option<String>,
-    doctor_pwz_number: Option<String>,
+    doctor_pwz_number: Option<PwzNumber>,
     doctor_pesel_number: Option<String>,
     doctor_created_at: Option<DateTime<Utc>>,
     doctor_updated_at: Option<DateTime<Utc>>,
+    doctor_specialization: Option<String>,
     pharmacist_id: Option<Uuid>,
     pharmacist_name: Option<String>,
     pharmacist_pesel_number: Option<String>,
     pharmacist_created_at: Option<DateTime<Utc>>,
     pharmacist_updated_at: Option<DateTime<Utc>>,
+    patient_id: Option<Uuid>,
+    patient_name: Option<String>,
+    patient_pesel_number: Option<String>,
+    patient_created_at: Option<DateTime<Utc>>,
+    patient_updated_at: Option<DateTime<Utc>>,
+    patient_created_by: Option<Uuid>,
 }
 
 impl PostgresAuthenticationRepository {
@@ -50,6 +74,13 @@ impl PostgresAuthenticationRepository {
             user_email: row.try_get(3)?,
             user_phone_number: row.try_get(4)?,
             user_role: row.try_get(5)?,
+            user_is_active: row.try_get(19)?,
+            user_last_login_at: row.try_get(20)?,
+            user_last_login_ip: row
+                .try_get(21)
+                .map(|ip: Option<String>| ip.map(|ip| ip.parse().unwrap()))?,
+            user_totp_secret: row.try_get(22)?,
+            user_totp_enabled: row.try_get(23)?,
             user_created_at: row.try_get(6)?,
             user_updated_at: row.try_get(7)?,
             doctor_id: row.try_get(8)?,
@@ -58,11 +89,18 @@ impl PostgresAuthenticationRepository {
             doctor_pesel_number: row.try_get(11)?,
             doctor_created_at: row.try_get(12)?,
             doctor_updated_at: row.try_get(13)?,
+            doctor_specialization: row.try_get(24)?,
             pharmacist_id: row.try_get(14)?,
             pharmacist_name: row.try_get(15)?,
             pharmacist_pesel_number: row.try_get(16)?,
             pharmacist_created_at: row.try_get(17)?,
             pharmacist_updated_at: row.try_get(18)?,
+            patient_id: row.try_get(25)?,
+            patient_name: row.try_get(26)?,
+            patient_pesel_number: row.try_get(27)?,
+            patient_created_at: row.try_get(28)?,
+            patient_updated_at: row.try_get(29)?,
+            patient_created_by: row.try_get(30)?,
         };
 
         Ok(User {
@@ -72,6 +110,11 @@ impl PostgresAuthenticationRepository {
             email: users_row.user_email,
             phone_number: users_row.user_phone_number,
             role: users_row.user_role,
+            is_active: users_row.user_is_active,
+            last_login_at: users_row.user_last_login_at,
+            last_login_ip: users_row.user_last_login_ip,
+            totp_secret: users_row.user_totp_secret,
+            totp_enabled: users_row.user_totp_enabled,
             created_at: users_row.user_created_at,
             updated_at: users_row.user_updated_at,
             doctor: users_row.doctor_id.map(|id| Doctor {
@@ -81,6 +124,7 @@ impl PostgresAuthenticationRepository {
                 pesel_number: users_row.doctor_pesel_number.unwrap(),
                 created_at: users_row.doctor_created_at.unwrap(),
                 updated_at: users_row.doctor_updated_at.unwrap(),
+                specialization: users_row.doctor_specialization,
             }),
             pharmacist: users_row.pharmacist_id.map(|id| Pharmacist {
                 id,
@@ -89,6 +133,29 @@ impl PostgresAuthenticationRepository {
                 created_at: users_row.pharmacist_created_at.unwrap(),
                 updated_at: users_row.pharmacist_updated_at.unwrap(),
             }),
+            patient: users_row.patient_id.map(|id| Patient {
+                id,
+                name: users_row.patient_name.unwrap(),
+                pesel_number: users_row.patient_pesel_number.unwrap(),
+                created_at: users_row.patient_created_at.unwrap(),
+                updated_at: users_row.patient_updated_at.unwrap(),
+                created_by: users_row.patient_created_by,
+            }),
+        })
+    }
+
+    async fn get_updated_totp_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<User, UpdateTotpRepositoryError> {
+        self.get_user_by_id(user_id).await.map_err(|err| match err {
+            GetUserByIdRepositoryError::NotFound(user_id) => {
+                UpdateTotpRepositoryError::NotFound(user_id)
+            }
+            GetUserByIdRepositoryError::DatabaseError(message) => {
+                UpdateTotpRepositoryError::DatabaseError(message)
+            }
+            GetUserByIdRepositoryError::Unavailable => UpdateTotpRepositoryError::Unavailable,
         })
     }
 }
@@ -96,14 +163,16 @@ impl PostgresAuthenticationRepository {
 #[async_trait]
 impl AuthenticationRepository for PostgresAuthenticationRepository {
     async fn create_user(&self, new_user: NewUser) -> Result<User, CreateUserRepositoryError> {
-        let transaction = self
-            .pool
-            .begin()
-            .await
-            .map_err(|err| CreateUserRepositoryError::DatabaseError(err.to_string()))?;
+        let transaction = self.pool.begin().await.map_err(|err| {
+            map_database_error(
+                err,
+                CreateUserRepositoryError::DatabaseError,
+                CreateUserRepositoryError::Unavailable,
+            )
+        })?;
 
         sqlx::query(
-            r#"INSERT INTO users (username, password_hash, email, phone_number, role, doctor_id, pharmacist_id) VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+            r#"INSERT INTO users (username, password_hash, email, phone_number, role, doctor_id, pharmacist_id, patient_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
         )
         .bind(new_user.username.clone())
         .bind(new_user.password_hash)
@@ -112,19 +181,23 @@ impl AuthenticationRepository for PostgresAuthenticationRepository {
         .bind(new_user.role)
         .bind(new_user.doctor_id)
         .bind(new_user.pharmacist_id)
+        .bind(new_user.patient_id)
         .execute(&self.pool)
         .await
-        .map_err(|err| CreateUserRepositoryError::DatabaseError(err.to_string()))?;
+        .map_err(|err| map_database_error(err, CreateUserRepositoryError::DatabaseError, CreateUserRepositoryError::Unavailable))?;
 
         let user = self
             .get_user_by_username(&new_user.username)
             .await
             .map_err(|err| CreateUserRepositoryError::DatabaseError(err.to_string()))?;
 
-        transaction
-            .commit()
-            .await
-            .map_err(|err| CreateUserRepositoryError::DatabaseError(err.to_string()))?;
+        transaction.commit().await.map_err(|err| {
+            map_database_error(
+                err,
+                CreateUserRepositoryError::DatabaseError,
+                CreateUserRepositoryError::Unavailable,
+            )
+        })?;
 
         Ok(user)
     }
@@ -154,24 +227,244 @@ impl AuthenticationRepository for PostgresAuthenticationRepository {
                 pharmacists.name,
                 pharmacists.pesel_number,
                 pharmacists.created_at,
-                pharmacists.updated_at
+                pharmacists.updated_at,
+                users.is_active,
+                users.last_login_at,
+                users.last_login_ip,
+                users.totp_secret,
+                users.totp_enabled,
+                doctors.specialization,
+                patients.id,
+                patients.name,
+                patients.pesel_number,
+                patients.created_at,
+                patients.updated_at,
+                patients.created_by
             FROM users 
             LEFT JOIN doctors ON users.doctor_id = doctors.id
             LEFT JOIN pharmacists ON users.pharmacist_id = pharmacists.id
+            LEFT JOIN patients ON users.patient_id = patients.id
             WHERE username = $1
         "#,
         )
         .bind(username)
         .fetch_one(&self.pool)
         .await
-        .map_err(|err| GetUserRepositoryError::DatabaseError(err.to_string()))?;
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetUserRepositoryError::DatabaseError,
+                GetUserRepositoryError::Unavailable,
+            )
+        })?;
+
+        let user = self.parse_users_row(row).map_err(|err| {
+            map_database_error(
+                err,
+                GetUserRepositoryError::DatabaseError,
+                GetUserRepositoryError::Unavailable,
+            )
+        })?;
+
+        Ok(user)
+    }
+
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, GetUserByIdRepositoryError> {
+        let row = sqlx::query(
+            r#"
+            SELECT 
+                users.id, 
+                users.username, 
+                users.password_hash,
+                users.email, 
+                users.phone_number, 
+                users.role, 
+                users.created_at,
+                users.updated_at,
+                doctors.id,
+                doctors.name,
+                doctors.pwz_number,
+                doctors.pesel_number,
+                doctors.created_at,
+                doctors.updated_at,
+                pharmacists.id,
+                pharmacists.name,
+                pharmacists.pesel_number,
+                pharmacists.created_at,
+                pharmacists.updated_at,
+                users.is_active,
+                users.last_login_at,
+                users.last_login_ip,
+                users.totp_secret,
+                users.totp_enabled,
+                doctors.specialization,
+                patients.id,
+                patients.name,
+                patients.pesel_number,
+                patients.created_at,
+                patients.updated_at,
+                patients.created_by
+            FROM users 
+            LEFT JOIN doctors ON users.doctor_id = doctors.id
+            LEFT JOIN pharmacists ON users.pharmacist_id = pharmacists.id
+            LEFT JOIN patients ON users.patient_id = patients.id
+            WHERE users.id = $1
+        "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => GetUserByIdRepositoryError::NotFound(user_id),
+            _ => map_database_error(
+                err,
+                GetUserByIdRepositoryError::DatabaseError,
+                GetUserByIdRepositoryError::Unavailable,
+            ),
+        })?;
+
+        let user = self.parse_users_row(row).map_err(|err| {
+            map_database_error(
+                err,
+                GetUserByIdRepositoryError::DatabaseError,
+                GetUserByIdRepositoryError::Unavailable,
+            )
+        })?;
+
+        Ok(user)
+    }
+
+    async fn set_user_active(
+        &self,
+        user_id: Uuid,
+        active: bool,
+    ) -> Result<User, SetUserActiveRepositoryError> {
+        let result =
+            sqlx::query(r#"UPDATE users SET is_active = $1, updated_at = NOW() WHERE id = $2"#)
+                .bind(active)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await
+                .map_err(|err| {
+                    map_database_error(
+                        err,
+                        SetUserActiveRepositoryError::DatabaseError,
+                        SetUserActiveRepositoryError::Unavailable,
+                    )
+                })?;
+
+        if result.rows_affected() == 0 {
+            Err(SetUserActiveRepositoryError::NotFound(user_id))?;
+        }
 
         let user = self
-            .parse_users_row(row)
-            .map_err(|err| GetUserRepositoryError::DatabaseError(err.to_string()))?;
+            .get_user_by_id(user_id)
+            .await
+            .map_err(|err| match err {
+                GetUserByIdRepositoryError::NotFound(user_id) => {
+                    SetUserActiveRepositoryError::NotFound(user_id)
+                }
+                GetUserByIdRepositoryError::DatabaseError(message) => {
+                    SetUserActiveRepositoryError::DatabaseError(message)
+                }
+                GetUserByIdRepositoryError::Unavailable => {
+                    SetUserActiveRepositoryError::Unavailable
+                }
+            })?;
 
         Ok(user)
     }
+
+    async fn update_last_login(
+        &self,
+        user_id: Uuid,
+        ip_address: IpAddr,
+    ) -> Result<User, UpdateLastLoginRepositoryError> {
+        let result = sqlx::query(
+            r#"UPDATE users SET last_login_at = NOW(), last_login_ip = $1 WHERE id = $2"#,
+        )
+        .bind(ip_address.to_string())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                UpdateLastLoginRepositoryError::DatabaseError,
+                UpdateLastLoginRepositoryError::Unavailable,
+            )
+        })?;
+
+        if result.rows_affected() == 0 {
+            Err(UpdateLastLoginRepositoryError::NotFound(user_id))?;
+        }
+
+        let user = self
+            .get_user_by_id(user_id)
+            .await
+            .map_err(|err| match err {
+                GetUserByIdRepositoryError::NotFound(user_id) => {
+                    UpdateLastLoginRepositoryError::NotFound(user_id)
+                }
+                GetUserByIdRepositoryError::DatabaseError(message) => {
+                    UpdateLastLoginRepositoryError::DatabaseError(message)
+                }
+                GetUserByIdRepositoryError::Unavailable => {
+                    UpdateLastLoginRepositoryError::Unavailable
+                }
+            })?;
+
+        Ok(user)
+    }
+
+    async fn set_totp_secret(
+        &self,
+        user_id: Uuid,
+        totp_secret: String,
+    ) -> Result<User, UpdateTotpRepositoryError> {
+        let result = sqlx::query(
+            r#"UPDATE users SET totp_secret = $1, totp_enabled = FALSE, updated_at = NOW() WHERE id = $2"#,
+        )
+        .bind(totp_secret)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                UpdateTotpRepositoryError::DatabaseError,
+                UpdateTotpRepositoryError::Unavailable,
+            )
+        })?;
+
+        if result.rows_affected() == 0 {
+            Err(UpdateTotpRepositoryError::NotFound(user_id))?;
+        }
+
+        self.get_updated_totp_user(user_id).await
+    }
+
+    async fn enable_totp(&self, user_id: Uuid) -> Result<User, UpdateTotpRepositoryError> {
+        let result = sqlx::query(
+            r#"UPDATE users SET totp_enabled = TRUE, updated_at = NOW() WHERE id = $1"#,
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                UpdateTotpRepositoryError::DatabaseError,
+                UpdateTotpRepositoryError::Unavailable,
+            )
+        })?;
+
+        if result.rows_affected() == 0 {
+            Err(UpdateTotpRepositoryError::NotFound(user_id))?;
+        }
+
+        self.get_updated_totp_user(user_id).await
+    }
 }
 
 #[cfg(test)]
@@ -201,6 +494,7 @@ mod tests {
             UserRole::Doctor,
             Some(Uuid::default()),
             None,
+            None,
         )
         .unwrap()
     }
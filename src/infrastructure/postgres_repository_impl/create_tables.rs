@@ -1,11 +1,23 @@
 pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::Error> {
     if drop {
+        sqlx::query(r#"DROP TABLE IF EXISTS prescription_fill_items;"#)
+            .execute(pool)
+            .await?;
         sqlx::query(r#"DROP TABLE IF EXISTS prescription_fills;"#)
             .execute(pool)
             .await?;
         sqlx::query(r#"DROP TABLE IF EXISTS prescribed_drugs;"#)
             .execute(pool)
             .await?;
+        sqlx::query(r#"DROP TABLE IF EXISTS prescription_diagnoses;"#)
+            .execute(pool)
+            .await?;
+        sqlx::query(r#"DROP TABLE IF EXISTS prescription_template_drugs;"#)
+            .execute(pool)
+            .await?;
+        sqlx::query(r#"DROP TABLE IF EXISTS prescription_templates;"#)
+            .execute(pool)
+            .await?;
         sqlx::query(r#"DROP TABLE IF EXISTS drugs;"#)
             .execute(pool)
             .await?;
@@ -18,6 +30,9 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
         sqlx::query(r#"DROP TABLE IF EXISTS pharmacists;"#)
             .execute(pool)
             .await?;
+        sqlx::query(r#"DROP TABLE IF EXISTS doctor_pwz_number_changes;"#)
+            .execute(pool)
+            .await?;
         sqlx::query(r#"DROP TABLE IF EXISTS doctors;"#)
             .execute(pool)
             .await?;
@@ -30,6 +45,9 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
         sqlx::query(r#"DROP TYPE IF EXISTS drug_content_type;"#)
             .execute(pool)
             .await?;
+        sqlx::query(r#"DROP TYPE IF EXISTS drug_category;"#)
+            .execute(pool)
+            .await?;
         sqlx::query(r#"DROP TYPE IF EXISTS user_role;"#)
             .execute(pool)
             .await?;
@@ -53,7 +71,7 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
         DO $$
         BEGIN
             IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'drug_content_type') THEN
-            CREATE TYPE drug_content_type AS ENUM ('solid_pills', 'liquid_pills', 'bottle_of_liquid');
+            CREATE TYPE drug_content_type AS ENUM ('solid_pills', 'liquid_pills', 'bottle_of_liquid', 'inhaler', 'injection');
             END IF;
         END
         $$;"#
@@ -62,17 +80,49 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
         .await?;
 
     sqlx::query(
-            r#"
+        r#"
+        DO $$
+        BEGIN
+            IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'drug_category') THEN
+            CREATE TYPE drug_category AS ENUM ('antibiotic', 'chronic_disease', 'immunological');
+            END IF;
+        END
+        $$;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Databases created before inhalers and injections were supported are missing these labels,
+    // ADD VALUE only appends to the enum so existing rows keep deserializing
+    sqlx::query(r#"ALTER TYPE drug_content_type ADD VALUE IF NOT EXISTS 'inhaler';"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TYPE drug_content_type ADD VALUE IF NOT EXISTS 'injection';"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
             DO $$
             BEGIN
                 IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'user_role') THEN
-                CREATE TYPE user_role AS ENUM ('doctor', 'pharmacist');
+                CREATE TYPE user_role AS ENUM ('doctor', 'pharmacist', 'patient');
                 END IF;
             END
-            $$;"#
-        )
-            .execute(pool)
-            .await?;
+            $$;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Databases created before patients could log in are missing this label
+    sqlx::query(r#"ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'patient';"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'admin';"#)
+        .execute(pool)
+        .await?;
 
     sqlx::query(
         r#"
@@ -88,6 +138,24 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
     .execute(pool)
     .await?;
 
+    sqlx::query(r#"ALTER TABLE doctors ADD COLUMN IF NOT EXISTS specialization VARCHAR(100);"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS doctor_pwz_number_changes (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            doctor_id UUID NOT NULL REFERENCES doctors(id),
+            old_pwz_number VARCHAR(7) NOT NULL,
+            new_pwz_number VARCHAR(7) NOT NULL,
+            changed_by UUID NOT NULL,
+            changed_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+        );"#,
+    )
+    .execute(pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS pharmacists (
@@ -108,12 +176,17 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
             name VARCHAR(100) NOT NULL,
             pesel_number VARCHAR(11) UNIQUE NOT NULL,
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
-            updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+            updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+            created_by UUID
         );"#,
     )
     .execute(pool)
     .await?;
 
+    sqlx::query(r#"ALTER TABLE patients ADD COLUMN IF NOT EXISTS created_by UUID;"#)
+        .execute(pool)
+        .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS prescriptions (
@@ -124,6 +197,8 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
             code VARCHAR(8) NOT NULL,
             start_date TIMESTAMPTZ NOT NULL,
             end_date TIMESTAMPTZ NOT NULL,
+            cancelled_at TIMESTAMPTZ,
+            window_from_fill BOOLEAN DEFAULT FALSE NOT NULL,
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
             updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
         );"#,
@@ -131,6 +206,16 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
     .execute(pool)
     .await?;
 
+    sqlx::query(r#"ALTER TABLE prescriptions ADD COLUMN IF NOT EXISTS cancelled_at TIMESTAMPTZ;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"ALTER TABLE prescriptions ADD COLUMN IF NOT EXISTS window_from_fill BOOLEAN DEFAULT FALSE NOT NULL;"#,
+    )
+    .execute(pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS drugs (
@@ -141,13 +226,94 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
             mg_per_pill INT,
             ml_per_pill INT,
             volume_ml INT,
+            unit_price_cents INT,
+            manufacturer VARCHAR(100),
+            barcode VARCHAR(14),
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
-            updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+            updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+            deactivated_at TIMESTAMPTZ,
+            doses_count INT,
+            mcg_per_dose INT,
+            ml_per_dose INT,
+            created_by UUID,
+            category drug_category,
+            allowed_prescription_types prescription_type[],
+            stock INT DEFAULT 0 NOT NULL CHECK (stock >= 0)
         );"#,
     )
     .execute(pool)
     .await?;
 
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS unit_price_cents INT;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS manufacturer VARCHAR(100);"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS barcode VARCHAR(14);"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS deactivated_at TIMESTAMPTZ;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS doses_count INT;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS mcg_per_dose INT;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS ml_per_dose INT;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS created_by UUID;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS category drug_category;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS allowed_prescription_types prescription_type[];"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"ALTER TABLE drugs ADD COLUMN IF NOT EXISTS stock INT DEFAULT 0 NOT NULL CHECK (stock >= 0);"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(r#"CREATE UNIQUE INDEX IF NOT EXISTS drugs_barcode_key ON drugs (barcode);"#)
+        .execute(pool)
+        .await?;
+
+    // Replaced by drugs_name_dosage_key, which also covers the doses of inhalers
+    sqlx::query(r#"DROP INDEX IF EXISTS drugs_name_strength_key;"#)
+        .execute(pool)
+        .await?;
+
+    // Missing strengths are coalesced, so that they count as equal for uniqueness
+    sqlx::query(
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS drugs_name_dosage_key ON drugs (name, content_type, COALESCE(mg_per_pill, -1), COALESCE(ml_per_pill, -1), COALESCE(volume_ml, -1), COALESCE(doses_count, -1), COALESCE(mcg_per_dose, -1), COALESCE(ml_per_dose, -1));"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS prescriptions_code_key ON prescriptions (code);"#,
+    )
+    .execute(pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS prescribed_drugs (
@@ -155,6 +321,7 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
             prescription_id UUID NOT NULL REFERENCES prescriptions(id),
             drug_id UUID NOT NULL REFERENCES drugs(id),
             quantity INT NOT NULL,
+            dispensed_quantity INT DEFAULT 0 NOT NULL,
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
             updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
         );"#,
@@ -162,11 +329,72 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
     .execute(pool)
     .await?;
 
+    sqlx::query(
+        r#"ALTER TABLE prescribed_drugs ADD COLUMN IF NOT EXISTS dispensed_quantity INT DEFAULT 0 NOT NULL;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Rejects fills dispensing more than remains, also when they race each other
+    sqlx::query(
+        r#"ALTER TABLE prescribed_drugs DROP CONSTRAINT IF EXISTS prescribed_drugs_dispensed_quantity_check;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"ALTER TABLE prescribed_drugs ADD CONSTRAINT prescribed_drugs_dispensed_quantity_check CHECK (dispensed_quantity <= quantity);"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Position keeps the diagnoses in the order they were given by the doctor
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prescription_diagnoses (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            prescription_id UUID NOT NULL REFERENCES prescriptions(id),
+            code VARCHAR(8) NOT NULL,
+            position INT NOT NULL,
+            created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+            UNIQUE (prescription_id, code)
+        );"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prescription_templates (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            doctor_id UUID NOT NULL REFERENCES doctors(id),
+            name VARCHAR(100) NOT NULL,
+            created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+            updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+        );"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Position keeps the drugs in the order they were added to the template
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prescription_template_drugs (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            template_id UUID NOT NULL REFERENCES prescription_templates(id) ON DELETE CASCADE,
+            drug_id UUID NOT NULL REFERENCES drugs(id),
+            quantity INT NOT NULL,
+            position INT NOT NULL
+        );"#,
+    )
+    .execute(pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS prescription_fills (
             id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-            prescription_id UUID UNIQUE NOT NULL REFERENCES prescriptions(id),
+            prescription_id UUID NOT NULL REFERENCES prescriptions(id),
             pharmacist_id UUID NOT NULL REFERENCES pharmacists(id),
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
             updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
@@ -175,6 +403,26 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
     .execute(pool)
     .await?;
 
+    // A prescription can be filled partially, and the remainder by subsequent fills
+    sqlx::query(
+        r#"ALTER TABLE prescription_fills DROP CONSTRAINT IF EXISTS prescription_fills_prescription_id_key;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prescription_fill_items (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            prescription_fill_id UUID NOT NULL REFERENCES prescription_fills(id) ON DELETE CASCADE,
+            prescribed_drug_id UUID NOT NULL REFERENCES prescribed_drugs(id),
+            dispensed_quantity INT NOT NULL CHECK (dispensed_quantity > 0),
+            created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+        );"#,
+    )
+    .execute(pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS users (
@@ -186,6 +434,12 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
             role user_role NOT NULL,
             doctor_id UUID,
             pharmacist_id UUID,
+            patient_id UUID,
+            is_active BOOLEAN DEFAULT TRUE NOT NULL,
+            last_login_at TIMESTAMPTZ,
+            last_login_ip VARCHAR(255),
+            totp_secret VARCHAR(255),
+            totp_enabled BOOLEAN DEFAULT FALSE NOT NULL,
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
             updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
         );"#,
@@ -193,6 +447,34 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
     .execute(pool)
     .await?;
 
+    sqlx::query(
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS is_active BOOLEAN DEFAULT TRUE NOT NULL;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMPTZ;"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS last_login_ip VARCHAR(255);"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret VARCHAR(255);"#)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN DEFAULT FALSE NOT NULL;"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS patient_id UUID;"#)
+        .execute(pool)
+        .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS sessions (
@@ -200,6 +482,7 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
             user_id UUID NOT NULL,
             doctor_id UUID,
             pharmacist_id UUID,
+            patient_id UUID,
             ip_address VARCHAR(255) NOT NULL,
             user_agent VARCHAR(255) NOT NULL,
             created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
@@ -211,5 +494,9 @@ pub async fn create_tables(pool: &sqlx::PgPool, drop: bool) -> Result<(), sqlx::
     .execute(pool)
     .await?;
 
+    sqlx::query(r#"ALTER TABLE sessions ADD COLUMN IF NOT EXISTS patient_id UUID;"#)
+        .execute(pool)
+        .await?;
+
     Ok(())
 }
@@ -0,0 +1,56 @@
+const QUERY_CANCELED_CODE: &str = "57014";
+
+pub const STATEMENT_TIMEOUT_MESSAGE: &str =
+    "Query took longer than the statement timeout and was canceled";
+
+// Waiting too long for a connection from an exhausted pool is reported as `unavailable`, so it can
+// be told apart from the errors returned by the database itself
+pub fn map_database_error<E>(
+    err: sqlx::Error,
+    database_error: fn(String) -> E,
+    unavailable: E,
+) -> E {
+    match err {
+        sqlx::Error::PoolTimedOut => unavailable,
+        sqlx::Error::Database(err) if err.code().as_deref() == Some(QUERY_CANCELED_CODE) => {
+            database_error(STATEMENT_TIMEOUT_MESSAGE.into())
+        }
+        err => database_error(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::map_database_error;
+
+    #[derive(Debug, PartialEq)]
+    enum RepositoryError {
+        DatabaseError(String),
+        Unavailable,
+    }
+
+    #[test]
+    fn maps_pool_timeout_to_unavailable() {
+        let err = map_database_error(
+            sqlx::Error::PoolTimedOut,
+            RepositoryError::DatabaseError,
+            RepositoryError::Unavailable,
+        );
+
+        assert_eq!(err, RepositoryError::Unavailable);
+    }
+
+    #[test]
+    fn maps_other_errors_to_database_error() {
+        let err = map_database_error(
+            sqlx::Error::RowNotFound,
+            RepositoryError::DatabaseError,
+            RepositoryError::Unavailable,
+        );
+
+        assert_eq!(
+            err,
+            RepositoryError::DatabaseError(sqlx::Error::RowNotFound.to_string())
+        );
+    }
+}
@@ -4,15 +4,19 @@ use uuid::Uuid;
 
 use crate::domain::{
     doctors::{
-        entities::{Doctor, NewDoctor},
+        entities::{Doctor, DoctorPwzNumberChange, NewDoctor},
+        pwz_number::PwzNumber,
         repository::{
             CreateDoctorRepositoryError, DoctorsRepository, GetDoctorByIdRepositoryError,
-            GetDoctorsRepositoryError,
+            GetDoctorByPwzNumberRepositoryError, GetDoctorPwzNumberChangesRepositoryError,
+            GetDoctorsRepositoryError, UpdateDoctorPwzNumberRepositoryError,
         },
     },
-    utils::pagination::get_pagination_params,
+    utils::pagination::{get_pagination_params, DEFAULT_PAGE_SIZE},
 };
 
+use super::database_error::map_database_error;
+
 #[derive(Clone)]
 pub struct PostgresDoctorsRepository {
     pool: sqlx::PgPool,
@@ -31,6 +35,21 @@ impl PostgresDoctorsRepository {
             pesel_number: row.try_get(3)?,
             created_at: row.try_get(4)?,
             updated_at: row.try_get(5)?,
+            specialization: row.try_get(6)?,
+        })
+    }
+
+    fn parse_pwz_number_changes_row(
+        &self,
+        row: sqlx::postgres::PgRow,
+    ) -> Result<DoctorPwzNumberChange, sqlx::Error> {
+        Ok(DoctorPwzNumberChange {
+            id: row.try_get(0)?,
+            doctor_id: row.try_get(1)?,
+            old_pwz_number: row.try_get(2)?,
+            new_pwz_number: row.try_get(3)?,
+            changed_by: row.try_get(4)?,
+            changed_at: row.try_get(5)?,
         })
     }
 }
@@ -42,12 +61,13 @@ impl DoctorsRepository for PostgresDoctorsRepository {
         doctor: NewDoctor,
     ) -> Result<Doctor, CreateDoctorRepositoryError> {
         let result = sqlx::query(
-                r#"INSERT INTO doctors (id, name, pwz_number, pesel_number) VALUES ($1, $2, $3, $4) RETURNING id, name, pwz_number, pesel_number, created_at, updated_at"#
+                r#"INSERT INTO doctors (id, name, pwz_number, pesel_number, specialization) VALUES ($1, $2, $3, $4, $5) RETURNING id, name, pwz_number, pesel_number, created_at, updated_at, specialization"#
             )
             .bind(doctor.id)
             .bind(doctor.name)
             .bind(doctor.pwz_number)
             .bind(doctor.pesel_number)
+            .bind(doctor.specialization)
             .fetch_one(&self.pool).await
             .map_err(|err| {
                 match err {
@@ -62,13 +82,17 @@ impl DoctorsRepository for PostgresDoctorsRepository {
                             _ => CreateDoctorRepositoryError::DatabaseError(err.to_string()),
                         }
                     },
-                    _ => CreateDoctorRepositoryError::DatabaseError(err.to_string()),
+                    _ => map_database_error(err, CreateDoctorRepositoryError::DatabaseError, CreateDoctorRepositoryError::Unavailable),
                 }
             })?;
 
-        let doctor = self
-            .parse_doctors_row(result)
-            .map_err(|err| CreateDoctorRepositoryError::DatabaseError(err.to_string()))?;
+        let doctor = self.parse_doctors_row(result).map_err(|err| {
+            map_database_error(
+                err,
+                CreateDoctorRepositoryError::DatabaseError,
+                CreateDoctorRepositoryError::Unavailable,
+            )
+        })?;
 
         Ok(doctor)
     }
@@ -77,23 +101,29 @@ impl DoctorsRepository for PostgresDoctorsRepository {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        specialization: Option<&str>,
     ) -> Result<Vec<Doctor>, GetDoctorsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size)
-            .map_err(|err| GetDoctorsRepositoryError::InvalidPaginationParams(err.to_string()))?;
+        let (page_size, offset) = get_pagination_params(page, page_size, DEFAULT_PAGE_SIZE)
+            .map_err(GetDoctorsRepositoryError::InvalidPaginationParams)?;
 
         let doctors_from_db = sqlx::query(
-                r#"SELECT id, name, pwz_number, pesel_number, created_at, updated_at FROM doctors LIMIT $1 OFFSET $2"#
+                r#"SELECT id, name, pwz_number, pesel_number, created_at, updated_at, specialization FROM doctors WHERE ($3::TEXT IS NULL OR specialization = $3) ORDER BY created_at ASC, id ASC LIMIT $1 OFFSET $2"#
             )
             .bind(page_size)
             .bind(offset)
+            .bind(specialization)
             .fetch_all(&self.pool).await
-            .map_err(|err| GetDoctorsRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, GetDoctorsRepositoryError::DatabaseError, GetDoctorsRepositoryError::Unavailable))?;
 
         let mut doctors: Vec<Doctor> = Vec::new();
         for record in doctors_from_db {
-            let doctor = self
-                .parse_doctors_row(record)
-                .map_err(|err| GetDoctorsRepositoryError::DatabaseError(err.to_string()))?;
+            let doctor = self.parse_doctors_row(record).map_err(|err| {
+                map_database_error(
+                    err,
+                    GetDoctorsRepositoryError::DatabaseError,
+                    GetDoctorsRepositoryError::Unavailable,
+                )
+            })?;
             doctors.push(doctor);
         }
 
@@ -105,27 +135,151 @@ impl DoctorsRepository for PostgresDoctorsRepository {
         doctor_id: Uuid,
     ) -> Result<Doctor, GetDoctorByIdRepositoryError> {
         let doctor_from_db = sqlx::query(
-                r#"SELECT id, name, pwz_number, pesel_number, created_at, updated_at FROM doctors WHERE id = $1"#
+                r#"SELECT id, name, pwz_number, pesel_number, created_at, updated_at, specialization FROM doctors WHERE id = $1"#
             )
             .bind(doctor_id)
             .fetch_one(&self.pool).await
             .map_err(|err| {
                 match err {
                     sqlx::Error::RowNotFound => GetDoctorByIdRepositoryError::NotFound(doctor_id),
-                    _ => GetDoctorByIdRepositoryError::DatabaseError(err.to_string()),
+                    _ => map_database_error(err, GetDoctorByIdRepositoryError::DatabaseError, GetDoctorByIdRepositoryError::Unavailable),
+                }
+            })?;
+
+        let doctor = self.parse_doctors_row(doctor_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                GetDoctorByIdRepositoryError::DatabaseError,
+                GetDoctorByIdRepositoryError::Unavailable,
+            )
+        })?;
+
+        Ok(doctor)
+    }
+
+    async fn get_doctor_by_pwz_number(
+        &self,
+        pwz_number: &PwzNumber,
+    ) -> Result<Doctor, GetDoctorByPwzNumberRepositoryError> {
+        let doctor_from_db = sqlx::query(
+                r#"SELECT id, name, pwz_number, pesel_number, created_at, updated_at, specialization FROM doctors WHERE pwz_number = $1"#
+            )
+            .bind(pwz_number)
+            .fetch_one(&self.pool).await
+            .map_err(|err| {
+                match err {
+                    sqlx::Error::RowNotFound => {
+                        GetDoctorByPwzNumberRepositoryError::NotFound(pwz_number.to_string())
+                    }
+                    _ => map_database_error(err, GetDoctorByPwzNumberRepositoryError::DatabaseError, GetDoctorByPwzNumberRepositoryError::Unavailable),
+                }
+            })?;
+
+        let doctor = self.parse_doctors_row(doctor_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                GetDoctorByPwzNumberRepositoryError::DatabaseError,
+                GetDoctorByPwzNumberRepositoryError::Unavailable,
+            )
+        })?;
+
+        Ok(doctor)
+    }
+
+    async fn update_doctor_pwz_number(
+        &self,
+        doctor_id: Uuid,
+        new_pwz_number: &PwzNumber,
+        changed_by: Uuid,
+    ) -> Result<Doctor, UpdateDoctorPwzNumberRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                UpdateDoctorPwzNumberRepositoryError::DatabaseError,
+                UpdateDoctorPwzNumberRepositoryError::Unavailable,
+            )
+        };
+
+        // The old number is locked and read in the same transaction, so the audit entry always
+        // matches the number that was replaced
+        let mut transaction = self.pool.begin().await.map_err(map_error)?;
+
+        let old_pwz_number: PwzNumber =
+            sqlx::query_scalar(r#"SELECT pwz_number FROM doctors WHERE id = $1 FOR UPDATE"#)
+                .bind(doctor_id)
+                .fetch_one(&mut *transaction)
+                .await
+                .map_err(|err| match err {
+                    sqlx::Error::RowNotFound => {
+                        UpdateDoctorPwzNumberRepositoryError::NotFound(doctor_id)
+                    }
+                    _ => map_error(err),
+                })?;
+
+        let doctor_from_db = sqlx::query(
+                r#"UPDATE doctors SET pwz_number = $2, updated_at = NOW() WHERE id = $1 RETURNING id, name, pwz_number, pesel_number, created_at, updated_at, specialization"#
+            )
+            .bind(doctor_id)
+            .bind(new_pwz_number)
+            .fetch_one(&mut *transaction).await
+            .map_err(|err| {
+                match err {
+                    sqlx::Error::Database(err) if err.is_unique_violation() => {
+                        UpdateDoctorPwzNumberRepositoryError::DuplicatedPwzNumber
+                    },
+                    _ => map_error(err),
                 }
             })?;
 
-        let doctor = self
-            .parse_doctors_row(doctor_from_db)
-            .map_err(|err| GetDoctorByIdRepositoryError::DatabaseError(err.to_string()))?;
+        sqlx::query(
+                r#"INSERT INTO doctor_pwz_number_changes (doctor_id, old_pwz_number, new_pwz_number, changed_by) VALUES ($1, $2, $3, $4)"#
+            )
+            .bind(doctor_id)
+            .bind(old_pwz_number)
+            .bind(new_pwz_number)
+            .bind(changed_by)
+            .execute(&mut *transaction).await
+            .map_err(map_error)?;
+
+        transaction.commit().await.map_err(map_error)?;
+
+        let doctor = self.parse_doctors_row(doctor_from_db).map_err(map_error)?;
 
         Ok(doctor)
     }
+
+    async fn get_doctor_pwz_number_changes(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<DoctorPwzNumberChange>, GetDoctorPwzNumberChangesRepositoryError> {
+        let changes_from_db = sqlx::query(
+                r#"SELECT id, doctor_id, old_pwz_number, new_pwz_number, changed_by, changed_at FROM doctor_pwz_number_changes WHERE doctor_id = $1 ORDER BY changed_at"#
+            )
+            .bind(doctor_id)
+            .fetch_all(&self.pool).await
+            .map_err(|err| map_database_error(err, GetDoctorPwzNumberChangesRepositoryError::DatabaseError, GetDoctorPwzNumberChangesRepositoryError::Unavailable))?;
+
+        let mut changes: Vec<DoctorPwzNumberChange> = Vec::new();
+        for record in changes_from_db {
+            let change = self.parse_pwz_number_changes_row(record).map_err(|err| {
+                map_database_error(
+                    err,
+                    GetDoctorPwzNumberChangesRepositoryError::DatabaseError,
+                    GetDoctorPwzNumberChangesRepositoryError::Unavailable,
+                )
+            })?;
+            changes.push(change);
+        }
+
+        Ok(changes)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
     use uuid::Uuid;
 
     use super::PostgresDoctorsRepository;
@@ -134,7 +288,8 @@ mod tests {
             entities::NewDoctor,
             repository::{
                 CreateDoctorRepositoryError, DoctorsRepository, GetDoctorByIdRepositoryError,
-                GetDoctorsRepositoryError,
+                GetDoctorByPwzNumberRepositoryError, GetDoctorsRepositoryError,
+                UpdateDoctorPwzNumberRepositoryError,
             },
         },
         infrastructure::postgres_repository_impl::create_tables::create_tables,
@@ -172,6 +327,39 @@ mod tests {
         );
     }
 
+    #[sqlx::test]
+    async fn create_and_read_doctor_by_pwz_number(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let new_doctor =
+            NewDoctor::new("John Does".into(), "5425740".into(), "96021817257".into()).unwrap();
+
+        repository.create_doctor(new_doctor.clone()).await.unwrap();
+
+        let doctor_from_repo = repository
+            .get_doctor_by_pwz_number(&"5425740".try_into().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(doctor_from_repo, new_doctor);
+    }
+
+    #[sqlx::test]
+    async fn returns_error_if_doctor_with_given_pwz_number_doesnt_exist(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let doctor_from_repo = repository
+            .get_doctor_by_pwz_number(&"5425740".try_into().unwrap())
+            .await;
+
+        assert_eq!(
+            doctor_from_repo,
+            Err(GetDoctorByPwzNumberRepositoryError::NotFound(
+                "5425740".into()
+            ))
+        );
+    }
+
     #[sqlx::test]
     async fn create_and_read_doctors_from_database(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
@@ -202,7 +390,7 @@ mod tests {
             .await
             .unwrap();
 
-        let doctors = repository.get_doctors(None, Some(10)).await.unwrap();
+        let doctors = repository.get_doctors(None, Some(10), None).await.unwrap();
 
         assert_eq!(doctors.len(), 4);
         assert_eq!(doctors[0], new_doctor_0);
@@ -210,32 +398,74 @@ mod tests {
         assert_eq!(doctors[2], new_doctor_2);
         assert_eq!(doctors[3], new_doctor_3);
 
-        let doctors = repository.get_doctors(None, Some(2)).await.unwrap();
+        let doctors = repository.get_doctors(None, Some(2), None).await.unwrap();
 
         assert_eq!(doctors.len(), 2);
         assert_eq!(doctors[0], new_doctor_0);
         assert_eq!(doctors[1], new_doctor_1);
 
-        let doctors = repository.get_doctors(Some(1), Some(3)).await.unwrap();
+        let doctors = repository
+            .get_doctors(Some(1), Some(3), None)
+            .await
+            .unwrap();
 
         assert_eq!(doctors.len(), 1);
         assert_eq!(doctors[0], new_doctor_3);
 
-        let doctors = repository.get_doctors(Some(2), Some(3)).await.unwrap();
+        let doctors = repository
+            .get_doctors(Some(2), Some(3), None)
+            .await
+            .unwrap();
 
         assert_eq!(doctors.len(), 0);
     }
 
+    #[sqlx::test]
+    async fn filters_doctors_by_specialization(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let cardiologist =
+            NewDoctor::new("John First".into(), "5425740".into(), "96021817257".into())
+                .unwrap()
+                .with_specialization(Some("Cardiology".into()))
+                .unwrap();
+        let dermatologist =
+            NewDoctor::new("John Second".into(), "8463856".into(), "99031301347".into())
+                .unwrap()
+                .with_specialization(Some("dermatology".into()))
+                .unwrap();
+        let general_practitioner =
+            NewDoctor::new("John Third".into(), "3123456".into(), "92022900002".into()).unwrap();
+        for new_doctor in [&cardiologist, &dermatologist, &general_practitioner] {
+            repository.create_doctor(new_doctor.clone()).await.unwrap();
+        }
+
+        let doctors = repository
+            .get_doctors(None, None, Some("cardiology"))
+            .await
+            .unwrap();
+
+        assert_eq!(doctors.len(), 1);
+        assert_eq!(doctors[0], cardiologist);
+        assert_eq!(doctors[0].specialization, Some("cardiology".into()));
+
+        let doctors = repository.get_doctors(None, None, None).await.unwrap();
+
+        assert_eq!(doctors.len(), 3);
+    }
+
     #[sqlx::test]
     async fn get_doctors_returns_error_if_pagination_params_are_incorrect(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
 
-        assert!(match repository.get_doctors(Some(-1), Some(10)).await {
-            Err(GetDoctorsRepositoryError::InvalidPaginationParams(_)) => true,
-            _ => false,
-        });
+        assert!(
+            match repository.get_doctors(Some(-1), Some(10), None).await {
+                Err(GetDoctorsRepositoryError::InvalidPaginationParams(_)) => true,
+                _ => false,
+            }
+        );
 
-        assert!(match repository.get_doctors(Some(0), Some(0)).await {
+        assert!(match repository.get_doctors(Some(0), Some(0), None).await {
             Err(GetDoctorsRepositoryError::InvalidPaginationParams(_)) => true,
             _ => false,
         });
@@ -270,4 +500,108 @@ mod tests {
             Err(CreateDoctorRepositoryError::DuplicatedPeselNumber)
         );
     }
+
+    #[sqlx::test]
+    async fn updates_doctor_pwz_number_and_records_change(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+        let doctor = repository
+            .create_doctor(
+                NewDoctor::new("John Doe".into(), "5425740".into(), "96021817257".into()).unwrap(),
+            )
+            .await
+            .unwrap();
+        let changed_by = Uuid::new_v4();
+
+        let updated_doctor = repository
+            .update_doctor_pwz_number(doctor.id, &"8463856".try_into().unwrap(), changed_by)
+            .await
+            .unwrap();
+
+        assert_eq!(updated_doctor.pwz_number, "8463856");
+        assert!(updated_doctor.updated_at >= doctor.updated_at);
+        assert_eq!(
+            repository
+                .get_doctor_pwz_number_changes(doctor.id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|change| (
+                    change.old_pwz_number.to_string(),
+                    change.new_pwz_number.to_string(),
+                    change.changed_by
+                ))
+                .collect::<Vec<_>>(),
+            vec![("5425740".to_string(), "8463856".to_string(), changed_by)]
+        );
+        assert_eq!(
+            repository
+                .get_doctor_by_pwz_number(&"8463856".try_into().unwrap())
+                .await
+                .unwrap()
+                .id,
+            doctor.id
+        );
+    }
+
+    #[sqlx::test]
+    async fn doesnt_update_doctor_pwz_number_if_doctor_doesnt_exist_or_number_is_taken(
+        pool: sqlx::PgPool,
+    ) {
+        let repository = setup_repository(pool).await;
+        let doctor = repository
+            .create_doctor(
+                NewDoctor::new("John Doe".into(), "5425740".into(), "96021817257".into()).unwrap(),
+            )
+            .await
+            .unwrap();
+        repository
+            .create_doctor(
+                NewDoctor::new("Jane Doe".into(), "8463856".into(), "99031301347".into()).unwrap(),
+            )
+            .await
+            .unwrap();
+        let unknown_doctor_id = Uuid::new_v4();
+
+        assert_eq!(
+            repository
+                .update_doctor_pwz_number(doctor.id, &"8463856".try_into().unwrap(), doctor.id)
+                .await,
+            Err(UpdateDoctorPwzNumberRepositoryError::DuplicatedPwzNumber)
+        );
+        assert_eq!(
+            repository
+                .update_doctor_pwz_number(
+                    unknown_doctor_id,
+                    &"3123456".try_into().unwrap(),
+                    doctor.id
+                )
+                .await,
+            Err(UpdateDoctorPwzNumberRepositoryError::NotFound(
+                unknown_doctor_id
+            ))
+        );
+        assert_eq!(
+            repository
+                .get_doctor_pwz_number_changes(doctor.id)
+                .await
+                .unwrap(),
+            vec![]
+        );
+    }
+
+    #[sqlx::test]
+    async fn returns_unavailable_error_if_pool_is_exhausted(pool: sqlx::PgPool) {
+        let tiny_pool = PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let _held_connection = tiny_pool.acquire().await.unwrap();
+        let repository = PostgresDoctorsRepository::new(tiny_pool);
+
+        let doctors = repository.get_doctors(None, None, None).await;
+
+        assert_eq!(doctors, Err(GetDoctorsRepositoryError::Unavailable));
+    }
 }
@@ -1,18 +1,51 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use sqlx::Row;
 use uuid::Uuid;
 
 use crate::domain::{
     drugs::{
-        entities::{Drug, NewDrug},
+        entities::{Drug, DrugContentType, NewDrug},
         repository::{
-            CreateDrugRepositoryError, DrugsRepository, GetDrugByIdRepositoryError,
-            GetDrugsRepositoryError,
+            AdjustDrugStockRepositoryError, CreateDrugRepositoryError,
+            DeactivateDrugRepositoryError, DrugsRepository, GetDrugByBarcodeRepositoryError,
+            GetDrugByIdRepositoryError, GetDrugsRepositoryError, DRUGS_DEFAULT_PAGE_SIZE,
         },
     },
+    prescriptions::entities::PrescriptionType,
     utils::pagination::get_pagination_params,
 };
 
+use super::database_error::map_database_error;
+
+pub(super) fn parse_drugs_row(row: sqlx::postgres::PgRow) -> Result<Drug, sqlx::Error> {
+    Ok(Drug {
+        id: row.try_get(0)?,
+        name: row.try_get(1)?,
+        content_type: row.try_get(2)?,
+        pills_count: row.try_get(3)?,
+        mg_per_pill: row.try_get(4)?,
+        ml_per_pill: row.try_get(5)?,
+        volume_ml: row.try_get(6)?,
+        unit_price_cents: row.try_get(7)?,
+        manufacturer: row.try_get(8)?,
+        barcode: row.try_get(9)?,
+        created_at: row.try_get(10)?,
+        updated_at: row.try_get(11)?,
+        deactivated_at: row.try_get(12)?,
+        doses_count: row.try_get(13)?,
+        mcg_per_dose: row.try_get(14)?,
+        ml_per_dose: row.try_get(15)?,
+        created_by: row.try_get(16)?,
+        category: row.try_get(17)?,
+        allowed_prescription_types: row
+            .try_get::<Option<Vec<PrescriptionType>>, _>(18)?
+            .unwrap_or_default(),
+        stock: row.try_get(19)?,
+    })
+}
+
 pub struct PostgresDrugsRepository {
     pool: sqlx::PgPool,
 }
@@ -21,27 +54,13 @@ impl PostgresDrugsRepository {
     pub fn new(pool: sqlx::PgPool) -> Self {
         Self { pool }
     }
-
-    fn parse_drugs_row(&self, row: sqlx::postgres::PgRow) -> Result<Drug, sqlx::Error> {
-        Ok(Drug {
-            id: row.try_get(0)?,
-            name: row.try_get(1)?,
-            content_type: row.try_get(2)?,
-            pills_count: row.try_get(3)?,
-            mg_per_pill: row.try_get(4)?,
-            ml_per_pill: row.try_get(5)?,
-            volume_ml: row.try_get(6)?,
-            created_at: row.try_get(7)?,
-            updated_at: row.try_get(8)?,
-        })
-    }
 }
 
 #[async_trait]
 impl DrugsRepository for PostgresDrugsRepository {
     async fn create_drug(&self, drug: NewDrug) -> Result<Drug, CreateDrugRepositoryError> {
         let result = sqlx::query(
-                r#"INSERT INTO drugs (id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, created_at, updated_at"#
+                r#"INSERT INTO drugs (id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) RETURNING id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, created_at, updated_at, deactivated_at, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types, stock"#
             )
             .bind(drug.id)
             .bind(drug.name)
@@ -50,35 +69,65 @@ impl DrugsRepository for PostgresDrugsRepository {
             .bind(drug.mg_per_pill)
             .bind(drug.ml_per_pill)
             .bind(drug.volume_ml)
+            .bind(drug.unit_price_cents)
+            .bind(drug.manufacturer)
+            .bind(drug.barcode)
+            .bind(drug.doses_count)
+            .bind(drug.mcg_per_dose)
+            .bind(drug.ml_per_dose)
+            .bind(drug.created_by)
+            .bind(drug.category)
+            .bind(drug.allowed_prescription_types)
             .fetch_one(&self.pool).await
-            .map_err(|err| CreateDrugRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| {
+                match err {
+                    sqlx::Error::Database(err) if err.is_unique_violation() => {
+                        match err.constraint() {
+                            Some("drugs_barcode_key") => CreateDrugRepositoryError::DuplicatedBarcode,
+                            Some("drugs_name_dosage_key") => CreateDrugRepositoryError::DuplicatedDrug,
+                            _ => CreateDrugRepositoryError::DatabaseError(err.to_string()),
+                        }
+                    },
+                    _ => map_database_error(err, CreateDrugRepositoryError::DatabaseError, CreateDrugRepositoryError::Unavailable),
+                }
+            })?;
 
-        Ok(self
-            .parse_drugs_row(result)
-            .map_err(|err| CreateDrugRepositoryError::DatabaseError(err.to_string()))?)
+        Ok(parse_drugs_row(result).map_err(|err| {
+            map_database_error(
+                err,
+                CreateDrugRepositoryError::DatabaseError,
+                CreateDrugRepositoryError::Unavailable,
+            )
+        })?)
     }
 
     async fn get_drugs(
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        include_inactive: bool,
     ) -> Result<Vec<Drug>, GetDrugsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size)
-            .map_err(|err| GetDrugsRepositoryError::InvalidPaginationParams(err.to_string()))?;
+        let (page_size, offset) = get_pagination_params(page, page_size, DRUGS_DEFAULT_PAGE_SIZE)
+            .map_err(GetDrugsRepositoryError::InvalidPaginationParams)?;
 
         let drugs_from_db = sqlx::query(
-                r#"SELECT id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, created_at, updated_at FROM drugs LIMIT $1 OFFSET $2"#
+                r#"SELECT id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, created_at, updated_at, deactivated_at, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types, stock FROM drugs WHERE $3 OR deactivated_at IS NULL ORDER BY created_at ASC, id ASC LIMIT $1 OFFSET $2"#
             )
             .bind(page_size)
             .bind(offset)
+            .bind(include_inactive)
             .fetch_all(&self.pool).await
-            .map_err(|err| GetDrugsRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, GetDrugsRepositoryError::DatabaseError, GetDrugsRepositoryError::Unavailable))?;
 
         let mut drugs = vec![];
         for record in drugs_from_db {
-            let drug = self
-                .parse_drugs_row(record)
-                .map_err(|err| GetDrugsRepositoryError::DatabaseError(err.to_string()))?;
+            let drug = parse_drugs_row(record).map_err(|err| {
+                map_database_error(
+                    err,
+                    GetDrugsRepositoryError::DatabaseError,
+                    GetDrugsRepositoryError::Unavailable,
+                )
+            })?;
             drugs.push(drug);
         }
 
@@ -87,20 +136,143 @@ impl DrugsRepository for PostgresDrugsRepository {
 
     async fn get_drug_by_id(&self, drug_id: Uuid) -> Result<Drug, GetDrugByIdRepositoryError> {
         let drug_from_db = sqlx::query(
-                r#"SELECT id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, created_at, updated_at FROM drugs WHERE id = $1"#
+                r#"SELECT id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, created_at, updated_at, deactivated_at, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types, stock FROM drugs WHERE id = $1"#
             )
             .bind(drug_id)
             .fetch_one(&self.pool).await
             .map_err(|err| {
                 match err {
                     sqlx::Error::RowNotFound => GetDrugByIdRepositoryError::NotFound(drug_id),
-                    _ => GetDrugByIdRepositoryError::DatabaseError(err.to_string()),
+                    _ => map_database_error(err, GetDrugByIdRepositoryError::DatabaseError, GetDrugByIdRepositoryError::Unavailable),
+                }
+            })?;
+
+        Ok(parse_drugs_row(drug_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                GetDrugByIdRepositoryError::DatabaseError,
+                GetDrugByIdRepositoryError::Unavailable,
+            )
+        })?)
+    }
+
+    async fn get_drug_by_barcode(
+        &self,
+        barcode: &str,
+    ) -> Result<Drug, GetDrugByBarcodeRepositoryError> {
+        let drug_from_db = sqlx::query(
+                r#"SELECT id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, created_at, updated_at, deactivated_at, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types, stock FROM drugs WHERE barcode = $1"#
+            )
+            .bind(barcode)
+            .fetch_one(&self.pool).await
+            .map_err(|err| {
+                match err {
+                    sqlx::Error::RowNotFound => GetDrugByBarcodeRepositoryError::NotFound(barcode.to_string()),
+                    _ => map_database_error(err, GetDrugByBarcodeRepositoryError::DatabaseError, GetDrugByBarcodeRepositoryError::Unavailable),
+                }
+            })?;
+
+        Ok(parse_drugs_row(drug_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                GetDrugByBarcodeRepositoryError::DatabaseError,
+                GetDrugByBarcodeRepositoryError::Unavailable,
+            )
+        })?)
+    }
+
+    async fn deactivate_drug(&self, drug_id: Uuid) -> Result<Drug, DeactivateDrugRepositoryError> {
+        let drug_from_db = sqlx::query(
+                r#"UPDATE drugs SET deactivated_at = COALESCE(deactivated_at, NOW()), updated_at = NOW() WHERE id = $1 RETURNING id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, created_at, updated_at, deactivated_at, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types, stock"#
+            )
+            .bind(drug_id)
+            .fetch_one(&self.pool).await
+            .map_err(|err| {
+                match err {
+                    sqlx::Error::RowNotFound => DeactivateDrugRepositoryError::NotFound(drug_id),
+                    _ => map_database_error(err, DeactivateDrugRepositoryError::DatabaseError, DeactivateDrugRepositoryError::Unavailable),
                 }
             })?;
 
-        Ok(self
-            .parse_drugs_row(drug_from_db)
-            .map_err(|err| GetDrugByIdRepositoryError::DatabaseError(err.to_string()))?)
+        Ok(parse_drugs_row(drug_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                DeactivateDrugRepositoryError::DatabaseError,
+                DeactivateDrugRepositoryError::Unavailable,
+            )
+        })?)
+    }
+
+    async fn adjust_drug_stock(
+        &self,
+        drug_id: Uuid,
+        delta: i32,
+    ) -> Result<Drug, AdjustDrugStockRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                AdjustDrugStockRepositoryError::DatabaseError,
+                AdjustDrugStockRepositoryError::Unavailable,
+            )
+        };
+
+        let drug_from_db = sqlx::query(
+                r#"UPDATE drugs SET stock = stock + $2, updated_at = NOW() WHERE id = $1 AND stock + $2 >= 0 RETURNING id, name, content_type, pills_count, mg_per_pill, ml_per_pill, volume_ml, unit_price_cents, manufacturer, barcode, created_at, updated_at, deactivated_at, doses_count, mcg_per_dose, ml_per_dose, created_by, category, allowed_prescription_types, stock"#
+            )
+            .bind(drug_id)
+            .bind(delta)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(map_error)?;
+
+        match drug_from_db {
+            Some(row) => parse_drugs_row(row).map_err(map_error),
+            None => {
+                // Nothing was updated, either the drug is missing or its stock is too low
+                let drug_exists: bool =
+                    sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM drugs WHERE id = $1)"#)
+                        .bind(drug_id)
+                        .fetch_one(&self.pool)
+                        .await
+                        .map_err(map_error)?;
+                if drug_exists {
+                    Err(AdjustDrugStockRepositoryError::InsufficientStock(drug_id))
+                } else {
+                    Err(AdjustDrugStockRepositoryError::NotFound(drug_id))
+                }
+            }
+        }
+    }
+
+    async fn count_drugs_by_content_type(
+        &self,
+    ) -> Result<HashMap<DrugContentType, i64>, GetDrugsRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                GetDrugsRepositoryError::DatabaseError,
+                GetDrugsRepositoryError::Unavailable,
+            )
+        };
+
+        let counts_from_db = sqlx::query(
+            r#"SELECT content_type, COUNT(*) FROM drugs WHERE deactivated_at IS NULL GROUP BY content_type"#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_error)?;
+
+        let mut counts: HashMap<DrugContentType, i64> = DrugContentType::ALL
+            .into_iter()
+            .map(|content_type| (content_type, 0))
+            .collect();
+        for row in counts_from_db {
+            let content_type: DrugContentType = row.try_get(0).map_err(map_error)?;
+            let count: i64 = row.try_get(1).map_err(map_error)?;
+            counts.insert(content_type, count);
+        }
+
+        Ok(counts)
     }
 }
 
@@ -112,7 +284,11 @@ mod tests {
     use crate::{
         domain::drugs::{
             entities::{DrugContentType, NewDrug},
-            repository::{GetDrugByIdRepositoryError, GetDrugsRepositoryError},
+            repository::{
+                AdjustDrugStockRepositoryError, CreateDrugRepositoryError,
+                DeactivateDrugRepositoryError, GetDrugByBarcodeRepositoryError,
+                GetDrugByIdRepositoryError, GetDrugsRepositoryError,
+            },
         },
         infrastructure::postgres_repository_impl::create_tables::create_tables,
     };
@@ -133,6 +309,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -145,6 +327,84 @@ mod tests {
         assert_eq!(drug, drug_from_repo);
     }
 
+    #[sqlx::test]
+    async fn create_and_read_drug_with_creator(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let drug = NewDrug {
+            created_by: Some(Uuid::new_v4()),
+            ..NewDrug::new(
+                "Gripex Max".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        repository.create_drug(drug.clone()).await.unwrap();
+
+        let drug_from_repo = repository.get_drug_by_id(drug.id).await.unwrap();
+
+        assert_eq!(drug_from_repo.created_by, drug.created_by);
+    }
+
+    #[sqlx::test]
+    async fn creates_and_reads_inhaler_and_injection_drugs(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let inhaler = NewDrug::new(
+            "Ventolin".into(),
+            DrugContentType::Inhaler,
+            None,
+            None,
+            None,
+            None,
+            Some(200),
+            Some(100),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let injection = NewDrug::new(
+            "Clexane".into(),
+            DrugContentType::Injection,
+            None,
+            None,
+            None,
+            None,
+            Some(10),
+            None,
+            Some(1),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        repository.create_drug(inhaler.clone()).await.unwrap();
+        repository.create_drug(injection.clone()).await.unwrap();
+
+        assert_eq!(
+            repository.get_drug_by_id(inhaler.id).await.unwrap(),
+            inhaler
+        );
+        assert_eq!(
+            repository.get_drug_by_id(injection.id).await.unwrap(),
+            injection
+        );
+    }
+
     #[sqlx::test]
     async fn returns_error_if_drug_with_given_id_doesnt_exist(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
@@ -158,6 +418,163 @@ mod tests {
         );
     }
 
+    #[sqlx::test]
+    async fn create_and_read_drug_by_barcode(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let drug = NewDrug::new(
+            "Gripex Max".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("US Pharmacia".into()),
+            Some("5909990733828".into()),
+        )
+        .unwrap();
+
+        repository.create_drug(drug.clone()).await.unwrap();
+
+        let drug_from_repo = repository
+            .get_drug_by_barcode("5909990733828")
+            .await
+            .unwrap();
+
+        assert_eq!(drug, drug_from_repo);
+
+        assert_eq!(
+            repository.get_drug_by_barcode("5900017304007").await,
+            Err(GetDrugByBarcodeRepositoryError::NotFound(
+                "5900017304007".into()
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn returns_error_if_barcode_is_duplicated(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let create_drug_with_barcode = |name: &str, barcode: Option<&str>| {
+            NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                barcode.map(Into::into),
+            )
+            .unwrap()
+        };
+
+        repository
+            .create_drug(create_drug_with_barcode("Gripex", Some("5909990733828")))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_drug_with_barcode("Apap", None))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_drug_with_barcode("Flegamax", None))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .create_drug(create_drug_with_barcode(
+                    "Gripex Max",
+                    Some("5909990733828")
+                ))
+                .await,
+            Err(CreateDrugRepositoryError::DuplicatedBarcode)
+        );
+    }
+
+    #[sqlx::test]
+    async fn returns_error_if_drug_with_same_name_and_strength_exists(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let create_drug_with_strength = |mg_per_pill: i32| {
+            NewDrug::new(
+                "Apap".into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(mg_per_pill),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        repository
+            .create_drug(create_drug_with_strength(300))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_drug_with_strength(500))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository.create_drug(create_drug_with_strength(300)).await,
+            Err(CreateDrugRepositoryError::DuplicatedDrug)
+        );
+    }
+
+    #[sqlx::test]
+    async fn returns_error_if_inhaler_with_same_name_and_dose_exists(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let create_inhaler_with_dose = |mcg_per_dose: i32| {
+            NewDrug::new(
+                "Ventolin".into(),
+                DrugContentType::Inhaler,
+                None,
+                None,
+                None,
+                None,
+                Some(200),
+                Some(mcg_per_dose),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        repository
+            .create_drug(create_inhaler_with_dose(100))
+            .await
+            .unwrap();
+        repository
+            .create_drug(create_inhaler_with_dose(200))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository.create_drug(create_inhaler_with_dose(100)).await,
+            Err(CreateDrugRepositoryError::DuplicatedDrug)
+        );
+    }
+
     #[sqlx::test]
     async fn create_and_read_drugs_from_database(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
@@ -169,6 +586,12 @@ mod tests {
             Some(300),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
         let new_drug_1 = NewDrug::new(
@@ -178,6 +601,12 @@ mod tests {
             Some(400),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
         let new_drug_2 = NewDrug::new(
@@ -187,6 +616,12 @@ mod tests {
             Some(200),
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
         let new_drug_3 = NewDrug::new(
@@ -196,6 +631,12 @@ mod tests {
             None,
             None,
             Some(400),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -204,7 +645,7 @@ mod tests {
         repository.create_drug(new_drug_2.clone()).await.unwrap();
         repository.create_drug(new_drug_3.clone()).await.unwrap();
 
-        let drugs = repository.get_drugs(None, Some(10)).await.unwrap();
+        let drugs = repository.get_drugs(None, Some(10), false).await.unwrap();
 
         assert_eq!(drugs.len(), 4);
         assert_eq!(drugs[0], new_drug_0);
@@ -212,18 +653,18 @@ mod tests {
         assert_eq!(drugs[2], new_drug_2);
         assert_eq!(drugs[3], new_drug_3);
 
-        let drugs = repository.get_drugs(None, Some(2)).await.unwrap();
+        let drugs = repository.get_drugs(None, Some(2), false).await.unwrap();
 
         assert_eq!(drugs.len(), 2);
         assert_eq!(drugs[0], new_drug_0);
         assert_eq!(drugs[1], new_drug_1);
 
-        let drugs = repository.get_drugs(Some(1), Some(3)).await.unwrap();
+        let drugs = repository.get_drugs(Some(1), Some(3), false).await.unwrap();
 
         assert_eq!(drugs.len(), 1);
         assert_eq!(drugs[0], new_drug_3);
 
-        let drugs = repository.get_drugs(Some(2), Some(3)).await.unwrap();
+        let drugs = repository.get_drugs(Some(2), Some(3), false).await.unwrap();
 
         assert_eq!(drugs.len(), 0);
     }
@@ -232,14 +673,174 @@ mod tests {
     async fn get_drugs_returns_error_if_pagination_params_are_incorrect(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
 
-        assert!(match repository.get_drugs(Some(-1), Some(10)).await {
-            Err(GetDrugsRepositoryError::InvalidPaginationParams(_)) => true,
-            _ => false,
-        },);
+        assert!(
+            match repository.get_drugs(Some(-1), Some(10), false).await {
+                Err(GetDrugsRepositoryError::InvalidPaginationParams(_)) => true,
+                _ => false,
+            },
+        );
 
-        assert!(match repository.get_drugs(Some(0), Some(0)).await {
+        assert!(match repository.get_drugs(Some(0), Some(0), false).await {
             Err(GetDrugsRepositoryError::InvalidPaginationParams(_)) => true,
             _ => false,
         },);
     }
+
+    #[sqlx::test]
+    async fn adjusts_drug_stock_without_going_below_zero(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+        let new_drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        repository.create_drug(new_drug.clone()).await.unwrap();
+
+        let drug = repository.adjust_drug_stock(new_drug.id, 5).await.unwrap();
+
+        assert_eq!(drug.stock, 5);
+
+        let drug = repository.adjust_drug_stock(new_drug.id, -3).await.unwrap();
+
+        assert_eq!(drug.stock, 2);
+        assert_eq!(
+            repository.adjust_drug_stock(new_drug.id, -3).await,
+            Err(AdjustDrugStockRepositoryError::InsufficientStock(
+                new_drug.id
+            ))
+        );
+        assert_eq!(
+            repository.get_drug_by_id(new_drug.id).await.unwrap().stock,
+            2
+        );
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository.adjust_drug_stock(nonexistent_drug_id, 1).await,
+            Err(AdjustDrugStockRepositoryError::NotFound(
+                nonexistent_drug_id
+            ))
+        );
+    }
+    #[sqlx::test]
+    async fn hides_deactivated_drugs_unless_inactive_are_included(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let mut new_drugs = vec![];
+        for name in ["Gripex", "Apap", "Flegamax"] {
+            let new_drug = NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            repository.create_drug(new_drug.clone()).await.unwrap();
+            new_drugs.push(new_drug);
+        }
+
+        let deactivated_drug = repository.deactivate_drug(new_drugs[1].id).await.unwrap();
+
+        assert!(deactivated_drug.deactivated_at.is_some());
+
+        let drugs = repository.get_drugs(None, None, false).await.unwrap();
+
+        assert_eq!(drugs.len(), 2);
+        assert_eq!(drugs[0], new_drugs[0]);
+        assert_eq!(drugs[1], new_drugs[2]);
+
+        let drugs = repository.get_drugs(None, None, true).await.unwrap();
+
+        let deactivated_drugs: Vec<_> = drugs
+            .iter()
+            .filter(|drug| drug.deactivated_at.is_some())
+            .collect();
+
+        assert_eq!(drugs.len(), 3);
+        assert_eq!(deactivated_drugs.len(), 1);
+        assert_eq!(deactivated_drugs[0].id, new_drugs[1].id);
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository.deactivate_drug(nonexistent_drug_id).await,
+            Err(DeactivateDrugRepositoryError::NotFound(nonexistent_drug_id))
+        );
+    }
+
+    #[sqlx::test]
+    async fn counts_active_drugs_by_content_type(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+        let solid_pills = |name: &str| {
+            NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        let bottle_of_liquid = NewDrug::new(
+            "Flegamax".into(),
+            DrugContentType::BottleOfLiquid,
+            None,
+            None,
+            None,
+            Some(150),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let deactivated_drug = solid_pills("Ibuprom");
+        for new_drug in [
+            solid_pills("Apap"),
+            solid_pills("Gripex"),
+            bottle_of_liquid,
+            deactivated_drug.clone(),
+        ] {
+            repository.create_drug(new_drug).await.unwrap();
+        }
+        repository
+            .deactivate_drug(deactivated_drug.id)
+            .await
+            .unwrap();
+
+        let counts = repository.count_drugs_by_content_type().await.unwrap();
+
+        assert_eq!(counts.len(), DrugContentType::ALL.len());
+        assert_eq!(counts[&DrugContentType::SolidPills], 2);
+        assert_eq!(counts[&DrugContentType::BottleOfLiquid], 1);
+        assert_eq!(counts[&DrugContentType::LiquidPills], 0);
+        assert_eq!(counts[&DrugContentType::Inhaler], 0);
+        assert_eq!(counts[&DrugContentType::Injection], 0);
+    }
 }
@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::ReadCommitted => "READ COMMITTED",
+            Self::RepeatableRead => "REPEATABLE READ",
+            Self::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("Unknown transaction isolation level: {0}")]
+pub struct UnknownIsolationLevelError(String);
+
+impl FromStr for IsolationLevel {
+    type Err = UnknownIsolationLevelError;
+
+    // Accepts the SQL names in any case, with spaces, underscores or dashes between the words
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value
+            .trim()
+            .to_lowercase()
+            .replace(['_', '-'], " ")
+            .as_str()
+        {
+            "read committed" => Ok(Self::ReadCommitted),
+            "repeatable read" => Ok(Self::RepeatableRead),
+            "serializable" => Ok(Self::Serializable),
+            _ => Err(UnknownIsolationLevelError(value.to_string())),
+        }
+    }
+}
+
+// Without an isolation level the transaction runs at the database default, READ COMMITTED unless
+// the server is configured otherwise
+pub async fn begin_transaction(
+    pool: &PgPool,
+    isolation_level: Option<IsolationLevel>,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    if let Some(isolation_level) = isolation_level {
+        sqlx::query(&format!(
+            "SET TRANSACTION ISOLATION LEVEL {}",
+            isolation_level.as_sql()
+        ))
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{begin_transaction, IsolationLevel, UnknownIsolationLevelError};
+
+    async fn get_transaction_isolation(
+        pool: &sqlx::PgPool,
+        isolation_level: Option<IsolationLevel>,
+    ) -> String {
+        let mut transaction = begin_transaction(pool, isolation_level).await.unwrap();
+
+        sqlx::query_scalar("SELECT current_setting('transaction_isolation')")
+            .fetch_one(&mut *transaction)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_isolation_level() {
+        assert_eq!(
+            "REPEATABLE READ".parse::<IsolationLevel>(),
+            Ok(IsolationLevel::RepeatableRead)
+        );
+        assert_eq!(
+            "read_committed".parse::<IsolationLevel>(),
+            Ok(IsolationLevel::ReadCommitted)
+        );
+        assert_eq!(
+            " serializable ".parse::<IsolationLevel>(),
+            Ok(IsolationLevel::Serializable)
+        );
+        assert_eq!(
+            "snapshot".parse::<IsolationLevel>(),
+            Err(UnknownIsolationLevelError("snapshot".into()))
+        );
+    }
+
+    #[sqlx::test]
+    async fn runs_transaction_at_configured_isolation_level(pool: sqlx::PgPool) {
+        assert_eq!(
+            get_transaction_isolation(&pool, None).await,
+            "read committed"
+        );
+        assert_eq!(
+            get_transaction_isolation(&pool, Some(IsolationLevel::RepeatableRead)).await,
+            "repeatable read"
+        );
+        assert_eq!(
+            get_transaction_isolation(&pool, Some(IsolationLevel::Serializable)).await,
+            "serializable"
+        );
+    }
+}
@@ -1,8 +1,15 @@
+pub mod authentication;
 pub mod create_tables;
+pub mod database_error;
 pub mod doctors;
 pub mod drugs;
+pub mod isolation_level;
 pub mod patients;
 pub mod pharmacists;
+pub mod pool_metrics;
 pub mod prescriptions;
+pub mod query_metrics;
+pub mod retry;
 pub mod sessions;
-pub mod authentication;
\ No newline at end of file
+pub mod slow_query_log;
+pub mod statement_timeout;
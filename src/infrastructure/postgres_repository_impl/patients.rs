@@ -7,12 +7,15 @@ use crate::domain::{
         entities::{NewPatient, Patient},
         repository::{
             CreatePatientRepositoryError, GetPatientByIdRepositoryError,
-            GetPatientsRepositoryError, PatientsRepository,
+            GetPatientByPeselNumberRepositoryError, GetPatientsRepositoryError,
+            MergePatientsRepositoryError, PatientsRepository,
         },
     },
-    utils::pagination::get_pagination_params,
+    utils::pagination::{get_pagination_params, DEFAULT_PAGE_SIZE},
 };
 
+use super::database_error::map_database_error;
+
 pub struct PostgresPatientsRepository {
     pool: sqlx::PgPool,
 }
@@ -29,6 +32,7 @@ impl PostgresPatientsRepository {
             pesel_number: row.try_get(2)?,
             created_at: row.try_get(3)?,
             updated_at: row.try_get(4)?,
+            created_by: row.try_get(5)?,
         })
     }
 }
@@ -40,11 +44,12 @@ impl PatientsRepository for PostgresPatientsRepository {
         patient: NewPatient,
     ) -> Result<Patient, CreatePatientRepositoryError> {
         let result = sqlx::query(
-                r#"INSERT INTO patients (id, name, pesel_number) VALUES ($1, $2, $3) RETURNING id, name, pesel_number, created_at, updated_at"#
+                r#"INSERT INTO patients (id, name, pesel_number, created_by) VALUES ($1, $2, $3, $4) RETURNING id, name, pesel_number, created_at, updated_at, created_by"#
             )
             .bind(patient.id)
             .bind(patient.name)
             .bind(patient.pesel_number)
+            .bind(patient.created_by)
             .fetch_one(&self.pool).await
             .map_err(|err| {
                 match err {
@@ -56,13 +61,17 @@ impl PatientsRepository for PostgresPatientsRepository {
                             _ => CreatePatientRepositoryError::DatabaseError(err.to_string()),
                         }
                     }
-                    _ => CreatePatientRepositoryError::DatabaseError(err.to_string()),
+                    _ => map_database_error(err, CreatePatientRepositoryError::DatabaseError, CreatePatientRepositoryError::Unavailable),
                 }
             })?;
 
-        let patient = self
-            .parse_patients_row(result)
-            .map_err(|err| CreatePatientRepositoryError::DatabaseError(err.to_string()))?;
+        let patient = self.parse_patients_row(result).map_err(|err| {
+            map_database_error(
+                err,
+                CreatePatientRepositoryError::DatabaseError,
+                CreatePatientRepositoryError::Unavailable,
+            )
+        })?;
         Ok(patient)
     }
 
@@ -71,22 +80,26 @@ impl PatientsRepository for PostgresPatientsRepository {
         page: Option<i64>,
         page_size: Option<i64>,
     ) -> Result<Vec<Patient>, GetPatientsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size)
-            .map_err(|err| GetPatientsRepositoryError::InvalidPaginationParams(err.to_string()))?;
+        let (page_size, offset) = get_pagination_params(page, page_size, DEFAULT_PAGE_SIZE)
+            .map_err(GetPatientsRepositoryError::InvalidPaginationParams)?;
 
         let patients_from_db = sqlx::query(
-                r#"SELECT id, name, pesel_number, created_at, updated_at FROM patients LIMIT $1 OFFSET $2"#
+                r#"SELECT id, name, pesel_number, created_at, updated_at, created_by FROM patients ORDER BY created_at ASC, id ASC LIMIT $1 OFFSET $2"#
             )
             .bind(page_size)
             .bind(offset)
             .fetch_all(&self.pool).await
-            .map_err(|err| GetPatientsRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, GetPatientsRepositoryError::DatabaseError, GetPatientsRepositoryError::Unavailable))?;
 
         let mut patients: Vec<Patient> = Vec::new();
         for record in patients_from_db {
-            let patient = self
-                .parse_patients_row(record)
-                .map_err(|err| GetPatientsRepositoryError::DatabaseError(err.to_string()))?;
+            let patient = self.parse_patients_row(record).map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPatientsRepositoryError::DatabaseError,
+                    GetPatientsRepositoryError::Unavailable,
+                )
+            })?;
             patients.push(patient);
         }
 
@@ -98,21 +111,123 @@ impl PatientsRepository for PostgresPatientsRepository {
         patient_id: Uuid,
     ) -> Result<Patient, GetPatientByIdRepositoryError> {
         let patient_from_db = sqlx::query(
-            r#"SELECT id, name, pesel_number, created_at, updated_at FROM patients WHERE id = $1"#,
+            r#"SELECT id, name, pesel_number, created_at, updated_at, created_by FROM patients WHERE id = $1"#,
         )
         .bind(patient_id)
         .fetch_one(&self.pool)
         .await
         .map_err(|err| match err {
             sqlx::Error::RowNotFound => GetPatientByIdRepositoryError::NotFound(patient_id),
-            _ => GetPatientByIdRepositoryError::DatabaseError(err.to_string()),
+            _ => map_database_error(
+                err,
+                GetPatientByIdRepositoryError::DatabaseError,
+                GetPatientByIdRepositoryError::Unavailable,
+            ),
+        })?;
+
+        let patient = self.parse_patients_row(patient_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                GetPatientByIdRepositoryError::DatabaseError,
+                GetPatientByIdRepositoryError::Unavailable,
+            )
+        })?;
+        Ok(patient)
+    }
+
+    async fn get_patient_by_pesel_number(
+        &self,
+        pesel_number: &str,
+    ) -> Result<Patient, GetPatientByPeselNumberRepositoryError> {
+        let patient_from_db = sqlx::query(
+            r#"SELECT id, name, pesel_number, created_at, updated_at, created_by FROM patients WHERE pesel_number = $1"#,
+        )
+        .bind(pesel_number)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => {
+                GetPatientByPeselNumberRepositoryError::NotFound(pesel_number.to_string())
+            }
+            _ => map_database_error(
+                err,
+                GetPatientByPeselNumberRepositoryError::DatabaseError,
+                GetPatientByPeselNumberRepositoryError::Unavailable,
+            ),
         })?;
 
-        let patient = self
-            .parse_patients_row(patient_from_db)
-            .map_err(|err| GetPatientByIdRepositoryError::DatabaseError(err.to_string()))?;
+        let patient = self.parse_patients_row(patient_from_db).map_err(|err| {
+            map_database_error(
+                err,
+                GetPatientByPeselNumberRepositoryError::DatabaseError,
+                GetPatientByPeselNumberRepositoryError::Unavailable,
+            )
+        })?;
         Ok(patient)
     }
+
+    async fn merge_patients(
+        &self,
+        keep_id: Uuid,
+        merge_id: Uuid,
+    ) -> Result<Patient, MergePatientsRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                MergePatientsRepositoryError::DatabaseError,
+                MergePatientsRepositoryError::Unavailable,
+            )
+        };
+
+        // Both patients are locked, so no prescription is created for the merged one in the meantime
+        let mut transaction = self.pool.begin().await.map_err(map_error)?;
+
+        let mut patients = Vec::new();
+        for patient_id in [keep_id, merge_id] {
+            let patient_from_db = sqlx::query(
+                r#"SELECT id, name, pesel_number, created_at, updated_at, created_by FROM patients WHERE id = $1 FOR UPDATE"#,
+            )
+            .bind(patient_id)
+            .fetch_one(&mut *transaction)
+            .await
+            .map_err(|err| match err {
+                sqlx::Error::RowNotFound => MergePatientsRepositoryError::NotFound(patient_id),
+                _ => map_error(err),
+            })?;
+            patients.push(
+                self.parse_patients_row(patient_from_db)
+                    .map_err(map_error)?,
+            );
+        }
+        let kept_patient = patients.remove(0);
+
+        sqlx::query(
+            r#"UPDATE prescriptions SET patient_id = $1, updated_at = NOW() WHERE patient_id = $2"#,
+        )
+        .bind(keep_id)
+        .bind(merge_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(map_error)?;
+
+        // Accounts of the merged patient log in as the kept one from now on
+        sqlx::query(r#"UPDATE users SET patient_id = $1 WHERE patient_id = $2"#)
+            .bind(keep_id)
+            .bind(merge_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(map_error)?;
+
+        sqlx::query(r#"DELETE FROM patients WHERE id = $1"#)
+            .bind(merge_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(map_error)?;
+
+        transaction.commit().await.map_err(map_error)?;
+
+        Ok(kept_patient)
+    }
 }
 
 #[cfg(test)]
@@ -121,14 +236,29 @@ mod tests {
 
     use super::PostgresPatientsRepository;
     use crate::{
-        domain::patients::{
-            entities::NewPatient,
-            repository::{
-                CreatePatientRepositoryError, GetPatientByIdRepositoryError,
-                GetPatientsRepositoryError, PatientsRepository,
+        domain::{
+            doctors::{entities::NewDoctor, repository::DoctorsRepository},
+            drugs::{
+                entities::{DrugContentType, NewDrug},
+                repository::DrugsRepository,
+            },
+            patients::{
+                entities::NewPatient,
+                repository::{
+                    CreatePatientRepositoryError, GetPatientByIdRepositoryError,
+                    GetPatientByPeselNumberRepositoryError, GetPatientsRepositoryError,
+                    MergePatientsRepositoryError, PatientsRepository,
+                },
             },
+            prescriptions::{
+                entities::{NewPrescribedDrug, NewPrescription},
+                repository::PrescriptionsRepository,
+            },
+        },
+        infrastructure::postgres_repository_impl::{
+            create_tables::create_tables, doctors::PostgresDoctorsRepository,
+            drugs::PostgresDrugsRepository, prescriptions::PostgresPrescriptionsRepository,
         },
-        infrastructure::postgres_repository_impl::create_tables::create_tables,
     };
 
     async fn setup_repository(pool: sqlx::PgPool) -> PostgresPatientsRepository {
@@ -152,6 +282,26 @@ mod tests {
         assert_eq!(patient_from_repo, new_patient);
     }
 
+    #[sqlx::test]
+    async fn create_and_read_patient_with_creator(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let new_patient = NewPatient {
+            created_by: Some(Uuid::new_v4()),
+            ..NewPatient::new("John Doe".into(), "96021817257".into()).unwrap()
+        };
+
+        repository
+            .create_patient(new_patient.clone())
+            .await
+            .unwrap();
+
+        let patient_from_repo = repository.get_patient_by_id(new_patient.id).await.unwrap();
+
+        assert_eq!(patient_from_repo, new_patient);
+        assert_eq!(patient_from_repo.created_by, new_patient.created_by);
+    }
+
     #[sqlx::test]
     async fn returns_error_if_patients_with_given_id_doesnt_exist(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
@@ -165,6 +315,30 @@ mod tests {
         );
     }
 
+    #[sqlx::test]
+    async fn reads_patient_by_pesel_number(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let new_patient = NewPatient::new("John Doe".into(), "96021817257".into()).unwrap();
+        repository
+            .create_patient(new_patient.clone())
+            .await
+            .unwrap();
+
+        let patient_from_repo = repository
+            .get_patient_by_pesel_number("96021817257")
+            .await
+            .unwrap();
+
+        assert_eq!(patient_from_repo, new_patient);
+        assert_eq!(
+            repository.get_patient_by_pesel_number("99031301347").await,
+            Err(GetPatientByPeselNumberRepositoryError::NotFound(
+                "99031301347".into()
+            ))
+        );
+    }
+
     #[sqlx::test]
     async fn create_and_read_patients_from_database(pool: sqlx::PgPool) {
         let repository = setup_repository(pool).await;
@@ -246,4 +420,105 @@ mod tests {
             Err(CreatePatientRepositoryError::DuplicatedPeselNumber)
         )
     }
+
+    #[sqlx::test]
+    async fn merges_duplicated_patient_and_moves_prescriptions(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool.clone()).await;
+        let prescriptions_repository = PostgresPrescriptionsRepository::new(pool.clone());
+
+        let kept_patient = NewPatient::new("John Doe".into(), "96021817257".into()).unwrap();
+        repository
+            .create_patient(kept_patient.clone())
+            .await
+            .unwrap();
+        // PESEL numbers are unique, so a duplicate always has a mistyped one
+        let merged_patient = NewPatient::new("Jon Doe".into(), "99031301347".into()).unwrap();
+        repository
+            .create_patient(merged_patient.clone())
+            .await
+            .unwrap();
+
+        let doctor =
+            NewDoctor::new("John Doctor".into(), "3123456".into(), "96021807250".into()).unwrap();
+        PostgresDoctorsRepository::new(pool.clone())
+            .create_doctor(doctor.clone())
+            .await
+            .unwrap();
+        let drug = NewDrug::new(
+            "Gripex".into(),
+            DrugContentType::SolidPills,
+            Some(20),
+            Some(300),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        PostgresDrugsRepository::new(pool.clone())
+            .create_drug(drug.clone())
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let prescription = NewPrescription::new(
+                doctor.id,
+                merged_patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: drug.id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions_repository
+                .create_prescription(prescription)
+                .await
+                .unwrap();
+        }
+
+        let patient = repository
+            .merge_patients(kept_patient.id, merged_patient.id)
+            .await
+            .unwrap();
+
+        assert_eq!(patient, kept_patient);
+
+        let prescriptions = prescriptions_repository
+            .get_prescriptions_for_patient(kept_patient.id, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 2);
+        assert!(prescriptions
+            .iter()
+            .all(|prescription| prescription.patient.id == kept_patient.id));
+        assert_eq!(
+            repository.get_patient_by_id(merged_patient.id).await,
+            Err(GetPatientByIdRepositoryError::NotFound(merged_patient.id))
+        );
+    }
+
+    #[sqlx::test]
+    async fn doesnt_merge_missing_patient(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+
+        let patient = NewPatient::new("John Doe".into(), "96021817257".into()).unwrap();
+        repository.create_patient(patient.clone()).await.unwrap();
+
+        let missing_id = Uuid::new_v4();
+        assert_eq!(
+            repository.merge_patients(missing_id, patient.id).await,
+            Err(MergePatientsRepositoryError::NotFound(missing_id))
+        );
+        assert_eq!(
+            repository.merge_patients(patient.id, missing_id).await,
+            Err(MergePatientsRepositoryError::NotFound(missing_id))
+        );
+        assert!(repository.get_patient_by_id(patient.id).await.is_ok());
+    }
 }
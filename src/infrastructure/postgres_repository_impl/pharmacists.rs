@@ -10,9 +10,11 @@ use crate::domain::{
             GetPharmacistsRepositoryError, PharmacistsRepository,
         },
     },
-    utils::pagination::get_pagination_params,
+    utils::pagination::{get_pagination_params, DEFAULT_PAGE_SIZE},
 };
 
+use super::database_error::map_database_error;
+
 pub struct PostgresPharmacistsRepository {
     pool: sqlx::PgPool,
 }
@@ -48,9 +50,13 @@ impl PharmacistsRepository for PostgresPharmacistsRepository {
             .fetch_one(&self.pool).await
             .map_err(|_| CreatePharmacistRepositoryError::DuplicatedPeselNumber)?;
 
-        let pharmacist = self
-            .parse_pharmacists_row(result)
-            .map_err(|err| CreatePharmacistRepositoryError::DatabaseError(err.to_string()))?;
+        let pharmacist = self.parse_pharmacists_row(result).map_err(|err| {
+            map_database_error(
+                err,
+                CreatePharmacistRepositoryError::DatabaseError,
+                CreatePharmacistRepositoryError::Unavailable,
+            )
+        })?;
         Ok(pharmacist)
     }
 
@@ -59,23 +65,26 @@ impl PharmacistsRepository for PostgresPharmacistsRepository {
         page: Option<i64>,
         page_size: Option<i64>,
     ) -> Result<Vec<Pharmacist>, GetPharmacistsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size).map_err(|err| {
-            GetPharmacistsRepositoryError::InvalidPaginationParams(err.to_string())
-        })?;
+        let (page_size, offset) = get_pagination_params(page, page_size, DEFAULT_PAGE_SIZE)
+            .map_err(GetPharmacistsRepositoryError::InvalidPaginationParams)?;
 
         let pharmacists_from_db = sqlx::query(
-                r#"SELECT id, name, pesel_number, created_at, updated_at FROM pharmacists LIMIT $1 OFFSET $2"#,
+                r#"SELECT id, name, pesel_number, created_at, updated_at FROM pharmacists ORDER BY created_at ASC, id ASC LIMIT $1 OFFSET $2"#,
             )
             .bind(page_size)
             .bind(offset)
             .fetch_all(&self.pool).await
-            .map_err(|err| GetPharmacistsRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, GetPharmacistsRepositoryError::DatabaseError, GetPharmacistsRepositoryError::Unavailable))?;
 
         let mut pharmacists: Vec<Pharmacist> = Vec::new();
         for record in pharmacists_from_db {
-            let pharmacist = self
-                .parse_pharmacists_row(record)
-                .map_err(|err| GetPharmacistsRepositoryError::DatabaseError(err.to_string()))?;
+            let pharmacist = self.parse_pharmacists_row(record).map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPharmacistsRepositoryError::DatabaseError,
+                    GetPharmacistsRepositoryError::Unavailable,
+                )
+            })?;
             pharmacists.push(pharmacist);
         }
 
@@ -95,7 +104,13 @@ impl PharmacistsRepository for PostgresPharmacistsRepository {
 
         let pharmacist = self
             .parse_pharmacists_row(pharmacist_from_db)
-            .map_err(|err| GetPharmacistByIdRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPharmacistByIdRepositoryError::DatabaseError,
+                    GetPharmacistByIdRepositoryError::Unavailable,
+                )
+            })?;
         Ok(pharmacist)
     }
 }
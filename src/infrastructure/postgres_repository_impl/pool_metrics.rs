@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+pub const DEFAULT_POOL_METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+fn get_used_connections(pool: &PgPool) -> u32 {
+    pool.size().saturating_sub(pool.num_idle() as u32)
+}
+
+pub fn log_pool_configuration(pool: &PgPool) {
+    tracing::info!(
+        max_connections = pool.options().get_max_connections(),
+        idle_connections = pool.num_idle(),
+        used_connections = get_used_connections(pool),
+        "Database connection pool ready"
+    );
+}
+
+pub fn log_pool_utilization(pool: &PgPool) {
+    tracing::info!(
+        max_connections = pool.options().get_max_connections(),
+        idle_connections = pool.num_idle(),
+        used_connections = get_used_connections(pool),
+        "Database connection pool utilization"
+    );
+}
+
+// Logs the utilization of the pool every `interval` until the pool is closed. Zero disables it
+pub fn spawn_pool_utilization_logging(pool: PgPool, interval: Duration) -> Option<JoinHandle<()>> {
+    if interval.is_zero() {
+        return None;
+    }
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately, right after the configuration was logged
+        ticker.tick().await;
+        while !pool.is_closed() {
+            ticker.tick().await;
+            log_pool_utilization(&pool);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{log_pool_configuration, spawn_pool_utilization_logging};
+    use crate::captured_logs::capture_logs;
+
+    #[sqlx::test]
+    async fn logs_pool_configuration(pool: sqlx::PgPool) {
+        let (logs, _guard) = capture_logs();
+        let _connection = pool.acquire().await.unwrap();
+
+        log_pool_configuration(&pool);
+
+        let logs = logs.contents();
+        assert!(logs.contains("INFO"));
+        assert!(logs.contains("Database connection pool ready"));
+        assert!(logs.contains(&format!(
+            "max_connections={}",
+            pool.options().get_max_connections()
+        )));
+        assert!(logs.contains("used_connections=1"));
+    }
+
+    #[sqlx::test]
+    async fn doesnt_spawn_utilization_logging_with_zero_interval(pool: sqlx::PgPool) {
+        assert!(spawn_pool_utilization_logging(pool, Duration::ZERO).is_none());
+    }
+}
@@ -1,30 +1,84 @@
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::Row;
 use uuid::Uuid;
 
 use crate::domain::{
+    doctors::pwz_number::PwzNumber,
+    drugs::{
+        entities::{Drug, DrugCategory},
+        repository::DRUGS_DEFAULT_PAGE_SIZE,
+    },
     prescriptions::{
         entities::{
-            NewPrescription, NewPrescriptionFill, PrescribedDrug, Prescription, PrescriptionDoctor,
-            PrescriptionFill, PrescriptionPatient, PrescriptionType,
+            AffectedPatient, DoctorPrescriptionCount, DrugHistoryEntry,
+            DrugPrescriptionTypeRestriction, DrugUsage, NewPrescription, NewPrescriptionFill,
+            NewPrescriptionTemplate, PrescribedDrug, Prescription, PrescriptionDoctor,
+            PrescriptionFill, PrescriptionFillStatus, PrescriptionPatient, PrescriptionTemplate,
+            PrescriptionTemplateDrug, PrescriptionType,
         },
+        prescription_code::PrescriptionCode,
         repository::{
-            CreatePrescriptionRepositoryError, FillPrescriptionRepositoryError,
-            GetPrescriptionByIdRepositoryError, GetPrescriptionsRepositoryError,
-            PrescriptionsRepository,
+            CancelPrescriptionRepositoryError, CreatePrescriptionRepositoryError,
+            CreatePrescriptionTemplateRepositoryError, DeletePrescriptionTemplateRepositoryError,
+            FillPrescriptionRepositoryError, GetDrugUsageRepositoryError,
+            GetPharmacistFillStatsRepositoryError, GetPrescriptionByIdRepositoryError,
+            GetPrescriptionTemplateByIdRepositoryError, GetPrescriptionTemplatesRepositoryError,
+            GetPrescriptionsRepositoryError, PrescriptionsRepository,
+            ReassignPrescriptionPatientRepositoryError, UpdatePrescriptionTemplateRepositoryError,
+            MAX_CODE_GENERATION_ATTEMPTS, PRESCRIPTIONS_DEFAULT_PAGE_SIZE,
         },
+        use_cases::create_prescription::generate_prescription_code,
     },
     utils::pagination::get_pagination_params,
 };
 
+use super::{
+    database_error::map_database_error,
+    drugs::parse_drugs_row,
+    isolation_level::{begin_transaction, IsolationLevel},
+    query_metrics::QueryMetrics,
+    retry::{
+        retry_on_serialization_failure, retry_transaction_on_serialization_failure, RetryPolicy,
+        TransactionError,
+    },
+    slow_query_log::{log_slow_query, DEFAULT_SLOW_QUERY_THRESHOLD},
+};
+
+const PRESCRIBED_DRUGS_DISPENSED_QUANTITY_CONSTRAINT: &str =
+    "prescribed_drugs_dispensed_quantity_check";
+const PRESCRIPTION_TEMPLATE_DRUGS_DRUG_ID_CONSTRAINT: &str =
+    "prescription_template_drugs_drug_id_fkey";
+
+// User input is matched literally, the wildcards of LIKE patterns are escaped with a backslash
+fn escape_like_pattern(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        if matches!(character, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+
+    escaped
+}
+
 pub struct PostgresPrescriptionsRepository {
     pool: sqlx::PgPool,
+    retry_policy: RetryPolicy,
+    slow_query_threshold: Duration,
+    // Applied to the transactions creating and filling prescriptions
+    isolation_level: Option<IsolationLevel>,
+    // Replaces the code of a new prescription colliding with an existing one
+    code_generator: Box<dyn Fn() -> PrescriptionCode + Send + Sync>,
+    query_metrics: Arc<QueryMetrics>,
 }
 
 struct PrescriptionsRow {
     prescription_id: Uuid,
-    prescription_code: String,
+    prescription_code: PrescriptionCode,
     prescription_prescription_type: PrescriptionType,
     prescription_start_date: DateTime<Utc>,
     prescription_end_date: DateTime<Utc>,
@@ -33,7 +87,7 @@ struct PrescriptionsRow {
     doctor_id: Uuid,
     doctor_name: String,
     doctor_pesel_number: String,
-    doctor_pwz_number: String,
+    doctor_pwz_number: PwzNumber,
     patient_id: Uuid,
     patient_name: String,
     patient_pesel_number: String,
@@ -42,15 +96,61 @@ struct PrescriptionsRow {
     prescribed_drug_quantity: i32,
     prescribed_drug_created_at: DateTime<Utc>,
     prescribed_drug_updated_at: DateTime<Utc>,
+    prescribed_drug_unit_price_cents: Option<i32>,
+    prescribed_drug_dispensed_quantity: i32,
     prescription_fill_id: Option<Uuid>,
     prescription_fill_pharmacist_id: Option<Uuid>,
     prescription_fill_created_at: Option<DateTime<Utc>>,
     prescription_fill_updated_at: Option<DateTime<Utc>>,
+    prescription_diagnoses: Vec<String>,
+    prescription_cancelled_at: Option<DateTime<Utc>>,
+    prescription_window_from_fill: bool,
 }
 
 impl PostgresPrescriptionsRepository {
     pub fn new(pool: sqlx::PgPool) -> Self {
-        Self { pool }
+        Self::new_with_retry_policy(pool, RetryPolicy::default())
+    }
+
+    pub fn new_with_retry_policy(pool: sqlx::PgPool, retry_policy: RetryPolicy) -> Self {
+        Self {
+            pool,
+            retry_policy,
+            slow_query_threshold: DEFAULT_SLOW_QUERY_THRESHOLD,
+            isolation_level: None,
+            code_generator: Box::new(generate_prescription_code),
+            query_metrics: Arc::new(QueryMetrics::new()),
+        }
+    }
+
+    pub fn with_slow_query_threshold(mut self, slow_query_threshold: Duration) -> Self {
+        self.slow_query_threshold = slow_query_threshold;
+        self
+    }
+
+    pub fn with_isolation_level(mut self, isolation_level: Option<IsolationLevel>) -> Self {
+        self.isolation_level = isolation_level;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_code_generator(
+        mut self,
+        code_generator: impl Fn() -> PrescriptionCode + Send + Sync + 'static,
+    ) -> Self {
+        self.code_generator = Box::new(code_generator);
+        self
+    }
+
+    pub fn with_query_metrics(mut self, query_metrics: Arc<QueryMetrics>) -> Self {
+        self.query_metrics = query_metrics;
+        self
+    }
+
+    // Every query goes through here, counted and logged under the calling method's name
+    async fn run_query<T>(&self, method: &str, query: impl Future<Output = T>) -> T {
+        self.query_metrics.record(method);
+        log_slow_query(self.slow_query_threshold, method, || query).await
     }
 
     fn parse_prescriptions_row(
@@ -77,13 +177,133 @@ impl PostgresPrescriptionsRepository {
             prescribed_drug_quantity: row.try_get(16)?,
             prescribed_drug_created_at: row.try_get(17)?,
             prescribed_drug_updated_at: row.try_get(18)?,
-            prescription_fill_id: row.try_get(19)?,
-            prescription_fill_pharmacist_id: row.try_get(20)?,
-            prescription_fill_created_at: row.try_get(21)?,
-            prescription_fill_updated_at: row.try_get(22)?,
+            prescribed_drug_unit_price_cents: row.try_get(19)?,
+            prescribed_drug_dispensed_quantity: row.try_get(20)?,
+            prescription_fill_id: row.try_get(21)?,
+            prescription_fill_pharmacist_id: row.try_get(22)?,
+            prescription_fill_created_at: row.try_get(23)?,
+            prescription_fill_updated_at: row.try_get(24)?,
+            prescription_diagnoses: row.try_get(25)?,
+            prescription_cancelled_at: row.try_get(26)?,
+            prescription_window_from_fill: row.try_get(27)?,
         })
     }
 
+    fn parse_prescriptions_rows(
+        &self,
+        rows: Vec<sqlx::postgres::PgRow>,
+    ) -> Result<Vec<Prescription>, sqlx::Error> {
+        let mut prescriptions: Vec<Prescription> = vec![];
+
+        for record in rows {
+            let PrescriptionsRow {
+                prescription_id,
+                prescription_code,
+                prescription_prescription_type,
+                prescription_start_date,
+                prescription_end_date,
+                prescription_created_at,
+                prescription_updated_at,
+                doctor_id,
+                doctor_name,
+                doctor_pesel_number,
+                doctor_pwz_number,
+                patient_id,
+                patient_name,
+                patient_pesel_number,
+                prescribed_drug_id,
+                prescribed_drug_drug_id,
+                prescribed_drug_quantity,
+                prescribed_drug_created_at,
+                prescribed_drug_updated_at,
+                prescribed_drug_unit_price_cents,
+                prescribed_drug_dispensed_quantity,
+                prescription_fill_id,
+                prescription_fill_pharmacist_id,
+                prescription_fill_created_at,
+                prescription_fill_updated_at,
+                prescription_diagnoses,
+                prescription_cancelled_at,
+                prescription_window_from_fill,
+            } = self.parse_prescriptions_row(record)?;
+
+            // Every combination of prescribed drug and fill of the prescription has its own row
+            let prescribed_drug = PrescribedDrug {
+                id: prescribed_drug_id,
+                prescription_id,
+                drug_id: prescribed_drug_drug_id,
+                quantity: prescribed_drug_quantity,
+                dispensed_quantity: prescribed_drug_dispensed_quantity,
+                unit_price_cents: prescribed_drug_unit_price_cents,
+                created_at: prescribed_drug_created_at,
+                updated_at: prescribed_drug_updated_at,
+            };
+            let fill = prescription_fill_id.map(|prescription_fill_id| PrescriptionFill {
+                id: prescription_fill_id,
+                prescription_id,
+                pharmacist_id: prescription_fill_pharmacist_id.unwrap(),
+                created_at: prescription_fill_created_at.unwrap(),
+                updated_at: prescription_fill_updated_at.unwrap(),
+            });
+
+            let prescription = match prescriptions.iter_mut().find(|p| p.id == prescription_id) {
+                Some(prescription) => prescription,
+                None => {
+                    prescriptions.push(Prescription {
+                        id: prescription_id,
+                        patient: PrescriptionPatient {
+                            id: patient_id,
+                            name: patient_name,
+                            pesel_number: patient_pesel_number,
+                        },
+                        doctor: PrescriptionDoctor {
+                            id: doctor_id,
+                            name: doctor_name,
+                            pesel_number: doctor_pesel_number,
+                            pwz_number: doctor_pwz_number,
+                        },
+                        code: prescription_code,
+                        prescription_type: prescription_prescription_type,
+                        start_date: prescription_start_date,
+                        end_date: prescription_end_date,
+                        prescribed_drugs: vec![],
+                        diagnoses: prescription_diagnoses,
+                        fills: vec![],
+                        fill_status: PrescriptionFillStatus::Unfilled,
+                        cancelled_at: prescription_cancelled_at,
+                        window_from_fill: prescription_window_from_fill,
+                        estimated_cost_cents: None,
+                        fillable_now: false,
+                        created_at: prescription_created_at,
+                        updated_at: prescription_updated_at,
+                    });
+                    prescriptions.last_mut().unwrap()
+                }
+            };
+
+            if !prescription
+                .prescribed_drugs
+                .iter()
+                .any(|p| p.id == prescribed_drug.id)
+            {
+                prescription.prescribed_drugs.push(prescribed_drug);
+            }
+            if let Some(fill) = fill {
+                if !prescription.fills.iter().any(|f| f.id == fill.id) {
+                    prescription.fills.push(fill);
+                }
+            }
+        }
+
+        for prescription in &mut prescriptions {
+            prescription.fills.sort_by_key(|fill| fill.created_at);
+            prescription.fill_status = prescription.compute_fill_status();
+            prescription.estimated_cost_cents = prescription.estimate_cost_cents();
+        }
+
+        Ok(prescriptions)
+    }
+
     fn parse_prescription_fills_row(
         &self,
         row: sqlx::postgres::PgRow,
@@ -96,6 +316,165 @@ impl PostgresPrescriptionsRepository {
             updated_at: row.try_get(4)?,
         })
     }
+
+    fn parse_prescription_templates_rows(
+        &self,
+        rows: Vec<sqlx::postgres::PgRow>,
+    ) -> Result<Vec<PrescriptionTemplate>, sqlx::Error> {
+        let mut templates: Vec<PrescriptionTemplate> = vec![];
+
+        for row in rows {
+            let template_id: Uuid = row.try_get(0)?;
+            let prescribed_drug = PrescriptionTemplateDrug {
+                drug_id: row.try_get(5)?,
+                quantity: row.try_get(6)?,
+            };
+
+            match templates
+                .iter_mut()
+                .find(|template| template.id == template_id)
+            {
+                Some(template) => template.prescribed_drugs.push(prescribed_drug),
+                None => templates.push(PrescriptionTemplate {
+                    id: template_id,
+                    doctor_id: row.try_get(1)?,
+                    name: row.try_get(2)?,
+                    prescribed_drugs: vec![prescribed_drug],
+                    created_at: row.try_get(3)?,
+                    updated_at: row.try_get(4)?,
+                }),
+            }
+        }
+
+        Ok(templates)
+    }
+
+    // Returns the id of the drug that couldn't be inserted along with the error
+    async fn insert_prescription_template_drugs(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        template: &NewPrescriptionTemplate,
+    ) -> Result<(), (sqlx::Error, Uuid)> {
+        for (position, prescribed_drug) in template.prescribed_drugs.iter().enumerate() {
+            self.run_query("insert_prescription_template_drugs", sqlx::query(
+                    r#"INSERT INTO prescription_template_drugs (template_id, drug_id, quantity, position) VALUES ($1, $2, $3, $4)"#
+                )
+                .bind(template.id)
+                .bind(prescribed_drug.drug_id)
+                .bind(prescribed_drug.quantity as i32)
+                .bind(position as i32)
+                .execute(&mut **transaction))
+                .await
+                .map_err(|err| (err, prescribed_drug.drug_id))?;
+        }
+
+        Ok(())
+    }
+
+    fn map_fill_prescription_error(
+        &self,
+        err: sqlx::Error,
+        prescription_fill: &NewPrescriptionFill,
+    ) -> FillPrescriptionRepositoryError {
+        match err {
+            sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
+                match err.constraint() {
+                    Some("prescription_fills_pharmacist_id_fkey") => {
+                        FillPrescriptionRepositoryError::PharmacistNotFound(
+                            prescription_fill.pharmacist_id,
+                        )
+                    }
+                    Some("prescription_fills_prescription_id_fkey") => {
+                        FillPrescriptionRepositoryError::PrescriptionNotFound(
+                            prescription_fill.prescription_id,
+                        )
+                    }
+                    _ => FillPrescriptionRepositoryError::DatabaseError(err.to_string()),
+                }
+            }
+            sqlx::Error::Database(err) if err.is_check_violation() => match err.constraint() {
+                Some(PRESCRIBED_DRUGS_DISPENSED_QUANTITY_CONSTRAINT) => {
+                    FillPrescriptionRepositoryError::QuantityExceeded(
+                        prescription_fill.prescription_id,
+                    )
+                }
+                _ => FillPrescriptionRepositoryError::DatabaseError(err.to_string()),
+            },
+            err => map_database_error(
+                err,
+                FillPrescriptionRepositoryError::DatabaseError,
+                FillPrescriptionRepositoryError::Unavailable,
+            ),
+        }
+    }
+
+    async fn insert_prescription_fill(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        new_prescription_fill: &NewPrescriptionFill,
+    ) -> Result<PrescriptionFill, TransactionError<FillPrescriptionRepositoryError>> {
+        let map_error = |err| {
+            TransactionError::from_database_error(err, |err| {
+                self.map_fill_prescription_error(err, new_prescription_fill)
+            })
+        };
+
+        let result = self.run_query("insert_prescription_fill", sqlx::query(
+                r#"INSERT INTO prescription_fills (id, prescription_id, pharmacist_id) VALUES ($1, $2, $3) RETURNING id, prescription_id, pharmacist_id, created_at, updated_at"#
+            )
+            .bind(new_prescription_fill.id)
+            .bind(new_prescription_fill.prescription_id)
+            .bind(new_prescription_fill.pharmacist_id)
+            .fetch_one(&mut **transaction)).await
+            .map_err(map_error)?;
+
+        for dispensed_drug in &new_prescription_fill.dispensed_drugs {
+            self.run_query("insert_prescription_fill", sqlx::query(
+                    r#"INSERT INTO prescription_fill_items (prescription_fill_id, prescribed_drug_id, dispensed_quantity) VALUES ($1, $2, $3)"#
+                )
+                .bind(new_prescription_fill.id)
+                .bind(dispensed_drug.prescribed_drug_id)
+                .bind(dispensed_drug.quantity as i32)
+                .execute(&mut **transaction)).await
+                .map_err(map_error)?;
+
+            let updated_rows = self.run_query("insert_prescription_fill", sqlx::query(
+                    r#"UPDATE prescribed_drugs SET dispensed_quantity = dispensed_quantity + $2, updated_at = NOW() WHERE id = $1 AND prescription_id = $3"#
+                )
+                .bind(dispensed_drug.prescribed_drug_id)
+                .bind(dispensed_drug.quantity as i32)
+                .bind(new_prescription_fill.prescription_id)
+                .execute(&mut **transaction)).await
+                .map_err(map_error)?;
+            if updated_rows.rows_affected() == 0 {
+                Err(TransactionError::Failed(
+                    FillPrescriptionRepositoryError::QuantityExceeded(
+                        new_prescription_fill.prescription_id,
+                    ),
+                ))?;
+            }
+        }
+
+        let prescription_fill = self
+            .parse_prescription_fills_row(result)
+            .map_err(map_error)?;
+
+        if let Some(window) = new_prescription_fill.restarted_window {
+            self.run_query(
+                "insert_prescription_fill",
+                sqlx::query(
+                    r#"UPDATE prescriptions SET end_date = $2, updated_at = NOW() WHERE id = $1"#,
+                )
+                .bind(new_prescription_fill.prescription_id)
+                .bind(prescription_fill.created_at + window)
+                .execute(&mut **transaction),
+            )
+            .await
+            .map_err(map_error)?;
+        }
+
+        Ok(prescription_fill)
+    }
 }
 
 #[async_trait]
@@ -104,82 +483,239 @@ impl PrescriptionsRepository for PostgresPrescriptionsRepository {
         &self,
         prescription: NewPrescription,
     ) -> Result<Prescription, CreatePrescriptionRepositoryError> {
-        let transaction = self
-            .pool
-            .begin()
-            .await
-            .map_err(|err| CreatePrescriptionRepositoryError::DatabaseError(err.to_string()))?;
+        let map_transaction_error = |err| {
+            map_database_error(
+                err,
+                CreatePrescriptionRepositoryError::DatabaseError,
+                CreatePrescriptionRepositoryError::Unavailable,
+            )
+        };
+        // A serialization failure aborts the transaction, so the whole prescription is inserted again
+        retry_transaction_on_serialization_failure(
+            self.retry_policy,
+            map_transaction_error,
+            || async {
+                let map_error =
+                    |err| TransactionError::from_database_error(err, map_transaction_error);
+                let mut transaction = begin_transaction(&self.pool, self.isolation_level)
+                    .await
+                    .map_err(map_error)?;
 
-        sqlx::query(
-                r#"INSERT INTO prescriptions (id, patient_id, doctor_id, code, prescription_type, start_date, end_date) VALUES ($1, $2, $3, $4, $5, $6, $7)"#
-            )
-            .bind(prescription.id)
-            .bind(prescription.patient_id)
-            .bind(prescription.doctor_id)
-            .bind(prescription.code)
-            .bind(prescription.prescription_type)
-            .bind(prescription.start_date)
-            .bind(prescription.end_date)
-            .execute(&self.pool).await
-            .map_err(|err| {
-                match err {
-                    sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
-                        match err.constraint() {
-                            Some("prescriptions_doctor_id_fkey") => {
-                                CreatePrescriptionRepositoryError::DoctorNotFound(
-                                    prescription.doctor_id
-                                )
-                            }
-                            Some("prescriptions_patient_id_fkey") => {
-                                CreatePrescriptionRepositoryError::PatientNotFound(
-                                    prescription.patient_id
-                                )
+                // A colliding code is skipped instead of raising an error, which would abort the transaction
+                let mut code = prescription.code.clone();
+                let mut code_generation_attempts = 1;
+                loop {
+                    let inserted_rows = self.run_query("create_prescription", sqlx::query(
+                        r#"INSERT INTO prescriptions (id, patient_id, doctor_id, code, prescription_type, start_date, end_date, window_from_fill) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (code) DO NOTHING"#
+                    )
+                    .bind(prescription.id)
+                    .bind(prescription.patient_id)
+                    .bind(prescription.doctor_id)
+                    .bind(&code)
+                    .bind(prescription.prescription_type)
+                    .bind(prescription.start_date)
+                    .bind(prescription.end_date)
+                    .bind(prescription.window_from_fill)
+                    .execute(&mut *transaction))
+                    .await
+                    .map_err(|err| {
+                        TransactionError::from_database_error(err, |err| match err {
+                            sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
+                                match err.constraint() {
+                                    Some("prescriptions_doctor_id_fkey") => {
+                                        CreatePrescriptionRepositoryError::DoctorNotFound(
+                                            prescription.doctor_id,
+                                        )
+                                    }
+                                    Some("prescriptions_patient_id_fkey") => {
+                                        CreatePrescriptionRepositoryError::PatientNotFound(
+                                            prescription.patient_id,
+                                        )
+                                    }
+                                    _ => CreatePrescriptionRepositoryError::DatabaseError(
+                                        err.to_string(),
+                                    ),
+                                }
                             }
-                            _ => CreatePrescriptionRepositoryError::DatabaseError(err.to_string()),
-                        }
+                            err => map_transaction_error(err),
+                        })
+                    })?;
+
+                    if inserted_rows.rows_affected() > 0 {
+                        break;
                     }
-                    err => CreatePrescriptionRepositoryError::DatabaseError(err.to_string()),
+                    if code_generation_attempts >= MAX_CODE_GENERATION_ATTEMPTS {
+                        Err(TransactionError::Failed(
+                            CreatePrescriptionRepositoryError::CodeGenerationFailed,
+                        ))?;
+                    }
+                    code_generation_attempts += 1;
+                    code = (self.code_generator)();
                 }
-            })?;
 
-        for prescribed_drug in &prescription.prescribed_drugs {
-            sqlx
-                ::query(
-                    r#"INSERT INTO prescribed_drugs (prescription_id, drug_id, quantity) VALUES ($1, $2, $3)"#
-                )
-                .bind(prescription.id)
-                .bind(prescribed_drug.drug_id)
-                .bind(prescribed_drug.quantity as i32)
-                .execute(&self.pool).await
-                .map_err(|err| {
-                    match err {
-                        sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
-                            match err.constraint() {
-                                Some("prescribed_drugs_drug_id_fkey") => {
-                                    CreatePrescriptionRepositoryError::DrugNotFound(
-                                        prescribed_drug.drug_id
-                                    )
-                                }
-                                _ =>
-                                    CreatePrescriptionRepositoryError::DatabaseError(
-                                        err.to_string()
-                                    ),
+                for prescribed_drug in &prescription.prescribed_drugs {
+                    self.run_query("create_prescription", sqlx::query(
+                        r#"INSERT INTO prescribed_drugs (prescription_id, drug_id, quantity) VALUES ($1, $2, $3)"#
+                    )
+                    .bind(prescription.id)
+                    .bind(prescribed_drug.drug_id)
+                    .bind(prescribed_drug.quantity as i32)
+                    .execute(&mut *transaction))
+                    .await
+                    .map_err(|err| {
+                        TransactionError::from_database_error(err, |err| match err {
+                            sqlx::Error::Database(err)
+                                if err.is_foreign_key_violation()
+                                    && err.constraint() == Some("prescribed_drugs_drug_id_fkey") =>
+                            {
+                                CreatePrescriptionRepositoryError::DrugNotFound(
+                                    prescribed_drug.drug_id,
+                                )
                             }
-                        }
-                        _ => CreatePrescriptionRepositoryError::DatabaseError(err.to_string()),
-                    }
-                })?;
-        }
+                            err => map_transaction_error(err),
+                        })
+                    })?;
+                }
 
-        let prescription = self
-            .get_prescription_by_id(prescription.id)
+                for (position, code) in prescription.diagnoses.iter().enumerate() {
+                    self.run_query("create_prescription", sqlx::query(
+                        r#"INSERT INTO prescription_diagnoses (prescription_id, code, position) VALUES ($1, $2, $3)"#
+                    )
+                    .bind(prescription.id)
+                    .bind(code)
+                    .bind(position as i32)
+                    .execute(&mut *transaction))
+                    .await
+                    .map_err(map_error)?;
+                }
+
+                transaction.commit().await.map_err(map_error)?;
+
+                Ok(())
+            },
+        )
+        .await?;
+
+        self.get_prescription_by_id(prescription.id)
+            .await
+            .map_err(|err| match err {
+                GetPrescriptionByIdRepositoryError::Unavailable => {
+                    CreatePrescriptionRepositoryError::Unavailable
+                }
+                err => CreatePrescriptionRepositoryError::DatabaseError(err.to_string()),
+            })
+    }
+
+    async fn preview_prescription(
+        &self,
+        new_prescription: NewPrescription,
+    ) -> Result<Prescription, CreatePrescriptionRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                CreatePrescriptionRepositoryError::DatabaseError,
+                CreatePrescriptionRepositoryError::Unavailable,
+            )
+        };
+
+        let patient_row = self
+            .run_query(
+                "preview_prescription",
+                sqlx::query(r#"SELECT id, name, pesel_number FROM patients WHERE id = $1"#)
+                    .bind(new_prescription.patient_id)
+                    .fetch_optional(&self.pool),
+            )
+            .await
+            .map_err(map_error)?
+            .ok_or(CreatePrescriptionRepositoryError::PatientNotFound(
+                new_prescription.patient_id,
+            ))?;
+        let patient = PrescriptionPatient {
+            id: patient_row.try_get(0).map_err(map_error)?,
+            name: patient_row.try_get(1).map_err(map_error)?,
+            pesel_number: patient_row.try_get(2).map_err(map_error)?,
+        };
+
+        let doctor_row = self
+            .run_query(
+                "preview_prescription",
+                sqlx::query(
+                    r#"SELECT id, name, pesel_number, pwz_number FROM doctors WHERE id = $1"#,
+                )
+                .bind(new_prescription.doctor_id)
+                .fetch_optional(&self.pool),
+            )
             .await
-            .map_err(|err| CreatePrescriptionRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(map_error)?
+            .ok_or(CreatePrescriptionRepositoryError::DoctorNotFound(
+                new_prescription.doctor_id,
+            ))?;
+        let doctor = PrescriptionDoctor {
+            id: doctor_row.try_get(0).map_err(map_error)?,
+            name: doctor_row.try_get(1).map_err(map_error)?,
+            pesel_number: doctor_row.try_get(2).map_err(map_error)?,
+            pwz_number: doctor_row.try_get(3).map_err(map_error)?,
+        };
 
-        transaction
-            .commit()
+        let drug_ids: Vec<Uuid> = new_prescription
+            .prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| prescribed_drug.drug_id)
+            .collect();
+        let drug_rows = self
+            .run_query(
+                "preview_prescription",
+                sqlx::query(r#"SELECT id, unit_price_cents FROM drugs WHERE id = ANY($1)"#)
+                    .bind(&drug_ids)
+                    .fetch_all(&self.pool),
+            )
             .await
-            .map_err(|err| CreatePrescriptionRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(map_error)?;
+        let mut unit_prices_cents = HashMap::new();
+        for row in drug_rows {
+            let drug_id: Uuid = row.try_get(0).map_err(map_error)?;
+            let unit_price_cents: Option<i32> = row.try_get(1).map_err(map_error)?;
+            unit_prices_cents.insert(drug_id, unit_price_cents);
+        }
+
+        let now = Utc::now();
+        let mut prescribed_drugs = vec![];
+        for new_prescribed_drug in &new_prescription.prescribed_drugs {
+            let unit_price_cents = *unit_prices_cents.get(&new_prescribed_drug.drug_id).ok_or(
+                CreatePrescriptionRepositoryError::DrugNotFound(new_prescribed_drug.drug_id),
+            )?;
+            prescribed_drugs.push(PrescribedDrug {
+                id: Uuid::new_v4(),
+                drug_id: new_prescribed_drug.drug_id,
+                prescription_id: new_prescription.id,
+                quantity: new_prescribed_drug.quantity as i32,
+                dispensed_quantity: 0,
+                unit_price_cents,
+                created_at: now,
+                updated_at: now,
+            });
+        }
+
+        let mut prescription = Prescription {
+            id: new_prescription.id,
+            doctor,
+            patient,
+            code: new_prescription.code,
+            diagnoses: new_prescription.diagnoses,
+            prescription_type: new_prescription.prescription_type,
+            start_date: new_prescription.start_date,
+            end_date: new_prescription.end_date,
+            prescribed_drugs,
+            fills: vec![],
+            fill_status: PrescriptionFillStatus::Unfilled,
+            cancelled_at: None,
+            window_from_fill: new_prescription.window_from_fill,
+            estimated_cost_cents: None,
+            fillable_now: false,
+            created_at: now,
+            updated_at: now,
+        };
+        prescription.estimated_cost_cents = prescription.estimate_cost_cents();
 
         Ok(prescription)
     }
@@ -188,13 +724,19 @@ impl PrescriptionsRepository for PostgresPrescriptionsRepository {
         &self,
         page: Option<i64>,
         page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
     ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
-        let (page_size, offset) = get_pagination_params(page, page_size).map_err(|err| {
-            GetPrescriptionsRepositoryError::InvalidPaginationParams(err.to_string())
-        })?;
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
 
-        let prescriptions_from_db = sqlx::query(
-            r#"
+        let prescriptions_from_db = self
+            .run_query(
+                "get_prescriptions",
+                sqlx::query(
+                    r#"
         SELECT 
             prescriptions.id, 
             prescriptions.code,
@@ -215,118 +757,134 @@ impl PrescriptionsRepository for PostgresPrescriptionsRepository {
             prescribed_drugs.quantity,
             prescribed_drugs.created_at,
             prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
             prescription_fills.id,
             prescription_fills.pharmacist_id,
             prescription_fills.created_at,
-            prescription_fills.updated_at
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
         FROM (
-            SELECT * FROM prescriptions
-            ORDER BY created_at ASC
+            SELECT prescriptions.* FROM prescriptions
+            WHERE ($3::BOOLEAN IS NULL OR NOT EXISTS (
+                SELECT 1 FROM prescribed_drugs
+                WHERE prescribed_drugs.prescription_id = prescriptions.id
+                AND prescribed_drugs.dispensed_quantity < prescribed_drugs.quantity
+            ) = $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR prescriptions.created_at >= $4)
+            AND ($5::TIMESTAMPTZ IS NULL OR prescriptions.created_at <= $5)
+            ORDER BY prescriptions.created_at ASC, prescriptions.id ASC
             LIMIT $1 OFFSET $2
         ) AS prescriptions
         LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
         INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
         INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
         INNER JOIN patients ON prescriptions.patient_id = patients.id
+        ORDER BY prescriptions.created_at ASC, prescriptions.id ASC
     "#,
-        )
-        .bind(page_size)
-        .bind(offset)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|err| GetPrescriptionsRepositoryError::DatabaseError(err.to_string()))?;
-
-        let mut prescriptions: Vec<Prescription> = vec![];
+                )
+                .bind(page_size)
+                .bind(offset)
+                .bind(filled)
+                .bind(created_from)
+                .bind(created_to)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-        for record in prescriptions_from_db {
-            let PrescriptionsRow {
-                prescription_id,
-                prescription_code,
-                prescription_prescription_type,
-                prescription_start_date,
-                prescription_end_date,
-                prescription_created_at,
-                prescription_updated_at,
-                doctor_id,
-                doctor_name,
-                doctor_pesel_number,
-                doctor_pwz_number,
-                patient_id,
-                patient_name,
-                patient_pesel_number,
-                prescribed_drug_id,
-                prescribed_drug_drug_id,
-                prescribed_drug_quantity,
-                prescribed_drug_created_at,
-                prescribed_drug_updated_at,
-                prescription_fill_id,
-                prescription_fill_pharmacist_id,
-                prescription_fill_created_at,
-                prescription_fill_updated_at,
-            } = self
-                .parse_prescriptions_row(record)
-                .map_err(|err| GetPrescriptionsRepositoryError::DatabaseError(err.to_string()))?;
+        let prescriptions = self
+            .parse_prescriptions_rows(prescriptions_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-            let prescription = prescriptions.iter_mut().find(|p| p.id == prescription_id);
+        Ok(prescriptions)
+    }
 
-            let prescribed_drug = PrescribedDrug {
-                id: prescribed_drug_id,
-                prescription_id,
-                drug_id: prescribed_drug_drug_id,
-                quantity: prescribed_drug_quantity,
-                created_at: prescribed_drug_created_at,
-                updated_at: prescribed_drug_updated_at,
-            };
+    async fn get_prescription_ids(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+        filled: Option<bool>,
+        created_from: Option<DateTime<Utc>>,
+        created_to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Uuid>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
 
-            if let Some(prescription) = prescription {
-                prescription.prescribed_drugs.push(prescribed_drug);
-            } else {
-                let fill = if let Some(prescription_fill_id) = prescription_fill_id {
-                    Some(PrescriptionFill {
-                        id: prescription_fill_id,
-                        prescription_id,
-                        pharmacist_id: prescription_fill_pharmacist_id.unwrap(),
-                        created_at: prescription_fill_created_at.unwrap(),
-                        updated_at: prescription_fill_updated_at.unwrap(),
-                    })
-                } else {
-                    None
-                };
-
-                prescriptions.push(Prescription {
-                    id: prescription_id,
-                    patient: PrescriptionPatient {
-                        id: patient_id,
-                        name: patient_name,
-                        pesel_number: patient_pesel_number,
-                    },
-                    doctor: PrescriptionDoctor {
-                        id: doctor_id,
-                        name: doctor_name,
-                        pesel_number: doctor_pesel_number,
-                        pwz_number: doctor_pwz_number,
-                    },
-                    code: prescription_code,
-                    prescription_type: prescription_prescription_type,
-                    start_date: prescription_start_date,
-                    end_date: prescription_end_date,
-                    prescribed_drugs: vec![prescribed_drug],
-                    fill,
-                    created_at: prescription_created_at,
-                    updated_at: prescription_updated_at,
-                });
-            }
-        }
+        let rows = self
+            .run_query(
+                "get_prescription_ids",
+                sqlx::query(
+                    r#"
+        SELECT prescriptions.id FROM prescriptions
+        WHERE ($3::BOOLEAN IS NULL OR NOT EXISTS (
+            SELECT 1 FROM prescribed_drugs
+            WHERE prescribed_drugs.prescription_id = prescriptions.id
+            AND prescribed_drugs.dispensed_quantity < prescribed_drugs.quantity
+        ) = $3)
+        AND ($4::TIMESTAMPTZ IS NULL OR prescriptions.created_at >= $4)
+        AND ($5::TIMESTAMPTZ IS NULL OR prescriptions.created_at <= $5)
+        ORDER BY prescriptions.created_at ASC, prescriptions.id ASC
+        LIMIT $1 OFFSET $2
+    "#,
+                )
+                .bind(page_size)
+                .bind(offset)
+                .bind(filled)
+                .bind(created_from)
+                .bind(created_to)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-        Ok(prescriptions)
+        rows.into_iter()
+            .map(|row| row.try_get(0))
+            .collect::<Result<Vec<Uuid>, sqlx::Error>>()
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })
     }
 
     async fn get_prescription_by_id(
         &self,
         id: Uuid,
     ) -> Result<Prescription, GetPrescriptionByIdRepositoryError> {
-        let prescription_from_db = sqlx::query(
-            r#"
+        let prescription_from_db = self
+            .run_query(
+                "get_prescription_by_id",
+                sqlx::query(
+                    r#"
         SELECT
             prescriptions.id, 
             prescriptions.code,
@@ -347,109 +905,52 @@ impl PrescriptionsRepository for PostgresPrescriptionsRepository {
             prescribed_drugs.quantity,
             prescribed_drugs.created_at,
             prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
             prescription_fills.id,
             prescription_fills.pharmacist_id,
             prescription_fills.created_at,
-            prescription_fills.updated_at
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
         FROM (
             SELECT * FROM prescriptions
             WHERE id = $1
         ) AS prescriptions
         LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
         INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
         INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
         INNER JOIN patients ON prescriptions.patient_id = patients.id
     "#,
-        )
-        .bind(id)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|err| match err {
-            sqlx::Error::RowNotFound => GetPrescriptionByIdRepositoryError::NotFound(id),
-            _ => GetPrescriptionByIdRepositoryError::DatabaseError(err.to_string()),
-        })?;
-
-        let mut prescriptions: Vec<Prescription> = vec![];
-
-        for record in prescription_from_db {
-            let PrescriptionsRow {
-                prescription_id,
-                prescription_code,
-                prescription_prescription_type,
-                prescription_start_date,
-                prescription_end_date,
-                prescription_created_at,
-                prescription_updated_at,
-                doctor_id,
-                doctor_name,
-                doctor_pesel_number,
-                doctor_pwz_number,
-                patient_id,
-                patient_name,
-                patient_pesel_number,
-                prescribed_drug_id,
-                prescribed_drug_drug_id,
-                prescribed_drug_quantity,
-                prescribed_drug_created_at,
-                prescribed_drug_updated_at,
-                prescription_fill_id,
-                prescription_fill_pharmacist_id,
-                prescription_fill_created_at,
-                prescription_fill_updated_at,
-            } = self.parse_prescriptions_row(record).map_err(|err| {
-                GetPrescriptionByIdRepositoryError::DatabaseError(err.to_string())
+                )
+                .bind(id)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| match err {
+                sqlx::Error::RowNotFound => GetPrescriptionByIdRepositoryError::NotFound(id),
+                _ => map_database_error(
+                    err,
+                    GetPrescriptionByIdRepositoryError::DatabaseError,
+                    GetPrescriptionByIdRepositoryError::Unavailable,
+                ),
             })?;
 
-            let prescription = prescriptions.iter_mut().find(|p| p.id == prescription_id);
-
-            let prescribed_drug = PrescribedDrug {
-                id: prescribed_drug_id,
-                prescription_id,
-                drug_id: prescribed_drug_drug_id,
-                quantity: prescribed_drug_quantity,
-                created_at: prescribed_drug_created_at,
-                updated_at: prescribed_drug_updated_at,
-            };
-
-            if let Some(prescription) = prescription {
-                prescription.prescribed_drugs.push(prescribed_drug);
-            } else {
-                let fill = if let Some(prescription_fill_id) = prescription_fill_id {
-                    Some(PrescriptionFill {
-                        id: prescription_fill_id,
-                        prescription_id,
-                        pharmacist_id: prescription_fill_pharmacist_id.unwrap(),
-                        created_at: prescription_fill_created_at.unwrap(),
-                        updated_at: prescription_fill_updated_at.unwrap(),
-                    })
-                } else {
-                    None
-                };
-
-                prescriptions.push(Prescription {
-                    id: prescription_id,
-                    patient: PrescriptionPatient {
-                        id: patient_id,
-                        name: patient_name,
-                        pesel_number: patient_pesel_number,
-                    },
-                    doctor: PrescriptionDoctor {
-                        id: doctor_id,
-                        name: doctor_name,
-                        pesel_number: doctor_pesel_number,
-                        pwz_number: doctor_pwz_number,
-                    },
-                    code: prescription_code,
-                    prescription_type: prescription_prescription_type,
-                    start_date: prescription_start_date,
-                    end_date: prescription_end_date,
-                    prescribed_drugs: vec![prescribed_drug],
-                    fill,
-                    created_at: prescription_created_at,
-                    updated_at: prescription_updated_at,
-                });
-            }
-        }
+        let prescriptions = self
+            .parse_prescriptions_rows(prescription_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionByIdRepositoryError::DatabaseError,
+                    GetPrescriptionByIdRepositoryError::Unavailable,
+                )
+            })?;
 
         let prescription = prescriptions
             .first()
@@ -459,142 +960,2982 @@ impl PrescriptionsRepository for PostgresPrescriptionsRepository {
         Ok(prescription)
     }
 
-    async fn fill_prescription(
+    async fn get_prescriptions_by_ids(
         &self,
-        prescription_fill: NewPrescriptionFill,
-    ) -> Result<PrescriptionFill, FillPrescriptionRepositoryError> {
-        let result = sqlx::query(
-                r#"INSERT INTO prescription_fills (id, prescription_id, pharmacist_id) VALUES ($1, $2, $3) RETURNING id, prescription_id, pharmacist_id, created_at, updated_at"#
+        prescription_ids: &[Uuid],
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let prescriptions_from_db = self
+            .run_query(
+                "get_prescriptions_by_ids",
+                sqlx::query(
+                    r#"
+        SELECT
+            prescriptions.id, 
+            prescriptions.code,
+            prescriptions.prescription_type, 
+            prescriptions.start_date, 
+            prescriptions.end_date, 
+            prescriptions.created_at,
+            prescriptions.updated_at,
+            doctors.id,
+            doctors.name,
+            doctors.pesel_number,
+            doctors.pwz_number,
+            patients.id,
+            patients.name,
+            patients.pesel_number,
+            prescribed_drugs.id, 
+            prescribed_drugs.drug_id, 
+            prescribed_drugs.quantity,
+            prescribed_drugs.created_at,
+            prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
+            prescription_fills.id,
+            prescription_fills.pharmacist_id,
+            prescription_fills.created_at,
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
+        FROM (
+            SELECT * FROM prescriptions
+            WHERE id = ANY($1)
+        ) AS prescriptions
+        LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
+        INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
+        INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
+        INNER JOIN patients ON prescriptions.patient_id = patients.id
+        ORDER BY array_position($1, prescriptions.id)
+    "#,
+                )
+                .bind(prescription_ids)
+                .fetch_all(&self.pool),
             )
-            .bind(prescription_fill.id)
-            .bind(prescription_fill.prescription_id)
-            .bind(prescription_fill.pharmacist_id)
-            .fetch_one(&self.pool).await
+            .await
             .map_err(|err| {
-                match err {
-                    sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
-                        match err.constraint() {
-                            Some("prescription_fills_pharmacist_id_fkey") => {
-                                FillPrescriptionRepositoryError::PharmacistNotFound(
-                                    prescription_fill.pharmacist_id
-                                )
-                            }
-                            _ => FillPrescriptionRepositoryError::DatabaseError(err.to_string()),
-                        }
-                    }
-                    err => FillPrescriptionRepositoryError::DatabaseError(err.to_string()),
-                }
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
             })?;
 
-        let prescription_fill = self
-            .parse_prescription_fills_row(result)
-            .map_err(|err| FillPrescriptionRepositoryError::DatabaseError(err.to_string()))?;
-        Ok(prescription_fill)
+        self.parse_prescriptions_rows(prescriptions_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use uuid::Uuid;
+    async fn get_prescriptions_expiring_before(
+        &self,
+        patient_id: Uuid,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let prescriptions_from_db = self
+            .run_query(
+                "get_prescriptions_expiring_before",
+                sqlx::query(
+                    r#"
+        SELECT 
+            prescriptions.id, 
+            prescriptions.code,
+            prescriptions.prescription_type, 
+            prescriptions.start_date, 
+            prescriptions.end_date, 
+            prescriptions.created_at,
+            prescriptions.updated_at,
+            doctors.id,
+            doctors.name,
+            doctors.pesel_number,
+            doctors.pwz_number,
+            patients.id,
+            patients.name,
+            patients.pesel_number,
+            prescribed_drugs.id, 
+            prescribed_drugs.drug_id, 
+            prescribed_drugs.quantity,
+            prescribed_drugs.created_at,
+            prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
+            prescription_fills.id,
+            prescription_fills.pharmacist_id,
+            prescription_fills.created_at,
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
+        FROM (
+            SELECT * FROM prescriptions
+            WHERE patient_id = $1 AND end_date >= NOW() AND end_date < $2
+            AND EXISTS (
+                SELECT 1 FROM prescribed_drugs
+                WHERE prescribed_drugs.prescription_id = prescriptions.id
+                AND prescribed_drugs.dispensed_quantity < prescribed_drugs.quantity
+            )
+        ) AS prescriptions
+        LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
+        INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
+        INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
+        INNER JOIN patients ON prescriptions.patient_id = patients.id
+        ORDER BY prescriptions.end_date ASC
+    "#,
+                )
+                .bind(patient_id)
+                .bind(before)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-    use super::PostgresPrescriptionsRepository;
-    use crate::{
-        domain::{
-            doctors::{entities::NewDoctor, repository::DoctorsRepository},
-            drugs::{
-                entities::{DrugContentType, NewDrug},
-                repository::DrugsRepository,
-            },
-            patients::{entities::NewPatient, repository::PatientsRepository},
-            pharmacists::{entities::NewPharmacist, repository::PharmacistsRepository},
-            prescriptions::{
-                entities::{NewPrescribedDrug, NewPrescription},
-                repository::{
-                    CreatePrescriptionRepositoryError, FillPrescriptionRepositoryError,
-                    GetPrescriptionByIdRepositoryError, GetPrescriptionsRepositoryError,
-                    PrescriptionsRepository,
-                },
-            },
-        },
-        infrastructure::postgres_repository_impl::{
-            create_tables::create_tables, doctors::PostgresDoctorsRepository,
-            drugs::PostgresDrugsRepository, patients::PostgresPatientsRepository,
-            pharmacists::PostgresPharmacistsRepository,
-        },
-    };
+        let prescriptions = self
+            .parse_prescriptions_rows(prescriptions_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-    struct DatabaseSeedData {
-        doctor: NewDoctor,
-        pharmacist: NewPharmacist,
-        patient: NewPatient,
-        drugs: Vec<NewDrug>,
+        Ok(prescriptions)
     }
 
-    async fn seed_database(pool: sqlx::PgPool) -> DatabaseSeedData {
-        let pharmacists_repo = PostgresPharmacistsRepository::new(pool.clone());
-        let pharmacist = NewPharmacist::new(
-            "John Pharmacist".into(), //
-            "96021807250".into(),
-        )
-        .unwrap();
-        pharmacists_repo
-            .create_pharmacist(pharmacist.clone())
+    async fn get_active_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let prescriptions_from_db = self
+            .run_query(
+                "get_active_prescriptions_for_patient",
+                sqlx::query(
+                    r#"
+        SELECT 
+            prescriptions.id, 
+            prescriptions.code,
+            prescriptions.prescription_type, 
+            prescriptions.start_date, 
+            prescriptions.end_date, 
+            prescriptions.created_at,
+            prescriptions.updated_at,
+            doctors.id,
+            doctors.name,
+            doctors.pesel_number,
+            doctors.pwz_number,
+            patients.id,
+            patients.name,
+            patients.pesel_number,
+            prescribed_drugs.id, 
+            prescribed_drugs.drug_id, 
+            prescribed_drugs.quantity,
+            prescribed_drugs.created_at,
+            prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
+            prescription_fills.id,
+            prescription_fills.pharmacist_id,
+            prescription_fills.created_at,
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
+        FROM (
+            SELECT * FROM prescriptions
+            WHERE patient_id = $1 AND start_date <= $2 AND end_date >= $2
+            AND EXISTS (
+                SELECT 1 FROM prescribed_drugs
+                WHERE prescribed_drugs.prescription_id = prescriptions.id
+                AND prescribed_drugs.dispensed_quantity < prescribed_drugs.quantity
+            )
+        ) AS prescriptions
+        LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
+        INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
+        INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
+        INNER JOIN patients ON prescriptions.patient_id = patients.id
+        ORDER BY prescriptions.end_date ASC
+    "#,
+                )
+                .bind(patient_id)
+                .bind(now)
+                .fetch_all(&self.pool),
+            )
             .await
-            .unwrap();
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-        let patients_repo = PostgresPatientsRepository::new(pool.clone());
-        let patient = NewPatient::new(
-            "John Patient".into(), //
-            "96021807250".into(),
-        )
-        .unwrap();
-        patients_repo.create_patient(patient.clone()).await.unwrap();
-        let drugs_repo = PostgresDrugsRepository::new(pool.clone());
-        let mut drugs = vec![];
-        for _ in 0..4 {
-            let drug = NewDrug::new(
-                "Gripex".into(),
-                DrugContentType::SolidPills,
-                Some(20),
-                Some(300),
-                None,
-                None,
-            )
-            .unwrap();
-            drugs.push(drug.clone());
-            drugs_repo.create_drug(drug).await.unwrap();
-        }
+        let prescriptions = self
+            .parse_prescriptions_rows(prescriptions_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
 
-        let doctors_repo = PostgresDoctorsRepository::new(pool);
-        let doctor = NewDoctor::new(
-            "John Doctor".into(), //
-            "3123456".into(),
-            "96021807250".into(),
-        )
-        .unwrap();
-        doctors_repo.create_doctor(doctor.clone()).await.unwrap();
+        Ok(prescriptions)
+    }
 
-        DatabaseSeedData {
-            doctor,
-            pharmacist,
-            patient,
-            drugs,
+    async fn get_prescriptions_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let prescriptions_from_db = self
+            .run_query(
+                "get_prescriptions_for_patient",
+                sqlx::query(
+                    r#"
+        SELECT 
+            prescriptions.id, 
+            prescriptions.code,
+            prescriptions.prescription_type, 
+            prescriptions.start_date, 
+            prescriptions.end_date, 
+            prescriptions.created_at,
+            prescriptions.updated_at,
+            doctors.id,
+            doctors.name,
+            doctors.pesel_number,
+            doctors.pwz_number,
+            patients.id,
+            patients.name,
+            patients.pesel_number,
+            prescribed_drugs.id, 
+            prescribed_drugs.drug_id, 
+            prescribed_drugs.quantity,
+            prescribed_drugs.created_at,
+            prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
+            prescription_fills.id,
+            prescription_fills.pharmacist_id,
+            prescription_fills.created_at,
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
+        FROM (
+            SELECT * FROM prescriptions
+            WHERE patient_id = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2 OFFSET $3
+        ) AS prescriptions
+        LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
+        INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
+        INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
+        INNER JOIN patients ON prescriptions.patient_id = patients.id
+        ORDER BY prescriptions.created_at DESC, prescriptions.id
+    "#,
+                )
+                .bind(patient_id)
+                .bind(page_size)
+                .bind(offset)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
+
+        let prescriptions = self
+            .parse_prescriptions_rows(prescriptions_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
+
+        Ok(prescriptions)
+    }
+
+    async fn search_prescriptions(
+        &self,
+        query: &str,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Prescription>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let prescriptions_from_db = self
+            .run_query(
+                "search_prescriptions",
+                sqlx::query(
+                    r#"
+        SELECT 
+            prescriptions.id, 
+            prescriptions.code,
+            prescriptions.prescription_type, 
+            prescriptions.start_date, 
+            prescriptions.end_date, 
+            prescriptions.created_at,
+            prescriptions.updated_at,
+            doctors.id,
+            doctors.name,
+            doctors.pesel_number,
+            doctors.pwz_number,
+            patients.id,
+            patients.name,
+            patients.pesel_number,
+            prescribed_drugs.id, 
+            prescribed_drugs.drug_id, 
+            prescribed_drugs.quantity,
+            prescribed_drugs.created_at,
+            prescribed_drugs.updated_at,
+            drugs.unit_price_cents,
+            prescribed_drugs.dispensed_quantity,
+            prescription_fills.id,
+            prescription_fills.pharmacist_id,
+            prescription_fills.created_at,
+            prescription_fills.updated_at,
+            ARRAY(
+                SELECT prescription_diagnoses.code FROM prescription_diagnoses
+                WHERE prescription_diagnoses.prescription_id = prescriptions.id
+                ORDER BY prescription_diagnoses.position
+            ),
+            prescriptions.cancelled_at,
+            prescriptions.window_from_fill
+        FROM (
+            SELECT prescriptions.* FROM prescriptions
+            INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
+            INNER JOIN patients ON prescriptions.patient_id = patients.id
+            WHERE patients.name ILIKE $1 ESCAPE '\'
+            OR doctors.name ILIKE $1 ESCAPE '\'
+            OR prescriptions.code ILIKE $1 ESCAPE '\'
+            ORDER BY prescriptions.created_at DESC, prescriptions.id
+            LIMIT $2 OFFSET $3
+        ) AS prescriptions
+        LEFT JOIN prescription_fills ON prescriptions.id = prescription_fills.prescription_id
+        INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
+        INNER JOIN doctors ON prescriptions.doctor_id = doctors.id
+        INNER JOIN patients ON prescriptions.patient_id = patients.id
+        ORDER BY prescriptions.created_at DESC, prescriptions.id
+    "#,
+                )
+                .bind(format!("%{}%", escape_like_pattern(query)))
+                .bind(page_size)
+                .bind(offset)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
+
+        let prescriptions = self
+            .parse_prescriptions_rows(prescriptions_from_db)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
+
+        Ok(prescriptions)
+    }
+
+    async fn get_drug_history_for_patient(
+        &self,
+        patient_id: Uuid,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DrugHistoryEntry>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let rows = self
+            .run_query(
+                "get_drug_history_for_patient",
+                sqlx::query(
+                    r#"
+        SELECT
+            prescriptions.id,
+            prescriptions.code,
+            drugs.id,
+            drugs.name,
+            prescribed_drugs.quantity,
+            prescriptions.start_date
+        FROM prescriptions
+        INNER JOIN prescribed_drugs ON prescriptions.id = prescribed_drugs.prescription_id
+        INNER JOIN drugs ON prescribed_drugs.drug_id = drugs.id
+        WHERE prescriptions.patient_id = $1
+        ORDER BY prescriptions.start_date DESC, prescriptions.id, drugs.name
+        LIMIT $2 OFFSET $3
+    "#,
+                )
+                .bind(patient_id)
+                .bind(page_size)
+                .bind(offset)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(DrugHistoryEntry {
+                    prescription_id: row.try_get(0)?,
+                    prescription_code: row.try_get(1)?,
+                    drug_id: row.try_get(2)?,
+                    drug_name: row.try_get(3)?,
+                    quantity: row.try_get(4)?,
+                    start_date: row.try_get(5)?,
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionsRepositoryError::DatabaseError,
+                    GetPrescriptionsRepositoryError::Unavailable,
+                )
+            })
+    }
+
+    async fn count_prescriptions_by_type(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<PrescriptionType, i64>, GetPrescriptionsRepositoryError> {
+        let counts_from_db = self.run_query("count_prescriptions_by_type", sqlx::query(
+            r#"SELECT prescription_type, COUNT(*) FROM prescriptions WHERE created_at >= $1 AND created_at <= $2 GROUP BY prescription_type"#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool))
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetPrescriptionsRepositoryError::DatabaseError,
+                GetPrescriptionsRepositoryError::Unavailable,
+            )
+        })?;
+
+        let mut counts: HashMap<PrescriptionType, i64> = PrescriptionType::ALL
+            .into_iter()
+            .map(|prescription_type| (prescription_type, 0))
+            .collect();
+        let map_row_error = |err| {
+            map_database_error(
+                err,
+                GetPrescriptionsRepositoryError::DatabaseError,
+                GetPrescriptionsRepositoryError::Unavailable,
+            )
+        };
+        for row in counts_from_db {
+            let prescription_type: PrescriptionType = row.try_get(0).map_err(map_row_error)?;
+            let count: i64 = row.try_get(1).map_err(map_row_error)?;
+            counts.insert(prescription_type, count);
+        }
+
+        Ok(counts)
+    }
+
+    async fn count_prescriptions_per_doctor(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<DoctorPrescriptionCount>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, PRESCRIPTIONS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+        let map_error = |err| {
+            map_database_error(
+                err,
+                GetPrescriptionsRepositoryError::DatabaseError,
+                GetPrescriptionsRepositoryError::Unavailable,
+            )
+        };
+
+        let rows = self.run_query("count_prescriptions_per_doctor", sqlx::query(
+            r#"SELECT doctors.id, doctors.name, COUNT(*) AS prescriptions_count FROM prescriptions INNER JOIN doctors ON doctors.id = prescriptions.doctor_id WHERE prescriptions.created_at >= $1 AND prescriptions.created_at <= $2 GROUP BY doctors.id ORDER BY prescriptions_count DESC, doctors.name, doctors.id LIMIT $3 OFFSET $4"#,
+        )
+        .bind(from)
+        .bind(to)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool))
+        .await
+        .map_err(map_error)?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(DoctorPrescriptionCount {
+                    doctor_id: row.try_get(0).map_err(map_error)?,
+                    doctor_name: row.try_get(1).map_err(map_error)?,
+                    prescriptions_count: row.try_get(2).map_err(map_error)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn count_prescriptions_for_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<DrugUsage, GetDrugUsageRepositoryError> {
+        // Left join keeps the row of a drug that was never prescribed, so only unknown drugs yield
+        // no rows
+        let row = self.run_query("count_prescriptions_for_drug", sqlx::query(
+            r#"SELECT COUNT(DISTINCT prescriptions.id), COALESCE(SUM(prescribed_drugs.quantity), 0)::BIGINT FROM drugs LEFT JOIN (prescribed_drugs INNER JOIN prescriptions ON prescriptions.id = prescribed_drugs.prescription_id AND prescriptions.start_date >= $2 AND prescriptions.start_date <= $3) ON prescribed_drugs.drug_id = drugs.id WHERE drugs.id = $1 GROUP BY drugs.id"#,
+        )
+        .bind(drug_id)
+        .bind(from)
+        .bind(to)
+        .fetch_optional(&self.pool))
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetDrugUsageRepositoryError::DatabaseError,
+                GetDrugUsageRepositoryError::Unavailable,
+            )
+        })?
+        .ok_or(GetDrugUsageRepositoryError::DrugNotFound(drug_id))?;
+
+        let map_row_error = |err| {
+            map_database_error(
+                err,
+                GetDrugUsageRepositoryError::DatabaseError,
+                GetDrugUsageRepositoryError::Unavailable,
+            )
+        };
+
+        Ok(DrugUsage {
+            drug_id,
+            prescriptions_count: row.try_get(0).map_err(map_row_error)?,
+            total_quantity: row.try_get(1).map_err(map_row_error)?,
+        })
+    }
+
+    async fn get_patients_prescribed_drug(
+        &self,
+        drug_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<AffectedPatient>, GetDrugUsageRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                GetDrugUsageRepositoryError::DatabaseError,
+                GetDrugUsageRepositoryError::Unavailable,
+            )
+        };
+
+        // Left join keeps a row with NULL patient for a drug that wasn't prescribed in the range, so
+        // only unknown drugs yield no rows
+        let rows = self.run_query("get_patients_prescribed_drug", sqlx::query(
+            r#"SELECT DISTINCT patients.id, patients.name, patients.pesel_number FROM drugs LEFT JOIN (prescribed_drugs INNER JOIN prescriptions ON prescriptions.id = prescribed_drugs.prescription_id AND prescriptions.start_date >= $2 AND prescriptions.start_date <= $3 INNER JOIN patients ON patients.id = prescriptions.patient_id) ON prescribed_drugs.drug_id = drugs.id WHERE drugs.id = $1 ORDER BY patients.name, patients.id"#,
+        )
+        .bind(drug_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool))
+        .await
+        .map_err(map_error)?;
+        if rows.is_empty() {
+            Err(GetDrugUsageRepositoryError::DrugNotFound(drug_id))?;
+        }
+
+        let mut affected_patients = vec![];
+        for row in rows {
+            let patient_id: Option<Uuid> = row.try_get(0).map_err(map_error)?;
+            if let Some(id) = patient_id {
+                affected_patients.push(AffectedPatient {
+                    id,
+                    name: row.try_get(1).map_err(map_error)?,
+                    pesel_number: row.try_get(2).map_err(map_error)?,
+                });
+            }
+        }
+
+        Ok(affected_patients)
+    }
+
+    async fn count_fills_by_pharmacist_per_day(
+        &self,
+        pharmacist_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<NaiveDate, i64>, GetPharmacistFillStatsRepositoryError> {
+        // Left join keeps a row with NULL day for a pharmacist without fills in the range, so only
+        // unknown pharmacists yield no rows
+        let rows = self.run_query("count_fills_by_pharmacist_per_day", sqlx::query(
+            r#"SELECT (prescription_fills.created_at AT TIME ZONE 'UTC')::DATE AS day, COUNT(prescription_fills.id) FROM pharmacists LEFT JOIN prescription_fills ON prescription_fills.pharmacist_id = pharmacists.id AND prescription_fills.created_at >= $2 AND prescription_fills.created_at <= $3 WHERE pharmacists.id = $1 GROUP BY day"#,
+        )
+        .bind(pharmacist_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool))
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetPharmacistFillStatsRepositoryError::DatabaseError,
+                GetPharmacistFillStatsRepositoryError::Unavailable,
+            )
+        })?;
+
+        if rows.is_empty() {
+            Err(GetPharmacistFillStatsRepositoryError::PharmacistNotFound(
+                pharmacist_id,
+            ))?;
+        }
+
+        let map_row_error = |err| {
+            map_database_error(
+                err,
+                GetPharmacistFillStatsRepositoryError::DatabaseError,
+                GetPharmacistFillStatsRepositoryError::Unavailable,
+            )
+        };
+        let mut counts = HashMap::new();
+        for row in rows {
+            let day: Option<NaiveDate> = row.try_get(0).map_err(map_row_error)?;
+            if let Some(day) = day {
+                counts.insert(day, row.try_get(1).map_err(map_row_error)?);
+            }
+        }
+
+        Ok(counts)
+    }
+
+    async fn get_unprescribed_drugs(
+        &self,
+        page: Option<i64>,
+        page_size: Option<i64>,
+    ) -> Result<Vec<Drug>, GetPrescriptionsRepositoryError> {
+        let (page_size, offset) =
+            get_pagination_params(page, page_size, DRUGS_DEFAULT_PAGE_SIZE)
+                .map_err(GetPrescriptionsRepositoryError::InvalidPaginationParams)?;
+
+        let drugs_from_db = self.run_query("get_unprescribed_drugs", sqlx::query(
+            r#"SELECT drugs.id, drugs.name, drugs.content_type, drugs.pills_count, drugs.mg_per_pill, drugs.ml_per_pill, drugs.volume_ml, drugs.unit_price_cents, drugs.manufacturer, drugs.barcode, drugs.created_at, drugs.updated_at, drugs.deactivated_at, drugs.doses_count, drugs.mcg_per_dose, drugs.ml_per_dose, drugs.created_by, drugs.category, drugs.allowed_prescription_types, drugs.stock FROM drugs LEFT JOIN prescribed_drugs ON prescribed_drugs.drug_id = drugs.id WHERE prescribed_drugs.id IS NULL ORDER BY drugs.name, drugs.id LIMIT $1 OFFSET $2"#,
+        )
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool))
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetPrescriptionsRepositoryError::DatabaseError,
+                GetPrescriptionsRepositoryError::Unavailable,
+            )
+        })?;
+
+        drugs_from_db
+            .into_iter()
+            .map(|row| {
+                parse_drugs_row(row).map_err(|err| {
+                    map_database_error(
+                        err,
+                        GetPrescriptionsRepositoryError::DatabaseError,
+                        GetPrescriptionsRepositoryError::Unavailable,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    async fn get_drug_categories(
+        &self,
+        drug_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, DrugCategory>, CreatePrescriptionRepositoryError> {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                CreatePrescriptionRepositoryError::DatabaseError,
+                CreatePrescriptionRepositoryError::Unavailable,
+            )
+        };
+
+        let rows = self
+            .run_query(
+                "get_drug_categories",
+                sqlx::query(
+                    r#"SELECT id, category FROM drugs WHERE id = ANY($1) AND category IS NOT NULL"#,
+                )
+                .bind(drug_ids)
+                .fetch_all(&self.pool),
+            )
+            .await
+            .map_err(map_error)?;
+
+        rows.into_iter()
+            .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+            .collect::<Result<HashMap<Uuid, DrugCategory>, sqlx::Error>>()
+            .map_err(map_error)
+    }
+
+    async fn get_drug_prescription_type_restrictions(
+        &self,
+        drug_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, DrugPrescriptionTypeRestriction>, CreatePrescriptionRepositoryError>
+    {
+        let map_error = |err| {
+            map_database_error(
+                err,
+                CreatePrescriptionRepositoryError::DatabaseError,
+                CreatePrescriptionRepositoryError::Unavailable,
+            )
+        };
+
+        let rows = self.run_query("get_drug_prescription_type_restrictions", sqlx::query(
+            r#"SELECT id, name, allowed_prescription_types FROM drugs WHERE id = ANY($1) AND cardinality(allowed_prescription_types) > 0"#,
+        )
+        .bind(drug_ids)
+        .fetch_all(&self.pool))
+        .await
+        .map_err(map_error)?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok((
+                    row.try_get(0)?,
+                    DrugPrescriptionTypeRestriction {
+                        drug_name: row.try_get(1)?,
+                        allowed_prescription_types: row.try_get(2)?,
+                    },
+                ))
+            })
+            .collect::<Result<HashMap<Uuid, DrugPrescriptionTypeRestriction>, sqlx::Error>>()
+            .map_err(map_error)
+    }
+
+    async fn get_patient_pesel_number(
+        &self,
+        patient_id: Uuid,
+    ) -> Result<Option<String>, CreatePrescriptionRepositoryError> {
+        self.run_query(
+            "get_patient_pesel_number",
+            sqlx::query_scalar(r#"SELECT pesel_number FROM patients WHERE id = $1"#)
+                .bind(patient_id)
+                .fetch_optional(&self.pool),
+        )
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                CreatePrescriptionRepositoryError::DatabaseError,
+                CreatePrescriptionRepositoryError::Unavailable,
+            )
+        })
+    }
+
+    async fn get_doctor_pesel_number(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Option<String>, CreatePrescriptionRepositoryError> {
+        self.run_query(
+            "get_doctor_pesel_number",
+            sqlx::query_scalar(r#"SELECT pesel_number FROM doctors WHERE id = $1"#)
+                .bind(doctor_id)
+                .fetch_optional(&self.pool),
+        )
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                CreatePrescriptionRepositoryError::DatabaseError,
+                CreatePrescriptionRepositoryError::Unavailable,
+            )
+        })
+    }
+
+    async fn fill_prescription(
+        &self,
+        new_prescription_fill: NewPrescriptionFill,
+    ) -> Result<PrescriptionFill, FillPrescriptionRepositoryError> {
+        let mut prescription_fills = self.fill_prescriptions(vec![new_prescription_fill]).await?;
+
+        Ok(prescription_fills.remove(0))
+    }
+
+    async fn fill_prescriptions(
+        &self,
+        new_prescription_fills: Vec<NewPrescriptionFill>,
+    ) -> Result<Vec<PrescriptionFill>, FillPrescriptionRepositoryError> {
+        let map_transaction_error = |err| {
+            map_database_error(
+                err,
+                FillPrescriptionRepositoryError::DatabaseError,
+                FillPrescriptionRepositoryError::Unavailable,
+            )
+        };
+        // A serialization failure aborts the transaction, so all the fills are inserted again
+        retry_transaction_on_serialization_failure(
+            self.retry_policy,
+            map_transaction_error,
+            || async {
+                let map_error =
+                    |err| TransactionError::from_database_error(err, map_transaction_error);
+                let mut transaction = begin_transaction(&self.pool, self.isolation_level)
+                    .await
+                    .map_err(map_error)?;
+
+                // Returning early drops the transaction, which rolls back the fills inserted so far
+                let mut prescription_fills = vec![];
+                for new_prescription_fill in &new_prescription_fills {
+                    let prescription_fill = self
+                        .insert_prescription_fill(&mut transaction, new_prescription_fill)
+                        .await?;
+                    prescription_fills.push(prescription_fill);
+                }
+
+                transaction.commit().await.map_err(map_error)?;
+
+                Ok(prescription_fills)
+            },
+        )
+        .await
+    }
+
+    async fn reassign_prescription_patient(
+        &self,
+        prescription_id: Uuid,
+        new_patient_id: Uuid,
+    ) -> Result<Prescription, ReassignPrescriptionPatientRepositoryError> {
+        // The fill check is part of the update, so a prescription filled in the meantime isn't reassigned
+        let updated_rows = retry_on_serialization_failure(self.retry_policy, || {
+            self.run_query("reassign_prescription_patient", sqlx::query(
+                    r#"UPDATE prescriptions SET patient_id = $2, updated_at = NOW() WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM prescription_fills WHERE prescription_id = $1) RETURNING id"#
+                )
+                .bind(prescription_id)
+                .bind(new_patient_id)
+                .fetch_all(&self.pool))
+            })
+            .await
+            .map_err(|err| match err {
+                sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
+                    match err.constraint() {
+                        Some("prescriptions_patient_id_fkey") => {
+                            ReassignPrescriptionPatientRepositoryError::PatientNotFound(
+                                new_patient_id,
+                            )
+                        }
+                        _ => ReassignPrescriptionPatientRepositoryError::DatabaseError(
+                            err.to_string(),
+                        ),
+                    }
+                }
+                err => map_database_error(
+                    err,
+                    ReassignPrescriptionPatientRepositoryError::DatabaseError,
+                    ReassignPrescriptionPatientRepositoryError::Unavailable,
+                ),
+            })?;
+
+        let prescription = self
+            .get_prescription_by_id(prescription_id)
+            .await
+            .map_err(|err| match err {
+                GetPrescriptionByIdRepositoryError::NotFound(id) => {
+                    ReassignPrescriptionPatientRepositoryError::PrescriptionNotFound(id)
+                }
+                GetPrescriptionByIdRepositoryError::DatabaseError(message) => {
+                    ReassignPrescriptionPatientRepositoryError::DatabaseError(message)
+                }
+                GetPrescriptionByIdRepositoryError::Unavailable => {
+                    ReassignPrescriptionPatientRepositoryError::Unavailable
+                }
+            })?;
+        if updated_rows.is_empty() {
+            Err(ReassignPrescriptionPatientRepositoryError::AlreadyFilled(
+                prescription_id,
+            ))?;
+        }
+
+        Ok(prescription)
+    }
+
+    async fn cancel_prescription(
+        &self,
+        prescription_id: Uuid,
+    ) -> Result<Prescription, CancelPrescriptionRepositoryError> {
+        // Both checks are part of the update, so a prescription filled in the meantime isn't cancelled
+        let updated_rows = retry_on_serialization_failure(self.retry_policy, || {
+            self.run_query("cancel_prescription", sqlx::query(
+                    r#"UPDATE prescriptions SET cancelled_at = NOW(), updated_at = NOW() WHERE id = $1 AND cancelled_at IS NULL AND NOT EXISTS (SELECT 1 FROM prescription_fills WHERE prescription_id = $1) RETURNING id"#
+                )
+                .bind(prescription_id)
+                .fetch_all(&self.pool))
+            })
+            .await
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    CancelPrescriptionRepositoryError::DatabaseError,
+                    CancelPrescriptionRepositoryError::Unavailable,
+                )
+            })?;
+
+        let prescription = self
+            .get_prescription_by_id(prescription_id)
+            .await
+            .map_err(|err| match err {
+                GetPrescriptionByIdRepositoryError::NotFound(id) => {
+                    CancelPrescriptionRepositoryError::PrescriptionNotFound(id)
+                }
+                GetPrescriptionByIdRepositoryError::DatabaseError(message) => {
+                    CancelPrescriptionRepositoryError::DatabaseError(message)
+                }
+                GetPrescriptionByIdRepositoryError::Unavailable => {
+                    CancelPrescriptionRepositoryError::Unavailable
+                }
+            })?;
+        if updated_rows.is_empty() {
+            Err(match prescription.cancelled_at {
+                Some(_) => CancelPrescriptionRepositoryError::AlreadyCancelled(prescription_id),
+                None => CancelPrescriptionRepositoryError::AlreadyFilled(prescription_id),
+            })?;
+        }
+
+        Ok(prescription)
+    }
+
+    async fn create_prescription_template(
+        &self,
+        template: NewPrescriptionTemplate,
+    ) -> Result<PrescriptionTemplate, CreatePrescriptionTemplateRepositoryError> {
+        let map_transaction_error = |err| {
+            map_database_error(
+                err,
+                CreatePrescriptionTemplateRepositoryError::DatabaseError,
+                CreatePrescriptionTemplateRepositoryError::Unavailable,
+            )
+        };
+        let mut transaction = self.pool.begin().await.map_err(map_transaction_error)?;
+
+        self.run_query(
+            "create_prescription_template",
+            sqlx::query(
+                r#"INSERT INTO prescription_templates (id, doctor_id, name) VALUES ($1, $2, $3)"#,
+            )
+            .bind(template.id)
+            .bind(template.doctor_id)
+            .bind(&template.name)
+            .execute(&mut *transaction),
+        )
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(err)
+                if err.is_foreign_key_violation()
+                    && err.constraint() == Some("prescription_templates_doctor_id_fkey") =>
+            {
+                CreatePrescriptionTemplateRepositoryError::DoctorNotFound(template.doctor_id)
+            }
+            err => map_transaction_error(err),
+        })?;
+
+        self.insert_prescription_template_drugs(&mut transaction, &template)
+            .await
+            .map_err(|(err, drug_id)| match err {
+                sqlx::Error::Database(err)
+                    if err.is_foreign_key_violation()
+                        && err.constraint()
+                            == Some(PRESCRIPTION_TEMPLATE_DRUGS_DRUG_ID_CONSTRAINT) =>
+                {
+                    CreatePrescriptionTemplateRepositoryError::DrugNotFound(drug_id)
+                }
+                err => map_transaction_error(err),
+            })?;
+
+        transaction.commit().await.map_err(map_transaction_error)?;
+
+        self.get_prescription_template_by_id(template.id)
+            .await
+            .map_err(|err| {
+                CreatePrescriptionTemplateRepositoryError::DatabaseError(err.to_string())
+            })
+    }
+
+    async fn get_prescription_template_by_id(
+        &self,
+        template_id: Uuid,
+    ) -> Result<PrescriptionTemplate, GetPrescriptionTemplateByIdRepositoryError> {
+        let rows = self.run_query("get_prescription_template_by_id", sqlx::query(
+                    r#"
+        SELECT
+            prescription_templates.id,
+            prescription_templates.doctor_id,
+            prescription_templates.name,
+            prescription_templates.created_at,
+            prescription_templates.updated_at,
+            prescription_template_drugs.drug_id,
+            prescription_template_drugs.quantity
+        FROM prescription_templates
+        INNER JOIN prescription_template_drugs ON prescription_templates.id = prescription_template_drugs.template_id
+        WHERE prescription_templates.id = $1
+        ORDER BY prescription_template_drugs.position ASC
+    "#,
+                )
+                .bind(template_id)
+                .fetch_all(&self.pool))
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetPrescriptionTemplateByIdRepositoryError::DatabaseError,
+                GetPrescriptionTemplateByIdRepositoryError::Unavailable,
+            )
+        })?;
+
+        let templates = self
+            .parse_prescription_templates_rows(rows)
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetPrescriptionTemplateByIdRepositoryError::DatabaseError,
+                    GetPrescriptionTemplateByIdRepositoryError::Unavailable,
+                )
+            })?;
+
+        templates
+            .into_iter()
+            .next()
+            .ok_or(GetPrescriptionTemplateByIdRepositoryError::NotFound(
+                template_id,
+            ))
+    }
+
+    async fn get_prescription_templates_for_doctor(
+        &self,
+        doctor_id: Uuid,
+    ) -> Result<Vec<PrescriptionTemplate>, GetPrescriptionTemplatesRepositoryError> {
+        let rows = self.run_query("get_prescription_templates_for_doctor", sqlx::query(
+                    r#"
+        SELECT
+            prescription_templates.id,
+            prescription_templates.doctor_id,
+            prescription_templates.name,
+            prescription_templates.created_at,
+            prescription_templates.updated_at,
+            prescription_template_drugs.drug_id,
+            prescription_template_drugs.quantity
+        FROM prescription_templates
+        INNER JOIN prescription_template_drugs ON prescription_templates.id = prescription_template_drugs.template_id
+        WHERE prescription_templates.doctor_id = $1
+        ORDER BY prescription_templates.created_at ASC, prescription_templates.id, prescription_template_drugs.position ASC
+    "#,
+                )
+                .bind(doctor_id)
+                .fetch_all(&self.pool))
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                GetPrescriptionTemplatesRepositoryError::DatabaseError,
+                GetPrescriptionTemplatesRepositoryError::Unavailable,
+            )
+        })?;
+
+        self.parse_prescription_templates_rows(rows).map_err(|err| {
+            map_database_error(
+                err,
+                GetPrescriptionTemplatesRepositoryError::DatabaseError,
+                GetPrescriptionTemplatesRepositoryError::Unavailable,
+            )
+        })
+    }
+
+    async fn update_prescription_template(
+        &self,
+        template: NewPrescriptionTemplate,
+    ) -> Result<PrescriptionTemplate, UpdatePrescriptionTemplateRepositoryError> {
+        let map_transaction_error = |err| {
+            map_database_error(
+                err,
+                UpdatePrescriptionTemplateRepositoryError::DatabaseError,
+                UpdatePrescriptionTemplateRepositoryError::Unavailable,
+            )
+        };
+        let mut transaction = self.pool.begin().await.map_err(map_transaction_error)?;
+
+        let updated_rows = self.run_query("update_prescription_template", sqlx::query(
+                r#"UPDATE prescription_templates SET name = $2, updated_at = NOW() WHERE id = $1 RETURNING id"#
+            )
+            .bind(template.id)
+            .bind(&template.name)
+            .fetch_all(&mut *transaction))
+            .await
+            .map_err(map_transaction_error)?;
+        if updated_rows.is_empty() {
+            Err(UpdatePrescriptionTemplateRepositoryError::NotFound(
+                template.id,
+            ))?;
+        }
+
+        self.run_query(
+            "update_prescription_template",
+            sqlx::query(r#"DELETE FROM prescription_template_drugs WHERE template_id = $1"#)
+                .bind(template.id)
+                .execute(&mut *transaction),
+        )
+        .await
+        .map_err(map_transaction_error)?;
+
+        self.insert_prescription_template_drugs(&mut transaction, &template)
+            .await
+            .map_err(|(err, drug_id)| match err {
+                sqlx::Error::Database(err)
+                    if err.is_foreign_key_violation()
+                        && err.constraint()
+                            == Some(PRESCRIPTION_TEMPLATE_DRUGS_DRUG_ID_CONSTRAINT) =>
+                {
+                    UpdatePrescriptionTemplateRepositoryError::DrugNotFound(drug_id)
+                }
+                err => map_transaction_error(err),
+            })?;
+
+        transaction.commit().await.map_err(map_transaction_error)?;
+
+        self.get_prescription_template_by_id(template.id)
+            .await
+            .map_err(|err| {
+                UpdatePrescriptionTemplateRepositoryError::DatabaseError(err.to_string())
+            })
+    }
+
+    async fn delete_prescription_template(
+        &self,
+        template_id: Uuid,
+    ) -> Result<(), DeletePrescriptionTemplateRepositoryError> {
+        // The drugs of the template are removed along with it
+        let deleted_rows = retry_on_serialization_failure(self.retry_policy, || {
+            self.run_query(
+                "delete_prescription_template",
+                sqlx::query(r#"DELETE FROM prescription_templates WHERE id = $1 RETURNING id"#)
+                    .bind(template_id)
+                    .fetch_all(&self.pool),
+            )
+        })
+        .await
+        .map_err(|err| {
+            map_database_error(
+                err,
+                DeletePrescriptionTemplateRepositoryError::DatabaseError,
+                DeletePrescriptionTemplateRepositoryError::Unavailable,
+            )
+        })?;
+        if deleted_rows.is_empty() {
+            Err(DeletePrescriptionTemplateRepositoryError::NotFound(
+                template_id,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+    };
+
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use super::PostgresPrescriptionsRepository;
+    use crate::{
+        domain::{
+            doctors::{entities::NewDoctor, repository::DoctorsRepository},
+            drugs::{
+                entities::{DrugCategory, DrugContentType, NewDrug},
+                repository::DrugsRepository,
+            },
+            patients::{entities::NewPatient, repository::PatientsRepository},
+            pharmacists::{entities::NewPharmacist, repository::PharmacistsRepository},
+            prescriptions::{
+                entities::{
+                    DoctorPrescriptionCount, DrugPrescriptionTypeRestriction, NewPrescribedDrug,
+                    NewPrescription, NewPrescriptionTemplate, PrescriptionFillStatus,
+                    PrescriptionType,
+                },
+                policy::PrescriptionPolicy,
+                repository::{
+                    CancelPrescriptionRepositoryError, CreatePrescriptionRepositoryError,
+                    CreatePrescriptionTemplateRepositoryError,
+                    DeletePrescriptionTemplateRepositoryError, FillPrescriptionRepositoryError,
+                    GetDrugUsageRepositoryError, GetPharmacistFillStatsRepositoryError,
+                    GetPrescriptionByIdRepositoryError, GetPrescriptionTemplateByIdRepositoryError,
+                    GetPrescriptionsRepositoryError, PrescriptionsRepository,
+                    ReassignPrescriptionPatientRepositoryError,
+                    UpdatePrescriptionTemplateRepositoryError,
+                },
+            },
+        },
+        infrastructure::postgres_repository_impl::{
+            create_tables::create_tables, doctors::PostgresDoctorsRepository,
+            drugs::PostgresDrugsRepository, patients::PostgresPatientsRepository,
+            pharmacists::PostgresPharmacistsRepository, query_metrics::QueryMetrics,
+        },
+    };
+
+    struct DatabaseSeedData {
+        doctor: NewDoctor,
+        pharmacist: NewPharmacist,
+        patient: NewPatient,
+        drugs: Vec<NewDrug>,
+    }
+
+    async fn seed_database(pool: sqlx::PgPool) -> DatabaseSeedData {
+        let pharmacists_repo = PostgresPharmacistsRepository::new(pool.clone());
+        let pharmacist = NewPharmacist::new(
+            "John Pharmacist".into(), //
+            "96021807250".into(),
+        )
+        .unwrap();
+        pharmacists_repo
+            .create_pharmacist(pharmacist.clone())
+            .await
+            .unwrap();
+
+        let patients_repo = PostgresPatientsRepository::new(pool.clone());
+        let patient = NewPatient::new(
+            "John Patient".into(), //
+            "96021807250".into(),
+        )
+        .unwrap();
+        patients_repo.create_patient(patient.clone()).await.unwrap();
+        let drugs_repo = PostgresDrugsRepository::new(pool.clone());
+        let mut drugs = vec![];
+        for (name, unit_price_cents) in [
+            ("Gripex", Some(1000)),
+            ("Apap", None),
+            ("Flegamax", Some(250)),
+            ("Rutinoscorbin", None),
+        ] {
+            let drug = NewDrug::new(
+                name.into(),
+                DrugContentType::SolidPills,
+                Some(20),
+                Some(300),
+                None,
+                None,
+                None,
+                None,
+                None,
+                unit_price_cents,
+                None,
+                None,
+            )
+            .unwrap();
+            drugs.push(drug.clone());
+            drugs_repo.create_drug(drug).await.unwrap();
+        }
+
+        let doctors_repo = PostgresDoctorsRepository::new(pool);
+        let doctor = NewDoctor::new(
+            "John Doctor".into(), //
+            "3123456".into(),
+            "96021807250".into(),
+        )
+        .unwrap();
+        doctors_repo.create_doctor(doctor.clone()).await.unwrap();
+
+        DatabaseSeedData {
+            doctor,
+            pharmacist,
+            patient,
+            drugs,
+        }
+    }
+
+    async fn setup_repository(
+        pool: sqlx::PgPool,
+    ) -> (PostgresPrescriptionsRepository, DatabaseSeedData) {
+        create_tables(&pool, true).await.unwrap();
+        let seeds = seed_database(pool.clone()).await;
+        let repository = PostgresPrescriptionsRepository::new(pool);
+        (repository, seeds)
+    }
+
+    #[sqlx::test]
+    async fn creates_and_reads_prescription_by_id(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(new_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db, new_prescription);
+    }
+
+    #[sqlx::test]
+    async fn previews_prescription_without_saving_it(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 3,
+            }],
+        )
+        .unwrap()
+        .with_diagnoses(vec!["J06.9".into()])
+        .unwrap();
+
+        let prescription = repository
+            .preview_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(prescription, new_prescription);
+        assert_eq!(prescription.doctor.name, "John Doctor");
+        assert_eq!(prescription.patient.pesel_number, "96021807250");
+        // Gripex costs 10.00 per unit
+        assert_eq!(prescription.estimated_cost_cents, Some(3000));
+
+        let prescriptions_count: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM prescriptions"#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions_count, 0);
+
+        let missing_drug_id = Uuid::new_v4();
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: missing_drug_id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository.preview_prescription(new_prescription).await,
+            Err(CreatePrescriptionRepositoryError::DrugNotFound(
+                missing_drug_id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn counts_queries_per_repository_method(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let query_metrics = Arc::new(QueryMetrics::new());
+        let repository = repository.with_query_metrics(query_metrics.clone());
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap()
+        .with_diagnoses(vec!["J06.9".into()])
+        .unwrap();
+
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        // One insert for the prescription and one per prescribed drug and diagnosis
+        assert_eq!(query_metrics.get_count("create_prescription"), 4);
+        assert_eq!(query_metrics.get_count("get_prescription_by_id"), 1);
+
+        repository
+            .get_prescription_by_id(new_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(query_metrics.get_count("get_prescription_by_id"), 2);
+    }
+
+    #[sqlx::test]
+    async fn creates_and_reads_prescription_with_diagnoses(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap()
+        .with_diagnoses(vec!["J06.9".into(), "E11".into()])
+        .unwrap();
+
+        let created_prescription = repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(created_prescription.diagnoses, vec!["J06.9", "E11"]);
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(new_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db.diagnoses, vec!["J06.9", "E11"]);
+
+        let prescriptions = repository
+            .get_prescriptions(None, None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions[0].diagnoses, vec!["J06.9", "E11"]);
+    }
+
+    #[sqlx::test]
+    async fn doesnt_create_prescription_if_relations_dont_exist(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let nonexistent_doctor_id = Uuid::new_v4();
+        let new_prescription_with_nonexisting_doctor_id = NewPrescription::new(
+            nonexistent_doctor_id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription_with_nonexisting_doctor_id)
+                .await,
+            Err(CreatePrescriptionRepositoryError::DoctorNotFound(
+                nonexistent_doctor_id
+            ))
+        );
+
+        let nonexistent_patient_id = Uuid::new_v4();
+        let new_prescription_with_nonexisting_patient_id = NewPrescription::new(
+            seeds.patient.id,
+            nonexistent_patient_id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription_with_nonexisting_patient_id)
+                .await,
+            Err(CreatePrescriptionRepositoryError::PatientNotFound(
+                nonexistent_patient_id
+            ))
+        );
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        let new_prescription_with_nonexisting_drug_id = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: nonexistent_drug_id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription_with_nonexisting_drug_id)
+                .await,
+            Err(CreatePrescriptionRepositoryError::DrugNotFound(
+                nonexistent_drug_id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn rolls_back_prescription_if_one_of_its_drugs_doesnt_exist(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: nonexistent_drug_id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .create_prescription(new_prescription.clone())
+                .await,
+            Err(CreatePrescriptionRepositoryError::DrugNotFound(
+                nonexistent_drug_id
+            ))
+        );
+        assert_eq!(
+            repository.get_prescription_by_id(new_prescription.id).await,
+            Err(GetPrescriptionByIdRepositoryError::NotFound(
+                new_prescription.id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn get_prescription_by_id_returns_error_if_prescription_doesnt_exist(pool: sqlx::PgPool) {
+        let (repository, _) = setup_repository(pool).await;
+        let prescription_id = Uuid::new_v4();
+
+        let prescription_from_db = repository.get_prescription_by_id(prescription_id).await;
+
+        assert_eq!(
+            prescription_from_db,
+            Err(GetPrescriptionByIdRepositoryError::NotFound(
+                prescription_id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn creates_and_reads_prescriptions_from_database(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[3].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        for _ in 0..10 {
+            let another_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(another_prescription)
+                .await
+                .unwrap();
+        }
+
+        let prescriptions = repository
+            .get_prescriptions(None, Some(7), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 7);
+        assert_eq!(prescriptions[0], new_prescription);
+
+        let prescriptions = repository
+            .get_prescriptions(None, Some(20), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(prescriptions.len(), 11);
+
+        let prescriptions = repository
+            .get_prescriptions(Some(1), Some(10), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(prescriptions.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn paginates_prescriptions_with_identical_created_at_without_duplicates(
+        pool: sqlx::PgPool,
+    ) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+
+        for _ in 0..5 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription)
+                .await
+                .unwrap();
+        }
+        sqlx::query(r#"UPDATE prescriptions SET created_at = '2024-03-15T12:00:00Z'"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut prescription_ids = vec![];
+        let mut prescription_ids_of_pages = vec![];
+        for page in 0..3 {
+            let prescriptions = repository
+                .get_prescriptions(Some(page), Some(2), None, None, None)
+                .await
+                .unwrap();
+            prescription_ids.extend(prescriptions.iter().map(|prescription| prescription.id));
+            prescription_ids_of_pages.extend(
+                repository
+                    .get_prescription_ids(Some(page), Some(2), None, None, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let mut sorted_prescription_ids = prescription_ids.clone();
+        sorted_prescription_ids.sort();
+        sorted_prescription_ids.dedup();
+
+        assert_eq!(prescription_ids.len(), 5);
+        assert_eq!(prescription_ids, sorted_prescription_ids);
+        assert_eq!(prescription_ids_of_pages, prescription_ids);
+    }
+
+    #[sqlx::test]
+    async fn get_prescriptions_returns_error_if_pagination_params_are_incorrect(
+        pool: sqlx::PgPool,
+    ) {
+        let (repository, _) = setup_repository(pool).await;
+
+        assert!(match repository
+            .get_prescriptions(Some(-1), Some(10), None, None, None)
+            .await
+        {
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
+            _ => false,
+        });
+
+        assert!(match repository
+            .get_prescriptions(Some(0), Some(0), None, None, None)
+            .await
+        {
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[sqlx::test]
+    async fn fills_prescription_and_saves_to_database(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+
+        repository
+            .create_prescription(prescription.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert!(prescription_from_db.fills.is_empty());
+
+        let code = prescription_from_db.code.clone();
+        let new_prescription_fill = prescription_from_db
+            .fill(seeds.pharmacist.id, &code, None, Utc::now())
+            .unwrap();
+        let created_prescription_fill = repository
+            .fill_prescription(new_prescription_fill.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(created_prescription_fill, new_prescription_fill);
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db.fills[0], new_prescription_fill);
+    }
+
+    #[sqlx::test]
+    async fn doesnt_fill_if_pharmacist_relation_doesnt_exist(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let nonexistent_pharmacist_id = Uuid::new_v4();
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+
+        let prescription_from_db = repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let code = prescription_from_db.code.clone();
+        let new_prescription_fill_with_nonexistent_pharmacist_id = prescription_from_db
+            .fill(nonexistent_pharmacist_id, &code, None, Utc::now())
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .fill_prescription(new_prescription_fill_with_nonexistent_pharmacist_id)
+                .await,
+            Err(FillPrescriptionRepositoryError::PharmacistNotFound(
+                nonexistent_pharmacist_id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn fills_all_prescriptions_in_batch_or_none(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let new_prescription_fills: Vec<_> = prescriptions
+            .iter()
+            .map(|prescription| {
+                prescription
+                    .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+                    .unwrap()
+            })
+            .collect();
+
+        let nonexistent_pharmacist_id = Uuid::new_v4();
+        let mut fills_with_invalid_last_item = new_prescription_fills.clone();
+        fills_with_invalid_last_item[2].pharmacist_id = nonexistent_pharmacist_id;
+
+        assert_eq!(
+            repository
+                .fill_prescriptions(fills_with_invalid_last_item)
+                .await,
+            Err(FillPrescriptionRepositoryError::PharmacistNotFound(
+                nonexistent_pharmacist_id
+            ))
+        );
+        for prescription in &prescriptions {
+            let prescription_from_db = repository
+                .get_prescription_by_id(prescription.id)
+                .await
+                .unwrap();
+            assert!(prescription_from_db.fills.is_empty());
+        }
+
+        let prescription_fills = repository
+            .fill_prescriptions(new_prescription_fills.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_fills, new_prescription_fills);
+        for (prescription, new_prescription_fill) in
+            prescriptions.iter().zip(&new_prescription_fills)
+        {
+            let prescription_from_db = repository
+                .get_prescription_by_id(prescription.id)
+                .await
+                .unwrap();
+            assert_eq!(prescription_from_db.fills[0], *new_prescription_fill);
+        }
+
+        assert_eq!(
+            repository
+                .fill_prescriptions(vec![prescriptions[0]
+                    .fill(
+                        seeds.pharmacist.id,
+                        &prescriptions[0].code,
+                        None,
+                        Utc::now()
+                    )
+                    .unwrap()])
+                .await,
+            Err(FillPrescriptionRepositoryError::QuantityExceeded(
+                prescriptions[0].id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn retries_batch_fill_after_serialization_failure(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+
+        let mut prescriptions = vec![];
+        for _ in 0..2 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        // Sequences aren't rolled back, so only the second fill of the first attempt fails
+        sqlx::query(r#"CREATE SEQUENCE prescription_fill_attempts"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"CREATE FUNCTION fail_second_prescription_fill() RETURNS trigger AS $$
+            BEGIN
+                IF nextval('prescription_fill_attempts') = 2 THEN
+                    RAISE EXCEPTION 'could not serialize access' USING ERRCODE = 'serialization_failure';
+                END IF;
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"CREATE TRIGGER fail_second_prescription_fill BEFORE INSERT ON prescription_fills FOR EACH ROW EXECUTE FUNCTION fail_second_prescription_fill()"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let new_prescription_fills: Vec<_> = prescriptions
+            .iter()
+            .map(|prescription| {
+                prescription
+                    .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+                    .unwrap()
+            })
+            .collect();
+        let prescription_fills = repository
+            .fill_prescriptions(new_prescription_fills.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_fills, new_prescription_fills);
+        for prescription in &prescriptions {
+            let prescription_from_db = repository
+                .get_prescription_by_id(prescription.id)
+                .await
+                .unwrap();
+            assert_eq!(prescription_from_db.fills.len(), 1);
+            assert_eq!(
+                prescription_from_db.prescribed_drugs[0].dispensed_quantity,
+                1
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn fills_prescription_partially_and_then_dispenses_the_remainder(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let prescription = repository
+            .create_prescription(
+                NewPrescription::new(
+                    seeds.doctor.id,
+                    seeds.patient.id,
+                    None,
+                    None,
+                    vec![
+                        NewPrescribedDrug {
+                            drug_id: seeds.drugs[0].id,
+                            quantity: 3,
+                        },
+                        NewPrescribedDrug {
+                            drug_id: seeds.drugs[1].id,
+                            quantity: 1,
+                        },
+                    ],
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let partial_fill = prescription
+            .fill(
+                seeds.pharmacist.id,
+                &prescription.code,
+                Some(vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 2,
+                }]),
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(partial_fill.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            prescription_from_db.fill_status,
+            PrescriptionFillStatus::PartiallyFilled
+        );
+        assert_eq!(prescription_from_db.fills, vec![partial_fill.clone()]);
+        let dispensed_quantities: Vec<_> = prescription_from_db
+            .prescribed_drugs
+            .iter()
+            .map(|prescribed_drug| (prescribed_drug.drug_id, prescribed_drug.dispensed_quantity))
+            .collect();
+        assert!(dispensed_quantities.contains(&(seeds.drugs[0].id, 2)));
+        assert!(dispensed_quantities.contains(&(seeds.drugs[1].id, 0)));
+
+        // Was built before the partial fill, so it dispenses more than remains
+        let stale_fill = prescription
+            .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+            .unwrap();
+
+        assert_eq!(
+            repository.fill_prescription(stale_fill).await,
+            Err(FillPrescriptionRepositoryError::QuantityExceeded(
+                prescription.id
+            ))
+        );
+
+        let remainder_fill = prescription_from_db
+            .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+            .unwrap();
+        repository
+            .fill_prescription(remainder_fill.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            prescription_from_db.fill_status,
+            PrescriptionFillStatus::Filled
+        );
+        assert_eq!(prescription_from_db.fills.len(), 2);
+        assert!(prescription_from_db
+            .prescribed_drugs
+            .iter()
+            .all(|prescribed_drug| prescribed_drug.dispensed_quantity == prescribed_drug.quantity));
+    }
+
+    #[sqlx::test]
+    async fn first_fill_moves_end_date_of_prescription_with_window_from_fill(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 2,
+            }],
+        )
+        .unwrap()
+        .with_window_from_fill(true);
+        let prescription_id = repository
+            .create_prescription(new_prescription)
+            .await
+            .unwrap()
+            .id;
+        let prescription = repository
+            .get_prescription_by_id(prescription_id)
+            .await
+            .unwrap();
+        let window = prescription.end_date - prescription.start_date;
+
+        assert!(prescription.window_from_fill);
+
+        let partial_fill = prescription
+            .fill(
+                seeds.pharmacist.id,
+                &prescription.code,
+                Some(vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }]),
+                Utc::now(),
+            )
+            .unwrap();
+        let prescription_fill = repository.fill_prescription(partial_fill).await.unwrap();
+        let prescription = repository
+            .get_prescription_by_id(prescription_id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription.end_date, prescription_fill.created_at + window);
+
+        let remainder_fill = prescription
+            .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+            .unwrap();
+        repository.fill_prescription(remainder_fill).await.unwrap();
+        let filled_prescription = repository
+            .get_prescription_by_id(prescription_id)
+            .await
+            .unwrap();
+
+        assert_eq!(filled_prescription.end_date, prescription.end_date);
+    }
+
+    #[sqlx::test]
+    async fn gets_unfilled_prescriptions_expiring_before_given_date(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let now = Utc::now();
+        // Prescriptions are issued on their start date, so they can be backdated
+        let create_prescription = |start_date, prescription_type| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                Some(prescription_type),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let expired_prescription =
+            create_prescription(now - Duration::days(60), PrescriptionType::Regular);
+        let expiring_prescription =
+            create_prescription(now - Duration::days(25), PrescriptionType::Regular);
+        let filled_expiring_prescription =
+            create_prescription(now - Duration::days(26), PrescriptionType::Regular);
+        let far_future_prescription =
+            create_prescription(now, PrescriptionType::ForChronicDiseaseDrugs);
+        for prescription in [
+            &expired_prescription,
+            &expiring_prescription,
+            &filled_expiring_prescription,
+            &far_future_prescription,
+        ] {
+            repository
+                .create_prescription(prescription.clone())
+                .await
+                .unwrap();
+        }
+        let prescription_to_fill = repository
+            .get_prescription_by_id(filled_expiring_prescription.id)
+            .await
+            .unwrap();
+        let code = prescription_to_fill.code.clone();
+        repository
+            .fill_prescription(
+                prescription_to_fill
+                    .fill(seeds.pharmacist.id, &code, None, Utc::now())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let prescriptions = repository
+            .get_prescriptions_expiring_before(seeds.patient.id, now + Duration::days(7))
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, expiring_prescription.id);
+
+        let prescriptions = repository
+            .get_prescriptions_expiring_before(Uuid::new_v4(), now + Duration::days(7))
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn gets_active_unfilled_prescriptions_for_patient(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let now = Utc::now();
+        // Prescriptions are issued on their start date, so they can be backdated
+        let create_prescription = |start_date| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                Some(PrescriptionType::Regular),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let pending_prescription = create_prescription(now + Duration::days(2));
+        let active_prescription = create_prescription(now - Duration::days(2));
+        let expired_prescription = create_prescription(now - Duration::days(60));
+        let filled_prescription = create_prescription(now - Duration::days(1));
+        for prescription in [
+            &pending_prescription,
+            &active_prescription,
+            &expired_prescription,
+            &filled_prescription,
+        ] {
+            repository
+                .create_prescription(prescription.clone())
+                .await
+                .unwrap();
+        }
+        let prescription_to_fill = repository
+            .get_prescription_by_id(filled_prescription.id)
+            .await
+            .unwrap();
+        let code = prescription_to_fill.code.clone();
+        repository
+            .fill_prescription(
+                prescription_to_fill
+                    .fill(seeds.pharmacist.id, &code, None, Utc::now())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let prescriptions = repository
+            .get_active_prescriptions_for_patient(seeds.patient.id, now)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, active_prescription.id);
+
+        let prescriptions = repository
+            .get_active_prescriptions_for_patient(Uuid::new_v4(), now)
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn gets_prescriptions_for_patient_newest_first(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut created_prescription_ids = vec![];
+        for drug in &seeds.drugs[..3] {
+            let new_prescription = NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: drug.id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                Utc::now(),
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription.clone())
+                .await
+                .unwrap();
+            created_prescription_ids.push(new_prescription.id);
+        }
+
+        let prescriptions = repository
+            .get_prescriptions_for_patient(seeds.patient.id, None, None)
+            .await
+            .unwrap();
+
+        let prescription_ids: Vec<Uuid> = prescriptions
+            .iter()
+            .map(|prescription| prescription.id)
+            .collect();
+        assert_eq!(
+            prescription_ids,
+            created_prescription_ids
+                .iter()
+                .rev()
+                .copied()
+                .collect::<Vec<_>>()
+        );
+
+        let prescriptions = repository
+            .get_prescriptions_for_patient(seeds.patient.id, Some(1), Some(2))
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, created_prescription_ids[0]);
+
+        let prescriptions = repository
+            .get_prescriptions_for_patient(Uuid::new_v4(), None, None)
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn searches_prescriptions_by_patient_name_doctor_name_and_code(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let other_patient = NewPatient::new("Jane Patient".into(), "92022900002".into()).unwrap();
+        PostgresPatientsRepository::new(pool)
+            .create_patient(other_patient.clone())
+            .await
+            .unwrap();
+        let mut created_prescriptions = vec![];
+        for patient_id in [seeds.patient.id, other_patient.id] {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                patient_id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            created_prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let prescriptions = repository
+            .search_prescriptions("jAnE", None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, created_prescriptions[1].id);
+
+        let prescriptions = repository
+            .search_prescriptions(&created_prescriptions[0].code, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+        assert_eq!(prescriptions[0].id, created_prescriptions[0].id);
+
+        let prescriptions = repository
+            .search_prescriptions("John Doc", None, Some(1))
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 1);
+
+        // Wildcards are matched literally
+        for query in ["%", "_", "John%Patient"] {
+            let prescriptions = repository
+                .search_prescriptions(query, None, None)
+                .await
+                .unwrap();
+
+            assert!(prescriptions.is_empty());
+        }
+    }
+
+    #[sqlx::test]
+    async fn gets_drug_history_for_patient_ordered_by_date(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let now = Utc::now();
+        let create_prescription = |start_date, prescribed_drugs| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                None,
+                prescribed_drugs,
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let oldest_prescription = create_prescription(
+            now - Duration::days(20),
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        );
+        let newest_prescription = create_prescription(
+            now,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[1].id,
+                quantity: 2,
+            }],
+        );
+        let middle_prescription = create_prescription(
+            now - Duration::days(10),
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 3,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[3].id,
+                    quantity: 1,
+                },
+            ],
+        );
+        for prescription in [
+            &oldest_prescription,
+            &newest_prescription,
+            &middle_prescription,
+        ] {
+            repository
+                .create_prescription(prescription.clone())
+                .await
+                .unwrap();
+        }
+
+        let drug_history = repository
+            .get_drug_history_for_patient(seeds.patient.id, None, None)
+            .await
+            .unwrap();
+
+        let prescription_ids: Vec<Uuid> = drug_history
+            .iter()
+            .map(|entry| entry.prescription_id)
+            .collect();
+        assert_eq!(
+            prescription_ids,
+            vec![
+                newest_prescription.id,
+                middle_prescription.id,
+                middle_prescription.id,
+                oldest_prescription.id
+            ]
+        );
+        assert_eq!(drug_history[0].drug_id, seeds.drugs[1].id);
+        assert_eq!(drug_history[0].drug_name, seeds.drugs[1].name);
+        assert_eq!(drug_history[0].quantity, 2);
+        assert_eq!(drug_history[0].prescription_code, newest_prescription.code);
+        assert_eq!(drug_history[3].drug_id, seeds.drugs[0].id);
+
+        let second_page = repository
+            .get_drug_history_for_patient(seeds.patient.id, Some(1), Some(3))
+            .await
+            .unwrap();
+
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].prescription_id, oldest_prescription.id);
+
+        assert!(repository
+            .get_drug_history_for_patient(Uuid::new_v4(), None, None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            repository
+                .get_drug_history_for_patient(seeds.patient.id, Some(-1), None)
+                .await,
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn estimates_prescription_cost_from_priced_drugs(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 2,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 3,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let prescription_from_db = repository
+            .get_prescription_by_id(new_prescription.id)
+            .await
+            .unwrap();
+
+        assert_eq!(prescription_from_db.estimated_cost_cents, Some(2250));
+
+        let new_unpriced_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[1].id,
+                    quantity: 1,
+                },
+                NewPrescribedDrug {
+                    drug_id: seeds.drugs[3].id,
+                    quantity: 1,
+                },
+            ],
+        )
+        .unwrap();
+        let unpriced_prescription = repository
+            .create_prescription(new_unpriced_prescription)
+            .await
+            .unwrap();
+
+        assert_eq!(unpriced_prescription.estimated_cost_cents, None);
+    }
+
+    #[sqlx::test]
+    async fn reassigns_patient_of_unfilled_prescription(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let other_patient = NewPatient::new("Jane Patient".into(), "92022900002".into()).unwrap();
+        PostgresPatientsRepository::new(pool)
+            .create_patient(other_patient.clone())
+            .await
+            .unwrap();
+
+        let new_prescription = NewPrescription::new(
+            seeds.doctor.id,
+            seeds.patient.id,
+            None,
+            None,
+            vec![NewPrescribedDrug {
+                drug_id: seeds.drugs[0].id,
+                quantity: 1,
+            }],
+        )
+        .unwrap();
+        repository
+            .create_prescription(new_prescription.clone())
+            .await
+            .unwrap();
+
+        let reassigned_prescription = repository
+            .reassign_prescription_patient(new_prescription.id, other_patient.id)
+            .await
+            .unwrap();
+
+        assert_eq!(reassigned_prescription.patient.id, other_patient.id);
+        assert_eq!(reassigned_prescription.patient.name, other_patient.name);
+
+        let nonexistent_patient_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .reassign_prescription_patient(new_prescription.id, nonexistent_patient_id)
+                .await,
+            Err(ReassignPrescriptionPatientRepositoryError::PatientNotFound(
+                nonexistent_patient_id
+            ))
+        );
+
+        let nonexistent_prescription_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .reassign_prescription_patient(nonexistent_prescription_id, other_patient.id)
+                .await,
+            Err(
+                ReassignPrescriptionPatientRepositoryError::PrescriptionNotFound(
+                    nonexistent_prescription_id
+                )
+            )
+        );
+
+        let new_prescription_fill = reassigned_prescription
+            .fill(
+                seeds.pharmacist.id,
+                &reassigned_prescription.code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .reassign_prescription_patient(new_prescription.id, seeds.patient.id)
+                .await,
+            Err(ReassignPrescriptionPatientRepositoryError::AlreadyFilled(
+                new_prescription.id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn cancels_only_unfilled_prescription(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut prescriptions = vec![];
+        for _ in 0..2 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let cancelled_prescription = repository
+            .cancel_prescription(prescriptions[0].id)
+            .await
+            .unwrap();
+
+        assert!(cancelled_prescription.cancelled_at.is_some());
+        assert_eq!(
+            cancelled_prescription.fill_status,
+            PrescriptionFillStatus::Cancelled
+        );
+        assert_eq!(
+            repository
+                .get_prescription_by_id(prescriptions[0].id)
+                .await
+                .unwrap()
+                .cancelled_at,
+            cancelled_prescription.cancelled_at
+        );
+        assert_eq!(
+            repository.cancel_prescription(prescriptions[0].id).await,
+            Err(CancelPrescriptionRepositoryError::AlreadyCancelled(
+                prescriptions[0].id
+            ))
+        );
+
+        let new_prescription_fill = prescriptions[1]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[1].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository.cancel_prescription(prescriptions[1].id).await,
+            Err(CancelPrescriptionRepositoryError::AlreadyFilled(
+                prescriptions[1].id
+            ))
+        );
+        assert_eq!(
+            repository
+                .get_prescription_by_id(prescriptions[1].id)
+                .await
+                .unwrap()
+                .cancelled_at,
+            None
+        );
+
+        let nonexistent_prescription_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .cancel_prescription(nonexistent_prescription_id)
+                .await,
+            Err(CancelPrescriptionRepositoryError::PrescriptionNotFound(
+                nonexistent_prescription_id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn counts_prescriptions_by_type(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        for prescription_type in [
+            PrescriptionType::Regular,
+            PrescriptionType::ForAntibiotics,
+            PrescriptionType::Regular,
+        ] {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                Some(prescription_type),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription)
+                .await
+                .unwrap();
+        }
+
+        let now = Utc::now();
+        let counts = repository
+            .count_prescriptions_by_type(now - Duration::days(1), now + Duration::minutes(1))
+            .await
+            .unwrap();
+
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts[&PrescriptionType::Regular], 2);
+        assert_eq!(counts[&PrescriptionType::ForAntibiotics], 1);
+        assert_eq!(counts[&PrescriptionType::ForImmunologicalDrugs], 0);
+        assert_eq!(counts[&PrescriptionType::ForChronicDiseaseDrugs], 0);
+
+        let counts = repository
+            .count_prescriptions_by_type(now - Duration::days(2), now - Duration::days(1))
+            .await
+            .unwrap();
+
+        assert!(counts.values().all(|&count| count == 0));
+    }
+
+    #[sqlx::test]
+    async fn counts_prescriptions_per_doctor_busiest_first(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let other_doctor =
+            NewDoctor::new("Jane Doctor".into(), "8463856".into(), "92022900002".into()).unwrap();
+        PostgresDoctorsRepository::new(pool)
+            .create_doctor(other_doctor.clone())
+            .await
+            .unwrap();
+        for doctor_id in [seeds.doctor.id, other_doctor.id, other_doctor.id] {
+            let new_prescription = NewPrescription::new(
+                doctor_id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription)
+                .await
+                .unwrap();
+        }
+
+        let now = Utc::now();
+        let counts = repository
+            .count_prescriptions_per_doctor(
+                now - Duration::days(1),
+                now + Duration::minutes(1),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            counts,
+            vec![
+                DoctorPrescriptionCount {
+                    doctor_id: other_doctor.id,
+                    doctor_name: "Jane Doctor".to_string(),
+                    prescriptions_count: 2,
+                },
+                DoctorPrescriptionCount {
+                    doctor_id: seeds.doctor.id,
+                    doctor_name: "John Doctor".to_string(),
+                    prescriptions_count: 1,
+                },
+            ]
+        );
+
+        let second_page = repository
+            .count_prescriptions_per_doctor(
+                now - Duration::days(1),
+                now + Duration::minutes(1),
+                Some(1),
+                Some(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(second_page, counts[1..]);
+
+        let counts = repository
+            .count_prescriptions_per_doctor(
+                now - Duration::days(2),
+                now - Duration::days(1),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(counts.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn gets_prescriptions_by_ids_leaving_out_unknown_ones(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut prescription_ids = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription.clone())
+                .await
+                .unwrap();
+            prescription_ids.push(new_prescription.id);
         }
-    }
 
-    async fn setup_repository(
-        pool: sqlx::PgPool,
-    ) -> (PostgresPrescriptionsRepository, DatabaseSeedData) {
-        create_tables(&pool, true).await.unwrap();
-        let seeds = seed_database(pool.clone()).await;
-        let repository = PostgresPrescriptionsRepository::new(pool);
-        (repository, seeds)
+        let prescriptions = repository
+            .get_prescriptions_by_ids(&[
+                prescription_ids[2],
+                Uuid::new_v4(),
+                prescription_ids[0],
+                prescription_ids[2],
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(prescriptions.len(), 2);
+        assert_eq!(prescriptions[0].id, prescription_ids[2]);
+        assert_eq!(prescriptions[1].id, prescription_ids[0]);
+        assert_eq!(prescriptions[0].prescribed_drugs.len(), 1);
+
+        let prescriptions = repository
+            .get_prescriptions_by_ids(&[Uuid::new_v4()])
+            .await
+            .unwrap();
+
+        assert!(prescriptions.is_empty());
     }
 
     #[sqlx::test]
-    async fn creates_and_reads_prescription_by_id(pool: sqlx::PgPool) {
+    async fn gets_only_drugs_that_were_never_prescribed(pool: sqlx::PgPool) {
         let (repository, seeds) = setup_repository(pool).await;
-
         let new_prescription = NewPrescription::new(
             seeds.doctor.id,
             seeds.patient.id,
@@ -606,149 +3947,596 @@ mod tests {
                     quantity: 1,
                 },
                 NewPrescribedDrug {
-                    drug_id: seeds.drugs[1].id,
-                    quantity: 1,
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 2,
                 },
             ],
         )
         .unwrap();
-
         repository
-            .create_prescription(new_prescription.clone())
+            .create_prescription(new_prescription)
             .await
             .unwrap();
 
-        let prescription_from_db = repository
-            .get_prescription_by_id(new_prescription.id)
+        let drugs = repository.get_unprescribed_drugs(None, None).await.unwrap();
+
+        assert_eq!(drugs.len(), 2);
+        assert_eq!(drugs[0].id, seeds.drugs[1].id);
+        assert_eq!(drugs[1].id, seeds.drugs[3].id);
+
+        let drugs = repository
+            .get_unprescribed_drugs(Some(1), Some(1))
             .await
             .unwrap();
 
-        assert_eq!(prescription_from_db, new_prescription);
+        assert_eq!(drugs.len(), 1);
+        assert_eq!(drugs[0].id, seeds.drugs[3].id);
     }
 
     #[sqlx::test]
-    async fn doesnt_create_prescription_if_relations_dont_exist(pool: sqlx::PgPool) {
-        let (repository, seeds) = setup_repository(pool).await;
+    async fn counts_fills_by_pharmacist_per_day(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let now = Utc::now();
+        let mut prescription_fill_ids = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            repository
+                .create_prescription(new_prescription.clone())
+                .await
+                .unwrap();
+            let prescription = repository
+                .get_prescription_by_id(new_prescription.id)
+                .await
+                .unwrap();
+            let new_prescription_fill = prescription
+                .fill(seeds.pharmacist.id, &prescription.code, None, Utc::now())
+                .unwrap();
+            repository
+                .fill_prescription(new_prescription_fill.clone())
+                .await
+                .unwrap();
+            prescription_fill_ids.push(new_prescription_fill.id);
+        }
+        let two_days_ago = now - Duration::days(2);
+        sqlx::query("UPDATE prescription_fills SET created_at = $1 WHERE id = $2")
+            .bind(two_days_ago)
+            .bind(prescription_fill_ids[0])
+            .execute(&pool)
+            .await
+            .unwrap();
 
-        let nonexistent_doctor_id = Uuid::new_v4();
-        let new_prescription_with_nonexisting_doctor_id = NewPrescription::new(
-            nonexistent_doctor_id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: seeds.drugs[0].id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
+        let counts = repository
+            .count_fills_by_pharmacist_per_day(
+                seeds.pharmacist.id,
+                now - Duration::days(3),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
 
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&two_days_ago.date_naive()], 1);
+        assert_eq!(counts[&now.date_naive()], 2);
+
+        let counts = repository
+            .count_fills_by_pharmacist_per_day(
+                seeds.pharmacist.id,
+                now - Duration::days(1),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[&now.date_naive()], 2);
+
+        let pharmacist_id = Uuid::new_v4();
         assert_eq!(
             repository
-                .create_prescription(new_prescription_with_nonexisting_doctor_id)
+                .count_fills_by_pharmacist_per_day(pharmacist_id, now - Duration::days(3), now)
                 .await,
-            Err(CreatePrescriptionRepositoryError::DoctorNotFound(
-                nonexistent_doctor_id
+            Err(GetPharmacistFillStatsRepositoryError::PharmacistNotFound(
+                pharmacist_id
             ))
         );
+    }
 
-        let nonexistent_patient_id = Uuid::new_v4();
-        let new_prescription_with_nonexisting_patient_id = NewPrescription::new(
-            seeds.patient.id,
-            nonexistent_patient_id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: seeds.drugs[0].id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
+    #[sqlx::test]
+    async fn filters_prescriptions_by_filled_status(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let new_prescription_fill = prescriptions[1]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[1].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        let filled_prescriptions = repository
+            .get_prescriptions(None, None, Some(true), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(filled_prescriptions.len(), 1);
+        assert_eq!(filled_prescriptions[0].id, prescriptions[1].id);
+        assert_eq!(
+            filled_prescriptions[0].fill_status,
+            PrescriptionFillStatus::Filled
+        );
+
+        let unfilled_prescriptions = repository
+            .get_prescriptions(None, None, Some(false), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(unfilled_prescriptions.len(), 2);
+        assert!(unfilled_prescriptions
+            .iter()
+            .all(|prescription| prescription.fills.is_empty()));
+
+        let second_unfilled_page = repository
+            .get_prescriptions(Some(1), Some(1), Some(false), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(second_unfilled_page.len(), 1);
 
         assert_eq!(
             repository
-                .create_prescription(new_prescription_with_nonexisting_patient_id)
+                .get_prescriptions(None, None, None, None, None)
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[sqlx::test]
+    async fn filters_prescriptions_by_created_at_window(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        let new_prescription_fill = prescriptions[2]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[2].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+        let ids = |indexes: &[usize]| -> Vec<Uuid> {
+            indexes.iter().map(|&i| prescriptions[i].id).collect()
+        };
+        let created_at = |i: usize| Some(prescriptions[i].created_at);
+
+        assert_eq!(
+            repository
+                .get_prescription_ids(None, None, None, created_at(1), None)
+                .await
+                .unwrap(),
+            ids(&[1, 2])
+        );
+        assert_eq!(
+            repository
+                .get_prescription_ids(None, None, None, None, created_at(1))
+                .await
+                .unwrap(),
+            ids(&[0, 1])
+        );
+        assert_eq!(
+            repository
+                .get_prescription_ids(None, None, None, created_at(1), created_at(1))
+                .await
+                .unwrap(),
+            ids(&[1])
+        );
+        assert_eq!(
+            repository
+                .get_prescriptions(None, None, Some(false), created_at(1), created_at(2))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|prescription| prescription.id)
+                .collect::<Vec<_>>(),
+            ids(&[1])
+        );
+        assert_eq!(
+            repository
+                .get_prescriptions(Some(1), Some(1), None, created_at(0), created_at(2))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|prescription| prescription.id)
+                .collect::<Vec<_>>(),
+            ids(&[1])
+        );
+    }
+
+    #[sqlx::test]
+    async fn returns_empty_page_when_page_is_beyond_the_data(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut prescriptions = vec![];
+        for _ in 0..3 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap();
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let new_prescription_fill = prescriptions[0]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[0].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
+            .await
+            .unwrap();
+
+        let last_page = repository
+            .get_prescriptions(Some(2), Some(1), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(last_page.len(), 1);
+
+        for filled in [None, Some(true), Some(false)] {
+            for (page, page_size) in [
+                (Some(1), None),
+                (Some(3), Some(1)),
+                (Some(100), Some(2)),
+                (Some(i64::MAX), Some(10)),
+            ] {
+                assert!(repository
+                    .get_prescriptions(page, page_size, filled, None, None)
+                    .await
+                    .unwrap()
+                    .is_empty());
+                assert!(repository
+                    .get_prescription_ids(page, page_size, filled, None, None)
+                    .await
+                    .unwrap()
+                    .is_empty());
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn counts_prescriptions_for_drug_within_range(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let now = Utc::now();
+        let create_prescription = |start_date, prescribed_drugs| {
+            NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                seeds.patient.id,
+                Some(start_date),
+                None,
+                prescribed_drugs,
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap()
+        };
+        let prescriptions = [
+            create_prescription(
+                now - Duration::days(20),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            ),
+            create_prescription(
+                now - Duration::days(10),
+                vec![
+                    NewPrescribedDrug {
+                        drug_id: seeds.drugs[0].id,
+                        quantity: 3,
+                    },
+                    NewPrescribedDrug {
+                        drug_id: seeds.drugs[1].id,
+                        quantity: 2,
+                    },
+                ],
+            ),
+        ];
+        for prescription in prescriptions {
+            repository.create_prescription(prescription).await.unwrap();
+        }
+
+        let drug_usage = repository
+            .count_prescriptions_for_drug(
+                seeds.drugs[0].id,
+                now - Duration::days(30),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(drug_usage.prescriptions_count, 2);
+        assert_eq!(drug_usage.total_quantity, 4);
+
+        let drug_usage = repository
+            .count_prescriptions_for_drug(
+                seeds.drugs[0].id,
+                now - Duration::days(15),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(drug_usage.prescriptions_count, 1);
+        assert_eq!(drug_usage.total_quantity, 3);
+
+        let drug_usage = repository
+            .count_prescriptions_for_drug(
+                seeds.drugs[2].id,
+                now - Duration::days(30),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(drug_usage.prescriptions_count, 0);
+        assert_eq!(drug_usage.total_quantity, 0);
+
+        let drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .count_prescriptions_for_drug(drug_id, now - Duration::days(30), now)
                 .await,
-            Err(CreatePrescriptionRepositoryError::PatientNotFound(
-                nonexistent_patient_id
-            ))
+            Err(GetDrugUsageRepositoryError::DrugNotFound(drug_id))
         );
+    }
+
+    #[sqlx::test]
+    async fn gets_distinct_patients_prescribed_drug_within_range(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let other_patient = NewPatient::new("Adam Patient".into(), "92022900002".into()).unwrap();
+        PostgresPatientsRepository::new(pool)
+            .create_patient(other_patient.clone())
+            .await
+            .unwrap();
+        let now = Utc::now();
+        for (patient_id, start_date, drug_id) in [
+            (
+                seeds.patient.id,
+                now - Duration::days(20),
+                seeds.drugs[0].id,
+            ),
+            (
+                seeds.patient.id,
+                now - Duration::days(10),
+                seeds.drugs[0].id,
+            ),
+            (
+                other_patient.id,
+                now - Duration::days(10),
+                seeds.drugs[0].id,
+            ),
+            (
+                other_patient.id,
+                now - Duration::days(40),
+                seeds.drugs[1].id,
+            ),
+        ] {
+            let prescription = NewPrescription::new_with_policy(
+                seeds.doctor.id,
+                patient_id,
+                Some(start_date),
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+                start_date,
+            )
+            .unwrap();
+            repository.create_prescription(prescription).await.unwrap();
+        }
+
+        let affected_patients = repository
+            .get_patients_prescribed_drug(
+                seeds.drugs[0].id,
+                now - Duration::days(30),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
+        let patient_ids: Vec<Uuid> = affected_patients.iter().map(|patient| patient.id).collect();
 
-        let nonexistent_drug_id = Uuid::new_v4();
-        let new_prescription_with_nonexisting_drug_id = NewPrescription::new(
-            seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: nonexistent_drug_id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
+        assert_eq!(patient_ids, vec![other_patient.id, seeds.patient.id]);
+        assert_eq!(affected_patients[0].name, "Adam Patient");
+        assert_eq!(affected_patients[0].pesel_number, "92022900002");
+
+        let affected_patients = repository
+            .get_patients_prescribed_drug(
+                seeds.drugs[1].id,
+                now - Duration::days(30),
+                now + Duration::minutes(1),
+            )
+            .await
+            .unwrap();
 
+        assert!(affected_patients.is_empty());
+
+        let drug_id = Uuid::new_v4();
         assert_eq!(
             repository
-                .create_prescription(new_prescription_with_nonexisting_drug_id)
+                .get_patients_prescribed_drug(drug_id, now - Duration::days(30), now)
                 .await,
-            Err(CreatePrescriptionRepositoryError::DrugNotFound(
-                nonexistent_drug_id
-            ))
+            Err(GetDrugUsageRepositoryError::DrugNotFound(drug_id))
         );
     }
 
     #[sqlx::test]
-    async fn get_prescription_by_id_returns_error_if_prescription_doesnt_exist(pool: sqlx::PgPool) {
-        let (repository, _) = setup_repository(pool).await;
-        let prescription_id = Uuid::new_v4();
+    async fn gets_categories_of_categorized_drugs(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let antibiotic = NewDrug {
+            category: Some(DrugCategory::Antibiotic),
+            ..NewDrug::new(
+                "Amotaks".into(),
+                DrugContentType::SolidPills,
+                Some(16),
+                Some(500),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        PostgresDrugsRepository::new(pool)
+            .create_drug(antibiotic.clone())
+            .await
+            .unwrap();
 
-        let prescription_from_db = repository.get_prescription_by_id(prescription_id).await;
+        let drug_categories = repository
+            .get_drug_categories(vec![antibiotic.id, seeds.drugs[0].id, Uuid::new_v4()])
+            .await
+            .unwrap();
 
         assert_eq!(
-            prescription_from_db,
-            Err(GetPrescriptionByIdRepositoryError::NotFound(
-                prescription_id
-            ))
+            drug_categories,
+            HashMap::from([(antibiotic.id, DrugCategory::Antibiotic)])
         );
     }
 
     #[sqlx::test]
-    async fn creates_and_reads_prescriptions_from_database(pool: sqlx::PgPool) {
-        let (repository, seeds) = setup_repository(pool).await;
-
-        let new_prescription = NewPrescription::new(
-            seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[0].id,
-                    quantity: 1,
-                },
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[1].id,
-                    quantity: 1,
-                },
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[2].id,
-                    quantity: 1,
-                },
-                NewPrescribedDrug {
-                    drug_id: seeds.drugs[3].id,
-                    quantity: 1,
-                },
+    async fn gets_prescription_type_restrictions_of_restricted_drugs(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool.clone()).await;
+        let restricted_drug = NewDrug {
+            allowed_prescription_types: vec![
+                PrescriptionType::ForChronicDiseaseDrugs,
+                PrescriptionType::Regular,
             ],
-        )
-        .unwrap();
+            ..NewDrug::new(
+                "Euthyrox".into(),
+                DrugContentType::SolidPills,
+                Some(50),
+                Some(25),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        PostgresDrugsRepository::new(pool)
+            .create_drug(restricted_drug.clone())
+            .await
+            .unwrap();
 
-        repository
-            .create_prescription(new_prescription.clone())
+        let restrictions = repository
+            .get_drug_prescription_type_restrictions(vec![
+                restricted_drug.id,
+                seeds.drugs[0].id,
+                Uuid::new_v4(),
+            ])
             .await
             .unwrap();
 
-        for _ in 0..10 {
-            let another_prescription = NewPrescription::new(
+        assert_eq!(
+            restrictions,
+            HashMap::from([(
+                restricted_drug.id,
+                DrugPrescriptionTypeRestriction {
+                    drug_name: "Euthyrox".into(),
+                    allowed_prescription_types: vec![
+                        PrescriptionType::ForChronicDiseaseDrugs,
+                        PrescriptionType::Regular,
+                    ],
+                }
+            )])
+        );
+    }
+
+    #[sqlx::test]
+    async fn retries_prescription_code_generation_on_collision(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let create_new_prescription = || {
+            NewPrescription::new(
                 seeds.doctor.id,
                 seeds.patient.id,
                 None,
@@ -758,104 +4546,138 @@ mod tests {
                     quantity: 1,
                 }],
             )
+            .unwrap()
+        };
+        let existing_prescription = repository
+            .create_prescription(create_new_prescription())
+            .await
             .unwrap();
-            repository
-                .create_prescription(another_prescription)
-                .await
-                .unwrap();
-        }
-
-        let prescriptions = repository.get_prescriptions(None, Some(7)).await.unwrap();
-
-        assert_eq!(prescriptions.len(), 7);
-        assert_eq!(prescriptions[0], new_prescription);
-
-        let prescriptions = repository.get_prescriptions(None, Some(20)).await.unwrap();
-        assert_eq!(prescriptions.len(), 11);
+        let duplicated_code = existing_prescription.code.clone();
+        let generated_codes_count = AtomicU32::new(0);
+        let repository = repository.with_code_generator(move || {
+            match generated_codes_count.fetch_add(1, Ordering::SeqCst) {
+                0 => duplicated_code.clone(),
+                _ => "87654321".try_into().unwrap(),
+            }
+        });
+        let mut new_prescription = create_new_prescription();
+        new_prescription.code = existing_prescription.code.clone();
 
-        let prescriptions = repository
-            .get_prescriptions(Some(1), Some(10))
+        let created_prescription = repository
+            .create_prescription(new_prescription)
             .await
             .unwrap();
-        assert_eq!(prescriptions.len(), 1);
-    }
-
-    #[sqlx::test]
-    async fn get_prescriptions_returns_error_if_pagination_params_are_incorrect(
-        pool: sqlx::PgPool,
-    ) {
-        let (repository, _) = setup_repository(pool).await;
 
-        assert!(
-            match repository.get_prescriptions(Some(-1), Some(10)).await {
-                Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
-                _ => false,
-            }
-        );
-
-        assert!(match repository.get_prescriptions(Some(0), Some(0)).await {
-            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_)) => true,
-            _ => false,
-        });
+        assert_eq!(created_prescription.code, "87654321");
     }
 
     #[sqlx::test]
-    async fn fills_prescription_and_saves_to_database(pool: sqlx::PgPool) {
+    async fn fails_if_unique_prescription_code_cant_be_generated(pool: sqlx::PgPool) {
         let (repository, seeds) = setup_repository(pool).await;
-
-        let prescription = NewPrescription::new(
-            seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
-            vec![NewPrescribedDrug {
-                drug_id: seeds.drugs[0].id,
-                quantity: 1,
-            }],
-        )
-        .unwrap();
-
-        repository
-            .create_prescription(prescription.clone())
+        let create_new_prescription = || {
+            NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
+            .unwrap()
+        };
+        let existing_prescription = repository
+            .create_prescription(create_new_prescription())
             .await
             .unwrap();
+        let duplicated_code = existing_prescription.code.clone();
+        let repository = repository.with_code_generator(move || duplicated_code.clone());
+        let mut new_prescription = create_new_prescription();
+        new_prescription.code = existing_prescription.code.clone();
 
-        let prescription_from_db = repository
-            .get_prescription_by_id(prescription.id)
-            .await
-            .unwrap();
+        let result = repository.create_prescription(new_prescription).await;
 
-        assert!(prescription_from_db.fill.is_none());
+        assert!(matches!(
+            result,
+            Err(CreatePrescriptionRepositoryError::CodeGenerationFailed)
+        ));
+    }
 
-        let code = prescription_from_db.code.clone();
-        let new_prescription_fill = prescription_from_db
-            .fill(seeds.pharmacist.id, code)
+    #[sqlx::test]
+    async fn gets_prescription_ids_matching_prescriptions(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let mut prescriptions = vec![];
+        for _ in 0..4 {
+            let new_prescription = NewPrescription::new(
+                seeds.doctor.id,
+                seeds.patient.id,
+                None,
+                None,
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[0].id,
+                    quantity: 1,
+                }],
+            )
             .unwrap();
-        let created_prescription_fill = repository
-            .fill_prescription(new_prescription_fill.clone())
+            prescriptions.push(
+                repository
+                    .create_prescription(new_prescription)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let new_prescription_fill = prescriptions[2]
+            .fill(
+                seeds.pharmacist.id,
+                &prescriptions[2].code,
+                None,
+                Utc::now(),
+            )
+            .unwrap();
+        repository
+            .fill_prescription(new_prescription_fill)
             .await
             .unwrap();
 
-        assert_eq!(created_prescription_fill, new_prescription_fill);
+        for (page, page_size, filled) in [
+            (None, None, None),
+            (None, None, Some(true)),
+            (None, None, Some(false)),
+            (Some(1), Some(2), None),
+            (Some(1), Some(2), Some(false)),
+        ] {
+            let mut prescription_ids = repository
+                .get_prescription_ids(page, page_size, filled, None, None)
+                .await
+                .unwrap();
+            let mut expected_ids: Vec<Uuid> = repository
+                .get_prescriptions(page, page_size, filled, None, None)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|prescription| prescription.id)
+                .collect();
+            prescription_ids.sort();
+            expected_ids.sort();
 
-        let prescription_from_db = repository
-            .get_prescription_by_id(prescription.id)
-            .await
-            .unwrap();
+            assert_eq!(prescription_ids, expected_ids);
+        }
 
-        assert_eq!(prescription_from_db.fill.unwrap(), new_prescription_fill);
+        assert!(matches!(
+            repository
+                .get_prescription_ids(Some(-1), None, None, None, None)
+                .await,
+            Err(GetPrescriptionsRepositoryError::InvalidPaginationParams(_))
+        ));
     }
 
     #[sqlx::test]
-    async fn doesnt_fill_if_pharmacist_relation_doesnt_exist(pool: sqlx::PgPool) {
+    async fn creates_reads_updates_and_deletes_prescription_template(pool: sqlx::PgPool) {
         let (repository, seeds) = setup_repository(pool).await;
-
-        let nonexistent_pharmacist_id = Uuid::new_v4();
-        let new_prescription = NewPrescription::new(
+        let new_template = NewPrescriptionTemplate::new(
             seeds.doctor.id,
-            seeds.patient.id,
-            None,
-            None,
+            "Flu".into(),
             vec![
                 NewPrescribedDrug {
                     drug_id: seeds.drugs[0].id,
@@ -863,28 +4685,131 @@ mod tests {
                 },
                 NewPrescribedDrug {
                     drug_id: seeds.drugs[1].id,
-                    quantity: 1,
+                    quantity: 2,
                 },
             ],
+            &PrescriptionPolicy::default(),
         )
         .unwrap();
 
-        let prescription_from_db = repository
-            .create_prescription(new_prescription.clone())
+        let created_template = repository
+            .create_prescription_template(new_template.clone())
             .await
             .unwrap();
 
-        let code = prescription_from_db.code.clone();
-        let new_prescription_fill_with_nonexistent_pharmacist_id = prescription_from_db
-            .fill(nonexistent_pharmacist_id, code)
+        assert_eq!(created_template, new_template);
+        assert_eq!(
+            repository
+                .get_prescription_template_by_id(new_template.id)
+                .await
+                .unwrap(),
+            new_template
+        );
+        assert_eq!(
+            repository
+                .get_prescription_templates_for_doctor(seeds.doctor.id)
+                .await
+                .unwrap(),
+            vec![created_template.clone()]
+        );
+        assert!(repository
+            .get_prescription_templates_for_doctor(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty());
+
+        let updated_template = NewPrescriptionTemplate {
+            id: new_template.id,
+            ..NewPrescriptionTemplate::new(
+                seeds.doctor.id,
+                "Cold".into(),
+                vec![NewPrescribedDrug {
+                    drug_id: seeds.drugs[2].id,
+                    quantity: 3,
+                }],
+                &PrescriptionPolicy::default(),
+            )
+            .unwrap()
+        };
+
+        let template = repository
+            .update_prescription_template(updated_template.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(template, updated_template);
+        assert_eq!(template.created_at, created_template.created_at);
+
+        repository
+            .delete_prescription_template(new_template.id)
+            .await
             .unwrap();
 
         assert_eq!(
             repository
-                .fill_prescription(new_prescription_fill_with_nonexistent_pharmacist_id)
+                .get_prescription_template_by_id(new_template.id)
                 .await,
-            Err(FillPrescriptionRepositoryError::PharmacistNotFound(
-                nonexistent_pharmacist_id
+            Err(GetPrescriptionTemplateByIdRepositoryError::NotFound(
+                new_template.id
+            ))
+        );
+        assert_eq!(
+            repository
+                .delete_prescription_template(new_template.id)
+                .await,
+            Err(DeletePrescriptionTemplateRepositoryError::NotFound(
+                new_template.id
+            ))
+        );
+        assert_eq!(
+            repository
+                .update_prescription_template(updated_template.clone())
+                .await,
+            Err(UpdatePrescriptionTemplateRepositoryError::NotFound(
+                new_template.id
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn doesnt_create_prescription_template_if_relations_dont_exist(pool: sqlx::PgPool) {
+        let (repository, seeds) = setup_repository(pool).await;
+        let create_new_template = |doctor_id, drug_id| {
+            NewPrescriptionTemplate::new(
+                doctor_id,
+                "Flu".into(),
+                vec![NewPrescribedDrug {
+                    drug_id,
+                    quantity: 1,
+                }],
+                &PrescriptionPolicy::default(),
+            )
+            .unwrap()
+        };
+
+        let nonexistent_doctor_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .create_prescription_template(create_new_template(
+                    nonexistent_doctor_id,
+                    seeds.drugs[0].id
+                ))
+                .await,
+            Err(CreatePrescriptionTemplateRepositoryError::DoctorNotFound(
+                nonexistent_doctor_id
+            ))
+        );
+
+        let nonexistent_drug_id = Uuid::new_v4();
+        assert_eq!(
+            repository
+                .create_prescription_template(create_new_template(
+                    seeds.doctor.id,
+                    nonexistent_drug_id
+                ))
+                .await,
+            Err(CreatePrescriptionTemplateRepositoryError::DrugNotFound(
+                nonexistent_drug_id
             ))
         );
     }
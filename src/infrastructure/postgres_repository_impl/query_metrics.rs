@@ -0,0 +1,76 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+// Number of queries issued by each repository method, helps spotting N+1 patterns
+#[derive(Debug, Default)]
+pub struct QueryMetrics {
+    queries: Mutex<BTreeMap<String, u64>>,
+}
+
+impl QueryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, method: &str) {
+        let mut queries = self.queries.lock().unwrap();
+        match queries.get_mut(method) {
+            Some(count) => *count += 1,
+            None => {
+                queries.insert(method.to_string(), 1);
+            }
+        }
+    }
+
+    pub fn get_count(&self, method: &str) -> u64 {
+        self.queries
+            .lock()
+            .unwrap()
+            .get(method)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Text exposition format, see https://prometheus.io/docs/instrumenting/exposition_formats/
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let queries = self.queries.lock().unwrap();
+
+        output.push_str("# HELP db_queries_total Number of database queries issued.\n");
+        output.push_str("# TYPE db_queries_total counter\n");
+        for (method, count) in queries.iter() {
+            writeln!(
+                output,
+                r#"db_queries_total{{method="{}"}} {}"#,
+                method, count
+            )
+            .unwrap();
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryMetrics;
+
+    #[test]
+    fn counts_queries_per_method() {
+        let metrics = QueryMetrics::new();
+
+        metrics.record("create_prescription");
+        metrics.record("create_prescription");
+        metrics.record("get_prescription_by_id");
+
+        assert_eq!(metrics.get_count("create_prescription"), 2);
+        assert_eq!(metrics.get_count("get_prescription_by_id"), 1);
+        assert_eq!(metrics.get_count("get_prescriptions"), 0);
+        assert_eq!(
+            metrics.render(),
+            "# HELP db_queries_total Number of database queries issued.\n\
+             # TYPE db_queries_total counter\n\
+             db_queries_total{method=\"create_prescription\"} 2\n\
+             db_queries_total{method=\"get_prescription_by_id\"} 1\n"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::{future::Future, time::Duration};
+
+const SERIALIZATION_FAILURE_CODE: &str = "40001";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    // Doubled after every retry
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(10),
+        }
+    }
+}
+
+fn is_serialization_failure(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(err) => err.code().as_deref() == Some(SERIALIZATION_FAILURE_CODE),
+        _ => false,
+    }
+}
+
+// Serialization failures (SQLSTATE 40001) are returned under contention and the failed statement is
+// safe to run again, any other error is returned right away
+pub async fn retry_on_serialization_failure<T, F, Fut>(
+    retry_policy: RetryPolicy,
+    mut operation: F,
+) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut retries = 0;
+    let mut backoff = retry_policy.initial_backoff;
+    loop {
+        match operation().await {
+            Err(err) if retries < retry_policy.max_retries && is_serialization_failure(&err) => {
+                retries += 1;
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Error of a transaction retried as a whole. A serialization failure aborts the transaction, so it's
+// kept to run the transaction again, any other error is mapped right away
+#[derive(Debug)]
+pub enum TransactionError<E> {
+    SerializationFailure(sqlx::Error),
+    Failed(E),
+}
+
+impl<E> TransactionError<E> {
+    pub fn from_database_error(err: sqlx::Error, map_error: impl FnOnce(sqlx::Error) -> E) -> Self {
+        if is_serialization_failure(&err) {
+            Self::SerializationFailure(err)
+        } else {
+            Self::Failed(map_error(err))
+        }
+    }
+}
+
+// Runs `transaction` again on serialization failures, so it has to begin and commit the transaction
+// itself. Failures left after the last retry are mapped with `map_error`
+pub async fn retry_transaction_on_serialization_failure<T, E, F, Fut>(
+    retry_policy: RetryPolicy,
+    map_error: impl FnOnce(sqlx::Error) -> E,
+    mut transaction: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, TransactionError<E>>>,
+{
+    let result = retry_on_serialization_failure(retry_policy, || {
+        let attempt = transaction();
+        async move {
+            match attempt.await {
+                Ok(value) => Ok(Ok(value)),
+                Err(TransactionError::SerializationFailure(err)) => Err(err),
+                Err(TransactionError::Failed(err)) => Ok(Err(err)),
+            }
+        }
+    })
+    .await;
+
+    result.unwrap_or_else(|err| Err(map_error(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        error::Error,
+        fmt,
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    use super::{
+        retry_on_serialization_failure, retry_transaction_on_serialization_failure, RetryPolicy,
+        TransactionError,
+    };
+
+    #[derive(Debug)]
+    struct DatabaseErrorMock {
+        code: &'static str,
+    }
+
+    impl fmt::Display for DatabaseErrorMock {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "database error {}", self.code)
+        }
+    }
+
+    impl Error for DatabaseErrorMock {}
+
+    impl DatabaseError for DatabaseErrorMock {
+        fn message(&self) -> &str {
+            "database error"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.code))
+        }
+
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    fn database_error(code: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(DatabaseErrorMock { code }))
+    }
+
+    const RETRY_POLICY: RetryPolicy = RetryPolicy {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(1),
+    };
+
+    #[tokio::test]
+    async fn retries_serialization_failures_until_success() {
+        let attempts = AtomicU32::new(0);
+
+        let result = retry_on_serialization_failure(RETRY_POLICY, || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(database_error("40001")),
+                _ => Ok(42),
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), sqlx::Error> =
+            retry_on_serialization_failure(RETRY_POLICY, || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(database_error("40001"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn doesnt_retry_other_errors() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), sqlx::Error> =
+            retry_on_serialization_failure(RETRY_POLICY, || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(database_error("23505"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_whole_transaction_on_serialization_failure() {
+        let attempts = AtomicU32::new(0);
+
+        let result = retry_transaction_on_serialization_failure(
+            RETRY_POLICY,
+            |err| err.to_string(),
+            || async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(TransactionError::from_database_error(
+                        database_error("40001"),
+                        |err| err.to_string(),
+                    )),
+                    1 => Err(TransactionError::from_database_error(
+                        database_error("23505"),
+                        |_| "unique violation".to_string(),
+                    )),
+                    _ => Ok(42),
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err("unique violation".to_string()));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -3,13 +3,15 @@ use sqlx::Row;
 use uuid::Uuid;
 
 use crate::application::sessions::{
-    entities::{NewSession, Session},
+    entities::{NewSession, Session, SessionsStats},
     repository::{
         CreateSessionRepositoryError, GetSessionRepositoryError, SessionsRepository,
         UpdateSessionRepositoryError,
     },
 };
 
+use super::database_error::map_database_error;
+
 pub struct PostgresSessionsRepository {
     pool: sqlx::PgPool,
 }
@@ -25,6 +27,7 @@ impl PostgresSessionsRepository {
             user_id: row.try_get(1)?,
             doctor_id: row.try_get(2)?,
             pharmacist_id: row.try_get(3)?,
+            patient_id: row.try_get(10)?,
             ip_address: row.try_get(4).map(|ip: String| ip.parse().unwrap())?,
             user_agent: row.try_get(5)?,
             created_at: row.try_get(6)?,
@@ -33,6 +36,16 @@ impl PostgresSessionsRepository {
             invalidated_at: row.try_get(9)?,
         })
     }
+
+    fn parse_sessions_stats_row(
+        &self,
+        row: sqlx::postgres::PgRow,
+    ) -> Result<SessionsStats, sqlx::Error> {
+        Ok(SessionsStats {
+            active: row.try_get(0)?,
+            invalidated: row.try_get(1)?,
+        })
+    }
 }
 
 #[async_trait]
@@ -41,7 +54,7 @@ impl SessionsRepository for PostgresSessionsRepository {
         &self,
         new_session: NewSession,
     ) -> Result<Session, CreateSessionRepositoryError> {
-        let row = sqlx::query(r#"INSERT INTO sessions (id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, expires_at) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at"#)
+        let row = sqlx::query(r#"INSERT INTO sessions (id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, expires_at, patient_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at, patient_id"#)
             .bind(new_session.id)
             .bind(new_session.user_id)
             .bind(new_session.doctor_id)
@@ -49,27 +62,36 @@ impl SessionsRepository for PostgresSessionsRepository {
             .bind(new_session.ip_address.to_string())
             .bind(new_session.user_agent)
             .bind(new_session.expires_at)
+            .bind(new_session.patient_id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|err| CreateSessionRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, CreateSessionRepositoryError::DatabaseError, CreateSessionRepositoryError::Unavailable))?;
 
-        let session = self
-            .parse_sessions_row(row)
-            .map_err(|err| CreateSessionRepositoryError::DatabaseError(err.to_string()))?;
+        let session = self.parse_sessions_row(row).map_err(|err| {
+            map_database_error(
+                err,
+                CreateSessionRepositoryError::DatabaseError,
+                CreateSessionRepositoryError::Unavailable,
+            )
+        })?;
 
         Ok(session)
     }
 
     async fn get_session_by_id(&self, id: Uuid) -> Result<Session, GetSessionRepositoryError> {
-        let row = sqlx::query(r#"SELECT id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at FROM sessions WHERE id = $1"#)
+        let row = sqlx::query(r#"SELECT id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at, patient_id FROM sessions WHERE id = $1"#)
             .bind(id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|err| GetSessionRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, GetSessionRepositoryError::DatabaseError, GetSessionRepositoryError::Unavailable))?;
 
-        let session = self
-            .parse_sessions_row(row)
-            .map_err(|err| GetSessionRepositoryError::DatabaseError(err.to_string()))?;
+        let session = self.parse_sessions_row(row).map_err(|err| {
+            map_database_error(
+                err,
+                GetSessionRepositoryError::DatabaseError,
+                GetSessionRepositoryError::Unavailable,
+            )
+        })?;
 
         Ok(session)
     }
@@ -78,21 +100,100 @@ impl SessionsRepository for PostgresSessionsRepository {
         &self,
         session: Session,
     ) -> Result<Session, UpdateSessionRepositoryError> {
-        let row = sqlx::query(r#"UPDATE sessions SET updated_at = $1, expires_at = $2, invalidated_at = $3 WHERE id = $4 RETURNING id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at"#)
+        let row = sqlx::query(r#"UPDATE sessions SET updated_at = $1, expires_at = $2, invalidated_at = $3 WHERE id = $4 RETURNING id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at, patient_id"#)
             .bind(session.updated_at)
             .bind(session.expires_at)
             .bind(session.invalidated_at)
             .bind(session.id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|err| UpdateSessionRepositoryError::DatabaseError(err.to_string()))?;
+            .map_err(|err| map_database_error(err, UpdateSessionRepositoryError::DatabaseError, UpdateSessionRepositoryError::Unavailable))?;
 
-        let session = self
-            .parse_sessions_row(row)
-            .map_err(|err| UpdateSessionRepositoryError::DatabaseError(err.to_string()))?;
+        let session = self.parse_sessions_row(row).map_err(|err| {
+            map_database_error(
+                err,
+                UpdateSessionRepositoryError::DatabaseError,
+                UpdateSessionRepositoryError::Unavailable,
+            )
+        })?;
 
         Ok(session)
     }
+
+    async fn invalidate_all_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, UpdateSessionRepositoryError> {
+        let result = sqlx::query(r#"UPDATE sessions SET invalidated_at = NOW(), updated_at = NOW() WHERE user_id = $1 AND invalidated_at IS NULL"#)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| map_database_error(err, UpdateSessionRepositoryError::DatabaseError, UpdateSessionRepositoryError::Unavailable))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn count_active_sessions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<u64, GetSessionRepositoryError> {
+        let row = sqlx::query(r#"SELECT COUNT(*) FROM sessions WHERE user_id = $1 AND invalidated_at IS NULL AND expires_at > NOW()"#)
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| map_database_error(err, GetSessionRepositoryError::DatabaseError, GetSessionRepositoryError::Unavailable))?;
+
+        let count: i64 = row.try_get(0).map_err(|err| {
+            map_database_error(
+                err,
+                GetSessionRepositoryError::DatabaseError,
+                GetSessionRepositoryError::Unavailable,
+            )
+        })?;
+
+        Ok(count as u64)
+    }
+
+    async fn get_oldest_active_session_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<Session>, GetSessionRepositoryError> {
+        let row = sqlx::query(r#"SELECT id, user_id, doctor_id, pharmacist_id, ip_address, user_agent, created_at, updated_at, expires_at, invalidated_at, patient_id FROM sessions WHERE user_id = $1 AND invalidated_at IS NULL AND expires_at > NOW() ORDER BY created_at ASC LIMIT 1"#)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| map_database_error(err, GetSessionRepositoryError::DatabaseError, GetSessionRepositoryError::Unavailable))?;
+
+        let session = row
+            .map(|row| self.parse_sessions_row(row))
+            .transpose()
+            .map_err(|err| {
+                map_database_error(
+                    err,
+                    GetSessionRepositoryError::DatabaseError,
+                    GetSessionRepositoryError::Unavailable,
+                )
+            })?;
+
+        Ok(session)
+    }
+
+    async fn count_sessions(&self) -> Result<SessionsStats, GetSessionRepositoryError> {
+        let row = sqlx::query(r#"SELECT COUNT(*) FILTER (WHERE invalidated_at IS NULL AND expires_at > NOW()), COUNT(*) FILTER (WHERE invalidated_at IS NOT NULL) FROM sessions"#)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| map_database_error(err, GetSessionRepositoryError::DatabaseError, GetSessionRepositoryError::Unavailable))?;
+
+        let stats = self.parse_sessions_stats_row(row).map_err(|err| {
+            map_database_error(
+                err,
+                GetSessionRepositoryError::DatabaseError,
+                GetSessionRepositoryError::Unavailable,
+            )
+        })?;
+
+        Ok(stats)
+    }
 }
 
 #[cfg(test)]
@@ -102,6 +203,7 @@ mod tests {
         str::FromStr,
     };
 
+    use chrono::Utc;
     use uuid::Uuid;
 
     use super::PostgresSessionsRepository;
@@ -120,6 +222,7 @@ mod tests {
             Uuid::new_v4(),
             Some(Uuid::new_v4()),
             None,
+            None,
             IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
             "Mozilla/5.0".to_string(),
         )
@@ -162,7 +265,7 @@ mod tests {
 
         assert!(created_session.invalidated_at.is_none());
 
-        created_session_by_id.invalidate().unwrap();
+        created_session_by_id.invalidate(Utc::now()).unwrap();
 
         repository
             .update_session(created_session_by_id.clone())
@@ -176,4 +279,153 @@ mod tests {
 
         assert!(invalidated_session.invalidated_at.is_some());
     }
+
+    #[sqlx::test]
+    async fn invalidates_all_sessions_for_user(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+        let user_id = Uuid::new_v4();
+        let new_session = |user_id| {
+            NewSession::new(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+        };
+
+        let first_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        let second_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        let other_user_session = repository
+            .create_session(new_session(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let invalidated_count = repository
+            .invalidate_all_sessions_for_user(user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(invalidated_count, 2);
+        for session_id in [first_session.id, second_session.id] {
+            let session = repository.get_session_by_id(session_id).await.unwrap();
+            assert!(session.invalidated_at.is_some());
+        }
+        let other_user_session = repository
+            .get_session_by_id(other_user_session.id)
+            .await
+            .unwrap();
+        assert!(other_user_session.invalidated_at.is_none());
+    }
+
+    #[sqlx::test]
+    async fn counts_and_gets_oldest_active_sessions_for_user(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+        let user_id = Uuid::new_v4();
+        let new_session = |user_id| {
+            NewSession::new(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+        };
+
+        let mut invalidated_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        invalidated_session.invalidate(Utc::now()).unwrap();
+        repository
+            .update_session(invalidated_session)
+            .await
+            .unwrap();
+        let oldest_active_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let active_count = repository
+            .count_active_sessions_for_user(user_id)
+            .await
+            .unwrap();
+        let oldest_session = repository
+            .get_oldest_active_session_for_user(user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(active_count, 2);
+        assert_eq!(oldest_session.unwrap().id, oldest_active_session.id);
+        assert!(repository
+            .get_oldest_active_session_for_user(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[sqlx::test]
+    async fn counts_active_and_invalidated_sessions(pool: sqlx::PgPool) {
+        let repository = setup_repository(pool).await;
+        let user_id = Uuid::new_v4();
+        let new_session = |user_id| {
+            NewSession::new(
+                user_id,
+                Some(Uuid::new_v4()),
+                None,
+                None,
+                IpAddr::V4(Ipv4Addr::from_str("127.0.0.1").unwrap()),
+                "Mozilla/5.0".to_string(),
+            )
+        };
+
+        let mut invalidated_session = repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        invalidated_session.invalidate(Utc::now()).unwrap();
+        repository
+            .update_session(invalidated_session)
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(user_id))
+            .await
+            .unwrap();
+        repository
+            .create_session(new_session(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let stats = repository.count_sessions().await.unwrap();
+
+        assert_eq!(stats.active, 2);
+        assert_eq!(stats.invalidated, 1);
+
+        repository
+            .invalidate_all_sessions_for_user(user_id)
+            .await
+            .unwrap();
+
+        let stats = repository.count_sessions().await.unwrap();
+
+        assert_eq!(stats.active, 1);
+        assert_eq!(stats.invalidated, 2);
+    }
 }
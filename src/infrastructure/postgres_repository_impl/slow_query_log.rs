@@ -0,0 +1,64 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_SLOW_QUERY_THRESHOLD: Duration = Duration::from_millis(500);
+
+pub async fn log_slow_query<T, F, Fut>(threshold: Duration, label: &str, query: F) -> T
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = T>,
+{
+    let started_at = Instant::now();
+    let result = query().await;
+    let elapsed = started_at.elapsed();
+    if elapsed > threshold {
+        tracing::warn!(
+            query = label,
+            elapsed_ms = elapsed.as_millis() as u64,
+            threshold_ms = threshold.as_millis() as u64,
+            "Slow database query"
+        );
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::log_slow_query;
+    use crate::captured_logs::capture_logs;
+
+    #[tokio::test]
+    async fn logs_warning_if_query_exceeds_threshold() {
+        let (logs, _guard) = capture_logs();
+
+        let result = log_slow_query(Duration::from_millis(5), "get_prescriptions", || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            42
+        })
+        .await;
+
+        assert_eq!(result, 42);
+        let logs = logs.contents();
+        assert!(logs.contains("WARN"));
+        assert!(logs.contains("Slow database query"));
+        assert!(logs.contains("query=\"get_prescriptions\""));
+    }
+
+    #[tokio::test]
+    async fn doesnt_log_queries_below_threshold() {
+        let (logs, _guard) = capture_logs();
+
+        let result = log_slow_query(Duration::from_secs(10), "get_prescriptions", || async {
+            42
+        })
+        .await;
+
+        assert_eq!(result, 42);
+        assert!(logs.contents().is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use sqlx::{postgres::PgPoolOptions, Executor};
+
+// Long enough for the reports, short enough not to keep a connection busy with a runaway query
+pub const DEFAULT_STATEMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Set on every new connection of the pool, statements running longer are canceled by the database
+// and fail with SQLSTATE 57014. Zero disables the timeout
+pub fn with_statement_timeout(
+    pool_options: PgPoolOptions,
+    statement_timeout: Duration,
+) -> PgPoolOptions {
+    pool_options.after_connect(move |connection, _| {
+        Box::pin(async move {
+            let query = format!("SET statement_timeout = {}", statement_timeout.as_millis());
+            connection.execute(query.as_str()).await?;
+
+            Ok(())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::with_statement_timeout;
+    use crate::infrastructure::postgres_repository_impl::database_error::{
+        map_database_error, STATEMENT_TIMEOUT_MESSAGE,
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum RepositoryError {
+        DatabaseError(String),
+        Unavailable,
+    }
+
+    async fn create_pool_with_statement_timeout(
+        pool: &sqlx::PgPool,
+        statement_timeout: Duration,
+    ) -> sqlx::PgPool {
+        with_statement_timeout(PgPoolOptions::new(), statement_timeout)
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn sets_statement_timeout_on_new_connections(pool: sqlx::PgPool) {
+        let pool = create_pool_with_statement_timeout(&pool, Duration::from_secs(3)).await;
+
+        let statement_timeout: String = sqlx::query_scalar("SHOW statement_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(statement_timeout, "3s");
+    }
+
+    #[sqlx::test]
+    async fn cancels_statements_running_longer_than_timeout(pool: sqlx::PgPool) {
+        let pool = create_pool_with_statement_timeout(&pool, Duration::from_millis(50)).await;
+
+        let err = sqlx::query("SELECT pg_sleep(5)")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            map_database_error(
+                err,
+                RepositoryError::DatabaseError,
+                RepositoryError::Unavailable
+            ),
+            RepositoryError::DatabaseError(STATEMENT_TIMEOUT_MESSAGE.into())
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::postgres_repository_impl::retry::RetryPolicy;
+use crate::domain::prescriptions::{
+    entities::PrescriptionFillNotification,
+    fill_notifier::{NotifyPrescriptionFillError, PrescriptionFillNotifier},
+};
+
+// Kept short, the receiving side only has to acknowledge the notification
+pub const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+pub const DEFAULT_WEBHOOK_RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_retries: 2,
+    initial_backoff: Duration::from_millis(200),
+};
+
+// POSTs the notification as json to the configured url
+pub struct WebhookPrescriptionFillNotifier {
+    client: reqwest::Client,
+    url: String,
+    retry_policy: RetryPolicy,
+}
+
+impl WebhookPrescriptionFillNotifier {
+    pub fn new(url: String, timeout: Duration, retry_policy: RetryPolicy) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("webhook http client configuration is valid");
+
+        Self {
+            client,
+            url,
+            retry_policy,
+        }
+    }
+
+    async fn send(
+        &self,
+        notification: &PrescriptionFillNotification,
+    ) -> Result<(), reqwest::Error> {
+        self.client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PrescriptionFillNotifier for WebhookPrescriptionFillNotifier {
+    // Timeouts, connection errors and non-2xx responses are all retried
+    async fn notify_fill(
+        &self,
+        notification: &PrescriptionFillNotification,
+    ) -> Result<(), NotifyPrescriptionFillError> {
+        let mut retries = 0;
+        let mut backoff = self.retry_policy.initial_backoff;
+        loop {
+            match self.send(notification).await {
+                Err(_) if retries < self.retry_policy.max_retries => {
+                    retries += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => {
+                    return result.map_err(|err| NotifyPrescriptionFillError(err.to_string()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use uuid::Uuid;
+    use wiremock::{
+        matchers::{body_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::WebhookPrescriptionFillNotifier;
+    use crate::{
+        domain::prescriptions::{
+            entities::PrescriptionFillNotification, fill_notifier::PrescriptionFillNotifier,
+        },
+        infrastructure::postgres_repository_impl::retry::RetryPolicy,
+    };
+
+    fn create_notification() -> PrescriptionFillNotification {
+        PrescriptionFillNotification {
+            prescription_id: Uuid::new_v4(),
+            code: "12345678".try_into().unwrap(),
+            pharmacist_id: Uuid::new_v4(),
+            filled_at: Utc::now(),
+        }
+    }
+
+    fn create_notifier(server: &MockServer) -> WebhookPrescriptionFillNotifier {
+        WebhookPrescriptionFillNotifier::new(
+            format!("{}/fills", server.uri()),
+            Duration::from_millis(500),
+            RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn posts_notification_as_json() {
+        let server = MockServer::start().await;
+        let notification = create_notification();
+        Mock::given(method("POST"))
+            .and(path("/fills"))
+            .and(body_json(&notification))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = create_notifier(&server).notify_fill(&notification).await;
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn retries_failed_notification() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let result = create_notifier(&server)
+            .notify_fill(&create_notification())
+            .await;
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn returns_error_when_retries_are_exhausted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let result = create_notifier(&server)
+            .notify_fill(&create_notification())
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+}